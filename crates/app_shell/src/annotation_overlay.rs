@@ -0,0 +1,73 @@
+//! Screen-space drawing of annotation leaders and their text billboards.
+
+use core_document::{Document, FeatureId, ScreenSpaceLabel, ScreenSpaceOverlay};
+
+use crate::camera::CameraController;
+
+const THICKNESS: f32 = 1.5;
+const ACTIVE_THICKNESS: f32 = 2.5;
+/// Half size of the cross marking the anchor point, in pixels.
+const ANCHOR_SIZE: f32 = 4.0;
+
+/// Leaders and labels of every visible annotation. Annotations are shown in
+/// all workbenches, so the host draws them rather than the Annotation workbench.
+pub fn annotation_overlays(
+    document: &Document,
+    camera: &CameraController,
+    active: Option<FeatureId>,
+) -> (Vec<ScreenSpaceOverlay>, Vec<ScreenSpaceLabel>) {
+    let (origin_x, origin_y, _, _) = camera.viewport_info();
+    // Annotations are relative to their body's position.
+    let project = |id: FeatureId, local: [f32; 3]| {
+        let world = core_document::from_frame(local, document.feature_position(id));
+        camera
+            .world_to_screen(camera.to_frame(world))
+            .map(|(x, y)| [x - origin_x, y - origin_y])
+    };
+
+    let mut overlays = Vec::new();
+    let mut labels = Vec::new();
+    for (id, annotation) in wb_annotation::annotations(document) {
+        let shown = document
+            .get_feature_meta(id)
+            .is_some_and(|node| node.visible && !node.suppressed);
+        if !shown {
+            continue;
+        }
+        let (Some(anchor), Some(label)) = (
+            project(id, annotation.anchor),
+            project(id, annotation.label_position()),
+        ) else {
+            continue;
+        };
+        let highlighted = active == Some(id);
+        let color = annotation.kind.color();
+        let thickness = if highlighted {
+            ACTIVE_THICKNESS
+        } else {
+            THICKNESS
+        };
+
+        overlays.push(ScreenSpaceOverlay::new(anchor, label, color, thickness));
+        let [x, y] = anchor;
+        overlays.push(ScreenSpaceOverlay::new(
+            [x - ANCHOR_SIZE, y - ANCHOR_SIZE],
+            [x + ANCHOR_SIZE, y + ANCHOR_SIZE],
+            color,
+            thickness,
+        ));
+        overlays.push(ScreenSpaceOverlay::new(
+            [x - ANCHOR_SIZE, y + ANCHOR_SIZE],
+            [x + ANCHOR_SIZE, y - ANCHOR_SIZE],
+            color,
+            thickness,
+        ));
+        labels.push(ScreenSpaceLabel {
+            position: label,
+            text: annotation.text,
+            color,
+            highlighted,
+        });
+    }
+    (overlays, labels)
+}
@@ -1,11 +1,16 @@
 use crate::orientation_cube::{CameraSnapView, RotateAxis, RotateDelta};
-use axes::{AxisPreset, AxisSystem};
-use glam::{Mat3, Mat4, Quat, Vec3};
-use settings::{CameraSettings, ProjectionMode};
+use axes::AxisSystem;
+use glam::{DVec3, Mat3, Mat4, Quat, Vec3};
+use settings::{CameraPose, CameraSettings, ProjectionMode};
 use winit::dpi::PhysicalPosition;
 
 pub(super) const DEG_TO_RAD: f32 = std::f32::consts::PI / 180.0;
-pub(super) const MAX_PITCH_RAD: f32 = 1.570796; // ~90 degrees
+pub(super) const MAX_PITCH_RAD: f32 = std::f32::consts::FRAC_PI_2; // 90 degrees
+
+/// Tangent of half a vertical field of view given in degrees.
+pub(super) fn half_fov_tan(fov_y_deg: f32) -> f32 {
+    (fov_y_deg * DEG_TO_RAD * 0.5).tan()
+}
 
 /// Simple animation helper so camera snaps remain smooth when requested.
 #[derive(Debug, Clone)]
@@ -40,12 +45,44 @@ impl CameraAnimation {
     }
 }
 
+/// Glide of the orbit target, distance, and orthographic scale, used when
+/// zooming to a selection.
+#[derive(Debug, Clone)]
+pub(super) struct FrameAnimation {
+    start_target: DVec3,
+    end_target: DVec3,
+    start_radius: f32,
+    end_radius: f32,
+    start_half_height: f32,
+    end_half_height: f32,
+    progress: f32,
+    duration_secs: f32,
+}
+
+impl FrameAnimation {
+    /// Target, radius, and orthographic half height at the next step, None
+    /// once the glide is over.
+    fn update(&mut self, dt_secs: f32) -> Option<(DVec3, f32, f32)> {
+        self.progress += dt_secs / self.duration_secs.max(1e-3);
+        if self.progress >= 1.0 {
+            return None;
+        }
+        let t = 1.0 - (1.0 - self.progress).powi(3); // ease-out cubic
+        Some((
+            self.start_target.lerp(self.end_target, f64::from(t)),
+            self.start_radius + (self.end_radius - self.start_radius) * t,
+            self.start_half_height + (self.end_half_height - self.start_half_height) * t,
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub struct CameraController {
-    pub(super) target: Vec3,
+    /// Kept in double precision so the camera moves smoothly around parts far
+    /// from the origin.
+    pub(super) target: DVec3,
     pub(super) radius: f32,
     axes: AxisSystem,
-    axis_preset: AxisPreset,
 
     // Optional turntable state (used for snaps/animation rebasing if desired)
     pub(super) yaw: f32,   // around WORLD_UP
@@ -56,6 +93,9 @@ pub struct CameraController {
 
     pub(super) fov_y_deg: f32,
     pub(super) projection: ProjectionMode,
+    /// Half the height of the orthographic view volume. Zooming scales it
+    /// instead of moving the eye; unused in perspective.
+    pub(super) ortho_half_height: f32,
     pub(super) near: f32,
     pub(super) far: f32,
 
@@ -67,33 +107,37 @@ pub struct CameraController {
     pub(super) viewport_size: (u32, u32),
 
     pub(super) animation: Option<CameraAnimation>,
+    pub(super) framing: Option<FrameAnimation>,
+    /// Length of view changes such as snaps and zooming to a selection.
+    transition_secs: f32,
 
     // Dynamic orbit pivot support
-    /// When set, orbit will use this point instead of target during drag
-    pub(super) orbit_pivot: Option<Vec3>,
+    /// When set, orbit will use this world point instead of target during drag
+    pub(super) orbit_pivot: Option<DVec3>,
     /// The pivot point we're actually using for this orbit session (captured at mouse down)
-    pub(super) active_pivot: Option<Vec3>,
+    pub(super) active_pivot: Option<DVec3>,
+
+    /// World position the single-precision points of this API are relative
+    /// to (the frame of the body being worked on); see [`Self::set_frame`].
+    frame: DVec3,
 }
 
 impl CameraController {
     pub fn new(settings: &CameraSettings, initial_viewport: (u32, u32)) -> Self {
         let yaw = 45.0_f32.to_radians();
         let pitch = 35.0_f32.to_radians();
-        let axes = AxisSystem::from(settings.axis_preset);
-
-        let fov_degrees = match settings.projection {
-            ProjectionMode::Perspective => settings.fov_degrees,
-            ProjectionMode::Orthographic => 50.0,
-        };
+        let axes = settings.axis_system();
+        let radius = settings.min_distance.max(5.0);
 
         let mut controller = Self {
-            target: Vec3::ZERO,
-            radius: settings.min_distance.max(5.0),
+            target: DVec3::ZERO,
+            radius,
             yaw,
             pitch,
             orientation: Quat::IDENTITY,
-            fov_y_deg: fov_degrees,
+            fov_y_deg: settings.fov_degrees,
             projection: settings.projection,
+            ortho_half_height: radius * half_fov_tan(settings.fov_degrees),
             near: 0.05,
             far: 10_000.0,
             orbiting: false,
@@ -102,29 +146,108 @@ impl CameraController {
             viewport_origin: (0.0, 0.0),
             viewport_size: initial_viewport,
             animation: None,
+            framing: None,
+            transition_secs: settings.transition_secs(),
             orbit_pivot: None,
             active_pivot: None,
+            frame: DVec3::ZERO,
             axes,
-            axis_preset: settings.axis_preset,
         };
 
         controller.rebuild_orientation_from_yaw_pitch();
         controller
     }
 
-    /// Recenter the camera on a bounding sphere.
-    pub fn reset_to_fit(&mut self, center: Vec3, radius_hint: f32) {
-        self.target = center;
-        self.radius = radius_hint.max(1.0) * 2.5;
+    /// Make the single-precision positions taken and returned by the camera
+    /// (the view-projection, `position`, `target`, screen projections)
+    /// relative to the world position `frame`. Differences to the frame are
+    /// taken in double precision, so geometry near it stays exact wherever it
+    /// is; the camera itself does not move.
+    pub fn set_frame(&mut self, frame: [f64; 3]) {
+        self.frame = DVec3::from_array(frame);
+    }
 
-        self.yaw = 45.0_f32.to_radians();
-        self.pitch = 30.0_f32.to_radians();
-        self.animation = None;
+    /// World position the camera's single-precision positions are relative to.
+    pub fn frame(&self) -> [f64; 3] {
+        self.frame.to_array()
+    }
+
+    /// A world position relative to the frame.
+    pub fn to_frame(&self, world: [f64; 3]) -> Vec3 {
+        (DVec3::from_array(world) - self.frame).as_vec3()
+    }
+
+    /// Recenter the camera on a bounding sphere, turning to the default
+    /// isometric direction.
+    pub fn reset_to_fit(&mut self, center: Vec3, radius_hint: f32) {
         self.last_cursor = None;
         self.orbiting = false;
         self.panning = false;
 
+        let current = self.orientation;
+        self.yaw = 45.0_f32.to_radians();
+        self.pitch = 30.0_f32.to_radians();
         self.rebuild_orientation_from_yaw_pitch();
+        let target = self.orientation;
+        self.orientation = current;
+        self.turn_to(target);
+        self.glide_to(self.frame + center.as_dvec3(), radius_hint.max(1.0) * 2.5);
+    }
+
+    /// Center the view on a region without changing the viewing direction.
+    pub fn focus_on(&mut self, center: Vec3, radius: f32) {
+        self.target = self.frame + center.as_dvec3();
+        self.radius = radius.max(1.0) * 2.5;
+        self.ortho_half_height = self.radius * half_fov_tan(self.fov_y_deg);
+        self.animation = None;
+        self.framing = None;
+    }
+
+    /// Glide to frame the box `min`..`max` (in world space) without changing
+    /// the viewing direction, close enough that it fills most of the viewport.
+    pub fn zoom_to(&mut self, min: DVec3, max: DVec3) {
+        let center = (min + max) * 0.5;
+        let sphere_radius = ((max - min).length() * 0.5).max(1e-3) as f32;
+        let aspect = self.aspect();
+        // In orthographic views the distance only keeps the eye outside the box.
+        let half_fov_y = self.fov_y_deg * DEG_TO_RAD * 0.5;
+        let half_fov_x = (half_fov_y.tan() * aspect).atan();
+        let radius = sphere_radius / half_fov_y.min(half_fov_x).sin();
+        let half_height = sphere_radius / aspect.min(1.0);
+        self.glide(
+            center,
+            (radius * 1.1).clamp(1e-3, self.far * 0.5),
+            half_height * 1.1,
+        );
+    }
+
+    /// Animate the orientation to `target` over the transition duration.
+    fn turn_to(&mut self, target: Quat) {
+        self.animation = Some(CameraAnimation::new(
+            self.orientation,
+            target,
+            self.transition_secs,
+        ));
+    }
+
+    /// Animate the orbit target and distance over the transition duration; an
+    /// orthographic view is scaled to show what a perspective one would at
+    /// that distance.
+    fn glide_to(&mut self, target: DVec3, radius: f32) {
+        self.glide(target, radius, radius * half_fov_tan(self.fov_y_deg));
+    }
+
+    fn glide(&mut self, target: DVec3, radius: f32, half_height: f32) {
+        self.framing = Some(FrameAnimation {
+            start_target: self.target,
+            end_target: target,
+            start_radius: self.radius,
+            end_radius: radius,
+            start_half_height: self.ortho_half_height,
+            end_half_height: half_height,
+            progress: 0.0,
+            duration_secs: self.transition_secs,
+        });
     }
 
     fn rebuild_orientation_from_yaw_pitch(&mut self) {
@@ -143,6 +266,7 @@ impl CameraController {
     }
 
     pub fn update(&mut self, dt_secs: f32) -> bool {
+        let framed = self.update_framing(dt_secs);
         if let Some(anim) = self.animation.as_mut() {
             if let Some(orientation) = anim.update(dt_secs) {
                 self.orientation = orientation;
@@ -155,23 +279,58 @@ impl CameraController {
                 true
             }
         } else {
-            false
+            framed
         }
     }
 
+    fn update_framing(&mut self, dt_secs: f32) -> bool {
+        let Some(framing) = self.framing.as_mut() else {
+            return false;
+        };
+        match framing.update(dt_secs) {
+            Some((target, radius, half_height)) => {
+                self.target = target;
+                self.radius = radius;
+                self.ortho_half_height = half_height;
+            }
+            None => {
+                self.target = framing.end_target;
+                self.radius = framing.end_radius;
+                self.ortho_half_height = framing.end_half_height;
+                self.framing = None;
+            }
+        }
+        true
+    }
+
     pub fn update_viewport(&mut self, origin: (u32, u32), size: (u32, u32)) {
         self.viewport_origin = (origin.0 as f32, origin.1 as f32);
         self.viewport_size = size;
     }
 
+    /// View-projection for positions relative to the frame.
     pub fn view_projection(&self) -> [[f32; 4]; 4] {
+        self.view_proj(self.aspect()).to_cols_array_2d()
+    }
+
+    /// View-projection for positions relative to [`Self::render_origin`]
+    /// (the eye), which keeps far-off parts precise on the GPU.
+    pub fn relative_view_projection(&self) -> [[f32; 4]; 4] {
+        self.relative_view_proj(self.aspect()).to_cols_array_2d()
+    }
+
+    /// Origin of the camera-relative coordinates the viewport renders in.
+    pub fn render_origin(&self) -> [f64; 3] {
+        self.eye().to_array()
+    }
+
+    fn aspect(&self) -> f32 {
         let (w, h) = self.viewport_size;
-        let aspect = if w == 0 || h == 0 {
+        if w == 0 || h == 0 {
             1.0
         } else {
             w as f32 / h as f32
-        };
-        self.view_proj(aspect).to_cols_array_2d()
+        }
     }
 
     /// Get viewport info: (origin_x, origin_y, width, height)
@@ -184,31 +343,42 @@ impl CameraController {
         )
     }
 
-    /// Get the active orbit pivot point (only set while orbiting with a pivot)
-    pub fn active_pivot(&self) -> Option<Vec3> {
-        self.active_pivot
+    /// Length (mm) a pixel spans at the depth of the camera target, the scale
+    /// the scale bar and rulers show.
+    pub fn mm_per_pixel(&self) -> f32 {
+        2.0 * self.half_height() / self.viewport_size.1.max(1) as f32
+    }
+
+    /// Half the height of the view at the depth of the camera target.
+    pub(super) fn half_height(&self) -> f32 {
+        match self.projection {
+            ProjectionMode::Perspective => self.radius * half_fov_tan(self.fov_y_deg),
+            ProjectionMode::Orthographic => self.ortho_half_height,
+        }
     }
 
-    /// Project a world position to screen coordinates
+    /// Get the active orbit pivot point in world space (only set while
+    /// orbiting with a pivot)
+    pub fn active_pivot(&self) -> Option<[f64; 3]> {
+        self.active_pivot.map(|pivot| pivot.to_array())
+    }
+
+    /// Project a position (relative to the frame) to screen coordinates
     /// Returns (x, y) in pixels relative to viewport, or None if behind camera
     pub fn world_to_screen(&self, world_pos: Vec3) -> Option<(f32, f32)> {
         let (w, h) = self.viewport_size;
-        let aspect = if w == 0 || h == 0 {
-            1.0
-        } else {
-            w as f32 / h as f32
-        };
-        let view_proj = self.view_proj(aspect);
+        let view_proj = self.relative_view_proj(self.aspect());
 
         // Transform to clip space
-        let clip = view_proj * world_pos.extend(1.0);
+        let relative = (self.frame + world_pos.as_dvec3() - self.eye()).as_vec3();
+        let clip = view_proj * relative.extend(1.0);
 
         // Check if behind camera
         if clip.w <= 0.0 {
             return None;
         }
 
-        // Perspective divide to NDC
+        // Perspective divide to NDC (w is 1 in orthographic views)
         let ndc = clip.truncate() / clip.w;
 
         // Convert NDC to screen coordinates (Vulkan-style, Y grows downward)
@@ -219,8 +389,8 @@ impl CameraController {
     }
 
     /// Convert viewport-local coordinates (relative to the viewport origin) to a
-    /// world position on a plane. This is useful when we already have cursor
-    /// coordinates expressed in the viewport's local space.
+    /// position on a plane, both relative to the frame. This is useful when we
+    /// already have cursor coordinates expressed in the viewport's local space.
     pub fn viewport_to_plane(
         &self,
         viewport_x: f32,
@@ -229,18 +399,13 @@ impl CameraController {
         plane_normal: Vec3,
     ) -> Option<Vec3> {
         let (w, h) = self.viewport_size;
-        let aspect = if w == 0 || h == 0 {
-            1.0
-        } else {
-            w as f32 / h as f32
-        };
 
         // Convert viewport-local coordinates to NDC in the range [-1, 1].
         let ndc_x = (viewport_x / w as f32) * 2.0 - 1.0;
         let ndc_y = 1.0 - (viewport_y / h as f32) * 2.0; // Flip Y
 
-        // Get inverse view-projection
-        let view_proj = self.view_proj(aspect);
+        // Get inverse view-projection (relative to the eye)
+        let view_proj = self.relative_view_proj(self.aspect());
         let inv_view_proj = view_proj.inverse();
 
         // Create ray in clip space
@@ -258,43 +423,54 @@ impl CameraController {
         let near = near_world.truncate() / near_world.w;
         let far = far_world.truncate() / far_world.w;
 
-        // Ray direction
-        let ray_dir = (far - near).normalize();
-        let ray_origin = self.position_vec();
+        // Ray direction; the intersection is found in double precision. Rays
+        // start level with the eye: at the eye itself in perspective, and at
+        // the pixel's spot on the eye plane in orthographic views, where all
+        // rays are parallel.
+        let direction = (far - near).normalize();
+        let ray_dir = direction.as_dvec3();
+        let ray_origin = self.eye() + (near - direction * direction.dot(near)).as_dvec3();
 
         // Ray-plane intersection
-        let normal = plane_normal.normalize();
+        let normal = plane_normal.normalize().as_dvec3();
         let denom = ray_dir.dot(normal);
 
         if denom.abs() < 1e-6 {
             return None; // Ray parallel to plane
         }
 
-        let t = (plane_origin - ray_origin).dot(normal) / denom;
+        let t = (self.frame + plane_origin.as_dvec3() - ray_origin).dot(normal) / denom;
         if t < 0.0 {
             return None; // Plane behind ray
         }
 
-        Some(ray_origin + ray_dir * t)
+        Some((ray_origin + ray_dir * t - self.frame).as_vec3())
     }
 
     fn view_proj(&self, aspect: f32) -> Mat4 {
+        self.relative_view_proj(aspect) * Mat4::from_translation(-self.position_vec())
+    }
+
+    fn relative_view_proj(&self, aspect: f32) -> Mat4 {
         let view = self.view_matrix();
-        let fov_persp_rad = self.fov_y_deg * DEG_TO_RAD;
-        let fov_ortho_rad = 50.0_f32.to_radians();
         let proj = match self.projection {
-            ProjectionMode::Perspective => {
-                Mat4::perspective_rh(fov_persp_rad, aspect.max(0.001), self.near, self.far)
-            }
+            ProjectionMode::Perspective => Mat4::perspective_rh(
+                self.fov_y_deg * DEG_TO_RAD,
+                aspect.max(0.001),
+                self.near,
+                self.far,
+            ),
             ProjectionMode::Orthographic => {
-                let half_height = self.radius * (fov_ortho_rad * 0.5).tan();
-                let half_width = half_height * aspect;
+                let half_height = self.ortho_half_height;
+                let half_width = half_height * aspect.max(0.001);
+                // Zooming doesn't move the eye, so the volume reaches behind it
+                // to keep parts larger than the orbit distance whole.
                 Mat4::orthographic_rh(
                     -half_width,
                     half_width,
                     -half_height,
                     half_height,
-                    self.near,
+                    -self.far,
                     self.far,
                 )
             }
@@ -302,15 +478,21 @@ impl CameraController {
         proj * view
     }
 
+    /// View rotation, with the eye at the origin.
     fn view_matrix(&self) -> Mat4 {
-        let eye = self.position_vec();
+        let forward = self.orientation * (-self.axis_depth_vec());
         let up = self.orientation * self.axis_vertical_vec();
-        Mat4::look_at_rh(eye, self.target, up)
+        Mat4::look_to_rh(Vec3::ZERO, forward, up)
     }
 
-    pub(super) fn position_vec(&self) -> Vec3 {
+    pub(super) fn eye(&self) -> DVec3 {
         let forward = self.orientation * (-self.axis_depth_vec());
-        self.target - forward * self.radius
+        self.target - (forward * self.radius).as_dvec3()
+    }
+
+    /// Eye position relative to the frame.
+    pub(super) fn position_vec(&self) -> Vec3 {
+        (self.eye() - self.frame).as_vec3()
     }
 
     pub fn position(&self) -> [f32; 3] {
@@ -318,13 +500,55 @@ impl CameraController {
     }
 
     pub fn target(&self) -> [f32; 3] {
-        self.target.to_array()
+        (self.target - self.frame).as_vec3().to_array()
     }
 
     pub fn orientation(&self) -> [f32; 4] {
         self.orientation.to_array()
     }
 
+    /// Placement to record in the session.
+    pub fn pose(&self) -> CameraPose {
+        let orientation = self
+            .animation
+            .as_ref()
+            .map_or(self.orientation, CameraAnimation::target);
+        let (target, radius) = self
+            .framing
+            .as_ref()
+            .map_or((self.target, self.radius), |framing| {
+                (framing.end_target, framing.end_radius)
+            });
+        let half_height = self
+            .framing
+            .as_ref()
+            .map_or(self.ortho_half_height, |framing| framing.end_half_height);
+        CameraPose {
+            target: target.to_array(),
+            radius,
+            orientation: orientation.to_array(),
+            ortho_half_height: Some(half_height),
+        }
+    }
+
+    /// Move to a recorded placement.
+    pub fn set_pose(&mut self, pose: &CameraPose) {
+        let orientation = Quat::from_array(pose.orientation);
+        if !orientation.is_finite() || orientation.length_squared() < 1e-6 {
+            return;
+        }
+        self.target = DVec3::from_array(pose.target);
+        self.radius = pose.radius.max(1e-3);
+        self.ortho_half_height = pose
+            .ortho_half_height
+            .unwrap_or_else(|| self.radius * half_fov_tan(self.fov_y_deg))
+            .max(1e-3);
+        self.orientation = orientation.normalize();
+        self.animation = None;
+        self.framing = None;
+        self.sync_yaw_pitch_from_orientation();
+    }
+
     pub fn axis_system(&self) -> AxisSystem {
         self.axes
     }
@@ -383,30 +607,42 @@ impl CameraController {
         self.radius = self
             .radius
             .clamp(settings.min_distance, settings.max_distance);
+        // Switching projection keeps the size of what is at the target.
+        match (self.projection, settings.projection) {
+            (ProjectionMode::Perspective, ProjectionMode::Orthographic) => {
+                self.ortho_half_height = self.radius * half_fov_tan(settings.fov_degrees);
+            }
+            (ProjectionMode::Orthographic, ProjectionMode::Perspective) => {
+                self.radius = (self.ortho_half_height / half_fov_tan(settings.fov_degrees))
+                    .clamp(settings.min_distance, settings.max_distance);
+            }
+            _ => {}
+        }
         self.projection = settings.projection;
         self.fov_y_deg = settings.fov_degrees;
+        self.transition_secs = settings.transition_secs();
         self.last_cursor = None;
         self.orbiting = false;
         self.panning = false;
-        if self.axis_preset != settings.axis_preset {
-            self.axis_preset = settings.axis_preset;
-            self.axes = AxisSystem::from(self.axis_preset);
+        let axes = settings.axis_system();
+        if self.axes != axes {
+            self.axes = axes;
             self.sync_yaw_pitch_from_orientation();
         }
     }
 
-    /// Set a dynamic orbit pivot point.
+    /// Set a dynamic orbit pivot point, in world space.
     /// When orbiting starts, the camera will orbit around this point instead of target.
     /// Call with None to clear the pivot (orbit around target).
-    pub fn set_orbit_pivot(&mut self, pivot: Option<Vec3>) {
+    pub fn set_orbit_pivot(&mut self, pivot: Option<[f64; 3]>) {
         if !self.orbiting {
-            self.orbit_pivot = pivot;
+            self.orbit_pivot = pivot.map(DVec3::from_array);
         }
     }
 
     pub fn snap_to_view(&mut self, view: CameraSnapView) {
         let target = self.canonical_quat_to_world(view.orientation());
-        self.animation = Some(CameraAnimation::new(self.orientation, target, 0.25));
+        self.turn_to(target);
     }
 
     /// Orient camera to look at a plane defined by origin, normal, and up direction.
@@ -432,15 +668,13 @@ impl CameraController {
         let rotation_mat = Mat3::from_cols(right, camera_up, forward);
         let target_orientation = Quat::from_mat3(&rotation_mat);
 
-        // Update target to plane origin
-        self.target = plane_origin;
-
-        // Animate to new orientation
-        self.animation = Some(CameraAnimation::new(
-            self.orientation,
-            target_orientation,
-            0.3,
-        ));
+        // Move the target to the plane origin while turning to face it
+        self.glide(
+            self.frame + plane_origin.as_dvec3(),
+            self.radius,
+            self.ortho_half_height,
+        );
+        self.turn_to(target_orientation);
     }
 
     pub fn apply_rotate_delta(&mut self, delta: &RotateDelta, _settings: &CameraSettings) {
@@ -456,7 +690,7 @@ impl CameraController {
         }
         let rotation = Quat::from_axis_angle(axis.normalize(), angle_rad);
         let target = (rotation * current).normalize();
-        self.animation = Some(CameraAnimation::new(current, target, 0.2));
+        self.turn_to(target);
     }
 
     pub(super) fn sync_yaw_pitch_from_orientation(&mut self) {
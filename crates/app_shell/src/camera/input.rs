@@ -26,6 +26,7 @@ impl CameraController {
                     }
                     (b, true) if *b == pan_button => {
                         self.panning = true;
+                        self.framing = None;
                         true
                     }
                     (b, false) if *b == pan_button => {
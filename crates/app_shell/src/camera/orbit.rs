@@ -1,9 +1,17 @@
 use glam::{Quat, Vec2};
-use settings::CameraSettings;
+use settings::{CameraSettings, ProjectionMode};
 
-use super::controller::{CameraController, DEG_TO_RAD};
+use super::controller::{half_fov_tan, CameraController};
 
 impl CameraController {
+    /// Orbit around the target by a drag of `delta` physical pixels, as when
+    /// dragging on the orientation cube. Stops any running animation.
+    pub fn orbit_by(&mut self, delta: [f32; 2], settings: &CameraSettings) {
+        self.animation = None;
+        self.framing = None;
+        self.orbit_trackball(Vec2::from(delta), settings);
+    }
+
     pub(super) fn orbit_trackball(&mut self, delta: Vec2, settings: &CameraSettings) {
         let sens = settings.orbit_sensitivity * 0.005;
 
@@ -26,15 +34,14 @@ impl CameraController {
         let delta_q = yaw_q * pitch_q;
 
         if let Some(pivot) = self.active_pivot {
-            let eye_world = self.position_vec();
-            let pivot_to_eye_world = eye_world - pivot;
+            let pivot_to_eye_world = (self.eye() - pivot).as_vec3();
             let new_pivot_to_eye = delta_q * pivot_to_eye_world;
-            let new_eye = pivot + new_pivot_to_eye;
+            let new_eye = pivot + new_pivot_to_eye.as_dvec3();
 
             self.orientation = (delta_q * self.orientation).normalize();
 
             let new_forward = (self.orientation * -self.axis_depth_vec()).normalize_or_zero();
-            self.target = new_eye + new_forward * self.radius;
+            self.target = new_eye + (new_forward * self.radius).as_dvec3();
         } else {
             self.orientation = (delta_q * self.orientation).normalize();
         }
@@ -46,17 +53,27 @@ impl CameraController {
         let right = (self.orientation * -self.control_horizontal_vec()).normalize_or_zero();
         let up = (self.orientation * -self.axis_vertical_vec()).normalize_or_zero();
 
-        let fov_rad = self.fov_y_deg * DEG_TO_RAD;
-        let visible_height = 2.0 * self.radius * (fov_rad * 0.5).tan();
-        let world_per_pixel = visible_height / height;
+        let world_per_pixel = 2.0 * self.half_height() / height;
 
         let offset = (delta.x * world_per_pixel) * right + (delta.y * world_per_pixel) * up;
-        self.target += offset;
+        self.target += offset.as_dvec3();
     }
 
     pub(super) fn zoom(&mut self, amount: f32, settings: &CameraSettings) {
         let direction = if settings.invert_zoom { 1.0 } else { -1.0 };
         let delta = amount * direction * settings.zoom_sensitivity;
-        self.radius = (self.radius + delta).clamp(settings.min_distance, settings.max_distance);
+        self.framing = None;
+        match self.projection {
+            ProjectionMode::Perspective => {
+                self.radius =
+                    (self.radius + delta).clamp(settings.min_distance, settings.max_distance);
+            }
+            // Scale the view by what the same dolly would show at the target.
+            ProjectionMode::Orthographic => {
+                let tan = half_fov_tan(settings.fov_degrees);
+                self.ortho_half_height = (self.ortho_half_height + delta * tan)
+                    .clamp(settings.min_distance * tan, settings.max_distance * tan);
+            }
+        }
     }
 }
@@ -0,0 +1,366 @@
+//! Drawing, picking, and dragging of the gizmos provided by workbenches.
+
+use core_document::{
+    Gizmo, GizmoDrag, GizmoDragPhase, GizmoHandle, GizmoKind, GizmoMotion, ScreenSpaceOverlay,
+    SnapOptions,
+};
+use glam::{Vec2, Vec3};
+
+use crate::camera::CameraController;
+
+/// Cursor distance in pixels within which a handle can be grabbed.
+const PICK_RADIUS: f32 = 7.0;
+const THICKNESS: f32 = 2.0;
+const ACTIVE_THICKNESS: f32 = 3.0;
+const ACTIVE_COLOR: [f32; 3] = [1.0, 0.85, 0.2];
+const AXIS_COLORS: [[f32; 3]; 3] = [[0.9, 0.25, 0.25], [0.25, 0.8, 0.25], [0.3, 0.45, 0.95]];
+const PLANE_COLOR: [f32; 3] = [0.55, 0.7, 0.85];
+/// Arrowhead length and half width, and the half size of boxes, in pixels.
+const HEAD_LENGTH: f32 = 12.0;
+const HEAD_WIDTH: f32 = 5.0;
+const BOX_SIZE: f32 = 5.0;
+const RING_SEGMENTS: usize = 48;
+/// Handle positions as fractions of the gizmo size.
+const PLANE_SQUARE: (f32, f32) = (0.25, 0.45);
+const PLANE_HALF_SIZE: f32 = 0.6;
+const RING_RADIUS: f32 = 0.8;
+/// Smallest scale factor a drag produces (scaling never mirrors).
+const MIN_SCALE: f32 = 0.01;
+
+type Segment = [Vec2; 2];
+
+/// Screen-space lines for all gizmos, with the hovered or dragged handle highlighted.
+pub fn overlays(
+    gizmos: &[Gizmo],
+    camera: &CameraController,
+    active: Option<(&str, GizmoHandle)>,
+) -> Vec<ScreenSpaceOverlay> {
+    let mut overlays = Vec::new();
+    for gizmo in gizmos {
+        for handle in gizmo.handles() {
+            let (color, thickness) = if active == Some((gizmo.id.as_str(), handle)) {
+                (ACTIVE_COLOR, ACTIVE_THICKNESS)
+            } else {
+                (handle_color(gizmo, handle), THICKNESS)
+            };
+            for [start, end] in handle_segments(gizmo, handle, camera) {
+                overlays.push(ScreenSpaceOverlay::new(
+                    start.to_array(),
+                    end.to_array(),
+                    color,
+                    thickness,
+                ));
+            }
+        }
+    }
+    overlays
+}
+
+/// Handle under the cursor (viewport coordinates), as (gizmo id, handle).
+pub fn pick(
+    gizmos: &[Gizmo],
+    camera: &CameraController,
+    cursor: (f32, f32),
+) -> Option<(String, GizmoHandle)> {
+    let cursor = Vec2::new(cursor.0, cursor.1);
+    let mut best: Option<(f32, &Gizmo, GizmoHandle)> = None;
+    for gizmo in gizmos {
+        for handle in gizmo.handles() {
+            let distance = handle_segments(gizmo, handle, camera)
+                .iter()
+                .map(|&segment| distance_to_segment(cursor, segment))
+                .fold(f32::INFINITY, f32::min);
+            if distance <= PICK_RADIUS && best.map_or(true, |(d, _, _)| distance < d) {
+                best = Some((distance, gizmo, handle));
+            }
+        }
+    }
+    best.map(|(_, gizmo, handle)| (gizmo.id.clone(), handle))
+}
+
+fn handle_color(gizmo: &Gizmo, handle: GizmoHandle) -> [f32; 3] {
+    match (gizmo.kind, handle) {
+        (GizmoKind::Plane, GizmoHandle::Plane(_)) => PLANE_COLOR,
+        (_, GizmoHandle::Axis(axis) | GizmoHandle::Plane(axis) | GizmoHandle::Ring(axis)) => {
+            AXIS_COLORS[axis.min(2)]
+        }
+        (_, GizmoHandle::Center) => [0.9, 0.9, 0.9],
+    }
+}
+
+/// World-space length of the gizmo's handles at its current screen size.
+fn world_size(gizmo: &Gizmo, camera: &CameraController) -> Option<f32> {
+    let origin = Vec3::from(gizmo.origin);
+    let view = (Vec3::from(camera.target()) - Vec3::from(camera.position())).try_normalize()?;
+    let side = view.any_orthonormal_vector();
+    let a = project(camera, origin)?;
+    let b = project(camera, origin + side)?;
+    let pixels_per_unit = a.distance(b);
+    (pixels_per_unit > f32::EPSILON).then(|| gizmo.size / pixels_per_unit)
+}
+
+/// Project a world point to viewport coordinates.
+fn project(camera: &CameraController, world: Vec3) -> Option<Vec2> {
+    let (origin_x, origin_y, _, _) = camera.viewport_info();
+    camera
+        .world_to_screen(world)
+        .map(|(x, y)| Vec2::new(x - origin_x, y - origin_y))
+}
+
+/// Lines (viewport coordinates) drawing one handle; also used for picking.
+fn handle_segments(gizmo: &Gizmo, handle: GizmoHandle, camera: &CameraController) -> Vec<Segment> {
+    let Some(size) = world_size(gizmo, camera) else {
+        return Vec::new();
+    };
+    let origin = Vec3::from(gizmo.origin);
+    let axes = gizmo.axes.map(Vec3::from);
+    // The two axes spanning the plane perpendicular to `axis`.
+    let others = |axis: usize| (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+    let mut segments = Vec::new();
+    let mut polyline = |points: &[Vec3], closed: bool| {
+        let projected: Vec<Option<Vec2>> = points.iter().map(|&p| project(camera, p)).collect();
+        let count = if closed {
+            projected.len()
+        } else {
+            projected.len().saturating_sub(1)
+        };
+        for i in 0..count {
+            if let (Some(a), Some(b)) = (projected[i], projected[(i + 1) % projected.len()]) {
+                segments.push([a, b]);
+            }
+        }
+    };
+
+    match handle {
+        GizmoHandle::Axis(axis) => {
+            let tip = origin + axes[axis.min(2)] * size;
+            polyline(&[origin, tip], false);
+            let (Some(start), Some(end)) = (project(camera, origin), project(camera, tip)) else {
+                return segments;
+            };
+            if gizmo.kind == GizmoKind::Scale {
+                segments.extend(screen_box(end, BOX_SIZE));
+            } else if let Some(direction) = (end - start).try_normalize() {
+                let back = end - direction * HEAD_LENGTH;
+                let side = direction.perp() * HEAD_WIDTH;
+                segments.push([end, back + side]);
+                segments.push([end, back - side]);
+            }
+        }
+        GizmoHandle::Plane(axis) => {
+            let (u, v) = others(axis.min(2));
+            let corners = if gizmo.kind == GizmoKind::Plane {
+                let half = size * PLANE_HALF_SIZE;
+                [(-half, -half), (half, -half), (half, half), (-half, half)]
+            } else {
+                let (near, far) = (size * PLANE_SQUARE.0, size * PLANE_SQUARE.1);
+                [(near, near), (far, near), (far, far), (near, far)]
+            };
+            let points: Vec<Vec3> = corners
+                .iter()
+                .map(|&(a, b)| origin + u * a + v * b)
+                .collect();
+            polyline(&points, true);
+        }
+        GizmoHandle::Ring(axis) => {
+            let (u, v) = others(axis.min(2));
+            let radius = size * RING_RADIUS;
+            let points: Vec<Vec3> = (0..RING_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                    origin + (u * angle.cos() + v * angle.sin()) * radius
+                })
+                .collect();
+            polyline(&points, true);
+        }
+        GizmoHandle::Center => {
+            if let Some(center) = project(camera, origin) {
+                segments.extend(screen_box(center, BOX_SIZE * 1.4));
+            }
+        }
+    }
+    segments
+}
+
+fn screen_box(center: Vec2, half: f32) -> [Segment; 4] {
+    let corners = [
+        center + Vec2::new(-half, -half),
+        center + Vec2::new(half, -half),
+        center + Vec2::new(half, half),
+        center + Vec2::new(-half, half),
+    ];
+    [0, 1, 2, 3].map(|i| [corners[i], corners[(i + 1) % 4]])
+}
+
+fn distance_to_segment(p: Vec2, [a, b]: Segment) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+/// A handle being dragged.
+pub struct GizmoDragState {
+    /// The gizmo as it was when the drag started.
+    gizmo: Gizmo,
+    handle: GizmoHandle,
+    start_cursor: Vec2,
+    /// Point grabbed on the drag plane (for axis and plane handles).
+    grab: Option<Vec3>,
+    /// Cursor angle around the gizmo origin at the last update (ring handles).
+    last_angle: f32,
+    angle: f32,
+    motion: GizmoMotion,
+}
+
+impl GizmoDragState {
+    /// Grab `handle` of `gizmo` with the cursor at `cursor` (viewport coordinates).
+    pub fn start(
+        gizmo: &Gizmo,
+        handle: GizmoHandle,
+        camera: &CameraController,
+        cursor: (f32, f32),
+    ) -> Self {
+        let start_cursor = Vec2::new(cursor.0, cursor.1);
+        let motion = match (gizmo.kind, handle) {
+            (_, GizmoHandle::Ring(axis)) => GizmoMotion::Rotate {
+                axis: gizmo.axes[axis.min(2)],
+                angle: 0.0,
+            },
+            (GizmoKind::Scale, _) => GizmoMotion::Scale([1.0; 3]),
+            _ => GizmoMotion::Translate([0.0; 3]),
+        };
+        let mut state = Self {
+            gizmo: gizmo.clone(),
+            handle,
+            start_cursor,
+            grab: None,
+            last_angle: 0.0,
+            angle: 0.0,
+            motion,
+        };
+        state.grab = state.plane_hit(camera, start_cursor);
+        state.last_angle = state.screen_angle(camera, start_cursor).unwrap_or(0.0);
+        state
+    }
+
+    pub fn event(&self, phase: GizmoDragPhase) -> GizmoDrag {
+        GizmoDrag {
+            gizmo: self.gizmo.id.clone(),
+            handle: self.handle,
+            phase,
+            motion: self.motion,
+        }
+    }
+
+    pub fn active(&self) -> (&str, GizmoHandle) {
+        (self.gizmo.id.as_str(), self.handle)
+    }
+
+    /// Follow the cursor; positions off the drag plane keep the last motion.
+    pub fn update(&mut self, camera: &CameraController, cursor: (f32, f32), snap: &SnapOptions) {
+        let cursor = Vec2::new(cursor.0, cursor.1);
+        let origin = Vec3::from(self.gizmo.origin);
+        let axes = self.gizmo.axes.map(Vec3::from);
+        match (self.gizmo.kind, self.handle) {
+            (_, GizmoHandle::Ring(axis)) => {
+                let Some(angle) = self.screen_angle(camera, cursor) else {
+                    return;
+                };
+                let mut step = angle - self.last_angle;
+                if step > std::f32::consts::PI {
+                    step -= std::f32::consts::TAU;
+                } else if step < -std::f32::consts::PI {
+                    step += std::f32::consts::TAU;
+                }
+                self.last_angle = angle;
+                // Screen Y grows downward, so the screen angle turns clockwise;
+                // a ring seen from the front of its axis turns the other way.
+                let eye = Vec3::from(camera.position());
+                let facing = axes[axis.min(2)].dot(eye - origin) >= 0.0;
+                self.angle += if facing { -step } else { step };
+                self.motion = GizmoMotion::Rotate {
+                    axis: axes[axis.min(2)].to_array(),
+                    angle: self.angle,
+                };
+            }
+            (GizmoKind::Scale, GizmoHandle::Center) => {
+                let Some(center) = project(camera, origin) else {
+                    return;
+                };
+                let start = self.start_cursor.distance(center);
+                if start > f32::EPSILON {
+                    let factor = (cursor.distance(center) / start).max(MIN_SCALE);
+                    self.motion = GizmoMotion::Scale([factor; 3]);
+                }
+            }
+            (GizmoKind::Scale, GizmoHandle::Axis(axis)) => {
+                let axis = axis.min(2);
+                let (Some(grab), Some(hit)) = (self.grab, self.plane_hit(camera, cursor)) else {
+                    return;
+                };
+                let start = (grab - origin).dot(axes[axis]);
+                if start.abs() > f32::EPSILON {
+                    let mut factors = [1.0; 3];
+                    factors[axis] = ((hit - origin).dot(axes[axis]) / start).max(MIN_SCALE);
+                    self.motion = GizmoMotion::Scale(factors);
+                }
+            }
+            (_, GizmoHandle::Axis(axis)) => {
+                let axis = axes[axis.min(2)];
+                let (Some(grab), Some(hit)) = (self.grab, self.plane_hit(camera, cursor)) else {
+                    return;
+                };
+                let distance = snap_distance((hit - grab).dot(axis), snap);
+                self.motion = GizmoMotion::Translate((axis * distance).to_array());
+            }
+            (_, GizmoHandle::Plane(axis)) => {
+                let axis = axis.min(2);
+                let (u, v) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+                let (Some(grab), Some(hit)) = (self.grab, self.plane_hit(camera, cursor)) else {
+                    return;
+                };
+                let delta = hit - grab;
+                let moved =
+                    u * snap_distance(delta.dot(u), snap) + v * snap_distance(delta.dot(v), snap);
+                self.motion = GizmoMotion::Translate(moved.to_array());
+            }
+            (_, GizmoHandle::Center) => {}
+        }
+    }
+
+    /// Where the cursor ray meets the plane the handle moves in: the plane of
+    /// a plane handle, or the plane through an axis that best faces the camera.
+    fn plane_hit(&self, camera: &CameraController, cursor: Vec2) -> Option<Vec3> {
+        let origin = Vec3::from(self.gizmo.origin);
+        let axes = self.gizmo.axes.map(Vec3::from);
+        let view = (Vec3::from(camera.target()) - Vec3::from(camera.position())).try_normalize()?;
+        let normal = match self.handle {
+            GizmoHandle::Plane(axis) => axes[axis.min(2)],
+            GizmoHandle::Axis(axis) => {
+                let axis = axes[axis.min(2)];
+                axis.cross(view.cross(axis)).try_normalize().unwrap_or(view)
+            }
+            GizmoHandle::Ring(_) | GizmoHandle::Center => return None,
+        };
+        camera.viewport_to_plane(cursor.x, cursor.y, origin, normal)
+    }
+
+    /// Angle of the cursor around the projected gizmo origin.
+    fn screen_angle(&self, camera: &CameraController, cursor: Vec2) -> Option<f32> {
+        let center = project(camera, Vec3::from(self.gizmo.origin))?;
+        let offset = cursor - center;
+        (offset.length_squared() > 1.0).then(|| offset.y.atan2(offset.x))
+    }
+}
+
+fn snap_distance(distance: f32, snap: &SnapOptions) -> f32 {
+    if snap.snap_to_grid && snap.grid_spacing > 0.0 {
+        (distance / snap.grid_spacing).round() * snap.grid_spacing
+    } else {
+        distance
+    }
+}
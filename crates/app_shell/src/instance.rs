@@ -0,0 +1,166 @@
+//! Single-instance handoff: a second launch (e.g. double-clicking a `.prtcad`
+//! file) passes its files to the running instance and exits.
+//!
+//! The running instance listens on a Unix socket in the user's private
+//! runtime directory (a loopback TCP port elsewhere, announced in a file in
+//! that directory with a token the sender has to present). Each connection
+//! sends the token on the first line, then one absolute path per line.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use settings::SettingsStore;
+
+use crate::app_log;
+
+/// Longest a sender may take to hand over its files before it is dropped.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of trying to become the running instance.
+pub enum Startup {
+    /// No other instance runs; files sent by later launches arrive here.
+    Primary(Receiver<PathBuf>),
+    /// The files were handed to the running instance.
+    Forwarded,
+}
+
+/// Hand `files` to a running instance, or start listening for later launches.
+/// If the handoff mechanism itself fails, this instance runs on its own.
+pub fn claim(files: &[PathBuf]) -> Startup {
+    let files: Vec<PathBuf> = files
+        .iter()
+        .map(|file| std::path::absolute(file).unwrap_or_else(|_| file.clone()))
+        .collect();
+    if !files.is_empty() && forward(&files).is_ok() {
+        return Startup::Forwarded;
+    }
+    let (tx, rx) = mpsc::channel();
+    // Launched without files while another instance runs: leave it the listener.
+    if !is_running() {
+        if let Err(err) = listen(tx) {
+            app_log::warn(format!(
+                "Files opened from the desktop will start a new window: {err:#}"
+            ));
+        }
+    }
+    Startup::Primary(rx)
+}
+
+fn send(mut stream: impl Write, token: &str, files: &[PathBuf]) -> Result<()> {
+    writeln!(stream, "{token}")?;
+    for file in files {
+        writeln!(stream, "{}", file.display())?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Read the paths of one connection, if it presents `token`.
+fn receive(stream: impl std::io::Read, token: &str, tx: &Sender<PathBuf>) {
+    let mut lines = BufReader::new(stream).lines();
+    if !matches!(lines.next(), Some(Ok(line)) if line == token) {
+        return;
+    }
+    for line in lines.map_while(std::result::Result::ok) {
+        if !line.is_empty() && tx.send(PathBuf::from(line)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Socket of the running instance, in a directory only the user can open, so
+/// the socket is never reachable by others, even before it is bound.
+#[cfg(unix)]
+fn socket_path() -> Result<PathBuf> {
+    Ok(SettingsStore::runtime_dir()?.join("instance.sock"))
+}
+
+#[cfg(unix)]
+fn is_running() -> bool {
+    socket_path().is_ok_and(|path| std::os::unix::net::UnixStream::connect(path).is_ok())
+}
+
+#[cfg(unix)]
+fn forward(files: &[PathBuf]) -> Result<()> {
+    let stream = std::os::unix::net::UnixStream::connect(socket_path()?)?;
+    // Only the owner can connect to the socket, so no token is needed.
+    send(stream, "", files)
+}
+
+#[cfg(unix)]
+fn listen(tx: Sender<PathBuf>) -> Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path()?;
+    // Nobody answered, so a socket file left here belongs to an instance that crashed.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("cannot listen on {}", path.display()))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(std::result::Result::ok) {
+            if stream.set_read_timeout(Some(RECEIVE_TIMEOUT)).is_ok() {
+                receive(stream, "", &tx);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn announcement_path() -> Result<PathBuf> {
+    Ok(SettingsStore::runtime_dir()?.join("instance"))
+}
+
+/// Port and token announced by the running instance.
+#[cfg(not(unix))]
+fn announcement() -> Result<(u16, String)> {
+    let announcement = std::fs::read_to_string(announcement_path()?)?;
+    let (port, token) = announcement
+        .trim()
+        .split_once(' ')
+        .context("malformed instance file")?;
+    Ok((port.parse()?, token.to_string()))
+}
+
+#[cfg(not(unix))]
+fn is_running() -> bool {
+    announcement().is_ok_and(|(port, _)| std::net::TcpStream::connect(("127.0.0.1", port)).is_ok())
+}
+
+#[cfg(not(unix))]
+fn forward(files: &[PathBuf]) -> Result<()> {
+    let (port, token) = announcement()?;
+    let stream = std::net::TcpStream::connect(("127.0.0.1", port))?;
+    send(stream, &token, files)
+}
+
+#[cfg(not(unix))]
+fn listen(tx: Sender<PathBuf>) -> Result<()> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    let port = listener.local_addr()?.port();
+    let token = uuid::Uuid::new_v4().to_string();
+    let path = announcement_path()?;
+    std::fs::write(&path, format!("{port} {token}"))
+        .with_context(|| format!("cannot write {}", path.display()))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(std::result::Result::ok) {
+            if stream.set_read_timeout(Some(RECEIVE_TIMEOUT)).is_ok() {
+                receive(stream, &token, &tx);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Documents among the command-line arguments (everything that is not an option).
+pub fn files_from_args() -> Vec<PathBuf> {
+    std::env::args_os()
+        .skip(1)
+        .filter(|arg| !arg.to_string_lossy().starts_with('-'))
+        .filter(|arg| !arg.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
@@ -0,0 +1,84 @@
+//! Isolate mode: everything but the selected bodies and features is hidden
+//! from the viewport until the user exits it. Only what is drawn (and can be
+//! picked) changes; the document is left alone.
+
+use std::collections::HashSet;
+
+use core_document::{BodyId, Document, FeatureId, SelectionItem};
+use uuid::Uuid;
+
+/// What stays visible while isolating.
+pub struct Isolation {
+    /// Bodies shown with all their features.
+    bodies: HashSet<BodyId>,
+    /// Features shown on their own (with their body's mesh, which they are
+    /// part of, but not its other features).
+    features: HashSet<FeatureId>,
+}
+
+impl Isolation {
+    /// Isolate the selected `items`; None when nothing is selected.
+    pub fn new(items: &[SelectionItem]) -> Option<Self> {
+        let mut isolation = Self {
+            bodies: HashSet::new(),
+            features: HashSet::new(),
+        };
+        for item in items {
+            match *item {
+                SelectionItem::Body(body) | SelectionItem::Element { body, .. } => {
+                    isolation.bodies.insert(body);
+                }
+                SelectionItem::Feature(feature) => {
+                    isolation.features.insert(feature);
+                }
+            }
+        }
+        isolation.is_active().then_some(isolation)
+    }
+
+    /// Whether the submission with `id` (a body or a feature) stays visible.
+    pub fn shows(&self, document: &Document, id: Uuid) -> bool {
+        let (body, feature) = (BodyId(id), FeatureId(id));
+        if self.bodies.contains(&body) || self.features.contains(&feature) {
+            return true;
+        }
+        match document.get_feature_meta(feature) {
+            Some(node) => node.body.is_some_and(|body| self.bodies.contains(&body)),
+            None => self.features.iter().any(|feature| {
+                document
+                    .get_feature_meta(*feature)
+                    .is_some_and(|node| node.body == Some(body))
+            }),
+        }
+    }
+
+    /// Forget deleted bodies and features, returning whether anything is
+    /// left to isolate.
+    pub fn prune(&mut self, document: &Document) -> bool {
+        self.bodies
+            .retain(|id| document.bodies().iter().any(|body| body.id == *id));
+        self.features
+            .retain(|id| document.get_feature_meta(*id).is_some());
+        self.is_active()
+    }
+
+    fn is_active(&self) -> bool {
+        !self.bodies.is_empty() || !self.features.is_empty()
+    }
+
+    /// What is isolated, e.g. "2 bodies, 1 feature".
+    pub fn summary(&self) -> String {
+        let count = |n: usize, one: &str, many: &str| match n {
+            1 => format!("1 {one}"),
+            n => format!("{n} {many}"),
+        };
+        let mut parts = Vec::new();
+        if !self.bodies.is_empty() {
+            parts.push(count(self.bodies.len(), "body", "bodies"));
+        }
+        if !self.features.is_empty() {
+            parts.push(count(self.features.len(), "feature", "features"));
+        }
+        parts.join(", ")
+    }
+}
@@ -0,0 +1,468 @@
+//! Part library: reusable parts kept in a folder chosen in Settings → Part Library.
+//!
+//! The folder is scanned on a background thread, which also loads the preview
+//! saved in each document (or renders one for STL files and older documents).
+//! Parts are inserted into the open document as new
+//! bodies, either copied (independent of the file) or linked: a linked body
+//! keeps a copy of the part and is updated when the file changes. STL parts
+//! are read on the document's job queue; their body gets its mesh once the
+//! file is read.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{bail, Context, Result};
+use axes::AxisSystem;
+use core_document::{BodyId, BodyLink, Document, JobId, JobQueue};
+use egui::ColorImage;
+use glam::Vec3;
+use kernel_api::{ExchangeOptions, TriMesh};
+use wb_mesh::mesh::IndexedMesh;
+use wb_mesh::{MeshFeature, MeshOperation};
+
+use crate::{app_log, thumbnail};
+
+/// Subfolders deeper than this are not scanned.
+const MAX_DEPTH: usize = 4;
+
+/// File formats the library lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartFormat {
+    Document,
+    Step,
+    Stl,
+}
+
+impl PartFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if [".prtcad", ".prtcad.zst", ".prtcad.gz"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+        {
+            Some(PartFormat::Document)
+        } else if name.ends_with(".step") || name.ends_with(".stp") {
+            Some(PartFormat::Step)
+        } else if name.ends_with(".stl") {
+            Some(PartFormat::Stl)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PartFormat::Document => "printCAD",
+            PartFormat::Step => "STEP",
+            PartFormat::Stl => "STL",
+        }
+    }
+
+    /// STEP parts are listed but need the geometry kernel to be inserted.
+    pub fn can_insert(self) -> bool {
+        self != PartFormat::Step
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    /// File name without its extension.
+    pub name: String,
+    pub format: PartFormat,
+}
+
+enum ScanMessage {
+    Entries(Vec<LibraryEntry>),
+    Thumbnail(PathBuf, Option<ColorImage>),
+}
+
+/// Contents of the library folder, refreshed when the folder setting changes
+/// or on request.
+#[derive(Default)]
+pub struct PartLibrary {
+    folder: Option<PathBuf>,
+    entries: Vec<LibraryEntry>,
+    /// Rendered thumbnails (None when the part has no mesh to draw).
+    thumbnails: HashMap<PathBuf, Option<ColorImage>>,
+    scan_rx: Option<Receiver<ScanMessage>>,
+}
+
+impl PartLibrary {
+    pub fn folder(&self) -> Option<&Path> {
+        self.folder.as_deref()
+    }
+
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.entries
+    }
+
+    /// Thumbnail of a part: `None` while it is being rendered, `Some(None)`
+    /// when there is nothing to draw.
+    pub fn thumbnail(&self, path: &Path) -> Option<Option<&ColorImage>> {
+        self.thumbnails.get(path).map(Option::as_ref)
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.scan_rx.is_some()
+    }
+
+    /// Follow the folder setting, rescanning when it changed.
+    pub fn set_folder(&mut self, folder: Option<&Path>) {
+        if self.folder.as_deref() != folder {
+            self.folder = folder.map(Path::to_path_buf);
+            self.rescan();
+        }
+    }
+
+    /// Scan the folder again and re-render all thumbnails.
+    pub fn rescan(&mut self) {
+        self.entries.clear();
+        self.thumbnails.clear();
+        self.scan_rx = None;
+        let Some(folder) = self.folder.clone() else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        self.scan_rx = Some(rx);
+        std::thread::spawn(move || {
+            let mut entries = Vec::new();
+            scan(&folder, 0, &mut entries);
+            entries.sort_by_key(|entry| entry.name.to_lowercase());
+            if tx.send(ScanMessage::Entries(entries.clone())).is_err() {
+                return;
+            }
+            for entry in entries {
+                let image = render_part(&entry);
+                if tx.send(ScanMessage::Thumbnail(entry.path, image)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Collect scan results. Returns true when something changed.
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.scan_rx else {
+            return false;
+        };
+        let mut changed = false;
+        loop {
+            match rx.try_recv() {
+                Ok(ScanMessage::Entries(entries)) => self.entries = entries,
+                Ok(ScanMessage::Thumbnail(path, image)) => {
+                    self.thumbnails.insert(path, image);
+                }
+                Err(mpsc::TryRecvError::Empty) => return changed,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.scan_rx = None;
+                    return true;
+                }
+            }
+            changed = true;
+        }
+    }
+}
+
+fn scan(folder: &Path, depth: usize, entries: &mut Vec<LibraryEntry>) {
+    let Ok(read_dir) = std::fs::read_dir(folder) else {
+        return;
+    };
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                scan(&path, depth + 1, entries);
+            }
+            continue;
+        }
+        let Some(format) = PartFormat::from_path(&path) else {
+            continue;
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match file_name.find('.') {
+            Some(dot) if dot > 0 => file_name[..dot].to_string(),
+            _ => file_name,
+        };
+        entries.push(LibraryEntry { path, name, format });
+    }
+}
+
+/// Modification time of a file in epoch milliseconds (0 if unknown).
+fn modified_time(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+fn read_stl(path: &Path, stl: &ExchangeOptions, axes: AxisSystem) -> Result<TriMesh> {
+    let mut mesh = wb_mesh::stl::read_stl(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    stl.import_mesh(&mut mesh, axes);
+    Ok(mesh)
+}
+
+/// Import feature holding the STL's mesh, read with `stl`.
+fn stl_feature(path: &Path, mesh: &TriMesh, stl: &ExchangeOptions) -> MeshFeature {
+    let operation = MeshOperation::import(path, &IndexedMesh::from_trimesh(mesh), *stl, [0.0; 3]);
+    MeshFeature::new(None, operation)
+}
+
+/// Add the STL's mesh to `body` as an import feature.
+fn add_stl_feature(
+    document: &mut Document,
+    body: BodyId,
+    path: &Path,
+    mesh: &TriMesh,
+    stl: &ExchangeOptions,
+) -> Result<()> {
+    add_feature(document, body, stl_feature(path, mesh, stl))
+}
+
+fn add_feature(document: &mut Document, body: BodyId, feature: MeshFeature) -> Result<()> {
+    let name = feature.name.clone();
+    document.add_feature_in_body(feature, name, Some(body))?;
+    Ok(())
+}
+
+/// STL part inserted from the library whose file is still being read.
+pub struct PendingPart {
+    /// Queue of the document the part was inserted into.
+    jobs: JobQueue,
+    job: JobId,
+    body: BodyId,
+    path: PathBuf,
+}
+
+/// Add the meshes of pending STL parts whose file was read.
+pub fn finish_parts(document: &mut Document, pending: &mut Vec<PendingPart>) {
+    pending.retain(|part| {
+        if part.jobs.is_running(part.job) {
+            return true;
+        }
+        let result = part.jobs.take::<Result<MeshFeature>>(part.job);
+        // The body was deleted, or another document opened, while reading.
+        if !document.bodies().iter().any(|body| body.id == part.body) {
+            return false;
+        }
+        let Some(result) = result else {
+            // Cancelled: drop the body that was waiting for the mesh.
+            document.remove_body(part.body);
+            app_log::info(format!("Insertion of {} cancelled", part.path.display()));
+            return false;
+        };
+        let result = result.map_err(anyhow::Error::from).and_then(|read| read);
+        if let Err(err) = result.and_then(|feature| add_feature(document, part.body, feature)) {
+            app_log::error(format!("Failed to insert {}: {err:#}", part.path.display()));
+        }
+        false
+    });
+}
+
+/// Insert a library part into the document as new bodies. STL parts get their
+/// mesh once the file is read; they are added to `pending` until then.
+pub fn insert(
+    document: &mut Document,
+    entry: &LibraryEntry,
+    linked: bool,
+    stl: &ExchangeOptions,
+    axes: AxisSystem,
+    pending: &mut Vec<PendingPart>,
+) -> Result<Vec<BodyId>> {
+    let path = entry.path.as_path();
+    let link = |source_body| {
+        linked.then(|| BodyLink {
+            path: path.display().to_string(),
+            source_body,
+            modified: modified_time(path),
+        })
+    };
+    match entry.format {
+        PartFormat::Stl => {
+            let body = document.create_body(Some(entry.name.clone()));
+            document.set_body_link(body, link(None));
+            let (source, stl) = (path.to_path_buf(), *stl);
+            let jobs = document.jobs().clone();
+            let label = format!("Importing {}", entry.name);
+            let job = jobs.spawn_with_progress(label, move |progress| -> Result<MeshFeature> {
+                let mut mesh = wb_mesh::stl::read_stl_with_progress(&source, |fraction| {
+                    progress.set(fraction * 0.8);
+                })
+                .with_context(|| format!("Failed to read {}", source.display()))?;
+                stl.import_mesh(&mut mesh, axes);
+                Ok(stl_feature(&source, &mesh, &stl))
+            });
+            pending.push(PendingPart {
+                jobs,
+                job,
+                body,
+                path: path.to_path_buf(),
+            });
+            Ok(vec![body])
+        }
+        PartFormat::Document => {
+            let source = Document::load_from_file(path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            if !source.has_bodies() {
+                bail!("{} has no bodies", path.display());
+            }
+            let mut bodies = Vec::new();
+            for source_body in source.bodies() {
+                let name = if source.bodies().len() == 1 {
+                    entry.name.clone()
+                } else {
+                    format!("{} {}", entry.name, source_body.name)
+                };
+                let body = document.create_body(Some(name));
+                document.copy_body_features(&source, source_body.id, body);
+                document.set_body_position(body, source_body.position);
+                document.set_body_link(body, link(Some(source_body.id)));
+                bodies.push(body);
+            }
+            Ok(bodies)
+        }
+        PartFormat::Step => step_bodies(document, path, &entry.name),
+    }
+}
+
+/// One body per part occurrence of a STEP file, named after where it sits in
+/// the assembly ("Gearbox / Shaft:1 / Bearing:2"). The bodies stay empty:
+/// part geometry needs the geometry kernel, which will also place them.
+fn step_bodies(document: &mut Document, path: &Path, name: &str) -> Result<Vec<BodyId>> {
+    let text = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let assembly = kernel_api::step::read_assembly(&String::from_utf8_lossy(&text))
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let parts = assembly.parts();
+    let bodies = parts
+        .iter()
+        .map(|part| {
+            let mut body_name = name.to_string();
+            // The first name is the top-level product, which the file is named after.
+            for occurrence in &part.path[1..] {
+                body_name.push_str(" / ");
+                body_name.push_str(occurrence);
+            }
+            document.create_body(Some(body_name))
+        })
+        .collect();
+    app_log::warn(format!(
+        "{}: read the structure of {} part{}; their geometry will be read once the geometry \
+         kernel is available",
+        path.display(),
+        parts.len(),
+        if parts.len() == 1 { "" } else { "s" }
+    ));
+    Ok(bodies)
+}
+
+/// Update linked bodies whose source file changed since they were copied.
+/// Returns the names of the updated bodies; failures are logged and the body
+/// keeps its previous copy.
+pub fn refresh_links(
+    document: &mut Document,
+    stl: &ExchangeOptions,
+    axes: AxisSystem,
+) -> Vec<String> {
+    let stale: Vec<(BodyId, String, BodyLink)> = document
+        .bodies()
+        .iter()
+        .filter_map(|body| {
+            let link = body.link.clone()?;
+            let modified = modified_time(Path::new(&link.path));
+            (modified > link.modified).then(|| (body.id, body.name.clone(), link))
+        })
+        .collect();
+
+    let mut sources: HashMap<String, Document> = HashMap::new();
+    let mut updated = Vec::new();
+    for (body, name, link) in stale {
+        let path = PathBuf::from(&link.path);
+        let result = match link.source_body {
+            None => read_stl(&path, stl, axes).and_then(|mesh| {
+                document.clear_body_features(body);
+                add_stl_feature(document, body, &path, &mesh, stl)
+            }),
+            Some(source_body) => {
+                if !sources.contains_key(&link.path) {
+                    match Document::load_from_file(&path) {
+                        Ok(source) => {
+                            sources.insert(link.path.clone(), source);
+                        }
+                        Err(err) => {
+                            app_log::warn(format!("Cannot update {name}: {err}"));
+                            continue;
+                        }
+                    }
+                }
+                let source = &sources[&link.path];
+                if source.bodies().iter().any(|b| b.id == source_body) {
+                    document.clear_body_features(body);
+                    document.copy_body_features(source, source_body, body);
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "the body it was linked to no longer exists in {}",
+                        path.display()
+                    ))
+                }
+            }
+        };
+        match result {
+            Ok(()) => {
+                document.set_body_link(
+                    body,
+                    Some(BodyLink {
+                        modified: modified_time(&path),
+                        ..link
+                    }),
+                );
+                updated.push(name);
+            }
+            Err(err) => app_log::warn(format!("Cannot update {name}: {err:#}")),
+        }
+    }
+    updated
+}
+
+/// Thumbnail of a part, or None if it has no mesh to draw.
+fn render_part(entry: &LibraryEntry) -> Option<ColorImage> {
+    match entry.format {
+        PartFormat::Stl => {
+            // Drawn in the file's own convention (Z up for STL).
+            let mesh = wb_mesh::stl::read_stl(&entry.path).ok()?;
+            thumbnail::render(&[mesh], Vec3::Z)
+        }
+        PartFormat::Document => thumbnail::document_preview(&entry.path),
+        PartFormat::Step => None,
+    }
+}
+
+/// A new document holding an STL file as its only body, or a STEP file as a
+/// body per part, for opening the file on its own; None for other files. The STL mesh arrives through
+/// `pending`, as for inserted parts.
+pub fn part_document(
+    path: &Path,
+    stl: &ExchangeOptions,
+    axes: AxisSystem,
+    pending: &mut Vec<PendingPart>,
+) -> Option<Result<Document>> {
+    let format = PartFormat::from_path(path).filter(|format| *format != PartFormat::Document)?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Untitled".to_string());
+    let mut document = Document::new(name.clone());
+    let entry = LibraryEntry {
+        path: path.to_path_buf(),
+        name,
+        format,
+    };
+    Some(insert(&mut document, &entry, false, stl, axes, pending).map(|_| document))
+}
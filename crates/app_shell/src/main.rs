@@ -1,26 +1,50 @@
+mod annotation_overlay;
 mod camera;
+mod gizmo;
+mod instance;
+mod isolation;
+mod library;
 mod log_panel;
+mod merge;
 mod orientation_cube;
+mod origin_overlay;
+mod recent;
+mod recompute;
+mod reference_image_overlay;
+#[cfg(feature = "remote-api")]
+mod remote;
+mod section_view;
+mod thumbnail;
 mod ui;
+mod viewer;
 
 use anyhow::{Context, Result};
 use camera::CameraController;
 use core_document::{
-    BodyId, Document, DocumentService, LogLevel, MouseButton as WbMouseButton, WorkbenchFeature,
-    WorkbenchId, WorkbenchInputEvent, WorkbenchRuntimeContext,
+    BodyId, BodyMesh, Document, DocumentService, DocumentSnapshot, FeatureId, FileOpenRequest,
+    Gizmo, GizmoDrag, GizmoDragPhase, GizmoHandle, JobId, JobQueue, LogLevel,
+    MouseButton as WbMouseButton, Selection, SelectionItem, WorkbenchFeature, WorkbenchId,
+    WorkbenchInputEvent, WorkbenchRuntimeContext,
 };
 use glam::Vec3;
+use kernel_api::ExchangeFormat;
 use log_panel as app_log;
 use orientation_cube::OrientationCubeInput;
 use render_vk::{
     BodySubmission, FrameSubmission, GpuLight, HighlightState, LightingData, RenderBackend,
-    RenderSettings, ViewportRect as RenderViewportRect, VulkanRenderer,
+    RenderSettings, Shading, ViewportRect as RenderViewportRect, VulkanRenderer,
 };
-use settings::{LightingSettings, SettingsStore, UserSettings};
-use std::path::PathBuf;
+use settings::{
+    LightingSettings, ObjectSnapKind, PrinterSettings, SessionState, SettingsStore,
+    SettingsWatcher, SnappingSettings, UserSettings,
+};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use tracing::error;
-use ui::{ActiveTool, ActiveWorkbench, TreeItemId, UiLayer};
+use ui::{
+    ActiveTool, ActiveWorkbench, AxisPromptChoice, LibraryAction, ProfileAction, RecoveryChoice,
+    TreeItemId, UiLayer,
+};
 use uuid::Uuid;
 use winit::{
     application::ApplicationHandler,
@@ -30,6 +54,18 @@ use winit::{
 };
 use workbenches::register_all_workbenches;
 
+/// Viewport color of bodies built from imported meshes, and of solid features.
+const MESH_BODY_COLOR: [f32; 3] = [0.7, 0.72, 0.75];
+/// Longest time and cursor travel (pixels) between two presses of a double click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+const DOUBLE_CLICK_DISTANCE: f32 = 5.0;
+/// Cursor travel (pixels) above which a right button press and release is a
+/// camera drag rather than a click opening the tool menu.
+const TOOL_MENU_CLICK_DISTANCE: f32 = 5.0;
+/// Most rounds of queued workbench commands run at once, so commands that
+/// keep queuing each other can't hang the app.
+const MAX_COMMAND_ROUNDS: usize = 8;
+
 fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -37,6 +73,33 @@ fn main() -> Result<()> {
         )
         .init();
 
+    if merge::requested() {
+        return merge::run();
+    }
+
+    // A document opened from the desktop goes to the running instance, if
+    // any. Viewers open their own window, so a file is never handed to an
+    // editing instance (or the other way round).
+    let files = instance::files_from_args();
+    let viewer = viewer::requested();
+    let incoming_files = if viewer {
+        std::sync::mpsc::channel().1
+    } else {
+        match instance::claim(&files) {
+            instance::Startup::Forwarded => {
+                tracing::info!("Opened in the running printCAD instance");
+                return Ok(());
+            }
+            instance::Startup::Primary(incoming_files) => incoming_files,
+        }
+    };
+    if files.len() > 1 {
+        app_log::warn(format!(
+            "Only one document can be open at a time; opening {}",
+            files[0].display()
+        ));
+    }
+
     let document = Document::new("Untitled");
     let mut registry = DocumentService::default();
     register_all_workbenches(&mut registry)?;
@@ -55,22 +118,40 @@ fn main() -> Result<()> {
     let user_settings = match settings_store.load() {
         Ok(settings) => settings,
         Err(err) => {
-            app_log::warn(format!("Using default settings (failed to load): {err}"));
+            app_log::warn(format!(
+                "Using default settings (failed to load: {err}); previous file kept at {}",
+                settings_store.unreadable_path().display()
+            ));
             UserSettings::default()
         }
     };
 
     let event_loop = EventLoop::new().context("failed to create event loop")?;
-    let mut render_settings = RenderSettings::default();
-    render_settings.preferred_gpu = user_settings.preferred_gpu.clone();
-    render_settings.msaa_samples = user_settings.rendering.msaa_samples;
+    let render_settings = RenderSettings {
+        preferred_gpu: user_settings.preferred_gpu.clone(),
+        msaa_samples: user_settings.rendering.msaa_samples,
+        ..RenderSettings::default()
+    };
     let mut app = PrintCadApp::new(
         render_settings,
         settings_store,
         user_settings,
         document,
         registry,
+        files.into_iter().next(),
+        incoming_files,
+        viewer,
     );
+    #[cfg(feature = "remote-api")]
+    if let Some(port) = remote::requested() {
+        if viewer {
+            app_log::warn("The remote API is not available in the viewer");
+        } else {
+            app.remote = remote::RemoteApi::start(port)
+                .map_err(|err| app_log::error(format!("Remote API not started: {err:#}")))
+                .ok();
+        }
+    }
     event_loop.run_app(&mut app).context("event loop error")?;
     Ok(())
 }
@@ -84,6 +165,10 @@ struct PrintCadApp {
     ui_layer: Option<UiLayer>,
     settings_store: SettingsStore,
     user_settings: UserSettings,
+    // Names of the saved settings profiles (refreshed after profile operations).
+    settings_profiles: Vec<String>,
+    // Watches settings.json for external edits (None if the watcher could not be started).
+    settings_watcher: Option<SettingsWatcher>,
     camera: CameraController,
     active_tool: ActiveTool,
     last_frame_time: Option<Instant>,
@@ -93,13 +178,27 @@ struct PrintCadApp {
     fps_accum_time: f32,
     fps_frame_count: u32,
     // Selected body ID (for highlighting/selection)
-    selected_body: Option<Uuid>,
+    selection: Selection,
+    // Selection as of the tree's last update; a difference means it changed
+    // elsewhere (viewport, workbench) and the tree follows
+    synced_selection: Selection,
     // Hovered body ID (for highlighting)
     hovered_body: Option<Uuid>,
-    // Hovered world position (for status bar display)
-    hovered_world_pos: Option<[f32; 3]>,
+    // Document as of the last rendered frame; picked ids are resolved against it
+    frame_snapshot: DocumentSnapshot,
+    // Keyboard modifiers held (Ctrl/Shift-click extends the selection)
+    modifiers: winit::keyboard::ModifiersState,
+    // Hovered world position (for status bar display), in double precision
+    hovered_world_pos: Option<[f64; 3]>,
+    // Surface normal at the hovered world position
+    hovered_normal: Option<[f32; 3]>,
     // Current cursor position in viewport
     cursor_in_viewport: Option<(f32, f32)>,
+    // Last mouse press in the viewport, to detect double clicks
+    last_press: Option<(WbMouseButton, Instant, (f32, f32))>,
+    // Right button press in the viewport with a tool active, which opens the
+    // tool menu when released without dragging
+    tool_menu_press: Option<(f32, f32)>,
     // Document and workbench registry
     document: Document,
     registry: DocumentService,
@@ -107,18 +206,66 @@ struct PrintCadApp {
     active_workbench: ActiveWorkbench,
     // Active document object (selected feature in tree - separate from editing mode)
     active_document_object: Option<core_document::FeatureId>,
-    active_body_id: Option<BodyId>,
     tree_selection: Option<TreeItemId>,
     // Current file on disk (if any).
     current_file: Option<PathBuf>,
+    // When the document was last saved (manually or by autosave).
+    last_save_time: Instant,
+    // Per-body geometry rebuilt every frame and handed to workbenches.
+    body_meshes: Vec<BodyMesh>,
+    // Annotation text drawn over the viewport this frame
+    screen_labels: Vec<core_document::ScreenSpaceLabel>,
+    reference_images: Vec<reference_image_overlay::ReferenceImageOverlay>,
+    // Regenerated feature and body geometry, kept until a feature is edited.
+    recompute: recompute::Recompute,
+    // Evaluated mesh features, kept until a feature is edited.
+    mesh_cache: wb_mesh::MeshCache,
+    // Bodies cut by the section plane, kept until a body or the plane changes.
+    section_view: section_view::SectionView,
+    // Bodies and features left visible in isolate mode.
+    isolation: Option<isolation::Isolation>,
     // Pending file dialog result from background thread.
     file_dialog_rx: Option<std::sync::mpsc::Receiver<FileDialogResult>>,
+    // Parts found in the configured library folder.
+    part_library: library::PartLibrary,
+    // STL parts inserted from the library whose file is still being read.
+    pending_parts: Vec<library::PendingPart>,
+    // Post-save hooks of the save being handled, as a job.
+    save_hooks_job: Option<(JobQueue, JobId)>,
+    // Saved files whose post-save hooks wait for that job to finish.
+    queued_save_hooks: std::collections::VecDeque<PathBuf>,
+    // Recently opened or saved documents.
+    recent_documents: recent::RecentDocuments,
+    // Document given on the command line, opened once the event loop runs.
+    startup_file: Option<PathBuf>,
+    // Documents opened from the desktop while this instance runs.
+    incoming_files: std::sync::mpsc::Receiver<PathBuf>,
+    // Session recorded at the last exit, while the user is asked whether to restore it.
+    previous_session: Option<SessionState>,
+    // Gizmos the active workbench showed in the last frame.
+    gizmos: Vec<Gizmo>,
+    // Gizmo handle under the cursor (gizmo id, handle).
+    hovered_gizmo: Option<(String, GizmoHandle)>,
+    // Gizmo handle being dragged.
+    gizmo_drag: Option<gizmo::GizmoDragState>,
+    // Commands workbenches queued with `ctx.execute_command`, not yet run.
+    pending_commands: Vec<String>,
+    // Read-only viewer mode: no workbench, no editing or saving.
+    viewer: bool,
+    // Viewer's point-to-point measurement.
+    measurement: viewer::Measurement,
+    // Local HTTP API, when started with `--remote-api`.
+    #[cfg(feature = "remote-api")]
+    remote: Option<remote::RemoteApi>,
 }
 
 enum FileDialogKind {
     Open,
     Save,
     SaveAs,
+    ExportProfile,
+    ImportProfile,
+    Workbench(FileOpenRequest),
 }
 
 struct FileDialogResult {
@@ -127,14 +274,49 @@ struct FileDialogResult {
 }
 
 impl PrintCadApp {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         settings: RenderSettings,
         settings_store: SettingsStore,
         user_settings: UserSettings,
-        document: Document,
+        mut document: Document,
         registry: DocumentService,
+        startup_file: Option<PathBuf>,
+        incoming_files: std::sync::mpsc::Receiver<PathBuf>,
+        viewer: bool,
     ) -> Self {
         let camera = CameraController::new(&user_settings.camera, (1, 1));
+        let settings_profiles = settings_store.list_profiles().unwrap_or_else(|err| {
+            app_log::warn(format!("Failed to list settings profiles: {err}"));
+            Vec::new()
+        });
+        let settings_watcher = match SettingsWatcher::new(&settings_store) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                app_log::warn(format!("Settings hot-reload disabled: {err}"));
+                None
+            }
+        };
+
+        let recent_documents = recent::RecentDocuments::load(&settings_store);
+        // A document given on the command line replaces the previous session.
+        let previous_session = match settings_store.session() {
+            Ok(_) if viewer => None,
+            Ok(session) if startup_file.is_none() => {
+                session.filter(|session| session.document.exists())
+            }
+            Ok(_) => None,
+            Err(err) => {
+                app_log::warn(format!("Failed to read the previous session: {err}"));
+                None
+            }
+        };
+        let active_workbench = if viewer {
+            ActiveWorkbench(WorkbenchId::from(viewer::VIEWER_WORKBENCH_ID))
+        } else {
+            ActiveWorkbench::default()
+        };
+        document.set_active_workbench(Some(active_workbench.0.clone()));
 
         Self {
             settings,
@@ -145,6 +327,8 @@ impl PrintCadApp {
             ui_layer: None,
             settings_store,
             user_settings,
+            settings_profiles,
+            settings_watcher,
             camera,
             active_tool: ActiveTool::default(),
             last_frame_time: None,
@@ -153,19 +337,68 @@ impl PrintCadApp {
             available_gpus: Vec::new(),
             fps_accum_time: 0.0,
             fps_frame_count: 0,
-            selected_body: None,
+            selection: Selection::default(),
+            synced_selection: Selection::default(),
             hovered_body: None,
+            frame_snapshot: DocumentSnapshot::default(),
+            modifiers: winit::keyboard::ModifiersState::empty(),
             hovered_world_pos: None,
+            hovered_normal: None,
             cursor_in_viewport: None,
+            last_press: None,
+            tool_menu_press: None,
             document,
             registry,
-            active_workbench: ActiveWorkbench::default(),
+            active_workbench,
             active_document_object: None,
-            active_body_id: None,
             tree_selection: Some(TreeItemId::DocumentRoot),
             current_file: None,
+            last_save_time: Instant::now(),
+            body_meshes: Vec::new(),
+            screen_labels: Vec::new(),
+            reference_images: Vec::new(),
+            recompute: recompute::Recompute::new(Box::new(kernel_occt::OcctKernel::new())),
+            mesh_cache: wb_mesh::MeshCache::in_background(),
+            section_view: section_view::SectionView::default(),
+            isolation: None,
             file_dialog_rx: None,
+            part_library: library::PartLibrary::default(),
+            pending_parts: Vec::new(),
+            save_hooks_job: None,
+            queued_save_hooks: std::collections::VecDeque::new(),
+            recent_documents,
+            startup_file,
+            incoming_files,
+            previous_session,
+            gizmos: Vec::new(),
+            hovered_gizmo: None,
+            gizmo_drag: None,
+            pending_commands: Vec::new(),
+            viewer,
+            measurement: viewer::Measurement::default(),
+            #[cfg(feature = "remote-api")]
+            remote: None,
+        }
+    }
+
+    /// World position of the body being worked on (the active object's, or
+    /// else the selection's), which the camera and the workbenches use as
+    /// the frame of their single-precision positions. Print layouts place
+    /// bodies on the bed at the world origin, so there the frame stays there.
+    fn active_frame(&self) -> [f64; 3] {
+        if self.active_workbench.0.as_str() == "wb.print" {
+            return [0.0; 3];
         }
+        self.active_document_object
+            .and_then(|id| self.document.get_feature_meta(id)?.body)
+            .or_else(|| self.selection.primary_body(&self.document))
+            .map_or([0.0; 3], |body| self.document.body_position(body))
+    }
+
+    /// The hovered position relative to the camera's frame.
+    fn hovered_in_frame(&self) -> Option<[f32; 3]> {
+        self.hovered_world_pos
+            .map(|world| self.camera.to_frame(world).to_array())
     }
 
     /// Get the workbench ID for the currently active workbench.
@@ -190,10 +423,17 @@ impl PrintCadApp {
         let cam_pos = self.camera.position();
         let cam_target = self.camera.target();
         let vp = self.camera.viewport_info();
-        let hovered_world_pos = self.hovered_world_pos;
-        let hovered_body_id = self.hovered_body;
-        let selected_body_id = self.selected_body;
+        let hovered_world_pos = self.hovered_in_frame();
+        let hovered_normal = self.hovered_normal;
+        let hovered = self.hovered_item();
+        let selection = self.selection.clone();
         let cursor_viewport_pos = self.cursor_in_viewport;
+        let effective_settings = self.effective_settings();
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = self.selected_origin();
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
 
         // Get workbench and call hook
         if let Ok(wb) = self.registry.workbench_mut(wb_id) {
@@ -203,13 +443,23 @@ impl PrintCadApp {
                 cam_target,
                 (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
             );
+            ctx.frame = self.camera.frame();
             ctx.hovered_world_pos = hovered_world_pos;
-            ctx.hovered_body_id = hovered_body_id;
-            ctx.selected_body_id = selected_body_id;
+            ctx.hovered_normal = hovered_normal;
+            ctx.hovered = hovered;
+            ctx.selection = selection;
             ctx.cursor_viewport_pos = cursor_viewport_pos;
+            ctx.snap = snap;
+            ctx.units = units;
+            ctx.selected_reference = selected_reference;
+            ctx.axes = axes;
+            ctx.body_meshes = &self.body_meshes;
+            ctx.printer = printer;
 
             wb.on_deactivate(&mut ctx);
             Self::flush_logs(ctx.drain_logs());
+            self.pending_commands.extend(ctx.drain_commands());
+            self.selection = std::mem::take(&mut ctx.selection);
         }
     }
 
@@ -219,10 +469,17 @@ impl PrintCadApp {
         let cam_pos = self.camera.position();
         let cam_target = self.camera.target();
         let vp = self.camera.viewport_info();
-        let hovered_world_pos = self.hovered_world_pos;
-        let hovered_body_id = self.hovered_body;
-        let selected_body_id = self.selected_body;
+        let hovered_world_pos = self.hovered_in_frame();
+        let hovered_normal = self.hovered_normal;
+        let hovered = self.hovered_item();
+        let selection = self.selection.clone();
         let cursor_viewport_pos = self.cursor_in_viewport;
+        let effective_settings = self.effective_settings();
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = self.selected_origin();
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
 
         // Get workbench and call hook
         if let Ok(wb) = self.registry.workbench_mut(wb_id) {
@@ -232,13 +489,64 @@ impl PrintCadApp {
                 cam_target,
                 (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
             );
+            ctx.frame = self.camera.frame();
             ctx.hovered_world_pos = hovered_world_pos;
-            ctx.hovered_body_id = hovered_body_id;
-            ctx.selected_body_id = selected_body_id;
+            ctx.hovered_normal = hovered_normal;
+            ctx.hovered = hovered;
+            ctx.selection = selection;
             ctx.cursor_viewport_pos = cursor_viewport_pos;
+            ctx.snap = snap;
+            ctx.units = units;
+            ctx.selected_reference = selected_reference;
+            ctx.axes = axes;
+            ctx.body_meshes = &self.body_meshes;
+            ctx.printer = printer;
 
             wb.on_activate(&mut ctx);
             Self::flush_logs(ctx.drain_logs());
+            self.pending_commands.extend(ctx.drain_commands());
+            self.selection = std::mem::take(&mut ctx.selection);
+        }
+    }
+
+    /// Deliver a file picked for a workbench's `file_open_request` to the active workbench.
+    fn call_workbench_file_opened(&mut self, request_id: &str, path: &std::path::Path) {
+        let wb_id = self.active_workbench_id();
+        let cam_pos = self.camera.position();
+        let cam_target = self.camera.target();
+        let vp = self.camera.viewport_info();
+        let selection = self.selection.clone();
+        let effective_settings = self.effective_settings();
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = self.selected_origin();
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
+
+        if let Ok(wb) = self.registry.workbench_mut(&wb_id) {
+            let mut ctx = WorkbenchRuntimeContext::new(
+                &mut self.document,
+                cam_pos,
+                cam_target,
+                (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
+            );
+            ctx.frame = self.camera.frame();
+            ctx.selection = selection;
+            ctx.snap = snap;
+            ctx.units = units;
+            ctx.selected_reference = selected_reference;
+            ctx.axes = axes;
+            ctx.body_meshes = &self.body_meshes;
+            ctx.printer = printer;
+            ctx.exchange = ExchangeFormat::ALL
+                .into_iter()
+                .map(|format| (format, effective_settings.exchange.options(format)))
+                .collect();
+
+            wb.on_file_opened(request_id, path, &mut ctx);
+            Self::flush_logs(ctx.drain_logs());
+            self.pending_commands.extend(ctx.drain_commands());
+            self.selection = std::mem::take(&mut ctx.selection);
         }
     }
 }
@@ -249,16 +557,19 @@ impl ApplicationHandler for PrintCadApp {
             return;
         }
 
-        let window = match event_loop.create_window(
-            WindowAttributes::default().with_title("printCAD (prototype)".to_string()),
-        ) {
-            Ok(window) => window,
-            Err(err) => {
-                error!("failed to create window: {err}");
-                event_loop.exit();
-                return;
-            }
-        };
+        let window =
+            match event_loop.create_window(WindowAttributes::default().with_title(if self.viewer {
+                "printCAD Viewer"
+            } else {
+                "printCAD (prototype)"
+            })) {
+                Ok(window) => window,
+                Err(err) => {
+                    error!("failed to create window: {err}");
+                    event_loop.exit();
+                    return;
+                }
+            };
 
         let mut renderer = VulkanRenderer::new(self.settings.clone());
         if let Err(err) = renderer.initialize(&window) {
@@ -268,7 +579,12 @@ impl ApplicationHandler for PrintCadApp {
         }
 
         let window_id = window.id();
-        self.ui_layer = Some(UiLayer::new(&window));
+        let mut ui_layer = UiLayer::new(&window, self.viewer);
+        ui_layer.set_active_workbench(self.active_workbench.clone());
+        if let Some(session) = &self.previous_session {
+            ui_layer.show_session_prompt(session.document.clone());
+        }
+        self.ui_layer = Some(ui_layer);
         self.gpu_name = renderer.gpu_name().map(|s| s.to_string());
         if let Some(list) = renderer.available_gpus() {
             self.available_gpus = list.to_vec();
@@ -291,6 +607,10 @@ impl ApplicationHandler for PrintCadApp {
             return;
         }
 
+        if let WindowEvent::ModifiersChanged(modifiers) = &event {
+            self.modifiers = modifiers.state();
+        }
+
         if let (Some(ui_layer), Some(window)) = (self.ui_layer.as_mut(), self.window.as_ref()) {
             let response = ui_layer.on_window_event(window, &event);
             if response.repaint {
@@ -334,6 +654,13 @@ impl ApplicationHandler for PrintCadApp {
             }
         }
 
+        if self.handle_gizmo_input(&event) {
+            if let Some(window) = self.window.as_ref() {
+                window.request_redraw();
+            }
+            return;
+        }
+
         if self.handle_tool_input(&event) {
             if let Some(window) = self.window.as_ref() {
                 window.request_redraw();
@@ -348,7 +675,10 @@ impl ApplicationHandler for PrintCadApp {
         }
 
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CloseRequested => {
+                self.record_session();
+                event_loop.exit();
+            }
             WindowEvent::Resized(size) => {
                 if let Some(renderer) = self.renderer.as_mut() {
                     renderer.resize(size);
@@ -375,6 +705,18 @@ impl ApplicationHandler for PrintCadApp {
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.reload_settings_if_changed();
+        self.autosave_if_due();
+        self.run_queued_save_hooks();
+        self.open_requested_files();
+        #[cfg(feature = "remote-api")]
+        self.serve_remote_requests();
+        self.part_library
+            .set_folder(self.user_settings.library.folder.as_deref());
+        self.part_library.poll();
+        library::finish_parts(&mut self.document, &mut self.pending_parts);
+        self.recent_documents.poll();
+
         let now = Instant::now();
         // Optional FPS cap from settings (0 = uncapped).
         // We only advance timing/FPS when we actually render a frame.
@@ -419,6 +761,9 @@ impl ApplicationHandler for PrintCadApp {
 
         let mut new_body_requested_flag = false;
         let mut workbench_change: Option<(ActiveWorkbench, ActiveWorkbench)> = None;
+        let mut tree_edit = None;
+        let mut tool_menu_action = None;
+        let frame = self.active_frame();
 
         let (window, renderer) = match (self.window.as_ref(), self.renderer.as_mut()) {
             (Some(window), Some(renderer)) => (window, renderer),
@@ -427,37 +772,191 @@ impl ApplicationHandler for PrintCadApp {
 
         // Update camera animation
         self.camera.update(dt_secs);
-
-        // Collect sketch features from document and convert to meshes
-        let sketch_meshes: Vec<BodySubmission> = self
-            .document
-            .feature_tree()
-            .all_nodes()
-            .filter_map(|(feature_id, node)| {
-                // Only process sketch features
-                if node.workbench_id.as_str() != "wb.sketch" {
-                    return None;
-                }
-
-                // Deserialize sketch feature
-                let sketch_feature = wb_sketch::SketchFeature::from_json(&node.data).ok()?;
-
-                // Convert to mesh
-                let mesh = wb_sketch::render::sketch_to_mesh(
-                    &sketch_feature.sketch,
-                    &sketch_feature.plane,
-                );
-
-                // Create body submission for sketch (use feature ID UUID as body ID)
-                Some(BodySubmission {
+        self.camera.set_frame(frame);
+
+        // The frame is assembled from a snapshot, so edits made by the UI
+        // below only show up (and are picked) from the next frame on.
+        let snapshot = self.document.snapshot();
+
+        // Bodies hidden, see-through, or recolored by the active display state.
+        let display_states = self.document.display_states().clone();
+
+        // Regenerate the features that changed; each is drawn on its own
+        // (sketches in green, solids like bodies, using the feature ID as body
+        // ID), and a body's geometry is the union of its features.
+        self.recompute.run(&mut self.document, &self.registry);
+        let mut body_meshes: Vec<BodyMesh> = self
+            .recompute
+            .body_meshes()
+            .map(|(body, mesh)| BodyMesh {
+                body,
+                mesh: mesh.clone(),
+                position: self.document.body_position(body),
+            })
+            .collect();
+        let mut sketch_meshes: Vec<BodySubmission> = self
+            .recompute
+            .feature_meshes()
+            .filter_map(|(feature_id, body, mesh)| {
+                let display = body
+                    .map(|body| display_states.body(body))
+                    .unwrap_or_default();
+                let is_sketch = self
+                    .document
+                    .get_feature_meta(feature_id)
+                    .is_some_and(|node| node.workbench_id.as_str() == "wb.sketch");
+                let default_color = if is_sketch {
+                    [0.2, 0.8, 0.2]
+                } else {
+                    MESH_BODY_COLOR
+                };
+                let offset = body.map_or([0.0; 3], |body| self.document.body_position(body));
+                display.visible.then(|| BodySubmission {
                     id: feature_id.0,
-                    mesh,
-                    color: [0.2, 0.8, 0.2], // Green color for sketches
+                    mesh: mesh.clone(),
+                    offset,
+                    color: display.color.unwrap_or(default_color),
                     highlight: HighlightState::None,
+                    shading: Shading::Lit,
+                    opacity: display.opacity,
+                    clipped: true,
                 })
             })
             .collect();
 
+        // Imported mesh bodies are rebuilt from their mesh feature history.
+        // Above the memory budget, bodies out of view are dropped until they
+        // come back; print layouts and drawings use every body, so there all
+        // of them count as in view.
+        self.mesh_cache
+            .set_budget(self.user_settings.rendering.mesh_memory_budget_mb as usize * 1024 * 1024);
+        let uses_all_bodies = matches!(self.active_workbench.0.as_str(), "wb.print" | "wb.drawing");
+        self.mesh_cache.set_view((!uses_all_bodies).then(|| {
+            (
+                self.camera.render_origin(),
+                self.camera.relative_view_projection(),
+            )
+        }));
+        for body_mesh in self.mesh_cache.body_meshes(&mut self.document) {
+            let display = display_states.body(body_mesh.body);
+            if display.visible {
+                sketch_meshes.push(BodySubmission {
+                    id: body_mesh.body.0,
+                    mesh: body_mesh.mesh.clone(),
+                    offset: body_mesh.position,
+                    color: display.color.unwrap_or(MESH_BODY_COLOR),
+                    highlight: HighlightState::None,
+                    shading: Shading::Lit,
+                    opacity: display.opacity,
+                    clipped: true,
+                });
+            }
+            match body_meshes
+                .iter_mut()
+                .find(|entry| entry.body == body_mesh.body)
+            {
+                Some(entry) => entry.mesh.append(&body_mesh.mesh),
+                None => body_meshes.push(body_mesh),
+            }
+        }
+        self.body_meshes = body_meshes;
+        #[cfg(feature = "remote-api")]
+        if let Some(remote) = &mut self.remote {
+            remote.frame_built(
+                self.document.dirty_features().is_empty()
+                    && self.document.jobs().running().is_empty()
+                    && self.pending_parts.is_empty(),
+            );
+        }
+
+        // Datums are placed again from the geometry they are defined from,
+        // and sketches on datum planes follow them.
+        wb_part::update_datums(&mut self.document);
+        wb_sketch::follow_datum_planes(&mut self.document);
+
+        // Sketches on a body face move with it; the face is looked up in the
+        // mesh of the feature that made it, so the sketch's own lines (part
+        // of the body's mesh) are not mistaken for it.
+        wb_sketch::follow_body_faces(
+            &mut self.document,
+            |id| self.recompute.feature_mesh(id),
+            &self.body_meshes,
+        );
+
+        // Part Design features sweep their sketch's profile where it is now,
+        // and pockets and fillets change their base's solid as it is now.
+        wb_part::update_features(&mut self.document);
+
+        // Isolate mode hides everything else, which also keeps it from being
+        // picked; it ends once nothing isolated is left.
+        if self
+            .isolation
+            .as_mut()
+            .is_some_and(|isolation| !isolation.prune(&self.document))
+        {
+            self.isolation = None;
+        }
+        if let Some(isolation) = &self.isolation {
+            sketch_meshes.retain(|submission| isolation.shows(&self.document, submission.id));
+        }
+
+        // Highlight selected and hovered geometry.
+        self.selection.prune(&self.document);
+        for submission in &mut sketch_meshes {
+            submission.highlight =
+                highlight_state(&snapshot, &self.selection, self.hovered_body, submission.id);
+        }
+
+        let effective_settings =
+            apply_document_overrides(&self.user_settings, self.document.overrides());
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = match self.tree_selection {
+            Some(TreeItemId::OriginElement(origin)) => Some(origin),
+            _ => None,
+        };
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
+
+        // Per-frame update of the active workbench (collects finished background jobs)
+        let hovered_world_pos = self
+            .hovered_world_pos
+            .map(|world| self.camera.to_frame(world).to_array());
+        if let Ok(wb) = self.registry.workbench_mut(&self.active_workbench.0) {
+            let cam_pos = self.camera.position();
+            let cam_target = self.camera.target();
+            let vp = self.camera.viewport_info();
+            let hovered = self
+                .hovered_body
+                .and_then(|id| pick_item(&self.frame_snapshot, id));
+            let mut wb_ctx = WorkbenchRuntimeContext::new(
+                &mut self.document,
+                cam_pos,
+                cam_target,
+                (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
+            );
+            wb_ctx.frame = self.camera.frame();
+            wb_ctx.active_document_object = self.active_document_object;
+            wb_ctx.hovered_world_pos = hovered_world_pos;
+            wb_ctx.hovered_normal = self.hovered_normal;
+            wb_ctx.hovered = hovered;
+            wb_ctx.selection = self.selection.clone();
+            wb_ctx.cursor_viewport_pos = self.cursor_in_viewport;
+            wb_ctx.snap = snap.clone();
+            wb_ctx.units = units;
+            wb_ctx.selected_reference = selected_reference;
+            wb_ctx.axes = axes;
+            wb_ctx.body_meshes = &self.body_meshes;
+            wb_ctx.printer = printer;
+            wb_ctx.view_proj = Some(self.camera.view_projection());
+
+            wb.on_frame(dt_secs, &mut wb_ctx);
+            Self::flush_logs(wb_ctx.drain_logs());
+            self.pending_commands.extend(wb_ctx.drain_commands());
+            self.selection = std::mem::take(&mut wb_ctx.selection);
+            self.active_document_object = wb_ctx.active_document_object;
+        }
+
         // Get overlay meshes from the active workbench (grid lines, guides, etc.)
         let mut overlay_meshes: Vec<BodySubmission> =
             if let Ok(wb) = self.registry.workbench_mut(&self.active_workbench.0) {
@@ -471,43 +970,144 @@ impl ApplicationHandler for PrintCadApp {
                 };
                 let mut wb_ctx =
                     WorkbenchRuntimeContext::new(&mut self.document, cam_pos, cam_target, viewport);
+                wb_ctx.frame = self.camera.frame();
                 wb_ctx.active_document_object = self.active_document_object;
-                wb_ctx.selected_body_id = self.active_body_id.map(|id| id.0);
-
-                wb.get_overlay_meshes(&wb_ctx, self.active_document_object)
+                wb_ctx.selection = self.selection.clone();
+                wb_ctx.snap = snap.clone();
+                wb_ctx.units = units;
+                wb_ctx.selected_reference = selected_reference;
+                wb_ctx.axes = axes;
+                wb_ctx.body_meshes = &self.body_meshes;
+                wb_ctx.printer = printer;
+
+                // Workbenches draw in the camera's frame.
+                let frame = self.camera.frame();
+                let mut overlays: Vec<BodySubmission> = wb
+                    .get_overlay_meshes(&wb_ctx, self.active_document_object)
                     .into_iter()
                     .map(|(mesh, color)| BodySubmission {
                         id: Uuid::new_v4(), // Unique ID for overlay meshes
                         mesh,
+                        offset: frame,
                         color,
                         highlight: HighlightState::None,
+                        shading: Shading::Lit,
+                        opacity: 1.0,
+                        clipped: false,
                     })
-                    .collect()
+                    .collect();
+                overlays.extend(
+                    wb.get_zebra_meshes(&wb_ctx, self.active_document_object)
+                        .into_iter()
+                        .map(|zebra| BodySubmission {
+                            id: Uuid::new_v4(),
+                            mesh: zebra.mesh,
+                            offset: frame,
+                            color: [1.0, 1.0, 1.0],
+                            highlight: HighlightState::None,
+                            shading: Shading::Zebra {
+                                axis: zebra.axis,
+                                stripes: zebra.stripes,
+                            },
+                            opacity: 1.0,
+                            clipped: true,
+                        }),
+                );
+                overlays
             } else {
                 Vec::new()
             };
 
-        // Get screen-space overlays from the active workbench (constant-thickness lines)
-        let screen_space_overlays: Vec<core_document::ScreenSpaceOverlay> =
-            if let Ok(wb) = self.registry.workbench_mut(&self.active_workbench.0) {
-                // Build runtime context for overlay generation
-                let cam_pos = self.camera.position();
-                let cam_target = self.camera.target();
-                let viewport = if let Some(rect) = self.frame_submission.viewport_rect {
-                    (rect.x, rect.y, rect.width, rect.height)
-                } else {
-                    (0, 0, 1920, 1080) // Fallback
-                };
-                let mut wb_ctx =
-                    WorkbenchRuntimeContext::new(&mut self.document, cam_pos, cam_target, viewport);
-                wb_ctx.active_document_object = self.active_document_object;
-                wb_ctx.selected_body_id = self.active_body_id.map(|id| id.0);
-                wb_ctx.view_proj = Some(self.camera.view_projection());
-
-                wb.get_screen_space_overlays(&wb_ctx, self.active_document_object)
+        // Get screen-space overlays and gizmos from the active workbench (constant-thickness lines)
+        let (mut screen_space_overlays, gizmos, workbench_labels): (
+            Vec<core_document::ScreenSpaceOverlay>,
+            _,
+            _,
+        ) = if let Ok(wb) = self.registry.workbench_mut(&self.active_workbench.0) {
+            // Build runtime context for overlay generation
+            let cam_pos = self.camera.position();
+            let cam_target = self.camera.target();
+            let viewport = if let Some(rect) = self.frame_submission.viewport_rect {
+                (rect.x, rect.y, rect.width, rect.height)
             } else {
-                Vec::new()
+                (0, 0, 1920, 1080) // Fallback
             };
+            let mut wb_ctx =
+                WorkbenchRuntimeContext::new(&mut self.document, cam_pos, cam_target, viewport);
+            wb_ctx.frame = self.camera.frame();
+            wb_ctx.active_document_object = self.active_document_object;
+            wb_ctx.selection = self.selection.clone();
+            wb_ctx.snap = snap.clone();
+            wb_ctx.units = units;
+            wb_ctx.selected_reference = selected_reference;
+            wb_ctx.axes = axes;
+            wb_ctx.body_meshes = &self.body_meshes;
+            wb_ctx.printer = printer;
+            wb_ctx.view_proj = Some(self.camera.view_projection());
+
+            let mut overlays = wb.get_screen_space_overlays(&wb_ctx, self.active_document_object);
+            let mut labels = wb.get_screen_space_labels(&wb_ctx, self.active_document_object);
+            // Sketch dimensions stay visible in other workbenches; the sketch
+            // workbench draws them itself, around the sketch being edited.
+            if self.active_workbench.0.as_str() != "wb.sketch" {
+                let isolation = self.isolation.as_ref();
+                let (lines, dimensions) = wb_sketch::sketch_dimensions(&wb_ctx, |id| {
+                    isolation.map_or(true, |isolation| isolation.shows(wb_ctx.document, id.0))
+                });
+                overlays.extend(lines);
+                labels.extend(dimensions);
+            }
+            (overlays, wb.gizmos(&wb_ctx), labels)
+        } else {
+            (Vec::new(), Vec::new(), Vec::new())
+        };
+        screen_space_overlays.extend(origin_overlay::origin_overlays(
+            &self.document,
+            &self.camera,
+            selected_reference,
+        ));
+        screen_space_overlays.extend(origin_overlay::datum_overlays(
+            &self.document,
+            &self.camera,
+            &self.selection,
+        ));
+        let (leaders, mut screen_labels) = annotation_overlay::annotation_overlays(
+            &self.document,
+            &self.camera,
+            self.active_document_object,
+        );
+        screen_space_overlays.extend(leaders);
+        let (world_axes, axis_labels) = origin_overlay::world_axes_overlays(
+            &self.camera,
+            axes,
+            self.user_settings.rendering.show_origin_triad,
+            self.user_settings.rendering.show_axis_lines,
+        );
+        screen_space_overlays.extend(world_axes);
+        screen_labels.extend(axis_labels);
+        screen_labels.extend(workbench_labels);
+        if self.viewer {
+            let (lines, distance) = self.measurement.overlays(&self.camera, &units);
+            screen_space_overlays.extend(lines);
+            screen_labels.extend(distance);
+        }
+        self.screen_labels = screen_labels;
+        self.reference_images =
+            reference_image_overlay::reference_image_overlays(&self.document, &self.camera);
+        self.gizmos = gizmos;
+        let active_gizmo = match &self.gizmo_drag {
+            Some(drag) => Some(drag.active()),
+            None => self
+                .hovered_gizmo
+                .as_ref()
+                .map(|(id, handle)| (id.as_str(), *handle)),
+        };
+        screen_space_overlays.extend(gizmo::overlays(&self.gizmos, &self.camera, active_gizmo));
+
+        // Cut the bodies with the section plane, filling the cut faces.
+        self.frame_submission.clip_plane = self
+            .section_view
+            .apply(&self.user_settings.rendering.section, &mut sketch_meshes);
 
         // Combine sketch meshes and overlay meshes
         let mut all_meshes = sketch_meshes;
@@ -515,16 +1115,42 @@ impl ApplicationHandler for PrintCadApp {
 
         // For now, only render sketch meshes (no demo bodies).
         self.frame_submission.bodies = all_meshes;
-        self.frame_submission.view_proj = self.camera.view_projection();
-        self.frame_submission.camera_pos = self.camera.position();
+        self.frame_submission.render_origin = self.camera.render_origin();
+        self.frame_submission.view_proj = self.camera.relative_view_projection();
+        self.frame_submission.camera_pos = self.camera.render_origin();
         self.frame_submission.lighting = lighting_data_from_settings(&self.user_settings.lighting);
         self.frame_submission.screen_space_overlays = screen_space_overlays;
 
         let mut ui_result_open = false;
         let mut ui_result_save = false;
         let mut ui_result_save_as = false;
+        let mut profile_action = None;
+        let mut library_action = None;
+        let mut open_recent = None;
+        let mut restore_session = None;
+        let mut recovery_choice = None;
+        let mut workbench_file_request = None;
 
         if let Some(ui_layer) = self.ui_layer.as_mut() {
+            // Follow selection changes made outside the tree: the newest item
+            // becomes the tree's current row and is scrolled into view.
+            if self.selection != self.synced_selection {
+                self.synced_selection = self.selection.clone();
+                match self.selection.last().map(TreeItemId::for_selection) {
+                    Some(item) => {
+                        self.tree_selection = Some(item);
+                        ui_layer.reveal_in_tree(item);
+                    }
+                    None => self.tree_selection = Some(TreeItemId::DocumentRoot),
+                }
+            }
+            if ui_layer.settings_open() {
+                ui_layer.set_mesh_memory(mesh_memory_report(
+                    &self.document,
+                    &self.mesh_cache,
+                    renderer.mesh_memory(),
+                ));
+            }
             let orientation_input = OrientationCubeInput {
                 camera_orientation: self.camera.orientation(),
                 axis_system: self.camera.axis_system(),
@@ -534,7 +1160,8 @@ impl ApplicationHandler for PrintCadApp {
             let pivot_screen_pos = self
                 .camera
                 .active_pivot()
-                .and_then(|pivot| self.camera.world_to_screen(pivot));
+                .and_then(|pivot| self.camera.world_to_screen(self.camera.to_frame(pivot)));
+            let isolation = self.isolation.as_ref().map(isolation::Isolation::summary);
 
             let ui_result = ui_layer.run(
                 window,
@@ -543,18 +1170,30 @@ impl ApplicationHandler for PrintCadApp {
                 self.current_fps,
                 self.gpu_name.as_deref(),
                 &self.available_gpus,
-                self.hovered_world_pos,
+                &self.settings_profiles,
+                self.hovered_world_pos.map(|pos| pos.map(|v| v as f32)),
                 pivot_screen_pos,
                 self.camera.axis_system(),
                 &mut self.document,
                 &mut self.registry,
                 self.tree_selection,
                 self.active_document_object,
-                self.active_body_id,
+                &mut self.selection,
                 &self.frame_submission.screen_space_overlays,
+                &self.screen_labels,
+                &self.reference_images,
+                &self.part_library,
+                &self.recent_documents,
+                &self.body_meshes,
+                self.camera.frame(),
+                printer,
+                isolation.as_deref(),
+                self.camera.mm_per_pixel(),
+                self.measurement.active,
             );
             self.frame_submission.egui = Some(ui_result.submission);
             self.active_tool = ui_result.active_tool;
+            tool_menu_action = ui_result.tool_menu_action;
 
             // Track workbench change
             if ui_result.workbench_changed {
@@ -587,13 +1226,59 @@ impl ApplicationHandler for PrintCadApp {
                 self.camera
                     .apply_rotate_delta(rotate_delta, &self.user_settings.camera);
             }
+            if let Some(delta) = ui_result.orbit_delta {
+                self.camera.orbit_by(delta, &self.user_settings.camera);
+            }
+
+            let mut document_overrides_changed = ui_result.document_overrides_changed;
+            if let Some(AxisPromptChoice::UseDocument(axes)) = ui_result.axis_prompt_choice {
+                let mut overrides = self.document.overrides().clone();
+                overrides.set_axes(axes);
+                self.document.set_overrides(overrides);
+                document_overrides_changed = true;
+                app_log::info(format!(
+                    "Using the document's axis convention ({})",
+                    axes.label()
+                ));
+            }
 
+            if ui_result.settings_changed || document_overrides_changed {
+                self.camera.sync_with_settings(
+                    &apply_document_overrides(&self.user_settings, self.document.overrides())
+                        .camera,
+                );
+            }
             if ui_result.settings_changed {
-                self.camera.sync_with_settings(&self.user_settings.camera);
                 if let Err(err) = self.settings_store.save(&self.user_settings) {
                     app_log::warn(format!("Failed to save settings: {err}"));
                 }
             }
+            if let Some(align) = ui_result.section_align {
+                let reference = match self.tree_selection {
+                    Some(TreeItemId::OriginElement(origin)) => Some(origin),
+                    _ => None,
+                };
+                match section_view::aligned_plane(
+                    align,
+                    &self.camera,
+                    &self.document,
+                    &self.selection,
+                    reference,
+                ) {
+                    Some(plane) => {
+                        let section = &mut self.user_settings.rendering.section;
+                        section.custom_plane = Some(plane);
+                        section.offset = 0.0;
+                        section.enabled = true;
+                        if let Err(err) = self.settings_store.save(&self.user_settings) {
+                            app_log::warn(format!("Failed to save settings: {err}"));
+                        }
+                    }
+                    None => app_log::warn(
+                        "Select an origin plane or a datum plane to put the section on",
+                    ),
+                }
+            }
 
             if ui_result.new_body_requested {
                 new_body_requested_flag = true;
@@ -601,6 +1286,13 @@ impl ApplicationHandler for PrintCadApp {
             ui_result_open = ui_result.open_requested;
             ui_result_save = ui_result.save_requested;
             ui_result_save_as = ui_result.save_as_requested;
+            profile_action = ui_result.profile_action;
+            library_action = ui_result.library_action;
+            open_recent = ui_result.open_recent;
+            restore_session = ui_result.restore_session;
+            recovery_choice = ui_result.recovery_choice;
+            workbench_file_request = ui_result.file_open_request;
+            self.pending_commands.extend(ui_result.commands);
 
             if ui_result.reset_view_requested {
                 app_log::info("Fit View requested");
@@ -610,6 +1302,41 @@ impl ApplicationHandler for PrintCadApp {
                 self.camera.reset_to_fit(Vec3::ZERO, 1.0);
             }
 
+            if ui_result.measure_toggled {
+                self.measurement.toggle();
+            }
+
+            if ui_result.exit_isolation_requested {
+                self.isolation = None;
+            }
+            if ui_result.isolate_requested {
+                self.isolation = isolation::Isolation::new(self.selection.items());
+            }
+
+            if let Some(focus) = ui_result.camera_focus_request {
+                self.camera
+                    .focus_on(Vec3::from_array(focus.center), focus.radius);
+            }
+
+            let zoom_items = match ui_result.tree_zoom_to {
+                Some(TreeItemId::Body(id)) => vec![SelectionItem::Body(id)],
+                Some(TreeItemId::Feature(id)) => vec![SelectionItem::Feature(id)],
+                _ if ui_result.zoom_to_selection_requested => self.selection.items().to_vec(),
+                _ => Vec::new(),
+            };
+            if !zoom_items.is_empty() {
+                match zoom_bounds(
+                    &self.document,
+                    &self.body_meshes,
+                    &self.recompute,
+                    &self.mesh_cache,
+                    &zoom_items,
+                ) {
+                    Some((min, max)) => self.camera.zoom_to(min, max),
+                    None => app_log::warn("Nothing to zoom to: the selection has no geometry yet"),
+                }
+            }
+
             if ui_result.finish_sketch_requested {
                 // Defer handling until after rendering to avoid borrow conflicts.
                 // We'll process this flag once we exit the UI closure.
@@ -617,35 +1344,53 @@ impl ApplicationHandler for PrintCadApp {
 
             if let Some(selection) = ui_result.tree_selection {
                 self.tree_selection = Some(selection);
-                match selection {
-                    TreeItemId::DocumentRoot => {
-                        self.active_document_object = None;
-                        self.active_body_id = None;
-                        self.selected_body = None;
+                // Ctrl/Shift-click toggles bodies and features in the selection.
+                let toggled = match selection {
+                    TreeItemId::Body(id) if ui_result.tree_extend => Some(SelectionItem::Body(id)),
+                    TreeItemId::Feature(id) if ui_result.tree_extend => {
+                        Some(SelectionItem::Feature(id))
                     }
-                    TreeItemId::Body(id) => {
-                        self.active_body_id = Some(id);
-                        self.active_document_object = None;
-                        self.selected_body = Some(id.0);
-                    }
-                    TreeItemId::Feature(id) => {
-                        if self.active_document_object != Some(id) {
-                            app_log::info(format!("Selected feature {:?}", id));
+                    _ => None,
+                };
+                if let Some(item) = toggled {
+                    self.selection.toggle(item);
+                } else {
+                    match selection {
+                        TreeItemId::DocumentRoot => {
+                            self.active_document_object = None;
+                            self.selection.clear();
+                        }
+                        TreeItemId::Body(id) | TreeItemId::Origin(id) => {
+                            self.active_document_object = None;
+                            self.selection.set(SelectionItem::Body(id));
+                        }
+                        TreeItemId::OriginElement(origin) => {
+                            self.active_document_object = None;
+                            self.selection.set(SelectionItem::Body(origin.body));
+                        }
+                        TreeItemId::Feature(id) => {
+                            if self.active_document_object != Some(id) {
+                                app_log::info(format!("Selected feature {:?}", id));
+                            }
+                            self.active_document_object = Some(id);
+                            self.selection.set(SelectionItem::Feature(id));
                         }
-                        self.active_document_object = Some(id);
                     }
                 }
+                self.synced_selection = self.selection.clone();
             }
 
             if let Some(item) = ui_result.tree_activation {
                 match item {
                     TreeItemId::Feature(id) => {
-                        app_log::info(format!("Activated feature {:?} (double-click in tree)", id));
+                        tree_edit = Some(id);
                     }
                     TreeItemId::Body(id) => {
                         app_log::info(format!("Activated body {:?} (double-click in tree)", id));
                     }
-                    TreeItemId::DocumentRoot => {}
+                    TreeItemId::DocumentRoot
+                    | TreeItemId::Origin(_)
+                    | TreeItemId::OriginElement(_) => {}
                 }
             }
         } else {
@@ -661,16 +1406,18 @@ impl ApplicationHandler for PrintCadApp {
             return;
         }
 
+        self.frame_snapshot = snapshot;
+
         // Retrieve pick result from GPU picking (processed during render)
         let pick_result = renderer.pick_at(0, 0); // Coordinates don't matter, we use cached result
         self.hovered_body = pick_result.body_id;
         self.hovered_world_pos = pick_result.world_position;
+        self.hovered_normal = pick_result.normal;
 
         // Set orbit pivot based on what's under the cursor
         // If hovering over geometry, orbit around that point; otherwise use default target
         if let Some(world_pos) = pick_result.world_position {
-            self.camera
-                .set_orbit_pivot(Some(Vec3::from_array(world_pos)));
+            self.camera.set_orbit_pivot(Some(world_pos));
         } else {
             self.camera.set_orbit_pivot(None);
         }
@@ -679,6 +1426,34 @@ impl ApplicationHandler for PrintCadApp {
             self.start_file_dialog(ui_result_open, ui_result_save, ui_result_save_as);
         }
 
+        if let Some(action) = profile_action {
+            self.handle_profile_action(action);
+        }
+
+        if let Some(action) = library_action {
+            self.handle_library_action(action);
+        }
+
+        match restore_session {
+            Some(true) => self.restore_session(),
+            Some(false) => self.previous_session = None,
+            None => {}
+        }
+
+        if let Some(choice) = recovery_choice {
+            self.answer_recovery_prompt(choice);
+        }
+
+        if let Some(path) = open_recent {
+            if let Err(err) = self.open_document_at(&path) {
+                app_log::error(format!("Failed to open document: {err}"));
+            }
+        }
+
+        if let Some(request) = workbench_file_request {
+            self.start_workbench_file_dialog(request);
+        }
+
         if let Some(rx) = &self.file_dialog_rx {
             if let Ok(result) = rx.try_recv() {
                 match result.kind {
@@ -703,6 +1478,21 @@ impl ApplicationHandler for PrintCadApp {
                             }
                         }
                     }
+                    FileDialogKind::ExportProfile => {
+                        if let Some(path) = result.path {
+                            self.export_settings_profile(&path);
+                        }
+                    }
+                    FileDialogKind::ImportProfile => {
+                        if let Some(path) = result.path {
+                            self.import_settings_profile(&path);
+                        }
+                    }
+                    FileDialogKind::Workbench(request) => {
+                        if let Some(path) = result.path {
+                            self.call_workbench_file_opened(&request.id, &path);
+                        }
+                    }
                 }
                 self.file_dialog_rx = None;
             }
@@ -712,11 +1502,19 @@ impl ApplicationHandler for PrintCadApp {
             self.create_new_body();
         }
 
+        if let Some(action) = tool_menu_action {
+            self.apply_tool_menu_action(action);
+        }
+
+        self.run_pending_commands();
+
         // Now handle workbench change (after renderer borrow ends)
         if let Some((old_wb, new_wb)) = workbench_change {
-            self.call_workbench_deactivate(&old_wb.0);
+            self.switch_workbench(&old_wb.0, &new_wb.0);
+        }
 
-            self.call_workbench_activate(&new_wb.0);
+        if let Some(id) = tree_edit {
+            self.edit_feature(id);
         }
     }
 }
@@ -729,30 +1527,47 @@ impl PrintCadApp {
         } else {
             app_log::info(format!("Created body {:?}", body_id));
         }
-        self.active_body_id = Some(body_id);
         self.active_document_object = None;
         self.tree_selection = Some(TreeItemId::Body(body_id));
-        self.selected_body = Some(body_id.0);
+        self.selection.set(SelectionItem::Body(body_id));
     }
 
     fn open_document_at(&mut self, path: &PathBuf) -> Result<()> {
-        // Support legacy .json files directly, otherwise use the .prtcad tar-based format.
-        let document = match path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_ascii_lowercase())
-        {
-            Some(ext) if ext == "json" => {
-                let file = std::fs::File::open(path)
-                    .with_context(|| format!("Failed to open document file {}", path.display()))?;
-                serde_json::from_reader(file).with_context(|| "Failed to parse document JSON")?
-            }
-            _ => Document::load_from_file(path)
-                .with_context(|| format!("Failed to open .prtcad document {}", path.display()))?,
+        // STL and STEP files open as a new document holding the part.
+        let effective_settings = self.effective_settings();
+        let part = library::part_document(
+            path,
+            &effective_settings.exchange.options(ExchangeFormat::Stl),
+            effective_settings.camera.axis_system(),
+            &mut self.pending_parts,
+        );
+        let is_part = part.is_some();
+        let document = match part {
+            Some(document) => document?,
+            // Support legacy .json files directly, otherwise use the .prtcad tar-based format.
+            None => match path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_ascii_lowercase())
+            {
+                Some(ext) if ext == "json" => {
+                    let file = std::fs::File::open(path).with_context(|| {
+                        format!("Failed to open document file {}", path.display())
+                    })?;
+                    serde_json::from_reader(file)
+                        .with_context(|| "Failed to parse document JSON")?
+                }
+                _ => Document::load_from_file(path).with_context(|| {
+                    format!("Failed to open .prtcad document {}", path.display())
+                })?,
+            },
         };
 
         self.document = document;
-        self.current_file = Some(path.clone());
+        self.document
+            .set_active_workbench(Some(self.active_workbench_id()));
+        // Saving writes a document, never over the part file.
+        self.current_file = (!is_part).then(|| path.clone());
         // Derive a user-facing document name from the file name (strip known extensions).
         let file_name = path
             .file_name()
@@ -767,17 +1582,35 @@ impl PrintCadApp {
             &file_name[..stripped.len()]
         } else if let Some(stripped) = lowered.strip_suffix(".json") {
             &file_name[..stripped.len()]
+        } else if is_part {
+            file_name
+                .rsplit_once('.')
+                .map_or(file_name, |(stem, _)| stem)
         } else {
             file_name
         };
         self.document.set_name(name);
+        self.document.mark_clean();
+        // Updated links leave the document modified, so they get saved.
+        self.update_linked_bodies();
+        self.camera
+            .sync_with_settings(&self.effective_settings().camera);
+        if let Some(document_axes) = self.document.authoring_axes() {
+            if document_axes != self.camera.axis_system() {
+                if let Some(ui_layer) = self.ui_layer.as_mut() {
+                    ui_layer.show_axis_prompt(document_axes);
+                }
+            }
+        }
+        self.last_save_time = Instant::now();
         self.active_document_object = None;
-        self.active_body_id = None;
         self.tree_selection = Some(TreeItemId::DocumentRoot);
-        self.selected_body = None;
+        self.selection.clear();
 
         Self::write_recent_dir(path);
+        self.recent_documents.add(&self.settings_store, path);
         app_log::info(format!("Opened document from {}", path.display()));
+        self.offer_recovery_copy(path);
         Ok(())
     }
 
@@ -800,6 +1633,15 @@ impl PrintCadApp {
             file_name
         };
         self.document.set_name(name);
+        let axes = self.effective_settings().camera.axis_system();
+        self.document.set_authoring_axes(axes);
+
+        if let Err(err) = self.user_settings.autosave.rotate_backups(path) {
+            app_log::warn(format!(
+                "Failed to rotate backups of {}: {err}",
+                path.display()
+            ));
+        }
 
         // For legacy .json files, keep writing plain JSON.
         // For everything else, use the .prtcad tar-based container with optional compression.
@@ -825,53 +1667,465 @@ impl PrintCadApp {
                     core_document::Compression::None
                 };
 
+                let thumbnail = self.render_thumbnail();
+                // Meshes are saved along, so the document shows up right away
+                // when opened; afterwards only the caches keep them.
+                self.recompute.persist(&mut self.document);
+                self.mesh_cache.persist(&mut self.document);
                 self.document
-                    .save_to_file(path, compression)
-                    .with_context(|| {
-                        format!("Failed to save .prtcad document {}", path.display())
-                    })?;
+                    .record_journal(&core_document::journal::current_user());
+                let saved = self.document.save_to_file_with_thumbnail(
+                    path,
+                    compression,
+                    thumbnail.as_deref(),
+                );
+                self.document.clear_cached_meshes();
+                saved.with_context(|| {
+                    format!("Failed to save .prtcad document {}", path.display())
+                })?;
             }
         }
 
+        self.document.mark_clean();
         self.current_file = Some(path.clone());
+        self.last_save_time = Instant::now();
+        // The saved file supersedes what autosave kept.
+        let recovery = self.user_settings.autosave.recovery_path(path);
+        if recovery != *path && recovery.exists() {
+            if let Err(err) = std::fs::remove_file(&recovery) {
+                app_log::warn(format!("Failed to remove {}: {err}", recovery.display()));
+            }
+        }
         Self::write_recent_dir(path);
+        self.recent_documents.add(&self.settings_store, path);
         app_log::info(format!("Saved document to {}", path.display()));
+        if self
+            .user_settings
+            .autosave
+            .hooks
+            .iter()
+            .any(|hook| hook.enabled)
+            && !self.queued_save_hooks.contains(path)
+        {
+            self.queued_save_hooks.push_back(path.clone());
+        }
         Ok(())
     }
 
-    fn start_file_dialog(&mut self, open: bool, _save: bool, save_as: bool) {
-        use std::sync::mpsc;
-        if self.file_dialog_rx.is_some() {
-            return;
+    /// Run the enabled post-save hooks of the next queued save as a job, once
+    /// the previous save's hooks are done, so slow copies or uploads neither
+    /// hold up the UI nor pile up. The job can be cancelled from the status
+    /// bar, which kills a running command. Outcomes go to the log.
+    fn run_queued_save_hooks(&mut self) {
+        if let Some((jobs, job)) = &self.save_hooks_job {
+            if jobs.is_running(*job) {
+                return;
+            }
+            if let Some(Err(err)) = jobs.take::<()>(*job) {
+                app_log::error(format!("Save hooks failed: {err}"));
+            }
+            self.save_hooks_job = None;
         }
+        let Some(path) = self.queued_save_hooks.pop_front() else {
+            return;
+        };
+        let hooks: Vec<_> = self
+            .user_settings
+            .autosave
+            .hooks
+            .iter()
+            .filter(|hook| hook.enabled)
+            .cloned()
+            .collect();
+        let jobs = self.document.jobs().clone();
+        let job = jobs.spawn_with_progress("Save hooks", move |progress| {
+            for (index, hook) in hooks.iter().enumerate() {
+                if progress.is_cancelled() {
+                    return;
+                }
+                match hook.run(&path, progress.cancellation()) {
+                    Ok(summary) => app_log::info(format!("Save hook: {summary}")),
+                    Err(err) => app_log::error(format!("Save hook failed: {err}")),
+                }
+                progress.set((index + 1) as f32 / hooks.len() as f32);
+            }
+        });
+        self.save_hooks_job = Some((jobs, job));
+    }
 
-        let (tx, rx) = mpsc::channel::<FileDialogResult>();
-        self.file_dialog_rx = Some(rx);
+    /// PNG preview of the bodies for the saved file (None for an empty document).
+    fn render_thumbnail(&self) -> Option<Vec<u8>> {
+        self.render_preview(thumbnailer::THUMBNAIL_SIZE)?
+            .map_err(|err| app_log::warn(format!("Document saved without a preview: {err}")))
+            .ok()
+    }
 
-        let kind = if open {
-            FileDialogKind::Open
-        } else if save_as {
-            FileDialogKind::SaveAs
-        } else {
-            FileDialogKind::Save
-        };
+    /// PNG of the bodies `size` pixels square, seen from the front corner
+    /// (None for an empty document).
+    fn render_preview(&self, size: u32) -> Option<Result<Vec<u8>, thumbnailer::ThumbnailError>> {
+        let meshes = thumbnailer::body_meshes(&self.body_meshes);
+        let image = thumbnailer::render(&meshes, self.camera.axis_system().up_vec(), size)?;
+        Some(thumbnailer::encode_png(&image))
+    }
 
-        let current_path = self.current_file.clone();
+    /// Selection item under the cursor, if any.
+    fn hovered_item(&self) -> Option<SelectionItem> {
+        self.hovered_body
+            .and_then(|id| pick_item(&self.frame_snapshot, id))
+    }
 
-        std::thread::spawn(move || {
-            let mut dialog =
-                rfd::FileDialog::new().add_filter("printCAD Document", &["prtcad", "json"]);
+    /// Origin element selected in the feature tree, if any.
+    fn selected_origin(&self) -> Option<core_document::OriginRef> {
+        match self.tree_selection {
+            Some(TreeItemId::OriginElement(origin)) => Some(origin),
+            _ => None,
+        }
+    }
 
-            if let Ok(recent_path) = settings::SettingsStore::recent_file_path() {
-                if let Ok(file) = std::fs::File::open(&recent_path) {
-                    if let Ok(saved_dir_str) = serde_json::from_reader::<_, String>(file) {
-                        let saved_dir = std::path::PathBuf::from(saved_dir_str);
-                        dialog = dialog.set_directory(saved_dir);
-                    }
-                }
-            }
+    /// User settings with the active document's overrides applied.
+    fn effective_settings(&self) -> UserSettings {
+        apply_document_overrides(&self.user_settings, self.document.overrides())
+    }
 
-            let path = match kind {
+    /// Run the remote API requests that are ready, answering each (a
+    /// recompute is answered once it has finished).
+    #[cfg(feature = "remote-api")]
+    fn serve_remote_requests(&mut self) {
+        use remote::{Operation, Reply};
+
+        let Some(requests) = self.remote.as_mut().map(remote::RemoteApi::ready) else {
+            return;
+        };
+        for mut request in requests {
+            let reply = match &request.operation {
+                Operation::Status => Reply::Json(self.remote_status()),
+                Operation::Open(path) => match self.open_document_at(path) {
+                    Ok(()) => {
+                        self.remote.as_mut().map(remote::RemoteApi::unsettle);
+                        Reply::Json(self.remote_status())
+                    }
+                    Err(err) => Reply::Error(format!("{err:#}")),
+                },
+                Operation::Save(path) => match path.clone().or_else(|| self.current_file.clone()) {
+                    Some(path) => match self.save_document_at(&path) {
+                        Ok(()) => Reply::Json(self.remote_status()),
+                        Err(err) => Reply::Error(format!("{err:#}")),
+                    },
+                    None => Reply::Error("the document has no file yet; give a path".to_string()),
+                },
+                Operation::Recompute => {
+                    self.pending_commands.push("part.recompute".to_string());
+                    if let Some(remote) = &mut self.remote {
+                        remote.unsettle();
+                        request.operation = Operation::Status;
+                        remote.wait(request);
+                    }
+                    continue;
+                }
+                Operation::Export(path) => self.remote_export(path),
+                Operation::Screenshot { size } => match self.render_preview(*size) {
+                    Some(Ok(png)) => Reply::Png(png),
+                    Some(Err(err)) => Reply::Error(err.to_string()),
+                    None => Reply::Error("the document has no geometry to draw".to_string()),
+                },
+            };
+            request.answer(reply);
+        }
+    }
+
+    #[cfg(feature = "remote-api")]
+    fn remote_status(&self) -> serde_json::Value {
+        serde_json::json!({
+            "document": self.document.name(),
+            "file": self.current_file.as_ref().map(|path| path.display().to_string()),
+            "modified": self.document.metadata().dirty(),
+            "bodies": self.body_meshes.len(),
+            "workbench": self.active_workbench_id().as_str(),
+        })
+    }
+
+    /// Write every body with geometry to `path`, in the format of its extension.
+    #[cfg(feature = "remote-api")]
+    fn remote_export(&self, path: &Path) -> remote::Reply {
+        use wb_mesh::batch::{self, BatchFormat};
+
+        let Some(format) = BatchFormat::from_path(path) else {
+            let extensions: Vec<&str> = BatchFormat::ALL.iter().map(|f| f.extension()).collect();
+            return remote::Reply::Error(format!(
+                "cannot export to {}: use one of .{}",
+                path.display(),
+                extensions.join(", .")
+            ));
+        };
+        let settings = self.effective_settings();
+        let bodies = wb_mesh::document_bodies(&self.document, &self.body_meshes);
+        match batch::export_file(
+            &bodies,
+            self.document.name(),
+            format,
+            &settings.exchange.options(format.exchange_format()),
+            settings.camera.axis_system(),
+            path,
+        ) {
+            Ok(count) => {
+                app_log::info(format!(
+                    "Exported {count} bod{} to {}",
+                    if count == 1 { "y" } else { "ies" },
+                    path.display()
+                ));
+                remote::Reply::Json(serde_json::json!({
+                    "path": path.display().to_string(),
+                    "bodies": count,
+                }))
+            }
+            Err(err) => remote::Reply::Error(format!("cannot export to {}: {err}", path.display())),
+        }
+    }
+
+    /// Open the document given on the command line or handed over by a later
+    /// launch (the newest one if several arrived), and bring the window forward.
+    fn open_requested_files(&mut self) {
+        let mut path = self.startup_file.take();
+        while let Ok(incoming) = self.incoming_files.try_recv() {
+            path = Some(incoming);
+        }
+        let Some(path) = path else {
+            return;
+        };
+        if let Err(err) = self.open_document_at(&path) {
+            app_log::error(format!("Failed to open document: {err:#}"));
+        }
+        if let Some(window) = &self.window {
+            window.focus_window();
+        }
+    }
+
+    /// Run the deactivate/activate hooks of a workbench switch.
+    fn switch_workbench(&mut self, old_wb: &WorkbenchId, new_wb: &WorkbenchId) {
+        if let Some(drag) = self.gizmo_drag.take() {
+            self.call_workbench_gizmo_drag(old_wb, &drag.event(GizmoDragPhase::Cancel));
+        }
+        self.gizmos.clear();
+        self.hovered_gizmo = None;
+        self.call_workbench_deactivate(old_wb);
+
+        self.document.set_active_workbench(Some(new_wb.clone()));
+        self.call_workbench_activate(new_wb);
+    }
+
+    /// Reopen the document of the previous session with its workbench and view.
+    fn restore_session(&mut self) {
+        let Some(session) = self.previous_session.take() else {
+            return;
+        };
+        if let Err(err) = self.open_document_at(&session.document) {
+            app_log::error(format!("Failed to restore the previous session: {err:#}"));
+            return;
+        }
+        self.camera.set_pose(&session.camera);
+
+        let workbench = WorkbenchId::new(session.workbench);
+        if self.registry.workbench(&workbench).is_ok() {
+            self.activate_workbench(workbench);
+        }
+        app_log::info("Restored the previous session");
+    }
+
+    /// Make `workbench` the active one, as if it had been picked in the
+    /// workbench selector.
+    fn activate_workbench(&mut self, workbench: WorkbenchId) {
+        if workbench == self.active_workbench.0 {
+            return;
+        }
+        let old_wb = std::mem::replace(&mut self.active_workbench, ActiveWorkbench(workbench));
+        if let Some(ui_layer) = self.ui_layer.as_mut() {
+            ui_layer.set_active_workbench(self.active_workbench.clone());
+        }
+        self.active_tool = ActiveTool::default();
+        self.sync_active_tool();
+        let new_wb = self.active_workbench_id();
+        self.switch_workbench(&old_wb.0, &new_wb);
+    }
+
+    /// Open a feature double-clicked in the tree for editing: switch to the
+    /// workbench it was created in and hand it over.
+    fn edit_feature(&mut self, id: FeatureId) {
+        if self.viewer {
+            return;
+        }
+        let Some(editor) = self.document.feature_editor(id).cloned() else {
+            return;
+        };
+        if self.registry.workbench(&editor).is_err() {
+            app_log::info("This feature has no workbench to edit it in");
+            return;
+        }
+        self.activate_workbench(editor.clone());
+        self.active_document_object = Some(id);
+        self.selection.set(SelectionItem::Feature(id));
+        self.synced_selection = self.selection.clone();
+        self.with_workbench_context(&editor, |wb, ctx| wb.edit_feature(id, ctx));
+    }
+
+    /// Remember the open document, workbench, and view for the next launch.
+    fn record_session(&self) {
+        if self.viewer {
+            return;
+        }
+        let session = self.current_file.as_ref().map(|document| SessionState {
+            document: document.clone(),
+            workbench: self.active_workbench.0.as_str().to_string(),
+            camera: self.camera.pose(),
+        });
+        // Keep an unanswered offer for the next launch rather than forgetting it.
+        if session.is_none() && self.previous_session.is_some() {
+            return;
+        }
+        if let Err(err) = self.settings_store.save_session(session.as_ref()) {
+            app_log::warn(format!("Failed to record the session: {err}"));
+        }
+    }
+
+    /// Save the current document in place once the autosave interval has elapsed.
+    ///
+    /// Only documents that already have a file and unsaved changes are autosaved; untitled
+    /// documents are left alone so no Save As dialog pops up unexpectedly.
+    fn autosave_if_due(&mut self) {
+        let autosave = &self.user_settings.autosave;
+        if !autosave.enabled
+            || self.viewer
+            || !self.document.metadata().dirty()
+            || self.last_save_time.elapsed() < autosave.interval()
+        {
+            return;
+        }
+        let Some(path) = self.current_file.clone() else {
+            return;
+        };
+        // Don't retry every frame after a failure; wait for the next interval.
+        self.last_save_time = Instant::now();
+        let recovery = self.user_settings.autosave.recovery_path(&path);
+        match self.write_recovery_copy(&recovery) {
+            Ok(()) => app_log::info(format!("Autosaved to {}", recovery.display())),
+            Err(err) => app_log::error(format!("Autosave failed: {err:#}")),
+        }
+    }
+
+    /// Offer to recover an autosaved recovery copy of `path` that is newer
+    /// than the file, i.e. changes that were never saved.
+    fn offer_recovery_copy(&mut self, path: &Path) {
+        let recovery = self.user_settings.autosave.recovery_path(path);
+        if recovery == path {
+            return;
+        }
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let (Some(recovery_time), Some(file_time)) = (modified(&recovery), modified(path)) {
+            if recovery_time > file_time {
+                app_log::warn(format!(
+                    "{} holds autosaved changes newer than this file",
+                    recovery.display()
+                ));
+                if let Some(ui_layer) = self.ui_layer.as_mut() {
+                    ui_layer.show_recovery_prompt(recovery);
+                }
+            }
+        }
+    }
+
+    /// Act on the answer to the recovery prompt for the open document.
+    fn answer_recovery_prompt(&mut self, choice: RecoveryChoice) {
+        let Some(path) = self.current_file.clone() else {
+            return;
+        };
+        let recovery = self.user_settings.autosave.recovery_path(&path);
+        match choice {
+            RecoveryChoice::Recover => match Document::load_from_file(&recovery) {
+                Ok(document) => {
+                    let name = self.document.name().to_string();
+                    self.document = document;
+                    self.document.set_name(&name);
+                    self.document
+                        .set_active_workbench(Some(self.active_workbench_id()));
+                    // Still saved to the document's own file, which lacks these changes.
+                    self.document.mark_dirty();
+                    self.update_linked_bodies();
+                    self.active_document_object = None;
+                    self.tree_selection = Some(TreeItemId::DocumentRoot);
+                    self.selection.clear();
+                    app_log::info(format!(
+                        "Recovered autosaved changes from {}",
+                        recovery.display()
+                    ));
+                }
+                Err(err) => {
+                    app_log::error(format!("Failed to recover {}: {err:#}", recovery.display()))
+                }
+            },
+            RecoveryChoice::Discard => match std::fs::remove_file(&recovery) {
+                Ok(()) => app_log::info(format!("Discarded {}", recovery.display())),
+                Err(err) => {
+                    app_log::warn(format!("Failed to remove {}: {err}", recovery.display()))
+                }
+            },
+            RecoveryChoice::Keep => {}
+        }
+    }
+
+    /// Write the document to the autosave recovery file `recovery`. Unlike a
+    /// save, this leaves the document's own file, its modified state, the
+    /// change journal, and the post-save hooks alone.
+    fn write_recovery_copy(&mut self, recovery: &Path) -> Result<()> {
+        if let Some(dir) = recovery.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        self.recompute.persist(&mut self.document);
+        self.mesh_cache.persist(&mut self.document);
+        let saved = self.document.save_to_file_with_thumbnail(
+            recovery,
+            core_document::Compression::None,
+            None,
+        );
+        self.document.clear_cached_meshes();
+        saved.with_context(|| format!("Failed to write {}", recovery.display()))
+    }
+
+    fn start_file_dialog(&mut self, open: bool, _save: bool, save_as: bool) {
+        use std::sync::mpsc;
+        if self.file_dialog_rx.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel::<FileDialogResult>();
+        self.file_dialog_rx = Some(rx);
+
+        let kind = if open {
+            FileDialogKind::Open
+        } else if save_as {
+            FileDialogKind::SaveAs
+        } else {
+            FileDialogKind::Save
+        };
+
+        let current_path = self.current_file.clone();
+
+        std::thread::spawn(move || {
+            let mut dialog =
+                rfd::FileDialog::new().add_filter("printCAD Document", &["prtcad", "json"]);
+            if matches!(kind, FileDialogKind::Open) {
+                dialog = dialog.add_filter("STL or STEP Part", &["stl", "step", "stp"]);
+            }
+
+            if let Ok(recent_path) = settings::SettingsStore::recent_file_path() {
+                if let Ok(file) = std::fs::File::open(&recent_path) {
+                    if let Ok(saved_dir_str) = serde_json::from_reader::<_, String>(file) {
+                        let saved_dir = std::path::PathBuf::from(saved_dir_str);
+                        dialog = dialog.set_directory(saved_dir);
+                    }
+                }
+            }
+
+            let path = match kind {
                 FileDialogKind::Open => dialog.pick_file(),
                 FileDialogKind::Save => {
                     if let Some(existing) = current_path {
@@ -881,13 +2135,219 @@ impl PrintCadApp {
                     }
                 }
                 FileDialogKind::SaveAs => dialog.set_file_name("untitled.prtcad").save_file(),
+                FileDialogKind::ExportProfile
+                | FileDialogKind::ImportProfile
+                | FileDialogKind::Workbench(_) => None,
             };
 
             let _ = tx.send(FileDialogResult { kind, path });
         });
     }
 
-    fn write_recent_dir(path: &PathBuf) {
+    fn start_profile_dialog(&mut self, kind: FileDialogKind) {
+        use std::sync::mpsc;
+        if self.file_dialog_rx.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel::<FileDialogResult>();
+        self.file_dialog_rx = Some(rx);
+
+        let file_name = format!(
+            "{}.json",
+            self.user_settings
+                .active_profile
+                .as_deref()
+                .unwrap_or("printcad-settings")
+        );
+
+        std::thread::spawn(move || {
+            let dialog = rfd::FileDialog::new().add_filter("printCAD Settings Profile", &["json"]);
+            let path = match kind {
+                FileDialogKind::ExportProfile => dialog.set_file_name(file_name).save_file(),
+                FileDialogKind::ImportProfile => dialog.pick_file(),
+                _ => None,
+            };
+            let _ = tx.send(FileDialogResult { kind, path });
+        });
+    }
+
+    fn start_workbench_file_dialog(&mut self, request: FileOpenRequest) {
+        use std::sync::mpsc;
+        if self.file_dialog_rx.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel::<FileDialogResult>();
+        self.file_dialog_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let dialog =
+                rfd::FileDialog::new().add_filter(&request.filter_name, &request.extensions);
+            let path = match &request.save_name {
+                _ if request.folder => rfd::FileDialog::new().pick_folder(),
+                Some(name) => dialog.set_file_name(name).save_file(),
+                None => dialog.pick_file(),
+            };
+            let _ = tx.send(FileDialogResult {
+                kind: FileDialogKind::Workbench(request),
+                path,
+            });
+        });
+    }
+
+    fn handle_library_action(&mut self, action: LibraryAction) {
+        match action {
+            LibraryAction::Insert { entry, linked } => {
+                let effective_settings = self.effective_settings();
+                let stl = effective_settings.exchange.options(ExchangeFormat::Stl);
+                let axes = effective_settings.camera.axis_system();
+                match library::insert(
+                    &mut self.document,
+                    &entry,
+                    linked,
+                    &stl,
+                    axes,
+                    &mut self.pending_parts,
+                ) {
+                    Ok(bodies) => {
+                        let kind = if linked { "linked" } else { "copied" };
+                        app_log::info(format!(
+                            "Inserted {} as {} {kind} bod{}",
+                            entry.name,
+                            bodies.len(),
+                            if bodies.len() == 1 { "y" } else { "ies" }
+                        ));
+                        if let Some(&body) = bodies.first() {
+                            self.tree_selection = Some(TreeItemId::Body(body));
+                        }
+                    }
+                    Err(err) => app_log::error(format!("Failed to insert {}: {err:#}", entry.name)),
+                }
+            }
+            LibraryAction::Rescan => self.part_library.rescan(),
+            LibraryAction::UpdateLinks => {
+                if !self.update_linked_bodies() {
+                    app_log::info("Linked bodies are up to date");
+                }
+            }
+        }
+    }
+
+    /// Reload linked bodies whose file changed. Returns whether any was updated.
+    fn update_linked_bodies(&mut self) -> bool {
+        let effective_settings = self.effective_settings();
+        let stl = effective_settings.exchange.options(ExchangeFormat::Stl);
+        let axes = effective_settings.camera.axis_system();
+        let updated = library::refresh_links(&mut self.document, &stl, axes);
+        for name in &updated {
+            app_log::info(format!("Updated linked body {name}"));
+        }
+        !updated.is_empty()
+    }
+
+    fn handle_profile_action(&mut self, action: ProfileAction) {
+        match action {
+            ProfileAction::Save(name) => {
+                match self.settings_store.save_profile(&name, &self.user_settings) {
+                    Ok(()) => {
+                        app_log::info(format!("Saved settings profile `{name}`"));
+                        self.user_settings.active_profile = Some(name);
+                        self.persist_user_settings();
+                    }
+                    Err(err) => app_log::error(format!("Failed to save profile: {err}")),
+                }
+            }
+            ProfileAction::Load(name) => match self.settings_store.load_profile(&name) {
+                Ok(settings) => {
+                    self.user_settings = settings;
+                    self.camera
+                        .sync_with_settings(&self.effective_settings().camera);
+                    self.persist_user_settings();
+                    app_log::info(format!("Switched to settings profile `{name}`"));
+                }
+                Err(err) => app_log::error(format!("Failed to load profile: {err}")),
+            },
+            ProfileAction::Delete(name) => match self.settings_store.delete_profile(&name) {
+                Ok(()) => {
+                    if self.user_settings.active_profile.as_deref() == Some(name.as_str()) {
+                        self.user_settings.active_profile = None;
+                        self.persist_user_settings();
+                    }
+                    app_log::info(format!("Deleted settings profile `{name}`"));
+                }
+                Err(err) => app_log::error(format!("Failed to delete profile: {err}")),
+            },
+            ProfileAction::Export => self.start_profile_dialog(FileDialogKind::ExportProfile),
+            ProfileAction::Import => self.start_profile_dialog(FileDialogKind::ImportProfile),
+        }
+        self.refresh_settings_profiles();
+    }
+
+    fn export_settings_profile(&self, path: &Path) {
+        let name = self
+            .user_settings
+            .active_profile
+            .clone()
+            .unwrap_or_else(|| "Exported".to_string());
+        match settings::SettingsProfile::new(name, &self.user_settings).write_to(path) {
+            Ok(()) => app_log::info(format!("Exported settings to {}", path.display())),
+            Err(err) => app_log::error(format!("Failed to export settings: {err}")),
+        }
+    }
+
+    fn import_settings_profile(&mut self, path: &Path) {
+        match self.settings_store.import_profile(path) {
+            Ok(name) => app_log::info(format!(
+                "Imported settings profile `{name}` from {}",
+                path.display()
+            )),
+            Err(err) => app_log::error(format!("Failed to import settings profile: {err}")),
+        }
+        self.refresh_settings_profiles();
+    }
+
+    fn refresh_settings_profiles(&mut self) {
+        match self.settings_store.list_profiles() {
+            Ok(list) => self.settings_profiles = list,
+            Err(err) => app_log::warn(format!("Failed to list settings profiles: {err}")),
+        }
+    }
+
+    /// Apply edits made to settings.json outside the app (camera, lighting, rendering).
+    fn reload_settings_if_changed(&mut self) {
+        let changed = self
+            .settings_watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.take_changed());
+        if !changed {
+            return;
+        }
+        match self.settings_store.load() {
+            // Our own saves also trigger the watcher; ignore them.
+            Ok(settings) if settings == self.user_settings => {}
+            Ok(settings) => {
+                self.user_settings = settings;
+                self.camera
+                    .sync_with_settings(&self.effective_settings().camera);
+                app_log::info("Reloaded settings changed on disk");
+                if let Some(window) = self.window.as_ref() {
+                    window.request_redraw();
+                }
+            }
+            // Editors may write the file in several steps; keep the current settings and wait
+            // for the next change event.
+            Err(err) => app_log::warn(format!("Ignoring invalid settings file on disk: {err}")),
+        }
+    }
+
+    fn persist_user_settings(&self) {
+        if let Err(err) = self.settings_store.save(&self.user_settings) {
+            app_log::warn(format!("Failed to save settings: {err}"));
+        }
+    }
+
+    fn write_recent_dir(path: &Path) {
         if let Ok(recent_path) = settings::SettingsStore::recent_file_path() {
             if let Some(dir) = path.parent() {
                 if let Ok(file) = std::fs::File::create(&recent_path) {
@@ -902,6 +2362,10 @@ impl PrintCadApp {
     }
 
     fn handle_tool_input(&mut self, event: &WindowEvent) -> bool {
+        if self.viewer {
+            return self.handle_viewer_input(event);
+        }
+
         // Convert winit event to workbench input event
         let wb_event = match self.convert_to_wb_event(event) {
             Some(e) => e,
@@ -913,13 +2377,29 @@ impl PrintCadApp {
         // For input handling, we pass the first active tool (or None if no tools active)
         // This maintains compatibility with the existing on_input API
         let active_tool_id = self.active_tool.active_ids.iter().next().cloned();
+        if let Some(tool_id) = &active_tool_id {
+            if self.tool_lifecycle_input(&wb_id, tool_id, &wb_event) {
+                return true;
+            }
+        }
         let active_tool_str = active_tool_id.as_deref();
+        if let Some(double_click) = self.double_click(&wb_event) {
+            let result = self.call_workbench_input(&wb_id, &double_click, active_tool_str);
+            if result.consumed {
+                return result.redraw;
+            }
+        }
         let result = self.call_workbench_input(&wb_id, &wb_event, active_tool_str);
 
-        // Clear action tools after they're handled
+        // Clear action tools after they're handled, and tools that finished an
+        // element unless tools are sticky
         if let Some(tool_id) = active_tool_id {
-            if tool_id == "sketch.create" && result.consumed {
-                self.active_tool.active_ids.remove(&tool_id);
+            let is_action = matches!(tool_id.as_str(), "sketch.create" | "sketch.create_on_view");
+            let sticky = self.effective_settings().input.sticky_tools;
+            if ((is_action && result.consumed) || (result.finished && !sticky))
+                && self.active_tool.active_ids.remove(&tool_id)
+            {
+                self.sync_active_tool();
             }
         }
 
@@ -927,10 +2407,391 @@ impl PrintCadApp {
             return result.redraw;
         }
 
+        if let WorkbenchInputEvent::KeyPress { key } = wb_event {
+            if self.activate_tool_shortcut(&wb_id, key) {
+                return true;
+            }
+        }
+
         // If workbench didn't consume, handle with default behavior (select tool)
         self.handle_select_tool(event)
     }
 
+    /// Viewport input of the viewer: clicks select, or place measurement
+    /// points while measuring; Escape clears the measurement.
+    fn handle_viewer_input(&mut self, event: &WindowEvent) -> bool {
+        match self.convert_to_wb_event(event) {
+            Some(WorkbenchInputEvent::KeyPress {
+                key: core_document::KeyCode::Escape,
+            }) => self.measurement.clear(),
+            Some(WorkbenchInputEvent::MouseRelease {
+                button: WbMouseButton::Left,
+                ..
+            }) if self.measurement.active => {
+                let Some(world) = self.hovered_world_pos else {
+                    return false;
+                };
+                if let Some(offset) = self.measurement.place(world) {
+                    let units = self.effective_settings().units.format();
+                    app_log::info(format!(
+                        "Distance: {} (dX {}, dY {}, dZ {})",
+                        units.format_length(offset.length()),
+                        units.format_length(offset.x),
+                        units.format_length(offset.y),
+                        units.format_length(offset.z),
+                    ));
+                }
+                true
+            }
+            _ => self.handle_select_tool(event),
+        }
+    }
+
+    /// Input every tool handles the same way, whatever its workbench: Escape
+    /// cancels the tool's operation in progress, or exits the tool when there
+    /// is none, and a right click (without dragging the camera) opens a menu
+    /// to finish or cancel it. Returns true when the event was used up here.
+    fn tool_lifecycle_input(
+        &mut self,
+        wb_id: &WorkbenchId,
+        tool_id: &str,
+        event: &WorkbenchInputEvent,
+    ) -> bool {
+        let menu_was_open = match event {
+            WorkbenchInputEvent::MousePress { .. }
+            | WorkbenchInputEvent::KeyPress {
+                key: core_document::KeyCode::Escape,
+            } => self
+                .ui_layer
+                .as_mut()
+                .is_some_and(|ui_layer| ui_layer.close_tool_menu()),
+            _ => false,
+        };
+        if menu_was_open {
+            // Clicking away from the menu (or Escape) only closes it.
+            return true;
+        }
+        match *event {
+            WorkbenchInputEvent::KeyPress {
+                key: core_document::KeyCode::Escape,
+            } => {
+                let cancelled = self
+                    .with_workbench_context(wb_id, |wb, ctx| wb.cancel_tool_operation(tool_id, ctx))
+                    .unwrap_or(false);
+                if !cancelled {
+                    self.deactivate_tool(wb_id, tool_id);
+                }
+                true
+            }
+            WorkbenchInputEvent::MousePress {
+                button: WbMouseButton::Right,
+                viewport_pos,
+            } => {
+                self.tool_menu_press = Some(viewport_pos);
+                false
+            }
+            WorkbenchInputEvent::MouseRelease {
+                button: WbMouseButton::Right,
+                viewport_pos,
+            } => {
+                let Some(press) = self.tool_menu_press.take() else {
+                    return false;
+                };
+                let travel = (viewport_pos.0 - press.0).hypot(viewport_pos.1 - press.1);
+                if travel <= TOOL_MENU_CLICK_DISTANCE {
+                    let vp = self.camera.viewport_info();
+                    let label = self.tool_label(wb_id, tool_id);
+                    if let Some(ui_layer) = self.ui_layer.as_mut() {
+                        ui_layer
+                            .show_tool_menu((vp.0 + viewport_pos.0, vp.1 + viewport_pos.1), label);
+                    }
+                }
+                // The release still ends a camera drag started by the press.
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Finish or cancel the active tool from its right-click menu, then exit it.
+    fn apply_tool_menu_action(&mut self, action: ui::ToolMenuAction) {
+        let Some(tool_id) = self.active_tool.active_ids.iter().next().cloned() else {
+            return;
+        };
+        let wb_id = self.active_workbench_id();
+        self.with_workbench_context(&wb_id, |wb, ctx| match action {
+            ui::ToolMenuAction::Finish => wb.finish_tool_operation(&tool_id, ctx),
+            ui::ToolMenuAction::Cancel => {
+                wb.cancel_tool_operation(&tool_id, ctx);
+            }
+        });
+        self.deactivate_tool(&wb_id, &tool_id);
+    }
+
+    fn deactivate_tool(&mut self, wb_id: &WorkbenchId, tool_id: &str) {
+        if self.active_tool.active_ids.remove(tool_id) {
+            self.sync_active_tool();
+            app_log::info(format!("Exited tool: {}", self.tool_label(wb_id, tool_id)));
+        }
+    }
+
+    /// The label of a workbench's tool (its id if it isn't registered).
+    fn tool_label(&self, wb_id: &WorkbenchId, tool_id: &str) -> String {
+        self.registry
+            .tools_for(wb_id)
+            .ok()
+            .and_then(|tools| tools.iter().find(|tool| tool.id == tool_id))
+            .map_or_else(|| tool_id.to_string(), |tool| tool.label.clone())
+    }
+
+    /// Activate the active workbench's tool bound to `key`, cycling to the
+    /// next enabled tool with the same key when one of them is already active.
+    /// Returns whether a tool was activated.
+    fn activate_tool_shortcut(&mut self, wb_id: &WorkbenchId, key: core_document::KeyCode) -> bool {
+        let modifiers = self.modifiers;
+        if !self.effective_settings().input.tool_shortcuts
+            || modifiers.control_key()
+            || modifiers.alt_key()
+            || modifiers.super_key()
+        {
+            return false;
+        }
+        let Ok(tools) = self.registry.tools_for(wb_id).map(<[_]>::to_vec) else {
+            return false;
+        };
+        let candidates: Vec<_> = self
+            .with_workbench_context(wb_id, |wb, ctx| {
+                tools
+                    .iter()
+                    .filter(|tool| tool.shortcut == Some(key) && wb.is_tool_enabled(&tool.id, ctx))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let next = match candidates
+            .iter()
+            .position(|tool| self.active_tool.active_ids.contains(&tool.id))
+        {
+            Some(index) => candidates[(index + 1) % candidates.len()],
+            None => match candidates.first() {
+                Some(tool) => tool,
+                None => return false,
+            },
+        };
+        self.active_tool.activate(next, &tools);
+        self.sync_active_tool();
+        app_log::info(format!("Tool: {}", next.label));
+        true
+    }
+
+    /// Pass tool changes made here to the UI, which owns the tool buttons.
+    fn sync_active_tool(&mut self) {
+        if let Some(ui_layer) = self.ui_layer.as_mut() {
+            ui_layer.set_active_tool(self.active_tool.clone());
+        }
+    }
+
+    /// The double click completed by `event`, if it is a press close enough in
+    /// time and place to the previous one.
+    fn double_click(&mut self, event: &WorkbenchInputEvent) -> Option<WorkbenchInputEvent> {
+        let WorkbenchInputEvent::MousePress {
+            button,
+            viewport_pos,
+        } = *event
+        else {
+            return None;
+        };
+        let now = Instant::now();
+        let previous = self.last_press.replace((button, now, viewport_pos));
+        let (last_button, last_time, last_pos) = previous?;
+        let travel = (viewport_pos.0 - last_pos.0).hypot(viewport_pos.1 - last_pos.1);
+        if last_button != button
+            || now.duration_since(last_time) > DOUBLE_CLICK_TIME
+            || travel > DOUBLE_CLICK_DISTANCE
+        {
+            return None;
+        }
+        // A third press starts a new double click rather than completing another.
+        self.last_press = None;
+        Some(WorkbenchInputEvent::MouseDoubleClick {
+            button,
+            viewport_pos,
+        })
+    }
+
+    /// Hover and drag gizmo handles. Returns true when the event was used by a gizmo.
+    fn handle_gizmo_input(&mut self, event: &WindowEvent) -> bool {
+        let wb_id = self.active_workbench_id();
+        match event {
+            WindowEvent::CursorMoved { .. } => {
+                let Some(cursor) = self.cursor_in_viewport else {
+                    return false;
+                };
+                if self.gizmo_drag.is_some() {
+                    let snap = snap_options_from_settings(&self.effective_settings().snapping);
+                    let Some(drag) = self.gizmo_drag.as_mut() else {
+                        return false;
+                    };
+                    drag.update(&self.camera, cursor, &snap);
+                    let event = drag.event(GizmoDragPhase::Update);
+                    self.call_workbench_gizmo_drag(&wb_id, &event);
+                    return true;
+                }
+                let hovered = gizmo::pick(&self.gizmos, &self.camera, cursor);
+                if hovered != self.hovered_gizmo {
+                    self.hovered_gizmo = hovered;
+                    if let Some(window) = self.window.as_ref() {
+                        window.request_redraw();
+                    }
+                }
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if self.gizmo_drag.is_some() {
+                    return true;
+                }
+                let (Some((id, handle)), Some(cursor)) =
+                    (self.hovered_gizmo.clone(), self.cursor_in_viewport)
+                else {
+                    return false;
+                };
+                let Some(target) = self.gizmos.iter().find(|gizmo| gizmo.id == id) else {
+                    return false;
+                };
+                let drag = gizmo::GizmoDragState::start(target, handle, &self.camera, cursor);
+                let event = drag.event(GizmoDragPhase::Start);
+                self.gizmo_drag = Some(drag);
+                self.call_workbench_gizmo_drag(&wb_id, &event);
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => match self.gizmo_drag.take() {
+                Some(drag) => {
+                    self.call_workbench_gizmo_drag(&wb_id, &drag.event(GizmoDragPhase::End));
+                    true
+                }
+                None => false,
+            },
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => self.cancel_gizmo_drag(&wb_id),
+            WindowEvent::KeyboardInput { event, .. }
+                if event.state == ElementState::Pressed
+                    && event.logical_key
+                        == winit::keyboard::Key::Named(winit::keyboard::NamedKey::Escape) =>
+            {
+                self.cancel_gizmo_drag(&wb_id)
+            }
+            _ => false,
+        }
+    }
+
+    /// Abort the current gizmo drag (if any), returning whether there was one.
+    fn cancel_gizmo_drag(&mut self, wb_id: &WorkbenchId) -> bool {
+        match self.gizmo_drag.take() {
+            Some(drag) => {
+                self.call_workbench_gizmo_drag(wb_id, &drag.event(GizmoDragPhase::Cancel));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Run the commands workbenches queued, each on the workbench that
+    /// registered it. Commands queued by those run as well, for up to
+    /// `MAX_COMMAND_ROUNDS` rounds.
+    fn run_pending_commands(&mut self) {
+        for _ in 0..MAX_COMMAND_ROUNDS {
+            if self.pending_commands.is_empty() {
+                return;
+            }
+            for command in std::mem::take(&mut self.pending_commands) {
+                let Some(owner) = self.registry.command_owner(&command) else {
+                    app_log::warn(format!("Unknown command: {command}"));
+                    continue;
+                };
+                self.with_workbench_context(&owner, |wb, ctx| wb.on_command(&command, ctx));
+            }
+        }
+        if !self.pending_commands.is_empty() {
+            app_log::warn(format!(
+                "Dropped commands that kept queuing each other: {}",
+                self.pending_commands.join(", ")
+            ));
+            self.pending_commands.clear();
+        }
+    }
+
+    /// Call on_gizmo_drag on a workbench.
+    fn call_workbench_gizmo_drag(&mut self, wb_id: &WorkbenchId, drag: &GizmoDrag) {
+        self.with_workbench_context(wb_id, |wb, ctx| wb.on_gizmo_drag(drag, ctx));
+    }
+
+    /// Run `f` on a workbench with a runtime context of the current view and
+    /// selection, then flush its logs and take back the selection. None if
+    /// the workbench isn't registered.
+    fn with_workbench_context<R>(
+        &mut self,
+        wb_id: &WorkbenchId,
+        f: impl FnOnce(&mut dyn core_document::Workbench, &mut WorkbenchRuntimeContext) -> R,
+    ) -> Option<R> {
+        let cam_pos = self.camera.position();
+        let cam_target = self.camera.target();
+        let vp = self.camera.viewport_info();
+        let selection = self.selection.clone();
+        let cursor_viewport_pos = self.cursor_in_viewport;
+        let effective_settings = self.effective_settings();
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = self.selected_origin();
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
+        let view_proj = self.camera.view_projection();
+
+        if let Ok(wb) = self.registry.workbench_mut(wb_id) {
+            let mut ctx = WorkbenchRuntimeContext::new(
+                &mut self.document,
+                cam_pos,
+                cam_target,
+                (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
+            );
+            ctx.frame = self.camera.frame();
+            ctx.selection = selection;
+            ctx.cursor_viewport_pos = cursor_viewport_pos;
+            ctx.snap = snap;
+            ctx.units = units;
+            ctx.selected_reference = selected_reference;
+            ctx.axes = axes;
+            ctx.body_meshes = &self.body_meshes;
+            ctx.printer = printer;
+            ctx.view_proj = Some(view_proj);
+            ctx.active_document_object = self.active_document_object;
+
+            let result = f(wb.as_mut(), &mut ctx);
+            Self::flush_logs(ctx.drain_logs());
+            self.pending_commands.extend(ctx.drain_commands());
+            self.selection = std::mem::take(&mut ctx.selection);
+            if let Some(orient_req) = ctx.camera_orient_request.take() {
+                self.camera.orient_to_plane(
+                    glam::Vec3::from_array(orient_req.plane_origin),
+                    glam::Vec3::from_array(orient_req.plane_normal),
+                    glam::Vec3::from_array(orient_req.plane_up),
+                );
+            }
+            Some(result)
+        } else {
+            None
+        }
+    }
+
     /// Call on_input on a workbench.
     fn call_workbench_input(
         &mut self,
@@ -942,10 +2803,18 @@ impl PrintCadApp {
         let cam_pos = self.camera.position();
         let cam_target = self.camera.target();
         let vp = self.camera.viewport_info();
-        let mut hovered_world_pos = self.hovered_world_pos;
-        let hovered_body_id = self.hovered_body;
-        let selected_body_id = self.selected_body;
+        let mut hovered_world_pos = self.hovered_in_frame();
+        let hovered_normal = self.hovered_normal;
+        let hovered = self.hovered_item();
+        let selection = self.selection.clone();
         let cursor_viewport_pos = self.cursor_in_viewport;
+        let effective_settings = self.effective_settings();
+        let snap = snap_options_from_settings(&effective_settings.snapping);
+        let units = effective_settings.units.format();
+        let selected_reference = self.selected_origin();
+        let axes = effective_settings.camera.axis_system();
+        let printer = print_volume_from_settings(&effective_settings.printers);
+        let view_proj = self.camera.view_projection();
 
         // For sketch workbench, if we have a mouse event with viewport coordinates
         // and no hovered world position, try to project onto the active sketch plane
@@ -961,7 +2830,14 @@ impl PrintCadApp {
                     {
                         if let Ok(sketch_feature) = wb_sketch::SketchFeature::from_json(&node.data)
                         {
-                            let plane_origin = glam::Vec3::from_array(sketch_feature.plane.origin);
+                            // The plane is in the sketch's body frame.
+                            let position = node
+                                .body
+                                .map_or([0.0; 3], |b| self.document.body_position(b));
+                            let plane_origin = self.camera.to_frame(core_document::from_frame(
+                                sketch_feature.plane.origin,
+                                position,
+                            ));
                             let plane_normal = glam::Vec3::from_array(sketch_feature.plane.normal);
 
                             // Use viewport-local coordinates directly to project onto the sketch plane.
@@ -995,13 +2871,23 @@ impl PrintCadApp {
                 cam_target,
                 (vp.0 as u32, vp.1 as u32, vp.2, vp.3),
             );
+            ctx.frame = self.camera.frame();
             ctx.hovered_world_pos = hovered_world_pos;
-            ctx.hovered_body_id = hovered_body_id;
-            ctx.selected_body_id = selected_body_id;
+            ctx.hovered_normal = hovered_normal;
+            ctx.hovered = hovered;
+            ctx.selection = selection;
             ctx.cursor_viewport_pos = cursor_viewport_pos;
+            ctx.snap = snap;
+            ctx.units = units;
+            ctx.selected_reference = selected_reference;
+            ctx.axes = axes;
+            ctx.body_meshes = &self.body_meshes;
+            ctx.printer = printer;
+            ctx.view_proj = Some(view_proj);
             ctx.active_document_object = self.active_document_object;
 
             let result = wb.on_input(event, active_tool, &mut ctx);
+            self.selection = std::mem::take(&mut ctx.selection);
 
             // Sync active_document_object from context (workbench may have set it)
             if ctx.active_document_object != self.active_document_object {
@@ -1016,8 +2902,17 @@ impl PrintCadApp {
                     glam::Vec3::from_array(orient_req.plane_up),
                 );
             }
+            if let Some(focus) = ctx.camera_focus_request.take() {
+                self.camera
+                    .focus_on(glam::Vec3::from_array(focus.center), focus.radius);
+            }
+            let file_request = ctx.file_open_request.take();
 
             Self::flush_logs(ctx.drain_logs());
+            self.pending_commands.extend(ctx.drain_commands());
+            if let Some(request) = file_request {
+                self.start_workbench_file_dialog(request);
+            }
             result
         } else {
             core_document::InputResult::ignored()
@@ -1117,23 +3012,31 @@ impl PrintCadApp {
                 button: MouseButton::Left,
                 ..
             } => {
-                // Select the hovered body, or deselect if clicking empty space
-                if let Some(hovered) = self.hovered_body {
-                    if self.selected_body == Some(hovered) {
-                        // Clicking on already selected body - deselect
-                        self.selected_body = None;
-                        app_log::info("Deselected body");
-                    } else {
-                        // Select the new body
-                        self.selected_body = Some(hovered);
-                        app_log::info(format!("Selected body: {hovered:?}"));
+                // Ctrl/Shift-click toggles the hovered item in the selection;
+                // a plain click selects only it, or deselects everything when
+                // clicking empty space or the sole selected item.
+                let extend = self.modifiers.control_key() || self.modifiers.shift_key();
+                match self.hovered_item() {
+                    Some(item) if extend => {
+                        if self.selection.toggle(item) {
+                            app_log::info(format!("Added to selection: {item:?}"));
+                        } else {
+                            app_log::info(format!("Removed from selection: {item:?}"));
+                        }
                     }
-                } else {
-                    // Clicked on empty space - deselect
-                    if self.selected_body.is_some() {
-                        self.selected_body = None;
+                    Some(item) if self.selection.items() == [item] => {
+                        self.selection.clear();
+                        app_log::info("Deselected");
+                    }
+                    Some(item) => {
+                        self.selection.set(item);
+                        app_log::info(format!("Selected: {item:?}"));
+                    }
+                    None if !extend && !self.selection.is_empty() => {
+                        self.selection.clear();
                         app_log::info("Deselected (clicked empty space)");
                     }
+                    None => {}
                 }
                 true // Request redraw
             }
@@ -1142,6 +3045,123 @@ impl PrintCadApp {
     }
 }
 
+/// Item a picked submission id stands for: a body, or a sketch feature.
+fn pick_item(document: &DocumentSnapshot, id: Uuid) -> Option<SelectionItem> {
+    if document.has_body(BodyId(id)) {
+        Some(SelectionItem::Body(BodyId(id)))
+    } else if document.get_feature_meta(FeatureId(id)).is_some() {
+        Some(SelectionItem::Feature(FeatureId(id)))
+    } else {
+        None
+    }
+}
+
+/// Bounds of the geometry drawn for `items`: a body's mesh, or a feature's
+/// own result (falling back to its body's mesh).
+fn zoom_bounds(
+    document: &Document,
+    body_meshes: &[BodyMesh],
+    recompute: &recompute::Recompute,
+    mesh_cache: &wb_mesh::MeshCache,
+    items: &[SelectionItem],
+) -> Option<(glam::DVec3, glam::DVec3)> {
+    // Bounds are moved to world space in double precision.
+    let in_world = |(min, max): (glam::Vec3, glam::Vec3), body: Option<BodyId>| {
+        let position =
+            glam::DVec3::from_array(body.map_or([0.0; 3], |body| document.body_position(body)));
+        (position + min.as_dvec3(), position + max.as_dvec3())
+    };
+    let mesh_bounds = |mesh: &kernel_api::TriMesh| {
+        mesh.bounds()
+            .map(|(min, max)| (glam::Vec3::from(min), glam::Vec3::from(max)))
+    };
+    let body_bounds = |body: BodyId| {
+        let entry = body_meshes.iter().find(|entry| entry.body == body)?;
+        Some(in_world(mesh_bounds(&entry.mesh)?, Some(body)))
+    };
+    let item_bounds = |item: &SelectionItem| match *item {
+        SelectionItem::Body(body) | SelectionItem::Element { body, .. } => body_bounds(body),
+        SelectionItem::Feature(id) => {
+            let node = document.get_feature_meta(id)?;
+            let own = recompute
+                .feature_mesh(id)
+                .and_then(mesh_bounds)
+                .or_else(|| mesh_cache.feature_bounds(id));
+            own.map(|bounds| in_world(bounds, node.body))
+                .or_else(|| node.body.and_then(body_bounds))
+        }
+    };
+    items
+        .iter()
+        .filter_map(item_bounds)
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))
+}
+
+/// Memory held by each body's meshes: cached by the mesh workbench and drawn
+/// by the renderer (sketch geometry counts toward its body).
+fn mesh_memory_report(
+    document: &Document,
+    mesh_cache: &wb_mesh::MeshCache,
+    gpu: render_vk::MeshMemory,
+) -> ui::MeshMemoryReport {
+    let cpu = mesh_cache.memory(document);
+    let mut bodies: Vec<(String, usize, usize)> = document
+        .bodies()
+        .iter()
+        .map(|body| {
+            let cached = cpu
+                .iter()
+                .find(|(id, _)| *id == body.id)
+                .map_or(0, |(_, bytes)| *bytes);
+            let drawn = gpu
+                .bodies
+                .iter()
+                .filter(|(id, _)| {
+                    *id == body.id.0
+                        || document
+                            .get_feature_meta(FeatureId(*id))
+                            .is_some_and(|node| node.body == Some(body.id))
+                })
+                .map(|(_, bytes)| bytes)
+                .sum();
+            (body.name.clone(), cached, drawn)
+        })
+        .filter(|(_, cached, drawn)| cached + drawn > 0)
+        .collect();
+    bodies.sort_by_key(|(_, cached, drawn)| std::cmp::Reverse(cached + drawn));
+    ui::MeshMemoryReport {
+        cpu_total: cpu.iter().map(|(_, bytes)| bytes).sum(),
+        gpu_allocated: gpu.allocated,
+        bodies,
+    }
+}
+
+/// Highlight of the submission with `id`; geometry of a selected body counts
+/// as selected.
+fn highlight_state(
+    document: &DocumentSnapshot,
+    selection: &Selection,
+    hovered: Option<Uuid>,
+    id: Uuid,
+) -> HighlightState {
+    let selected = pick_item(document, id).is_some_and(|item| {
+        let body = match item {
+            SelectionItem::Feature(feature) => document
+                .get_feature_meta(feature)
+                .and_then(|node| node.body),
+            SelectionItem::Body(body) | SelectionItem::Element { body, .. } => Some(body),
+        };
+        selection.contains(item)
+            || body.is_some_and(|body| selection.contains(SelectionItem::Body(body)))
+    });
+    match (hovered == Some(id), selected) {
+        (true, true) => HighlightState::HoveredAndSelected,
+        (true, false) => HighlightState::Hovered,
+        (false, true) => HighlightState::Selected,
+        (false, false) => HighlightState::None,
+    }
+}
+
 fn lighting_data_from_settings(settings: &LightingSettings) -> LightingData {
     LightingData {
         main_light: GpuLight::new(
@@ -1166,3 +3186,71 @@ fn lighting_data_from_settings(settings: &LightingSettings) -> LightingData {
         ambient_intensity: settings.ambient_intensity,
     }
 }
+
+/// Apply a document's setting overrides on top of the user's preferences.
+fn apply_document_overrides(
+    settings: &UserSettings,
+    overrides: &core_document::DocumentOverrides,
+) -> UserSettings {
+    let mut effective = settings.clone();
+    if let Some(format) = overrides.units {
+        effective.units.display_unit = format.unit;
+        effective.units.decimal_places = format.decimals;
+        effective.units.inch_fractions = format.fraction_denominator.is_some();
+        if let Some(denominator) = format.fraction_denominator {
+            effective.units.fraction_denominator = denominator;
+        }
+    }
+    if let Some(preset) = overrides.axis_preset {
+        effective.camera.axis_preset = preset;
+        effective.camera.custom_axes = None;
+    }
+    if let Some(axes) = overrides.custom_axes {
+        effective.camera.custom_axes = Some(axes);
+    }
+    if let Some(spacing) = overrides.grid_spacing {
+        effective.snapping.grid_spacing = spacing;
+    }
+    effective
+}
+
+/// Build volume of the active printer profile, if any.
+fn print_volume_from_settings(settings: &PrinterSettings) -> Option<core_document::PrintVolume> {
+    let profile = settings.active_profile()?;
+    Some(core_document::PrintVolume {
+        size: [profile.bed_width, profile.bed_depth, profile.max_height],
+        shape: match profile.shape {
+            settings::BedShape::Rectangular => core_document::BedShape::Rectangular,
+            settings::BedShape::Circular => core_document::BedShape::Circular,
+        },
+        origin: match profile.origin {
+            settings::BedOrigin::FrontLeftCorner => core_document::BedOrigin::FrontLeftCorner,
+            settings::BedOrigin::Center => core_document::BedOrigin::Center,
+        },
+    })
+}
+
+fn snap_options_from_settings(settings: &SnappingSettings) -> core_document::SnapOptions {
+    let object_snaps = if settings.object_snap {
+        settings
+            .object_snap_priorities
+            .iter()
+            .map(|kind| match kind {
+                ObjectSnapKind::Endpoint => core_document::ObjectSnapKind::Endpoint,
+                ObjectSnapKind::Midpoint => core_document::ObjectSnapKind::Midpoint,
+                ObjectSnapKind::Center => core_document::ObjectSnapKind::Center,
+                ObjectSnapKind::Intersection => core_document::ObjectSnapKind::Intersection,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    core_document::SnapOptions {
+        grid_spacing: settings.grid_spacing,
+        show_grid: settings.show_grid,
+        grid_subdivisions: settings.grid_subdivisions,
+        snap_to_grid: settings.snap_to_grid,
+        object_snaps,
+        snap_radius_px: settings.snap_radius_px,
+    }
+}
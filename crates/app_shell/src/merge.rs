@@ -0,0 +1,125 @@
+//! Merging two copies of a document from the command line, without opening a
+//! window: `app_shell --merge OURS THEIRS [--output FILE] [--prefer ours|theirs]`.
+//!
+//! Changes made in only one copy are combined using the journals the copies
+//! share (see `core_document::journal`). Parts both copies changed are conflicts:
+//! without `--prefer` nothing is written and the conflicts are listed. The
+//! result goes to OURS unless `--output` is given, so the command can serve as a
+//! git merge driver (`printcad --merge %A %B`).
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use core_document::journal::{self, Side};
+use core_document::{Compression, Document};
+
+/// Command line flag starting a merge.
+pub const MERGE_FLAG: &str = "--merge";
+
+const USAGE: &str = "usage: printcad --merge OURS THEIRS [--output FILE] [--prefer ours|theirs]";
+
+/// Whether a merge was asked for on the command line.
+pub fn requested() -> bool {
+    std::env::args_os().skip(1).any(|arg| arg == MERGE_FLAG)
+}
+
+/// Merge the copies named on the command line and save the result.
+pub fn run() -> Result<()> {
+    let mut paths = Vec::new();
+    let mut output = None;
+    let mut prefer = None;
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some(MERGE_FLAG) => {}
+            Some("-o" | "--output") => output = Some(PathBuf::from(args.next().context(USAGE)?)),
+            Some("--prefer") => {
+                prefer = Some(match args.next().and_then(|side| side.into_string().ok()) {
+                    Some(side) if side == "ours" => Side::Ours,
+                    Some(side) if side == "theirs" => Side::Theirs,
+                    _ => bail!(USAGE),
+                });
+            }
+            Some("-h" | "--help") => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [ours_path, theirs_path] = <[PathBuf; 2]>::try_from(paths).ok().context(USAGE)?;
+    let ours = Document::load_from_file(&ours_path)
+        .with_context(|| format!("Failed to open {}", ours_path.display()))?;
+    let theirs = Document::load_from_file(&theirs_path)
+        .with_context(|| format!("Failed to open {}", theirs_path.display()))?;
+
+    let merge = journal::merge(&ours, &theirs, prefer.unwrap_or(Side::Ours))
+        .context("Cannot merge the documents")?;
+    if prefer.is_none() && !merge.conflicts.is_empty() {
+        for conflict in &merge.conflicts {
+            eprintln!("conflict: {} changed in both copies", conflict.label);
+        }
+        bail!(
+            "{} conflict(s); pass --prefer ours or --prefer theirs to resolve them",
+            merge.conflicts.len()
+        );
+    }
+    for conflict in &merge.conflicts {
+        let side = match conflict.resolution {
+            Side::Ours => "ours",
+            Side::Theirs => "theirs",
+        };
+        println!(
+            "conflict: {} changed in both copies, kept {side}",
+            conflict.label
+        );
+    }
+    for label in &merge.from_theirs {
+        println!("merged: {label}");
+    }
+
+    let mut document = merge.document;
+    document.record_journal(&journal::current_user());
+    let output = output.unwrap_or_else(|| ours_path.clone());
+    // The preview of our copy is kept; the app renders a new one when the
+    // merged document is saved from it.
+    let thumbnail = Document::read_thumbnail(&ours_path).ok().flatten();
+    document
+        .save_to_file_with_thumbnail(
+            &output,
+            compression(&output, &ours_path),
+            thumbnail.as_deref(),
+        )
+        .with_context(|| format!("Failed to save {}", output.display()))?;
+    println!(
+        "Merged {} change(s) from {} into {}",
+        merge.from_theirs.len(),
+        theirs_path.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Compression of the merged file: from its name, or else the one our copy
+/// was saved with (git hands merge drivers temporary files).
+fn compression(output: &Path, ours: &Path) -> Compression {
+    let name = output.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".gz") {
+        return Compression::Gzip;
+    }
+    if name.ends_with(".zst") {
+        return Compression::Zstd;
+    }
+    if name.ends_with(".prtcad") {
+        return Compression::None;
+    }
+    let mut magic = [0u8; 4];
+    let read = std::fs::File::open(ours).and_then(|mut file| file.read_exact(&mut magic));
+    match magic {
+        _ if read.is_err() => Compression::None,
+        [0x1f, 0x8b, ..] => Compression::Gzip,
+        [0x28, 0xb5, 0x2f, 0xfd] => Compression::Zstd,
+        _ => Compression::None,
+    }
+}
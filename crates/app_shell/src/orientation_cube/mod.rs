@@ -66,6 +66,8 @@ pub struct OrientationCubeResult {
     pub snap_to_view: Option<CameraSnapView>,
     /// If set, rotate camera by this amount (in degrees) around the specified axis
     pub rotate_delta: Option<RotateDelta>,
+    /// Drag across the cube this frame in physical pixels, orbiting the camera freely
+    pub orbit_delta: Option<[f32; 2]>,
 }
 
 /// Predefined camera snap views
@@ -105,7 +107,6 @@ pub enum CameraSnapView {
 impl CameraSnapView {
     /// Get the yaw and pitch angles (in degrees) for this view.
     /// Used by the turntable camera system.
-
     pub fn yaw_pitch(&self) -> (f32, f32) {
         match self {
             // Main faces
@@ -201,8 +202,21 @@ pub fn draw(
         .fixed_pos(pos)
         .order(egui::Order::Foreground)
         .show(ctx, |ui| {
-            let (response, painter) =
-                ui.allocate_painter(egui::Vec2::new(total_width, total_height), Sense::click());
+            let (response, painter) = ui.allocate_painter(
+                egui::Vec2::new(total_width, total_height),
+                Sense::click_and_drag(),
+            );
+
+            // Dragging anywhere on the widget orbits; a click (no drag) still snaps.
+            if response.dragged() {
+                let delta = response.drag_delta() * ctx.pixels_per_point();
+                if delta != egui::Vec2::ZERO {
+                    result.orbit_delta = Some([delta.x, delta.y]);
+                }
+                ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+            } else if response.hovered() {
+                ctx.set_cursor_icon(egui::CursorIcon::Grab);
+            }
 
             // Center the circle within the allocated space (offset down to make room for arc arrows)
             let local_center = Pos2::new(
@@ -1099,10 +1113,12 @@ fn face_texture_palette() -> [(&'static str, Color32); 6] {
 }
 
 fn rasterize_svg(svg: &str) -> Option<ColorImage> {
-    let mut opt = Options::default();
-    opt.font_family = "DejaVu Sans".into();
-    opt.languages = vec!["en".into()];
-    opt.font_size = 44.0;
+    let opt = Options {
+        font_family: "DejaVu Sans".into(),
+        languages: vec!["en".into()],
+        font_size: 44.0,
+        ..Options::default()
+    };
     let mut fontdb = fontdb::Database::new();
     fontdb.load_system_fonts();
     let tree = usvg::Tree::from_data(svg.as_bytes(), &opt, &fontdb).ok()?;
@@ -0,0 +1,237 @@
+//! Screen-space drawing of body origin planes, axes, and points, of datum
+//! features, and of the world origin triad and axis lines.
+
+use axes::AxisSystem;
+use core_document::{
+    DatumGeometry, Document, OriginElement, OriginRef, ScreenSpaceLabel, ScreenSpaceOverlay,
+    Selection, SelectionItem,
+};
+use glam::{DVec3, Mat4, Vec3};
+
+use crate::camera::CameraController;
+
+/// Origin geometry is sized relative to the camera distance so it stays readable at any zoom.
+const SIZE_FRACTION: f32 = 0.25;
+const THICKNESS: f32 = 1.5;
+const SELECTED_THICKNESS: f32 = 3.0;
+const SELECTED_COLOR: [f32; 3] = [1.0, 0.85, 0.2];
+const DATUM_COLOR: [f32; 3] = [0.9, 0.55, 0.25];
+/// Colors of the horizontal, vertical, and depth axes, as in the orientation cube.
+const AXIS_COLORS: [[f32; 3]; 3] = [[0.86, 0.31, 0.31], [0.31, 0.78, 0.31], [0.31, 0.47, 0.86]];
+const TRIAD_THICKNESS: f32 = 2.5;
+/// Axis lines reach this many camera distances each way from the origin.
+const AXIS_LINE_REACH: f64 = 1000.0;
+
+/// Overlays for every visible origin element, plus the selected one even if hidden.
+pub fn origin_overlays(
+    document: &Document,
+    camera: &CameraController,
+    selected: Option<OriginRef>,
+) -> Vec<ScreenSpaceOverlay> {
+    let size =
+        (Vec3::from(camera.position()) - Vec3::from(camera.target())).length() * SIZE_FRACTION;
+    let (origin_x, origin_y, _, _) = camera.viewport_info();
+    let project = |world: Vec3| {
+        camera
+            .world_to_screen(world)
+            .map(|(x, y)| [x - origin_x, y - origin_y])
+    };
+
+    let mut overlays = Vec::new();
+    for body in document.bodies() {
+        for element in OriginElement::ALL {
+            let is_selected = selected == Some(OriginRef::new(body.id, element));
+            if !is_selected && !body.origin.is_visible(element) {
+                continue;
+            }
+            let (color, thickness) = if is_selected {
+                (SELECTED_COLOR, SELECTED_THICKNESS)
+            } else {
+                (element_color(element), THICKNESS)
+            };
+            // Origin elements sit at the body's position.
+            let offset = camera.to_frame(body.position);
+            let mut line = |a: Vec3, b: Vec3| {
+                if let (Some(start), Some(end)) = (project(a + offset), project(b + offset)) {
+                    overlays.push(ScreenSpaceOverlay::new(start, end, color, thickness));
+                }
+            };
+
+            if let Some(direction) = element.axis_direction() {
+                line(Vec3::ZERO, Vec3::from(direction) * size);
+            } else if let Some(plane) = element.plane() {
+                let origin = Vec3::from(plane.origin);
+                let u = Vec3::from(plane.x_axis) * size * 0.5;
+                let v = Vec3::from(plane.y_axis) * size * 0.5;
+                let corners = [
+                    origin - u - v,
+                    origin + u - v,
+                    origin + u + v,
+                    origin - u + v,
+                ];
+                for i in 0..corners.len() {
+                    line(corners[i], corners[(i + 1) % corners.len()]);
+                }
+            } else {
+                let tick = size * 0.05;
+                line(Vec3::new(-tick, 0.0, 0.0), Vec3::new(tick, 0.0, 0.0));
+                line(Vec3::new(0.0, -tick, 0.0), Vec3::new(0.0, tick, 0.0));
+                line(Vec3::new(0.0, 0.0, -tick), Vec3::new(0.0, 0.0, tick));
+            }
+        }
+    }
+    overlays
+}
+
+/// Overlays for every visible datum plane, axis, and point: planes as squares
+/// around their origin, axes as segments through it, points as small crosses.
+pub fn datum_overlays(
+    document: &Document,
+    camera: &CameraController,
+    selection: &Selection,
+) -> Vec<ScreenSpaceOverlay> {
+    let size =
+        (Vec3::from(camera.position()) - Vec3::from(camera.target())).length() * SIZE_FRACTION;
+    let (origin_x, origin_y, _, _) = camera.viewport_info();
+    let project = |world: Vec3| {
+        camera
+            .world_to_screen(world)
+            .map(|(x, y)| [x - origin_x, y - origin_y])
+    };
+
+    let mut overlays = Vec::new();
+    for (id, node) in document.feature_tree().all_nodes() {
+        if !node.visible || node.suppressed {
+            continue;
+        }
+        let Some(datum) = document.datum_geometry(*id) else {
+            continue;
+        };
+        let (color, thickness) = if selection.contains(SelectionItem::Feature(*id)) {
+            (SELECTED_COLOR, SELECTED_THICKNESS)
+        } else {
+            (DATUM_COLOR, THICKNESS)
+        };
+        // Datum geometry is relative to its body's position.
+        let offset = camera.to_frame(document.feature_position(*id));
+        let mut line = |a: Vec3, b: Vec3| {
+            if let (Some(start), Some(end)) = (project(a + offset), project(b + offset)) {
+                overlays.push(ScreenSpaceOverlay::new(start, end, color, thickness));
+            }
+        };
+
+        match datum {
+            DatumGeometry::Plane(plane) => {
+                let origin = Vec3::from(plane.origin);
+                let u = Vec3::from(plane.x_axis) * size * 0.5;
+                let v = Vec3::from(plane.y_axis) * size * 0.5;
+                let corners = [
+                    origin - u - v,
+                    origin + u - v,
+                    origin + u + v,
+                    origin - u + v,
+                ];
+                for i in 0..corners.len() {
+                    line(corners[i], corners[(i + 1) % corners.len()]);
+                }
+            }
+            DatumGeometry::Axis(axis) => {
+                let origin = Vec3::from(axis.origin);
+                let half = Vec3::from(axis.direction) * size * 0.5;
+                line(origin - half, origin + half);
+            }
+            DatumGeometry::Point(point) => {
+                let point = Vec3::from(point);
+                let tick = size * 0.05;
+                line(point - Vec3::X * tick, point + Vec3::X * tick);
+                line(point - Vec3::Y * tick, point + Vec3::Y * tick);
+                line(point - Vec3::Z * tick, point + Vec3::Z * tick);
+            }
+        }
+    }
+    overlays
+}
+
+fn element_color(element: OriginElement) -> [f32; 3] {
+    match element {
+        OriginElement::XAxis => [0.9, 0.25, 0.25],
+        OriginElement::YAxis => [0.25, 0.8, 0.25],
+        OriginElement::ZAxis => [0.3, 0.45, 0.95],
+        OriginElement::Point => [0.9, 0.9, 0.9],
+        OriginElement::XyPlane | OriginElement::XzPlane | OriginElement::YzPlane => {
+            [0.55, 0.7, 0.85]
+        }
+    }
+}
+
+/// World origin triad (arrows with labels along the configured axes) and axis
+/// lines through the origin; each part is drawn only when enabled.
+pub fn world_axes_overlays(
+    camera: &CameraController,
+    axes: AxisSystem,
+    show_triad: bool,
+    show_lines: bool,
+) -> (Vec<ScreenSpaceOverlay>, Vec<ScreenSpaceLabel>) {
+    let mut overlays = Vec::new();
+    let mut labels = Vec::new();
+    let distance =
+        f64::from((Vec3::from(camera.position()) - Vec3::from(camera.target())).length());
+    let roles = [axes.horizontal(), axes.vertical(), axes.depth()];
+
+    if show_lines {
+        let reach = distance.max(1.0) * AXIS_LINE_REACH;
+        for (axis, color) in roles.iter().zip(AXIS_COLORS) {
+            let direction = axis.vector().as_dvec3() * reach;
+            if let Some((start, end)) = project_segment(camera, -direction, direction) {
+                let faded = color.map(|c| c * 0.7);
+                overlays.push(ScreenSpaceOverlay::new(start, end, faded, THICKNESS));
+            }
+        }
+    }
+
+    if show_triad {
+        let size = distance * f64::from(SIZE_FRACTION) * 0.6;
+        for (axis, color) in roles.iter().zip(AXIS_COLORS) {
+            let tip = axis.vector().as_dvec3() * size;
+            let Some((start, end)) = project_segment(camera, DVec3::ZERO, tip) else {
+                continue;
+            };
+            overlays.push(ScreenSpaceOverlay::new(start, end, color, TRIAD_THICKNESS));
+            labels.push(ScreenSpaceLabel {
+                position: [end[0] + 4.0, end[1] - 4.0],
+                text: axis.signed_label().to_string(),
+                color,
+                highlighted: false,
+            });
+        }
+    }
+    (overlays, labels)
+}
+
+/// Viewport-local pixel coordinates of the segment `a`..`b`, clipped to the
+/// part in front of the camera (so lines passing behind it still show).
+fn project_segment(camera: &CameraController, a: DVec3, b: DVec3) -> Option<([f32; 2], [f32; 2])> {
+    // Clip at the camera's near plane distance.
+    const MIN_W: f32 = 0.05;
+    let eye = DVec3::from_array(camera.render_origin());
+    let view_proj = Mat4::from_cols_array_2d(&camera.relative_view_projection());
+    let clip = |world: DVec3| view_proj * (world - eye).as_vec3().extend(1.0);
+    let (mut a, mut b) = (clip(a), clip(b));
+    if a.w < MIN_W && b.w < MIN_W {
+        return None;
+    }
+    if a.w < MIN_W {
+        a = a.lerp(b, (MIN_W - a.w) / (b.w - a.w));
+    } else if b.w < MIN_W {
+        b = b.lerp(a, (MIN_W - b.w) / (a.w - b.w));
+    }
+    let (_, _, width, height) = camera.viewport_info();
+    let to_pixels = |clip: glam::Vec4| {
+        let ndc = clip.truncate() / clip.w;
+        [
+            (ndc.x + 1.0) * 0.5 * width as f32,
+            (ndc.y + 1.0) * 0.5 * height as f32,
+        ]
+    };
+    Some((to_pixels(a), to_pixels(b)))
+}
@@ -0,0 +1,99 @@
+//! Recently opened or saved documents, shown with the previews saved in them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use egui::ColorImage;
+use settings::SettingsStore;
+
+use crate::{app_log, thumbnail};
+
+/// The recent documents list, with previews read on a background thread.
+#[derive(Default)]
+pub struct RecentDocuments {
+    paths: Vec<PathBuf>,
+    /// Loaded previews (None when the file has none).
+    thumbnails: HashMap<PathBuf, Option<ColorImage>>,
+    load_rx: Option<Receiver<(PathBuf, Option<ColorImage>)>>,
+}
+
+impl RecentDocuments {
+    pub fn load(store: &SettingsStore) -> Self {
+        let paths = store.recent_documents().unwrap_or_else(|err| {
+            app_log::warn(format!("Failed to read recent documents: {err}"));
+            Vec::new()
+        });
+        let mut recent = Self {
+            paths,
+            ..Self::default()
+        };
+        recent.load_thumbnails();
+        recent
+    }
+
+    /// Newest first.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Preview of a document, once loaded.
+    pub fn thumbnail(&self, path: &Path) -> Option<&ColorImage> {
+        self.thumbnails.get(path)?.as_ref()
+    }
+
+    /// Record that `path` was opened or saved, reloading its preview.
+    pub fn add(&mut self, store: &SettingsStore, path: &Path) {
+        match store.add_recent_document(path) {
+            Ok(paths) => self.paths = paths,
+            Err(err) => app_log::warn(format!("Failed to update recent documents: {err}")),
+        }
+        self.thumbnails.remove(path);
+        self.load_thumbnails();
+    }
+
+    /// Collect loaded previews. Returns true when something changed.
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.load_rx else {
+            return false;
+        };
+        let mut changed = false;
+        loop {
+            match rx.try_recv() {
+                Ok((path, image)) => {
+                    self.thumbnails.insert(path, image);
+                    changed = true;
+                }
+                Err(mpsc::TryRecvError::Empty) => return changed,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.load_rx = None;
+                    return changed;
+                }
+            }
+        }
+    }
+
+    /// Read the previews that are not loaded yet. A load still running is
+    /// abandoned; its remaining documents are part of the new one.
+    fn load_thumbnails(&mut self) {
+        let missing: Vec<PathBuf> = self
+            .paths
+            .iter()
+            .filter(|path| !self.thumbnails.contains_key(*path))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        self.load_rx = Some(rx);
+        std::thread::spawn(move || {
+            for path in missing {
+                let image = thumbnail::document_preview(&path);
+                if tx.send((path, image)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
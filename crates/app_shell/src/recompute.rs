@@ -0,0 +1,424 @@
+//! Feature recompute: dirty features are regenerated in dependency order by
+//! the workbench they belong to, building solids through the geometry
+//! kernel, and the resulting meshes are kept per feature and per body until
+//! one of their features changes. A feature that fails keeps no geometry and
+//! shows the error in the feature tree. A feature that changes an earlier
+//! one's solid (a pocket, say) takes its place in the body once it has a
+//! mesh.
+//!
+//! The kernel work runs as a document job ("Recompute" in the status bar), so
+//! the window stays responsive and a long rebuild can be cancelled. Features
+//! keep their previous meshes, and stay dirty, until the job has finished; a
+//! job whose features are edited meanwhile is dropped and started again.
+//! Edits are noticed from the revision of the feature nodes.
+//!
+//! Mesh features are left to [`wb_mesh::MeshCache`], which evaluates their
+//! history itself (their workbench doesn't regenerate them here).
+//!
+//! Meshes saved with the document are used for features that haven't changed
+//! since, so an opened document is drawn without regenerating everything.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use core_document::{BodyId, Document, DocumentService, FeatureId, JobId, JobQueue};
+use kernel_api::{Diagnostic, Kernel, KernelError, KernelResult, RebuildRequest, TriMesh};
+
+use crate::app_log;
+
+struct FeatureEntry {
+    /// Revision of the feature node when it was last looked at.
+    revision: u64,
+    /// What the mesh was regenerated from, to notice edits.
+    data: serde_json::Value,
+    body: Option<BodyId>,
+    suppressed: bool,
+    /// None when the feature is suppressed or failed.
+    mesh: Option<Arc<TriMesh>>,
+}
+
+struct BodyEntry {
+    /// Features whose meshes make up the body, sorted.
+    features: Vec<FeatureId>,
+    mesh: TriMesh,
+}
+
+/// A feature handed to the rebuild job, as it was when the job started.
+struct Submitted {
+    id: FeatureId,
+    revision: u64,
+    data: serde_json::Value,
+    body: Option<BodyId>,
+    suppressed: bool,
+}
+
+/// The rebuild job in progress, with the features it regenerates in order.
+struct RebuildJob {
+    jobs: JobQueue,
+    id: JobId,
+    features: Vec<Submitted>,
+}
+
+/// What the rebuild job hands back.
+struct Rebuilt {
+    /// Why the kernel could not rebuild (the features are regenerated anyway).
+    error: Option<KernelError>,
+    diagnostics: Vec<Diagnostic>,
+    /// Outcome of each submitted feature, in order; None when it is
+    /// suppressed.
+    meshes: Vec<Option<KernelResult<TriMesh>>>,
+}
+
+/// Regenerated geometry of the document's features and bodies.
+pub struct Recompute {
+    /// Shared with the rebuild job while it runs.
+    kernel: Arc<Mutex<Box<dyn Kernel>>>,
+    features: HashMap<FeatureId, FeatureEntry>,
+    /// Features whose workbench gave no geometry to keep.
+    passive: HashSet<FeatureId>,
+    /// Features whose place in their body a regenerated feature took.
+    replaced: HashSet<FeatureId>,
+    bodies: HashMap<BodyId, BodyEntry>,
+    job: Option<RebuildJob>,
+    /// Features (with their revisions) of the last job that was cancelled or
+    /// failed: they are not tried again until one of them changes.
+    stopped: Vec<(FeatureId, u64)>,
+}
+
+impl Recompute {
+    pub fn new(mut kernel: Box<dyn Kernel>) -> Self {
+        if let Err(err) = kernel.initialize() {
+            app_log::error(format!(
+                "Failed to initialize the {} kernel: {err}",
+                kernel.name()
+            ));
+        }
+        Self {
+            kernel: Arc::new(Mutex::new(kernel)),
+            features: HashMap::new(),
+            passive: HashSet::new(),
+            replaced: HashSet::new(),
+            bodies: HashMap::new(),
+            job: None,
+            stopped: Vec::new(),
+        }
+    }
+
+    /// Collect the finished rebuild job, clearing the dirty flag of the
+    /// features it regenerated and updating their status, and start one for
+    /// the features that changed since.
+    pub fn run(&mut self, document: &mut Document, registry: &DocumentService) {
+        self.features
+            .retain(|id, _| document.get_feature_meta(*id).is_some());
+        self.passive
+            .retain(|id| document.get_feature_meta(*id).is_some());
+
+        self.mark_edited(document);
+        let unsaved = self.adopt_saved(document);
+        let recomputed = self.collect(document);
+        if self.job.is_none() {
+            self.start(document, registry, unsaved);
+        }
+        self.update_replaced(document, registry);
+        self.update_bodies(&recomputed);
+    }
+
+    /// Ask the workbenches which features the ones with a mesh replace.
+    fn update_replaced(&mut self, document: &Document, registry: &DocumentService) {
+        self.replaced = self
+            .features
+            .iter()
+            .filter(|(_, entry)| entry.mesh.is_some())
+            .filter_map(|(id, _)| document.get_feature_meta(*id))
+            .flat_map(|node| {
+                registry
+                    .workbench(&node.workbench_id)
+                    .map(|workbench| workbench.replaced_features(node))
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+
+    /// Mark features edited without being marked dirty (or restored by undo)
+    /// dirty. Only the nodes written since they were last looked at are
+    /// compared.
+    fn mark_edited(&mut self, document: &mut Document) {
+        let mut edited = Vec::new();
+        for (id, node) in document.feature_tree().all_nodes() {
+            let Some(entry) = self.features.get_mut(id) else {
+                continue;
+            };
+            if entry.revision == node.revision() {
+                continue;
+            }
+            entry.revision = node.revision();
+            if entry.data != node.data
+                || entry.body != node.body
+                || entry.suppressed != node.suppressed
+            {
+                edited.push(*id);
+            }
+        }
+        for id in edited {
+            document.feature_tree_mut().mark_dirty(id);
+        }
+    }
+
+    /// Use the mesh saved with the document for features seen for the first
+    /// time, while it is current. Returns the features without one, which are
+    /// regenerated.
+    fn adopt_saved(&mut self, document: &Document) -> Vec<FeatureId> {
+        let mut unsaved = Vec::new();
+        for (id, node) in document.feature_tree().all_nodes() {
+            if self.features.contains_key(id) || self.passive.contains(id) {
+                continue;
+            }
+            let saved = (!node.dirty)
+                .then(|| document.cached_mesh(id.0, document.content_hash(*id)))
+                .flatten();
+            match saved {
+                Some(mesh) => {
+                    self.features.insert(
+                        *id,
+                        FeatureEntry {
+                            revision: node.revision(),
+                            data: node.data.clone(),
+                            body: node.body,
+                            suppressed: node.suppressed,
+                            mesh: Some(mesh.clone()),
+                        },
+                    );
+                }
+                None => unsaved.push(*id),
+            }
+        }
+        unsaved
+    }
+
+    /// Apply the results of the rebuild job once it has finished, and drop it
+    /// if its features were edited meanwhile. Returns the features it
+    /// regenerated.
+    fn collect(&mut self, document: &mut Document) -> Vec<FeatureId> {
+        let Some(job) = self.job.take() else {
+            return Vec::new();
+        };
+        let edited = job.features.iter().any(|feature| {
+            document
+                .get_feature_meta(feature.id)
+                .map_or(true, |node| node.revision() != feature.revision)
+        });
+        if edited {
+            // Its results would be stale; a new job starts with the edits.
+            job.jobs.cancel(job.id);
+            return Vec::new();
+        }
+        if job.jobs.is_running(job.id) {
+            self.job = Some(job);
+            return Vec::new();
+        }
+
+        let rebuilt = match job.jobs.take::<Rebuilt>(job.id) {
+            Some(Ok(rebuilt)) => rebuilt,
+            failed => {
+                match failed {
+                    Some(Err(err)) => app_log::error(format!("Recompute failed: {err}")),
+                    _ => app_log::warn("Recompute cancelled; features keep their last geometry"),
+                }
+                self.stopped = job
+                    .features
+                    .iter()
+                    .map(|feature| (feature.id, feature.revision))
+                    .collect();
+                return Vec::new();
+            }
+        };
+        if let Some(err) = rebuilt.error {
+            app_log::error(format!("Kernel rebuild failed: {err}"));
+        }
+        let mut diagnostics = rebuilt.diagnostics;
+        let mut recomputed = Vec::new();
+        for (feature, result) in job.features.into_iter().zip(rebuilt.meshes) {
+            let mesh = match result {
+                None => None,
+                Some(Ok(mesh)) => Some(Arc::new(mesh)),
+                Some(Err(err)) => {
+                    diagnostics.push(Diagnostic::error(feature.id.0.to_string(), err.to_string()));
+                    None
+                }
+            };
+            self.features.insert(
+                feature.id,
+                FeatureEntry {
+                    revision: feature.revision,
+                    data: feature.data,
+                    body: feature.body,
+                    suppressed: feature.suppressed,
+                    mesh,
+                },
+            );
+            recomputed.push(feature.id);
+        }
+        for id in &recomputed {
+            if let Some(node) = document.feature_tree_mut().get_node_mut(*id) {
+                node.dirty = false;
+            }
+        }
+        for diagnostic in document.apply_diagnostics(&recomputed, &diagnostics) {
+            tracing::debug!("{}", diagnostic.message);
+        }
+        // Clearing the flags and statuses wrote the nodes.
+        for id in &recomputed {
+            if let (Some(entry), Some(node)) =
+                (self.features.get_mut(id), document.get_feature_meta(*id))
+            {
+                entry.revision = node.revision();
+            }
+        }
+        recomputed
+    }
+
+    /// Start a rebuild job for the dirty features, their dependents, and
+    /// `unsaved` (new features without a saved mesh).
+    fn start(&mut self, document: &Document, registry: &DocumentService, unsaved: Vec<FeatureId>) {
+        let mut pending = document.recompute_order();
+        for id in unsaved {
+            if !pending.contains(&id) {
+                pending.push(id);
+            }
+        }
+        let order: Vec<FeatureId> = document
+            .feature_tree()
+            .recompute_order(&pending)
+            .into_iter()
+            .filter(|id| !self.passive.contains(id))
+            .collect();
+        let revisions: Vec<(FeatureId, u64)> = order
+            .iter()
+            .filter_map(|id| Some((*id, document.get_feature_meta(*id)?.revision())))
+            .collect();
+        if revisions.is_empty() || revisions == self.stopped {
+            return;
+        }
+        self.stopped.clear();
+
+        let mut features = Vec::new();
+        let mut builds = Vec::new();
+        for id in order {
+            let Some(node) = document.get_feature_meta(id) else {
+                continue;
+            };
+            let build = registry
+                .workbench(&node.workbench_id)
+                .ok()
+                .and_then(|workbench| workbench.regenerate_feature(node));
+            let Some(build) = build else {
+                self.passive.insert(id);
+                continue;
+            };
+            builds.push((!node.suppressed).then_some(build));
+            features.push(Submitted {
+                id,
+                revision: node.revision(),
+                data: node.data.clone(),
+                body: node.body,
+                suppressed: node.suppressed,
+            });
+        }
+        if features.is_empty() {
+            return;
+        }
+
+        let request = RebuildRequest {
+            dirty_features: features.iter().map(|f| f.id.0.to_string()).collect(),
+            propagate: false,
+        };
+        let kernel = Arc::clone(&self.kernel);
+        let jobs = document.jobs().clone();
+        let id = jobs.spawn_with_progress("Recompute", move |progress| {
+            let mut kernel = kernel.lock().unwrap_or_else(PoisonError::into_inner);
+            let (error, diagnostics) = match kernel.rebuild(&request, progress.cancellation()) {
+                Ok(response) => (None, response.diagnostics),
+                Err(err) => (Some(err), Vec::new()),
+            };
+            let total = builds.len();
+            let mut meshes = Vec::with_capacity(total);
+            for (done, build) in builds.into_iter().enumerate() {
+                if progress.is_cancelled() {
+                    break;
+                }
+                meshes.push(build.map(|build| build(kernel.as_mut())));
+                progress.set((done + 1) as f32 / total as f32);
+            }
+            Rebuilt {
+                error,
+                diagnostics,
+                meshes,
+            }
+        });
+        self.job = Some(RebuildJob { jobs, id, features });
+    }
+
+    /// Rebuild the meshes of bodies that gained, lost, or recomputed a feature.
+    fn update_bodies(&mut self, recomputed: &[FeatureId]) {
+        let mut members: HashMap<BodyId, Vec<FeatureId>> = HashMap::new();
+        for (id, entry) in &self.features {
+            if self.replaced.contains(id) {
+                continue;
+            }
+            if let (Some(body), Some(_)) = (entry.body, &entry.mesh) {
+                members.entry(body).or_default().push(*id);
+            }
+        }
+        self.bodies.retain(|body, _| members.contains_key(body));
+        for (body, mut features) in members {
+            features.sort_by_key(|id| id.0);
+            let changed = self.bodies.get(&body).map_or(true, |entry| {
+                entry.features != features || features.iter().any(|id| recomputed.contains(id))
+            });
+            if !changed {
+                continue;
+            }
+            let mut mesh = TriMesh::default();
+            for id in &features {
+                if let Some(feature_mesh) = &self.features[id].mesh {
+                    mesh.append(feature_mesh);
+                }
+            }
+            self.bodies.insert(body, BodyEntry { features, mesh });
+        }
+    }
+
+    /// Mesh of every regenerated feature that another hasn't replaced, with
+    /// the body it belongs to.
+    pub fn feature_meshes(&self) -> impl Iterator<Item = (FeatureId, Option<BodyId>, &TriMesh)> {
+        self.features
+            .iter()
+            .filter(|(id, _)| !self.replaced.contains(id))
+            .filter_map(|(id, entry)| Some((*id, entry.body, entry.mesh.as_deref()?)))
+    }
+
+    /// Mesh of a regenerated feature, None until it has been regenerated.
+    pub fn feature_mesh(&self, id: FeatureId) -> Option<&TriMesh> {
+        self.features.get(&id)?.mesh.as_deref()
+    }
+
+    /// Store the regenerated meshes in `document`, to be saved with it and
+    /// shown right away when the document is opened again.
+    pub fn persist(&self, document: &mut Document) {
+        for (id, entry) in &self.features {
+            let current = document
+                .get_feature_meta(*id)
+                .is_some_and(|node| !node.dirty && node.data == entry.data);
+            if let (true, Some(mesh)) = (current, &entry.mesh) {
+                let hash = document.content_hash(*id);
+                document.set_cached_mesh(id.0, hash, mesh.clone());
+            }
+        }
+    }
+
+    /// Geometry of every body with regenerated features: the union of their
+    /// meshes.
+    pub fn body_meshes(&self) -> impl Iterator<Item = (BodyId, &TriMesh)> {
+        self.bodies.iter().map(|(body, entry)| (*body, &entry.mesh))
+    }
+}
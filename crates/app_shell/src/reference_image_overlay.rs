@@ -0,0 +1,108 @@
+//! Screen-space drawing of reference images. The renderer has no textured
+//! pipeline, so each image is projected as a grid of egui vertices (fine
+//! enough to hide the affine texture mapping) and drawn under the overlays.
+
+use std::collections::HashMap;
+
+use core_document::Document;
+use uuid::Uuid;
+
+use crate::camera::CameraController;
+use crate::log_panel as app_log;
+
+/// Quads per side of the projected grid.
+pub const GRID: usize = 16;
+
+/// A reference image projected to the viewport.
+pub struct ReferenceImageOverlay {
+    /// Asset holding the image file.
+    pub asset: Uuid,
+    pub opacity: f32,
+    /// `(GRID + 1)²` pixel positions relative to the viewport origin, row by
+    /// row from the top-left corner of the image.
+    pub points: Vec<[f32; 2]>,
+}
+
+/// Visible reference images, projected with the current camera. Images
+/// crossing the camera plane are skipped.
+pub fn reference_image_overlays(
+    document: &Document,
+    camera: &CameraController,
+) -> Vec<ReferenceImageOverlay> {
+    let (origin_x, origin_y, _, _) = camera.viewport_info();
+    let mut overlays = Vec::new();
+    for (id, image) in wb_sketch::reference_images(document) {
+        let shown = document
+            .get_feature_meta(id)
+            .is_some_and(|node| node.visible && !node.suppressed);
+        if !shown || image.opacity <= 0.0 {
+            continue;
+        }
+        // Corners are relative to the image's body position.
+        let position = document.feature_position(id);
+        let [top_left, top_right, _, bottom_left] = image
+            .corners()
+            .map(|corner| camera.to_frame(core_document::from_frame(corner, position)));
+        let (across, down) = (top_right - top_left, bottom_left - top_left);
+        let points: Option<Vec<[f32; 2]>> = (0..=GRID)
+            .flat_map(|row| (0..=GRID).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let world = top_left
+                    + across * (column as f32 / GRID as f32)
+                    + down * (row as f32 / GRID as f32);
+                camera
+                    .world_to_screen(world)
+                    .map(|(x, y)| [x - origin_x, y - origin_y])
+            })
+            .collect();
+        if let Some(points) = points {
+            overlays.push(ReferenceImageOverlay {
+                asset: image.asset,
+                opacity: image.opacity.min(1.0),
+                points,
+            });
+        }
+    }
+    overlays
+}
+
+/// GPU textures of the reference images, decoded from the document's assets
+/// the first time they are drawn.
+#[derive(Default)]
+pub struct ReferenceImageTextures {
+    /// `None` marks an asset that failed to decode, so it is not retried.
+    textures: HashMap<Uuid, Option<egui::TextureHandle>>,
+}
+
+impl ReferenceImageTextures {
+    pub fn texture(
+        &mut self,
+        ctx: &egui::Context,
+        document: &Document,
+        asset: Uuid,
+    ) -> Option<egui::TextureId> {
+        self.textures
+            .entry(asset)
+            .or_insert_with(|| {
+                let data = document.asset_data(asset)?;
+                match image::load_from_memory(data.bytes()) {
+                    Ok(decoded) => {
+                        let rgba = decoded.to_rgba8();
+                        let size = [rgba.width() as usize, rgba.height() as usize];
+                        let image = egui::ColorImage::from_rgba_unmultiplied(size, &rgba);
+                        Some(ctx.load_texture(
+                            format!("reference_image_{asset}"),
+                            image,
+                            egui::TextureOptions::LINEAR,
+                        ))
+                    }
+                    Err(err) => {
+                        app_log::warn(format!("Failed to decode reference image: {err}"));
+                        None
+                    }
+                }
+            })
+            .as_ref()
+            .map(egui::TextureHandle::id)
+    }
+}
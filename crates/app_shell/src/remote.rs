@@ -0,0 +1,571 @@
+//! Local HTTP API for driving printCAD from scripts, print farms, and other
+//! tools (the `remote-api` feature). Started with `--remote-api[=PORT]`, it
+//! listens on the loopback interface only.
+//!
+//! Every request presents a token, as `Authorization: Bearer <token>` or a
+//! `token=` query parameter. The token is `PRINTCAD_REMOTE_TOKEN` if set, or
+//! else a random one written with the port to `printcad-remote.json` in the
+//! user's runtime directory (the local data directory where there is none),
+//! readable by the owner only. The API is not started without one of these.
+//!
+//! `GET /status` and `GET /screenshot?size=N` (a PNG), and `POST /open`,
+//! `/save`, `/recompute`, and `/export` with a JSON body such as
+//! `{"path": "…"}`, answer with JSON. `GET /ws` upgrades to a WebSocket
+//! taking the same operations as JSON text messages, e.g.
+//! `{"op": "export", "path": "…", "id": 1}`; each answer echoes the `id`.
+//!
+//! Up to [`MAX_CONNECTIONS`] connections are served at once, each on its own
+//! thread; request lines and headers are capped, and clients that stall are
+//! dropped. The operations run on the app's thread between frames, which
+//! answers through a channel. Exports and screenshots wait until the document
+//! has finished recomputing.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use base64::Engine as _;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use subtle::ConstantTimeEq;
+
+use settings::SettingsStore;
+
+use crate::app_log;
+
+const FLAG: &str = "--remote-api";
+const DEFAULT_PORT: u16 = 7878;
+const TOKEN_VARIABLE: &str = "PRINTCAD_REMOTE_TOKEN";
+/// Longest a connection waits for an answer; exports of large documents
+/// wait for their recompute.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(600);
+/// Largest request body or WebSocket message accepted.
+const MAX_BODY: usize = 1 << 20;
+/// Longest request line or header line accepted, in bytes.
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// Connections served at once; more are answered 503 and closed.
+const MAX_CONNECTIONS: usize = 8;
+/// Longest a client may take to send its request or read an answer.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest an open WebSocket may go without a message.
+const WEBSOCKET_IDLE: Duration = Duration::from_secs(600);
+/// Frames in a row built with nothing left to recompute or load before
+/// waiting requests are answered; a job finishing during one frame is only
+/// picked up in the next.
+const SETTLED_FRAMES: u32 = 2;
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Port asked for on the command line, if the API was.
+pub fn requested() -> Option<u16> {
+    std::env::args_os().skip(1).find_map(|arg| {
+        let arg = arg.to_str()?;
+        if arg == FLAG {
+            return Some(DEFAULT_PORT);
+        }
+        let port = arg.strip_prefix(FLAG)?.strip_prefix('=')?;
+        port.parse()
+            .map_err(|_| app_log::warn(format!("Remote API not started: bad port `{port}`")))
+            .ok()
+    })
+}
+
+/// Something a client asked the app to do.
+#[derive(Debug)]
+pub enum Operation {
+    Status,
+    Open(PathBuf),
+    /// Save to the path, or to the document's file.
+    Save(Option<PathBuf>),
+    Recompute,
+    /// Write every body to one file, in the format of its extension.
+    Export(PathBuf),
+    /// PNG of the bodies, `size` pixels square.
+    Screenshot {
+        size: u32,
+    },
+}
+
+impl Operation {
+    fn parse(name: &str, args: &Value) -> Result<Operation, String> {
+        let path = |required: bool| match args.get("path") {
+            Some(Value::String(path)) if !path.is_empty() => Ok(Some(PathBuf::from(path))),
+            None | Some(Value::Null) if !required => Ok(None),
+            _ => Err(format!("`{name}` needs a \"path\" string")),
+        };
+        Ok(match name {
+            "status" => Operation::Status,
+            "open" => Operation::Open(path(true)?.unwrap_or_default()),
+            "save" => Operation::Save(path(false)?),
+            "recompute" => Operation::Recompute,
+            "export" => Operation::Export(path(true)?.unwrap_or_default()),
+            "screenshot" => {
+                let size = match args.get("size") {
+                    None | Some(Value::Null) => 512,
+                    Some(Value::Number(size)) => size.as_u64().unwrap_or(0),
+                    Some(Value::String(size)) => size.parse().unwrap_or(0),
+                    Some(_) => 0,
+                };
+                if !(16..=4096).contains(&size) {
+                    return Err("\"size\" must be 16 to 4096 pixels".to_string());
+                }
+                Operation::Screenshot { size: size as u32 }
+            }
+            _ => return Err(format!("unknown operation `{name}`")),
+        })
+    }
+
+    /// Whether the operation reads geometry, and so waits for the document
+    /// to finish recomputing.
+    fn reads_geometry(&self) -> bool {
+        matches!(self, Operation::Export(_) | Operation::Screenshot { .. })
+    }
+}
+
+/// The app's answer to an operation.
+pub enum Reply {
+    Json(Value),
+    Png(Vec<u8>),
+    Error(String),
+}
+
+/// An operation with the connection waiting for its answer.
+pub struct Request {
+    pub operation: Operation,
+    reply: Sender<Reply>,
+}
+
+impl Request {
+    pub fn answer(self, reply: Reply) {
+        // The client may have given up waiting.
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// The running server, as seen from the app.
+pub struct RemoteApi {
+    requests: Receiver<Request>,
+    /// Requests waiting for the document to finish recomputing.
+    waiting: Vec<Request>,
+    settled_frames: u32,
+}
+
+impl RemoteApi {
+    /// Listen on `port` of the loopback interface.
+    pub fn start(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("cannot listen on port {port}"))?;
+        let token = match std::env::var(TOKEN_VARIABLE) {
+            Ok(token) if !token.is_empty() => token,
+            _ => {
+                let token = uuid::Uuid::new_v4().simple().to_string();
+                let path = announce(port, &token)?;
+                app_log::info(format!("Remote API token written to {}", path.display()));
+                token
+            }
+        };
+        let (tx, rx) = mpsc::channel();
+        let active = Arc::new(AtomicUsize::new(0));
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(std::result::Result::ok) {
+                if stream.set_read_timeout(Some(IO_TIMEOUT)).is_err()
+                    || stream.set_write_timeout(Some(IO_TIMEOUT)).is_err()
+                {
+                    continue;
+                }
+                let Some(slot) = Slot::take(&active) else {
+                    let _ = respond_error(&mut stream, 503, "too many connections");
+                    continue;
+                };
+                let (tx, token) = (tx.clone(), token.clone());
+                std::thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(err) = serve(stream, &token, &tx) {
+                        tracing::debug!("remote API connection closed: {err:#}");
+                    }
+                });
+            }
+        });
+        app_log::info(format!("Remote API listening on http://127.0.0.1:{port}"));
+        Ok(Self {
+            requests: rx,
+            waiting: Vec::new(),
+            settled_frames: 0,
+        })
+    }
+
+    /// Record whether the frame just built had nothing left to recompute or
+    /// load.
+    pub fn frame_built(&mut self, settled: bool) {
+        self.settled_frames = if settled {
+            self.settled_frames.saturating_add(1)
+        } else {
+            0
+        };
+    }
+
+    /// Wait for the document to finish recomputing again, after a change.
+    pub fn unsettle(&mut self) {
+        self.settled_frames = 0;
+    }
+
+    /// Answer `request` once the document has finished recomputing.
+    pub fn wait(&mut self, request: Request) {
+        self.waiting.push(request);
+    }
+
+    /// Requests to run now: new ones that don't read geometry, and the
+    /// waiting ones once the document has finished recomputing.
+    pub fn ready(&mut self) -> Vec<Request> {
+        let mut ready = Vec::new();
+        for request in self.requests.try_iter() {
+            if request.operation.reads_geometry() {
+                self.waiting.push(request);
+            } else {
+                ready.push(request);
+            }
+        }
+        if self.settled_frames >= SETTLED_FRAMES {
+            ready.append(&mut self.waiting);
+        }
+        ready
+    }
+}
+
+/// One of the [`MAX_CONNECTIONS`] connections served at once, given back on
+/// drop.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(active: &Arc<AtomicUsize>) -> Option<Slot> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Write the port and token where local clients find them, in a directory
+/// only the user can open. A file left there by an earlier run is replaced by
+/// a new one rather than rewritten, so its permissions are always ours.
+fn announce(port: u16, token: &str) -> Result<PathBuf> {
+    let dir = SettingsStore::runtime_dir().context("no private directory for the token")?;
+    let path = dir.join("printcad-remote.json");
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Err(err).with_context(|| format!("cannot replace {}", path.display()));
+        }
+        _ => {}
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("cannot write {}", path.display()))?;
+    serde_json::to_writer(&mut file, &json!({ "port": port, "token": token }))?;
+    Ok(path)
+}
+
+/// Hand `operation` to the app and wait for its answer.
+fn call(tx: &Sender<Request>, operation: Operation) -> Reply {
+    let (reply, answer) = mpsc::channel();
+    if tx.send(Request { operation, reply }).is_err() {
+        return Reply::Error("printCAD is closing".to_string());
+    }
+    answer
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| Reply::Error("printCAD did not answer in time".to_string()))
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    /// Header names in lower case.
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn read(reader: &mut impl BufRead) -> Result<HttpRequest> {
+        let mut line = String::new();
+        read_line(reader, &mut line)?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            bail!("malformed request line");
+        };
+        let method = method.to_string();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = path.to_string();
+        let query = form_urlencoded::parse(query.as_bytes())
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        let mut headers = HashMap::new();
+        for count in 0.. {
+            if read_line(reader, &mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if count == MAX_HEADERS {
+                bail!("more than {MAX_HEADERS} headers");
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let length: usize = headers
+            .get("content-length")
+            .map_or(Ok(0), |length| length.parse())
+            .context("bad Content-Length")?;
+        if length > MAX_BODY {
+            bail!("request body too large");
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        Ok(HttpRequest {
+            method,
+            path,
+            query,
+            headers,
+            body,
+        })
+    }
+
+    fn authorized(&self, token: &str) -> bool {
+        let bearer = self
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        // Compared in constant time so response timing doesn't reveal how much
+        // of a guessed token was right.
+        bearer
+            .or(self.query.get("token").map(String::as_str))
+            .is_some_and(|presented| bool::from(presented.as_bytes().ct_eq(token.as_bytes())))
+    }
+}
+
+/// Read one line into `line`, refusing lines longer than [`MAX_LINE`].
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize> {
+    line.clear();
+    let read = reader.take(MAX_LINE + 1).read_line(line)?;
+    if read as u64 > MAX_LINE {
+        bail!("request line or header longer than {MAX_LINE} bytes");
+    }
+    Ok(read)
+}
+
+fn serve(stream: TcpStream, token: &str, tx: &Sender<Request>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let request = match HttpRequest::read(&mut reader) {
+        Ok(request) => request,
+        Err(err) => return respond_error(&mut stream, 400, &format!("{err:#}")),
+    };
+    if !request.authorized(token) {
+        return respond_error(&mut stream, 401, "missing or wrong token");
+    }
+    let name = request.path.trim_matches('/');
+    if name == "ws" {
+        return websocket(stream, reader, &request, tx);
+    }
+    let method = match name {
+        "status" | "screenshot" => "GET",
+        "open" | "save" | "recompute" | "export" => "POST",
+        _ => return respond_error(&mut stream, 404, "no such operation"),
+    };
+    if request.method != method {
+        return respond_error(&mut stream, 405, &format!("use {method} /{name}"));
+    }
+    let args = if request.body.iter().all(u8::is_ascii_whitespace) {
+        Value::Object(
+            request
+                .query
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect(),
+        )
+    } else {
+        match serde_json::from_slice(&request.body) {
+            Ok(args) => args,
+            Err(err) => return respond_error(&mut stream, 400, &format!("bad JSON: {err}")),
+        }
+    };
+    let operation = match Operation::parse(name, &args) {
+        Ok(operation) => operation,
+        Err(err) => return respond_error(&mut stream, 400, &err),
+    };
+    match call(tx, operation) {
+        Reply::Json(value) => respond(&mut stream, 200, "application/json", value.to_string()),
+        Reply::Png(png) => respond(&mut stream, 200, "image/png", &png),
+        Reply::Error(err) => respond_error(&mut stream, 500, &err),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: impl AsRef<[u8]>,
+) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let body = body.as_ref();
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn respond_error(stream: &mut TcpStream, status: u16, message: &str) -> Result<()> {
+    let body = json!({ "error": message }).to_string();
+    respond(stream, status, "application/json", body)
+}
+
+/// Answer each JSON text message of a WebSocket with one of its own.
+fn websocket(
+    mut stream: TcpStream,
+    mut reader: impl Read,
+    request: &HttpRequest,
+    tx: &Sender<Request>,
+) -> Result<()> {
+    let Some(key) = request.headers.get("sec-websocket-key") else {
+        return respond_error(&mut stream, 400, "expected a WebSocket upgrade");
+    };
+    let accept = base64::engine::general_purpose::STANDARD
+        .encode(Sha1::digest(format!("{key}{WEBSOCKET_GUID}").as_bytes()));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+         Connection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+    )?;
+    stream.flush()?;
+    stream.set_read_timeout(Some(WEBSOCKET_IDLE))?;
+
+    let mut message = Vec::new();
+    loop {
+        let (fin, opcode, payload) = read_frame(&mut reader)?;
+        match opcode {
+            // Text, and continuations of it.
+            0x0 | 0x1 => {
+                message.extend(payload);
+                if message.len() > MAX_BODY {
+                    bail!("message too large");
+                }
+                if !fin {
+                    continue;
+                }
+            }
+            0x8 => return write_frame(&mut stream, 0x8, &payload),
+            0x9 => {
+                write_frame(&mut stream, 0xA, &payload)?;
+                continue;
+            }
+            _ => continue,
+        }
+        let reply = websocket_reply(&std::mem::take(&mut message), tx);
+        write_frame(&mut stream, 0x1, reply.to_string().as_bytes())?;
+    }
+}
+
+fn websocket_reply(message: &[u8], tx: &Sender<Request>) -> Value {
+    let args: Value = match serde_json::from_slice(message) {
+        Ok(args) => args,
+        Err(err) => return json!({ "error": format!("bad JSON: {err}") }),
+    };
+    let id = args.get("id").cloned().unwrap_or(Value::Null);
+    let operation = match args.get("op").and_then(Value::as_str) {
+        Some(name) => Operation::parse(name, &args),
+        None => Err("messages need an \"op\"".to_string()),
+    };
+    let mut reply = match operation.map(|operation| call(tx, operation)) {
+        Ok(Reply::Json(value)) => value,
+        Ok(Reply::Png(png)) => {
+            json!({ "png": base64::engine::general_purpose::STANDARD.encode(png) })
+        }
+        Ok(Reply::Error(err)) | Err(err) => json!({ "error": err }),
+    };
+    if let Value::Object(fields) = &mut reply {
+        fields.insert("id".to_string(), id);
+    }
+    reply
+}
+
+/// Read one frame from a client: whether it is the last of its message, its
+/// opcode, and its unmasked payload.
+fn read_frame(reader: &mut impl Read) -> Result<(bool, u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    reader.read_exact(&mut head)?;
+    let (fin, opcode) = (head[0] & 0x80 != 0, head[0] & 0x0F);
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as usize
+        }
+        127 => {
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            usize::try_from(u64::from_be_bytes(length)).unwrap_or(usize::MAX)
+        }
+        length => length as usize,
+    };
+    if length > MAX_BODY {
+        bail!("frame too large");
+    }
+    let mut mask = [0; 4];
+    if head[1] & 0x80 != 0 {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
+
+/// Write one unmasked frame holding a whole message.
+fn write_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) -> Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend((length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend((length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)?;
+    stream.flush()?;
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! Section view: every body is cut by the plane set in the View menu, and the
+//! faces left open on the plane can be filled (solid or hatched) so cut solids
+//! don't look hollow. The renderer does the cutting, discarding what lies
+//! behind the plane; the fill is computed here from the tessellated meshes,
+//! since that is the only geometry the kernel provides.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use core_document::{Document, GeometryRef, Selection, SelectionItem};
+use glam::Vec3;
+use kernel_api::TriMesh;
+use render_vk::{BodySubmission, ClipPlane, HighlightState, Shading};
+use settings::{SectionFill, SectionPlane, SectionSettings};
+use uuid::Uuid;
+use wb_mesh::mesh::IndexedMesh;
+
+use crate::camera::CameraController;
+
+const SECTION_COLOR: [f32; 3] = [0.85, 0.45, 0.35];
+const HATCH_COLOR: [f32; 3] = [0.25, 0.12, 0.1];
+/// Width of a hatch line, as a fraction of the spacing.
+const HATCH_WIDTH: f32 = 0.25;
+/// How far hatch lines sit in front of the section, as a fraction of the
+/// body's bounding box diagonal, so they win the depth test.
+const HATCH_LIFT: f32 = 5e-4;
+
+/// Where to place the section plane, from the View menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionAlign {
+    /// Through the orbit center, facing the camera.
+    View,
+    /// On the selected origin plane or datum plane.
+    SelectedPlane,
+}
+
+struct CachedSection {
+    key: u64,
+    cap: TriMesh,
+    hatch: TriMesh,
+}
+
+/// Section fills of the submitted bodies, kept until a body or the plane changes.
+#[derive(Default)]
+pub struct SectionView {
+    cache: HashMap<Uuid, CachedSection>,
+}
+
+impl SectionView {
+    /// Add the faces filling the cuts of `bodies` after them, and return the
+    /// section plane for the renderer to cut the clipped bodies with. Does
+    /// nothing while the section view is off.
+    pub fn apply(
+        &mut self,
+        settings: &SectionSettings,
+        bodies: &mut Vec<BodySubmission>,
+    ) -> Option<ClipPlane> {
+        if !settings.enabled {
+            self.cache.clear();
+            return None;
+        }
+        let (origin, normal) = settings.plane();
+        let plane = ClipPlane {
+            point: origin,
+            normal,
+        };
+        if settings.fill == SectionFill::None {
+            self.cache.clear();
+            return Some(plane);
+        }
+        let normal = Vec3::from(normal);
+
+        let mut sections = Vec::new();
+        let mut seen = Vec::with_capacity(bodies.len());
+        for body in bodies.iter() {
+            // Meshes are relative to their body's position, so the plane is
+            // brought into that frame (in double precision) to cut them.
+            let origin = Vec3::from(core_document::to_frame(origin, body.offset));
+            let key = section_key(settings, body.offset, &body.mesh);
+            let cached = self.cache.entry(body.id).or_insert_with(|| CachedSection {
+                key: !key,
+                cap: TriMesh::default(),
+                hatch: TriMesh::default(),
+            });
+            if cached.key != key {
+                let mesh = IndexedMesh::from_trimesh(&body.mesh);
+                let (_, cap) = wb_mesh::cut::section(&mesh, origin, normal);
+                cached.hatch = match settings.fill {
+                    SectionFill::Hatched => hatch(&cap, normal, settings.hatch_spacing),
+                    SectionFill::Solid | SectionFill::None => TriMesh::default(),
+                };
+                cached.cap = cap.to_trimesh();
+                cached.key = key;
+            }
+            seen.push(body.id);
+            // The fill lies on the plane, so the plane mustn't cut it.
+            if !cached.cap.indices.is_empty() {
+                sections.push(BodySubmission {
+                    id: body.id,
+                    mesh: cached.cap.clone(),
+                    offset: body.offset,
+                    color: SECTION_COLOR,
+                    highlight: body.highlight,
+                    shading: Shading::Lit,
+                    opacity: body.opacity,
+                    clipped: false,
+                });
+            }
+            if !cached.hatch.indices.is_empty() {
+                sections.push(BodySubmission {
+                    id: body.id,
+                    mesh: cached.hatch.clone(),
+                    offset: body.offset,
+                    color: HATCH_COLOR,
+                    highlight: HighlightState::None,
+                    shading: Shading::Lit,
+                    opacity: body.opacity,
+                    clipped: false,
+                });
+            }
+        }
+        self.cache.retain(|id, _| seen.contains(id));
+        bodies.append(&mut sections);
+        Some(plane)
+    }
+}
+
+/// Section plane placed as `align` asks: through the orbit center facing the
+/// camera, or on the origin plane picked in the tree (`reference`) or the
+/// datum plane selected. None if no plane is selected.
+pub fn aligned_plane(
+    align: SectionAlign,
+    camera: &CameraController,
+    document: &Document,
+    selection: &Selection,
+    reference: Option<core_document::OriginRef>,
+) -> Option<SectionPlane> {
+    match align {
+        SectionAlign::View => Some(view_plane(camera)),
+        SectionAlign::SelectedPlane => selected_plane(document, selection, reference),
+    }
+}
+
+/// Section plane through the orbit center, keeping what lies beyond it as
+/// seen from the camera.
+fn view_plane(camera: &CameraController) -> SectionPlane {
+    let target = camera.target();
+    let direction = Vec3::from(target) - Vec3::from(camera.position());
+    SectionPlane {
+        origin: core_document::from_frame(target, camera.frame()),
+        normal: direction.try_normalize().unwrap_or(Vec3::Z).to_array(),
+    }
+}
+
+/// Section plane on the origin plane picked in the tree or the one datum
+/// plane selected, keeping what lies in front of it.
+fn selected_plane(
+    document: &Document,
+    selection: &Selection,
+    reference: Option<core_document::OriginRef>,
+) -> Option<SectionPlane> {
+    let reference = match (reference, selection.items()) {
+        (Some(origin), _) if origin.element.is_plane() => GeometryRef::Origin(origin),
+        (_, [SelectionItem::Feature(id)]) => GeometryRef::Datum(*id),
+        _ => return None,
+    };
+    let plane = wb_part::reference_plane(document, &reference)?;
+    let position = reference
+        .body(document)
+        .map_or([0.0; 3], |body| document.body_position(body));
+    Some(SectionPlane {
+        origin: core_document::from_frame(plane.origin, position),
+        normal: plane.normal,
+    })
+}
+
+/// Identifies a mesh, where it is, and the plane it was cut with.
+fn section_key(settings: &SectionSettings, offset: [f64; 3], mesh: &TriMesh) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let (origin, normal) = settings.plane();
+    origin.map(f64::to_bits).hash(&mut hasher);
+    offset.map(f64::to_bits).hash(&mut hasher);
+    normal.map(f32::to_bits).hash(&mut hasher);
+    settings.fill.hash(&mut hasher);
+    settings.hatch_spacing.to_bits().hash(&mut hasher);
+    for p in &mesh.positions {
+        p.map(f32::to_bits).hash(&mut hasher);
+    }
+    mesh.indices.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Strips at 45° across the section faces of `cap` (which lie on a plane
+/// with unit `normal` and face along `-normal`), `spacing` mm apart.
+fn hatch(cap: &IndexedMesh, normal: Vec3, spacing: f32) -> TriMesh {
+    let mut hatch = TriMesh::default();
+    if cap.triangles.is_empty() || spacing <= 0.0 {
+        return hatch;
+    }
+    let u = normal.any_orthonormal_vector();
+    let v = u.cross(normal);
+    // Across the strips, at 45° to the plane coordinates.
+    let across = (u - v) / std::f32::consts::SQRT_2;
+    let width = spacing * HATCH_WIDTH;
+
+    let (min, max) = cap.positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), &p| (min.min(p), max.max(p)),
+    );
+    let lift = -normal * (max - min).length() * HATCH_LIFT;
+
+    for triangle in &cap.triangles {
+        let corners = triangle.map(|i| cap.positions[i as usize]);
+        let offsets = corners.map(|p| p.dot(across));
+        let low = offsets.iter().copied().fold(f32::MAX, f32::min);
+        let high = offsets.iter().copied().fold(f32::MIN, f32::max);
+        let first = (low / spacing).floor() as i64;
+        let last = (high / spacing).floor() as i64;
+        for k in first..=last {
+            let start = k as f32 * spacing;
+            let mut piece = clip(&corners, across, start, true);
+            piece = clip(&piece, across, start + width, false);
+            if piece.len() < 3 {
+                continue;
+            }
+            let base = hatch.positions.len() as u32;
+            for p in &piece {
+                hatch.positions.push((*p + lift).to_array());
+                hatch.normals.push((-normal).to_array());
+            }
+            for j in 1..piece.len() as u32 - 1 {
+                hatch.indices.extend([base, base + j, base + j + 1]);
+            }
+        }
+    }
+    hatch
+}
+
+/// The part of the convex polygon `points` where `p · across` is above
+/// `bound` (or below it, if not `above`).
+fn clip(points: &[Vec3], across: Vec3, bound: f32, above: bool) -> Vec<Vec3> {
+    let inside = |p: Vec3| {
+        let d = p.dot(across) - bound;
+        if above {
+            d
+        } else {
+            -d
+        }
+    };
+    let mut result = Vec::with_capacity(points.len() + 1);
+    for (i, &a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let (da, db) = (inside(a), inside(b));
+        if da >= 0.0 {
+            result.push(a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            result.push(a.lerp(b, da / (da - db)));
+        }
+    }
+    result
+}
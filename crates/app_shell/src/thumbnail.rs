@@ -0,0 +1,31 @@
+//! Previews of documents and library parts as egui images, rendered by the
+//! `thumbnailer` crate (shared with the file-manager thumbnailer).
+
+use std::path::Path;
+
+use egui::ColorImage;
+use glam::Vec3;
+use kernel_api::TriMesh;
+use tiny_skia::Pixmap;
+
+/// Isometric view of the meshes (None if there is nothing to draw).
+pub fn render(meshes: &[TriMesh], up: Vec3) -> Option<ColorImage> {
+    thumbnailer::render(meshes, up, thumbnailer::THUMBNAIL_SIZE).map(to_image)
+}
+
+/// Preview of a `.prtcad` file: the one saved in it, or one rendered from its
+/// bodies for documents saved without.
+pub fn document_preview(path: &Path) -> Option<ColorImage> {
+    thumbnailer::document_preview(path, thumbnailer::THUMBNAIL_SIZE)
+        .ok()
+        .flatten()
+        .map(to_image)
+}
+
+fn to_image(pixmap: Pixmap) -> ColorImage {
+    // Both egui and tiny-skia keep colors premultiplied by alpha.
+    ColorImage::from_rgba_premultiplied(
+        [pixmap.width() as usize, pixmap.height() as usize],
+        pixmap.data(),
+    )
+}
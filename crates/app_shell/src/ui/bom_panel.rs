@@ -0,0 +1,161 @@
+use core_document::{BodyMesh, Document, MassProperties, UnitFormat};
+use egui::{self, Ui};
+
+use super::materials_panel::format_mass;
+
+/// One line of the bill of materials: the bodies with the same name,
+/// material, and source file.
+struct BomLine {
+    name: String,
+    material: Option<String>,
+    /// File a linked body comes from.
+    source: Option<String>,
+    quantity: usize,
+    /// Properties of one of the bodies.
+    properties: MassProperties,
+}
+
+fn bom_lines(document: &Document, body_meshes: &[BodyMesh]) -> Vec<BomLine> {
+    let library = document.materials();
+    let mut lines: Vec<BomLine> = Vec::new();
+    for body in document.bodies() {
+        let material = body.material.as_ref().and_then(|id| library.get(id));
+        let name = material.as_ref().map(|material| material.name.clone());
+        let source = body.link.as_ref().map(|link| link.path.clone());
+        if let Some(line) = lines
+            .iter_mut()
+            .find(|line| line.name == body.name && line.material == name && line.source == source)
+        {
+            line.quantity += 1;
+            continue;
+        }
+        let properties = MassProperties::of_meshes(
+            body_meshes
+                .iter()
+                .filter(|m| m.body == body.id)
+                .map(|m| &m.mesh),
+            material.as_ref(),
+        );
+        lines.push(BomLine {
+            name: body.name.clone(),
+            material: name,
+            source,
+            quantity: 1,
+            properties,
+        });
+    }
+    lines
+}
+
+/// Bill of materials panel: the document's bodies, alike ones counted on one
+/// line, with their material, source file, volume, mass, and cost.
+pub(super) fn bom_ui(
+    ui: &mut Ui,
+    document: &Document,
+    body_meshes: &[BodyMesh],
+    units: &UnitFormat,
+) {
+    if !document.has_bodies() {
+        ui.weak("The document has no bodies.");
+        return;
+    }
+    let library = document.materials();
+    let lines = bom_lines(document, body_meshes);
+    if ui
+        .button("Copy as CSV")
+        .on_hover_text("Copy the bill of materials for a spreadsheet")
+        .clicked()
+    {
+        ui.ctx().copy_text(csv(&lines));
+    }
+    let (mut total_mass, mut total_cost) = (0.0, 0.0);
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("bom_table")
+            .num_columns(7)
+            .striped(true)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for header in [
+                    "Qty", "Item", "Material", "Source", "Volume", "Mass", "Cost",
+                ] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for line in &lines {
+                    ui.label(line.quantity.to_string());
+                    ui.label(&line.name);
+                    ui.label(line.material.as_deref().unwrap_or("—"));
+                    match &line.source {
+                        Some(source) => {
+                            let file = std::path::Path::new(source)
+                                .file_name()
+                                .map_or(source.clone(), |name| name.to_string_lossy().into_owned());
+                            ui.label(file).on_hover_text(source);
+                        }
+                        None => {
+                            ui.weak("—");
+                        }
+                    }
+                    ui.label(units.format_volume(line.properties.volume));
+                    match (line.properties.mass, line.properties.cost) {
+                        (Some(mass), Some(cost)) => {
+                            let count = line.quantity as f32;
+                            total_mass += mass * count;
+                            total_cost += cost * count;
+                            ui.label(format_mass(mass));
+                            ui.label(library.format_price(cost));
+                        }
+                        _ => {
+                            ui.weak("—");
+                            ui.weak("—");
+                        }
+                    }
+                    ui.end_row();
+                }
+
+                ui.strong(
+                    lines
+                        .iter()
+                        .map(|line| line.quantity)
+                        .sum::<usize>()
+                        .to_string(),
+                );
+                ui.strong("Total");
+                for _ in 0..3 {
+                    ui.label("");
+                }
+                ui.strong(format_mass(total_mass));
+                ui.strong(library.format_price(total_cost));
+                ui.end_row();
+            });
+    });
+}
+
+/// The bill of materials as comma-separated values, volumes in mm³ and
+/// masses in grams.
+fn csv(lines: &[BomLine]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut csv = String::from("Qty,Item,Material,Source,Volume (mm³),Mass (g),Cost\n");
+    for line in lines {
+        let number =
+            |value: Option<f32>| value.map_or(String::new(), |value| format!("{value:.2}"));
+        csv.push_str(&format!(
+            "{},{},{},{},{:.1},{},{}\n",
+            line.quantity,
+            field(&line.name),
+            field(line.material.as_deref().unwrap_or("")),
+            field(line.source.as_deref().unwrap_or("")),
+            line.properties.volume,
+            number(line.properties.mass),
+            number(line.properties.cost),
+        ));
+    }
+    csv
+}
@@ -0,0 +1,165 @@
+use core_document::{BodyDisplay, BodyId, DisplayState, Document};
+use egui::{self, Context, Ui};
+
+/// Label of the entry showing every body as usual.
+const NO_STATE: &str = "Default";
+
+/// Dropdown in the top bar switching the active display state, with an entry
+/// opening the editor window.
+pub(super) fn display_state_combo(ui: &mut Ui, document: &mut Document, show_editor: &mut bool) {
+    let mut states = document.display_states().clone();
+    let selected = states
+        .active_state()
+        .map_or(NO_STATE, |state| state.name.as_str())
+        .to_string();
+    ui.label("Display:");
+    egui::ComboBox::from_id_salt("display_state_combo")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut states.active, None, NO_STATE);
+            for (index, state) in states.states.clone().iter().enumerate() {
+                ui.selectable_value(&mut states.active, Some(index), &state.name);
+            }
+            ui.separator();
+            if ui.button("Edit Display States…").clicked() {
+                *show_editor = true;
+                ui.close();
+            }
+        });
+    document.set_display_states(states);
+}
+
+/// Window adding, renaming, and removing display states, and setting how each
+/// body is shown in the active one.
+pub(super) fn draw_display_states_window(
+    ctx: &Context,
+    show_display_states: &mut bool,
+    document: &mut Document,
+) {
+    if !*show_display_states {
+        return;
+    }
+    egui::Window::new("Display States")
+        .open(show_display_states)
+        .default_width(480.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            states_ui(ui, document);
+            ui.add_space(8.0);
+            ui.separator();
+            bodies_ui(ui, document);
+        });
+}
+
+fn states_ui(ui: &mut Ui, document: &mut Document) {
+    let mut states = document.display_states().clone();
+    let mut remove = None;
+    egui::Grid::new("display_states_list")
+        .num_columns(3)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            for (index, state) in states.states.iter_mut().enumerate() {
+                let active = states.active == Some(index);
+                if ui.radio(active, "").on_hover_text("Activate").clicked() {
+                    states.active = Some(index);
+                }
+                ui.text_edit_singleline(&mut state.name);
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+    if states.states.is_empty() {
+        ui.weak("No display states yet. Every body is shown as usual.");
+    }
+    if let Some(index) = remove {
+        states.remove(index);
+    }
+    ui.horizontal(|ui| {
+        if ui.button("New State").clicked() {
+            let name = states.unused_name();
+            states.states.push(DisplayState::new(name));
+            states.active = Some(states.states.len() - 1);
+        }
+        if ui
+            .add_enabled(states.active.is_some(), egui::Button::new("Show All"))
+            .on_hover_text("Show every body as usual without a display state")
+            .clicked()
+        {
+            states.active = None;
+        }
+    });
+    document.set_display_states(states);
+}
+
+/// Show `body` at `opacity` in the active display state, adding and
+/// activating a state first if none is active.
+pub(super) fn set_body_opacity(document: &mut Document, body: BodyId, opacity: f32) {
+    let mut states = document.display_states().clone();
+    if states.active.is_none() {
+        let name = states.unused_name();
+        states.states.push(DisplayState::new(name));
+        states.active = Some(states.states.len() - 1);
+    }
+    if let Some(state) = states.active_state_mut() {
+        let display = BodyDisplay {
+            opacity,
+            ..state.body(body)
+        };
+        state.set_body(body, display);
+    }
+    document.set_display_states(states);
+}
+
+fn bodies_ui(ui: &mut Ui, document: &mut Document) {
+    let mut states = document.display_states().clone();
+    let Some(state) = states.active_state_mut() else {
+        ui.weak("Activate a display state to change how its bodies are shown.");
+        return;
+    };
+    if !document.has_bodies() {
+        ui.weak("The document has no bodies.");
+        return;
+    }
+    ui.strong(format!("Bodies in \"{}\"", state.name));
+    egui::Grid::new("display_states_bodies")
+        .num_columns(4)
+        .striped(true)
+        .spacing([12.0, 4.0])
+        .show(ui, |ui| {
+            for header in ["Body", "Visible", "Opacity", "Color"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for body in document.bodies() {
+                let mut display = state.body(body.id);
+                ui.label(&body.name);
+                ui.checkbox(&mut display.visible, "");
+                ui.add_enabled(
+                    display.visible,
+                    egui::Slider::new(&mut display.opacity, 0.05..=1.0).fixed_decimals(2),
+                );
+                ui.horizontal(|ui| {
+                    let mut overridden = display.color.is_some();
+                    if ui.checkbox(&mut overridden, "").changed() {
+                        display.color = overridden.then_some([0.8, 0.3, 0.3]);
+                    }
+                    if let Some(color) = display.color.as_mut() {
+                        ui.color_edit_button_rgb(color);
+                    } else {
+                        ui.weak("Body color");
+                    }
+                });
+                ui.end_row();
+                state.set_body(body.id, display);
+            }
+        });
+    if ui.button("Reset All Bodies").clicked() {
+        for body in document.bodies() {
+            state.set_body(body.id, BodyDisplay::default());
+        }
+    }
+    document.set_display_states(states);
+}
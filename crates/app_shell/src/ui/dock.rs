@@ -0,0 +1,322 @@
+//! Dockable panels.
+//!
+//! The model tree, the workbench's properties and analyses, the log, the
+//! parameters, the part library, and the bill of materials are tabs docked to the left, right, or bottom of the window, or
+//! float in windows of their own. A tab is moved by dragging it onto another
+//! side (the side's tab order follows where it is dropped) or onto the viewport
+//! to float it, or from its right-click menu. Closing a floating panel docks it
+//! back where it started, except for the panels opened from the menus
+//! ([`PanelId::closable`]), which are closed wherever they are. The
+//! arrangement is kept in the user settings ([`PanelLayout`]).
+
+use std::collections::HashMap;
+
+use egui::{self, Context, Id, Ui};
+use settings::{PanelDock, PanelId, PanelLayout};
+
+/// Width of the drop zones shown along the viewport for empty sides.
+const DROP_ZONE_SIZE: f32 = 48.0;
+
+/// Panel layout state that is not saved: the open tab of each side and where
+/// panels just dropped on the viewport open.
+#[derive(Default)]
+pub(super) struct DockState {
+    active: HashMap<PanelDock, PanelId>,
+    float_at: HashMap<PanelId, egui::Pos2>,
+}
+
+/// What the user did with the panels in a frame.
+#[derive(Default)]
+pub(super) struct DockResult {
+    /// The layout changed and should be saved.
+    pub changed: bool,
+    /// A closable panel was closed.
+    pub closed: Option<PanelId>,
+}
+
+/// A panel moved to `dock`, as the tab before `before` (last when None).
+struct PanelMove {
+    panel: PanelId,
+    dock: PanelDock,
+    before: Option<PanelId>,
+}
+
+/// Draw the panels docked to the sides of the window and the floating ones.
+/// `available` tells which panels have anything to show (the others are left
+/// out, and a side with none is not drawn); `focus` brings a panel's tab to
+/// the front; `panel_ui` draws a panel's contents.
+pub(super) fn draw_docks(
+    ctx: &Context,
+    layout: &mut PanelLayout,
+    state: &mut DockState,
+    available: impl Fn(PanelId) -> bool,
+    focus: Option<PanelId>,
+    mut panel_ui: impl FnMut(&mut Ui, PanelId),
+) -> DockResult {
+    if let Some(panel) = focus {
+        state.active.insert(layout.dock(panel), panel);
+    }
+    let mut moved = None;
+    let mut closed = None;
+
+    for dock in PanelDock::SIDES {
+        let panels: Vec<PanelId> = layout
+            .panels(dock)
+            .into_iter()
+            .filter(|&panel| available(panel))
+            .collect();
+        let Some(&first) = panels.first() else {
+            continue;
+        };
+        let mut selected = state
+            .active
+            .get(&dock)
+            .copied()
+            .filter(|panel| panels.contains(panel))
+            .unwrap_or(first);
+        let contents = |ui: &mut Ui| {
+            tab_strip(ui, dock, &panels, &mut selected, &mut moved, &mut closed);
+            ui.separator();
+            panel_ui(ui, selected);
+            drop_target(ui, dock, &mut moved);
+        };
+        match dock {
+            PanelDock::Left => {
+                egui::SidePanel::left("left_panel")
+                    .resizable(true)
+                    .default_width(260.0)
+                    .show(ctx, contents);
+            }
+            PanelDock::Right => {
+                egui::SidePanel::right("right_panel")
+                    .resizable(true)
+                    .default_width(280.0)
+                    .show(ctx, contents);
+            }
+            PanelDock::Bottom | PanelDock::Floating => {
+                egui::TopBottomPanel::bottom("bottom_panel")
+                    .resizable(true)
+                    .default_height(160.0)
+                    .min_height(80.0)
+                    .show(ctx, contents);
+            }
+        }
+        state.active.insert(dock, selected);
+    }
+
+    for panel in layout.panels(PanelDock::Floating) {
+        if !available(panel) {
+            continue;
+        }
+        let mut open = true;
+        let mut window = egui::Window::new(panel.label())
+            .id(Id::new(("floating_panel", panel)))
+            .open(&mut open)
+            .default_size([280.0, 320.0]);
+        if let Some(pos) = state.float_at.remove(&panel) {
+            window = window.current_pos(pos);
+        }
+        window.show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.menu_button("Dock", |ui| {
+                    dock_menu(ui, panel, PanelDock::Floating, &mut moved, &mut closed);
+                });
+            });
+            ui.separator();
+            panel_ui(ui, panel);
+        });
+        if !open {
+            if panel.closable() {
+                closed = Some(panel);
+            } else {
+                moved = Some(PanelMove {
+                    panel,
+                    dock: panel.default_dock(),
+                    before: None,
+                });
+            }
+        }
+    }
+
+    DockResult {
+        changed: apply_move(layout, state, moved),
+        closed,
+    }
+}
+
+/// While a tab is dragged, show drop zones along the edges of `viewport` for
+/// the sides without panels, and dock or float the tab when it is dropped
+/// there or elsewhere on the viewport. Returns whether the layout changed.
+pub(super) fn draw_drop_zones(
+    ctx: &Context,
+    viewport: egui::Rect,
+    layout: &mut PanelLayout,
+    state: &mut DockState,
+    available: impl Fn(PanelId) -> bool,
+) -> bool {
+    let Some(panel) = egui::DragAndDrop::payload::<PanelId>(ctx) else {
+        return false;
+    };
+    let Some(pointer) = ctx.pointer_latest_pos() else {
+        return false;
+    };
+    let released = ctx.input(|i| i.pointer.any_released());
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        Id::new("dock_drop_zones"),
+    ));
+    let selection = ctx.style().visuals.selection.bg_fill;
+
+    let mut moved = None;
+    for dock in PanelDock::SIDES {
+        if layout.panels(dock).into_iter().any(&available) {
+            continue;
+        }
+        let zone = match dock {
+            PanelDock::Left => egui::Rect::from_min_max(
+                viewport.min,
+                egui::pos2(viewport.min.x + DROP_ZONE_SIZE, viewport.max.y),
+            ),
+            PanelDock::Right => egui::Rect::from_min_max(
+                egui::pos2(viewport.max.x - DROP_ZONE_SIZE, viewport.min.y),
+                viewport.max,
+            ),
+            PanelDock::Bottom | PanelDock::Floating => egui::Rect::from_min_max(
+                egui::pos2(viewport.min.x, viewport.max.y - DROP_ZONE_SIZE),
+                viewport.max,
+            ),
+        };
+        let hovered = zone.contains(pointer);
+        painter.rect_filled(
+            zone,
+            4.0,
+            selection.gamma_multiply(if hovered { 0.5 } else { 0.2 }),
+        );
+        if hovered && released {
+            moved = Some(PanelMove {
+                panel: *panel,
+                dock,
+                before: None,
+            });
+        }
+    }
+    if moved.is_none() && released && viewport.contains(pointer) {
+        state.float_at.insert(*panel, pointer);
+        moved = Some(PanelMove {
+            panel: *panel,
+            dock: PanelDock::Floating,
+            before: None,
+        });
+    }
+    if moved.is_some() {
+        egui::DragAndDrop::clear_payload(ctx);
+    }
+    apply_move(layout, state, moved)
+}
+
+/// Tabs of the panels docked to `dock`; each can be dragged elsewhere, and
+/// panels dragged onto one are placed before it.
+fn tab_strip(
+    ui: &mut Ui,
+    dock: PanelDock,
+    panels: &[PanelId],
+    selected: &mut PanelId,
+    moved: &mut Option<PanelMove>,
+    closed: &mut Option<PanelId>,
+) {
+    ui.horizontal(|ui| {
+        for &panel in panels {
+            let tab = ui.dnd_drag_source(Id::new(("panel_tab", panel)), panel, |ui| {
+                ui.selectable_label(
+                    *selected == panel,
+                    egui::RichText::new(panel.label()).strong(),
+                )
+            });
+            if tab.inner.clicked() {
+                *selected = panel;
+            }
+            if let Some(dragged) = tab.response.dnd_release_payload::<PanelId>() {
+                *moved = Some(PanelMove {
+                    panel: *dragged,
+                    dock,
+                    before: Some(panel),
+                });
+            }
+            tab.response
+                .context_menu(|ui| dock_menu(ui, panel, dock, moved, closed));
+        }
+    });
+}
+
+/// Highlight the side while a tab is dragged over it, and take the tab when
+/// it is dropped there.
+fn drop_target(ui: &mut Ui, dock: PanelDock, moved: &mut Option<PanelMove>) {
+    let rect = ui.max_rect();
+    let response = ui.interact(rect, Id::new(("dock_side", dock)), egui::Sense::hover());
+    if response.dnd_hover_payload::<PanelId>().is_some() {
+        let fill = ui.visuals().selection.bg_fill.gamma_multiply(0.25);
+        ui.painter().rect_filled(rect, 0.0, fill);
+    }
+    if let Some(dragged) = response.dnd_release_payload::<PanelId>() {
+        *moved = Some(PanelMove {
+            panel: *dragged,
+            dock,
+            before: None,
+        });
+    }
+}
+
+/// Buttons moving `panel` from `dock` to the other sides or a window, and
+/// closing it if it can be closed.
+fn dock_menu(
+    ui: &mut Ui,
+    panel: PanelId,
+    dock: PanelDock,
+    moved: &mut Option<PanelMove>,
+    closed: &mut Option<PanelId>,
+) {
+    for target in PanelDock::SIDES {
+        if target != dock && ui.button(format!("Dock {}", target.label())).clicked() {
+            *moved = Some(PanelMove {
+                panel,
+                dock: target,
+                before: None,
+            });
+            ui.close();
+        }
+    }
+    if dock != PanelDock::Floating && ui.button("Float").clicked() {
+        *moved = Some(PanelMove {
+            panel,
+            dock: PanelDock::Floating,
+            before: None,
+        });
+        ui.close();
+    }
+    if panel.closable() && dock != PanelDock::Floating {
+        ui.separator();
+        if ui.button("Close").clicked() {
+            *closed = Some(panel);
+            ui.close();
+        }
+    }
+}
+
+fn apply_move(layout: &mut PanelLayout, state: &mut DockState, moved: Option<PanelMove>) -> bool {
+    let Some(PanelMove {
+        panel,
+        dock,
+        before,
+    }) = moved
+    else {
+        return false;
+    };
+    if before == Some(panel) {
+        // Dropped on its own tab.
+        return false;
+    }
+    let old_layout = layout.clone();
+    layout.move_panel(panel, dock, before);
+    state.active.insert(dock, panel);
+    *layout != old_layout
+}
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
 
-use core_document::{Body, BodyId, Document, FeatureId, FeatureNode, FeatureTree};
+use core_document::{
+    Body, BodyId, Document, FeatureId, FeatureNode, FeatureStatus, FeatureTree, OriginElement,
+    OriginRef, Selection, SelectionItem,
+};
 use egui::{Color32, Response, RichText, Ui};
 
 /// Identifier for selectable items in the tree panel.
@@ -8,6 +11,10 @@ use egui::{Color32, Response, RichText, Ui};
 pub enum TreeItemId {
     DocumentRoot,
     Body(BodyId),
+    /// The "Origin" group of a body.
+    Origin(BodyId),
+    /// A plane, axis, or the point inside a body's origin group.
+    OriginElement(OriginRef),
     Feature(FeatureId),
 }
 
@@ -17,10 +24,48 @@ impl From<FeatureId> for TreeItemId {
     }
 }
 
+impl TreeItemId {
+    /// Row showing a selected item (a body's faces, edges, and vertices show on the body).
+    pub fn for_selection(item: SelectionItem) -> Self {
+        match item {
+            SelectionItem::Body(body) | SelectionItem::Element { body, .. } => {
+                TreeItemId::Body(body)
+            }
+            SelectionItem::Feature(feature) => TreeItemId::Feature(feature),
+        }
+    }
+
+    /// Whether the row shows an item of `selection`.
+    fn in_selection(self, selection: &Selection) -> bool {
+        match self {
+            TreeItemId::Body(body) => selection.items().iter().any(|item| match item {
+                SelectionItem::Body(selected) | SelectionItem::Element { body: selected, .. } => {
+                    *selected == body
+                }
+                SelectionItem::Feature(_) => false,
+            }),
+            TreeItemId::Feature(feature) => selection.contains(SelectionItem::Feature(feature)),
+            TreeItemId::DocumentRoot | TreeItemId::Origin(_) | TreeItemId::OriginElement(_) => {
+                false
+            }
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct TreeUiResult {
     pub selection: Option<TreeItemId>,
+    /// The click held Ctrl or Shift: toggle the item instead of selecting only it.
+    pub extend: bool,
     pub activation: Option<TreeItemId>,
+    /// Visibility checkbox toggled on an item: (item, new visibility).
+    pub visibility_change: Option<(TreeItemId, bool)>,
+    /// "Zoom to" picked from an item's context menu.
+    pub zoom_to: Option<TreeItemId>,
+    /// Opacity set from a body's context menu: (body, new opacity).
+    pub opacity_change: Option<(BodyId, f32)>,
+    /// Position set from a body's context menu: (body, new position).
+    pub position_change: Option<(BodyId, [f64; 3])>,
 }
 
 /// View model describing the current document tree.
@@ -37,8 +82,16 @@ struct TreeNode {
     badge: Option<String>,
     tooltip: Option<String>,
     dirty: bool,
+    /// Warning or error from the last recompute, shown as a badge.
+    status: Option<FeatureStatus>,
     visible: bool,
+    /// Show a visibility checkbox next to the label.
+    toggles_visibility: bool,
     suppressed: bool,
+    /// Opacity of a body in the active display state.
+    opacity: Option<f32>,
+    /// World position of a body's frame.
+    position: Option<[f64; 3]>,
     created_at_ms: i64,
     children: Vec<TreeNode>,
 }
@@ -85,9 +138,11 @@ impl DocumentTree {
             .bodies()
             .iter()
             .map(|body| {
-                let mut node = build_body_node(body);
-                if let Some(children) = roots_by_body.remove(&Some(body.id)) {
-                    node.children = children;
+                let opacity = document.display_states().body(body.id).opacity;
+                let mut node = build_body_node(body, opacity);
+                node.children.push(build_origin_node(body));
+                if let Some(mut children) = roots_by_body.remove(&Some(body.id)) {
+                    node.children.append(&mut children);
                 }
                 node
             })
@@ -114,6 +169,23 @@ impl DocumentTree {
     fn nodes(&self) -> &[TreeNode] {
         &self.nodes
     }
+
+    /// Rows from the top level down to `id` (inclusive), or empty if it isn't in the tree.
+    fn path_to(&self, id: TreeItemId) -> Vec<TreeItemId> {
+        fn search(nodes: &[TreeNode], id: TreeItemId, path: &mut Vec<TreeItemId>) -> bool {
+            for node in nodes {
+                path.push(node.id);
+                if node.id == id || search(&node.children, id, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        let mut path = Vec::new();
+        search(&self.nodes, id, &mut path);
+        path
+    }
 }
 
 fn build_feature_node(
@@ -141,48 +213,117 @@ fn build_feature_node(
         badge: Some(format_workbench_tag(node.workbench_id.as_str())),
         tooltip: Some(feature_tooltip(node)),
         dirty: node.dirty,
+        status: node.status.clone(),
         visible: node.visible,
+        toggles_visibility: false,
         suppressed: node.suppressed,
+        opacity: None,
+        position: None,
         created_at_ms: node.created_at,
         children,
     }
 }
 
-fn build_body_node(body: &Body) -> TreeNode {
+fn build_body_node(body: &Body, opacity: f32) -> TreeNode {
     TreeNode {
         id: TreeItemId::Body(body.id),
         label: body.name.clone(),
         badge: None,
         tooltip: None,
         dirty: false,
+        status: None,
         visible: true,
+        toggles_visibility: false,
         suppressed: false,
+        opacity: Some(opacity),
+        position: Some(body.position),
         created_at_ms: body.created_at,
         children: Vec::new(),
     }
 }
 
+fn build_origin_node(body: &Body) -> TreeNode {
+    let children = OriginElement::ALL
+        .into_iter()
+        .map(|element| TreeNode {
+            id: TreeItemId::OriginElement(OriginRef::new(body.id, element)),
+            label: element.label().to_string(),
+            badge: None,
+            tooltip: element
+                .is_plane()
+                .then(|| "Select, then Create Sketch to sketch on this plane".to_string()),
+            dirty: false,
+            status: None,
+            visible: body.origin.is_visible(element),
+            toggles_visibility: true,
+            suppressed: false,
+            opacity: None,
+            position: None,
+            created_at_ms: body.created_at,
+            children: Vec::new(),
+        })
+        .collect();
+
+    TreeNode {
+        id: TreeItemId::Origin(body.id),
+        label: "Origin".to_string(),
+        badge: None,
+        tooltip: None,
+        dirty: false,
+        status: None,
+        visible: true,
+        toggles_visibility: false,
+        suppressed: false,
+        opacity: None,
+        position: None,
+        created_at_ms: body.created_at,
+        children,
+    }
+}
+
 fn format_workbench_tag(raw: &str) -> String {
-    raw.trim_start_matches("wb.")
-        .replace('-', " ")
-        .replace('_', " ")
+    raw.trim_start_matches("wb.").replace(['-', '_'], " ")
 }
 
-pub fn draw_tree(ui: &mut Ui, model: &DocumentTree, selected: Option<TreeItemId>) -> TreeUiResult {
+/// What the tree highlights, and the row to bring into view this frame.
+pub struct TreeHighlight<'a> {
+    /// Row last clicked (or following the newest selected item).
+    pub current: Option<TreeItemId>,
+    /// Items selected in the viewport or the tree.
+    pub selection: &'a Selection,
+    /// Expand the row's parents and scroll it into view.
+    pub reveal: Option<TreeItemId>,
+}
+
+impl TreeHighlight<'_> {
+    fn is_selected(&self, id: TreeItemId) -> bool {
+        self.current == Some(id) || id.in_selection(self.selection)
+    }
+}
+
+pub fn draw_tree(ui: &mut Ui, model: &DocumentTree, highlight: &TreeHighlight) -> TreeUiResult {
     let mut result = TreeUiResult::default();
+    let reveal_path = highlight
+        .reveal
+        .map(|id| model.path_to(id))
+        .unwrap_or_default();
 
     // Document root behaves like a top-level collapsible item.
     let header_text = format!("Document: {}", model.document_label());
     let collapsing = egui::CollapsingHeader::new(header_text)
         .id_salt("document_root")
+        .open((!reveal_path.is_empty()).then_some(true))
         .show(ui, |ui| {
             for node in model.nodes() {
-                draw_node(ui, node, 0, selected, &mut result);
+                draw_node(ui, node, 0, highlight, &reveal_path, &mut result);
             }
         });
     handle_response(
+        ui,
         collapsing.header_response,
         TreeItemId::DocumentRoot,
+        None,
+        None,
         &mut result,
     );
 
@@ -193,49 +334,147 @@ fn draw_node(
     ui: &mut Ui,
     node: &TreeNode,
     depth: usize,
-    selected: Option<TreeItemId>,
+    highlight: &TreeHighlight,
+    reveal_path: &[TreeItemId],
     result: &mut TreeUiResult,
 ) {
     let indent = (depth as f32) * 14.0;
+    let revealed = highlight.reveal == Some(node.id);
 
     // Nodes with children are rendered as collapsible tree branches; leaves as simple rows.
     if node.children.is_empty() {
         ui.horizontal(|ui| {
             ui.add_space(indent);
+            if node.toggles_visibility {
+                let mut visible = node.visible;
+                if ui
+                    .checkbox(&mut visible, "")
+                    .on_hover_text("Show in viewport")
+                    .changed()
+                {
+                    result.visibility_change = Some((node.id, visible));
+                }
+            }
+            status_badge(ui, node);
             let label = compose_label(node);
-            let is_selected = selected == Some(node.id);
+            let is_selected = highlight.is_selected(node.id);
             let response = if let Some(tooltip) = &node.tooltip {
                 ui.selectable_label(is_selected, label)
                     .on_hover_text(tooltip)
             } else {
                 ui.selectable_label(is_selected, label)
             };
-            handle_response(response, node.id, result);
+            if revealed {
+                response.scroll_to_me(Some(egui::Align::Center));
+            }
+            handle_response(ui, response, node.id, node.opacity, node.position, result);
         });
     } else {
         ui.horizontal(|ui| {
             ui.add_space(indent);
-            let label = compose_label(node);
+            status_badge(ui, node);
+            let mut label = compose_label(node);
+            if highlight.is_selected(node.id) {
+                label = label.background_color(ui.visuals().selection.bg_fill);
+            }
+            // Parents of the revealed row open; the row itself keeps its state.
+            let open_for_reveal = !revealed && reveal_path.contains(&node.id);
             let collapsing = egui::CollapsingHeader::new(label)
                 .id_salt(format!("tree_node_{:?}", node.id))
+                .open(open_for_reveal.then_some(true))
                 .show(ui, |ui| {
                     for child in &node.children {
-                        draw_node(ui, child, depth + 1, selected, result);
+                        draw_node(ui, child, depth + 1, highlight, reveal_path, result);
                     }
                 });
 
-            handle_response(collapsing.header_response, node.id, result);
+            if revealed {
+                collapsing
+                    .header_response
+                    .scroll_to_me(Some(egui::Align::Center));
+            }
+            handle_response(
+                ui,
+                collapsing.header_response,
+                node.id,
+                node.opacity,
+                node.position,
+                result,
+            );
         });
     }
 }
 
-fn handle_response(response: Response, id: TreeItemId, result: &mut TreeUiResult) {
+/// Red (error) or yellow (warning) marker whose tooltip is the message.
+fn status_badge(ui: &mut Ui, node: &TreeNode) {
+    let Some(status) = &node.status else {
+        return;
+    };
+    let color = if status.is_error() {
+        Color32::from_rgb(220, 60, 50)
+    } else {
+        Color32::from_rgb(230, 180, 40)
+    };
+    ui.label(RichText::new("⚠").color(color).strong())
+        .on_hover_text(status.message());
+}
+
+/// Selection, activation, and the context menu of a tree row; `opacity` and
+/// `position` are a body's, edited from its menu.
+fn handle_response(
+    ui: &Ui,
+    response: Response,
+    id: TreeItemId,
+    opacity: Option<f32>,
+    position: Option<[f64; 3]>,
+    result: &mut TreeUiResult,
+) {
     if response.clicked() {
         result.selection = Some(id);
+        result.extend = ui.input(|input| input.modifiers.command || input.modifiers.shift);
     }
     if response.double_clicked() {
         result.activation = Some(id);
     }
+    if matches!(id, TreeItemId::Body(_) | TreeItemId::Feature(_)) {
+        response.context_menu(|ui| {
+            if ui.button("Zoom to").clicked() {
+                result.zoom_to = Some(id);
+                ui.close();
+            }
+            if let (TreeItemId::Body(body), Some(mut opacity)) = (id, opacity) {
+                ui.separator();
+                ui.label("Opacity");
+                if ui
+                    .add(egui::Slider::new(&mut opacity, 0.05..=1.0).fixed_decimals(2))
+                    .on_hover_text(
+                        "Set in the active display state (one is added if none is active)",
+                    )
+                    .changed()
+                {
+                    result.opacity_change = Some((body, opacity));
+                }
+            }
+            if let (TreeItemId::Body(body), Some(mut position)) = (id, position) {
+                ui.separator();
+                ui.label("Position").on_hover_text(
+                    "Where the body's origin lies in the world; its geometry moves with it",
+                );
+                let mut changed = false;
+                for (value, axis) in position.iter_mut().zip(["X", "Y", "Z"]) {
+                    ui.horizontal(|ui| {
+                        ui.label(axis);
+                        changed |= ui
+                            .add(egui::DragValue::new(value).speed(1.0).suffix(" mm"))
+                            .changed();
+                    });
+                }
+                if changed {
+                    result.position_change = Some((body, position));
+                }
+            }
+        });
+    }
 }
 
 fn compose_label(node: &TreeNode) -> RichText {
@@ -250,7 +489,7 @@ fn compose_label(node: &TreeNode) -> RichText {
     let text = pieces.join(" ");
 
     let mut rich = RichText::new(text);
-    if node.suppressed || !node.visible {
+    if node.suppressed || (!node.visible && !node.toggles_visibility) {
         rich = rich.color(Color32::from_gray(150)).italics();
     }
     rich
@@ -267,5 +506,10 @@ fn feature_tooltip(node: &FeatureNode) -> String {
     if node.dirty {
         parts.push("Pending recompute".into());
     }
+    match &node.status {
+        Some(FeatureStatus::Error(message)) => parts.push(format!("Error: {message}")),
+        Some(FeatureStatus::Warning(message)) => parts.push(format!("Warning: {message}")),
+        None => {}
+    }
     parts.join("\n")
 }
@@ -1,37 +1,85 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use axes::AxisSystem;
-use core_document::{DocumentService, WorkbenchId};
-use egui::{self, Color32, Context};
+use core_document::{
+    BodyMesh, DocumentService, JobId, JobStatus, PrintVolume, UnitFormat, WorkbenchId,
+};
+use egui::{self, Color32, Context, Ui};
+use settings::PanelId;
 
 use crate::log_panel;
+use crate::recent::RecentDocuments;
+use crate::reference_image_overlay::{self, ReferenceImageOverlay, ReferenceImageTextures};
+use crate::section_view::SectionAlign;
 use glam::Vec3;
 use workbenches::REGISTERED_WORKBENCHES;
 
-use super::{feature_tree, ActiveTool, ActiveWorkbench};
+use super::{
+    display_states_panel, feature_tree, library_panel, task_panel, ActiveTool, ActiveWorkbench,
+    AxisPromptChoice, RecoveryChoice,
+};
+
+/// Size of the previews in the recent documents menu.
+const RECENT_THUMBNAIL_SIZE: f32 = 48.0;
 
 pub struct TopBarResult {
     pub open_requested: bool,
+    pub open_recent: Option<PathBuf>,
     pub save_requested: bool,
     pub save_as_requested: bool,
     pub new_body_requested: bool,
     pub reset_view_requested: bool,
+    pub zoom_to_selection_requested: bool,
+    /// Hide everything but the selection.
+    pub isolate_requested: bool,
+    pub exit_isolation_requested: bool,
+    pub measure_toggled: bool,
+    /// An option in the View menu was toggled.
+    pub view_changed: bool,
+    /// Move the section plane to the view or the selected plane.
+    pub section_align: Option<SectionAlign>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_top_panel(
     ctx: &Context,
     active_workbench: &mut ActiveWorkbench,
     show_settings: &mut bool,
+    show_materials: &mut bool,
+    show_display_states: &mut bool,
+    show_parameters: &mut bool,
+    show_library: &mut bool,
+    show_bom: &mut bool,
+    rendering: &mut settings::RenderingSettings,
+    projection: &mut settings::ProjectionMode,
+    panels: &mut settings::PanelLayout,
+    recent: &RecentDocuments,
+    recent_textures: &mut HashMap<PathBuf, egui::TextureHandle>,
     active_tool: &mut ActiveTool,
     registry: &mut DocumentService,
     document: &mut core_document::Document,
     active_document_object: Option<core_document::FeatureId>,
-    selected_body_id: Option<core_document::BodyId>,
+    selection: &core_document::Selection,
+    isolated: bool,
+    // Whether the viewer is measuring; None outside the viewer, which shows
+    // no editing, saving, or workbench controls.
+    measuring: Option<bool>,
 ) -> TopBarResult {
+    let viewer = measuring.is_some();
     let mut result = TopBarResult {
         open_requested: false,
+        open_recent: None,
         save_requested: false,
         save_as_requested: false,
         new_body_requested: false,
         reset_view_requested: false,
+        zoom_to_selection_requested: false,
+        isolate_requested: false,
+        exit_isolation_requested: false,
+        measure_toggled: false,
+        view_changed: false,
+        section_align: None,
     };
     egui::TopBottomPanel::top("top_bar")
         .frame(
@@ -42,11 +90,81 @@ pub fn draw_top_panel(
         .show(ctx, |ui| {
             ui.vertical(|ui| {
                 ui.horizontal(|ui| {
-                    ui.heading("printCAD");
+                    ui.heading(if viewer {
+                        "printCAD Viewer"
+                    } else {
+                        "printCAD"
+                    });
                     ui.separator();
                     if ui.button("Settings").clicked() {
                         *show_settings = true;
                     }
+                    if !viewer && ui.button("Materials").clicked() {
+                        *show_materials = true;
+                    }
+                    if !viewer && ui.button("Parameters").clicked() {
+                        *show_parameters = true;
+                    }
+                    if !viewer && ui.button("Library").clicked() {
+                        *show_library = true;
+                    }
+                    if ui
+                        .button("BOM")
+                        .on_hover_text("Bill of Materials")
+                        .clicked()
+                    {
+                        *show_bom = true;
+                    }
+                    ui.menu_button("View", |ui| {
+                        result.view_changed |= ui
+                            .checkbox(&mut rendering.show_origin_triad, "World Origin Triad")
+                            .changed();
+                        result.view_changed |= ui
+                            .checkbox(&mut rendering.show_axis_lines, "Axis Lines")
+                            .changed();
+                        result.view_changed |= ui
+                            .checkbox(&mut rendering.show_scale_bar, "Scale Bar")
+                            .changed();
+                        result.view_changed |=
+                            ui.checkbox(&mut rendering.show_rulers, "Rulers").changed();
+                        if ui
+                            .button("Reset Panel Layout")
+                            .on_hover_text("Dock the panels back where they started")
+                            .clicked()
+                        {
+                            *panels = settings::PanelLayout::default();
+                            result.view_changed = true;
+                            ui.close();
+                        }
+                        ui.separator();
+                        if ui
+                            .add_enabled(
+                                !selection.is_empty(),
+                                egui::Button::new("Isolate Selection"),
+                            )
+                            .on_hover_text(
+                                "Hide everything except the selected bodies and features",
+                            )
+                            .clicked()
+                        {
+                            result.isolate_requested = true;
+                            ui.close();
+                        }
+                        if ui
+                            .add_enabled(isolated, egui::Button::new("Exit Isolation"))
+                            .clicked()
+                        {
+                            result.exit_isolation_requested = true;
+                            ui.close();
+                        }
+                        ui.separator();
+                        result.view_changed |=
+                            section_menu(ui, &mut rendering.section, &mut result.section_align);
+                    });
+                    display_states_panel::display_state_combo(ui, document, show_display_states);
+                    if viewer {
+                        return;
+                    }
                     ui.separator();
                     ui.label("Workbench:");
                     let workbenches = REGISTERED_WORKBENCHES.lock().unwrap();
@@ -63,24 +181,80 @@ pub fn draw_top_panel(
                     if ui.button("Open").clicked() {
                         result.open_requested = true;
                     }
-                    if ui.button("Save").clicked() {
-                        result.save_requested = true;
+                    ui.menu_button("Recent", |ui| {
+                        if recent.paths().is_empty() {
+                            ui.weak("No recent documents");
+                        }
+                        for path in recent.paths() {
+                            if recent_document_button(ui, recent, recent_textures, path).clicked() {
+                                result.open_recent = Some(path.clone());
+                                ui.close();
+                            }
+                        }
+                    });
+                    if !viewer {
+                        if ui.button("Save").clicked() {
+                            result.save_requested = true;
+                        }
+                        if ui.button("Save As").clicked() {
+                            result.save_as_requested = true;
+                        }
+                        ui.separator();
+                        if ui
+                            .add(egui::Button::new("New Body").min_size(egui::vec2(80.0, 0.0)))
+                            .clicked()
+                        {
+                            result.new_body_requested = true;
+                        }
                     }
-                    if ui.button("Save As").clicked() {
-                        result.save_as_requested = true;
+                    if ui.button("Fit View").clicked() {
+                        result.reset_view_requested = true;
                     }
-                    ui.separator();
                     if ui
-                        .add(egui::Button::new("New Body").min_size(egui::vec2(80.0, 0.0)))
+                        .add_enabled(
+                            !selection.is_empty(),
+                            egui::Button::new("Zoom to Selection"),
+                        )
                         .clicked()
                     {
-                        result.new_body_requested = true;
+                        result.zoom_to_selection_requested = true;
                     }
-                    if ui.button("Fit View").clicked() {
-                        result.reset_view_requested = true;
+                    let orthographic = *projection == settings::ProjectionMode::Orthographic;
+                    if ui
+                        .selectable_label(orthographic, "Orthographic")
+                        .on_hover_text("Parallel projection without perspective foreshortening")
+                        .clicked()
+                    {
+                        *projection = if orthographic {
+                            settings::ProjectionMode::Perspective
+                        } else {
+                            settings::ProjectionMode::Orthographic
+                        };
+                        result.view_changed = true;
+                    }
+                    let section = &mut rendering.section;
+                    if ui
+                        .selectable_label(section.enabled, "Section")
+                        .on_hover_text("Cut the bodies with the section plane set in the View menu")
+                        .clicked()
+                    {
+                        section.enabled = !section.enabled;
+                        result.view_changed = true;
+                    }
+                    if let Some(measuring) = measuring {
+                        ui.separator();
+                        result.measure_toggled = ui
+                            .selectable_label(measuring, "Measure")
+                            .on_hover_text(
+                                "Click two points on the model to measure their distance",
+                            )
+                            .clicked();
                     }
                 });
 
+                if viewer {
+                    return;
+                }
                 ui.add_space(6.0);
 
                 ui.horizontal_wrapped(|ui| {
@@ -98,7 +272,7 @@ pub fn draw_top_panel(
                         document, cam_pos, cam_target, viewport,
                     );
                     wb_ctx.active_document_object = active_document_object;
-                    wb_ctx.selected_body_id = selected_body_id.map(|id| id.0);
+                    wb_ctx.selection = selection.clone();
 
                     // Get workbench once for tool enabling checks (now we can get mutable borrow)
                     let workbench = match registry.workbench_mut(&active_workbench.0) {
@@ -122,13 +296,17 @@ pub fn draw_top_panel(
                                 egui::Button::new(&tool.label).selected(is_active),
                             )
                         };
+                        let button = match tool.shortcut {
+                            Some(key) => button.on_hover_text(format!("Shortcut: {key:?}")),
+                            None => button,
+                        };
 
                         if button.clicked() && enabled {
                             match tool.behavior {
                                 core_document::ToolBehavior::Action => {
                                     // Fire-and-forget: always select the action tool for this frame.
                                     // The host will clear it after handling the input.
-                                    active_tool.active_ids.insert(tool.id.clone());
+                                    active_tool.activate(tool, &tools);
                                 }
                                 core_document::ToolBehavior::Check => {
                                     // Check behavior: toggle independently
@@ -144,23 +322,7 @@ pub fn draw_top_panel(
                                         // Clicking an active tool deactivates it
                                         active_tool.active_ids.remove(&tool.id);
                                     } else {
-                                        // Deactivate other tools in the same group
-                                        if let Some(group) = &tool.group {
-                                            // Remove all tools in this group
-                                            active_tool.active_ids.retain(|active_id| {
-                                                // Find the tool descriptor to check its group
-                                                tools
-                                                    .iter()
-                                                    .find(|t| &t.id == active_id)
-                                                    .map(|t| t.group.as_deref() != Some(group))
-                                                    .unwrap_or(true)
-                                            });
-                                        } else {
-                                            // No group: this tool is its own group, so just clear all
-                                            active_tool.active_ids.clear();
-                                        }
-                                        // Activate this tool
-                                        active_tool.active_ids.insert(tool.id.clone());
+                                        active_tool.activate(tool, &tools);
                                     }
                                 }
                             }
@@ -172,158 +334,321 @@ pub fn draw_top_panel(
     result
 }
 
+/// Section view entries of the View menu. Returns whether anything changed;
+/// moving the plane to the view or the selection is left to the caller.
+fn section_menu(
+    ui: &mut egui::Ui,
+    section: &mut settings::SectionSettings,
+    align: &mut Option<SectionAlign>,
+) -> bool {
+    let mut changed = ui.checkbox(&mut section.enabled, "Section View").changed();
+    ui.add_enabled_ui(section.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Normal:");
+            for axis in settings::SectionAxis::ALL {
+                let selected = section.custom_plane.is_none() && section.axis == axis;
+                if ui.selectable_label(selected, axis.label()).clicked() && !selected {
+                    section.axis = axis;
+                    section.custom_plane = None;
+                    section.offset = 0.0;
+                    changed = true;
+                }
+            }
+            changed |= ui.checkbox(&mut section.flip, "Flip").changed();
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("Align to View")
+                .on_hover_text("Put the plane through the orbit center, facing the camera")
+                .clicked()
+            {
+                *align = Some(SectionAlign::View);
+            }
+            if ui
+                .button("Selected Plane")
+                .on_hover_text("Put the plane on the selected origin plane or datum plane")
+                .clicked()
+            {
+                *align = Some(SectionAlign::SelectedPlane);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Offset:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut section.offset)
+                        .speed(0.5)
+                        .suffix(" mm"),
+                )
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Fill:");
+            for fill in settings::SectionFill::ALL {
+                changed |= ui
+                    .selectable_value(&mut section.fill, fill, fill.label())
+                    .changed();
+            }
+        });
+        if section.fill == settings::SectionFill::Hatched {
+            ui.horizontal(|ui| {
+                ui.label("Hatch spacing:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut section.hatch_spacing)
+                            .range(0.1..=100.0)
+                            .speed(0.1)
+                            .suffix(" mm"),
+                    )
+                    .changed();
+            });
+        }
+    });
+    changed
+}
+
+/// Entry of the recent documents menu: the saved preview and the file name.
+fn recent_document_button(
+    ui: &mut egui::Ui,
+    recent: &RecentDocuments,
+    textures: &mut HashMap<PathBuf, egui::TextureHandle>,
+    path: &std::path::Path,
+) -> egui::Response {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let size = egui::vec2(RECENT_THUMBNAIL_SIZE, RECENT_THUMBNAIL_SIZE);
+    let button = match recent.thumbnail(path) {
+        Some(image) => {
+            let texture = library_panel::thumbnail_texture(ui.ctx(), textures, path, image);
+            egui::Button::image_and_text(egui::Image::new((texture, size)), name)
+        }
+        None => egui::Button::new(name).min_size(egui::vec2(0.0, size.y)),
+    };
+    ui.add(button).on_hover_text(path.display().to_string())
+}
+
+#[derive(Default)]
 pub struct LeftPanelResult {
     pub finish_sketch_requested: bool,
+    /// Commands the workbench queued from its panel.
+    pub commands: Vec<String>,
     pub tree_selection: Option<feature_tree::TreeItemId>,
+    pub tree_extend: bool,
     pub tree_activation: Option<feature_tree::TreeItemId>,
+    pub tree_zoom_to: Option<feature_tree::TreeItemId>,
 }
 
-impl Default for LeftPanelResult {
-    fn default() -> Self {
-        Self {
-            finish_sketch_requested: false,
-            tree_selection: None,
-            tree_activation: None,
-        }
-    }
-}
-
-pub fn draw_left_panel(
-    ctx: &Context,
+#[allow(clippy::too_many_arguments)]
+pub fn model_panel_ui(
+    ui: &mut Ui,
     active_workbench: ActiveWorkbench,
     document: &mut core_document::Document,
     registry: &mut core_document::DocumentService,
     active_tree_selection: Option<feature_tree::TreeItemId>,
+    tree_reveal: Option<feature_tree::TreeItemId>,
     active_document_object: Option<core_document::FeatureId>,
+    selection: &mut core_document::Selection,
+    units: &UnitFormat,
+    axis_system: AxisSystem,
+    body_meshes: &[BodyMesh],
+    frame: [f64; 3],
+    printer: Option<PrintVolume>,
 ) -> LeftPanelResult {
     let mut panel_result = LeftPanelResult::default();
 
-    egui::SidePanel::left("left_panel")
-        .resizable(true)
-        .default_width(260.0)
-        .show(ctx, |ui| {
-            ui.heading("Model");
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let tree_model = feature_tree::DocumentTree::build(document);
-                let selected_id = active_tree_selection
-                    .or_else(|| active_document_object.map(feature_tree::TreeItemId::from))
-                    .unwrap_or(feature_tree::TreeItemId::DocumentRoot);
-                let tree_ui_result = feature_tree::draw_tree(ui, &tree_model, Some(selected_id));
-                panel_result.tree_selection = tree_ui_result.selection;
-                panel_result.tree_activation = tree_ui_result.activation;
-            });
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        let tree_model = feature_tree::DocumentTree::build(document);
+        let selected_id = active_tree_selection
+            .or_else(|| active_document_object.map(feature_tree::TreeItemId::from))
+            .unwrap_or(feature_tree::TreeItemId::DocumentRoot);
+        let highlight = feature_tree::TreeHighlight {
+            current: Some(selected_id),
+            selection,
+            reveal: tree_reveal,
+        };
+        let tree_ui_result = feature_tree::draw_tree(ui, &tree_model, &highlight);
+        panel_result.tree_selection = tree_ui_result.selection;
+        panel_result.tree_extend = tree_ui_result.extend;
+        panel_result.tree_zoom_to = tree_ui_result.zoom_to;
+        panel_result.tree_activation = tree_ui_result.activation;
+        if let Some((feature_tree::TreeItemId::OriginElement(origin), visible)) =
+            tree_ui_result.visibility_change
+        {
+            document.set_origin_visible(origin, visible);
+        }
+        if let Some((body, opacity)) = tree_ui_result.opacity_change {
+            display_states_panel::set_body_opacity(document, body, opacity);
+        }
+        if let Some((body, position)) = tree_ui_result.position_change {
+            document.set_body_position(body, position);
+        }
+    });
 
-            ui.separator();
+    ui.separator();
 
-            // Call workbench's ui_left_panel hook
-            if let Ok(wb) = registry.workbench_mut(&active_workbench.0) {
-                // Build a minimal runtime context for UI hooks
-                let cam_pos = [0.0, 0.0, 5.0]; // Placeholder
-                let cam_target = [0.0, 0.0, 0.0]; // Placeholder
-                let viewport = (0, 0, 1920, 1080); // Placeholder
-                let mut ctx = core_document::WorkbenchRuntimeContext::new(
-                    document, cam_pos, cam_target, viewport,
-                );
-                ctx.active_document_object = active_document_object;
-
-                wb.ui_left_panel(ui, &mut ctx);
-
-                // Check for finish sketch request
-                if ctx.finish_sketch_requested {
-                    panel_result.finish_sketch_requested = true;
-                }
-            }
-        });
+    // Call workbench's ui_left_panel hook
+    if let Ok(wb) = registry.workbench_mut(&active_workbench.0) {
+        // Build a minimal runtime context for UI hooks
+        let cam_pos = [0.0, 0.0, 5.0]; // Placeholder
+        let cam_target = [0.0, 0.0, 0.0]; // Placeholder
+        let viewport = (0, 0, 1920, 1080); // Placeholder
+        let mut ctx =
+            core_document::WorkbenchRuntimeContext::new(document, cam_pos, cam_target, viewport);
+        ctx.active_document_object = active_document_object;
+        ctx.selection = selection.clone();
+        ctx.units = *units;
+        ctx.axes = axis_system;
+        ctx.body_meshes = body_meshes;
+        ctx.frame = frame;
+        ctx.printer = printer;
+
+        wb.ui_left_panel(ui, &mut ctx);
+        *selection = std::mem::take(&mut ctx.selection);
+        panel_result.commands = ctx.drain_commands();
+
+        // Check for finish sketch request
+        if ctx.finish_sketch_requested {
+            panel_result.finish_sketch_requested = true;
+        }
+    }
 
     panel_result
 }
 
-pub fn draw_right_panel(
-    ctx: &Context,
+#[derive(Default)]
+pub struct RightPanelResult {
+    pub camera_focus_request: Option<core_document::CameraFocusRequest>,
+    pub file_open_request: Option<core_document::FileOpenRequest>,
+    /// Commands the workbench queued from its panel.
+    pub commands: Vec<String>,
+}
+
+impl RightPanelResult {
+    /// Add what the workbench did in another of its panels this frame.
+    pub fn merge(&mut self, other: RightPanelResult) {
+        self.camera_focus_request = self
+            .camera_focus_request
+            .take()
+            .or(other.camera_focus_request);
+        self.file_open_request = self.file_open_request.take().or(other.file_open_request);
+        self.commands.extend(other.commands);
+    }
+}
+
+/// Whether the workbench has anything for the Properties panel, and whether
+/// that is a task.
+pub fn properties_content(
+    registry: &mut core_document::DocumentService,
+    active_workbench: &ActiveWorkbench,
+) -> (bool, bool) {
+    registry
+        .workbench_mut(&active_workbench.0)
+        .map(|wb| {
+            let task = wb.active_task().is_some();
+            (wb.wants_right_panel() || task, task)
+        })
+        .unwrap_or_default()
+}
+
+/// Whether the workbench has anything for the Analysis panel.
+pub fn analysis_content(
+    registry: &mut core_document::DocumentService,
+    active_workbench: &ActiveWorkbench,
+) -> bool {
+    registry
+        .workbench_mut(&active_workbench.0)
+        .is_ok_and(|wb| wb.wants_analysis_panel())
+}
+
+/// A panel drawn by the workbench: for [`PanelId::Properties`] its right
+/// panel or the form of its open task, for [`PanelId::Analysis`] its
+/// analyses.
+#[allow(clippy::too_many_arguments)]
+pub fn workbench_panel_ui(
+    ui: &mut Ui,
+    panel: PanelId,
     active_workbench: ActiveWorkbench,
     document: &mut core_document::Document,
     registry: &mut core_document::DocumentService,
     active_document_object: Option<core_document::FeatureId>,
-) {
-    let wants_panel = registry
-        .workbench_mut(&active_workbench.0)
-        .map(|wb| wb.wants_right_panel())
-        .unwrap_or(false);
-
-    if !wants_panel {
-        return;
+    selection: &mut core_document::Selection,
+    units: &UnitFormat,
+    axis_system: AxisSystem,
+    body_meshes: &[BodyMesh],
+    frame: [f64; 3],
+    printer: Option<PrintVolume>,
+) -> RightPanelResult {
+    let mut panel_result = RightPanelResult::default();
+    if let Ok(wb) = registry.workbench_mut(&active_workbench.0) {
+        let cam_pos = [0.0, 0.0, 5.0];
+        let cam_target = [0.0, 0.0, 0.0];
+        let viewport = (0, 0, 1920, 1080);
+        let mut ctx =
+            core_document::WorkbenchRuntimeContext::new(document, cam_pos, cam_target, viewport);
+        ctx.active_document_object = active_document_object;
+        ctx.selection = selection.clone();
+        ctx.units = *units;
+        ctx.axes = axis_system;
+        ctx.body_meshes = body_meshes;
+        ctx.frame = frame;
+        ctx.printer = printer;
+        match (panel, wb.active_task()) {
+            (PanelId::Analysis, _) => wb.ui_analysis_panel(ui, &mut ctx),
+            (_, Some(task)) => task_panel::task_ui(ui, wb.as_mut(), &mut ctx, &task),
+            (_, None) => wb.ui_right_panel(ui, &mut ctx),
+        }
+        *selection = std::mem::take(&mut ctx.selection);
+        panel_result.camera_focus_request = ctx.camera_focus_request.take();
+        panel_result.file_open_request = ctx.file_open_request.take();
+        panel_result.commands = ctx.drain_commands();
     }
 
-    egui::SidePanel::right("right_panel")
-        .resizable(true)
-        .default_width(280.0)
-        .show(ctx, |ui| {
-            if let Ok(wb) = registry.workbench_mut(&active_workbench.0) {
-                let cam_pos = [0.0, 0.0, 5.0];
-                let cam_target = [0.0, 0.0, 0.0];
-                let viewport = (0, 0, 1920, 1080);
-                let mut ctx = core_document::WorkbenchRuntimeContext::new(
-                    document, cam_pos, cam_target, viewport,
-                );
-                ctx.active_document_object = active_document_object;
-                wb.ui_right_panel(ui, &mut ctx);
-            }
-        });
+    panel_result
 }
 
-pub fn draw_log_panel(ctx: &Context, show: bool) {
-    if !show {
-        return;
-    }
+/// Whether the log panel has anything to show.
+pub fn log_has_entries(show: bool) -> bool {
+    show && !log_panel::entries().is_empty()
+}
 
+pub fn log_panel_ui(ui: &mut Ui) {
     let entries = log_panel::entries();
-    if entries.is_empty() {
-        return;
+    if ui.button("Clear").clicked() {
+        log_panel::clear();
     }
 
-    egui::TopBottomPanel::bottom("log_panel")
-        .resizable(true)
-        .default_height(160.0)
-        .min_height(80.0)
-        .show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.heading("Log");
-                ui.add_space(8.0);
-                if ui.button("Clear").clicked() {
-                    log_panel::clear();
-                }
-            });
-            ui.separator();
-
-            egui::ScrollArea::vertical()
-                .auto_shrink([false, false])
-                .stick_to_bottom(true)
-                .show(ui, |ui| {
-                    for entry in entries {
-                        let secs = entry.timestamp_secs % 86_400;
-                        let h = secs / 3600;
-                        let m = (secs % 3600) / 60;
-                        let s = secs % 60;
-                        let time_str = format!("{h:02}:{m:02}:{s:02}");
-                        let (label, color) = match entry.level {
-                            log_panel::LogLevel::Info => ("INFO", Color32::from_rgb(180, 220, 255)),
-                            log_panel::LogLevel::Warn => ("WARN", Color32::from_rgb(255, 210, 120)),
-                            log_panel::LogLevel::Error => {
-                                ("ERROR", Color32::from_rgb(255, 140, 140))
-                            }
-                        };
-                        ui.colored_label(color, format!("[{time_str}] {label}: {}", entry.message));
-                    }
-                });
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for entry in entries {
+                let secs = entry.timestamp_secs % 86_400;
+                let h = secs / 3600;
+                let m = (secs % 3600) / 60;
+                let s = secs % 60;
+                let time_str = format!("{h:02}:{m:02}:{s:02}");
+                let (label, color) = match entry.level {
+                    log_panel::LogLevel::Info => ("INFO", Color32::from_rgb(180, 220, 255)),
+                    log_panel::LogLevel::Warn => ("WARN", Color32::from_rgb(255, 210, 120)),
+                    log_panel::LogLevel::Error => ("ERROR", Color32::from_rgb(255, 140, 140)),
+                };
+                ui.colored_label(color, format!("[{time_str}] {label}: {}", entry.message));
+            }
         });
 }
 
+/// Status bar. Returns a job the user chose to cancel.
 pub fn draw_bottom_panel(
     ctx: &Context,
     fps: f32,
     hovered_point: Option<[f32; 3]>,
     axis_system: AxisSystem,
-) {
+    units: &UnitFormat,
+    running_jobs: &[JobStatus],
+) -> Option<JobId> {
+    let mut cancel = None;
     egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
         ui.horizontal(|ui| {
             let fps_text = if fps > 0.0 {
@@ -344,10 +669,10 @@ pub fn draw_bottom_panel(
                 let mut parts = Vec::with_capacity(3);
                 for (idx, (role, axis)) in axes.iter().enumerate() {
                     parts.push(format!(
-                        "{}({}): {:.3}",
+                        "{}({}): {}",
                         role,
                         axis.signed_label(),
-                        values[idx]
+                        units.format_length(values[idx])
                     ));
                 }
                 ui.label(parts.join("  "));
@@ -358,8 +683,197 @@ pub fn draw_bottom_panel(
                 }
                 ui.label(parts.join("  "));
             }
+            if !running_jobs.is_empty() {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    for job in running_jobs.iter().rev() {
+                        if ui
+                            .small_button("✖")
+                            .on_hover_text(format!("Cancel {}", job.label))
+                            .clicked()
+                        {
+                            cancel = Some(job.id);
+                        }
+                        match job.progress {
+                            Some(progress) => {
+                                ui.add(
+                                    egui::ProgressBar::new(progress)
+                                        .desired_width(120.0)
+                                        .show_percentage(),
+                                );
+                            }
+                            None => {
+                                ui.spinner();
+                            }
+                        }
+                        ui.label(&job.label);
+                    }
+                });
+            }
         });
     });
+    cancel
+}
+
+/// Ask whether to view a document with the axis convention it was authored with.
+pub fn draw_axis_prompt(
+    ctx: &Context,
+    document_axes: AxisSystem,
+    current_axes: AxisSystem,
+) -> Option<AxisPromptChoice> {
+    let describe = |axes: AxisSystem| match axes.preset() {
+        Some(preset) => preset.label().to_string(),
+        None => axes.label(),
+    };
+
+    let mut choice = None;
+    egui::Window::new("Different axis convention")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("This document was saved with a different axis convention:");
+            egui::Grid::new("axis_prompt_grid")
+                .num_columns(2)
+                .spacing([8.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Document:");
+                    ui.strong(describe(document_axes));
+                    ui.end_row();
+                    ui.label("Your preference:");
+                    ui.label(describe(current_axes));
+                    ui.end_row();
+                });
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Use document's axes").clicked() {
+                    choice = Some(AxisPromptChoice::UseDocument(document_axes));
+                }
+                if ui.button("Keep my preference").clicked() {
+                    choice = Some(AxisPromptChoice::KeepMine);
+                }
+            });
+        });
+    choice
+}
+
+/// Offer to reopen the document, workbench, and view of the previous session.
+/// Returns `Some(true)` to restore, `Some(false)` to start fresh.
+/// Banner across the top of the viewport while isolating, saying what is
+/// shown; returns whether its exit button was clicked.
+pub fn draw_isolation_banner(ctx: &Context, viewport: egui::Rect, summary: &str) -> bool {
+    let mut exit = false;
+    egui::Area::new(egui::Id::new("isolation_banner"))
+        .order(egui::Order::Foreground)
+        .pivot(egui::Align2::CENTER_TOP)
+        .fixed_pos(viewport.center_top() + egui::vec2(0.0, 8.0))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style())
+                .fill(Color32::from_rgb(150, 95, 20))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            egui::RichText::new(format!("Isolated: {summary}"))
+                                .strong()
+                                .color(Color32::WHITE),
+                        );
+                        ui.label(
+                            egui::RichText::new("everything else is hidden")
+                                .color(Color32::from_gray(230)),
+                        );
+                        if ui.button("Exit Isolation").clicked() {
+                            exit = true;
+                        }
+                    });
+                });
+        });
+    exit
+}
+
+/// Choice in the menu opened by right-clicking the viewport with a tool active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolMenuAction {
+    /// Complete the tool's operation in progress and exit the tool.
+    Finish,
+    /// Abort the tool's operation in progress and exit the tool.
+    Cancel,
+}
+
+/// Menu of the active tool at `pos`. Returns the choice made, and whether the
+/// menu stays open (it closes on a choice or a click elsewhere).
+pub fn draw_tool_menu(
+    ctx: &Context,
+    pos: egui::Pos2,
+    tool: &str,
+) -> (Option<ToolMenuAction>, bool) {
+    let mut action = None;
+    let response = egui::Area::new(egui::Id::new("tool_menu"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(pos)
+        .show(ctx, |ui| {
+            egui::Frame::menu(ui.style()).show(ui, |ui| {
+                ui.label(egui::RichText::new(tool).weak());
+                ui.separator();
+                if ui.button("Finish").clicked() {
+                    action = Some(ToolMenuAction::Finish);
+                }
+                if ui.button("Cancel").clicked() {
+                    action = Some(ToolMenuAction::Cancel);
+                }
+            });
+        })
+        .response;
+    let open = action.is_none() && !response.clicked_elsewhere();
+    (action, open)
+}
+
+pub fn draw_session_prompt(ctx: &Context, document: &std::path::Path) -> Option<bool> {
+    let mut choice = None;
+    egui::Window::new("Restore previous session?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("printCAD was last closed with this document open:");
+            ui.strong(document.display().to_string());
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    choice = Some(true);
+                }
+                if ui.button("Start fresh").clicked() {
+                    choice = Some(false);
+                }
+            });
+        });
+    choice
+}
+
+/// Offer to open the autosaved recovery copy `recovery`, which is newer than the
+/// document just opened.
+pub fn draw_recovery_prompt(ctx: &Context, recovery: &std::path::Path) -> Option<RecoveryChoice> {
+    let mut choice = None;
+    egui::Window::new("Recover unsaved changes?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("Autosave kept changes to this document that were never saved:");
+            ui.strong(recovery.display().to_string());
+            ui.label("Recovering opens them in place of the file; saving then writes them to it.");
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Recover").clicked() {
+                    choice = Some(RecoveryChoice::Recover);
+                }
+                if ui.button("Discard").clicked() {
+                    choice = Some(RecoveryChoice::Discard);
+                }
+                if ui.button("Not now").clicked() {
+                    choice = Some(RecoveryChoice::Keep);
+                }
+            });
+        });
+    choice
 }
 
 pub fn draw_pivot_indicator(ctx: &Context, x: f32, y: f32) {
@@ -400,6 +914,59 @@ pub fn draw_pivot_indicator(ctx: &Context, x: f32, y: f32) {
     );
 }
 
+/// Draw reference images as textured meshes in the viewport area, under the
+/// screen-space overlays.
+pub fn draw_reference_images(
+    ctx: &egui::Context,
+    images: &[ReferenceImageOverlay],
+    textures: &mut ReferenceImageTextures,
+    document: &core_document::Document,
+) {
+    if images.is_empty() {
+        return;
+    }
+
+    let viewport_rect = ctx.available_rect();
+    let painter = ctx
+        .layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("reference_images"),
+        ))
+        .with_clip_rect(viewport_rect);
+    let ppp = ctx.pixels_per_point();
+    let side = (reference_image_overlay::GRID + 1) as u32;
+
+    for image in images {
+        let Some(texture) = textures.texture(ctx, document, image.asset) else {
+            continue;
+        };
+        let tint = Color32::from_white_alpha((image.opacity * 255.0) as u8);
+        let mut mesh = egui::Mesh::with_texture(texture);
+        for (index, point) in image.points.iter().enumerate() {
+            let (row, column) = (index as u32 / side, index as u32 % side);
+            let uv = egui::pos2(
+                column as f32 / (side - 1) as f32,
+                row as f32 / (side - 1) as f32,
+            );
+            let pos = viewport_rect.min + egui::vec2(point[0], point[1]) / ppp;
+            mesh.vertices.push(egui::epaint::Vertex {
+                pos,
+                uv,
+                color: tint,
+            });
+        }
+        for row in 0..side - 1 {
+            for column in 0..side - 1 {
+                let top_left = row * side + column;
+                let bottom_left = top_left + side;
+                mesh.add_triangle(top_left, top_left + 1, bottom_left);
+                mesh.add_triangle(top_left + 1, bottom_left + 1, bottom_left);
+            }
+        }
+        painter.add(mesh);
+    }
+}
+
 /// Draw screen-space overlays in the viewport area.
 /// These are rendered as 2D lines in screen coordinates, maintaining constant thickness.
 pub fn draw_screen_space_overlays(
@@ -441,3 +1008,46 @@ pub fn draw_screen_space_overlays(
         painter.line_segment([start, end], egui::Stroke::new(stroke_width, color));
     }
 }
+
+/// Draw screen-space labels (annotation text) in the viewport area, each on a
+/// framed background so it reads over the model.
+pub fn draw_screen_space_labels(ctx: &egui::Context, labels: &[core_document::ScreenSpaceLabel]) {
+    if labels.is_empty() {
+        return;
+    }
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("screen_space_labels"),
+    ));
+
+    let ppp = ctx.pixels_per_point();
+    let viewport_rect = ctx.available_rect();
+    let font = egui::FontId::proportional(13.0);
+    let padding = egui::vec2(5.0, 3.0);
+
+    for label in labels {
+        let anchor = egui::pos2(
+            viewport_rect.min.x + label.position[0] / ppp,
+            viewport_rect.min.y + label.position[1] / ppp,
+        );
+        let color = Color32::from_rgb(
+            (label.color[0] * 255.0) as u8,
+            (label.color[1] * 255.0) as u8,
+            (label.color[2] * 255.0) as u8,
+        );
+
+        let galley = painter.layout_no_wrap(label.text.clone(), font.clone(), Color32::WHITE);
+        let size = galley.size() + padding * 2.0;
+        let rect = egui::Rect::from_min_size(anchor - egui::vec2(0.0, size.y), size);
+        let stroke_width = if label.highlighted { 2.0 } else { 1.0 };
+        painter.rect(
+            rect,
+            3.0,
+            Color32::from_black_alpha(200),
+            egui::Stroke::new(stroke_width, color),
+            egui::StrokeKind::Inside,
+        );
+        painter.galley(rect.min + padding, galley, Color32::WHITE);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use egui::{self, Context, TextureHandle, Ui};
+
+use crate::library::{LibraryEntry, PartLibrary};
+
+const TILE_SIZE: f32 = 96.0;
+
+/// Part library operations handled by the app shell.
+#[derive(Debug, Clone)]
+pub enum LibraryAction {
+    /// Insert a part as new bodies, copied or linked to the file.
+    Insert {
+        entry: LibraryEntry,
+        linked: bool,
+    },
+    Rescan,
+    /// Update linked bodies whose file changed.
+    UpdateLinks,
+}
+
+/// Payload of a part dragged from the library.
+struct DraggedPart(LibraryEntry);
+
+/// Part Library panel: the parts of the library folder as tiles that can be
+/// dragged into the viewport.
+pub(super) fn library_ui(
+    ui: &mut Ui,
+    library: &PartLibrary,
+    textures: &mut HashMap<PathBuf, TextureHandle>,
+    insert_linked: &mut bool,
+) -> Option<LibraryAction> {
+    let mut action = None;
+    let Some(folder) = library.folder() else {
+        ui.label("No library folder is set.");
+        ui.weak("Choose one in Settings → Part Library.");
+        return action;
+    };
+    ui.horizontal(|ui| {
+        ui.weak(folder.display().to_string());
+        if library.is_scanning() {
+            ui.spinner();
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Rescan").clicked() {
+            action = Some(LibraryAction::Rescan);
+        }
+        if ui
+            .button("Update Links")
+            .on_hover_text("Reload linked bodies whose file changed")
+            .clicked()
+        {
+            action = Some(LibraryAction::UpdateLinks);
+        }
+        ui.checkbox(insert_linked, "Insert as link")
+            .on_hover_text("Linked bodies follow later changes to the file");
+    });
+    ui.weak("Drag a part into the viewport to insert it.");
+    ui.separator();
+
+    if library.entries().is_empty() && !library.is_scanning() {
+        ui.label("The folder has no .prtcad, STEP, or STL files.");
+        return action;
+    }
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for entry in library.entries() {
+                if let Some(linked) = part_tile(ui, library, textures, entry) {
+                    action = Some(LibraryAction::Insert {
+                        entry: entry.clone(),
+                        linked,
+                    });
+                }
+            }
+        });
+    });
+    action
+}
+
+/// One part: thumbnail and name, draggable, with insert actions in its
+/// context menu. Returns `Some(linked)` when an insertion was chosen.
+fn part_tile(
+    ui: &mut Ui,
+    library: &PartLibrary,
+    textures: &mut HashMap<PathBuf, TextureHandle>,
+    entry: &LibraryEntry,
+) -> Option<bool> {
+    let mut chosen = None;
+    let id = egui::Id::new(("library_part", &entry.path));
+    let tile = |ui: &mut Ui| {
+        ui.vertical(|ui| {
+            ui.set_width(TILE_SIZE);
+            let size = egui::vec2(TILE_SIZE, TILE_SIZE);
+            match library.thumbnail(&entry.path) {
+                Some(Some(image)) => {
+                    let texture = thumbnail_texture(ui.ctx(), textures, &entry.path, image);
+                    ui.image((texture, size));
+                }
+                thumbnail => {
+                    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                    ui.painter()
+                        .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+                    if thumbnail.is_none() {
+                        ui.put(rect, egui::Spinner::new());
+                    } else {
+                        ui.painter().text(
+                            rect.center(),
+                            egui::Align2::CENTER_CENTER,
+                            entry.format.label(),
+                            egui::FontId::proportional(14.0),
+                            ui.visuals().weak_text_color(),
+                        );
+                    }
+                }
+            }
+            ui.add(egui::Label::new(&entry.name).truncate());
+        });
+    };
+
+    let response = if entry.format.can_insert() {
+        ui.dnd_drag_source(id, DraggedPart(entry.clone()), tile)
+            .response
+    } else {
+        ui.scope(tile)
+            .response
+            .on_hover_text("STEP parts can be inserted once the geometry kernel is available")
+    };
+    let response = response.on_hover_text(format!(
+        "{} ({})",
+        entry.path.display(),
+        entry.format.label()
+    ));
+    if entry.format.can_insert() {
+        // The drag source only senses drags; right-clicks need their own sense.
+        let menu = ui.interact(response.rect, id.with("menu"), egui::Sense::click());
+        menu.context_menu(|ui| {
+            if ui.button("Insert Copy").clicked() {
+                chosen = Some(false);
+                ui.close();
+            }
+            if ui.button("Insert Linked").clicked() {
+                chosen = Some(true);
+                ui.close();
+            }
+        });
+    }
+    chosen
+}
+
+/// Texture of a preview image, uploaded the first time it is shown.
+pub(super) fn thumbnail_texture(
+    ctx: &Context,
+    textures: &mut HashMap<PathBuf, TextureHandle>,
+    path: &Path,
+    image: &egui::ColorImage,
+) -> egui::TextureId {
+    textures
+        .entry(path.to_path_buf())
+        .or_insert_with(|| {
+            ctx.load_texture(
+                format!("thumbnail:{}", path.display()),
+                image.clone(),
+                egui::TextureOptions::LINEAR,
+            )
+        })
+        .id()
+}
+
+/// Part dropped on the viewport this frame (outside the library panel, whose
+/// area is `library_rect` when it floats over the viewport).
+pub(super) fn dropped_part(
+    ctx: &Context,
+    viewport: egui::Rect,
+    library_rect: Option<egui::Rect>,
+) -> Option<LibraryEntry> {
+    if !ctx.input(|input| input.pointer.any_released()) {
+        return None;
+    }
+    let position = ctx.pointer_interact_pos()?;
+    let over_viewport =
+        viewport.contains(position) && !library_rect.is_some_and(|rect| rect.contains(position));
+    if !over_viewport {
+        return None;
+    }
+    egui::DragAndDrop::take_payload::<DraggedPart>(ctx).map(|part| part.0.clone())
+}
@@ -0,0 +1,174 @@
+use core_document::{BodyMesh, Document, MassProperties, MaterialId, UnitFormat};
+use egui::{self, Context, Ui};
+
+/// Window assigning materials to bodies, with each body's volume, mass, and
+/// cost, and the document's custom materials.
+pub(super) fn draw_materials_window(
+    ctx: &Context,
+    show_materials: &mut bool,
+    document: &mut Document,
+    body_meshes: &[BodyMesh],
+    units: &UnitFormat,
+) {
+    if !*show_materials {
+        return;
+    }
+    egui::Window::new("Materials & Mass")
+        .open(show_materials)
+        .default_width(560.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.heading("Bodies");
+            bodies_ui(ui, document, body_meshes, units);
+            ui.add_space(8.0);
+            egui::CollapsingHeader::new("Material library")
+                .default_open(false)
+                .show(ui, |ui| library_ui(ui, document));
+        });
+}
+
+fn bodies_ui(ui: &mut Ui, document: &mut Document, body_meshes: &[BodyMesh], units: &UnitFormat) {
+    if !document.has_bodies() {
+        ui.weak("The document has no bodies.");
+        return;
+    }
+    let materials = document.materials().all();
+    let library = document.materials().clone();
+    let mut assignments = Vec::new();
+    let (mut total_mass, mut total_cost) = (0.0, 0.0);
+
+    egui::Grid::new("materials_bodies")
+        .num_columns(5)
+        .striped(true)
+        .spacing([12.0, 4.0])
+        .show(ui, |ui| {
+            for header in ["Body", "Material", "Volume", "Mass", "Cost"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for body in document.bodies() {
+                ui.label(&body.name);
+                let mut material = body.material.clone();
+                let selected = material
+                    .as_ref()
+                    .and_then(|id| library.get(id))
+                    .map_or_else(|| "None".to_string(), |material| material.name);
+                egui::ComboBox::from_id_salt(("body_material", body.id.0))
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut material, None, "None");
+                        for option in &materials {
+                            ui.selectable_value(
+                                &mut material,
+                                Some(option.id.clone()),
+                                &option.name,
+                            );
+                        }
+                    });
+                if material != body.material {
+                    assignments.push((body.id, material.clone()));
+                }
+
+                let material = material.as_ref().and_then(|id| library.get(id));
+                let properties = MassProperties::of_meshes(
+                    body_meshes
+                        .iter()
+                        .filter(|m| m.body == body.id)
+                        .map(|m| &m.mesh),
+                    material.as_ref(),
+                );
+                ui.label(units.format_volume(properties.volume));
+                match (properties.mass, properties.cost) {
+                    (Some(mass), Some(cost)) => {
+                        total_mass += mass;
+                        total_cost += cost;
+                        ui.label(format_mass(mass));
+                        ui.label(library.format_price(cost));
+                    }
+                    _ => {
+                        ui.weak("—");
+                        ui.weak("—");
+                    }
+                }
+                ui.end_row();
+            }
+
+            ui.strong("Total");
+            ui.label("");
+            ui.label("");
+            ui.strong(format_mass(total_mass));
+            ui.strong(library.format_price(total_cost));
+            ui.end_row();
+        });
+
+    for (body, material) in assignments {
+        document.set_body_material(body, material);
+    }
+}
+
+fn library_ui(ui: &mut Ui, document: &mut Document) {
+    let mut library = document.materials().clone();
+    ui.horizontal(|ui| {
+        ui.label("Currency:");
+        ui.add(egui::TextEdit::singleline(&mut library.currency).desired_width(40.0));
+    });
+    ui.add_space(4.0);
+
+    let currency = library.currency.clone();
+    let mut removed: Option<MaterialId> = None;
+    egui::Grid::new("materials_library")
+        .num_columns(4)
+        .striped(true)
+        .spacing([12.0, 4.0])
+        .show(ui, |ui| {
+            for header in ["Name", "Density", "Price", ""] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for preset in core_document::material::presets() {
+                ui.label(&preset.name);
+                ui.label(format!("{:.2} g/cm³", preset.density));
+                ui.label(format!("{currency}{:.2}/kg", preset.price_per_kg));
+                ui.weak("Preset");
+                ui.end_row();
+            }
+            for material in &mut library.custom {
+                ui.add(egui::TextEdit::singleline(&mut material.name).desired_width(120.0));
+                ui.add(
+                    egui::DragValue::new(&mut material.density)
+                        .speed(0.01)
+                        .range(0.01..=25.0)
+                        .suffix(" g/cm³"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut material.price_per_kg)
+                        .speed(0.5)
+                        .range(0.0..=f32::MAX)
+                        .prefix(currency.as_str())
+                        .suffix("/kg"),
+                );
+                if ui.button("Remove").clicked() {
+                    removed = Some(material.id.clone());
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(id) = removed {
+        library.remove_custom(&id);
+    }
+    if ui.button("Add Material").clicked() {
+        library.add_custom("Custom material", 1.2, 25.0);
+    }
+    document.set_materials(library);
+}
+
+pub(super) fn format_mass(grams: f32) -> String {
+    if grams >= 1000.0 {
+        format!("{:.2} kg", grams / 1000.0)
+    } else {
+        format!("{grams:.1} g")
+    }
+}
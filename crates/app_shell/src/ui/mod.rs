@@ -1,19 +1,28 @@
+mod bom_panel;
+mod display_states_panel;
+mod dock;
 mod feature_tree;
 mod layout;
+mod library_panel;
+mod materials_panel;
+mod parameters_panel;
+mod scale_overlay;
 mod settings_panel;
+mod task_panel;
 
 use axes::AxisSystem;
 use core_document::WorkbenchId;
 use egui::Context;
 use egui_winit::{egui as egui_core, State};
 use render_vk::EguiSubmission;
-use settings::UserSettings;
+use settings::{PanelId, UserSettings};
 use winit::{event::WindowEvent, window::Window};
 
 use crate::orientation_cube::{
     self, CameraSnapView, OrientationCubeConfig, OrientationCubeInput, OrientationCubeResult,
     RotateDelta,
 };
+use crate::reference_image_overlay::{ReferenceImageOverlay, ReferenceImageTextures};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ActiveWorkbench(pub WorkbenchId);
@@ -31,6 +40,30 @@ pub struct ActiveTool {
     pub active_ids: std::collections::HashSet<String>,
 }
 
+impl ActiveTool {
+    /// Make `tool` active. A Radio tool first deactivates the other tools of
+    /// its group (all other tools when it has none).
+    pub fn activate(
+        &mut self,
+        tool: &core_document::ToolDescriptor,
+        tools: &[core_document::ToolDescriptor],
+    ) {
+        if tool.behavior == core_document::ToolBehavior::Radio {
+            match &tool.group {
+                Some(group) => self.active_ids.retain(|active_id| {
+                    tools
+                        .iter()
+                        .find(|t| &t.id == active_id)
+                        .map(|t| t.group.as_deref() != Some(group))
+                        .unwrap_or(true)
+                }),
+                None => self.active_ids.clear(),
+            }
+        }
+        self.active_ids.insert(tool.id.clone());
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ViewportRect {
     pub x: u32,
@@ -42,34 +75,126 @@ pub struct ViewportRect {
 pub struct UiFrameResult {
     pub submission: EguiSubmission,
     pub settings_changed: bool,
+    /// The active document's setting overrides were edited (already applied to the document).
+    pub document_overrides_changed: bool,
     pub active_tool: ActiveTool,
     pub active_workbench: ActiveWorkbench,
     pub workbench_changed: bool,
     pub snap_to_view: Option<CameraSnapView>,
     pub rotate_delta: Option<RotateDelta>,
+    /// Drag on the orientation cube, in physical pixels.
+    pub orbit_delta: Option<[f32; 2]>,
     pub viewport: ViewportRect,
     pub finish_sketch_requested: bool,
+    pub camera_focus_request: Option<core_document::CameraFocusRequest>,
+    /// A workbench asked for a file to be picked.
+    pub file_open_request: Option<core_document::FileOpenRequest>,
+    /// Commands the workbench queued from its panels.
+    pub commands: Vec<String>,
     pub tree_selection: Option<feature_tree::TreeItemId>,
+    /// The tree click held Ctrl or Shift (toggle instead of replacing the selection).
+    pub tree_extend: bool,
     pub tree_activation: Option<feature_tree::TreeItemId>,
+    /// Tree item whose "Zoom to" was picked.
+    pub tree_zoom_to: Option<feature_tree::TreeItemId>,
     pub new_body_requested: bool,
     pub open_requested: bool,
+    /// A document picked from the recent documents list.
+    pub open_recent: Option<std::path::PathBuf>,
     pub save_requested: bool,
     pub save_as_requested: bool,
     pub reset_view_requested: bool,
+    /// Frame the current selection in the viewport.
+    pub zoom_to_selection_requested: bool,
+    /// Hide everything but the selection.
+    pub isolate_requested: bool,
+    /// The viewer's Measure button was clicked.
+    pub measure_toggled: bool,
+    /// Show everything again (from the View menu or the isolation banner).
+    pub exit_isolation_requested: bool,
+    /// Move the section plane to the view or the selected plane.
+    pub section_align: Option<crate::section_view::SectionAlign>,
+    pub profile_action: Option<ProfileAction>,
+    pub library_action: Option<LibraryAction>,
+    pub axis_prompt_choice: Option<AxisPromptChoice>,
+    /// Answer to the offer to restore the previous session.
+    pub restore_session: Option<bool>,
+    /// Answer to the offer to recover autosaved changes.
+    pub recovery_choice: Option<RecoveryChoice>,
+    /// Choice in the active tool's right-click menu.
+    pub tool_menu_action: Option<ToolMenuAction>,
+}
+
+/// Answer to the prompt shown when a document was authored with a different axis convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisPromptChoice {
+    /// Pin the document's convention as a per-document override.
+    UseDocument(AxisSystem),
+    /// Keep viewing with the user's preference.
+    KeepMine,
+}
+
+/// Answer to the prompt shown when an opened document has a newer autosaved recovery copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryChoice {
+    /// Open the recovery copy in place of the file (still saved to the file).
+    Recover,
+    /// Delete the recovery copy.
+    Discard,
+    /// Leave it for later.
+    Keep,
+}
+
+/// Memory held by body meshes, shown in the Rendering settings.
+#[derive(Debug, Clone, Default)]
+pub struct MeshMemoryReport {
+    /// Cached meshes in main memory, in bytes.
+    pub cpu_total: usize,
+    /// Mesh buffers allocated on the GPU, in bytes.
+    pub gpu_allocated: usize,
+    /// Per body: name, cached bytes, and bytes drawn last frame; largest first.
+    pub bodies: Vec<(String, usize, usize)>,
 }
 
 pub struct UiLayer {
     ctx: Context,
     state: State,
+    // Read-only viewer: no workbenches, editing, or saving in the top bar.
+    viewer: bool,
     active_workbench: ActiveWorkbench,
     active_tool: ActiveTool,
     settings_tab: settings_panel::SettingsTab,
     show_settings: bool,
+    show_materials: bool,
+    show_display_states: bool,
+    show_parameters: bool,
+    show_library: bool,
+    show_bom: bool,
+    /// Parts dragged from the library are inserted as links.
+    insert_linked: bool,
+    library_textures: std::collections::HashMap<std::path::PathBuf, egui::TextureHandle>,
+    recent_textures: std::collections::HashMap<std::path::PathBuf, egui::TextureHandle>,
+    reference_textures: ReferenceImageTextures,
+    profile_name_input: String,
+    // Axis convention of a just-opened document that differs from the current one.
+    axis_prompt: Option<AxisSystem>,
+    // Document of the previous session, while offering to restore it.
+    session_prompt: Option<std::path::PathBuf>,
+    // Recovery copy newer than the open document, while offering to recover it.
+    recovery_prompt: Option<std::path::PathBuf>,
+    mesh_memory: MeshMemoryReport,
+    // Tree row to expand to and scroll into view on the next frame.
+    tree_reveal: Option<feature_tree::TreeItemId>,
+    // Active tool's right-click menu: where it opens, and the tool's label.
+    tool_menu: Option<(egui::Pos2, String)>,
     orientation_cube_config: OrientationCubeConfig,
+    dock: dock::DockState,
+    // Whether the workbench had a task open last frame.
+    task_open: bool,
 }
 
 impl UiLayer {
-    pub fn new(window: &Window) -> Self {
+    pub fn new(window: &Window, viewer: bool) -> Self {
         let ctx = Context::default();
         let state = State::new(
             ctx.clone(),
@@ -85,11 +210,30 @@ impl UiLayer {
         Self {
             ctx,
             state,
+            viewer,
             active_workbench: ActiveWorkbench::default(),
             active_tool: ActiveTool::default(),
             settings_tab: settings_panel::SettingsTab::Camera,
             show_settings: false,
+            show_materials: false,
+            show_display_states: false,
+            show_parameters: false,
+            show_library: false,
+            show_bom: false,
+            insert_linked: false,
+            library_textures: std::collections::HashMap::new(),
+            recent_textures: std::collections::HashMap::new(),
+            reference_textures: ReferenceImageTextures::default(),
+            profile_name_input: String::new(),
+            axis_prompt: None,
+            session_prompt: None,
+            recovery_prompt: None,
+            mesh_memory: MeshMemoryReport::default(),
+            tree_reveal: None,
+            tool_menu: None,
             orientation_cube_config: OrientationCubeConfig::default(),
+            dock: dock::DockState::default(),
+            task_open: false,
         }
     }
 
@@ -110,6 +254,7 @@ impl UiLayer {
         fps: f32,
         gpu_name: Option<&str>,
         gpus: &[String],
+        profiles: &[String],
         hovered_point: Option<[f32; 3]>,
         pivot_screen_pos: Option<(f32, f32)>,
         axis_system: AxisSystem,
@@ -117,14 +262,37 @@ impl UiLayer {
         registry: &mut core_document::DocumentService,
         active_tree_selection: Option<feature_tree::TreeItemId>,
         active_document_object: Option<core_document::FeatureId>,
-        selected_body_id: Option<core_document::BodyId>,
+        selection: &mut core_document::Selection,
         screen_space_overlays: &[core_document::ScreenSpaceOverlay],
+        screen_labels: &[core_document::ScreenSpaceLabel],
+        reference_images: &[ReferenceImageOverlay],
+        library: &crate::library::PartLibrary,
+        recent: &crate::recent::RecentDocuments,
+        body_meshes: &[core_document::BodyMesh],
+        frame: [f64; 3],
+        printer: Option<core_document::PrintVolume>,
+        isolation: Option<&str>,
+        mm_per_pixel: f32,
+        measuring: bool,
     ) -> UiFrameResult {
         let raw_input = self.state.take_egui_input(window);
         let prev_workbench = self.active_workbench.clone();
         let mut active_workbench = self.active_workbench.clone();
         let mut active_tool = self.active_tool.clone();
         let mut show_settings = self.show_settings;
+        let mut show_materials = self.show_materials;
+        let mut show_display_states = self.show_display_states;
+        let mut show_parameters = self.show_parameters;
+        let mut show_library = self.show_library;
+        let mut show_bom = self.show_bom;
+        let mut library_action = None;
+        let mut library_rect = None;
+        let library_textures = &mut self.library_textures;
+        let insert_linked = &mut self.insert_linked;
+        library_textures.retain(|path, _| library.thumbnail(path).is_some_and(|t| t.is_some()));
+        let recent_textures = &mut self.recent_textures;
+        let reference_textures = &mut self.reference_textures;
+        recent_textures.retain(|path, _| recent.thumbnail(path).is_some());
         let mut settings_tab = self.settings_tab;
 
         let cube_config = self.orientation_cube_config.clone();
@@ -134,59 +302,246 @@ impl UiLayer {
         let mut finish_requested = false;
 
         let mut tree_selection = None;
+        let mut tree_extend = false;
+        let tree_reveal = self.tree_reveal.take();
+        let mut tool_menu = self.tool_menu.take();
+        let mut tool_menu_action = None;
+        let mut camera_focus_request = None;
+        let mut file_open_request = None;
+        let mut commands = Vec::new();
         let mut tree_activation = None;
+        let mut tree_zoom_to = None;
         let mut new_body_requested = false;
         let mut open_requested = false;
+        let mut open_recent = None;
         let mut save_requested = false;
         let mut save_as_requested = false;
         let mut reset_view_requested = false;
+        let mut zoom_to_selection_requested = false;
+        let mut isolate_requested = false;
+        let mut measure_toggled = false;
+        let mut exit_isolation_requested = false;
+        let mut section_align = None;
+        let viewer = self.viewer;
+        let mut profile_action = None;
+        let profile_name_input = &mut self.profile_name_input;
+        let mut axis_prompt_choice = None;
+        let axis_prompt = self.axis_prompt;
+        let mut restore_session = None;
+        let session_prompt = self.session_prompt.as_deref();
+        let mut recovery_choice = None;
+        let recovery_prompt = self.recovery_prompt.as_deref();
+        let mesh_memory = &self.mesh_memory;
+        let dock_state = &mut self.dock;
+        let task_open = &mut self.task_open;
+        let mut left_panel = layout::LeftPanelResult::default();
+        let mut right_panel = layout::RightPanelResult::default();
+
+        let units = document
+            .overrides()
+            .units
+            .unwrap_or_else(|| settings.units.format());
+        let mut overrides = document.overrides().clone();
+        let running_jobs = document.jobs().running();
+        let mut cancel_job = None;
+        let mut document_overrides_changed = false;
 
         let full_output = self.ctx.run(raw_input, |ctx| {
             let top = layout::draw_top_panel(
                 ctx,
                 &mut active_workbench,
                 &mut show_settings,
+                &mut show_materials,
+                &mut show_display_states,
+                &mut show_parameters,
+                &mut show_library,
+                &mut show_bom,
+                &mut settings.rendering,
+                &mut settings.camera.projection,
+                &mut settings.panels,
+                recent,
+                recent_textures,
                 &mut active_tool,
                 registry,
                 document,
                 active_document_object,
-                selected_body_id,
+                selection,
+                isolation.is_some(),
+                viewer.then_some(measuring),
             );
             new_body_requested = top.new_body_requested;
             open_requested = top.open_requested;
+            open_recent = top.open_recent;
             save_requested = top.save_requested;
             save_as_requested = top.save_as_requested;
             reset_view_requested = top.reset_view_requested;
-            let left_panel = layout::draw_left_panel(
+            zoom_to_selection_requested = top.zoom_to_selection_requested;
+            isolate_requested = top.isolate_requested;
+            measure_toggled = top.measure_toggled;
+            exit_isolation_requested = top.exit_isolation_requested;
+            section_align = top.section_align;
+            settings_changed |= top.view_changed;
+            let (has_properties, has_task) =
+                layout::properties_content(registry, &active_workbench);
+            let has_analysis = layout::analysis_content(registry, &active_workbench);
+            let has_log = layout::log_has_entries(settings.rendering.show_log_panel);
+            let (parameters_open, library_open, bom_open) =
+                (show_parameters, show_library, show_bom);
+            let available = |panel| match panel {
+                PanelId::Model => true,
+                PanelId::Properties => has_properties,
+                PanelId::Log => has_log,
+                PanelId::Parameters => parameters_open,
+                PanelId::Library => library_open,
+                PanelId::Analysis => has_analysis,
+                PanelId::Bom => bom_open,
+            };
+            // Bring a task's form, or a panel opened from the menu, to the front.
+            let focus = (has_task && !*task_open)
+                .then_some(PanelId::Properties)
+                .or((parameters_open && !self.show_parameters).then_some(PanelId::Parameters))
+                .or((library_open && !self.show_library).then_some(PanelId::Library))
+                .or((bom_open && !self.show_bom).then_some(PanelId::Bom));
+            *task_open = has_task;
+            let docks = dock::draw_docks(
                 ctx,
-                active_workbench.clone(),
-                document,
-                registry,
-                active_tree_selection,
-                active_document_object,
+                &mut settings.panels,
+                dock_state,
+                available,
+                focus,
+                |ui, panel| match panel {
+                    PanelId::Model => {
+                        left_panel = layout::model_panel_ui(
+                            ui,
+                            active_workbench.clone(),
+                            document,
+                            registry,
+                            active_tree_selection,
+                            tree_reveal,
+                            active_document_object,
+                            selection,
+                            &units,
+                            axis_system,
+                            body_meshes,
+                            frame,
+                            printer,
+                        );
+                    }
+                    PanelId::Properties | PanelId::Analysis => {
+                        right_panel.merge(layout::workbench_panel_ui(
+                            ui,
+                            panel,
+                            active_workbench.clone(),
+                            document,
+                            registry,
+                            active_document_object,
+                            selection,
+                            &units,
+                            axis_system,
+                            body_meshes,
+                            frame,
+                            printer,
+                        ));
+                    }
+                    PanelId::Log => layout::log_panel_ui(ui),
+                    PanelId::Parameters => parameters_panel::parameters_ui(ui, document, &units),
+                    PanelId::Bom => bom_panel::bom_ui(ui, document, body_meshes, &units),
+                    PanelId::Library => {
+                        library_action =
+                            library_panel::library_ui(ui, library, library_textures, insert_linked);
+                        library_rect = Some(ui.clip_rect());
+                    }
+                },
             );
+            settings_changed |= docks.changed;
+            match docks.closed {
+                Some(PanelId::Parameters) => show_parameters = false,
+                Some(PanelId::Library) => show_library = false,
+                Some(PanelId::Bom) => show_bom = false,
+                _ => {}
+            }
             finish_requested = left_panel.finish_sketch_requested;
+            commands.extend(std::mem::take(&mut left_panel.commands));
             tree_selection = left_panel.tree_selection;
+            tree_extend = left_panel.tree_extend;
+            tree_zoom_to = left_panel.tree_zoom_to;
             tree_activation = left_panel.tree_activation;
-            layout::draw_right_panel(
-                ctx,
-                active_workbench.clone(),
-                document,
-                registry,
-                active_document_object,
-            );
-            settings_changed |= settings_panel::draw_settings_window(
+            camera_focus_request = right_panel.camera_focus_request.take();
+            file_open_request = right_panel.file_open_request.take();
+            commands.extend(std::mem::take(&mut right_panel.commands));
+            let settings_window = settings_panel::draw_settings_window(
                 ctx,
                 settings,
                 &mut show_settings,
                 &mut settings_tab,
                 gpus,
                 gpu_name,
+                profiles,
+                profile_name_input,
+                &mut overrides,
+                mesh_memory,
             );
-            layout::draw_log_panel(ctx, settings.rendering.show_log_panel);
-            layout::draw_bottom_panel(ctx, fps, hovered_point, axis_system);
+            settings_changed |= settings_window.changed;
+            document_overrides_changed |= settings_window.document_overrides_changed;
+            profile_action = settings_window.profile_action;
+            materials_panel::draw_materials_window(
+                ctx,
+                &mut show_materials,
+                document,
+                body_meshes,
+                &units,
+            );
+            display_states_panel::draw_display_states_window(
+                ctx,
+                &mut show_display_states,
+                document,
+            );
+            cancel_job = layout::draw_bottom_panel(
+                ctx,
+                fps,
+                hovered_point,
+                axis_system,
+                &units,
+                &running_jobs,
+            );
+            if let Some(document_axes) = axis_prompt {
+                axis_prompt_choice = layout::draw_axis_prompt(ctx, document_axes, axis_system);
+            }
+            if let Some(document) = session_prompt {
+                restore_session = layout::draw_session_prompt(ctx, document);
+            }
+            if let Some(recovery) = recovery_prompt {
+                recovery_choice = layout::draw_recovery_prompt(ctx, recovery);
+            }
 
             viewport_rect_logical = ctx.available_rect();
+            settings_changed |= dock::draw_drop_zones(
+                ctx,
+                viewport_rect_logical,
+                &mut settings.panels,
+                dock_state,
+                available,
+            );
+            scale_overlay::draw_scale_overlay(
+                ctx,
+                viewport_rect_logical,
+                mm_per_pixel,
+                &units,
+                settings.rendering.show_scale_bar,
+                settings.rendering.show_rulers,
+            );
+            if let Some(summary) = isolation {
+                exit_isolation_requested |=
+                    layout::draw_isolation_banner(ctx, viewport_rect_logical, summary);
+            }
+            if let Some(entry) =
+                library_panel::dropped_part(ctx, viewport_rect_logical, library_rect)
+            {
+                library_action = Some(LibraryAction::Insert {
+                    entry,
+                    linked: *insert_linked,
+                });
+            }
 
             if let Some(input) = orientation_input {
                 cube_result = orientation_cube::draw(ctx, input, &cube_config);
@@ -196,21 +551,55 @@ impl UiLayer {
                 layout::draw_pivot_indicator(ctx, px, py);
             }
 
+            layout::draw_reference_images(ctx, reference_images, reference_textures, document);
+
             // Draw screen-space overlays in the viewport area
             layout::draw_screen_space_overlays(ctx, screen_space_overlays);
+            layout::draw_screen_space_labels(ctx, screen_labels);
+
+            if let Some((pos, tool)) = &tool_menu {
+                let (action, open) = layout::draw_tool_menu(ctx, *pos, tool);
+                tool_menu_action = action;
+                if !open {
+                    tool_menu = None;
+                }
+            }
         });
 
+        if document_overrides_changed {
+            document.set_overrides(overrides);
+        }
+
         // Detect workbench change
         let workbench_changed = active_workbench != prev_workbench;
         if workbench_changed {
             // Reset tool when switching workbenches
             active_tool = ActiveTool::default();
+            tool_menu = None;
         }
+        self.tool_menu = tool_menu;
 
         self.active_workbench = active_workbench.clone();
         self.active_tool = active_tool.clone();
         self.show_settings = show_settings;
+        self.show_materials = show_materials;
+        self.show_display_states = show_display_states;
+        self.show_parameters = show_parameters;
+        self.show_library = show_library;
+        self.show_bom = show_bom;
         self.settings_tab = settings_tab;
+        if axis_prompt_choice.is_some() {
+            self.axis_prompt = None;
+        }
+        if restore_session.is_some() {
+            self.session_prompt = None;
+        }
+        if recovery_choice.is_some() {
+            self.recovery_prompt = None;
+        }
+        if let Some(job) = cancel_job {
+            document.jobs().cancel(job);
+        }
         self.state
             .handle_platform_output(window, full_output.platform_output.clone());
         let primitives = self
@@ -232,22 +621,96 @@ impl UiLayer {
                 primitives,
             },
             settings_changed,
+            document_overrides_changed,
             active_tool,
             active_workbench,
             workbench_changed,
             snap_to_view: cube_result.snap_to_view,
             rotate_delta: cube_result.rotate_delta,
+            orbit_delta: cube_result.orbit_delta,
             viewport,
             finish_sketch_requested: finish_requested,
+            camera_focus_request,
+            file_open_request,
+            commands,
             tree_selection,
+            tree_extend,
             tree_activation,
+            tree_zoom_to,
             new_body_requested,
             open_requested,
+            open_recent,
             save_requested,
             save_as_requested,
             reset_view_requested,
+            zoom_to_selection_requested,
+            isolate_requested,
+            measure_toggled,
+            exit_isolation_requested,
+            section_align,
+            profile_action,
+            library_action,
+            axis_prompt_choice,
+            restore_session,
+            recovery_choice,
+            tool_menu_action,
         }
     }
+
+    /// Ask whether to view the open document with its own axis convention.
+    pub fn show_axis_prompt(&mut self, document_axes: AxisSystem) {
+        self.axis_prompt = Some(document_axes);
+    }
+
+    /// Offer to reopen the document of the previous session.
+    pub fn show_session_prompt(&mut self, document: std::path::PathBuf) {
+        self.session_prompt = Some(document);
+    }
+
+    /// Offer to recover the autosaved changes in `recovery`.
+    pub fn show_recovery_prompt(&mut self, recovery: std::path::PathBuf) {
+        self.recovery_prompt = Some(recovery);
+    }
+
+    /// Whether the settings window is open (the mesh memory report is only
+    /// needed then).
+    pub fn settings_open(&self) -> bool {
+        self.show_settings
+    }
+
+    pub fn set_mesh_memory(&mut self, report: MeshMemoryReport) {
+        self.mesh_memory = report;
+    }
+
+    /// Expand the model tree down to `item` and scroll it into view.
+    pub fn reveal_in_tree(&mut self, item: feature_tree::TreeItemId) {
+        self.tree_reveal = Some(item);
+    }
+
+    /// Follow a workbench switch made outside the UI.
+    pub fn set_active_workbench(&mut self, workbench: ActiveWorkbench) {
+        self.active_workbench = workbench;
+    }
+
+    /// Open the menu of the active tool `tool` at `pos` (physical pixels in
+    /// the window).
+    pub fn show_tool_menu(&mut self, pos: (f32, f32), tool: String) {
+        let ppp = self.ctx.pixels_per_point();
+        self.tool_menu = Some((egui::pos2(pos.0 / ppp, pos.1 / ppp), tool));
+    }
+
+    /// Close the tool menu, returning whether it was open.
+    pub fn close_tool_menu(&mut self) -> bool {
+        self.tool_menu.take().is_some()
+    }
+
+    /// Follow tool changes made outside the UI (shortcuts, finished tools).
+    pub fn set_active_tool(&mut self, tool: ActiveTool) {
+        self.active_tool = tool;
+    }
 }
 
 pub use feature_tree::TreeItemId;
+pub use layout::ToolMenuAction;
+pub use library_panel::LibraryAction;
+pub use settings_panel::ProfileAction;
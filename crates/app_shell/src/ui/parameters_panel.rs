@@ -0,0 +1,248 @@
+use core_document::parameters::CellValue;
+use core_document::{BindingError, CellRef, Document, FeatureId, ParameterUnit, UnitFormat};
+use egui::{self, Ui};
+
+use crate::log_panel;
+
+/// Content of a parameter added with "Add Parameter".
+const NEW_PARAMETER: &str = "10";
+
+/// Column of the table being typed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Expression,
+}
+
+/// Text typed into a table field, applied when the field loses focus.
+#[derive(Debug, Clone, Default)]
+struct Editing {
+    target: Option<(CellRef, Field)>,
+    text: String,
+}
+
+enum Change {
+    Add,
+    Rename(CellRef, String),
+    Expression(CellRef, String),
+    Unit(CellRef, ParameterUnit),
+    Delete(CellRef),
+}
+
+/// Parameters panel: the named cells of the parameter table with their
+/// expression, value, and unit. Edits are applied to the document, which
+/// recomputes the features bound to the changed parameters.
+pub(super) fn parameters_ui(ui: &mut Ui, document: &mut Document, units: &UnitFormat) {
+    let sheet = document.parameters().clone();
+    let evaluation = sheet.evaluate();
+    let editing_id = ui.id().with("parameters_editing");
+    let mut editing: Editing = ui.data_mut(|data| data.get_temp(editing_id).unwrap_or_default());
+    let mut change = None;
+
+    let parameters: Vec<_> = sheet
+        .cells()
+        .filter_map(|(cell, content)| Some((cell, content.alias.clone()?, content)))
+        .collect();
+    if parameters.is_empty() {
+        ui.weak(
+            "No parameters yet. Named parameters can be used in formulas and feature bindings.",
+        );
+    } else {
+        egui::Grid::new("parameters_table")
+            .num_columns(5)
+            .striped(true)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("Expression");
+                ui.strong("Value");
+                ui.strong("Unit");
+                ui.label("");
+                ui.end_row();
+                for (cell, alias, content) in &parameters {
+                    let cell = *cell;
+                    if let Some(text) = edit_text(ui, &mut editing, cell, Field::Name, alias, 120.0)
+                    {
+                        change = Some(Change::Rename(cell, text));
+                    }
+                    if let Some(text) = edit_text(
+                        ui,
+                        &mut editing,
+                        cell,
+                        Field::Expression,
+                        &content.content,
+                        180.0,
+                    ) {
+                        change = Some(Change::Expression(cell, text));
+                    }
+                    match evaluation.value(cell) {
+                        Some(Ok(CellValue::Number(value))) => {
+                            ui.label(format_value(*value, content.unit, units));
+                        }
+                        Some(Ok(CellValue::Text(_))) => {
+                            ui.weak("Not a number");
+                        }
+                        Some(Err(err)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "Error")
+                                .on_hover_text(err.to_string());
+                        }
+                        None => {
+                            ui.weak("Empty");
+                        }
+                    }
+                    let mut unit = content.unit;
+                    egui::ComboBox::from_id_salt(("parameter_unit", cell))
+                        .selected_text(unit.label())
+                        .show_ui(ui, |ui| {
+                            for option in ParameterUnit::ALL {
+                                ui.selectable_value(&mut unit, option, option.label());
+                            }
+                        });
+                    if unit != content.unit {
+                        change = Some(Change::Unit(cell, unit));
+                    }
+                    let users = users(document, cell);
+                    let delete =
+                        ui.add_enabled(users.is_empty(), egui::Button::new("Delete").small());
+                    if delete
+                        .on_disabled_hover_text(format!("Used by {}", users.join(", ")))
+                        .clicked()
+                    {
+                        change = Some(Change::Delete(cell));
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+    ui.add_space(4.0);
+    if ui.button("Add Parameter").clicked() {
+        change = Some(Change::Add);
+    }
+    ui.data_mut(|data| data.insert_temp(editing_id, editing));
+
+    if let Some(change) = change {
+        apply(document, change);
+    }
+}
+
+/// Text field showing `current`. Returns the typed text once the field loses focus
+/// with a different value; Escape discards it.
+fn edit_text(
+    ui: &mut Ui,
+    editing: &mut Editing,
+    cell: CellRef,
+    field: Field,
+    current: &str,
+    width: f32,
+) -> Option<String> {
+    let target = Some((cell, field));
+    let mut text = if editing.target == target {
+        editing.text.clone()
+    } else {
+        current.to_string()
+    };
+    let response = ui.add(egui::TextEdit::singleline(&mut text).desired_width(width));
+    if response.lost_focus() {
+        if editing.target == target {
+            editing.target = None;
+        }
+        let cancelled = ui.input(|input| input.key_pressed(egui::Key::Escape));
+        return (!cancelled && text.trim() != current.trim()).then_some(text);
+    }
+    if response.has_focus() {
+        editing.target = target;
+        editing.text = text;
+    }
+    None
+}
+
+/// Cells and feature values whose expressions use `cell`.
+fn users(document: &Document, cell: CellRef) -> Vec<String> {
+    let sheet = document.parameters();
+    let cells = sheet.dependents(cell).into_iter().map(|other| {
+        match sheet
+            .cell(other)
+            .and_then(|content| content.alias.as_deref())
+        {
+            Some(alias) => alias.to_string(),
+            None => other.to_string(),
+        }
+    });
+    let bindings = document
+        .parameter_bindings(cell)
+        .into_iter()
+        .map(|(feature, path)| format!("{} {path}", feature_name(document, feature)));
+    cells.chain(bindings).collect()
+}
+
+fn apply(document: &mut Document, change: Change) {
+    let mut sheet = document.parameters().clone();
+    match change {
+        Change::Add => {
+            let row = (0..)
+                .find(|row| sheet.cell(CellRef::new(0, *row)).is_none())
+                .unwrap_or_default();
+            let cell = CellRef::new(0, row);
+            let alias = (1..)
+                .map(|index| format!("param{index}"))
+                .find(|alias| sheet.find_alias(alias).is_none())
+                .unwrap_or_default();
+            sheet.set_content(cell, NEW_PARAMETER);
+            if let Err(err) = sheet.set_alias(cell, Some(&alias)) {
+                log_panel::warn(format!("Cannot add parameter: {err}"));
+                return;
+            }
+            sheet.set_unit(cell, ParameterUnit::Length);
+        }
+        Change::Rename(cell, alias) => {
+            match document.rename_parameter(cell, &alias) {
+                Ok(errors) => report(document, &errors),
+                Err(err) => log_panel::warn(format!("Cannot rename parameter: {err}")),
+            }
+            return;
+        }
+        Change::Expression(cell, text) => sheet.set_content(cell, text.trim()),
+        Change::Unit(cell, unit) => sheet.set_unit(cell, unit),
+        Change::Delete(cell) => {
+            sheet.remove(cell);
+        }
+    }
+    let errors = document.set_parameters(sheet);
+    report(document, &errors);
+}
+
+/// Log the feature bindings that could not be applied.
+fn report(document: &Document, errors: &[BindingError]) {
+    for error in errors {
+        log_panel::warn(format!(
+            "{}: {error}",
+            feature_name(document, error.feature)
+        ));
+    }
+}
+
+fn feature_name(document: &Document, feature: FeatureId) -> String {
+    document
+        .feature_tree()
+        .get_node(feature)
+        .map_or_else(|| "(deleted feature)".to_string(), |node| node.name.clone())
+}
+
+fn format_value(value: f64, unit: ParameterUnit, units: &UnitFormat) -> String {
+    match unit {
+        ParameterUnit::Length => units.format_length(value as f32),
+        ParameterUnit::Angle => format!("{}°", format_number(value)),
+        ParameterUnit::Number => format_number(value),
+    }
+}
+
+/// Number without trailing zeros.
+fn format_number(value: f64) -> String {
+    let text = format!("{value:.6}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,182 @@
+use core_document::UnitFormat;
+use egui::{self, Align2, Color32, Context, FontId, Pos2, Rect, Stroke};
+
+/// Shortest the scale bar is drawn (points); it grows up to 2.5× this to end
+/// on a round length.
+const BAR_MIN_LENGTH: f32 = 80.0;
+/// Closest two labeled ruler ticks are drawn (points).
+const RULER_MIN_GAP: f32 = 70.0;
+/// Width of the ruler bands (points).
+const RULER_WIDTH: f32 = 20.0;
+const MARGIN: f32 = 16.0;
+const TEXT_COLOR: Color32 = Color32::from_gray(235);
+const BACKDROP: Color32 = Color32::from_black_alpha(150);
+
+/// Scale bar in the bottom-left corner of the viewport and rulers along its
+/// top and left edges, measured at the depth of the camera target in the
+/// display unit. Ruler values count from the middle of the viewport.
+pub(super) fn draw_scale_overlay(
+    ctx: &Context,
+    viewport: Rect,
+    mm_per_pixel: f32,
+    units: &UnitFormat,
+    scale_bar: bool,
+    rulers: bool,
+) {
+    let mm_per_point = mm_per_pixel * ctx.pixels_per_point();
+    if !(mm_per_point.is_finite() && mm_per_point > 0.0) || viewport.width() < 1.0 {
+        return;
+    }
+    // Points per display unit.
+    let scale = units.unit.mm_per_unit() / mm_per_point;
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("scale_overlay"),
+    ));
+    if rulers {
+        draw_rulers(&painter, viewport, scale);
+    }
+    if scale_bar {
+        draw_scale_bar(&painter, viewport, scale, units);
+    }
+}
+
+fn draw_scale_bar(painter: &egui::Painter, viewport: Rect, scale: f32, units: &UnitFormat) {
+    let step = RoundStep::at_least(BAR_MIN_LENGTH / scale);
+    let length = step.value * scale;
+    let start = Pos2::new(viewport.left() + MARGIN, viewport.bottom() - MARGIN);
+    let end = start + egui::vec2(length, 0.0);
+    let label = format!("{} {}", step.format(step.value), units.unit.symbol());
+
+    let backdrop = Rect::from_min_max(start + egui::vec2(-8.0, -26.0), end + egui::vec2(8.0, 6.0));
+    painter.rect_filled(backdrop, 4.0, BACKDROP);
+    let stroke = Stroke::new(2.0, TEXT_COLOR);
+    painter.line_segment([start, end], stroke);
+    for x in [start.x, end.x] {
+        painter.line_segment([Pos2::new(x, start.y - 6.0), Pos2::new(x, start.y)], stroke);
+    }
+    painter.text(
+        Pos2::new((start.x + end.x) / 2.0, start.y - 6.0),
+        Align2::CENTER_BOTTOM,
+        label,
+        FontId::proportional(12.0),
+        TEXT_COLOR,
+    );
+}
+
+fn draw_rulers(painter: &egui::Painter, viewport: Rect, scale: f32) {
+    let major = RoundStep::at_least(RULER_MIN_GAP / scale);
+    let minor = major.value / major.subdivisions() as f32;
+    let center = viewport.center();
+    let top = Rect::from_min_max(
+        viewport.left_top() + egui::vec2(RULER_WIDTH, 0.0),
+        Pos2::new(viewport.right(), viewport.top() + RULER_WIDTH),
+    );
+    let left = Rect::from_min_max(
+        viewport.left_top(),
+        Pos2::new(viewport.left() + RULER_WIDTH, viewport.bottom()),
+    );
+    painter.rect_filled(top, 0.0, BACKDROP);
+    painter.rect_filled(left, 0.0, BACKDROP);
+    let stroke = Stroke::new(1.0, TEXT_COLOR);
+    let font = FontId::proportional(9.0);
+
+    // Ticks (in minor steps from the middle) within `low..high` points from it.
+    let ticks = |low: f32, high: f32| {
+        let first = (low / (minor * scale)).ceil() as i64;
+        let last = (high / (minor * scale)).floor() as i64;
+        first..=last
+    };
+    let subdivisions = major.subdivisions() as i64;
+
+    for k in ticks(top.left() - center.x, top.right() - center.x) {
+        let x = center.x + k as f32 * minor * scale;
+        let labeled = k % subdivisions == 0;
+        let length = if labeled { 10.0 } else { 5.0 };
+        painter.line_segment(
+            [
+                Pos2::new(x, top.bottom() - length),
+                Pos2::new(x, top.bottom()),
+            ],
+            stroke,
+        );
+        if labeled {
+            painter.text(
+                Pos2::new(x + 2.0, top.top() + 1.0),
+                Align2::LEFT_TOP,
+                major.format(k as f32 * minor),
+                font.clone(),
+                TEXT_COLOR,
+            );
+        }
+    }
+    // Upward is positive, as on the screen's vertical axis.
+    for k in ticks(
+        center.y - left.bottom(),
+        center.y - left.top() - RULER_WIDTH,
+    ) {
+        let y = center.y - k as f32 * minor * scale;
+        let labeled = k % subdivisions == 0;
+        let length = if labeled { 10.0 } else { 5.0 };
+        painter.line_segment(
+            [
+                Pos2::new(left.right() - length, y),
+                Pos2::new(left.right(), y),
+            ],
+            stroke,
+        );
+        if labeled {
+            painter.text(
+                Pos2::new(left.left() + 1.0, y - 1.0),
+                Align2::LEFT_BOTTOM,
+                major.format(k as f32 * minor),
+                font.clone(),
+                TEXT_COLOR,
+            );
+        }
+    }
+}
+
+/// A round length: 1, 2, or 5 times a power of ten.
+struct RoundStep {
+    value: f32,
+    mantissa: u8,
+    /// Decimal places needed to write multiples of the step.
+    decimals: usize,
+}
+
+impl RoundStep {
+    /// The smallest round length of at least `length`.
+    fn at_least(length: f32) -> Self {
+        let exponent = length.max(f32::MIN_POSITIVE).log10().floor() as i32;
+        let (mantissa, exponent) = [1, 2, 5]
+            .into_iter()
+            .map(|mantissa| (mantissa, exponent))
+            .find(|&(mantissa, exponent)| mantissa as f32 * 10f32.powi(exponent) >= length)
+            .unwrap_or((1, exponent + 1));
+        Self {
+            value: mantissa as f32 * 10f32.powi(exponent),
+            mantissa,
+            decimals: (-exponent).max(0) as usize,
+        }
+    }
+
+    /// Minor ticks per labeled step, so minor ticks also fall on round values.
+    fn subdivisions(&self) -> u32 {
+        match self.mantissa {
+            2 => 4,
+            _ => 5,
+        }
+    }
+
+    /// A multiple of the step, without a sign on zero.
+    fn format(&self, value: f32) -> String {
+        let text = format!("{value:.*}", self.decimals);
+        match text.strip_prefix('-') {
+            Some(magnitude) if magnitude.chars().all(|c| c == '0' || c == '.') => {
+                magnitude.to_string()
+            }
+            _ => text,
+        }
+    }
+}
@@ -1,22 +1,46 @@
-use axes::AxisPreset;
+use axes::{Axis, AxisPreset, AxisSystem, Handedness};
+use core_document::DocumentOverrides;
 use egui::{self, Color32, Context, Ui};
-use settings::{LightSource, ProjectionMode, UserSettings};
+use kernel_api::ExchangeFormat;
+use settings::{
+    BedOrigin, BedShape, LightSource, ObjectSnapKind, PrinterProfile, ProjectionMode, SaveHook,
+    SaveHookAction, UserSettings,
+};
+use units::LengthUnit;
+
+use super::MeshMemoryReport;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum SettingsTab {
     Camera,
     Lighting,
     Input,
+    Snapping,
+    Units,
+    Autosave,
+    Exchange,
+    Printers,
+    Library,
     Rendering,
+    Document,
+    Profiles,
     About,
 }
 
 impl SettingsTab {
-    pub const ALL: [SettingsTab; 5] = [
+    pub const ALL: [SettingsTab; 13] = [
         SettingsTab::Camera,
         SettingsTab::Lighting,
         SettingsTab::Input,
+        SettingsTab::Snapping,
+        SettingsTab::Units,
+        SettingsTab::Autosave,
+        SettingsTab::Exchange,
+        SettingsTab::Printers,
+        SettingsTab::Library,
         SettingsTab::Rendering,
+        SettingsTab::Document,
+        SettingsTab::Profiles,
         SettingsTab::About,
     ];
 
@@ -25,12 +49,42 @@ impl SettingsTab {
             SettingsTab::Camera => "Camera",
             SettingsTab::Lighting => "Lighting",
             SettingsTab::Input => "Input",
+            SettingsTab::Snapping => "Snapping",
+            SettingsTab::Units => "Units",
+            SettingsTab::Autosave => "Autosave",
+            SettingsTab::Exchange => "Import/Export",
+            SettingsTab::Printers => "Printers",
+            SettingsTab::Library => "Part Library",
             SettingsTab::Rendering => "Rendering",
+            SettingsTab::Document => "This Document",
+            SettingsTab::Profiles => "Profiles",
             SettingsTab::About => "About",
         }
     }
 }
 
+/// Profile operations requested from the Profiles tab, handled by the app shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileAction {
+    /// Save the current settings under the given profile name.
+    Save(String),
+    /// Replace the current settings with the given profile.
+    Load(String),
+    Delete(String),
+    /// Export the current settings to a JSON file chosen by the user.
+    Export,
+    /// Import a profile from a JSON file chosen by the user.
+    Import,
+}
+
+#[derive(Default)]
+pub(super) struct SettingsWindowResult {
+    pub changed: bool,
+    pub document_overrides_changed: bool,
+    pub profile_action: Option<ProfileAction>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn draw_settings_window(
     ctx: &Context,
     settings: &mut UserSettings,
@@ -38,9 +92,14 @@ pub(super) fn draw_settings_window(
     settings_tab: &mut SettingsTab,
     gpus: &[String],
     gpu_name: Option<&str>,
-) -> bool {
+    profiles: &[String],
+    profile_name_input: &mut String,
+    overrides: &mut DocumentOverrides,
+    mesh_memory: &MeshMemoryReport,
+) -> SettingsWindowResult {
+    let mut result = SettingsWindowResult::default();
     if !*show_settings {
-        return false;
+        return result;
     }
 
     let mut changed = false;
@@ -72,10 +131,36 @@ pub(super) fn draw_settings_window(
                         changed |= lighting_settings_ui(right, settings);
                     }
                     SettingsTab::Input => {
-                        right.label("Input settings coming soon.");
+                        changed |= input_settings_ui(right, settings);
+                    }
+                    SettingsTab::Snapping => {
+                        changed |= snapping_settings_ui(right, settings);
+                    }
+                    SettingsTab::Units => {
+                        changed |= units_settings_ui(right, settings);
+                    }
+                    SettingsTab::Autosave => {
+                        changed |= autosave_settings_ui(right, settings);
+                    }
+                    SettingsTab::Exchange => {
+                        changed |= exchange_settings_ui(right, settings);
+                    }
+                    SettingsTab::Printers => {
+                        changed |= printer_settings_ui(right, settings);
+                    }
+                    SettingsTab::Library => {
+                        changed |= library_settings_ui(right, settings);
                     }
                     SettingsTab::Rendering => {
-                        changed |= render_settings_ui(right, settings, gpus);
+                        changed |= render_settings_ui(right, settings, gpus, mesh_memory);
+                    }
+                    SettingsTab::Document => {
+                        result.document_overrides_changed |=
+                            document_settings_ui(right, settings, overrides);
+                    }
+                    SettingsTab::Profiles => {
+                        result.profile_action =
+                            profiles_ui(right, settings, profiles, profile_name_input);
                     }
                     SettingsTab::About => {
                         about_ui(right, gpu_name);
@@ -83,7 +168,8 @@ pub(super) fn draw_settings_window(
                 }
             });
         });
-    changed
+    result.changed = changed;
+    result
 }
 
 fn camera_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
@@ -105,23 +191,26 @@ fn camera_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
     changed |= ui
         .add(egui::Slider::new(&mut camera.max_distance, 5.0..=2000.0).text("Max distance"))
         .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut camera.transition_ms, 0..=1000)
+                .suffix(" ms")
+                .text("View transition"),
+        )
+        .on_hover_text("Duration of animated snaps, fits and zooms; 0 jumps instantly")
+        .changed();
 
     ui.separator();
     ui.label("Axis preset");
-    egui::ComboBox::from_id_salt("axis_preset_combo")
-        .width(260.0)
-        .selected_text(camera.axis_preset.label())
-        .show_ui(ui, |ui| {
-            for preset in AxisPreset::ALL {
-                if ui
-                    .selectable_value(&mut camera.axis_preset, preset, preset.label())
-                    .changed()
-                {
-                    changed = true;
-                }
-            }
-        });
-    ui.weak(camera.axis_preset.description());
+    changed |= axis_convention_ui(
+        ui,
+        "axis_preset_combo",
+        &mut camera.axis_preset,
+        &mut camera.custom_axes,
+    );
+    if camera.custom_axes.is_none() {
+        ui.weak(camera.axis_preset.description());
+    }
 
     ui.separator();
     ui.label("Projection");
@@ -215,7 +304,835 @@ fn lighting_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
     changed
 }
 
-fn render_settings_ui(ui: &mut Ui, settings: &mut UserSettings, gpus: &[String]) -> bool {
+fn input_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let input = &mut settings.input;
+    let mut changed = false;
+
+    changed |= ui
+        .checkbox(&mut input.tool_shortcuts, "Tool keyboard shortcuts")
+        .on_hover_text(
+            "Activate tools of the active workbench with their key, shown in the tool's tooltip. \
+             Pressing it again cycles through tools sharing the key.",
+        )
+        .changed();
+    changed |= ui
+        .checkbox(&mut input.sticky_tools, "Sticky tools")
+        .on_hover_text(
+            "Keep a drawing or placement tool active after it finishes an element, \
+             instead of returning to no tool.",
+        )
+        .changed();
+
+    changed
+}
+
+fn snapping_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let snapping = &mut settings.snapping;
+    let mut changed = false;
+
+    ui.label("Grid");
+    ui.horizontal(|ui| {
+        ui.label("Spacing:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut snapping.grid_spacing)
+                    .range(0.01..=1000.0)
+                    .speed(0.1)
+                    .suffix(" mm"),
+            )
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Major line every:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut snapping.grid_subdivisions)
+                    .range(1..=100)
+                    .suffix(" cells"),
+            )
+            .changed();
+    });
+    changed |= ui
+        .checkbox(&mut snapping.show_grid, "Show grid while sketching")
+        .changed();
+    changed |= ui
+        .checkbox(&mut snapping.snap_to_grid, "Snap to grid")
+        .changed();
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("Object snap");
+    changed |= ui
+        .checkbox(&mut snapping.object_snap, "Snap to existing geometry")
+        .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut snapping.snap_radius_px, 2.0..=40.0)
+                .text("Snap radius (px)")
+                .integer(),
+        )
+        .changed();
+
+    ui.add_space(4.0);
+    ui.label("Priority (highest first)");
+    let priorities = &mut snapping.object_snap_priorities;
+    let mut swap = None;
+    let mut remove = None;
+    egui::Grid::new("object_snap_priority_grid")
+        .num_columns(4)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            let count = priorities.len();
+            for (index, kind) in priorities.iter().enumerate() {
+                ui.label(format!("{}. {}", index + 1, kind.label()));
+                if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some((index, index - 1));
+                }
+                if ui
+                    .add_enabled(index + 1 < count, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some((index, index + 1));
+                }
+                if ui.button("Disable").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some((a, b)) = swap {
+        priorities.swap(a, b);
+        changed = true;
+    }
+    if let Some(index) = remove {
+        priorities.remove(index);
+        changed = true;
+    }
+
+    let disabled: Vec<_> = ObjectSnapKind::ALL
+        .into_iter()
+        .filter(|kind| !priorities.contains(kind))
+        .collect();
+    if !disabled.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Enable:");
+            for kind in disabled {
+                if ui.button(kind.label()).clicked() {
+                    priorities.push(kind);
+                    changed = true;
+                }
+            }
+        });
+    }
+
+    changed
+}
+
+fn units_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let units = &mut settings.units;
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Display unit:");
+        egui::ComboBox::from_id_salt("display_unit_combo")
+            .selected_text(units.display_unit.label())
+            .show_ui(ui, |ui| {
+                for unit in LengthUnit::ALL {
+                    changed |= ui
+                        .selectable_value(&mut units.display_unit, unit, unit.label())
+                        .changed();
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        ui.label("Decimal places:");
+        changed |= ui
+            .add(egui::DragValue::new(&mut units.decimal_places).range(0..=6))
+            .changed();
+    });
+
+    ui.add_enabled_ui(units.display_unit == LengthUnit::Inch, |ui| {
+        changed |= ui
+            .checkbox(&mut units.inch_fractions, "Show inches as fractions")
+            .changed();
+        ui.add_enabled_ui(units.inch_fractions, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Precision:");
+                egui::ComboBox::from_id_salt("fraction_denominator_combo")
+                    .selected_text(format!("1/{}\"", units.fraction_denominator))
+                    .show_ui(ui, |ui| {
+                        for denominator in [2, 4, 8, 16, 32, 64] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut units.fraction_denominator,
+                                    denominator,
+                                    format!("1/{}\"", denominator),
+                                )
+                                .changed();
+                        }
+                    });
+            });
+        });
+    });
+
+    ui.add_space(8.0);
+    ui.label(
+        egui::RichText::new(format!(
+            "Preview: {}",
+            units.format().format_length(25.4 * 1.5 + 0.4)
+        ))
+        .weak(),
+    );
+
+    changed
+}
+
+fn autosave_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let autosave = &mut settings.autosave;
+    let mut changed = false;
+
+    changed |= ui
+        .checkbox(&mut autosave.enabled, "Autosave recovery copies")
+        .changed();
+    ui.add_enabled_ui(autosave.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Interval:");
+            let mut minutes = autosave.interval_secs as f32 / 60.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut minutes)
+                        .range(0.5..=120.0)
+                        .speed(0.5)
+                        .suffix(" min"),
+                )
+                .changed()
+            {
+                autosave.interval_secs = (minutes * 60.0).round() as u32;
+                changed = true;
+            }
+        });
+    });
+    ui.label(
+        egui::RichText::new(
+            "Only documents that have been saved to a file are autosaved. Autosave writes \
+             name.autosave.prtcad next to the document (or in the backup folder) and never \
+             changes the document itself; saving removes the copy.",
+        )
+        .weak(),
+    );
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("Backups");
+    ui.horizontal(|ui| {
+        ui.label("Copies to keep:");
+        changed |= ui
+            .add(egui::DragValue::new(&mut autosave.backup_count).range(0..=20))
+            .changed();
+    });
+    ui.label(
+        egui::RichText::new("Each save moves the previous file to file.prtcad.bak1, bak2, …")
+            .weak(),
+    );
+
+    let mut next_to_document = autosave.backup_dir.is_none();
+    if ui
+        .checkbox(&mut next_to_document, "Store backups next to the document")
+        .changed()
+    {
+        autosave.backup_dir = if next_to_document {
+            None
+        } else {
+            settings::SettingsStore::new()
+                .ok()
+                .and_then(|store| store.path().parent().map(|dir| dir.join("backups")))
+        };
+        changed = true;
+    }
+    if let Some(dir) = autosave.backup_dir.as_mut() {
+        ui.horizontal(|ui| {
+            ui.label("Backup folder:");
+            let mut text = dir.display().to_string();
+            if ui.text_edit_singleline(&mut text).changed() {
+                *dir = text.into();
+                changed = true;
+            }
+        });
+    }
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("After Saving");
+    ui.label(
+        egui::RichText::new(
+            "Copy each saved file to another folder (e.g. a synced cloud folder) or run a \
+             command such as \"rclone copy {file} remote:printcad\".",
+        )
+        .weak(),
+    );
+    let mut remove = None;
+    for (index, hook) in autosave.hooks.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.group(|ui| {
+                changed |= save_hook_ui(ui, hook);
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+        });
+    }
+    if let Some(index) = remove {
+        autosave.hooks.remove(index);
+        changed = true;
+    }
+    if ui.button("Add Hook").clicked() {
+        autosave.hooks.push(SaveHook::default());
+        changed = true;
+    }
+
+    changed
+}
+
+fn save_hook_ui(ui: &mut Ui, hook: &mut SaveHook) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut hook.enabled, "").changed();
+        let is_copy = matches!(hook.action, SaveHookAction::CopyToFolder { .. });
+        egui::ComboBox::from_id_salt("save_hook_kind")
+            .selected_text(if is_copy {
+                "Copy to folder"
+            } else {
+                "Run command"
+            })
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(is_copy, "Copy to folder").clicked() && !is_copy {
+                    hook.action = SaveHookAction::CopyToFolder {
+                        folder: std::path::PathBuf::new(),
+                    };
+                    changed = true;
+                }
+                if ui.selectable_label(!is_copy, "Run command").clicked() && is_copy {
+                    hook.action = SaveHookAction::Command {
+                        command: String::new(),
+                    };
+                    changed = true;
+                }
+            });
+        match &mut hook.action {
+            SaveHookAction::CopyToFolder { folder } => {
+                let mut text = folder.display().to_string();
+                if ui
+                    .add(egui::TextEdit::singleline(&mut text).hint_text("Folder"))
+                    .changed()
+                {
+                    *folder = text.into();
+                    changed = true;
+                }
+            }
+            SaveHookAction::Command { command } => {
+                changed |= ui
+                    .add(egui::TextEdit::singleline(command).hint_text("Command"))
+                    .changed();
+            }
+        }
+    });
+    if matches!(hook.action, SaveHookAction::CopyToFolder { .. }) {
+        ui.horizontal(|ui| {
+            ui.label("Keep:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut hook.keep_copies)
+                        .range(0..=1000)
+                        .custom_formatter(|value, _| match value as u32 {
+                            0 => "all".to_string(),
+                            n => format!("{n} copies"),
+                        }),
+                )
+                .changed();
+            ui.label("Delete after:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut hook.max_age_days)
+                        .range(0..=3650)
+                        .custom_formatter(|value, _| match value as u32 {
+                            0 => "never".to_string(),
+                            n => format!("{n} days"),
+                        }),
+                )
+                .changed();
+        });
+    } else {
+        ui.horizontal(|ui| {
+            ui.label("Stop after:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut hook.timeout_secs)
+                        .range(0..=86_400)
+                        .custom_formatter(|value, _| match value as u32 {
+                            0 => "never".to_string(),
+                            n => format!("{n} s"),
+                        }),
+                )
+                .changed();
+        });
+    }
+    changed
+}
+
+fn library_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let mut changed = false;
+    ui.label(
+        egui::RichText::new(
+            "The Part Library lists the .prtcad, STEP, and STL files of this folder and its \
+             subfolders.",
+        )
+        .weak(),
+    );
+    ui.horizontal(|ui| {
+        ui.label("Library folder:");
+        let mut text = settings
+            .library
+            .folder
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        if ui.text_edit_singleline(&mut text).changed() {
+            let text = text.trim();
+            settings.library.folder = (!text.is_empty()).then(|| text.into());
+            changed = true;
+        }
+    });
+    changed
+}
+
+fn document_settings_ui(
+    ui: &mut Ui,
+    settings: &UserSettings,
+    overrides: &mut DocumentOverrides,
+) -> bool {
+    let mut changed = false;
+    ui.label(
+        egui::RichText::new(
+            "Settings checked here are saved in the document and take precedence over your \
+             preferences while it is open.",
+        )
+        .weak(),
+    );
+    ui.add_space(8.0);
+
+    changed |= override_checkbox(ui, &mut overrides.units, "Units", || {
+        settings.units.format()
+    });
+    if let Some(units) = overrides.units.as_mut() {
+        ui.indent("document_units", |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("document_unit_combo")
+                    .selected_text(units.unit.label())
+                    .show_ui(ui, |ui| {
+                        for unit in LengthUnit::ALL {
+                            changed |= ui
+                                .selectable_value(&mut units.unit, unit, unit.label())
+                                .changed();
+                        }
+                    });
+                ui.label("Decimals:");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut units.decimals).range(0..=6))
+                    .changed();
+            });
+        });
+    }
+
+    if override_checkbox(ui, &mut overrides.axis_preset, "Axis convention", || {
+        settings.camera.axis_preset
+    }) {
+        overrides.custom_axes = overrides.axis_preset.and(settings.camera.custom_axes);
+        changed = true;
+    }
+    if let Some(preset) = overrides.axis_preset.as_mut() {
+        ui.indent("document_axes", |ui| {
+            changed |= axis_convention_ui(
+                ui,
+                "document_axis_preset_combo",
+                preset,
+                &mut overrides.custom_axes,
+            );
+        });
+    }
+
+    changed |= override_checkbox(ui, &mut overrides.grid_spacing, "Grid spacing", || {
+        settings.snapping.grid_spacing
+    });
+    if let Some(spacing) = overrides.grid_spacing.as_mut() {
+        ui.indent("document_grid", |ui| {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(spacing)
+                        .range(0.01..=1000.0)
+                        .speed(0.1)
+                        .suffix(" mm"),
+                )
+                .changed();
+        });
+    }
+
+    changed |= override_checkbox(
+        ui,
+        &mut overrides.tessellation,
+        "Tessellation quality",
+        Default::default,
+    );
+    if let Some(tessellation) = overrides.tessellation.as_mut() {
+        ui.indent("document_tessellation", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Chord tolerance:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut tessellation.chord_tolerance)
+                            .range(0.001..=10.0)
+                            .speed(0.01)
+                            .suffix(" mm"),
+                    )
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Angular tolerance:");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut tessellation.angular_tolerance_deg)
+                            .range(1.0..=90.0)
+                            .suffix("°"),
+                    )
+                    .changed();
+            });
+        });
+    }
+
+    changed
+}
+
+/// Preset picker with a "Custom" entry that reveals per-axis controls.
+fn axis_convention_ui(
+    ui: &mut Ui,
+    id_salt: &str,
+    preset: &mut AxisPreset,
+    custom: &mut Option<AxisSystem>,
+) -> bool {
+    let mut changed = false;
+    let selected_text = match custom {
+        Some(axes) => format!("Custom ({})", axes.label()),
+        None => preset.label().to_string(),
+    };
+    egui::ComboBox::from_id_salt(id_salt)
+        .width(260.0)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for option in AxisPreset::ALL {
+                if ui
+                    .selectable_label(custom.is_none() && *preset == option, option.label())
+                    .clicked()
+                {
+                    *preset = option;
+                    *custom = None;
+                    changed = true;
+                }
+            }
+            if ui.selectable_label(custom.is_some(), "Custom…").clicked() && custom.is_none() {
+                *custom = Some(preset.axis_system());
+                changed = true;
+            }
+        });
+
+    if let Some(axes) = custom.as_mut() {
+        changed |= custom_axes_ui(ui, id_salt, axes);
+    }
+    changed
+}
+
+/// Edit a custom axis system by picking the right and up axes plus handedness; the forward axis
+/// follows from those.
+fn custom_axes_ui(ui: &mut Ui, id_salt: &str, axes: &mut AxisSystem) -> bool {
+    let mut right = axes.horizontal();
+    let mut up = axes.vertical();
+    let mut handedness = axes.handedness();
+    let mut edited = false;
+
+    egui::Grid::new((id_salt, "custom_axes"))
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Right:");
+            edited |= signed_axis_combo(ui, (id_salt, "right"), &mut right);
+            ui.end_row();
+            ui.label("Up:");
+            edited |= signed_axis_combo(ui, (id_salt, "up"), &mut up);
+            ui.end_row();
+            ui.label("Handedness:");
+            ui.horizontal(|ui| {
+                for option in [Handedness::Right, Handedness::Left] {
+                    edited |= ui
+                        .radio_value(&mut handedness, option, option.label())
+                        .changed();
+                }
+            });
+            ui.end_row();
+        });
+
+    let mut changed = false;
+    if edited {
+        // Picking the up axis as the new right axis (or vice versa) swaps the two.
+        if right.direction() == up.direction() {
+            if right != axes.horizontal() {
+                up = axes.horizontal();
+            } else {
+                right = axes.vertical();
+            }
+        }
+        match AxisSystem::with_handedness(right, up, handedness) {
+            Ok(system) => {
+                changed = system != *axes;
+                *axes = system;
+            }
+            Err(err) => {
+                ui.colored_label(Color32::from_rgb(220, 80, 80), err.to_string());
+            }
+        }
+    }
+    ui.weak(format!("Forward: {}", axes.depth().signed_label()));
+    changed
+}
+
+fn signed_axis_combo(ui: &mut Ui, id_salt: impl std::hash::Hash, axis: &mut Axis) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id_salt)
+        .width(60.0)
+        .selected_text(axis.signed_label())
+        .show_ui(ui, |ui| {
+            for option in Axis::ALL {
+                changed |= ui
+                    .selectable_value(axis, option, option.signed_label())
+                    .changed();
+            }
+        });
+    changed
+}
+
+/// Checkbox toggling a document override; enabling it starts from `initial`.
+fn override_checkbox<T>(
+    ui: &mut Ui,
+    value: &mut Option<T>,
+    label: &str,
+    initial: impl FnOnce() -> T,
+) -> bool {
+    let mut enabled = value.is_some();
+    if !ui.checkbox(&mut enabled, label).changed() {
+        return false;
+    }
+    *value = enabled.then(initial);
+    true
+}
+
+fn exchange_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let mut changed = false;
+    ui.label(
+        egui::RichText::new(
+            "Conventions assumed for files of each format. Imported geometry is rotated and \
+             scaled into the document's axes and millimetres; exports do the reverse.",
+        )
+        .weak(),
+    );
+    ui.add_space(8.0);
+
+    egui::Grid::new("exchange_settings_grid")
+        .num_columns(4)
+        .spacing([8.0, 6.0])
+        .show(ui, |ui| {
+            ui.strong("Format");
+            ui.strong("Axes");
+            ui.strong("Unit");
+            ui.strong("Scale");
+            ui.end_row();
+            for format in ExchangeFormat::ALL {
+                let options = settings.exchange.options_mut(format);
+                ui.label(format.label());
+                let selected_text = match options.file_axes.preset() {
+                    Some(preset) => preset.label().to_string(),
+                    None => options.file_axes.label(),
+                };
+                egui::ComboBox::from_id_salt(("exchange_axes", format))
+                    .width(220.0)
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for preset in AxisPreset::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut options.file_axes,
+                                    preset.axis_system(),
+                                    preset.label(),
+                                )
+                                .changed();
+                        }
+                    });
+                egui::ComboBox::from_id_salt(("exchange_unit", format))
+                    .selected_text(options.file_unit.label())
+                    .show_ui(ui, |ui| {
+                        for unit in LengthUnit::ALL {
+                            changed |= ui
+                                .selectable_value(&mut options.file_unit, unit, unit.label())
+                                .changed();
+                        }
+                    });
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut options.scale)
+                            .speed(0.01)
+                            .range(0.0001..=10_000.0),
+                    )
+                    .changed();
+                ui.end_row();
+            }
+        });
+
+    if ui.button("Reset to format defaults").clicked() {
+        settings.exchange = Default::default();
+        changed = true;
+    }
+    changed
+}
+
+fn printer_settings_ui(ui: &mut Ui, settings: &mut UserSettings) -> bool {
+    let mut changed = false;
+    let printers = &mut settings.printers;
+    ui.label(
+        egui::RichText::new(
+            "Build volumes used by the Print Preparation workbench. The active printer is \
+             drawn on the bed and used to check that bodies fit.",
+        )
+        .weak(),
+    );
+    ui.add_space(8.0);
+
+    let mut remove = None;
+    for (index, profile) in printers.profiles.iter().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut printers.active, index, &profile.name)
+                .changed();
+            if ui.small_button("Remove").clicked() {
+                remove = Some(index);
+            }
+        });
+    }
+    if let Some(index) = remove {
+        printers.profiles.remove(index);
+        if printers.active >= index && printers.active > 0 {
+            printers.active -= 1;
+        }
+        changed = true;
+    }
+    if ui.button("Add printer").clicked() {
+        printers.profiles.push(PrinterProfile {
+            name: format!("Printer {}", printers.profiles.len() + 1),
+            ..PrinterProfile::default()
+        });
+        printers.active = printers.profiles.len() - 1;
+        changed = true;
+    }
+
+    let Some(profile) = printers.profiles.get_mut(printers.active) else {
+        ui.add_space(8.0);
+        ui.label("No printer configured.");
+        return changed;
+    };
+
+    ui.separator();
+    egui::Grid::new("printer_settings_grid")
+        .num_columns(2)
+        .spacing([8.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("Name:");
+            changed |= ui.text_edit_singleline(&mut profile.name).changed();
+            ui.end_row();
+
+            ui.label("Bed shape:");
+            egui::ComboBox::from_id_salt("printer_bed_shape")
+                .selected_text(profile.shape.label())
+                .show_ui(ui, |ui| {
+                    for shape in BedShape::ALL {
+                        changed |= ui
+                            .selectable_value(&mut profile.shape, shape, shape.label())
+                            .changed();
+                    }
+                });
+            ui.end_row();
+
+            let circular = profile.shape == BedShape::Circular;
+            ui.label(if circular {
+                "Bed diameter:"
+            } else {
+                "Bed width (X):"
+            });
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut profile.bed_width)
+                        .range(10.0..=2000.0)
+                        .speed(1.0)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+
+            if !circular {
+                ui.label("Bed depth (Y):");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut profile.bed_depth)
+                            .range(10.0..=2000.0)
+                            .speed(1.0)
+                            .suffix(" mm"),
+                    )
+                    .changed();
+                ui.end_row();
+            }
+
+            ui.label("Max height (Z):");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut profile.max_height)
+                        .range(10.0..=2000.0)
+                        .speed(1.0)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Origin:");
+            egui::ComboBox::from_id_salt("printer_bed_origin")
+                .selected_text(profile.origin.label())
+                .show_ui(ui, |ui| {
+                    for origin in BedOrigin::ALL {
+                        changed |= ui
+                            .selectable_value(&mut profile.origin, origin, origin.label())
+                            .changed();
+                    }
+                });
+            ui.end_row();
+        });
+
+    // Round beds are described by their diameter alone.
+    if profile.shape == BedShape::Circular && profile.bed_depth != profile.bed_width {
+        profile.bed_depth = profile.bed_width;
+        changed = true;
+    }
+    changed
+}
+
+fn render_settings_ui(
+    ui: &mut Ui,
+    settings: &mut UserSettings,
+    gpus: &[String],
+    mesh_memory: &MeshMemoryReport,
+) -> bool {
     let mut changed = false;
     ui.label("GPU");
     ui.separator();
@@ -318,9 +1235,131 @@ fn render_settings_ui(ui: &mut Ui, settings: &mut UserSettings, gpus: &[String])
             });
     });
 
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("Mesh memory");
+    ui.horizontal(|ui| {
+        ui.label("Budget (0 = unlimited):");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut settings.rendering.mesh_memory_budget_mb)
+                    .range(0..=65536)
+                    .speed(64.0)
+                    .suffix(" MiB"),
+            )
+            .changed();
+    });
+    ui.label(
+        egui::RichText::new(
+            "Above the budget, meshes of bodies out of view are dropped and rebuilt when they \
+             come back into view.",
+        )
+        .weak(),
+    );
+    ui.label(format!(
+        "Cached: {}   GPU buffers: {}",
+        format_mib(mesh_memory.cpu_total),
+        format_mib(mesh_memory.gpu_allocated)
+    ));
+    if !mesh_memory.bodies.is_empty() {
+        egui::Grid::new("mesh_memory_grid")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong("Body");
+                ui.strong("Cached");
+                ui.strong("Drawn");
+                ui.end_row();
+                for (name, cpu, gpu) in mesh_memory.bodies.iter().take(8) {
+                    ui.label(name);
+                    ui.label(format_mib(*cpu));
+                    ui.label(format_mib(*gpu));
+                    ui.end_row();
+                }
+            });
+    }
+
     changed
 }
 
+fn format_mib(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn profiles_ui(
+    ui: &mut Ui,
+    settings: &UserSettings,
+    profiles: &[String],
+    name_input: &mut String,
+) -> Option<ProfileAction> {
+    let mut action = None;
+
+    ui.label(format!(
+        "Active profile: {}",
+        settings.active_profile.as_deref().unwrap_or("(unsaved)")
+    ));
+    ui.add_space(4.0);
+    ui.separator();
+    ui.label("Saved profiles");
+
+    if profiles.is_empty() {
+        ui.weak("No saved profiles yet.");
+    }
+    egui::Grid::new("settings_profiles_grid")
+        .num_columns(3)
+        .spacing([10.0, 6.0])
+        .show(ui, |ui| {
+            for name in profiles {
+                let is_active = settings.active_profile.as_deref() == Some(name.as_str());
+                if is_active {
+                    ui.strong(name);
+                } else {
+                    ui.label(name);
+                }
+                if ui.button("Load").clicked() {
+                    action = Some(ProfileAction::Load(name.clone()));
+                }
+                if ui.button("Delete").clicked() {
+                    action = Some(ProfileAction::Delete(name.clone()));
+                }
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("Save current settings as");
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(name_input)
+                .desired_width(160.0)
+                .hint_text("Profile name"),
+        );
+        let name = name_input.trim();
+        if ui
+            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+            .clicked()
+        {
+            action = Some(ProfileAction::Save(name.to_string()));
+            name_input.clear();
+        }
+    });
+
+    ui.add_space(12.0);
+    ui.separator();
+    ui.label("Share");
+    ui.horizontal(|ui| {
+        if ui.button("Export…").clicked() {
+            action = Some(ProfileAction::Export);
+        }
+        if ui.button("Import…").clicked() {
+            action = Some(ProfileAction::Import);
+        }
+    });
+
+    action
+}
+
 fn light_source_row(ui: &mut Ui, label: &str, light: &mut LightSource) -> bool {
     let mut changed = false;
 
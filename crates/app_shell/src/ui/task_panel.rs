@@ -0,0 +1,45 @@
+use core_document::{TaskAction, TaskPanel, Workbench, WorkbenchRuntimeContext};
+use egui::{self, Ui};
+
+/// Form of the workbench's open task with OK, Apply, and Cancel buttons below
+/// it, drawn in place of the workbench's right panel. Button presses are passed
+/// on to the workbench.
+pub(super) fn task_ui(
+    ui: &mut Ui,
+    workbench: &mut dyn Workbench,
+    ctx: &mut WorkbenchRuntimeContext,
+    task: &TaskPanel,
+) {
+    ui.heading(&task.title);
+    ui.separator();
+    let mut action = None;
+    egui::TopBottomPanel::bottom("task_panel_buttons")
+        .show_separator_line(true)
+        .show_inside(ui, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(task.enabled, egui::Button::new(&task.ok_label))
+                    .clicked()
+                {
+                    action = Some(TaskAction::Ok);
+                }
+                if task.apply
+                    && ui
+                        .add_enabled(task.enabled, egui::Button::new("Apply"))
+                        .clicked()
+                {
+                    action = Some(TaskAction::Apply);
+                }
+                if ui.button("Cancel").clicked() {
+                    action = Some(TaskAction::Cancel);
+                }
+            });
+        });
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| workbench.ui_task(ui, ctx));
+    if let Some(action) = action {
+        workbench.on_task_action(action, ctx);
+    }
+}
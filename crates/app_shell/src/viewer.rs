@@ -0,0 +1,114 @@
+//! Read-only viewer mode (`app_shell --viewer`): documents, STL, and STEP
+//! files can be navigated, sectioned, and measured, but not edited or saved.
+//!
+//! The viewer runs with a workbench id no workbench is registered under, so
+//! no workbench tools, panels, or viewport input show up; features are still
+//! regenerated by the workbenches that made them.
+
+use core_document::{ScreenSpaceLabel, ScreenSpaceOverlay, UnitFormat};
+use glam::{DVec3, Vec3};
+
+use crate::camera::CameraController;
+
+/// Command line flag starting the viewer.
+pub const VIEWER_FLAG: &str = "--viewer";
+
+/// Active workbench of the viewer.
+pub const VIEWER_WORKBENCH_ID: &str = "viewer";
+
+const COLOR: [f32; 3] = [0.95, 0.75, 0.1];
+const THICKNESS: f32 = 2.0;
+/// Half size of the crosses marking the measured points, in pixels.
+const POINT_SIZE: f32 = 5.0;
+
+/// Whether the viewer was asked for on the command line.
+pub fn requested() -> bool {
+    std::env::args_os().skip(1).any(|arg| arg == VIEWER_FLAG)
+}
+
+/// Distance between two points clicked on the model.
+#[derive(Debug, Default)]
+pub struct Measurement {
+    /// Clicks in the viewport place points instead of selecting.
+    pub active: bool,
+    /// World positions, in double precision so distances between points
+    /// far from the origin stay exact.
+    points: Vec<DVec3>,
+}
+
+impl Measurement {
+    /// Start or stop measuring; stopping clears the measurement.
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        if !self.active {
+            self.points.clear();
+        }
+    }
+
+    /// Clear the measurement, returning whether there was one.
+    pub fn clear(&mut self) -> bool {
+        let had_points = !self.points.is_empty();
+        self.points.clear();
+        had_points
+    }
+
+    /// Place a point at `world` (the surface under the cursor); a third
+    /// point starts a new measurement. Returns the offset from the first
+    /// point to the second once both are placed.
+    pub fn place(&mut self, world: [f64; 3]) -> Option<Vec3> {
+        if self.points.len() == 2 {
+            self.points.clear();
+        }
+        self.points.push(DVec3::from_array(world));
+        match self.points[..] {
+            [a, b] => Some((b - a).as_vec3()),
+            _ => None,
+        }
+    }
+
+    /// Crosses at the placed points, and the line between them labelled with
+    /// its length.
+    pub fn overlays(
+        &self,
+        camera: &CameraController,
+        units: &UnitFormat,
+    ) -> (Vec<ScreenSpaceOverlay>, Vec<ScreenSpaceLabel>) {
+        let (origin_x, origin_y, _, _) = camera.viewport_info();
+        let project = |world: DVec3| {
+            camera
+                .world_to_screen(camera.to_frame(world.to_array()))
+                .map(|(x, y)| [x - origin_x, y - origin_y])
+        };
+
+        let mut overlays = Vec::new();
+        let mut labels = Vec::new();
+        for [x, y] in self.points.iter().filter_map(|point| project(*point)) {
+            overlays.push(ScreenSpaceOverlay::new(
+                [x - POINT_SIZE, y - POINT_SIZE],
+                [x + POINT_SIZE, y + POINT_SIZE],
+                COLOR,
+                THICKNESS,
+            ));
+            overlays.push(ScreenSpaceOverlay::new(
+                [x - POINT_SIZE, y + POINT_SIZE],
+                [x + POINT_SIZE, y - POINT_SIZE],
+                COLOR,
+                THICKNESS,
+            ));
+        }
+        if let [a, b] = self.points[..] {
+            if let (Some(start), Some(end), Some(middle)) =
+                (project(a), project(b), project((a + b) * 0.5))
+            {
+                overlays.push(ScreenSpaceOverlay::new(start, end, COLOR, THICKNESS));
+                labels.push(ScreenSpaceLabel {
+                    position: middle,
+                    text: units.format_length(a.distance(b) as f32),
+                    color: COLOR,
+                    highlighted: false,
+                });
+            }
+        }
+        (overlays, labels)
+    }
+}
@@ -1,5 +1,35 @@
 use glam::{Mat3, Vec3};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum AxisSystemError {
+    #[error("axes must be orthogonal, but {0} is used more than once")]
+    NotOrthogonal(AxisDirection),
+    #[error("axis system is {found:?}-handed, expected {expected:?}-handed")]
+    WrongHandedness {
+        found: Handedness,
+        expected: Handedness,
+    },
+}
+
+/// Handedness of the (right, up, forward) basis of an [`AxisSystem`].
+///
+/// `Right` means `right × up = forward`, which is the case for the default CAD preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Handedness {
+    Right,
+    Left,
+}
+
+impl Handedness {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Handedness::Right => "Right-handed",
+            Handedness::Left => "Left-handed",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AxisDirection {
@@ -8,7 +38,15 @@ pub enum AxisDirection {
     Z,
 }
 
+impl std::fmt::Display for AxisDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
 impl AxisDirection {
+    pub const ALL: [AxisDirection; 3] = [AxisDirection::X, AxisDirection::Y, AxisDirection::Z];
+
     pub const fn label(self) -> &'static str {
         match self {
             AxisDirection::X => "X",
@@ -40,13 +78,23 @@ impl AxisSign {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Axis {
     direction: AxisDirection,
     sign: AxisSign,
 }
 
 impl Axis {
+    /// All six signed world axes, in `+X, -X, +Y, -Y, +Z, -Z` order.
+    pub const ALL: [Axis; 6] = [
+        Axis::positive(AxisDirection::X),
+        Axis::negative(AxisDirection::X),
+        Axis::positive(AxisDirection::Y),
+        Axis::negative(AxisDirection::Y),
+        Axis::positive(AxisDirection::Z),
+        Axis::negative(AxisDirection::Z),
+    ];
+
     pub const fn new(direction: AxisDirection, sign: AxisSign) -> Self {
         Self { direction, sign }
     }
@@ -101,18 +149,106 @@ impl Axis {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Maps the view roles (right, up, forward) onto signed world axes.
+///
+/// Serialized systems are validated on load, so a hand-edited file cannot produce a degenerate
+/// basis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawAxisSystem")]
 pub struct AxisSystem {
     horizontal: Axis,
     vertical: Axis,
     depth: Axis,
 }
 
+#[derive(Deserialize)]
+struct RawAxisSystem {
+    horizontal: Axis,
+    vertical: Axis,
+    depth: Axis,
+}
+
+impl TryFrom<RawAxisSystem> for AxisSystem {
+    type Error = AxisSystemError;
+
+    fn try_from(raw: RawAxisSystem) -> Result<Self, Self::Error> {
+        AxisSystem::try_new(raw.horizontal, raw.vertical, raw.depth)
+    }
+}
+
 impl AxisSystem {
     pub const fn from_preset(preset: AxisPreset) -> Self {
         preset.axis_system()
     }
 
+    /// Build a user-defined axis system, checking that the three axes are orthogonal.
+    pub fn try_new(horizontal: Axis, vertical: Axis, depth: Axis) -> Result<Self, AxisSystemError> {
+        let directions = [horizontal.direction, vertical.direction, depth.direction];
+        for (index, direction) in directions.iter().enumerate() {
+            if directions[index + 1..].contains(direction) {
+                return Err(AxisSystemError::NotOrthogonal(*direction));
+            }
+        }
+        Ok(Self::new(horizontal, vertical, depth))
+    }
+
+    /// Build an axis system from its right and up axes, choosing the forward axis that gives
+    /// the requested handedness.
+    pub fn with_handedness(
+        horizontal: Axis,
+        vertical: Axis,
+        handedness: Handedness,
+    ) -> Result<Self, AxisSystemError> {
+        let system = Self::try_new(horizontal, vertical, Axis::positive(AxisDirection::X))
+            .or_else(|_| Self::try_new(horizontal, vertical, Axis::positive(AxisDirection::Y)))
+            .or_else(|_| Self::try_new(horizontal, vertical, Axis::positive(AxisDirection::Z)))?;
+        if system.handedness() == handedness {
+            Ok(system)
+        } else {
+            Ok(Self::new(horizontal, vertical, system.depth.inverted()))
+        }
+    }
+
+    /// Check that this system has the expected handedness.
+    pub fn require_handedness(self, expected: Handedness) -> Result<Self, AxisSystemError> {
+        let found = self.handedness();
+        if found == expected {
+            Ok(self)
+        } else {
+            Err(AxisSystemError::WrongHandedness { found, expected })
+        }
+    }
+
+    pub fn handedness(&self) -> Handedness {
+        if self
+            .right_vec()
+            .cross(self.up_vec())
+            .dot(self.forward_vec())
+            > 0.0
+        {
+            Handedness::Right
+        } else {
+            Handedness::Left
+        }
+    }
+
+    /// The preset this system matches, if any.
+    pub fn preset(&self) -> Option<AxisPreset> {
+        AxisPreset::ALL
+            .into_iter()
+            .find(|preset| preset.axis_system() == *self)
+    }
+
+    /// Short description such as `+X right / +Z up / -Y forward`.
+    pub fn label(&self) -> String {
+        format!(
+            "{} right / {} up / {} forward",
+            self.horizontal.signed_label(),
+            self.vertical.signed_label(),
+            self.depth.signed_label()
+        )
+    }
+
     pub const fn new(horizontal: Axis, vertical: Axis, depth: Axis) -> Self {
         Self {
             horizontal,
@@ -176,9 +312,10 @@ impl Default for AxisSystem {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum AxisPreset {
     /// X right, Y up, Z forward (right-handed, default CAD layout)
+    #[default]
     RightHandedZForward,
     /// X right, Y up, Z backward (right-handed, OpenGL-style forward)
     RightHandedZBackward,
@@ -236,12 +373,6 @@ impl AxisPreset {
     }
 }
 
-impl Default for AxisPreset {
-    fn default() -> Self {
-        AxisPreset::RightHandedZForward
-    }
-}
-
 impl From<AxisPreset> for AxisSystem {
     fn from(value: AxisPreset) -> Self {
         value.axis_system()
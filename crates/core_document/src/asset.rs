@@ -1,5 +1,8 @@
 //! Asset management for external files referenced in documents.
 
+use std::fmt;
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -8,7 +11,7 @@ use uuid::Uuid;
 pub struct AssetReference {
     /// Unique identifier for this asset.
     pub id: Uuid,
-    /// Path within the .prtcad archive (e.g., "assets/imported_base.step").
+    /// Path within the .prtcad archive (`assets/<id>` for embedded assets).
     pub path: String,
     /// Type of asset.
     pub asset_type: AssetType,
@@ -48,6 +51,10 @@ pub enum AssetType {
     Iges,
     /// OBJ file
     Obj,
+    /// PNG image
+    Png,
+    /// JPEG image
+    Jpeg,
     /// Other/unknown format
     Other,
 }
@@ -60,6 +67,8 @@ impl AssetType {
             AssetType::Stl => "stl",
             AssetType::Iges => "iges",
             AssetType::Obj => "obj",
+            AssetType::Png => "png",
+            AssetType::Jpeg => "jpg",
             AssetType::Other => "bin",
         }
     }
@@ -71,7 +80,37 @@ impl AssetType {
             "stl" => AssetType::Stl,
             "iges" | "igs" => AssetType::Iges,
             "obj" => AssetType::Obj,
+            "png" => AssetType::Png,
+            "jpg" | "jpeg" => AssetType::Jpeg,
             _ => AssetType::Other,
         }
     }
+
+    /// Whether the asset is a raster image.
+    pub fn is_image(&self) -> bool {
+        matches!(self, AssetType::Png | AssetType::Jpeg)
+    }
+}
+
+/// Contents of an asset file embedded in the document. Copies of the document
+/// share the bytes.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AssetData(Arc<[u8]>);
+
+impl AssetData {
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for AssetData {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes.into())
+    }
+}
+
+impl fmt::Debug for AssetData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AssetData({} bytes)", self.0.len())
+    }
 }
@@ -0,0 +1,383 @@
+//! Bounding volume hierarchy over the triangles of a mesh, for the ray and
+//! nearest-point queries of the workbenches' analyses (print checks, deviation
+//! from a reference scan).
+//!
+//! Testing a query against every triangle is linear in the triangle count, so
+//! an analysis querying from every triangle is quadratic; the tree narrows each
+//! query down to the few triangles whose boxes it passes near, so a whole
+//! analysis takes about n·log(n) steps.
+
+use glam::Vec3;
+use kernel_api::TriMesh;
+
+/// Triangles kept together in a leaf.
+const LEAF_SIZE: usize = 4;
+/// Candidates whose squared distances differ by less than this fraction are
+/// treated as equally close.
+const TIE_TOLERANCE: f32 = 1e-4;
+
+/// A triangle with its outward unit normal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    pub normal: Vec3,
+}
+
+impl Triangle {
+    /// Triangle with its normal from the winding, or None if it is degenerate.
+    pub fn new(vertices: [Vec3; 3]) -> Option<Self> {
+        let normal = (vertices[1] - vertices[0])
+            .cross(vertices[2] - vertices[0])
+            .try_normalize()?;
+        Some(Self { vertices, normal })
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.vertices[0] + self.vertices[1] + self.vertices[2]) / 3.0
+    }
+
+    /// Distance along the ray to the triangle (Möller–Trumbore), if it is hit.
+    pub fn ray_hit(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let [a, b, c] = self.vertices;
+        let (edge1, edge2) = (b - a, c - a);
+        let p = direction.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(q) * inv_det;
+        (t > 0.0).then_some(t)
+    }
+
+    /// Closest point to `p` on the triangle (Ericson, "Real-Time Collision Detection").
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+        let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = 1.0 / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    /// Leaf: range of `order` holding its triangles. Inner node: index of the
+    /// first child (the second follows it) and 0.
+    start: u32,
+    count: u32,
+}
+
+impl Node {
+    const EMPTY: Node = Node {
+        min: Vec3::ZERO,
+        max: Vec3::ZERO,
+        start: 0,
+        count: 0,
+    };
+
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+
+    /// Entry distance of the ray into the box (0 when it starts inside), if it
+    /// enters it before `limit`.
+    fn ray_entry(&self, origin: Vec3, inv_direction: Vec3, limit: f32) -> Option<f32> {
+        let t1 = (self.min - origin) * inv_direction;
+        let t2 = (self.max - origin) * inv_direction;
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element().min(limit);
+        (near <= far).then_some(near)
+    }
+
+    /// Squared distance from `p` to the box (0 inside it).
+    fn distance_squared(&self, p: Vec3) -> f32 {
+        (self.min - p)
+            .max(p - self.max)
+            .max(Vec3::ZERO)
+            .length_squared()
+    }
+}
+
+/// Component-wise reciprocal of a ray direction. Zero components are nudged
+/// off zero so rays lying in a box's face don't produce NaN slab distances.
+fn inverse_direction(direction: Vec3) -> Vec3 {
+    let nudge = |d: f32| {
+        if d.abs() < 1e-20 {
+            1e-20f32.copysign(d)
+        } else {
+            d
+        }
+    };
+    Vec3::new(nudge(direction.x), nudge(direction.y), nudge(direction.z)).recip()
+}
+
+/// Closest point of the surface to a query point.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfacePoint {
+    pub point: Vec3,
+    /// Index of the triangle the point lies on.
+    pub triangle: usize,
+    /// Normal of that triangle.
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl SurfacePoint {
+    /// Distance to `p`, positive when `p` is on the outer side of the surface.
+    pub fn signed_distance(&self, p: Vec3) -> f32 {
+        if (p - self.point).dot(self.normal) < 0.0 {
+            -self.distance
+        } else {
+            self.distance
+        }
+    }
+}
+
+/// Triangles organized for ray and nearest-point queries. Triangle indices are
+/// those of the list the tree was built from.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    triangles: Vec<Triangle>,
+    nodes: Vec<Node>,
+    /// Triangle indices, grouped by leaf.
+    order: Vec<u32>,
+}
+
+impl Bvh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let mut bvh = Self {
+            order: (0..triangles.len() as u32).collect(),
+            triangles,
+            nodes: Vec::new(),
+        };
+        if !bvh.triangles.is_empty() {
+            let centroids: Vec<Vec3> = bvh.triangles.iter().map(Triangle::centroid).collect();
+            bvh.nodes.push(Node::EMPTY);
+            bvh.build(0, 0, bvh.triangles.len(), &centroids);
+        }
+        bvh
+    }
+
+    /// Tree over the non-degenerate triangles of `mesh`.
+    pub fn from_mesh(mesh: &TriMesh) -> Self {
+        let vertex = |i: u32| mesh.positions.get(i as usize).map(|&p| Vec3::from(p));
+        Self::new(
+            mesh.indices
+                .chunks_exact(3)
+                .filter_map(|tri| {
+                    Triangle::new([vertex(tri[0])?, vertex(tri[1])?, vertex(tri[2])?])
+                })
+                .collect(),
+        )
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Fill node `node` with the triangles `order[start..end]`, splitting it at
+    /// the median centroid along its longest axis until the leaves are small.
+    fn build(&mut self, node: usize, start: usize, end: usize, centroids: &[Vec3]) {
+        let (min, max) = self.order[start..end]
+            .iter()
+            .flat_map(|&triangle| self.triangles[triangle as usize].vertices)
+            .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+                (min.min(p), max.max(p))
+            });
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+        if end - start <= LEAF_SIZE {
+            self.nodes[node].start = start as u32;
+            self.nodes[node].count = (end - start) as u32;
+            return;
+        }
+
+        let (low, high) = self.order[start..end]
+            .iter()
+            .map(|&triangle| centroids[triangle as usize])
+            .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+                (min.min(p), max.max(p))
+            });
+        let extent = high - low;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let middle = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+            centroids[a as usize][axis].total_cmp(&centroids[b as usize][axis])
+        });
+
+        let first_child = self.nodes.len();
+        self.nodes.extend([Node::EMPTY, Node::EMPTY]);
+        self.nodes[node].start = first_child as u32;
+        self.build(first_child, start, middle, centroids);
+        self.build(first_child + 1, middle, end, centroids);
+    }
+
+    /// Triangles of a leaf.
+    fn leaf_triangles(&self, node: &Node) -> impl Iterator<Item = usize> + '_ {
+        self.order[node.start as usize..(node.start + node.count) as usize]
+            .iter()
+            .map(|&triangle| triangle as usize)
+    }
+
+    /// Distance along the ray to the nearest triangle it hits, leaving out the
+    /// triangle `skip` (the one the ray starts from).
+    pub fn ray_hit(&self, origin: Vec3, direction: Vec3, skip: Option<usize>) -> Option<f32> {
+        let inv_direction = inverse_direction(direction);
+        let mut nearest = f32::INFINITY;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.ray_entry(origin, inv_direction, nearest).is_none() {
+                continue;
+            }
+            if node.is_leaf() {
+                for triangle in self.leaf_triangles(node).filter(|&t| Some(t) != skip) {
+                    if let Some(t) = self.triangles[triangle].ray_hit(origin, direction) {
+                        nearest = nearest.min(t);
+                    }
+                }
+            } else {
+                stack.extend([node.start as usize, node.start as usize + 1]);
+            }
+        }
+        nearest.is_finite().then_some(nearest)
+    }
+
+    /// Number of triangles the ray passes through.
+    pub fn ray_hit_count(&self, origin: Vec3, direction: Vec3) -> usize {
+        let inv_direction = inverse_direction(direction);
+        let mut count = 0;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node
+                .ray_entry(origin, inv_direction, f32::INFINITY)
+                .is_none()
+            {
+                continue;
+            }
+            if node.is_leaf() {
+                count += self
+                    .leaf_triangles(node)
+                    .filter(|&t| self.triangles[t].ray_hit(origin, direction).is_some())
+                    .count();
+            } else {
+                stack.extend([node.start as usize, node.start as usize + 1]);
+            }
+        }
+        count
+    }
+
+    /// Whether `p` lies inside the closed surface formed by the triangles (ray
+    /// parity).
+    pub fn contains(&self, p: Vec3) -> bool {
+        // A slightly skewed direction avoids rays running exactly along edges.
+        let direction = Vec3::new(0.01, 0.02, 1.0).normalize();
+        self.ray_hit_count(p, direction) % 2 == 1
+    }
+
+    /// Closest point of the surface to `p`.
+    pub fn closest(&self, p: Vec3) -> Option<SurfacePoint> {
+        // Squared distance, the point, its triangle, and how well the
+        // triangle's normal lines up with the offset to `p`.
+        let mut best: Option<(f32, Vec3, usize, f32)> = None;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let best_squared = best.map_or(f32::INFINITY, |(d, ..)| d);
+            if node.distance_squared(p) > best_squared * (1.0 + TIE_TOLERANCE) {
+                continue;
+            }
+            if node.is_leaf() {
+                for index in self.leaf_triangles(node) {
+                    let triangle = &self.triangles[index];
+                    let q = triangle.closest_point(p);
+                    let squared = p.distance_squared(q);
+                    let alignment = (p - q).normalize_or_zero().dot(triangle.normal).abs();
+                    // Near edges and corners several triangles are about
+                    // equally close; the one facing `p` gives the reliable
+                    // side (slivers have arbitrary normals).
+                    let better = best.map_or(true, |(d, _, _, a)| {
+                        squared < d * (1.0 - TIE_TOLERANCE)
+                            || (squared <= d * (1.0 + TIE_TOLERANCE) && alignment > a)
+                    });
+                    if better {
+                        best = Some((squared, q, index, alignment));
+                    }
+                }
+            } else {
+                // Visit the nearer child first so the farther one is more
+                // likely to be pruned.
+                let (first, second) = (node.start as usize, node.start as usize + 1);
+                if self.nodes[first].distance_squared(p) < self.nodes[second].distance_squared(p) {
+                    stack.extend([second, first]);
+                } else {
+                    stack.extend([first, second]);
+                }
+            }
+        }
+        best.map(|(squared, point, triangle, _)| SurfacePoint {
+            point,
+            triangle,
+            normal: self.triangles[triangle].normal,
+            distance: squared.sqrt(),
+        })
+    }
+}
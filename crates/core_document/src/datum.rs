@@ -0,0 +1,50 @@
+//! Datum geometry: construction planes, axes, and points placed by features.
+//!
+//! Datum features keep their resolved placement in their data under
+//! [`DATUM_GEOMETRY_KEY`], so other workbenches can build on them (a sketch on
+//! a datum plane, a revolve about a datum axis) without knowing how the datum
+//! was defined.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ReferenceAxis, ReferencePlane};
+
+/// Key of the resolved [`DatumGeometry`] in a datum feature's data.
+pub const DATUM_GEOMETRY_KEY: &str = "geometry";
+
+/// World-space placement of a datum feature.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DatumGeometry {
+    Plane(ReferencePlane),
+    Axis(ReferenceAxis),
+    Point([f32; 3]),
+}
+
+impl DatumGeometry {
+    /// Placement stored in a feature's data (None for features that place no
+    /// datum geometry).
+    pub fn from_feature_data(data: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(data.get(DATUM_GEOMETRY_KEY)?.clone()).ok()
+    }
+
+    pub fn plane(&self) -> Option<ReferencePlane> {
+        match self {
+            DatumGeometry::Plane(plane) => Some(*plane),
+            _ => None,
+        }
+    }
+
+    pub fn axis(&self) -> Option<ReferenceAxis> {
+        match self {
+            DatumGeometry::Axis(axis) => Some(*axis),
+            _ => None,
+        }
+    }
+
+    pub fn point(&self) -> Option<[f32; 3]> {
+        match self {
+            DatumGeometry::Point(point) => Some(*point),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Display states: named sets of per-body visibility, opacity, and color
+//! overrides such as "internal view" or "print plate A". At most one state is
+//! active; bodies it does not mention are shown as usual.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BodyId;
+
+/// How a body is shown in a display state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BodyDisplay {
+    pub visible: bool,
+    /// 1.0 is opaque, 0.0 fully transparent.
+    pub opacity: f32,
+    /// Color drawn instead of the body's own (None keeps it).
+    pub color: Option<[f32; 3]>,
+}
+
+impl Default for BodyDisplay {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            color: None,
+        }
+    }
+}
+
+impl BodyDisplay {
+    /// Whether the body is drawn blended with what is behind it.
+    pub fn is_translucent(&self) -> bool {
+        self.opacity < 1.0
+    }
+}
+
+/// A named set of body display overrides.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayState {
+    pub name: String,
+    /// Overridden bodies; the rest use [`BodyDisplay::default`].
+    #[serde(default)]
+    pub bodies: HashMap<BodyId, BodyDisplay>,
+}
+
+impl DisplayState {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bodies: HashMap::new(),
+        }
+    }
+
+    /// How `body` is shown in this state.
+    pub fn body(&self, body: BodyId) -> BodyDisplay {
+        self.bodies.get(&body).copied().unwrap_or_default()
+    }
+
+    /// Change how `body` is shown. Setting the default display drops the
+    /// override.
+    pub fn set_body(&mut self, body: BodyId, display: BodyDisplay) {
+        if display == BodyDisplay::default() {
+            self.bodies.remove(&body);
+        } else {
+            self.bodies.insert(body, display);
+        }
+    }
+}
+
+/// The display states of a document and which one is active.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisplayStates {
+    pub states: Vec<DisplayState>,
+    /// Index into `states` of the active state (None shows every body as usual).
+    #[serde(default)]
+    pub active: Option<usize>,
+}
+
+impl DisplayStates {
+    pub fn active_state(&self) -> Option<&DisplayState> {
+        self.states.get(self.active?)
+    }
+
+    pub fn active_state_mut(&mut self) -> Option<&mut DisplayState> {
+        self.states.get_mut(self.active?)
+    }
+
+    /// How `body` is shown in the active state.
+    pub fn body(&self, body: BodyId) -> BodyDisplay {
+        self.active_state()
+            .map(|state| state.body(body))
+            .unwrap_or_default()
+    }
+
+    /// A name for a new state that no existing state uses.
+    pub fn unused_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Display State {n}"))
+            .find(|name| self.states.iter().all(|state| &state.name != name))
+            .expect("unbounded range")
+    }
+
+    /// Remove the state at `index`, keeping the active one active (or none,
+    /// if it was the one removed).
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.states.len() {
+            return;
+        }
+        self.states.remove(index);
+        self.active = match self.active {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            active => active,
+        };
+    }
+}
@@ -5,7 +5,9 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -69,11 +71,46 @@ pub trait WorkbenchFeature: Send + Sync {
     fn name(&self) -> &str;
 }
 
+/// Problem found when the feature was last recomputed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureStatus {
+    /// Computed, but the result may not be what the user expects.
+    Warning(String),
+    /// Could not be computed.
+    Error(String),
+}
+
+impl FeatureStatus {
+    pub fn message(&self) -> &str {
+        match self {
+            FeatureStatus::Warning(message) | FeatureStatus::Error(message) => message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, FeatureStatus::Error(_))
+    }
+}
+
+/// Source of [`FeatureNode::revision`] values. Shared by every document, so a
+/// node restored by undo or reloaded from disk never takes the value of a
+/// later edit.
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
 /// A feature node in the tree (type-erased).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeatureNode {
     pub id: FeatureId,
     pub workbench_id: WorkbenchId,
+    /// Workbench the feature was created in, which opens it for editing when
+    /// it is double-clicked in the tree (None for features added outside any
+    /// workbench, and in documents saved before this was recorded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<WorkbenchId>,
     pub name: String,
     /// Owning body for this feature (if any). Used for tree hierarchy / grouping.
     #[serde(default)]
@@ -84,6 +121,18 @@ pub struct FeatureNode {
     pub created_at: i64,
     /// Type-erased feature data (serialized JSON)
     pub data: serde_json::Value,
+    /// Parameter expressions driving numeric fields of `data`, keyed by JSON pointer.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub expressions: BTreeMap<String, String>,
+    /// Outcome of the last recompute (None if it succeeded or has not run).
+    /// Not saved: recomputing after loading fills it again.
+    #[serde(skip)]
+    pub status: Option<FeatureStatus>,
+    /// Changes whenever the node is added, loaded, or written through
+    /// [`FeatureTree::get_node_mut`], so edits can be noticed without
+    /// comparing the data. Not saved.
+    #[serde(skip, default = "next_revision")]
+    pub(crate) revision: u64,
 }
 
 impl FeatureNode {
@@ -91,6 +140,7 @@ impl FeatureNode {
         Self {
             id,
             workbench_id: F::workbench_id(),
+            editor: None,
             name: feature.name().to_string(),
             body: None,
             visible: true,
@@ -101,8 +151,17 @@ impl FeatureNode {
                 .unwrap()
                 .as_millis() as i64,
             data: feature.to_json(),
+            expressions: BTreeMap::new(),
+            status: None,
+            revision: next_revision(),
         }
     }
+
+    /// Current revision of the node; a different value means it may have
+    /// been edited since.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
 }
 
 /// Directed acyclic graph representing the feature tree.
@@ -110,8 +169,9 @@ impl FeatureNode {
 pub struct FeatureTree {
     /// Root features (no dependencies).
     roots: Vec<FeatureId>,
-    /// All features indexed by ID (type-erased).
-    features: HashMap<FeatureId, FeatureNode>,
+    /// All features indexed by ID (type-erased). Nodes are shared with
+    /// document snapshots and copied on first write.
+    features: HashMap<FeatureId, Arc<FeatureNode>>,
     /// Dependency graph: feature -> list of dependencies.
     dependencies: HashMap<FeatureId, Vec<FeatureId>>,
     /// Reverse dependencies: feature -> list of dependents.
@@ -125,26 +185,51 @@ impl FeatureTree {
     }
 
     /// Add a feature node to the tree.
-    pub fn add_node(&mut self, node: FeatureNode) -> FeatureId {
+    pub fn add_node(&mut self, mut node: FeatureNode) -> FeatureId {
         let id = node.id;
+        node.revision = next_revision();
 
         // If feature has no dependencies, it's a root
         if !self.dependencies.contains_key(&id) {
             self.roots.push(id);
         }
 
-        self.features.insert(id, node);
+        self.features.insert(id, Arc::new(node));
         id
     }
 
     /// Get a feature node by ID.
     pub fn get_node(&self, id: FeatureId) -> Option<&FeatureNode> {
-        self.features.get(&id)
+        self.features.get(&id).map(Arc::as_ref)
     }
 
-    /// Get a mutable feature node by ID.
+    /// Get a mutable feature node by ID. Gives the node a new revision.
     pub fn get_node_mut(&mut self, id: FeatureId) -> Option<&mut FeatureNode> {
-        self.features.get_mut(&id)
+        let node = Arc::make_mut(self.features.get_mut(&id)?);
+        node.revision = next_revision();
+        Some(node)
+    }
+
+    /// Remove a feature node and its dependency edges. Features that depended
+    /// on it keep their data; they become roots if nothing else is left.
+    pub fn remove_node(&mut self, id: FeatureId) -> Option<FeatureNode> {
+        let node = Arc::unwrap_or_clone(self.features.remove(&id)?);
+        self.roots.retain(|&root| root != id);
+        for dependency in self.dependencies.remove(&id).unwrap_or_default() {
+            if let Some(dependents) = self.dependents.get_mut(&dependency) {
+                dependents.retain(|&dependent| dependent != id);
+            }
+        }
+        for dependent in self.dependents.remove(&id).unwrap_or_default() {
+            if let Some(dependencies) = self.dependencies.get_mut(&dependent) {
+                dependencies.retain(|&dependency| dependency != id);
+                if dependencies.is_empty() {
+                    self.dependencies.remove(&dependent);
+                    self.roots.push(dependent);
+                }
+            }
+        }
+        Some(node)
     }
 
     /// Add a dependency: `dependent` depends on `dependency`.
@@ -152,19 +237,37 @@ impl FeatureTree {
         // Add to dependencies
         self.dependencies
             .entry(dependent)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(dependency);
 
         // Add to reverse dependencies
         self.dependents
             .entry(dependency)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(dependent);
 
         // Remove from roots if it was a root
         self.roots.retain(|&id| id != dependent);
     }
 
+    /// Replace the dependencies of `feature`; without any it becomes a root.
+    pub fn set_dependencies(&mut self, feature: FeatureId, dependencies: &[FeatureId]) {
+        for dependency in self.dependencies.remove(&feature).unwrap_or_default() {
+            if let Some(dependents) = self.dependents.get_mut(&dependency) {
+                dependents.retain(|&dependent| dependent != feature);
+            }
+        }
+        if dependencies.is_empty() {
+            if !self.roots.contains(&feature) {
+                self.roots.push(feature);
+            }
+            return;
+        }
+        for &dependency in dependencies {
+            self.add_dependency(feature, dependency);
+        }
+    }
+
     /// Get all dependencies of a feature.
     pub fn dependencies(&self, feature: FeatureId) -> Vec<FeatureId> {
         self.dependencies.get(&feature).cloned().unwrap_or_default()
@@ -175,17 +278,35 @@ impl FeatureTree {
         self.dependents.get(&feature).cloned().unwrap_or_default()
     }
 
+    /// The given features and everything depending on them, directly or
+    /// through other features, each listed once (dependency cycles are cut).
+    pub fn dependents_closure(&self, features: &[FeatureId]) -> Vec<FeatureId> {
+        let mut seen: HashSet<FeatureId> = HashSet::new();
+        let mut closure = Vec::new();
+        let mut queue: VecDeque<FeatureId> = features.iter().copied().collect();
+
+        while let Some(id) = queue.pop_front() {
+            if !self.features.contains_key(&id) || !seen.insert(id) {
+                continue;
+            }
+            closure.push(id);
+            if let Some(dependents) = self.dependents.get(&id) {
+                queue.extend(dependents.iter().copied());
+            }
+        }
+        closure
+    }
+
     /// Mark a feature and all its dependents as dirty.
+    ///
+    /// Dependents are followed even when a feature is already dirty, since a
+    /// downstream feature may have been recomputed (and cleaned) on its own.
     pub fn mark_dirty(&mut self, feature: FeatureId) {
-        let mut to_mark = VecDeque::new();
-        to_mark.push_back(feature);
-
-        while let Some(id) = to_mark.pop_front() {
-            if let Some(node) = self.features.get_mut(&id) {
-                if !node.dirty {
+        for id in self.dependents_closure(&[feature]) {
+            // Leave dirty nodes alone so snapshots keep sharing them.
+            if self.get_node(id).is_some_and(|node| !node.dirty) {
+                if let Some(node) = self.get_node_mut(id) {
                     node.dirty = true;
-                    // Add all dependents to the queue
-                    to_mark.extend(self.dependents(id));
                 }
             }
         }
@@ -200,6 +321,12 @@ impl FeatureTree {
             .collect()
     }
 
+    /// Dirty features and everything depending on them, in recomputation order.
+    pub fn dirty_closure(&self) -> Vec<FeatureId> {
+        let closure = self.dependents_closure(&self.dirty_features());
+        self.recompute_order(&closure)
+    }
+
     /// Get recomputation order (topological sort) for dirty features.
     pub fn recompute_order(&self, dirty_features: &[FeatureId]) -> Vec<FeatureId> {
         if dirty_features.is_empty() {
@@ -253,7 +380,14 @@ impl FeatureTree {
 
     /// Get all feature nodes.
     pub fn all_nodes(&self) -> impl Iterator<Item = (&FeatureId, &FeatureNode)> {
-        self.features.iter()
+        self.features.iter().map(|(id, node)| (id, node.as_ref()))
+    }
+
+    /// Get all feature nodes for modification.
+    pub fn all_nodes_mut(&mut self) -> impl Iterator<Item = (&FeatureId, &mut FeatureNode)> {
+        self.features
+            .iter_mut()
+            .map(|(id, node)| (id, Arc::make_mut(node)))
     }
 }
 
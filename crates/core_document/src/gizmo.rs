@@ -0,0 +1,188 @@
+//! Viewport gizmos: interactive handles a workbench asks the host to draw.
+//!
+//! A workbench describes its gizmos through `Workbench::gizmos`; the host draws
+//! them, handles hovering and dragging, and reports each drag back through
+//! `Workbench::on_gizmo_drag` as a motion in world space. Workbenches therefore
+//! never deal with picking rays or screen-space math to move things around.
+
+/// Length of the gizmo handles in pixels unless a gizmo sets its own.
+pub const DEFAULT_GIZMO_SIZE: f32 = 90.0;
+
+/// What a gizmo manipulates, which decides the handles it shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoKind {
+    /// Arrows along the three axes and squares for the three planes between them.
+    Translate,
+    /// Rings around the three axes.
+    Rotate,
+    /// Boxes along the three axes and a center handle for uniform scaling.
+    Scale,
+    /// A single arrow along the third axis (e.g. an extrusion length).
+    Arrow,
+    /// A plane through the origin with the third axis as its normal: the square
+    /// moves it within the plane, the arrow along the normal.
+    Plane,
+}
+
+/// One grabbable part of a gizmo. Axis indices refer to `Gizmo::axes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoHandle {
+    /// Arrow (translate) or box (scale) along an axis.
+    Axis(usize),
+    /// Square in the plane perpendicular to an axis.
+    Plane(usize),
+    /// Ring around an axis.
+    Ring(usize),
+    /// Handle at the origin (uniform scale).
+    Center,
+}
+
+/// An interactive handle set drawn in the viewport.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gizmo {
+    /// Workbench-defined identifier passed back with drag events.
+    pub id: String,
+    pub kind: GizmoKind,
+    /// Where the gizmo sits (world space).
+    pub origin: [f32; 3],
+    /// Directions of the gizmo's axes (world space, unit length).
+    pub axes: [[f32; 3]; 3],
+    /// Handle length in pixels (the gizmo keeps its screen size while zooming).
+    pub size: f32,
+}
+
+impl Gizmo {
+    /// A gizmo of `kind` aligned to the world axes.
+    pub fn new(id: impl Into<String>, kind: GizmoKind, origin: [f32; 3]) -> Self {
+        Self {
+            id: id.into(),
+            kind,
+            origin,
+            axes: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            size: DEFAULT_GIZMO_SIZE,
+        }
+    }
+
+    pub fn translate(id: impl Into<String>, origin: [f32; 3]) -> Self {
+        Self::new(id, GizmoKind::Translate, origin)
+    }
+
+    pub fn rotate(id: impl Into<String>, origin: [f32; 3]) -> Self {
+        Self::new(id, GizmoKind::Rotate, origin)
+    }
+
+    pub fn scale(id: impl Into<String>, origin: [f32; 3]) -> Self {
+        Self::new(id, GizmoKind::Scale, origin)
+    }
+
+    /// A single arrow pointing along `direction`.
+    pub fn arrow(id: impl Into<String>, origin: [f32; 3], direction: [f32; 3]) -> Self {
+        Self::new(id, GizmoKind::Arrow, origin).with_normal(direction)
+    }
+
+    /// A plane handle for the plane through `origin` with the given normal.
+    pub fn plane(id: impl Into<String>, origin: [f32; 3], normal: [f32; 3]) -> Self {
+        Self::new(id, GizmoKind::Plane, origin).with_normal(normal)
+    }
+
+    /// Align the gizmo to the given axes (e.g. a body's local frame).
+    pub fn with_axes(mut self, axes: [[f32; 3]; 3]) -> Self {
+        self.axes = axes.map(normalized);
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Make `normal` the third axis, completing the frame with two
+    /// perpendicular directions.
+    fn with_normal(mut self, normal: [f32; 3]) -> Self {
+        let n = normalized(normal);
+        // Start from the world axis least aligned with the normal.
+        let helper = if n[0].abs() <= n[1].abs() && n[0].abs() <= n[2].abs() {
+            [1.0, 0.0, 0.0]
+        } else if n[1].abs() <= n[2].abs() {
+            [0.0, 1.0, 0.0]
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+        let u = normalized(cross(helper, n));
+        let v = cross(n, u);
+        self.axes = [u, v, n];
+        self
+    }
+
+    /// Handles this gizmo shows.
+    pub fn handles(&self) -> Vec<GizmoHandle> {
+        match self.kind {
+            GizmoKind::Translate => (0..3)
+                .map(GizmoHandle::Axis)
+                .chain((0..3).map(GizmoHandle::Plane))
+                .collect(),
+            GizmoKind::Rotate => (0..3).map(GizmoHandle::Ring).collect(),
+            GizmoKind::Scale => (0..3)
+                .map(GizmoHandle::Axis)
+                .chain([GizmoHandle::Center])
+                .collect(),
+            GizmoKind::Arrow => vec![GizmoHandle::Axis(2)],
+            GizmoKind::Plane => vec![GizmoHandle::Plane(2), GizmoHandle::Axis(2)],
+        }
+    }
+}
+
+/// Stage of a gizmo drag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoDragPhase {
+    /// The handle was grabbed; the motion is still the identity.
+    Start,
+    Update,
+    /// The handle was released; the motion is final.
+    End,
+    /// The drag was aborted (Escape or right click); undo any preview.
+    Cancel,
+}
+
+/// Motion of a drag since it started, in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GizmoMotion {
+    Translate([f32; 3]),
+    /// Rotation about `axis` through the gizmo origin, in radians
+    /// (counter-clockwise looking against the axis).
+    Rotate {
+        axis: [f32; 3],
+        angle: f32,
+    },
+    /// Scale factors along the gizmo's axes, about its origin.
+    Scale([f32; 3]),
+}
+
+/// A drag of a gizmo handle, reported to the workbench that owns the gizmo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GizmoDrag {
+    /// `Gizmo::id` of the dragged gizmo.
+    pub gizmo: String,
+    pub handle: GizmoHandle,
+    pub phase: GizmoDragPhase,
+    /// Total motion since the drag started, so workbenches can apply it to the
+    /// state they saved at `Start` without accumulating rounding errors.
+    pub motion: GizmoMotion,
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalized(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length > f32::EPSILON {
+        v.map(|c| c / length)
+    } else {
+        v
+    }
+}
@@ -0,0 +1,227 @@
+//! Background jobs scheduled by workbenches.
+//!
+//! Expensive work (solver iterations, preview tessellation, mesh analysis)
+//! runs on a worker thread so input stays responsive. A workbench spawns a job
+//! through `ctx.document.jobs()`, keeps the returned `JobId`, and collects the
+//! result with `JobQueue::take` from a later `Workbench::on_frame`, merging it
+//! into its state on the main thread. Long jobs (imports, loading large
+//! meshes) can report how far they got through `JobProgress`; the status bar
+//! shows it next to the job label.
+//!
+//! Jobs can be cancelled from the status bar. Cancelling discards the result
+//! and sets the job's `CancellationToken`, which kernel rebuilds (and any job
+//! that checks `JobProgress::is_cancelled`) honor to stop early.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use kernel_api::CancellationToken;
+use thiserror::Error;
+
+/// Handle of a spawned job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum JobError {
+    #[error("could not start job '{0}': {1}")]
+    Spawn(String, String),
+    #[error("job '{0}' panicked")]
+    Panicked(String),
+    #[error("job '{0}' returned a different result type than requested")]
+    UnexpectedResult(String),
+}
+
+type JobOutput = Result<Box<dyn Any + Send>, JobError>;
+
+/// A job still running, as listed in the status bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobStatus {
+    pub id: JobId,
+    pub label: String,
+    /// Fraction done (0 to 1), if the job reports it.
+    pub progress: Option<f32>,
+}
+
+struct RunningJob {
+    status: JobStatus,
+    cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct JobState {
+    /// Jobs still running.
+    running: HashMap<JobId, RunningJob>,
+    /// Results not collected yet, with the job label.
+    finished: HashMap<JobId, (String, JobOutput)>,
+}
+
+#[derive(Default)]
+struct Shared {
+    next_id: AtomicU64,
+    state: Mutex<JobState>,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, JobState> {
+        // Jobs never panic while holding the lock, so a poisoned lock still
+        // holds consistent data.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Jobs of one document. Clones share the same queue.
+#[derive(Clone, Default)]
+pub struct JobQueue {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for JobQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.state();
+        f.debug_struct("JobQueue")
+            .field("running", &state.running.len())
+            .field("finished", &state.finished.len())
+            .finish()
+    }
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `work` on a worker thread. `label` names the job in the status bar.
+    pub fn spawn<T, F>(&self, label: impl Into<String>, work: F) -> JobId
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.spawn_with_progress(label, move |_| work())
+    }
+
+    /// Like [`Self::spawn`], for work that reports its progress through the
+    /// `JobProgress` it is given.
+    pub fn spawn_with_progress<T, F>(&self, label: impl Into<String>, work: F) -> JobId
+    where
+        T: Send + 'static,
+        F: FnOnce(JobProgress) -> T + Send + 'static,
+    {
+        let label = label.into();
+        let id = JobId(self.shared.next_id.fetch_add(1, Ordering::Relaxed));
+        let cancel = CancellationToken::new();
+        self.shared.state().running.insert(
+            id,
+            RunningJob {
+                status: JobStatus {
+                    id,
+                    label: label.clone(),
+                    progress: None,
+                },
+                cancel: cancel.clone(),
+            },
+        );
+        let job_label = label.clone();
+
+        let shared = Arc::clone(&self.shared);
+        let progress = JobProgress {
+            shared: Arc::clone(&self.shared),
+            id,
+            cancel,
+        };
+        let run = move || {
+            let output = panic::catch_unwind(AssertUnwindSafe(|| work(progress)))
+                .map(|value| Box::new(value) as Box<dyn Any + Send>)
+                .map_err(|_| JobError::Panicked(job_label));
+            let mut state = shared.state();
+            // Cancelled jobs are no longer listed; drop their result.
+            if let Some(job) = state.running.remove(&id) {
+                state.finished.insert(id, (job.status.label, output));
+            }
+        };
+        if let Err(err) = thread::Builder::new()
+            .name(format!("job-{}", id.0))
+            .spawn(run)
+        {
+            let mut state = self.shared.state();
+            state.running.remove(&id);
+            let output = Err(JobError::Spawn(label.clone(), err.to_string()));
+            state.finished.insert(id, (label, output));
+        }
+        id
+    }
+
+    /// Result of a finished job, removing it from the queue. `None` while the
+    /// job is running, or if it was cancelled or already taken.
+    pub fn take<T: 'static>(&self, id: JobId) -> Option<Result<T, JobError>> {
+        let (label, output) = self.shared.state().finished.remove(&id)?;
+        Some(output.and_then(|value| {
+            value
+                .downcast::<T>()
+                .map(|value| *value)
+                .map_err(|_| JobError::UnexpectedResult(label))
+        }))
+    }
+
+    pub fn is_running(&self, id: JobId) -> bool {
+        self.shared.state().running.contains_key(&id)
+    }
+
+    /// Forget a job: its result is discarded instead of being kept for `take`,
+    /// and its cancellation token is set. Work that doesn't check the token
+    /// still runs to completion on its thread.
+    pub fn cancel(&self, id: JobId) {
+        let mut state = self.shared.state();
+        if let Some(job) = state.running.remove(&id) {
+            job.cancel.cancel();
+        }
+        state.finished.remove(&id);
+    }
+
+    /// Jobs still running, sorted by label.
+    pub fn running(&self) -> Vec<JobStatus> {
+        let mut jobs: Vec<JobStatus> = self
+            .shared
+            .state()
+            .running
+            .values()
+            .map(|job| job.status.clone())
+            .collect();
+        jobs.sort_by(|a, b| a.label.cmp(&b.label));
+        jobs
+    }
+}
+
+/// Lets a running job report how much of its work is done, and find out
+/// whether it was cancelled.
+pub struct JobProgress {
+    shared: Arc<Shared>,
+    id: JobId,
+    cancel: CancellationToken,
+}
+
+impl JobProgress {
+    /// Record that `fraction` (0 to 1) of the work is done. Each call takes
+    /// the queue lock, so report at a coarse granularity (e.g. per percent).
+    pub fn set(&self, fraction: f32) {
+        if let Some(job) = self.shared.state().running.get_mut(&self.id) {
+            job.status.progress = Some(fraction.clamp(0.0, 1.0));
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Token to pass to `Kernel::rebuild`, so cancelling the job stops the
+    /// rebuild between operations.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+}
@@ -0,0 +1,434 @@
+//! Change journal and three-way merge of document copies.
+//!
+//! A document is seen as a set of entities: its features, bodies, assets, and
+//! workbench storage, and the document-wide tables (parameters, materials,
+//! display states, overrides). Each save appends an entry to the journal,
+//! stored in the archive as `journal.jsonl`, listing the entities added,
+//! changed, or removed since the previous entry together with a fingerprint of
+//! their content. Entries are never rewritten.
+//!
+//! Two copies of a document share the entries written before they were
+//! copied. Replaying those gives the fingerprints of the common ancestor, so
+//! [`merge`] can tell for every entity which copy changed it: an entity
+//! changed in one copy only is taken from that copy, and one changed
+//! differently in both is a conflict.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::hash::Hasher;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::tessellation::StableHasher;
+use crate::{BodyId, Document, FeatureId};
+
+/// Archive entry holding the journal, one JSON entry per line.
+pub(crate) const JOURNAL_ENTRY: &str = "journal.jsonl";
+
+/// One save's worth of changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub timestamp_epoch_ms: i64,
+    pub author: String,
+    pub operations: Vec<Operation>,
+}
+
+/// Change to one entity; `key` names it, e.g. `feature/<uuid>` or `parameters`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// The entity was added or changed; `hash` fingerprints its new content.
+    Set {
+        key: String,
+        hash: u64,
+    },
+    Remove {
+        key: String,
+    },
+}
+
+/// Entries recorded for a document, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Read a journal from its archive entry. Unreadable lines are skipped: the
+    /// journal only matters for merging, and shouldn't keep a document from
+    /// opening.
+    pub(crate) fn parse(text: &str) -> Self {
+        Self {
+            entries: text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+        }
+    }
+
+    pub(crate) fn to_jsonl(&self) -> serde_json::Result<String> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+/// Fingerprints of the entities after `entries`.
+fn replay<'a>(entries: impl IntoIterator<Item = &'a JournalEntry>) -> BTreeMap<String, u64> {
+    let mut state = BTreeMap::new();
+    for entry in entries {
+        for operation in &entry.operations {
+            match operation {
+                Operation::Set { key, hash } => {
+                    state.insert(key.clone(), *hash);
+                }
+                Operation::Remove { key } => {
+                    state.remove(key);
+                }
+            }
+        }
+    }
+    state
+}
+
+/// Part of a document the journal tracks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entity {
+    Name,
+    Overrides,
+    AuthoringAxes,
+    Parameters,
+    Materials,
+    DisplayStates,
+    Feature(FeatureId),
+    Body(BodyId),
+    Asset(Uuid),
+    Workbench(String),
+}
+
+impl Entity {
+    fn parse(key: &str) -> Option<Self> {
+        Some(match key.split_once('/') {
+            None => match key {
+                "name" => Self::Name,
+                "overrides" => Self::Overrides,
+                "authoring_axes" => Self::AuthoringAxes,
+                "parameters" => Self::Parameters,
+                "materials" => Self::Materials,
+                "display_states" => Self::DisplayStates,
+                _ => return None,
+            },
+            Some(("feature", id)) => Self::Feature(FeatureId(Uuid::parse_str(id).ok()?)),
+            Some(("body", id)) => Self::Body(BodyId(Uuid::parse_str(id).ok()?)),
+            Some(("asset", id)) => Self::Asset(Uuid::parse_str(id).ok()?),
+            Some(("workbench", id)) => Self::Workbench(id.to_string()),
+            Some(_) => return None,
+        })
+    }
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "name"),
+            Self::Overrides => write!(f, "overrides"),
+            Self::AuthoringAxes => write!(f, "authoring_axes"),
+            Self::Parameters => write!(f, "parameters"),
+            Self::Materials => write!(f, "materials"),
+            Self::DisplayStates => write!(f, "display_states"),
+            Self::Feature(id) => write!(f, "feature/{}", id.0),
+            Self::Body(id) => write!(f, "body/{}", id.0),
+            Self::Asset(id) => write!(f, "asset/{id}"),
+            Self::Workbench(id) => write!(f, "workbench/{id}"),
+        }
+    }
+}
+
+/// Which copy an entity is taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// An entity both copies changed since they diverged.
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    /// Journal key of the entity.
+    pub key: String,
+    /// What the entity is, for people (e.g. `feature "Pad"`).
+    pub label: String,
+    /// Copy the merged document took it from.
+    pub resolution: Side,
+}
+
+/// Result of merging two copies of a document.
+#[derive(Debug, Clone)]
+pub struct Merge {
+    /// Our copy with the changes from theirs applied. Its journal holds the
+    /// entries of both; the merge itself is recorded when it is saved.
+    pub document: Document,
+    /// Entities taken from their copy (labels), conflicts included.
+    pub from_theirs: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum MergeError {
+    #[error("the files are not copies of the same document")]
+    Unrelated,
+    #[error("the copies have no saved changes in common (saved before journals were kept?)")]
+    NoCommonHistory,
+}
+
+/// Three-way merge of two copies of a document, using the journal entries they
+/// share as the common ancestor. Entities changed in both copies are taken from
+/// `prefer` and listed as conflicts.
+pub fn merge(ours: &Document, theirs: &Document, prefer: Side) -> Result<Merge, MergeError> {
+    if ours.id() != theirs.id() {
+        return Err(MergeError::Unrelated);
+    }
+    let theirs_entries: HashSet<Uuid> = theirs.journal.entries.iter().map(|e| e.id).collect();
+    let shared: Vec<&JournalEntry> = ours
+        .journal
+        .entries
+        .iter()
+        .filter(|entry| theirs_entries.contains(&entry.id))
+        .collect();
+    if shared.is_empty() {
+        return Err(MergeError::NoCommonHistory);
+    }
+    let base = replay(shared);
+    let ours_state = ours.fingerprints();
+    let theirs_state = theirs.fingerprints();
+
+    let mut document = ours.clone();
+    let mut from_theirs = Vec::new();
+    let mut conflicts = Vec::new();
+    let keys: BTreeSet<&String> = ours_state.keys().chain(theirs_state.keys()).collect();
+    for key in keys {
+        let Some(entity) = Entity::parse(key) else {
+            continue;
+        };
+        let (base, mine, other) = (base.get(key), ours_state.get(key), theirs_state.get(key));
+        if mine == other || other == base {
+            continue;
+        }
+        let label = theirs
+            .entity_label(&entity)
+            .or_else(|| ours.entity_label(&entity))
+            .unwrap_or_else(|| key.clone());
+        let side = if mine == base {
+            Side::Theirs
+        } else {
+            conflicts.push(MergeConflict {
+                key: key.clone(),
+                label: label.clone(),
+                resolution: prefer,
+            });
+            prefer
+        };
+        if side == Side::Theirs {
+            document.copy_entity(&entity, theirs);
+            from_theirs.push(label);
+        }
+    }
+
+    let known: HashSet<Uuid> = document.journal.entries.iter().map(|e| e.id).collect();
+    document.journal.entries.extend(
+        theirs
+            .journal
+            .entries
+            .iter()
+            .filter(|entry| !known.contains(&entry.id))
+            .cloned(),
+    );
+    if !from_theirs.is_empty() {
+        document.mark_dirty();
+    }
+    Ok(Merge {
+        document,
+        from_theirs,
+        conflicts,
+    })
+}
+
+/// Name of the user recording journal entries, from the environment.
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn fingerprint(value: &impl Serialize) -> u64 {
+    // Going through `Value` sorts object keys, so hash maps fingerprint the same
+    // whatever their iteration order.
+    let text = serde_json::to_value(value)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let mut hasher = StableHasher::default();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+impl Document {
+    /// Changes recorded at each save.
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Append a journal entry with the changes since the last one. Called when
+    /// the document is saved; returns whether anything had changed.
+    pub fn record_journal(&mut self, author: &str) -> bool {
+        let previous = replay(&self.journal.entries);
+        let current = self.fingerprints();
+        let mut operations: Vec<Operation> = current
+            .iter()
+            .filter(|(key, hash)| previous.get(*key) != Some(hash))
+            .map(|(key, hash)| Operation::Set {
+                key: key.clone(),
+                hash: *hash,
+            })
+            .collect();
+        operations.extend(
+            previous
+                .keys()
+                .filter(|key| !current.contains_key(*key))
+                .map(|key| Operation::Remove { key: key.clone() }),
+        );
+        if operations.is_empty() {
+            return false;
+        }
+        self.journal.entries.push(JournalEntry {
+            id: Uuid::new_v4(),
+            timestamp_epoch_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64,
+            author: author.to_string(),
+            operations,
+        });
+        true
+    }
+
+    /// Fingerprint of every entity, by journal key.
+    fn fingerprints(&self) -> BTreeMap<String, u64> {
+        let mut state = BTreeMap::new();
+        let mut insert = |entity: Entity, hash: u64| {
+            state.insert(entity.to_string(), hash);
+        };
+        insert(Entity::Name, fingerprint(&self.metadata.name));
+        insert(Entity::Overrides, fingerprint(&self.metadata.overrides));
+        if let Some(axes) = &self.metadata.authoring_axes {
+            insert(Entity::AuthoringAxes, fingerprint(axes));
+        }
+        insert(Entity::Parameters, fingerprint(&self.parameters));
+        insert(Entity::Materials, fingerprint(&self.materials));
+        insert(Entity::DisplayStates, fingerprint(&self.display_states));
+        for (&id, node) in self.feature_tree.all_nodes() {
+            // Whether the feature awaits a recompute isn't an edit.
+            let mut node = node.clone();
+            node.dirty = false;
+            let mut dependencies = self.feature_tree.dependencies(id);
+            dependencies.sort_by_key(|dependency| dependency.0);
+            insert(Entity::Feature(id), fingerprint(&(node, dependencies)));
+        }
+        for body in &self.bodies {
+            insert(Entity::Body(body.id), fingerprint(body));
+        }
+        for asset in self.assets.values() {
+            insert(Entity::Asset(asset.id), fingerprint(asset));
+        }
+        for (id, storage) in &self.workbench_storage {
+            insert(Entity::Workbench(id.clone()), fingerprint(storage));
+        }
+        state
+    }
+
+    /// Replace an entity by its content in `from`, or remove it if `from`
+    /// doesn't have it.
+    fn copy_entity(&mut self, entity: &Entity, from: &Document) {
+        match entity {
+            Entity::Name => self.metadata.name = from.metadata.name.clone(),
+            Entity::Overrides => self.metadata.overrides = from.metadata.overrides.clone(),
+            Entity::AuthoringAxes => self.metadata.authoring_axes = from.metadata.authoring_axes,
+            Entity::Parameters => self.parameters = from.parameters.clone(),
+            Entity::Materials => self.materials = from.materials.clone(),
+            Entity::DisplayStates => self.display_states = from.display_states.clone(),
+            Entity::Feature(id) => match from.feature_tree.get_node(*id) {
+                Some(node) => {
+                    match self.feature_tree.get_node_mut(*id) {
+                        Some(existing) => *existing = node.clone(),
+                        None => {
+                            self.feature_tree.add_node(node.clone());
+                        }
+                    }
+                    self.feature_tree
+                        .set_dependencies(*id, &from.feature_tree.dependencies(*id));
+                    self.feature_tree.mark_dirty(*id);
+                }
+                None => {
+                    self.feature_tree.remove_node(*id);
+                }
+            },
+            Entity::Body(id) => {
+                let body = from.bodies.iter().find(|body| body.id == *id).cloned();
+                match (self.bodies.iter_mut().find(|body| body.id == *id), body) {
+                    (Some(existing), Some(body)) => *existing = body,
+                    (None, Some(body)) => self.bodies.push(body),
+                    (_, None) => self.bodies.retain(|body| body.id != *id),
+                }
+            }
+            Entity::Asset(id) => match from.assets.get(id) {
+                Some(asset) => {
+                    self.assets.insert(*id, asset.clone());
+                    if let Some(data) = from.asset_data.get(id) {
+                        self.asset_data.insert(*id, data.clone());
+                    }
+                }
+                None => {
+                    self.assets.remove(id);
+                    self.asset_data.remove(id);
+                }
+            },
+            Entity::Workbench(id) => match from.workbench_storage.get(id) {
+                Some(storage) => {
+                    self.workbench_storage.insert(id.clone(), storage.clone());
+                }
+                None => {
+                    self.workbench_storage.remove(id);
+                }
+            },
+        }
+    }
+
+    /// What an entity is, for people; None if the document doesn't have it.
+    fn entity_label(&self, entity: &Entity) -> Option<String> {
+        Some(match entity {
+            Entity::Name => "document name".to_string(),
+            Entity::Overrides => "document settings".to_string(),
+            Entity::AuthoringAxes => "axis convention".to_string(),
+            Entity::Parameters => "parameter table".to_string(),
+            Entity::Materials => "material library".to_string(),
+            Entity::DisplayStates => "display states".to_string(),
+            Entity::Feature(id) => format!("feature \"{}\"", self.feature_tree.get_node(*id)?.name),
+            Entity::Body(id) => format!(
+                "body \"{}\"",
+                self.bodies.iter().find(|body| body.id == *id)?.name
+            ),
+            Entity::Asset(id) => format!("asset {}", self.assets.get(id)?.path),
+            Entity::Workbench(id) => format!("{id} workbench data"),
+        })
+    }
+}
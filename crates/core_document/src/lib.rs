@@ -1,25 +1,72 @@
 pub mod asset;
+pub mod bvh;
+pub mod datum;
+pub mod display;
 pub mod feature;
+pub mod gizmo;
+pub mod jobs;
+pub mod journal;
+pub mod material;
+pub mod naming;
+pub mod origin;
+pub mod overrides;
+pub mod parameters;
 pub mod registration;
 pub mod runtime;
+pub mod selection;
+pub mod snapshot;
+pub mod task;
+pub mod tessellation;
 
 use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Seek, Write};
 use std::path::Path;
+use std::sync::Arc;
 
+use axes::AxisSystem;
+use kernel_api::{Diagnostic, DiagnosticSeverity};
 use serde::{Deserialize, Serialize};
-use serde_json;
 use tar::{Archive, Builder, Header};
 use thiserror::Error;
 use uuid::Uuid;
 
-pub use asset::{AssetReference, AssetType};
-pub use feature::{BodyId, FeatureError, FeatureId, FeatureNode, FeatureTree, WorkbenchFeature};
+pub use asset::{AssetData, AssetReference, AssetType};
+pub use datum::{DatumGeometry, DATUM_GEOMETRY_KEY};
+pub use display::{BodyDisplay, DisplayState, DisplayStates};
+pub use feature::{
+    BodyId, FeatureError, FeatureId, FeatureNode, FeatureStatus, FeatureTree, WorkbenchFeature,
+};
+pub use gizmo::{Gizmo, GizmoDrag, GizmoDragPhase, GizmoHandle, GizmoKind, GizmoMotion};
+pub use jobs::{JobError, JobId, JobProgress, JobQueue, JobStatus};
+pub use journal::{Journal, JournalEntry, Merge, MergeConflict, MergeError, Side};
+pub use material::{MassProperties, Material, MaterialId, MaterialLibrary};
+pub use naming::{ElementKind, GeometryRef, NamedTopology, TopoName};
+pub use origin::{BodyOrigin, OriginElement, OriginRef, ReferenceAxis, ReferencePlane};
+pub use overrides::DocumentOverrides;
+pub use parameters::{BindingError, CellRef, ParameterError, ParameterSheet, ParameterUnit};
 pub use runtime::{
-    CameraOrientRequest, InputResult, KeyCode, LogEntry, LogLevel, MouseButton,
-    WorkbenchInputEvent, WorkbenchRuntimeContext,
+    BedOrigin, BedShape, BodyMesh, CameraFocusRequest, CameraOrientRequest, FileOpenRequest,
+    InputResult, KeyCode, LogEntry, LogLevel, MouseButton, ObjectSnapKind, PrintVolume,
+    SnapOptions, WorkbenchInputEvent, WorkbenchRuntimeContext,
 };
+pub use selection::{Selection, SelectionItem, SubElement};
+pub use snapshot::DocumentSnapshot;
+pub use task::{TaskAction, TaskPanel};
+pub use tessellation::{CachedMesh, TessellationCache};
+pub use units::{LengthUnit, UnitFormat};
+
+/// Archive entry holding the serialized document.
+const DOCUMENT_ENTRY: &str = "document.json";
+/// Archive entry holding the PNG preview (written before the document).
+const THUMBNAIL_ENTRY: &str = "thumbnail.png";
+/// Archive folder embedded assets are stored in, one `<id>` file each
+/// (written after the document).
+pub const ASSETS_DIR: &str = "assets/";
+/// Archive folder cached meshes are stored in, one `<id>.mesh` file each
+/// (written last, so they can be skipped when only the document is needed).
+const CACHE_DIR: &str = "cache/";
 
 /// Result type for document operations.
 pub type DocumentResult<T> = std::result::Result<T, DocumentError>;
@@ -43,6 +90,7 @@ impl WorkbenchStorage {
 ///
 /// The document is saved as a `.prtcad` file, which is a ZIP archive containing:
 /// - `document.json` - This document structure (serialized)
+/// - `journal.jsonl` - Changes recorded at each save, for merging copies
 /// - `assets/` - External files (STEP, STL, etc.) referenced by the document
 /// - `cache/` - Optional cached computed data (meshes, tessellations)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,7 +102,33 @@ pub struct Document {
     workbench_storage: HashMap<String, WorkbenchStorage>,
     /// References to external files stored in the .prtcad archive.
     assets: HashMap<Uuid, AssetReference>,
+    /// Contents of the assets embedded in the archive, stored next to
+    /// `document.json` under their `path`.
+    #[serde(skip)]
+    asset_data: HashMap<Uuid, AssetData>,
+    /// Parameter table that feature values can be bound to.
+    #[serde(default)]
+    parameters: ParameterSheet,
+    /// Custom materials and the currency used for costs.
+    #[serde(default)]
+    materials: MaterialLibrary,
+    /// Named sets of body visibility, opacity, and color overrides.
+    #[serde(default)]
+    display_states: DisplayStates,
+    /// Meshes computed from the document, stored in the archive under `cache/`.
+    #[serde(skip)]
+    tessellations: TessellationCache,
     history: Vec<DocumentRevision>,
+    /// Changes recorded at each save, stored in the archive as `journal.jsonl`.
+    #[serde(skip)]
+    journal: Journal,
+    /// Background work scheduled by workbenches (not saved).
+    #[serde(skip)]
+    jobs: JobQueue,
+    /// Workbench the user works in, recorded as the editor of features added
+    /// (not saved).
+    #[serde(skip)]
+    active_workbench: Option<WorkbenchId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +136,58 @@ pub struct Body {
     pub id: BodyId,
     pub name: String,
     pub created_at: i64,
+    /// Origin planes, axes, and point of this body.
+    #[serde(default)]
+    pub origin: BodyOrigin,
+    /// Material the body is made of, for mass and cost estimates.
+    #[serde(default)]
+    pub material: Option<MaterialId>,
+    /// Source file the body was inserted from as a link (None for modeled or
+    /// copied bodies).
+    #[serde(default)]
+    pub link: Option<BodyLink>,
+    /// World position of the body's frame. Features, sketches and meshes of
+    /// the body are stored relative to it in single precision, so a body far
+    /// from the world origin keeps its detail; see [`Body::to_local`].
+    #[serde(default)]
+    pub position: [f64; 3],
+}
+
+impl Body {
+    /// A world position in this body's frame.
+    pub fn to_local(&self, world: [f64; 3]) -> [f32; 3] {
+        to_frame(world, self.position)
+    }
+
+    /// A position in this body's frame in world space.
+    pub fn to_world(&self, local: [f32; 3]) -> [f64; 3] {
+        from_frame(local, self.position)
+    }
+}
+
+/// A world position relative to the frame at `origin`; the difference is
+/// taken in double precision before narrowing.
+pub fn to_frame(world: [f64; 3], origin: [f64; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| (world[i] - origin[i]) as f32)
+}
+
+/// A position relative to the frame at `origin` in world space.
+pub fn from_frame(local: [f32; 3], origin: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|i| local[i] as f64 + origin[i])
+}
+
+/// Where a linked body comes from. The body keeps a copy of the source's
+/// features so the document opens without the file; the copy is replaced when
+/// the file changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BodyLink {
+    /// Absolute path of the source file.
+    pub path: String,
+    /// Body of the source document (None for mesh files, which hold one body).
+    #[serde(default)]
+    pub source_body: Option<BodyId>,
+    /// Modification time of the file when it was last copied (epoch milliseconds).
+    pub modified: i64,
 }
 
 impl Document {
@@ -72,7 +198,15 @@ impl Document {
             bodies: Vec::new(),
             workbench_storage: HashMap::new(),
             assets: HashMap::new(),
+            asset_data: HashMap::new(),
+            parameters: ParameterSheet::default(),
+            materials: MaterialLibrary::default(),
+            display_states: DisplayStates::default(),
+            tessellations: TessellationCache::default(),
             history: Vec::new(),
+            journal: Journal::default(),
+            jobs: JobQueue::new(),
+            active_workbench: None,
         }
     }
 
@@ -92,6 +226,24 @@ impl Document {
         &self.metadata
     }
 
+    /// Set the workbench the user works in; features added from now on
+    /// remember it as their `editor`.
+    pub fn set_active_workbench(&mut self, workbench: Option<WorkbenchId>) {
+        self.active_workbench = workbench;
+    }
+
+    /// Workbench that opens `id` for editing: the one it was created in, or
+    /// for features that don't record it, the workbench named by its type.
+    pub fn feature_editor(&self, id: FeatureId) -> Option<&WorkbenchId> {
+        let node = self.get_feature_meta(id)?;
+        Some(node.editor.as_ref().unwrap_or(&node.workbench_id))
+    }
+
+    /// Queue for background work on this document (see `jobs`).
+    pub fn jobs(&self) -> &JobQueue {
+        &self.jobs
+    }
+
     pub fn mark_dirty(&mut self) {
         self.metadata.dirty = true;
     }
@@ -100,6 +252,38 @@ impl Document {
         self.metadata.dirty = false;
     }
 
+    /// User settings this document overrides.
+    pub fn overrides(&self) -> &DocumentOverrides {
+        &self.metadata.overrides
+    }
+
+    /// Replace the document's setting overrides.
+    pub fn set_overrides(&mut self, overrides: DocumentOverrides) {
+        if self.metadata.overrides != overrides {
+            self.metadata.overrides = overrides;
+            self.mark_dirty();
+        }
+    }
+
+    /// Axis convention the document was last saved with (None for files predating this field).
+    pub fn authoring_axes(&self) -> Option<AxisSystem> {
+        self.metadata.authoring_axes
+    }
+
+    /// Record the axis convention in effect when saving. Does not mark the document dirty.
+    pub fn set_authoring_axes(&mut self, axes: AxisSystem) {
+        self.metadata.authoring_axes = Some(axes);
+    }
+
+    /// Tessellation quality for this document (the override, or the kernel default).
+    pub fn tessellation_settings(&self) -> kernel_api::TessellationSettings {
+        self.metadata
+            .overrides
+            .tessellation
+            .clone()
+            .unwrap_or_default()
+    }
+
     pub fn push_revision(&mut self, revision: DocumentRevision) {
         self.history.push(revision);
         self.metadata.revision += 1;
@@ -128,6 +312,7 @@ impl Document {
         let node = FeatureNode {
             id,
             workbench_id: F::workbench_id(),
+            editor: self.active_workbench.clone(),
             name,
             body,
             visible: true,
@@ -138,6 +323,9 @@ impl Document {
                 .unwrap()
                 .as_millis() as i64,
             data: feature.to_json(),
+            expressions: Default::default(),
+            status: None,
+            revision: feature::next_revision(),
         };
 
         self.feature_tree.add_node(node);
@@ -176,7 +364,9 @@ impl Document {
         }
     }
 
-    /// Mark feature dirty (triggers recomputation).
+    /// Mark a feature dirty (triggers recomputation), together with every
+    /// feature that depends on it, so e.g. editing a sketch also flags the
+    /// features built from it.
     pub fn mark_feature_dirty(&mut self, feature_id: FeatureId) {
         self.feature_tree.mark_dirty(feature_id);
         self.mark_dirty();
@@ -187,10 +377,236 @@ impl Document {
         self.feature_tree.dirty_features()
     }
 
-    /// Get recomputation order for dirty features.
+    /// Record the outcome of recomputing `recomputed`: their previous status is
+    /// cleared, then warnings and errors tied to a feature are attached to it
+    /// (errors take precedence; messages of the same severity are joined).
+    /// Returns the diagnostics not tied to any feature of this document.
+    pub fn apply_diagnostics<'d>(
+        &mut self,
+        recomputed: &[FeatureId],
+        diagnostics: &'d [Diagnostic],
+    ) -> Vec<&'d Diagnostic> {
+        for id in recomputed {
+            if let Some(node) = self.feature_tree.get_node_mut(*id) {
+                node.status = None;
+            }
+        }
+        let mut unattached = Vec::new();
+        for diagnostic in diagnostics {
+            let node = diagnostic
+                .feature
+                .as_deref()
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .and_then(|id| self.feature_tree.get_node_mut(FeatureId(id)));
+            let Some(node) = node else {
+                unattached.push(diagnostic);
+                continue;
+            };
+            let message = diagnostic.message.clone();
+            node.status = match (node.status.take(), diagnostic.severity) {
+                (status, DiagnosticSeverity::Info) => status,
+                (Some(FeatureStatus::Error(previous)), DiagnosticSeverity::Error) => {
+                    Some(FeatureStatus::Error(format!("{previous}\n{message}")))
+                }
+                (Some(FeatureStatus::Warning(previous)), DiagnosticSeverity::Warning) => {
+                    Some(FeatureStatus::Warning(format!("{previous}\n{message}")))
+                }
+                (Some(error @ FeatureStatus::Error(_)), DiagnosticSeverity::Warning) => Some(error),
+                (_, DiagnosticSeverity::Warning) => Some(FeatureStatus::Warning(message)),
+                (_, DiagnosticSeverity::Error) => Some(FeatureStatus::Error(message)),
+            };
+        }
+        unattached
+    }
+
+    /// Features that need recomputing: the dirty ones and everything
+    /// depending on them, dependencies first.
+    pub fn dirty_closure(&self) -> Vec<FeatureId> {
+        self.feature_tree.dirty_closure()
+    }
+
+    /// Get recomputation order for dirty features (and their dependents).
     pub fn recompute_order(&self) -> Vec<FeatureId> {
-        let dirty = self.dirty_features();
-        self.feature_tree.recompute_order(&dirty)
+        self.dirty_closure()
+    }
+
+    /// Hash of everything a feature's result is computed from: its workbench,
+    /// data, and suppression, and the same for the features it depends on.
+    /// It is the same between runs, so it can be saved with cached meshes.
+    pub fn content_hash(&self, feature: FeatureId) -> u64 {
+        self.content_hash_memo(feature, &mut HashMap::new())
+    }
+
+    fn content_hash_memo(&self, feature: FeatureId, memo: &mut HashMap<FeatureId, u64>) -> u64 {
+        if let Some(hash) = memo.get(&feature) {
+            return *hash;
+        }
+        // Cut dependency cycles.
+        memo.insert(feature, 0);
+        let mut hasher = tessellation::StableHasher::default();
+        if let Some(node) = self.feature_tree.get_node(feature) {
+            node.workbench_id.as_str().hash(&mut hasher);
+            node.data.to_string().hash(&mut hasher);
+            node.suppressed.hash(&mut hasher);
+            let mut dependencies = self.feature_tree.dependencies(feature);
+            dependencies.sort_by_key(|id| id.0);
+            for dependency in dependencies {
+                hasher.write_u64(self.content_hash_memo(dependency, memo));
+            }
+        }
+        let hash = hasher.finish();
+        memo.insert(feature, hash);
+        hash
+    }
+
+    /// Meshes computed from the document, saved with it.
+    pub fn tessellations(&self) -> &TessellationCache {
+        &self.tessellations
+    }
+
+    /// Cached mesh of a feature or body, if it was computed from content
+    /// hashing to `hash`.
+    pub fn cached_mesh(&self, id: Uuid, hash: u64) -> Option<&Arc<kernel_api::TriMesh>> {
+        self.tessellations.get(id, hash)
+    }
+
+    /// Cache the mesh of a feature or body, computed from content hashing to
+    /// `hash`, to save it with the document. Not an edit, so the document is
+    /// not marked dirty.
+    pub fn set_cached_mesh(&mut self, id: Uuid, hash: u64, mesh: Arc<kernel_api::TriMesh>) {
+        self.tessellations.insert(id, hash, mesh);
+    }
+
+    /// Drop the cached meshes (the ones still needed are cached again before
+    /// the next save).
+    pub fn clear_cached_meshes(&mut self) {
+        self.tessellations = TessellationCache::default();
+    }
+
+    /// The document's parameter table.
+    pub fn parameters(&self) -> &ParameterSheet {
+        &self.parameters
+    }
+
+    /// Replace the parameter table and update the feature values bound to it.
+    pub fn set_parameters(&mut self, parameters: ParameterSheet) -> Vec<BindingError> {
+        if self.parameters == parameters {
+            return Vec::new();
+        }
+        self.parameters = parameters;
+        self.mark_dirty();
+        self.apply_parameters()
+    }
+
+    /// Drive the numeric field at `path` (a JSON pointer into the feature data) by an
+    /// expression over the parameter table, or release it with `None`.
+    pub fn set_feature_expression(
+        &mut self,
+        id: FeatureId,
+        path: &str,
+        expression: Option<String>,
+    ) -> DocumentResult<Vec<BindingError>> {
+        let node = self
+            .feature_tree
+            .get_node_mut(id)
+            .ok_or(DocumentError::FeatureNotFound(id))?;
+        match expression {
+            Some(expression) => node.expressions.insert(path.to_string(), expression),
+            None => node.expressions.remove(path),
+        };
+        self.mark_dirty();
+        Ok(self.apply_parameters())
+    }
+
+    /// Feature bindings (feature and field path) whose expressions use `cell`, by
+    /// reference or alias.
+    pub fn parameter_bindings(&self, cell: CellRef) -> Vec<(FeatureId, String)> {
+        let alias = self
+            .parameters
+            .cell(cell)
+            .and_then(|content| content.alias.as_deref());
+        let mut bindings = Vec::new();
+        for (&id, node) in self.feature_tree.all_nodes() {
+            for (path, expression) in &node.expressions {
+                if parameters::references(expression)
+                    .iter()
+                    .any(|name| parameters::names_cell(name, cell, alias))
+                {
+                    bindings.push((id, path.clone()));
+                }
+            }
+        }
+        bindings
+    }
+
+    /// Rename a parameter, rewriting the formulas and feature expressions that use
+    /// its old alias.
+    pub fn rename_parameter(
+        &mut self,
+        cell: CellRef,
+        alias: &str,
+    ) -> Result<Vec<BindingError>, ParameterError> {
+        let old = self
+            .parameters
+            .cell(cell)
+            .and_then(|content| content.alias.clone());
+        let mut sheet = self.parameters.clone();
+        sheet.rename(cell, alias)?;
+        if let Some(old) = old {
+            let new = alias.trim();
+            let bindings = self.parameter_bindings(cell);
+            for (id, path) in bindings {
+                if let Some(expression) = self
+                    .feature_tree
+                    .get_node_mut(id)
+                    .and_then(|node| node.expressions.get_mut(&path))
+                {
+                    *expression = parameters::rename_reference(expression, &old, new);
+                }
+            }
+        }
+        Ok(self.set_parameters(sheet))
+    }
+
+    /// Write the current value of every feature expression into its field, marking
+    /// features whose values changed dirty. Returns the bindings that failed.
+    pub fn apply_parameters(&mut self) -> Vec<BindingError> {
+        let evaluation = self.parameters.evaluate();
+        let mut errors = Vec::new();
+        let mut changed = Vec::new();
+        // Only bound features are written, so snapshots keep sharing the rest.
+        let bound: Vec<FeatureId> = self
+            .feature_tree
+            .all_nodes()
+            .filter(|(_, node)| !node.expressions.is_empty())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in bound {
+            let Some(node) = self.feature_tree.get_node_mut(id) else {
+                continue;
+            };
+            let mut node_changed = false;
+            for (path, expression) in &node.expressions {
+                let result = evaluation
+                    .expression(expression)
+                    .and_then(|value| parameters::write_field(&mut node.data, path, value));
+                match result {
+                    Ok(written) => node_changed |= written,
+                    Err(error) => errors.push(BindingError {
+                        feature: id,
+                        path: path.clone(),
+                        error,
+                    }),
+                }
+            }
+            if node_changed {
+                changed.push(id);
+            }
+        }
+        for id in changed {
+            self.mark_feature_dirty(id);
+        }
+        errors
     }
 
     /// Get workbench storage.
@@ -235,6 +651,127 @@ impl Document {
         !self.bodies.is_empty()
     }
 
+    /// Show or hide an origin element of a body in the viewport.
+    pub fn set_origin_visible(&mut self, origin: OriginRef, visible: bool) {
+        let Some(body) = self.bodies.iter_mut().find(|b| b.id == origin.body) else {
+            return;
+        };
+        if body.origin.is_visible(origin.element) != visible {
+            body.origin.set_visible(origin.element, visible);
+            self.mark_dirty();
+        }
+    }
+
+    /// World-space plane of an origin plane reference (None if the body does not exist or the
+    /// element is not a plane).
+    pub fn origin_plane(&self, origin: OriginRef) -> Option<ReferencePlane> {
+        self.bodies
+            .iter()
+            .any(|b| b.id == origin.body)
+            .then(|| origin.element.plane())
+            .flatten()
+    }
+
+    /// World-space line of an origin axis reference (None if the body does not exist or the
+    /// element is not an axis).
+    pub fn origin_axis(&self, origin: OriginRef) -> Option<ReferenceAxis> {
+        let direction = origin.element.axis_direction()?;
+        self.bodies
+            .iter()
+            .any(|b| b.id == origin.body)
+            .then_some(ReferenceAxis {
+                origin: [0.0, 0.0, 0.0],
+                direction,
+            })
+    }
+
+    /// Placement of a datum plane, axis, or point feature (None if the
+    /// feature does not exist or places no datum geometry).
+    pub fn datum_geometry(&self, id: FeatureId) -> Option<DatumGeometry> {
+        DatumGeometry::from_feature_data(&self.get_feature_meta(id)?.data)
+    }
+
+    /// The document's material library.
+    pub fn materials(&self) -> &MaterialLibrary {
+        &self.materials
+    }
+
+    /// Replace the material library. Bodies using a material that no longer
+    /// exists fall back to having none.
+    pub fn set_materials(&mut self, materials: MaterialLibrary) {
+        if self.materials == materials {
+            return;
+        }
+        self.materials = materials;
+        for body in &mut self.bodies {
+            if body
+                .material
+                .as_ref()
+                .is_some_and(|id| self.materials.get(id).is_none())
+            {
+                body.material = None;
+            }
+        }
+        self.mark_dirty();
+    }
+
+    /// Assign a material to a body (None clears it).
+    pub fn set_body_material(&mut self, body: BodyId, material: Option<MaterialId>) {
+        let Some(body) = self.bodies.iter_mut().find(|b| b.id == body) else {
+            return;
+        };
+        if body.material != material {
+            body.material = material;
+            self.mark_dirty();
+        }
+    }
+
+    /// World position of a body's frame (the origin for unknown bodies).
+    pub fn body_position(&self, body: BodyId) -> [f64; 3] {
+        self.bodies
+            .iter()
+            .find(|b| b.id == body)
+            .map_or([0.0; 3], |b| b.position)
+    }
+
+    /// World position of the frame a feature's geometry is relative to: its
+    /// body's position (the origin for features outside any body).
+    pub fn feature_position(&self, feature: FeatureId) -> [f64; 3] {
+        self.get_feature_meta(feature)
+            .and_then(|node| node.body)
+            .map_or([0.0; 3], |body| self.body_position(body))
+    }
+
+    /// Move a body's frame; its geometry moves with it.
+    pub fn set_body_position(&mut self, body: BodyId, position: [f64; 3]) {
+        let Some(body) = self.bodies.iter_mut().find(|b| b.id == body) else {
+            return;
+        };
+        if body.position != position && position.iter().all(|v| v.is_finite()) {
+            body.position = position;
+            self.mark_dirty();
+        }
+    }
+
+    /// The document's display states.
+    pub fn display_states(&self) -> &DisplayStates {
+        &self.display_states
+    }
+
+    /// Replace the display states (including which one is active).
+    pub fn set_display_states(&mut self, states: DisplayStates) {
+        if self.display_states != states {
+            self.display_states = states;
+            self.mark_dirty();
+        }
+    }
+
+    /// Material assigned to a body, if any.
+    pub fn body_material(&self, body: BodyId) -> Option<Material> {
+        let body = self.bodies.iter().find(|b| b.id == body)?;
+        self.materials.get(body.material.as_ref()?)
+    }
+
     /// Create a new body entry in the document.
     pub fn create_body(&mut self, name: Option<String>) -> BodyId {
         let id = BodyId::new();
@@ -251,12 +788,107 @@ impl Document {
             id,
             name: body_name,
             created_at,
+            origin: BodyOrigin::default(),
+            material: None,
+            link: None,
+            position: [0.0; 3],
         };
         self.bodies.push(body);
         self.mark_dirty();
         id
     }
 
+    /// Link a body to a source file, or make it a plain body with `None`.
+    pub fn set_body_link(&mut self, body: BodyId, link: Option<BodyLink>) {
+        let Some(body) = self.bodies.iter_mut().find(|b| b.id == body) else {
+            return;
+        };
+        if body.link != link {
+            body.link = link;
+            self.mark_dirty();
+        }
+    }
+
+    /// Remove a body and its features.
+    pub fn remove_body(&mut self, body: BodyId) {
+        self.clear_body_features(body);
+        let count = self.bodies.len();
+        self.bodies.retain(|b| b.id != body);
+        for state in &mut self.display_states.states {
+            state.bodies.remove(&body);
+        }
+        if self.bodies.len() != count {
+            self.mark_dirty();
+        }
+    }
+
+    /// Remove every feature of a body, keeping the body itself.
+    pub fn clear_body_features(&mut self, body: BodyId) {
+        let features: Vec<FeatureId> = self
+            .feature_tree
+            .all_nodes()
+            .filter(|(_, node)| node.body == Some(body))
+            .map(|(id, _)| *id)
+            .collect();
+        if features.is_empty() {
+            return;
+        }
+        for id in features {
+            self.feature_tree.remove_node(id);
+        }
+        self.mark_dirty();
+    }
+
+    /// Copy the features of `source_body` in `source` into `body` of this
+    /// document. The copies get fresh ids; references between them (and to
+    /// the source body) are rewritten to match. Returns the number of features
+    /// copied.
+    pub fn copy_body_features(
+        &mut self,
+        source: &Document,
+        source_body: BodyId,
+        body: BodyId,
+    ) -> usize {
+        let mut nodes: Vec<&FeatureNode> = source
+            .feature_tree
+            .all_nodes()
+            .filter(|(_, node)| node.body == Some(source_body))
+            .map(|(_, node)| node)
+            .collect();
+        nodes.sort_by_key(|node| node.created_at);
+
+        let ids: HashMap<FeatureId, FeatureId> = nodes
+            .iter()
+            .map(|node| (node.id, FeatureId::new()))
+            .collect();
+        let mut strings: HashMap<String, String> = ids
+            .iter()
+            .map(|(old, new)| (old.0.to_string(), new.0.to_string()))
+            .collect();
+        strings.insert(source_body.0.to_string(), body.0.to_string());
+
+        for node in &nodes {
+            let mut copy = (*node).clone();
+            copy.id = ids[&node.id];
+            copy.body = Some(body);
+            copy.dirty = true;
+            copy.status = None;
+            remap_ids(&mut copy.data, &strings);
+            self.feature_tree.add_node(copy);
+        }
+        for node in &nodes {
+            for dependency in source.feature_tree.dependencies(node.id) {
+                if let Some(dependency) = ids.get(&dependency) {
+                    self.feature_tree.add_dependency(ids[&node.id], *dependency);
+                }
+            }
+        }
+        if !nodes.is_empty() {
+            self.mark_dirty();
+        }
+        nodes.len()
+    }
+
     /// Add an asset reference to the document.
     pub fn add_asset(&mut self, asset: AssetReference) -> Uuid {
         let id = asset.id;
@@ -265,6 +897,56 @@ impl Document {
         id
     }
 
+    /// Add an asset and the file contents to embed in the document archive.
+    /// The contents are saved as `assets/<id>`, which becomes the asset's path.
+    pub fn add_asset_with_data(&mut self, mut asset: AssetReference, data: Vec<u8>) -> Uuid {
+        asset.path = format!("{ASSETS_DIR}{}", asset.id);
+        self.asset_data.insert(asset.id, data.into());
+        self.add_asset(asset)
+    }
+
+    /// Embed the file at `path` as an asset, typed by its extension. The
+    /// metadata records the original file name.
+    pub fn add_asset_from_file(&mut self, path: &Path) -> DocumentResult<Uuid> {
+        let data = std::fs::read(path)?;
+        let asset_type = AssetType::from_extension(
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default(),
+        );
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let asset = AssetReference::new(
+            "",
+            asset_type,
+            serde_json::json!({ "file_name": file_name }),
+        );
+        Ok(self.add_asset_with_data(asset, data))
+    }
+
+    /// Contents of an embedded asset.
+    pub fn asset_data(&self, asset_id: Uuid) -> Option<&AssetData> {
+        self.asset_data.get(&asset_id)
+    }
+
+    /// Bytes of an embedded asset, None for unknown assets and references
+    /// whose contents aren't in the archive.
+    pub fn read_asset_bytes(&self, asset_id: Uuid) -> Option<&[u8]> {
+        self.asset_data(asset_id).map(AssetData::bytes)
+    }
+
+    /// Remove an asset and its embedded contents.
+    pub fn remove_asset(&mut self, asset_id: Uuid) -> Option<AssetReference> {
+        self.asset_data.remove(&asset_id);
+        let removed = self.assets.remove(&asset_id);
+        if removed.is_some() {
+            self.mark_dirty();
+        }
+        removed
+    }
+
     /// Get an asset reference by ID.
     pub fn get_asset(&self, asset_id: Uuid) -> Option<&AssetReference> {
         self.assets.get(&asset_id)
@@ -282,18 +964,29 @@ impl Document {
 
     /// Save document to a .prtcad file (tar archive, optionally compressed).
     pub fn save_to_file(&self, path: &Path, compression: Compression) -> DocumentResult<()> {
+        self.save_to_file_with_thumbnail(path, compression, None)
+    }
+
+    /// Save document to a .prtcad file, with a PNG preview stored in the
+    /// archive as `thumbnail.png`.
+    pub fn save_to_file_with_thumbnail(
+        &self,
+        path: &Path,
+        compression: Compression,
+        thumbnail: Option<&[u8]>,
+    ) -> DocumentResult<()> {
         let file = File::create(path)?;
 
         match compression {
             Compression::None => {
                 let mut builder = Builder::new(file);
-                Self::write_archive(&mut builder, self)?;
+                Self::write_archive(&mut builder, self, thumbnail)?;
                 builder.finish()?;
             }
             Compression::Gzip => {
                 let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
                 let mut builder = Builder::new(encoder);
-                Self::write_archive(&mut builder, self)?;
+                Self::write_archive(&mut builder, self, thumbnail)?;
                 let encoder = builder.into_inner().map_err(|e| {
                     DocumentError::Compression(format!("gzip encoder finalize failed: {e}"))
                 })?;
@@ -304,7 +997,7 @@ impl Document {
                     .map_err(|e| DocumentError::Compression(e.to_string()))?;
                 {
                     let mut builder = Builder::new(&mut encoder);
-                    Self::write_archive(&mut builder, self)?;
+                    Self::write_archive(&mut builder, self, thumbnail)?;
                     builder.finish()?;
                 }
                 encoder
@@ -318,6 +1011,83 @@ impl Document {
 
     /// Load document from a .prtcad file (auto-detects compression).
     pub fn load_from_file(path: &Path) -> DocumentResult<Self> {
+        let mut archive = Self::open_archive(path)?;
+        let mut doc: Option<Document> = None;
+        let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut tessellations = TessellationCache::default();
+        let mut journal = Journal::default();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            if path == DOCUMENT_ENTRY {
+                let mut buf = String::new();
+                entry.read_to_string(&mut buf)?;
+                doc = Some(serde_json::from_str(&buf)?);
+            } else if path == journal::JOURNAL_ENTRY {
+                let mut buf = String::new();
+                entry.read_to_string(&mut buf)?;
+                journal = Journal::parse(&buf);
+            } else if path.starts_with(ASSETS_DIR) {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                files.insert(path, bytes);
+            } else if let Some(name) = path.strip_prefix(CACHE_DIR) {
+                // The cache is optional: unreadable meshes are computed again.
+                let Some(id) = name
+                    .strip_suffix(".mesh")
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                else {
+                    continue;
+                };
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                if let Ok(cached) = bincode::deserialize::<CachedMesh>(&bytes) {
+                    tessellations.insert(id, cached.hash, cached.mesh);
+                }
+            }
+        }
+
+        let mut doc = doc.ok_or_else(|| {
+            DocumentError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "document.json not found in archive",
+            ))
+        })?;
+        // Assets are stored as `assets/<id>`; older files used the asset's
+        // path, which may have had an extension.
+        for asset in doc.assets.values() {
+            let bytes = files
+                .remove(&format!("{ASSETS_DIR}{}", asset.id))
+                .or_else(|| files.remove(&asset.path));
+            if let Some(bytes) = bytes {
+                doc.asset_data.insert(asset.id, bytes.into());
+            }
+        }
+        doc.tessellations = tessellations;
+        doc.journal = journal;
+        Ok(doc)
+    }
+
+    /// PNG preview saved in a .prtcad file, if it has one. The thumbnail is
+    /// stored first, so the document itself is not read.
+    pub fn read_thumbnail(path: &Path) -> DocumentResult<Option<Vec<u8>>> {
+        let mut archive = Self::open_archive(path)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?;
+            if entry_path == Path::new(THUMBNAIL_ENTRY) {
+                let mut png = Vec::new();
+                entry.read_to_end(&mut png)?;
+                return Ok(Some(png));
+            }
+            if entry_path == Path::new(DOCUMENT_ENTRY) {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn open_archive(path: &Path) -> DocumentResult<Archive<Box<dyn Read>>> {
         let mut file = File::open(path)?;
 
         // Detect compression via extension and magic bytes.
@@ -337,13 +1107,16 @@ impl Document {
             || magic.starts_with(&[0x1f, 0x8b])
         {
             Compression::Gzip
-        } else if file_name.ends_with(".zst") || file_name.ends_with(".prtcad.zst") {
+        } else if file_name.ends_with(".zst")
+            || file_name.ends_with(".prtcad.zst")
+            || magic == [0x28, 0xb5, 0x2f, 0xfd]
+        {
             Compression::Zstd
         } else {
             Compression::None
         };
 
-        let mut archive: Archive<Box<dyn Read>> = match compression {
+        let archive: Archive<Box<dyn Read>> = match compression {
             Compression::None => Archive::new(Box::new(file)),
             Compression::Gzip => {
                 let decoder = flate2::read::GzDecoder::new(file);
@@ -355,42 +1128,97 @@ impl Document {
                 Archive::new(Box::new(decoder))
             }
         };
-
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let path = entry.path()?;
-            if path == Path::new("document.json") {
-                let mut buf = String::new();
-                entry.read_to_string(&mut buf)?;
-                let doc: Document = serde_json::from_str(&buf)?;
-                return Ok(doc);
-            }
-        }
-
-        Err(DocumentError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "document.json not found in archive",
-        )))
+        Ok(archive)
     }
 
-    fn write_archive<W: Write>(builder: &mut Builder<W>, doc: &Document) -> DocumentResult<()> {
+    fn write_archive<W: Write>(
+        builder: &mut Builder<W>,
+        doc: &Document,
+        thumbnail: Option<&[u8]>,
+    ) -> DocumentResult<()> {
+        if let Some(png) = thumbnail {
+            let mut header = Header::new_gnu();
+            header.set_path(THUMBNAIL_ENTRY)?;
+            header.set_size(png.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, png)?;
+        }
         let json = serde_json::to_vec_pretty(doc)?;
         let mut header = Header::new_gnu();
-        header.set_path("document.json")?;
+        header.set_path(DOCUMENT_ENTRY)?;
         header.set_size(json.len() as u64);
         header.set_mode(0o644);
         header.set_cksum();
         builder.append(&header, &json[..])?;
+        if !doc.journal.entries().is_empty() {
+            let journal = doc.journal.to_jsonl()?;
+            let mut header = Header::new_gnu();
+            header.set_path(journal::JOURNAL_ENTRY)?;
+            header.set_size(journal.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, journal.as_bytes())?;
+        }
+        for (id, data) in &doc.asset_data {
+            if !doc.assets.contains_key(id) {
+                continue;
+            }
+            let mut header = Header::new_gnu();
+            header.set_path(format!("{ASSETS_DIR}{id}"))?;
+            header.set_size(data.bytes().len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, data.bytes())?;
+        }
+        // Meshes of deleted features and bodies are left out.
+        for (id, cached) in doc.tessellations.iter() {
+            let exists = doc.feature_tree.get_node(FeatureId(*id)).is_some()
+                || doc.bodies.iter().any(|body| body.id.0 == *id);
+            if !exists {
+                continue;
+            }
+            let bytes = bincode::serialize(cached)?;
+            let mut header = Header::new_gnu();
+            header.set_path(format!("{CACHE_DIR}{id}.mesh"))?;
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &bytes[..])?;
+        }
         Ok(())
     }
 }
 
+/// Replace every string in `value` that is a key of `ids` (an old uuid) by its
+/// new value.
+fn remap_ids(value: &mut serde_json::Value, ids: &HashMap<String, String>) {
+    match value {
+        serde_json::Value::String(text) => {
+            if let Some(new) = ids.get(text.as_str()) {
+                *text = new.clone();
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                remap_ids(item, ids);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                remap_ids(field, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn next_indexed_name<'a>(base: &str, existing: impl Iterator<Item = &'a str>) -> String {
     let mut max_suffix: Option<u32> = None;
 
     for name in existing {
         if name.eq_ignore_ascii_case(base) {
-            max_suffix = Some(max_suffix.map_or(0, |m| m.max(0)));
+            max_suffix = Some(max_suffix.unwrap_or(0));
         } else if let Some(rest) = name
             .to_ascii_lowercase()
             .strip_prefix(&(base.to_ascii_lowercase() + "_"))
@@ -420,6 +1248,10 @@ pub struct DocumentMetadata {
     name: String,
     revision: u64,
     dirty: bool,
+    #[serde(default)]
+    overrides: DocumentOverrides,
+    #[serde(default)]
+    authoring_axes: Option<AxisSystem>,
 }
 
 impl DocumentMetadata {
@@ -429,6 +1261,8 @@ impl DocumentMetadata {
             name: name.into(),
             revision: 0,
             dirty: false,
+            overrides: DocumentOverrides::default(),
+            authoring_axes: None,
         }
     }
 
@@ -492,6 +1326,33 @@ impl ScreenSpaceOverlay {
     }
 }
 
+/// Text drawn at a fixed screen position in the viewport (a billboard), with a
+/// colored frame so it stays readable over the model.
+#[derive(Debug, Clone)]
+pub struct ScreenSpaceLabel {
+    /// Anchor in screen coordinates (x, y) in pixels, relative to viewport origin.
+    /// The label's bottom-left corner sits there.
+    pub position: [f32; 2],
+    pub text: String,
+    /// RGB color [r, g, b] in range 0.0-1.0 of the frame.
+    pub color: [f32; 3],
+    /// Draw with a thicker frame (e.g. the annotation being edited).
+    pub highlighted: bool,
+}
+
+/// A mesh drawn with zebra stripes instead of regular shading: the reflection
+/// of black and white bands around `axis`, which bend smoothly across
+/// tangent-continuous surfaces and kink where surfaces meet at a crease.
+#[derive(Debug, Clone)]
+pub struct ZebraMesh {
+    /// World-space mesh; its vertex normals decide the reflection.
+    pub mesh: kernel_api::TriMesh,
+    /// Direction the bands are wrapped around (the bands run across it).
+    pub axis: [f32; 3],
+    /// Bands per half turn around the axis.
+    pub stripes: f32,
+}
+
 /// User-facing description provided by workbenches to populate menus.
 #[derive(Debug, Clone)]
 pub struct WorkbenchDescriptor {
@@ -514,6 +1375,13 @@ impl WorkbenchDescriptor {
     }
 }
 
+/// Geometry work for one feature, from [`Workbench::regenerate_feature`]:
+/// run on the host's recompute job, it builds the feature's solid through the
+/// kernel and returns its mesh.
+pub type FeatureBuild = Box<
+    dyn FnOnce(&mut dyn kernel_api::Kernel) -> kernel_api::KernelResult<kernel_api::TriMesh> + Send,
+>;
+
 /// Trait implemented by all workbench plugins.
 ///
 /// Workbenches declare their tools/commands via `configure`, and can optionally
@@ -531,7 +1399,9 @@ pub trait Workbench: Send {
     /// Called when this workbench is deactivated (another WB becomes active).
     fn on_deactivate(&mut self, _ctx: &mut WorkbenchRuntimeContext) {}
 
-    /// Called every frame while this workbench is active.
+    /// Called every frame while this workbench is active, before the viewport
+    /// is drawn. Collect finished background jobs (`ctx.document.jobs()`) here
+    /// and merge their results; keep the work done on this thread short.
     fn on_frame(&mut self, _dt: f32, _ctx: &mut WorkbenchRuntimeContext) {}
 
     /// Called when an input event occurs while this workbench is active.
@@ -545,6 +1415,20 @@ pub trait Workbench: Send {
         InputResult::ignored()
     }
 
+    /// Abort the operation `tool` has in progress (e.g. a line whose start
+    /// point is placed), leaving the tool active. Return whether there was
+    /// one. The host calls this on Escape, which deactivates the tool instead
+    /// when nothing was in progress, and on Cancel in the viewport's tool menu.
+    /// Escape isn't passed to `on_input` while a tool is active.
+    fn cancel_tool_operation(&mut self, _tool: &str, _ctx: &mut WorkbenchRuntimeContext) -> bool {
+        false
+    }
+
+    /// Complete the operation `tool` has in progress with what was entered so
+    /// far. The host calls this on Finish in the viewport's tool menu, then
+    /// deactivates the tool.
+    fn finish_tool_operation(&mut self, _tool: &str, _ctx: &mut WorkbenchRuntimeContext) {}
+
     /// Draw custom UI in the left panel (below the tool list).
     /// Called every frame while this workbench is active.
     #[cfg(feature = "egui")]
@@ -561,6 +1445,34 @@ pub trait Workbench: Send {
         false
     }
 
+    /// Draw the workbench's analyses in the Analysis panel, which the user
+    /// can dock, tab, or float apart from the properties.
+    /// Called every frame while this workbench is active and the panel shown.
+    #[cfg(feature = "egui")]
+    fn ui_analysis_panel(&mut self, _ui: &mut egui::Ui, _ctx: &mut WorkbenchRuntimeContext) {}
+
+    /// Whether this workbench exposes Analysis-panel UI.
+    #[cfg(feature = "egui")]
+    fn wants_analysis_panel(&self) -> bool {
+        false
+    }
+
+    /// The task this workbench has open, if any. While it returns Some, the
+    /// host shows the task's form (`ui_task`) with its buttons in place of the
+    /// right panel and reports presses through `on_task_action`.
+    fn active_task(&self) -> Option<TaskPanel> {
+        None
+    }
+
+    /// Draw the form of the open task. Called every frame while `active_task`
+    /// returns Some.
+    #[cfg(feature = "egui")]
+    fn ui_task(&mut self, _ui: &mut egui::Ui, _ctx: &mut WorkbenchRuntimeContext) {}
+
+    /// OK, Apply, or Cancel was pressed in the task panel. The task should be
+    /// closed after `Ok` and `Cancel`.
+    fn on_task_action(&mut self, _action: TaskAction, _ctx: &mut WorkbenchRuntimeContext) {}
+
     /// Check if a tool is enabled given the current runtime context.
     /// Called by the UI to determine if a tool button should be enabled/disabled.
     /// Default implementation returns true for all tools.
@@ -575,10 +1487,31 @@ pub trait Workbench: Send {
         false // Return true if settings changed
     }
 
+    /// Called with the file the user picked for a `file_open_request` made by
+    /// this workbench (`request_id` is the request's `id`). For save requests
+    /// the file may not exist yet; the workbench writes it.
+    fn on_file_opened(
+        &mut self,
+        _request_id: &str,
+        _path: &std::path::Path,
+        _ctx: &mut WorkbenchRuntimeContext,
+    ) {
+    }
+
     /// Finish/close the current editing session (e.g., finish sketch).
     /// Called when the user requests to finish editing (e.g., via UI button).
     fn finish_editing(&mut self, _ctx: &mut WorkbenchRuntimeContext) {}
 
+    /// Open `feature` for editing. Called when the user double-clicks a
+    /// feature created in this workbench in the model tree, after switching to
+    /// it; the feature is already selected and the active document object.
+    fn edit_feature(&mut self, _feature: FeatureId, _ctx: &mut WorkbenchRuntimeContext) {}
+
+    /// Run `command`, one of the commands this workbench registered. Called by
+    /// the host for commands queued with `ctx.execute_command`, by this or any
+    /// other workbench, whether or not this workbench is active.
+    fn on_command(&mut self, _command: &str, _ctx: &mut WorkbenchRuntimeContext) {}
+
     /// Deserialize a feature of this workbench's type from JSON.
     /// Called by the document when loading features from storage.
     /// Returns None if the feature type doesn't belong to this workbench.
@@ -600,6 +1533,24 @@ pub trait Workbench: Send {
         Vec::new() // Default: no dependencies
     }
 
+    /// Prepare the regeneration of one of this workbench's features: the
+    /// returned work builds its geometry through the kernel. The host runs
+    /// the work of dirty features in dependency order on a background job
+    /// that can be cancelled; the mesh is kept until the feature is dirty
+    /// again, and an error is shown on the feature in the tree.
+    /// Returns None for features without geometry of their own, or whose
+    /// geometry the workbench computes itself.
+    fn regenerate_feature(&self, _feature: &FeatureNode) -> Option<FeatureBuild> {
+        None // Default: no geometry
+    }
+
+    /// Features whose geometry this feature takes the place of in its body,
+    /// such as the solid a pocket is cut from: while the feature has a mesh,
+    /// theirs are neither drawn nor part of the body's.
+    fn replaced_features(&self, _feature: &FeatureNode) -> Vec<FeatureId> {
+        Vec::new() // Default: adds to the body
+    }
+
     /// Get additional render meshes for overlay/helper visualization.
     /// Called every frame to allow workbenches to contribute visual aids (grid lines, guides, etc.).
     /// Returns a vector of (mesh, color) tuples where:
@@ -639,6 +1590,42 @@ pub trait Workbench: Send {
     ) -> Vec<ScreenSpaceOverlay> {
         Vec::new()
     }
+
+    /// Get screen-space labels (text on a framed background) to draw over the
+    /// viewport, such as dimension values. Called every frame alongside
+    /// `get_screen_space_overlays`.
+    /// Default implementation returns empty vector.
+    fn get_screen_space_labels(
+        &self,
+        _ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<ScreenSpaceLabel> {
+        Vec::new()
+    }
+
+    /// Get meshes to draw with zebra stripes, for judging surface smoothness.
+    /// Called every frame alongside `get_overlay_meshes`.
+    /// Default implementation returns empty vector.
+    fn get_zebra_meshes(
+        &self,
+        _ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<ZebraMesh> {
+        Vec::new()
+    }
+
+    /// Get the gizmos (move/rotate/scale handles, arrows, plane handles) to show
+    /// in the viewport. Called every frame; the host draws them, highlights the
+    /// hovered handle, and reports drags through `on_gizmo_drag`.
+    /// Default implementation returns empty vector.
+    fn gizmos(&self, _ctx: &WorkbenchRuntimeContext) -> Vec<Gizmo> {
+        Vec::new()
+    }
+
+    /// Called while the user drags a handle of one of this workbench's gizmos.
+    /// The motion is the total since the drag started; on `Cancel` the
+    /// workbench should restore the state it had at `Start`.
+    fn on_gizmo_drag(&mut self, _drag: &GizmoDrag, _ctx: &mut WorkbenchRuntimeContext) {}
 }
 
 /// Registry used by workbenches to declare the tools/commands they expose.
@@ -696,6 +1683,10 @@ pub struct ToolDescriptor {
     /// Only one tool per group can be active at a time. If None, each tool is its own group.
     /// Ignored for Check and Action tools.
     pub group: Option<String>,
+    /// Key that activates the tool while the viewport has keyboard focus.
+    /// Pressing it again cycles through the workbench's other tools with the
+    /// same key, in registration order.
+    pub shortcut: Option<KeyCode>,
 }
 
 impl ToolDescriptor {
@@ -712,6 +1703,7 @@ impl ToolDescriptor {
             category: category.map(|c| c.into()),
             behavior: ToolBehavior::Radio,
             group: None, // Each tool is its own group by default
+            shortcut: None,
         }
     }
 
@@ -729,6 +1721,7 @@ impl ToolDescriptor {
             category: category.map(|c| c.into()),
             behavior: ToolBehavior::Radio,
             group: Some(group.into()),
+            shortcut: None,
         }
     }
 
@@ -745,6 +1738,7 @@ impl ToolDescriptor {
             category: category.map(|c| c.into()),
             behavior: ToolBehavior::Check,
             group: None, // Groups don't apply to Check tools
+            shortcut: None,
         }
     }
 
@@ -760,8 +1754,15 @@ impl ToolDescriptor {
             category: category.map(|c| c.into()),
             behavior: ToolBehavior::Action,
             group: None, // Groups don't apply to Action tools
+            shortcut: None,
         }
     }
+
+    /// Activate the tool with `key`.
+    pub fn with_shortcut(mut self, key: KeyCode) -> Self {
+        self.shortcut = Some(key);
+        self
+    }
 }
 
 /// Simple metadata for commands that may be bound to shortcuts or macros.
@@ -836,6 +1837,14 @@ impl DocumentService {
         Ok(entry.context.commands())
     }
 
+    /// Workbench that registered the command `command_id`.
+    pub fn command_owner(&self, command_id: &str) -> Option<WorkbenchId> {
+        self.workbenches
+            .values()
+            .find(|entry| entry.context.commands().iter().any(|c| c.id == command_id))
+            .map(|entry| entry.descriptor.id.clone())
+    }
+
     pub fn workbench(&self, id: &WorkbenchId) -> DocumentResult<&dyn Workbench> {
         let entry = self
             .workbenches
@@ -870,6 +1879,8 @@ pub enum DocumentError {
     Io(#[from] std::io::Error),
     #[error("compression error: {0}")]
     Compression(String),
+    #[error("mesh cache encoding failed: {0}")]
+    Cache(#[from] bincode::Error),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,173 @@
+//! Materials bodies are made of.
+//!
+//! A material gives a body a density and a price per kilogram, from which mass
+//! and cost are estimated using the volume of the body's mesh. The common
+//! filaments and resin are built in; a document can add its own entries.
+
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+
+/// Identifier of a material. Presets use fixed ids (`"pla"`); custom entries get
+/// `"custom-"` followed by a number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MaterialId(pub String);
+
+impl MaterialId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    pub id: MaterialId,
+    pub name: String,
+    /// Density in g/cm³.
+    pub density: f32,
+    /// Price per kilogram, in the library's currency.
+    pub price_per_kg: f32,
+}
+
+impl Material {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, density: f32, price: f32) -> Self {
+        Self {
+            id: MaterialId::new(id),
+            name: name.into(),
+            density,
+            price_per_kg: price,
+        }
+    }
+
+    /// Mass in grams of `volume` mm³ of this material.
+    pub fn mass(&self, volume: f32) -> f32 {
+        // 1 cm³ = 1000 mm³.
+        volume / 1000.0 * self.density
+    }
+
+    /// Cost of `volume` mm³ of this material.
+    pub fn cost(&self, volume: f32) -> f32 {
+        self.mass(volume) / 1000.0 * self.price_per_kg
+    }
+}
+
+/// Built-in materials, with typical densities and street prices.
+pub fn presets() -> Vec<Material> {
+    vec![
+        Material::new("pla", "PLA", 1.24, 20.0),
+        Material::new("petg", "PETG", 1.27, 22.0),
+        Material::new("abs", "ABS", 1.04, 20.0),
+        Material::new("resin", "Standard resin", 1.12, 35.0),
+    ]
+}
+
+/// Presets plus the document's custom materials.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialLibrary {
+    /// Symbol shown with prices and costs.
+    pub currency: String,
+    pub custom: Vec<Material>,
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        Self {
+            currency: "$".to_string(),
+            custom: Vec::new(),
+        }
+    }
+}
+
+impl MaterialLibrary {
+    /// Every material, presets first.
+    pub fn all(&self) -> Vec<Material> {
+        let mut all = presets();
+        all.extend(self.custom.iter().cloned());
+        all
+    }
+
+    pub fn get(&self, id: &MaterialId) -> Option<Material> {
+        self.all().into_iter().find(|material| &material.id == id)
+    }
+
+    pub fn is_preset(id: &MaterialId) -> bool {
+        presets().iter().any(|material| &material.id == id)
+    }
+
+    /// Add a custom material and return its id.
+    pub fn add_custom(&mut self, name: impl Into<String>, density: f32, price: f32) -> MaterialId {
+        let next = (1..)
+            .map(|n| MaterialId::new(format!("custom-{n}")))
+            .find(|id| self.custom.iter().all(|material| &material.id != id))
+            .expect("unbounded range always yields a free id");
+        self.custom.push(Material {
+            id: next.clone(),
+            name: name.into(),
+            density,
+            price_per_kg: price,
+        });
+        next
+    }
+
+    /// Remove a custom material. Presets cannot be removed.
+    pub fn remove_custom(&mut self, id: &MaterialId) -> bool {
+        let before = self.custom.len();
+        self.custom.retain(|material| &material.id != id);
+        self.custom.len() != before
+    }
+
+    /// Format an amount with the library's currency.
+    pub fn format_price(&self, amount: f32) -> String {
+        format!("{}{amount:.2}", self.currency)
+    }
+}
+
+/// Volume, mass, and cost of a body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+    /// Volume in mm³.
+    pub volume: f32,
+    /// Mass in grams (None without a material).
+    pub mass: Option<f32>,
+    /// Cost in the library's currency (None without a material).
+    pub cost: Option<f32>,
+}
+
+impl MassProperties {
+    /// Properties of the solid enclosed by `meshes` (the pieces of one body).
+    pub fn of_meshes<'a>(
+        meshes: impl IntoIterator<Item = &'a TriMesh>,
+        material: Option<&Material>,
+    ) -> Self {
+        let volume = meshes.into_iter().map(mesh_volume).sum();
+        Self::from_volume(volume, material)
+    }
+
+    pub fn from_volume(volume: f32, material: Option<&Material>) -> Self {
+        Self {
+            volume,
+            mass: material.map(|material| material.mass(volume)),
+            cost: material.map(|material| material.cost(volume)),
+        }
+    }
+}
+
+/// Volume enclosed by a closed triangle mesh, in mm³ (divergence theorem).
+pub fn mesh_volume(mesh: &TriMesh) -> f32 {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            let cross = [
+                b[1] * c[2] - b[2] * c[1],
+                b[2] * c[0] - b[0] * c[2],
+                b[0] * c[1] - b[1] * c[0],
+            ];
+            (a[0] * cross[0] + a[1] * cross[1] + a[2] * cross[2]) / 6.0
+        })
+        .sum::<f32>()
+        .abs()
+}
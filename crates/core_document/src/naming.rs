@@ -0,0 +1,243 @@
+//! Persistent names for faces, edges, and vertices.
+//!
+//! Element indices change whenever an upstream feature is edited (a sketch
+//! gains a line, a pad gets one more side face), so references that store
+//! indices break. A [`TopoName`] instead records how an element was made: the
+//! feature that generated it and a tag that is stable within that feature, or
+//! the faces it lies between. Features store names in their references and
+//! resolve them against the body's current [`NamedTopology`] when recomputing.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BodyId, Document, FeatureId, OriginRef, SubElement};
+
+/// Dimension of a named element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ElementKind {
+    Face,
+    Edge,
+    Vertex,
+}
+
+/// Persistent name of a face, edge, or vertex.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TopoName {
+    /// Created by `feature`. `tag` tells the element apart from the feature's
+    /// other outputs and must not depend on their order: e.g. `"cap:end"`, or
+    /// `"side:"` followed by the id of the sketch curve a face was swept from.
+    Generated {
+        feature: FeatureId,
+        kind: ElementKind,
+        tag: String,
+    },
+    /// Where faces meet: an edge between two faces, or a vertex shared by
+    /// three or more. The faces are kept in a canonical order.
+    Intersection {
+        kind: ElementKind,
+        faces: Vec<TopoName>,
+    },
+    /// An element of the input carried through `feature`, which changed its
+    /// shape (e.g. a face trimmed by a fillet).
+    Modified {
+        feature: FeatureId,
+        base: Box<TopoName>,
+    },
+}
+
+impl TopoName {
+    pub fn generated(feature: FeatureId, kind: ElementKind, tag: impl Into<String>) -> Self {
+        TopoName::Generated {
+            feature,
+            kind,
+            tag: tag.into(),
+        }
+    }
+
+    /// Element lying between `faces` (order does not matter).
+    pub fn intersection(kind: ElementKind, faces: impl IntoIterator<Item = TopoName>) -> Self {
+        let mut faces: Vec<TopoName> = faces.into_iter().collect();
+        faces.sort_by_cached_key(|face| face.to_string());
+        faces.dedup();
+        TopoName::Intersection { kind, faces }
+    }
+
+    /// `base` as changed by `feature`.
+    pub fn modified(feature: FeatureId, base: TopoName) -> Self {
+        TopoName::Modified {
+            feature,
+            base: Box::new(base),
+        }
+    }
+
+    pub fn kind(&self) -> ElementKind {
+        match self {
+            TopoName::Generated { kind, .. } | TopoName::Intersection { kind, .. } => *kind,
+            TopoName::Modified { base, .. } => base.kind(),
+        }
+    }
+
+    /// The name without the modifications applied by later features: the
+    /// identity of the element as first created.
+    pub fn original(&self) -> TopoName {
+        match self {
+            TopoName::Generated { .. } => self.clone(),
+            TopoName::Intersection { kind, faces } => {
+                TopoName::intersection(*kind, faces.iter().map(TopoName::original))
+            }
+            TopoName::Modified { base, .. } => base.original(),
+        }
+    }
+
+    /// Features the name mentions. A feature referencing the element depends
+    /// on them.
+    pub fn features(&self) -> Vec<FeatureId> {
+        let mut features = Vec::new();
+        self.collect_features(&mut features);
+        features
+    }
+
+    fn collect_features(&self, features: &mut Vec<FeatureId>) {
+        match self {
+            TopoName::Generated { feature, .. } => {
+                if !features.contains(feature) {
+                    features.push(*feature);
+                }
+            }
+            TopoName::Intersection { faces, .. } => {
+                for face in faces {
+                    face.collect_features(features);
+                }
+            }
+            TopoName::Modified { feature, base } => {
+                if !features.contains(feature) {
+                    features.push(*feature);
+                }
+                base.collect_features(features);
+            }
+        }
+    }
+}
+
+impl fmt::Display for TopoName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopoName::Generated { feature, kind, tag } => {
+                write!(f, "{kind:?}({}:{tag})", feature.0)
+            }
+            TopoName::Intersection { kind, faces } => {
+                write!(f, "{kind:?}[")?;
+                for (i, face) in faces.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{face}")?;
+                }
+                write!(f, "]")
+            }
+            TopoName::Modified { feature, base } => write!(f, "{base}~{}", feature.0),
+        }
+    }
+}
+
+/// Names of a body's elements after the last recompute: `faces[i]` names
+/// `SubElement::Face(i)`, and likewise for edges and vertices.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamedTopology {
+    pub faces: Vec<TopoName>,
+    pub edges: Vec<TopoName>,
+    pub vertices: Vec<TopoName>,
+}
+
+impl NamedTopology {
+    fn names(&self, kind: ElementKind) -> &[TopoName] {
+        match kind {
+            ElementKind::Face => &self.faces,
+            ElementKind::Edge => &self.edges,
+            ElementKind::Vertex => &self.vertices,
+        }
+    }
+
+    fn element(kind: ElementKind, index: usize) -> SubElement {
+        let index = index as u32;
+        match kind {
+            ElementKind::Face => SubElement::Face(index),
+            ElementKind::Edge => SubElement::Edge(index),
+            ElementKind::Vertex => SubElement::Vertex(index),
+        }
+    }
+
+    /// Persistent name of an element (e.g. a picked face) to store in a reference.
+    pub fn name_of(&self, element: SubElement) -> Option<&TopoName> {
+        let (kind, index) = match element {
+            SubElement::Face(index) => (ElementKind::Face, index),
+            SubElement::Edge(index) => (ElementKind::Edge, index),
+            SubElement::Vertex(index) => (ElementKind::Vertex, index),
+        };
+        self.names(kind).get(index as usize)
+    }
+
+    /// Element a stored name refers to in the current topology.
+    ///
+    /// An exact match wins. Otherwise the element must have the same original
+    /// identity (it was created the same way and only modified differently
+    /// since); if several elements qualify the reference is ambiguous and
+    /// `None` is returned.
+    pub fn resolve(&self, name: &TopoName) -> Option<SubElement> {
+        let names = self.names(name.kind());
+        if let Some(index) = names.iter().position(|candidate| candidate == name) {
+            return Some(Self::element(name.kind(), index));
+        }
+        let original = name.original();
+        let mut matches = names
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| candidate.original() == original);
+        match (matches.next(), matches.next()) {
+            (Some((index, _)), None) => Some(Self::element(name.kind(), index)),
+            _ => None,
+        }
+    }
+}
+
+/// Geometry a feature builds on (a sketch's support, a fillet's edges), stored
+/// so it survives edits of the features that produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GeometryRef {
+    /// An origin plane, axis, or point of a body.
+    Origin(OriginRef),
+    /// A face, edge, or vertex of a body.
+    Element { body: BodyId, name: TopoName },
+    /// A datum plane, axis, or point feature.
+    Datum(FeatureId),
+    /// A planar face of a body's mesh, made by `feature` (None for mesh
+    /// bodies). Meshes have no named faces, so the face is found again by the
+    /// plane of the geometry placed on it.
+    Face {
+        body: BodyId,
+        feature: Option<FeatureId>,
+    },
+}
+
+impl GeometryRef {
+    /// Features the referenced geometry comes from.
+    pub fn features(&self) -> Vec<FeatureId> {
+        match self {
+            GeometryRef::Origin(_) => Vec::new(),
+            GeometryRef::Element { name, .. } => name.features(),
+            GeometryRef::Datum(feature) => vec![*feature],
+            GeometryRef::Face { feature, .. } => feature.iter().copied().collect(),
+        }
+    }
+
+    /// Body the referenced geometry belongs to, whose position its
+    /// coordinates are relative to.
+    pub fn body(&self, document: &Document) -> Option<BodyId> {
+        match self {
+            GeometryRef::Origin(origin) => Some(origin.body),
+            GeometryRef::Element { body, .. } | GeometryRef::Face { body, .. } => Some(*body),
+            GeometryRef::Datum(feature) => document.get_feature_meta(*feature)?.body,
+        }
+    }
+}
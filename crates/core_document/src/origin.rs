@@ -0,0 +1,149 @@
+//! Origin reference geometry carried by every body.
+//!
+//! Each body has a fixed origin point, X/Y/Z axes, and XY/XZ/YZ planes. They are not stored as
+//! features (they never change and cannot be deleted); only their visibility is persisted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::BodyId;
+
+/// One of the fixed reference elements at a body's origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OriginElement {
+    Point,
+    XAxis,
+    YAxis,
+    ZAxis,
+    XyPlane,
+    XzPlane,
+    YzPlane,
+}
+
+impl OriginElement {
+    pub const ALL: [OriginElement; 7] = [
+        OriginElement::Point,
+        OriginElement::XAxis,
+        OriginElement::YAxis,
+        OriginElement::ZAxis,
+        OriginElement::XyPlane,
+        OriginElement::XzPlane,
+        OriginElement::YzPlane,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            OriginElement::Point => "Origin",
+            OriginElement::XAxis => "X axis",
+            OriginElement::YAxis => "Y axis",
+            OriginElement::ZAxis => "Z axis",
+            OriginElement::XyPlane => "XY plane",
+            OriginElement::XzPlane => "XZ plane",
+            OriginElement::YzPlane => "YZ plane",
+        }
+    }
+
+    pub const fn is_plane(self) -> bool {
+        matches!(
+            self,
+            OriginElement::XyPlane | OriginElement::XzPlane | OriginElement::YzPlane
+        )
+    }
+
+    pub const fn is_axis(self) -> bool {
+        matches!(
+            self,
+            OriginElement::XAxis | OriginElement::YAxis | OriginElement::ZAxis
+        )
+    }
+
+    /// Direction of an axis element (None for planes and the point).
+    pub const fn axis_direction(self) -> Option<[f32; 3]> {
+        match self {
+            OriginElement::XAxis => Some([1.0, 0.0, 0.0]),
+            OriginElement::YAxis => Some([0.0, 1.0, 0.0]),
+            OriginElement::ZAxis => Some([0.0, 0.0, 1.0]),
+            _ => None,
+        }
+    }
+
+    /// Coordinate frame of a plane element (None for axes and the point).
+    ///
+    /// Frames are right-handed: `x_axis × y_axis = normal`.
+    pub const fn plane(self) -> Option<ReferencePlane> {
+        let (normal, x_axis, y_axis) = match self {
+            OriginElement::XyPlane => ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            OriginElement::XzPlane => ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            OriginElement::YzPlane => ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            _ => return None,
+        };
+        Some(ReferencePlane {
+            origin: [0.0, 0.0, 0.0],
+            normal,
+            x_axis,
+            y_axis,
+        })
+    }
+}
+
+/// A plane in world space together with an in-plane coordinate frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReferencePlane {
+    pub origin: [f32; 3],
+    pub normal: [f32; 3],
+    pub x_axis: [f32; 3],
+    pub y_axis: [f32; 3],
+}
+
+/// A line in world space: a point on it and its unit direction.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceAxis {
+    pub origin: [f32; 3],
+    pub direction: [f32; 3],
+}
+
+/// Per-body origin state. The elements themselves are fixed; only visibility is stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BodyOrigin {
+    /// Elements shown in the viewport (all hidden by default).
+    #[serde(default)]
+    visible: Vec<OriginElement>,
+}
+
+impl BodyOrigin {
+    pub fn is_visible(&self, element: OriginElement) -> bool {
+        self.visible.contains(&element)
+    }
+
+    pub fn set_visible(&mut self, element: OriginElement, visible: bool) {
+        if visible {
+            if !self.is_visible(element) {
+                self.visible.push(element);
+            }
+        } else {
+            self.visible.retain(|&e| e != element);
+        }
+    }
+
+    /// Visible elements, in [`OriginElement::ALL`] order.
+    pub fn visible_elements(&self) -> impl Iterator<Item = OriginElement> + '_ {
+        OriginElement::ALL
+            .into_iter()
+            .filter(|&element| self.is_visible(element))
+    }
+}
+
+/// Reference to an origin element of a specific body.
+///
+/// Used wherever a feature needs a datum: as a sketch plane, or as the mirror plane / pattern
+/// axis of a transformed feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OriginRef {
+    pub body: BodyId,
+    pub element: OriginElement,
+}
+
+impl OriginRef {
+    pub const fn new(body: BodyId, element: OriginElement) -> Self {
+        Self { body, element }
+    }
+}
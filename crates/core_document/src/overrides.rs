@@ -0,0 +1,50 @@
+//! Per-document overrides of user preferences.
+
+use axes::{AxisPreset, AxisSystem};
+use kernel_api::TessellationSettings;
+use serde::{Deserialize, Serialize};
+use units::UnitFormat;
+
+/// User settings a document pins for itself.
+///
+/// Each field is `None` when the document follows the user's preference. Overrides are stored in
+/// the document metadata and take precedence over user settings while the document is open.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentOverrides {
+    /// Units and precision used to display and enter lengths.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<UnitFormat>,
+    /// Axis convention (which direction is up).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub axis_preset: Option<AxisPreset>,
+    /// User-defined axis system; takes precedence over `axis_preset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_axes: Option<AxisSystem>,
+    /// Sketch/placement grid spacing in millimetres.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grid_spacing: Option<f32>,
+    /// Tessellation quality for bodies in this document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tessellation: Option<TessellationSettings>,
+}
+
+impl DocumentOverrides {
+    /// True if the document does not override anything.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Pin the axis convention, storing it as a preset when it matches one.
+    pub fn set_axes(&mut self, axes: AxisSystem) {
+        match axes.preset() {
+            Some(preset) => {
+                self.axis_preset = Some(preset);
+                self.custom_axes = None;
+            }
+            None => {
+                self.axis_preset = Some(AxisPreset::default());
+                self.custom_axes = Some(axes);
+            }
+        }
+    }
+}
@@ -0,0 +1,815 @@
+//! Document parameter table.
+//!
+//! A spreadsheet-like grid of cells holding numbers, text, or formulas (`=A1 * 2`).
+//! Cells can be given an alias so formulas and feature bindings can refer to them by
+//! name. Features bind numeric fields of their data to expressions over the table
+//! (see `Document::set_feature_expression`), so a design table drives dimensions
+//! across the whole model.
+//!
+//! Values are plain numbers; lengths are millimetres like the rest of the document.
+//! A number may carry a unit suffix (`3 in`, `2.5cm`), which converts it to
+//! millimetres. Trigonometric functions work in degrees.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use units::LengthUnit;
+
+use crate::FeatureId;
+
+/// Arrays longer than this inside feature data (mesh buffers, point lists) are not
+/// offered as binding targets.
+const MAX_BINDABLE_ARRAY: usize = 4;
+
+/// Functions available in formulas, with their argument count (`None` for any
+/// count of at least one).
+const FUNCTIONS: [(&str, Option<usize>); 13] = [
+    ("abs", Some(1)),
+    ("sqrt", Some(1)),
+    ("round", Some(1)),
+    ("floor", Some(1)),
+    ("ceil", Some(1)),
+    ("sin", Some(1)),
+    ("cos", Some(1)),
+    ("tan", Some(1)),
+    ("asin", Some(1)),
+    ("acos", Some(1)),
+    ("atan", Some(1)),
+    ("min", None),
+    ("max", None),
+];
+
+/// Errors from evaluating the parameter table or a binding.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParameterError {
+    #[error("syntax error: {0}")]
+    Syntax(String),
+    #[error("unknown name `{0}`")]
+    UnknownName(String),
+    #[error("cell {0} is empty")]
+    EmptyCell(CellRef),
+    #[error("cell {0} does not hold a number")]
+    NotANumber(CellRef),
+    #[error("circular reference through {0}")]
+    Circular(CellRef),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("`{0}` takes a different number of arguments")]
+    Arguments(String),
+    #[error("result is not a finite number")]
+    NotFinite,
+    #[error("`{0}` is not a valid alias")]
+    InvalidAlias(String),
+    #[error("alias `{0}` is already used")]
+    DuplicateAlias(String),
+    #[error("feature has no numeric value at `{0}`")]
+    MissingField(String),
+}
+
+/// A binding that could not be applied.
+#[derive(Debug, Clone, Error)]
+#[error("{path}: {error}")]
+pub struct BindingError {
+    pub feature: FeatureId,
+    pub path: String,
+    pub error: ParameterError,
+}
+
+/// Position of a cell; zero-based, written `A1` for column 0, row 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CellRef {
+    pub row: u32,
+    pub column: u32,
+}
+
+impl CellRef {
+    pub fn new(column: u32, row: u32) -> Self {
+        Self { row, column }
+    }
+
+    /// Parse a reference such as `B12` (column letters are upper case).
+    pub fn parse(text: &str) -> Option<Self> {
+        let split = text.find(|c: char| !c.is_ascii_uppercase())?;
+        let (letters, digits) = text.split_at(split);
+        if letters.is_empty() || letters.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let column = letters
+            .bytes()
+            .fold(0u32, |column, b| column * 26 + u32::from(b - b'A') + 1)
+            - 1;
+        let row: u32 = digits.parse().ok()?;
+        (row >= 1).then(|| Self::new(column, row - 1))
+    }
+
+    /// Column letters, e.g. `A`, `Z`, `AA`.
+    pub fn column_name(column: u32) -> String {
+        let mut name = Vec::new();
+        let mut rest = column + 1;
+        while rest > 0 {
+            rest -= 1;
+            name.push(b'A' + (rest % 26) as u8);
+            rest /= 26;
+        }
+        name.reverse();
+        String::from_utf8(name).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Self::column_name(self.column), self.row + 1)
+    }
+}
+
+impl From<CellRef> for String {
+    fn from(cell: CellRef) -> Self {
+        cell.to_string()
+    }
+}
+
+impl TryFrom<String> for CellRef {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CellRef::parse(&value).ok_or_else(|| format!("invalid cell reference `{value}`"))
+    }
+}
+
+/// What the user typed into a cell.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    /// A number (optionally with a unit), a formula starting with `=`, or text.
+    pub content: String,
+    /// Name formulas and bindings can use instead of the cell reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// What the value measures, for showing it in the parameters panel.
+    #[serde(default, skip_serializing_if = "ParameterUnit::is_number")]
+    pub unit: ParameterUnit,
+}
+
+/// Quantity a named parameter holds. Only affects how its value is shown; lengths
+/// are millimetres and angles degrees either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterUnit {
+    #[default]
+    Number,
+    Length,
+    Angle,
+}
+
+impl ParameterUnit {
+    pub const ALL: [ParameterUnit; 3] = [Self::Number, Self::Length, Self::Angle];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Number => "Number",
+            Self::Length => "Length",
+            Self::Angle => "Angle",
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        *self == Self::Number
+    }
+}
+
+/// Evaluated content of a cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Number(f64),
+    Text(String),
+}
+
+/// The document's parameter table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParameterSheet {
+    cells: BTreeMap<CellRef, Cell>,
+}
+
+impl ParameterSheet {
+    pub fn cell(&self, cell: CellRef) -> Option<&Cell> {
+        self.cells.get(&cell)
+    }
+
+    /// Non-empty cells, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (CellRef, &Cell)> {
+        self.cells.iter().map(|(cell, content)| (*cell, content))
+    }
+
+    /// Smallest (columns, rows) grid holding every non-empty cell.
+    pub fn used_size(&self) -> (u32, u32) {
+        self.cells.keys().fold((0, 0), |(columns, rows), cell| {
+            (columns.max(cell.column + 1), rows.max(cell.row + 1))
+        })
+    }
+
+    /// Replace the content of a cell (an empty cell without alias is removed).
+    pub fn set_content(&mut self, cell: CellRef, content: impl Into<String>) {
+        let content = content.into();
+        let entry = self.cells.entry(cell).or_default();
+        entry.content = content;
+        if entry.content.trim().is_empty() && entry.alias.is_none() {
+            self.cells.remove(&cell);
+        }
+    }
+
+    /// Name a cell, or remove its name with `None`.
+    pub fn set_alias(&mut self, cell: CellRef, alias: Option<&str>) -> Result<(), ParameterError> {
+        let alias = alias.map(str::trim).filter(|alias| !alias.is_empty());
+        if let Some(alias) = alias {
+            if !is_valid_alias(alias) {
+                return Err(ParameterError::InvalidAlias(alias.to_string()));
+            }
+            if self.find_alias(alias).is_some_and(|other| other != cell) {
+                return Err(ParameterError::DuplicateAlias(alias.to_string()));
+            }
+        }
+        let entry = self.cells.entry(cell).or_default();
+        entry.alias = alias.map(str::to_string);
+        if entry.content.trim().is_empty() && entry.alias.is_none() {
+            self.cells.remove(&cell);
+        }
+        Ok(())
+    }
+
+    /// Set what a cell's value measures.
+    pub fn set_unit(&mut self, cell: CellRef, unit: ParameterUnit) {
+        if let Some(entry) = self.cells.get_mut(&cell) {
+            entry.unit = unit;
+        }
+    }
+
+    /// Clear a cell, including its alias.
+    pub fn remove(&mut self, cell: CellRef) -> Option<Cell> {
+        self.cells.remove(&cell)
+    }
+
+    /// Rename a cell and rewrite the formulas using its old alias. A cell without an
+    /// alias is simply named.
+    pub fn rename(&mut self, cell: CellRef, alias: &str) -> Result<(), ParameterError> {
+        let old = self.cell(cell).and_then(|content| content.alias.clone());
+        self.set_alias(cell, Some(alias))?;
+        let Some(old) = old else {
+            return Ok(());
+        };
+        let new = alias.trim();
+        for content in self.cells.values_mut() {
+            if let Some(formula) = content.content.trim().strip_prefix('=') {
+                content.content = format!("={}", rename_reference(formula, &old, new));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cells whose formulas use `cell`, by reference or alias.
+    pub fn dependents(&self, cell: CellRef) -> Vec<CellRef> {
+        let alias = self.cell(cell).and_then(|content| content.alias.as_deref());
+        self.cells
+            .iter()
+            .filter(|(other, content)| {
+                **other != cell
+                    && content
+                        .content
+                        .trim()
+                        .strip_prefix('=')
+                        .is_some_and(|formula| {
+                            references(formula)
+                                .iter()
+                                .any(|name| names_cell(name, cell, alias))
+                        })
+            })
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// Cell carrying `alias`.
+    pub fn find_alias(&self, alias: &str) -> Option<CellRef> {
+        self.cells
+            .iter()
+            .find(|(_, content)| content.alias.as_deref() == Some(alias))
+            .map(|(cell, _)| *cell)
+    }
+
+    /// Evaluate every cell.
+    pub fn evaluate(&self) -> Evaluation {
+        let mut evaluator = Evaluator {
+            sheet: self,
+            values: HashMap::new(),
+            stack: Vec::new(),
+        };
+        for cell in self.cells.keys() {
+            evaluator.cell_value(*cell);
+        }
+        Evaluation {
+            values: evaluator.values,
+            aliases: self
+                .cells
+                .iter()
+                .filter_map(|(cell, content)| Some((content.alias.clone()?, *cell)))
+                .collect(),
+        }
+    }
+}
+
+/// Values of all cells of a parameter table.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    values: HashMap<CellRef, Result<CellValue, ParameterError>>,
+    aliases: HashMap<String, CellRef>,
+}
+
+impl Evaluation {
+    /// Value of a cell (`None` for empty cells).
+    pub fn value(&self, cell: CellRef) -> Option<&Result<CellValue, ParameterError>> {
+        self.values.get(&cell)
+    }
+
+    /// Evaluate an expression (without a leading `=`) against the table.
+    pub fn expression(&self, text: &str) -> Result<f64, ParameterError> {
+        let expr = parse(text)?;
+        eval(&expr, &mut |name| {
+            let cell = resolve_name(name, |alias| self.aliases.get(alias).copied())?;
+            match self.values.get(&cell) {
+                None => Err(ParameterError::EmptyCell(cell)),
+                Some(Ok(CellValue::Number(value))) => Ok(*value),
+                Some(Ok(CellValue::Text(_))) => Err(ParameterError::NotANumber(cell)),
+                Some(Err(err)) => Err(err.clone()),
+            }
+        })
+    }
+}
+
+/// Names an expression (without a leading `=`) reads: cell references and aliases,
+/// in order of appearance. Empty when the expression does not parse.
+pub fn references(expression: &str) -> Vec<String> {
+    let Ok((chars, spans)) = name_spans(expression) else {
+        return Vec::new();
+    };
+    spans
+        .into_iter()
+        .map(|span| chars[span].iter().collect::<String>())
+        .filter(|name| name != "pi")
+        .collect()
+}
+
+/// Replace the name `from` by `to` wherever an expression (without a leading `=`)
+/// reads it, leaving function names and unit suffixes alone. An expression that
+/// does not parse is returned unchanged.
+pub fn rename_reference(expression: &str, from: &str, to: &str) -> String {
+    let Ok((chars, spans)) = name_spans(expression) else {
+        return expression.to_string();
+    };
+    let mut renamed = String::with_capacity(expression.len());
+    let mut pos = 0;
+    for span in spans {
+        if chars[span.clone()].iter().copied().eq(from.chars()) {
+            renamed.extend(&chars[pos..span.start]);
+            renamed.push_str(to);
+            pos = span.end;
+        }
+    }
+    renamed.extend(&chars[pos..]);
+    renamed
+}
+
+/// Whether `name` in a formula refers to `cell`, which carries `alias`.
+pub(crate) fn names_cell(name: &str, cell: CellRef, alias: Option<&str>) -> bool {
+    CellRef::parse(name) == Some(cell) || alias == Some(name)
+}
+
+/// Numeric values inside feature data as JSON pointers (e.g. `/operation/ratio`),
+/// for choosing what a binding drives. Long arrays are skipped.
+pub fn numeric_fields(data: &serde_json::Value) -> Vec<(String, f64)> {
+    fn walk(value: &serde_json::Value, path: &mut String, out: &mut Vec<(String, f64)>) {
+        let len = path.len();
+        match value {
+            serde_json::Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    out.push((path.clone(), number));
+                }
+            }
+            serde_json::Value::Array(items) if items.len() <= MAX_BINDABLE_ARRAY => {
+                for (index, item) in items.iter().enumerate() {
+                    path.push_str(&format!("/{index}"));
+                    walk(item, path, out);
+                    path.truncate(len);
+                }
+            }
+            serde_json::Value::Object(fields) => {
+                for (key, item) in fields {
+                    path.push('/');
+                    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    walk(item, path, out);
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    walk(data, &mut String::new(), &mut out);
+    out
+}
+
+/// Write `value` at `path` in feature data, keeping integer fields integral.
+/// Returns whether the data changed.
+pub(crate) fn write_field(
+    data: &mut serde_json::Value,
+    path: &str,
+    value: f64,
+) -> Result<bool, ParameterError> {
+    let missing = || ParameterError::MissingField(path.to_string());
+    let field = data.pointer_mut(path).ok_or_else(missing)?;
+    let serde_json::Value::Number(current) = field else {
+        return Err(missing());
+    };
+    let new = if current.is_u64() {
+        serde_json::Number::from(value.round().max(0.0) as u64)
+    } else if current.is_i64() {
+        serde_json::Number::from(value.round() as i64)
+    } else {
+        serde_json::Number::from_f64(value).ok_or(ParameterError::NotFinite)?
+    };
+    if *current == new {
+        return Ok(false);
+    }
+    *field = serde_json::Value::Number(new);
+    Ok(true)
+}
+
+/// Exact conversion factor (`LengthUnit::mm_per_unit` is only single precision).
+fn mm_per_unit(unit: LengthUnit) -> f64 {
+    match unit {
+        LengthUnit::Millimeter => 1.0,
+        LengthUnit::Centimeter => 10.0,
+        LengthUnit::Meter => 1000.0,
+        LengthUnit::Inch => 25.4,
+        LengthUnit::Foot => 304.8,
+    }
+}
+
+fn is_valid_alias(alias: &str) -> bool {
+    let mut chars = alias.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && CellRef::parse(alias).is_none()
+        && alias != "pi"
+        && !FUNCTIONS.iter().any(|(name, _)| *name == alias)
+}
+
+/// Cell a name in a formula refers to.
+fn resolve_name(
+    name: &str,
+    alias: impl Fn(&str) -> Option<CellRef>,
+) -> Result<CellRef, ParameterError> {
+    CellRef::parse(name)
+        .or_else(|| alias(name))
+        .ok_or_else(|| ParameterError::UnknownName(name.to_string()))
+}
+
+struct Evaluator<'a> {
+    sheet: &'a ParameterSheet,
+    values: HashMap<CellRef, Result<CellValue, ParameterError>>,
+    /// Cells being evaluated, to detect circular references.
+    stack: Vec<CellRef>,
+}
+
+impl Evaluator<'_> {
+    fn cell_value(&mut self, cell: CellRef) -> Option<Result<CellValue, ParameterError>> {
+        if let Some(value) = self.values.get(&cell) {
+            return Some(value.clone());
+        }
+        if self.stack.contains(&cell) {
+            return Some(Err(ParameterError::Circular(cell)));
+        }
+        let content = self.sheet.cells.get(&cell)?.content.trim().to_string();
+        if content.is_empty() {
+            return None;
+        }
+        self.stack.push(cell);
+        let value = match content.strip_prefix('=') {
+            Some(formula) => parse(formula)
+                .and_then(|expr| eval(&expr, &mut |name| self.number(name)))
+                .map(CellValue::Number),
+            None => Ok(match parse(&content).ok().as_ref().and_then(literal) {
+                Some(value) => CellValue::Number(value),
+                None => CellValue::Text(content),
+            }),
+        };
+        self.stack.pop();
+        self.values.insert(cell, value.clone());
+        Some(value)
+    }
+
+    fn number(&mut self, name: &str) -> Result<f64, ParameterError> {
+        let cell = resolve_name(name, |alias| self.sheet.find_alias(alias))?;
+        match self.cell_value(cell) {
+            None => Err(ParameterError::EmptyCell(cell)),
+            Some(Ok(CellValue::Number(value))) => Ok(value),
+            Some(Ok(CellValue::Text(_))) => Err(ParameterError::NotANumber(cell)),
+            Some(Err(err)) => Err(err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Name(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Value of a plain (possibly negative) number.
+fn literal(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Number(value) => Some(*value),
+        Expr::Negate(inner) => literal(inner).map(|value| -value),
+        _ => None,
+    }
+}
+
+fn eval(
+    expr: &Expr,
+    resolve: &mut dyn FnMut(&str) -> Result<f64, ParameterError>,
+) -> Result<f64, ParameterError> {
+    let value = match expr {
+        Expr::Number(value) => *value,
+        Expr::Name(name) if name == "pi" => std::f64::consts::PI,
+        Expr::Name(name) => resolve(name)?,
+        Expr::Negate(inner) => -eval(inner, resolve)?,
+        Expr::Binary(op, lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs, resolve)?, eval(rhs, resolve)?);
+            match op {
+                BinaryOp::Add => lhs + rhs,
+                BinaryOp::Subtract => lhs - rhs,
+                BinaryOp::Multiply => lhs * rhs,
+                BinaryOp::Divide if rhs == 0.0 => return Err(ParameterError::DivisionByZero),
+                BinaryOp::Divide => lhs / rhs,
+                BinaryOp::Power => lhs.powf(rhs),
+            }
+        }
+        Expr::Call(name, args) => {
+            let Some((_, arity)) = FUNCTIONS.iter().find(|(function, _)| function == name) else {
+                return Err(ParameterError::UnknownFunction(name.clone()));
+            };
+            if args.is_empty() || arity.is_some_and(|arity| arity != args.len()) {
+                return Err(ParameterError::Arguments(name.clone()));
+            }
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, resolve))
+                .collect::<Result<Vec<_>, _>>()?;
+            let x = args[0];
+            match name.as_str() {
+                "abs" => x.abs(),
+                "sqrt" => x.sqrt(),
+                "round" => x.round(),
+                "floor" => x.floor(),
+                "ceil" => x.ceil(),
+                "sin" => x.to_radians().sin(),
+                "cos" => x.to_radians().cos(),
+                "tan" => x.to_radians().tan(),
+                "asin" => x.asin().to_degrees(),
+                "acos" => x.acos().to_degrees(),
+                "atan" => x.atan().to_degrees(),
+                "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+                _ => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            }
+        }
+    };
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(ParameterError::NotFinite)
+    }
+}
+
+fn parse(text: &str) -> Result<Expr, ParameterError> {
+    Parser::new(text).parse()
+}
+
+/// Characters of an expression and the spans of the names it reads.
+fn name_spans(text: &str) -> Result<(Vec<char>, Vec<Range<usize>>), ParameterError> {
+    let mut parser = Parser::new(text);
+    parser.parse()?;
+    Ok((parser.chars, parser.names))
+}
+
+/// Recursive-descent parser; `^` binds tighter than unary minus (`-2^2` is -4).
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Spans of the names read so far (not functions or unit suffixes).
+    names: Vec<Range<usize>>,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+            names: Vec::new(),
+        }
+    }
+
+    fn parse(&mut self) -> Result<Expr, ParameterError> {
+        let expr = self.expression()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(expr),
+            Some(c) => Err(self.error(format!("unexpected `{c}`"))),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` (after whitespace) if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParameterError {
+        ParameterError::Syntax(format!("{} at position {}", message.into(), self.pos + 1))
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParameterError> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Subtract
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParameterError> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Multiply
+            } else if self.eat('/') {
+                BinaryOp::Divide
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParameterError> {
+        if self.eat('-') {
+            Ok(Expr::Negate(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            let base = self.primary()?;
+            if self.eat('^') {
+                Ok(Expr::Binary(
+                    BinaryOp::Power,
+                    Box::new(base),
+                    Box::new(self.unary()?),
+                ))
+            } else {
+                Ok(base)
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParameterError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.pos;
+                let name = self.identifier();
+                let end = self.pos;
+                if !self.eat('(') {
+                    self.names.push(start..end);
+                    return Ok(Expr::Name(name));
+                }
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            return Err(self.error("expected `,` or `)`"));
+                        }
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}`"))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// A number literal with an optional unit suffix.
+    fn number(&mut self) -> Result<Expr, ParameterError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        // Exponent, only when digits follow (`2e3`, `1.5E-2`).
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let mark = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            } else {
+                self.pos = mark;
+            }
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        let value: f64 = literal
+            .parse()
+            .map_err(|_| self.error(format!("invalid number `{literal}`")))?;
+
+        let mark = self.pos;
+        self.skip_whitespace();
+        let unit = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                LengthUnit::from_symbol(&quote.to_string())
+            }
+            // A name directly followed by `(` is a function call, not a unit.
+            Some(c) if c.is_ascii_alphabetic() => {
+                let symbol = self.identifier();
+                LengthUnit::from_symbol(&symbol).filter(|_| self.peek() != Some('('))
+            }
+            _ => None,
+        };
+        match unit {
+            Some(unit) => Ok(Expr::Number(value * mm_per_unit(unit))),
+            None => {
+                self.pos = mark;
+                Ok(Expr::Number(value))
+            }
+        }
+    }
+}
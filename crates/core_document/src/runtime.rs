@@ -4,7 +4,13 @@
 //! the application shell: logging, document access, camera/picking info, and
 //! overlay drawing.
 
-use crate::{Document, FeatureId};
+use std::collections::HashMap;
+
+use axes::AxisSystem;
+use kernel_api::{ExchangeFormat, ExchangeOptions, TriMesh};
+
+use crate::{BodyId, Document, FeatureId, OriginRef, Selection, SelectionItem};
+use units::UnitFormat;
 
 /// Log levels for workbench messages.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,7 +34,7 @@ pub struct LogEntry {
 /// - Logging (routed to the in-app log panel)
 /// - Read/write access to the active document
 /// - Camera and viewport information (read-only)
-/// - Picking/selection state
+/// - Picking state and the shared selection set
 /// - Overlay drawing registration (for tool visualizations)
 pub struct WorkbenchRuntimeContext<'a> {
     /// The active document (mutable access for edits).
@@ -37,39 +43,195 @@ pub struct WorkbenchRuntimeContext<'a> {
     /// Pending log entries to be flushed by the host after the hook returns.
     pending_logs: Vec<LogEntry>,
 
-    /// Current camera position in world space.
+    /// Commands queued with `execute_command`, run by the host after the hook returns.
+    pending_commands: Vec<String>,
+
+    /// World position the positions in this context are relative to: the
+    /// camera, the cursor, and what the workbench draws or hands back
+    /// (overlays, gizmos, camera requests). It is the frame of the body being
+    /// worked on (see `Body::position`), so that body's geometry needs no
+    /// conversion; other bodies are offset by [`Self::frame_offset`].
+    pub frame: [f64; 3],
+
+    /// Current camera position, relative to `frame`.
     pub camera_position: [f32; 3],
 
-    /// Current camera target (orbit center) in world space.
+    /// Current camera target (orbit center), relative to `frame`.
     pub camera_target: [f32; 3],
 
     /// Viewport dimensions (x, y, width, height) in pixels.
     pub viewport: (u32, u32, u32, u32),
 
-    /// View-projection matrix for transforming 3D world coordinates to clip space.
-    /// Used for projecting 3D points to screen coordinates.
+    /// View-projection matrix for transforming 3D coordinates (relative to
+    /// `frame`) to clip space. Used for projecting 3D points to screen
+    /// coordinates.
     pub view_proj: Option<[[f32; 4]; 4]>,
 
-    /// World position under the cursor (if any geometry is hovered).
+    /// Position under the cursor, relative to `frame` (if any geometry is
+    /// hovered).
     pub hovered_world_pos: Option<[f32; 3]>,
 
-    /// ID of the body currently under the cursor (if any).
-    pub hovered_body_id: Option<uuid::Uuid>,
+    /// Surface normal at `hovered_world_pos`, facing the camera.
+    pub hovered_normal: Option<[f32; 3]>,
+
+    /// Body or feature currently under the cursor (if any).
+    pub hovered: Option<SelectionItem>,
 
-    /// ID of the currently selected body (if any).
-    pub selected_body_id: Option<uuid::Uuid>,
+    /// Selected bodies, features, and sub-elements. Workbenches may change it;
+    /// the host picks up the changes after the hook returns.
+    pub selection: Selection,
 
     /// Active document object (selected feature in tree - separate from editing mode).
     pub active_document_object: Option<FeatureId>,
 
+    /// Origin plane/axis/point selected in the tree (for use as a sketch plane or reference).
+    pub selected_reference: Option<OriginRef>,
+
     /// Current cursor position in viewport-local coordinates (if inside viewport).
     pub cursor_viewport_pos: Option<(f32, f32)>,
 
     /// Request camera orientation to a plane (set by workbench, read by host).
     pub camera_orient_request: Option<CameraOrientRequest>,
 
+    /// Request to center the view on a region (set by workbench, read by host).
+    pub camera_focus_request: Option<CameraFocusRequest>,
+
     /// Request to exit sketch mode (set by workbench UI, read by host).
     pub finish_sketch_requested: bool,
+
+    /// Ask the host to let the user pick a file (set by workbench, read by host).
+    /// The chosen path is delivered to `Workbench::on_file_opened`.
+    pub file_open_request: Option<FileOpenRequest>,
+
+    /// Grid and object-snap preferences (from user settings).
+    pub snap: SnapOptions,
+
+    /// How lengths should be displayed (document values are always millimetres).
+    pub units: UnitFormat,
+
+    /// Axis convention in effect (which world direction is up).
+    pub axes: AxisSystem,
+
+    /// Tessellated geometry of each body, in the body's frame.
+    pub body_meshes: &'a [BodyMesh],
+
+    /// Build volume of the active printer profile (None if no printer is configured).
+    pub printer: Option<PrintVolume>,
+
+    /// Axis, unit, and scale conventions of each file format from Settings,
+    /// for workbenches that import or export files (see `exchange_options`).
+    pub exchange: HashMap<ExchangeFormat, ExchangeOptions>,
+}
+
+/// Tessellated geometry of one body, provided by the host.
+#[derive(Debug, Clone)]
+pub struct BodyMesh {
+    pub body: BodyId,
+    /// Geometry relative to `position`.
+    pub mesh: TriMesh,
+    /// World position of the body's frame (see `Body::position`).
+    pub position: [f64; 3],
+}
+
+/// Shape of a printer's build plate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedShape {
+    Rectangular,
+    Circular,
+}
+
+/// Where the printer's coordinate origin sits on the bed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedOrigin {
+    FrontLeftCorner,
+    Center,
+}
+
+/// Build volume of a printer, in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintVolume {
+    /// Bed width (X), depth (Y), and maximum build height (Z). For circular beds X is the
+    /// diameter.
+    pub size: [f32; 3],
+    pub shape: BedShape,
+    pub origin: BedOrigin,
+}
+
+impl PrintVolume {
+    /// Minimum and maximum bed-plane coordinates (X, Y) of the build area.
+    pub fn bed_extent(&self) -> ([f32; 2], [f32; 2]) {
+        let [width, depth, _] = self.size;
+        match self.origin {
+            BedOrigin::FrontLeftCorner => ([0.0, 0.0], [width, depth]),
+            BedOrigin::Center => ([-width * 0.5, -depth * 0.5], [width * 0.5, depth * 0.5]),
+        }
+    }
+
+    /// Whether a point in printer coordinates lies inside the build volume.
+    pub fn contains(&self, point: [f32; 3]) -> bool {
+        const EPSILON: f32 = 1e-3;
+        let ([min_x, min_y], [max_x, max_y]) = self.bed_extent();
+        if point[2] < -EPSILON || point[2] > self.size[2] + EPSILON {
+            return false;
+        }
+        match self.shape {
+            BedShape::Rectangular => {
+                point[0] >= min_x - EPSILON
+                    && point[0] <= max_x + EPSILON
+                    && point[1] >= min_y - EPSILON
+                    && point[1] <= max_y + EPSILON
+            }
+            BedShape::Circular => {
+                let center_x = (min_x + max_x) * 0.5;
+                let center_y = (min_y + max_y) * 0.5;
+                let radius = self.size[0] * 0.5;
+                let (dx, dy) = (point[0] - center_x, point[1] - center_y);
+                (dx * dx + dy * dy).sqrt() <= radius + EPSILON
+            }
+        }
+    }
+}
+
+/// Grid and object-snap preferences supplied by the host.
+///
+/// Placement tools should route picked points through these options so snapping
+/// behaves the same in every workbench.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapOptions {
+    /// Grid spacing in document units.
+    pub grid_spacing: f32,
+    /// Draw the grid while editing on a plane.
+    pub show_grid: bool,
+    /// Grid cells between major grid lines (1 = no major lines).
+    pub grid_subdivisions: u32,
+    /// Snap picked points to the nearest grid intersection.
+    pub snap_to_grid: bool,
+    /// Enabled object snaps, highest priority first (empty = object snapping off).
+    pub object_snaps: Vec<ObjectSnapKind>,
+    /// Cursor distance in pixels within which an object snap engages.
+    pub snap_radius_px: f32,
+}
+
+impl Default for SnapOptions {
+    fn default() -> Self {
+        Self {
+            grid_spacing: 1.0,
+            show_grid: false,
+            grid_subdivisions: 10,
+            snap_to_grid: false,
+            object_snaps: Vec::new(),
+            snap_radius_px: 10.0,
+        }
+    }
+}
+
+/// Kinds of geometry an object snap can lock onto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectSnapKind {
+    Endpoint,
+    Midpoint,
+    Center,
+    Intersection,
 }
 
 /// Request to orient camera to a specific plane.
@@ -80,6 +242,31 @@ pub struct CameraOrientRequest {
     pub plane_up: [f32; 3],
 }
 
+/// Request for the host to show a file dialog on behalf of a workbench.
+#[derive(Debug, Clone)]
+pub struct FileOpenRequest {
+    /// Workbench-defined identifier passed back with the chosen path.
+    pub id: String,
+    /// Name of the file filter shown in the dialog (e.g. "G-code").
+    pub filter_name: String,
+    /// Accepted file extensions, without the dot.
+    pub extensions: Vec<String>,
+    /// When set, ask where to save a file (suggesting this name) instead of
+    /// picking an existing one.
+    pub save_name: Option<String>,
+    /// Pick a folder instead of a file; the filter and `save_name` are unused.
+    pub folder: bool,
+}
+
+/// Request to center the camera on a region, keeping the viewing direction.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraFocusRequest {
+    /// Point to center on (world space).
+    pub center: [f32; 3],
+    /// Approximate radius of the region to keep in view.
+    pub radius: f32,
+}
+
 impl<'a> WorkbenchRuntimeContext<'a> {
     /// Create a new runtime context.
     pub fn new(
@@ -91,20 +278,41 @@ impl<'a> WorkbenchRuntimeContext<'a> {
         Self {
             document,
             pending_logs: Vec::new(),
+            pending_commands: Vec::new(),
+            frame: [0.0; 3],
             camera_position,
             camera_target,
             viewport,
             hovered_world_pos: None,
-            hovered_body_id: None,
-            selected_body_id: None,
+            hovered_normal: None,
+            hovered: None,
+            selection: Selection::default(),
             cursor_viewport_pos: None,
             camera_orient_request: None,
+            camera_focus_request: None,
             finish_sketch_requested: false,
+            file_open_request: None,
             active_document_object: None,
+            selected_reference: None,
             view_proj: None,
+            snap: SnapOptions::default(),
+            units: UnitFormat::default(),
+            axes: AxisSystem::default(),
+            body_meshes: &[],
+            printer: None,
+            exchange: HashMap::new(),
         }
     }
 
+    /// Conventions of `format` files, the format's defaults if the host
+    /// provided none.
+    pub fn exchange_options(&self, format: ExchangeFormat) -> ExchangeOptions {
+        self.exchange
+            .get(&format)
+            .copied()
+            .unwrap_or_else(|| format.default_options())
+    }
+
     /// Log an info message to the application log panel.
     pub fn log_info(&mut self, message: impl Into<String>) {
         self.pending_logs.push(LogEntry {
@@ -129,41 +337,124 @@ impl<'a> WorkbenchRuntimeContext<'a> {
         });
     }
 
+    /// Body that single-body tools act on: the body of the most recently
+    /// selected item (see `Selection::primary_body`).
+    pub fn selected_body(&self) -> Option<BodyId> {
+        self.selection.primary_body(self.document)
+    }
+
+    /// Body under the cursor, also when a feature of the body is hovered.
+    pub fn hovered_body(&self) -> Option<BodyId> {
+        self.hovered.and_then(|item| item.body(self.document))
+    }
+
+    /// Where the origin of a body frame at `position` lies in this
+    /// context's frame; add it to that body's coordinates to draw or compare
+    /// them here.
+    pub fn frame_offset(&self, position: [f64; 3]) -> [f32; 3] {
+        crate::to_frame(position, self.frame)
+    }
+
+    /// [`Self::frame_offset`] of a body's frame (of the world origin for
+    /// features outside bodies).
+    pub fn body_offset(&self, body: Option<BodyId>) -> [f32; 3] {
+        self.frame_offset(body.map_or([0.0; 3], |body| self.document.body_position(body)))
+    }
+
+    /// A position in this context's frame (the cursor, the camera target) in
+    /// a body's frame, to store it in one of the body's features.
+    pub fn to_body(&self, body: Option<BodyId>, position: [f32; 3]) -> [f32; 3] {
+        let offset = self.body_offset(body);
+        [0, 1, 2].map(|i| position[i] - offset[i])
+    }
+
     /// Drain pending log entries (called by host after hook returns).
     pub fn drain_logs(&mut self) -> Vec<LogEntry> {
         std::mem::take(&mut self.pending_logs)
     }
 
+    /// Queue a command registered by any workbench (e.g. `"part.recompute"`).
+    /// The host runs it once the current hook returns, through the
+    /// registering workbench's `Workbench::on_command`.
+    pub fn execute_command(&mut self, id: impl Into<String>) {
+        self.pending_commands.push(id.into());
+    }
+
+    /// Drain queued commands (called by host after hook returns).
+    pub fn drain_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_commands)
+    }
+
     /// Convert a world position to viewport coordinates.
-    /// Returns None if the point is behind the camera or outside the viewport.
-    /// (Stub: actual implementation requires view-projection matrix from host.)
-    pub fn world_to_viewport(&self, _world_pos: [f32; 3]) -> Option<(f32, f32)> {
-        // TODO: Host should provide the actual transform
-        None
+    /// Returns None if the point is behind the camera or the host did not provide `view_proj`.
+    pub fn world_to_viewport(&self, world_pos: [f32; 3]) -> Option<(f32, f32)> {
+        let m = self.view_proj?;
+        // Column-major matrix (as produced by glam's `to_cols_array_2d`).
+        let clip = |row: usize| {
+            m[0][row] * world_pos[0]
+                + m[1][row] * world_pos[1]
+                + m[2][row] * world_pos[2]
+                + m[3][row]
+        };
+        let w = clip(3);
+        if w <= 0.0 {
+            return None;
+        }
+        let (ndc_x, ndc_y) = (clip(0) / w, clip(1) / w);
+        // Vulkan-style NDC: Y grows downward.
+        let width = self.viewport.2 as f32;
+        let height = self.viewport.3 as f32;
+        Some(((ndc_x + 1.0) * 0.5 * width, (ndc_y + 1.0) * 0.5 * height))
     }
 
-    /// Convert viewport coordinates to a ray in world space.
-    /// Returns (origin, direction) of the ray.
-    /// (Stub: actual implementation requires inverse view-projection from host.)
-    pub fn viewport_to_ray(&self, _viewport_pos: (f32, f32)) -> ([f32; 3], [f32; 3]) {
-        // TODO: Host should provide the actual transform
-        let origin = self.camera_position;
-        let direction = [0.0, 0.0, -1.0];
-        (origin, direction)
+    /// Convert viewport coordinates to a ray in world space, the inverse of
+    /// [`Self::world_to_viewport`]. Returns (origin, direction) of the ray,
+    /// with the direction pointing away from the camera, or None if the host
+    /// did not provide `view_proj`.
+    pub fn viewport_to_ray(&self, viewport_pos: (f32, f32)) -> Option<([f32; 3], [f32; 3])> {
+        let inverse = glam::Mat4::from_cols_array_2d(&self.view_proj?).inverse();
+        let width = self.viewport.2 as f32;
+        let height = self.viewport.3 as f32;
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+        let ndc_x = viewport_pos.0 / width * 2.0 - 1.0;
+        let ndc_y = viewport_pos.1 / height * 2.0 - 1.0;
+        // Two depths strictly inside the clip range stay finite for both
+        // regular and reversed (or infinite) depth projections.
+        let near = inverse.project_point3(glam::Vec3::new(ndc_x, ndc_y, 0.25));
+        let far = inverse.project_point3(glam::Vec3::new(ndc_x, ndc_y, 0.75));
+        let mut direction = (far - near).try_normalize()?;
+        let camera = glam::Vec3::from_array(self.camera_position);
+        if direction.dot(near - camera) < 0.0 {
+            direction = -direction;
+        }
+        // Start level with the camera, so perspective rays start at the eye.
+        let origin = near - direction * direction.dot(near - camera);
+        Some((origin.to_array(), direction.to_array()))
     }
 
     /// Convert viewport coordinates to a point on a plane in world space.
-    /// Returns the intersection point of the camera ray with the plane.
-    /// (Stub: actual implementation requires inverse view-projection from host.)
+    /// Returns the intersection point of the camera ray with the plane, or
+    /// None if the ray is parallel to the plane or the plane is behind it.
     pub fn viewport_to_plane(
         &self,
-        _viewport_pos: (f32, f32),
-        _plane_origin: [f32; 3],
-        _plane_normal: [f32; 3],
+        viewport_pos: (f32, f32),
+        plane_origin: [f32; 3],
+        plane_normal: [f32; 3],
     ) -> Option<[f32; 3]> {
-        // TODO: Host should provide the actual transform
-        // For now, return None - host will need to implement this
-        None
+        let (origin, direction) = self.viewport_to_ray(viewport_pos)?;
+        let (origin, direction) = (glam::Vec3::from(origin), glam::Vec3::from(direction));
+        let normal = glam::Vec3::from(plane_normal);
+        let denom = direction.dot(normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = (glam::Vec3::from(plane_origin) - origin).dot(normal) / denom;
+        if t < 0.0 {
+            return None;
+        }
+        Some((origin + direction * t).to_array())
     }
 }
 
@@ -180,6 +471,13 @@ pub enum WorkbenchInputEvent {
         button: MouseButton,
         viewport_pos: (f32, f32),
     },
+    /// Second press of a button shortly after the first, at about the same
+    /// place. Sent before that second `MousePress`, which is skipped when the
+    /// double click is consumed.
+    MouseDoubleClick {
+        button: MouseButton,
+        viewport_pos: (f32, f32),
+    },
     /// Mouse moved.
     MouseMove { viewport_pos: (f32, f32) },
     /// Key pressed.
@@ -272,6 +570,9 @@ pub struct InputResult {
     pub consumed: bool,
     /// If true, the viewport should be redrawn.
     pub redraw: bool,
+    /// If true, the active tool completed an element (e.g. the second click of
+    /// a line). Unless tools are sticky, the host then deactivates it.
+    pub finished: bool,
 }
 
 impl InputResult {
@@ -279,6 +580,15 @@ impl InputResult {
         Self {
             consumed: true,
             redraw: true,
+            finished: false,
+        }
+    }
+
+    /// Consumed, and the active tool completed an element.
+    pub fn finished() -> Self {
+        Self {
+            finished: true,
+            ..Self::consumed()
         }
     }
 
@@ -290,6 +600,7 @@ impl InputResult {
         Self {
             consumed: false,
             redraw: true,
+            finished: false,
         }
     }
 }
@@ -0,0 +1,158 @@
+//! Selection set shared between the host and workbenches.
+//!
+//! The host fills it from viewport clicks and the model tree; workbenches read
+//! it (and may change it) through `WorkbenchRuntimeContext::selection`.
+
+use crate::{BodyId, Document, FeatureId};
+
+/// A face, edge, or vertex of a body, by its index in the body's topology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubElement {
+    Face(u32),
+    Edge(u32),
+    Vertex(u32),
+}
+
+/// Something the user can select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionItem {
+    Body(BodyId),
+    Feature(FeatureId),
+    /// Part of a body's geometry.
+    Element {
+        body: BodyId,
+        element: SubElement,
+    },
+}
+
+impl SelectionItem {
+    /// Body the item belongs to (features are looked up in `document`).
+    pub fn body(&self, document: &Document) -> Option<BodyId> {
+        match *self {
+            SelectionItem::Body(body) | SelectionItem::Element { body, .. } => Some(body),
+            SelectionItem::Feature(feature) => document
+                .get_feature_meta(feature)
+                .and_then(|node| node.body),
+        }
+    }
+}
+
+/// Ordered set of selected items, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    items: Vec<SelectionItem>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A selection holding only `item`.
+    pub fn single(item: SelectionItem) -> Self {
+        Self { items: vec![item] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Selected items in the order they were selected.
+    pub fn items(&self) -> &[SelectionItem] {
+        &self.items
+    }
+
+    pub fn contains(&self, item: SelectionItem) -> bool {
+        self.items.contains(&item)
+    }
+
+    /// Most recently selected item.
+    pub fn last(&self) -> Option<SelectionItem> {
+        self.items.last().copied()
+    }
+
+    /// Add `item`; returns false if it was already selected.
+    pub fn add(&mut self, item: SelectionItem) -> bool {
+        if self.contains(item) {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    /// Remove `item`; returns false if it was not selected.
+    pub fn remove(&mut self, item: SelectionItem) -> bool {
+        let before = self.items.len();
+        self.items.retain(|selected| *selected != item);
+        self.items.len() != before
+    }
+
+    /// Select `item` if it is not selected, deselect it otherwise. Returns
+    /// whether it is selected afterwards.
+    pub fn toggle(&mut self, item: SelectionItem) -> bool {
+        if self.remove(item) {
+            false
+        } else {
+            self.items.push(item);
+            true
+        }
+    }
+
+    /// Replace the selection with `item`.
+    pub fn set(&mut self, item: SelectionItem) {
+        self.items.clear();
+        self.items.push(item);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Keep only the items for which `keep` returns true.
+    pub fn retain(&mut self, keep: impl FnMut(&SelectionItem) -> bool) {
+        self.items.retain(keep);
+    }
+
+    /// Selected bodies, in selection order.
+    pub fn bodies(&self) -> impl Iterator<Item = BodyId> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            SelectionItem::Body(body) => Some(*body),
+            _ => None,
+        })
+    }
+
+    /// Selected features, in selection order.
+    pub fn features(&self) -> impl Iterator<Item = FeatureId> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            SelectionItem::Feature(feature) => Some(*feature),
+            _ => None,
+        })
+    }
+
+    /// Selected faces, edges, and vertices, in selection order.
+    pub fn elements(&self) -> impl Iterator<Item = (BodyId, SubElement)> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            SelectionItem::Element { body, element } => Some((*body, *element)),
+            _ => None,
+        })
+    }
+
+    /// Body of the most recently selected item that belongs to one: the body
+    /// tools act on when they need a single body.
+    pub fn primary_body(&self, document: &Document) -> Option<BodyId> {
+        self.items.iter().rev().find_map(|item| item.body(document))
+    }
+
+    /// Drop items whose body or feature no longer exists in `document`.
+    pub fn prune(&mut self, document: &Document) {
+        let body_exists = |id: BodyId| document.bodies().iter().any(|body| body.id == id);
+        self.items.retain(|item| match *item {
+            SelectionItem::Body(body) | SelectionItem::Element { body, .. } => body_exists(body),
+            SelectionItem::Feature(feature) => document.get_feature_meta(feature).is_some(),
+        });
+    }
+}
@@ -0,0 +1,47 @@
+//! Read-only views of a document at one point in time.
+//!
+//! Feature nodes are shared between a document and its snapshots and only
+//! copied when the document writes to one of them, so taking a snapshot costs
+//! a pointer per feature however large the features are (imported meshes).
+//! The viewport assembles each frame, and resolves what was picked in it, from
+//! the snapshot the frame was drawn with, so edits the UI makes halfway through
+//! a frame show up consistently in the next one. Snapshots are `Send + Sync`
+//! and can be handed to worker threads.
+
+use crate::{Body, BodyId, Document, FeatureId, FeatureNode, FeatureTree};
+
+/// Features and bodies of a document, frozen when the snapshot was taken.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentSnapshot {
+    feature_tree: FeatureTree,
+    bodies: Vec<Body>,
+}
+
+impl DocumentSnapshot {
+    pub fn feature_tree(&self) -> &FeatureTree {
+        &self.feature_tree
+    }
+
+    pub fn get_feature_meta(&self, id: FeatureId) -> Option<&FeatureNode> {
+        self.feature_tree.get_node(id)
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    pub fn has_body(&self, id: BodyId) -> bool {
+        self.bodies.iter().any(|body| body.id == id)
+    }
+}
+
+impl Document {
+    /// Snapshot of the current features and bodies; later edits to the
+    /// document don't change it.
+    pub fn snapshot(&self) -> DocumentSnapshot {
+        DocumentSnapshot {
+            feature_tree: self.feature_tree.clone(),
+            bodies: self.bodies.clone(),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Task panels: the form a tool shows while it runs.
+//!
+//! A workbench with a task in progress (e.g. a revolve being set up, or the
+//! options of a pending import) describes it through `Workbench::active_task`.
+//! The host then shows the task's form, drawn by `Workbench::ui_task`, in place
+//! of the workbench's right panel, with OK, Apply, and Cancel buttons below it,
+//! and reports the button pressed through `Workbench::on_task_action`. The
+//! panel is modal only for the workbench: the viewport and the model tree stay
+//! interactive, so the form can follow what is picked while it is open.
+
+/// The task a workbench has open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskPanel {
+    /// Heading shown above the form.
+    pub title: String,
+    /// Label of the button completing the task.
+    pub ok_label: String,
+    /// Whether an Apply button commits the form without closing the task.
+    pub apply: bool,
+    /// Whether OK and Apply can be pressed (e.g. false until the form is complete).
+    pub enabled: bool,
+}
+
+impl TaskPanel {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ok_label: "OK".to_string(),
+            apply: false,
+            enabled: true,
+        }
+    }
+
+    pub fn with_ok_label(mut self, label: impl Into<String>) -> Self {
+        self.ok_label = label.into();
+        self
+    }
+
+    pub fn with_apply(mut self) -> Self {
+        self.apply = true;
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// Button pressed in a task panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskAction {
+    /// Commit the form and close the task.
+    Ok,
+    /// Commit the form and keep the task open.
+    Apply,
+    /// Close the task without committing the form.
+    Cancel,
+}
@@ -0,0 +1,74 @@
+//! Meshes computed from a document (feature results and body tessellations),
+//! saved in the archive under `cache/` so an opened document can be drawn
+//! right away instead of after a full rebuild.
+//!
+//! Each mesh is stored with the content hash of what it was computed from
+//! (see [`Document::content_hash`](crate::Document::content_hash)); a mesh
+//! whose hash no longer matches is stale and ignored.
+
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A cached mesh and the content hash it was computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMesh {
+    pub hash: u64,
+    pub mesh: Arc<TriMesh>,
+}
+
+/// Cached meshes keyed by the feature or body they belong to. Copies of the
+/// document share the meshes.
+#[derive(Debug, Clone, Default)]
+pub struct TessellationCache {
+    meshes: HashMap<Uuid, CachedMesh>,
+}
+
+impl TessellationCache {
+    /// Mesh of `id`, if it was cached from content hashing to `hash`.
+    pub fn get(&self, id: Uuid, hash: u64) -> Option<&Arc<TriMesh>> {
+        self.meshes
+            .get(&id)
+            .filter(|cached| cached.hash == hash)
+            .map(|cached| &cached.mesh)
+    }
+
+    pub fn insert(&mut self, id: Uuid, hash: u64, mesh: Arc<TriMesh>) {
+        self.meshes.insert(id, CachedMesh { hash, mesh });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Uuid, &CachedMesh)> {
+        self.meshes.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+}
+
+/// FNV-1a, whose output doesn't change between runs or Rust versions (unlike
+/// the standard library's hasher), so hashes can be saved with the document.
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Axis and unit remapping for importing and exporting geometry files.
+//!
+//! Files carry their own conventions: slicers and most CAD formats are Z-up millimetres, while
+//! DCC tools and glTF are Y-up (glTF in metres). Importers and exporters convert through the
+//! canonical right/up/forward frame of [`AxisSystem`] so a model keeps its orientation relative
+//! to "up" regardless of the document's convention.
+
+use axes::{AxisPreset, AxisSystem};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use units::LengthUnit;
+
+use crate::TriMesh;
+
+/// File formats that support axis/unit remapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExchangeFormat {
+    Stl,
+    Step,
+    Obj,
+    Ply,
+    Gltf,
+    Usd,
+    Amf,
+}
+
+impl ExchangeFormat {
+    pub const ALL: [ExchangeFormat; 7] = [
+        ExchangeFormat::Stl,
+        ExchangeFormat::Step,
+        ExchangeFormat::Obj,
+        ExchangeFormat::Ply,
+        ExchangeFormat::Gltf,
+        ExchangeFormat::Usd,
+        ExchangeFormat::Amf,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            ExchangeFormat::Stl => "STL",
+            ExchangeFormat::Step => "STEP",
+            ExchangeFormat::Obj => "OBJ",
+            ExchangeFormat::Ply => "PLY",
+            ExchangeFormat::Gltf => "glTF",
+            ExchangeFormat::Usd => "USD",
+            ExchangeFormat::Amf => "AMF",
+        }
+    }
+
+    /// Conventions files of this format usually follow.
+    pub const fn default_options(self) -> ExchangeOptions {
+        match self {
+            ExchangeFormat::Stl | ExchangeFormat::Step | ExchangeFormat::Amf => ExchangeOptions {
+                file_axes: AxisPreset::ZUpRightHanded.axis_system(),
+                file_unit: LengthUnit::Millimeter,
+                scale: 1.0,
+            },
+            ExchangeFormat::Obj | ExchangeFormat::Ply | ExchangeFormat::Usd => ExchangeOptions {
+                file_axes: AxisPreset::RightHandedZBackward.axis_system(),
+                file_unit: LengthUnit::Millimeter,
+                scale: 1.0,
+            },
+            ExchangeFormat::Gltf => ExchangeOptions {
+                file_axes: AxisPreset::RightHandedZBackward.axis_system(),
+                file_unit: LengthUnit::Meter,
+                scale: 1.0,
+            },
+        }
+    }
+}
+
+/// How coordinates in an exchanged file relate to the document.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeOptions {
+    /// Axis convention of the file.
+    pub file_axes: AxisSystem,
+    /// Length unit of the file's coordinates (documents are always millimetres).
+    pub file_unit: LengthUnit,
+    /// Factor imported geometry is scaled by on top of the unit conversion
+    /// (exports divide by it), for models drawn at a scale.
+    #[serde(default = "unit_scale")]
+    pub scale: f32,
+}
+
+fn unit_scale() -> f32 {
+    1.0
+}
+
+impl ExchangeOptions {
+    /// Map a point read from a file into document coordinates.
+    pub fn import_point(&self, point: [f32; 3], document_axes: AxisSystem) -> [f32; 3] {
+        let canonical = self.file_axes.world_to_canonical(Vec3::from(point));
+        let world = document_axes.canonical_to_world(canonical);
+        (world * self.file_unit.mm_per_unit() * self.scale).to_array()
+    }
+
+    /// Map an offset in the file's coordinates into the document, in double
+    /// precision (the mapping has no translation, so this also maps points).
+    pub fn import_offset(&self, offset: [f64; 3], document_axes: AxisSystem) -> [f64; 3] {
+        let columns = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            .map(|axis| self.import_point(axis, document_axes).map(f64::from));
+        [0, 1, 2].map(|i| (0..3).map(|j| columns[j][i] * offset[j]).sum())
+    }
+
+    /// Map a document point into the file's coordinates.
+    pub fn export_point(&self, point: [f32; 3], document_axes: AxisSystem) -> [f32; 3] {
+        let canonical = document_axes.world_to_canonical(Vec3::from(point));
+        let file = self.file_axes.canonical_to_world(canonical);
+        (file / (self.file_unit.mm_per_unit() * self.scale)).to_array()
+    }
+
+    /// Remap an imported mesh into document coordinates in place.
+    pub fn import_mesh(&self, mesh: &mut TriMesh, document_axes: AxisSystem) {
+        for position in &mut mesh.positions {
+            *position = self.import_point(*position, document_axes);
+        }
+        for normal in &mut mesh.normals {
+            let canonical = self.file_axes.world_to_canonical(Vec3::from(*normal));
+            *normal = document_axes.canonical_to_world(canonical).to_array();
+        }
+        if self.mirrors(document_axes) {
+            flip_winding(mesh);
+        }
+    }
+
+    /// Remap a document mesh into the file's coordinates in place.
+    pub fn export_mesh(&self, mesh: &mut TriMesh, document_axes: AxisSystem) {
+        for position in &mut mesh.positions {
+            *position = self.export_point(*position, document_axes);
+        }
+        for normal in &mut mesh.normals {
+            let canonical = document_axes.world_to_canonical(Vec3::from(*normal));
+            *normal = self.file_axes.canonical_to_world(canonical).to_array();
+        }
+        if self.mirrors(document_axes) {
+            flip_winding(mesh);
+        }
+    }
+
+    /// True if converting between the two conventions mirrors geometry (their handedness
+    /// differs), which requires reversing triangle winding to keep faces pointing outwards.
+    pub fn mirrors(&self, document_axes: AxisSystem) -> bool {
+        self.file_axes.handedness() != document_axes.handedness()
+    }
+}
+
+fn flip_winding(mesh: &mut TriMesh) {
+    for triangle in mesh.indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+}
@@ -1,6 +1,14 @@
+pub mod exchange;
+pub mod step;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub use exchange::{ExchangeFormat, ExchangeOptions};
+
 /// Convenience alias for kernel fallible operations.
 pub type KernelResult<T> = Result<T, KernelError>;
 
@@ -17,17 +25,93 @@ pub struct RebuildRequest {
     pub propagate: bool,
 }
 
+/// Shared flag asking a running rebuild to stop.
+///
+/// Clones share the flag: the UI keeps one to cancel from, the kernel checks
+/// another between operations (one boolean, one fillet, …). A single operation
+/// is not interrupted, so cancelling takes effect once the current one ends.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(KernelError::Cancelled)` once cancelled; call between operations.
+    pub fn check(&self) -> KernelResult<()> {
+        if self.is_cancelled() {
+            Err(KernelError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Response returned for every rebuild invocation.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RebuildResponse {
     /// Bodies that were modified or regenerated.
     pub updated_bodies: Vec<BodyHandle>,
     /// Kernel provided diagnostics or warnings.
-    pub diagnostics: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DiagnosticSeverity {
+    Info,
+    /// The feature was computed, but the result may not be what the user expects.
+    Warning,
+    /// The feature could not be computed.
+    Error,
+}
+
+/// Message produced while recomputing, optionally tied to a feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Identifier of the feature the message is about (as in `RebuildRequest::dirty_features`).
+    pub feature: Option<String>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        feature: Option<String>,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            feature,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(None, DiagnosticSeverity::Info, message)
+    }
+
+    pub fn warning(feature: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Some(feature.into()), DiagnosticSeverity::Warning, message)
+    }
+
+    pub fn error(feature: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Some(feature.into()), DiagnosticSeverity::Error, message)
+    }
 }
 
 /// Parameters controlling tessellation quality for viewport rendering.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TessellationSettings {
     pub chord_tolerance: f32,
     pub angular_tolerance_deg: f32,
@@ -50,7 +134,77 @@ pub struct TriMesh {
     pub indices: Vec<u32>,
 }
 
+impl TriMesh {
+    /// Append another mesh's triangles to this one.
+    pub fn append(&mut self, other: &TriMesh) {
+        let offset = self.positions.len() as u32;
+        self.positions.extend_from_slice(&other.positions);
+        self.normals.extend_from_slice(&other.normals);
+        self.indices
+            .extend(other.indices.iter().map(|index| index + offset));
+    }
+
+    /// Axis-aligned bounds as (minimum, maximum) corner; None when empty.
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let (first, rest) = self.positions.split_first()?;
+        Some(rest.iter().fold((*first, *first), |(mut min, mut max), p| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+            (min, max)
+        }))
+    }
+
+    /// Express the mesh relative to a frame at `origin`, taking each
+    /// difference in double precision.
+    pub fn to_frame(&mut self, origin: [f64; 3]) {
+        for position in &mut self.positions {
+            *position = [0, 1, 2].map(|i| (position[i] as f64 - origin[i]) as f32);
+        }
+    }
+
+    /// Express the mesh, relative to a frame at `origin`, in world
+    /// coordinates, taking each sum in double precision.
+    pub fn to_world(&mut self, origin: [f64; 3]) {
+        for position in &mut self.positions {
+            *position = [0, 1, 2].map(|i| (position[i] as f64 + origin[i]) as f32);
+        }
+    }
+}
+
+/// Planar region for sweeps: closed loops of points in world space, the last
+/// point joining the first. Loops inside an odd number of others are holes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub loops: Vec<Vec<[f32; 3]>>,
+}
+
+/// Line in world space: a point on it and its direction.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Axis {
+    pub origin: [f32; 3],
+    pub direction: [f32; 3],
+}
+
+/// How [`Kernel::boolean`] combines two bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BooleanOp {
+    /// Material of either body.
+    Union,
+    /// Material of the target that is not in the tool.
+    Difference,
+    /// Material of both bodies.
+    Intersection,
+}
+
 /// Trait implemented by any geometry kernel that can serve the application.
+///
+/// Besides the mesh-producing sweeps, kernels with a B-Rep model keep solids
+/// by [`BodyHandle`]: the operations below create new bodies from a profile or
+/// from existing bodies (leaving those as they are), [`Kernel::tessellate`]
+/// meshes them, and [`Kernel::release`] frees them. Handles are never reused
+/// within a session.
 pub trait Kernel: Send {
     /// Human-friendly identifier for logging purposes.
     fn name(&self) -> &str;
@@ -59,10 +213,81 @@ pub trait Kernel: Send {
     fn initialize(&mut self) -> KernelResult<()>;
 
     /// Recompute dirty features/bodies and return the affected handles.
-    fn rebuild(&mut self, request: &RebuildRequest) -> KernelResult<RebuildResponse>;
+    ///
+    /// Implementations check `cancel` between operations and return
+    /// [`KernelError::Cancelled`] when it is set, leaving the bodies as they
+    /// were before the rebuild.
+    fn rebuild(
+        &mut self,
+        request: &RebuildRequest,
+        cancel: &CancellationToken,
+    ) -> KernelResult<RebuildResponse>;
 
     /// Produce a triangular mesh for the provided body handle.
     fn tessellate(&self, body: BodyHandle, detail: &TessellationSettings) -> KernelResult<TriMesh>;
+
+    /// Solid swept by turning `profile` around `axis` by `angle_deg` degrees
+    /// (360 for a full turn), counterclockwise looking against the axis
+    /// direction, tessellated with `detail`. The axis lies in the profile's
+    /// plane without crossing it.
+    fn revolve(
+        &mut self,
+        _profile: &Profile,
+        _axis: &Axis,
+        _angle_deg: f32,
+        _detail: &TessellationSettings,
+    ) -> KernelResult<TriMesh> {
+        Err(KernelError::Unsupported(format!(
+            "{} cannot revolve profiles",
+            self.name()
+        )))
+    }
+
+    /// Solid swept by moving `profile` along `direction` (its length is the
+    /// distance).
+    fn extrude(&mut self, _profile: &Profile, _direction: [f32; 3]) -> KernelResult<BodyHandle> {
+        Err(KernelError::Unsupported(format!(
+            "{} cannot extrude profiles",
+            self.name()
+        )))
+    }
+
+    /// Solid body of [`Kernel::revolve`], kept instead of tessellated.
+    fn revolve_body(
+        &mut self,
+        _profile: &Profile,
+        _axis: &Axis,
+        _angle_deg: f32,
+    ) -> KernelResult<BodyHandle> {
+        Err(KernelError::Unsupported(format!(
+            "{} cannot keep revolved bodies",
+            self.name()
+        )))
+    }
+
+    /// Body combining `target` with `tool`.
+    fn boolean(
+        &mut self,
+        _op: BooleanOp,
+        _target: BodyHandle,
+        _tool: BodyHandle,
+    ) -> KernelResult<BodyHandle> {
+        Err(KernelError::Unsupported(format!(
+            "{} cannot combine bodies",
+            self.name()
+        )))
+    }
+
+    /// Copy of `body` with every sharp edge rounded to `radius`.
+    fn fillet(&mut self, _body: BodyHandle, _radius: f32) -> KernelResult<BodyHandle> {
+        Err(KernelError::Unsupported(format!(
+            "{} cannot fillet edges",
+            self.name()
+        )))
+    }
+
+    /// Forget a body; its handle is invalid afterwards.
+    fn release(&mut self, _body: BodyHandle) {}
 }
 
 /// Standardized error type for kernel interactions.
@@ -76,6 +301,8 @@ pub enum KernelError {
     Unsupported(String),
     #[error("invalid kernel input: {0}")]
     InvalidInput(String),
+    #[error("operation cancelled")]
+    Cancelled,
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
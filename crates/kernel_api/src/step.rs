@@ -0,0 +1,833 @@
+//! Product structure of STEP files: which parts an assembly is made of, and
+//! where each occurrence of a part sits.
+//!
+//! Only the structure is read here (ISO 10303-21 entities for products,
+//! assembly usages, and the placements relating them). Part geometry is
+//! B-rep and comes from the kernel, except for faceted geometry (polygon
+//! faces and triangulated face sets), which is read and written here so
+//! meshes can be exchanged with other CAD tools as STEP text.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use glam::{Mat4, Vec3};
+use thiserror::Error;
+
+use crate::TriMesh;
+
+/// Assemblies nested deeper than this are cut off (files can loop).
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum StepError {
+    #[error("not a STEP file (no DATA section)")]
+    NotStep,
+    #[error("entity #{id}: {message}")]
+    Parse { id: u64, message: String },
+    #[error("file contains no products")]
+    Empty,
+    #[error("file contains no faceted geometry")]
+    NoFacets,
+}
+
+/// A product: a single part, or an assembly of components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepProduct {
+    pub name: String,
+    pub components: Vec<StepComponent>,
+}
+
+/// One occurrence of a product inside an assembly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepComponent {
+    /// Occurrence name, often the product name followed by an instance number.
+    pub name: String,
+    /// Placement in the assembly's coordinates, in file units.
+    pub placement: Mat4,
+    pub product: StepProduct,
+}
+
+/// A part occurrence in the whole structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepPart {
+    /// Names from the top-level product down to the occurrence.
+    pub path: Vec<String>,
+    /// Name of the part's product.
+    pub product: String,
+    /// Placement in the top-level product's coordinates, in file units.
+    pub placement: Mat4,
+}
+
+/// Top-level products of a file (usually one).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepAssembly {
+    pub roots: Vec<StepProduct>,
+}
+
+impl StepAssembly {
+    /// Every occurrence of a product that has no components, depth first.
+    pub fn parts(&self) -> Vec<StepPart> {
+        fn visit(
+            product: &StepProduct,
+            path: &mut Vec<String>,
+            placement: Mat4,
+            parts: &mut Vec<StepPart>,
+        ) {
+            if product.components.is_empty() {
+                parts.push(StepPart {
+                    path: path.clone(),
+                    product: product.name.clone(),
+                    placement,
+                });
+            }
+            for component in &product.components {
+                path.push(component.name.clone());
+                visit(
+                    &component.product,
+                    path,
+                    placement * component.placement,
+                    parts,
+                );
+                path.pop();
+            }
+        }
+        let mut parts = Vec::new();
+        for root in &self.roots {
+            visit(
+                root,
+                &mut vec![root.name.clone()],
+                Mat4::IDENTITY,
+                &mut parts,
+            );
+        }
+        parts
+    }
+}
+
+/// Read the product structure of a STEP file's text.
+pub fn read_assembly(text: &str) -> Result<StepAssembly, StepError> {
+    let entities = parse_entities(text)?;
+    let mut structure = Structure {
+        entities: &entities,
+        placements: HashMap::new(),
+    };
+    structure.placements = structure.placements()?;
+
+    let definitions: Vec<u64> = structure.ids("PRODUCT_DEFINITION");
+    let usages: Vec<u64> = structure.ids("NEXT_ASSEMBLY_USAGE_OCCURRENCE");
+    let used: HashSet<u64> = usages
+        .iter()
+        .filter_map(|usage| structure.reference(*usage, "NEXT_ASSEMBLY_USAGE_OCCURRENCE", 4))
+        .collect();
+    let roots: Vec<StepProduct> = definitions
+        .iter()
+        .filter(|definition| !used.contains(definition))
+        .map(|definition| structure.product(*definition, &usages, 0))
+        .collect::<Result<_, _>>()?;
+    if roots.is_empty() {
+        return Err(StepError::Empty);
+    }
+    Ok(StepAssembly { roots })
+}
+
+/// Faceted geometry of a STEP file's text as one mesh (without normals), in
+/// the file's coordinates: faces bounded by polygons (as in faceted B-reps)
+/// and triangulated faces and surface sets (AP242 tessellated geometry). Polygons are
+/// split into fans and holes in faces are left out; curved B-rep faces are
+/// skipped.
+pub fn read_facets(text: &str) -> Result<TriMesh, StepError> {
+    let entities = parse_entities(text)?;
+    let structure = Structure {
+        entities: &entities,
+        placements: HashMap::new(),
+    };
+    let mut mesh = TriMesh::default();
+    let mut points: HashMap<u64, u32> = HashMap::new();
+
+    // Bounds listed after a face's outer bound are holes.
+    let mut holes = HashSet::new();
+    for records in entities.values() {
+        for (_, values) in records {
+            let Some(Value::List(bounds)) = values.get(1) else {
+                continue;
+            };
+            let is_outer = |value: &Value| matches!(value, Value::Reference(id) if structure.record(*id, "FACE_OUTER_BOUND").is_some());
+            if bounds.iter().any(is_outer) {
+                holes.extend(bounds.iter().filter_map(|value| match value {
+                    Value::Reference(id) if !is_outer(value) => Some(*id),
+                    _ => None,
+                }));
+            }
+        }
+    }
+    let mut bounds = structure.ids("FACE_OUTER_BOUND");
+    bounds.extend(
+        structure
+            .ids("FACE_BOUND")
+            .into_iter()
+            .filter(|id| !holes.contains(id)),
+    );
+    bounds.sort_unstable();
+    for bound in bounds {
+        let values = structure
+            .record(bound, "FACE_OUTER_BOUND")
+            .or_else(|| structure.record(bound, "FACE_BOUND"))
+            .unwrap_or_default();
+        let Some(Value::Reference(polygon)) = values.get(1) else {
+            continue;
+        };
+        let Some(Value::List(corners)) = structure
+            .record(*polygon, "POLY_LOOP")
+            .and_then(|values| values.get(1))
+        else {
+            continue;
+        };
+        let mut loop_indices = Vec::with_capacity(corners.len());
+        for corner in corners {
+            let Value::Reference(point) = corner else {
+                continue;
+            };
+            let index = match points.get(point) {
+                Some(index) => *index,
+                None => {
+                    let position =
+                        structure.vector(*point, "CARTESIAN_POINT").ok_or_else(|| {
+                            StepError::Parse {
+                                id: *point,
+                                message: "polygon corner is not a point".to_string(),
+                            }
+                        })?;
+                    mesh.positions.push(position.to_array());
+                    let index = mesh.positions.len() as u32 - 1;
+                    points.insert(*point, index);
+                    index
+                }
+            };
+            loop_indices.push(index);
+        }
+        // `.F.` runs the loop against the face's direction.
+        if values.get(2) == Some(&Value::Enumeration("F".to_string())) {
+            loop_indices.reverse();
+        }
+        for i in 1..loop_indices.len().saturating_sub(1) {
+            mesh.indices
+                .extend([loop_indices[0], loop_indices[i], loop_indices[i + 1]]);
+        }
+    }
+
+    for kind in ["TRIANGULATED_FACE", "TRIANGULATED_SURFACE_SET"] {
+        for id in structure.ids(kind) {
+            structure.triangulated_set(id, kind, &mut mesh)?;
+        }
+    }
+    if mesh.indices.is_empty() {
+        return Err(StepError::NoFacets);
+    }
+    Ok(mesh)
+}
+
+/// A STEP file (AP214) with each named mesh as a part holding a faceted
+/// B-rep of its triangles, in millimetres.
+pub fn write_faceted(bodies: &[(&str, &TriMesh)]) -> String {
+    let mut writer = Writer::default();
+    let context = writer.entity("APPLICATION_CONTEXT('automotive design')".to_string());
+    writer.entity(format!(
+        "APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{context})"
+    ));
+    let product_context = writer.entity(format!("PRODUCT_CONTEXT('',#{context},'mechanical')"));
+    let definition_context = writer.entity(format!(
+        "PRODUCT_DEFINITION_CONTEXT('part definition',#{context},'design')"
+    ));
+    let length = writer.entity("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))".to_string());
+    let angle = writer.entity("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))".to_string());
+    let solid_angle =
+        writer.entity("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())".to_string());
+    let uncertainty = writer.entity(format!(
+        "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-03),#{length},'distance_accuracy_value','')"
+    ));
+    let geometry = writer.entity(format!(
+        "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{uncertainty}))\
+GLOBAL_UNIT_ASSIGNED_CONTEXT((#{length},#{angle},#{solid_angle}))REPRESENTATION_CONTEXT('',''))"
+    ));
+    let origin = writer.point(Vec3::ZERO);
+    let z = writer.direction(Vec3::Z);
+    let x = writer.direction(Vec3::X);
+    let placement = writer.entity(format!("AXIS2_PLACEMENT_3D('',#{origin},#{z},#{x})"));
+
+    for (name, mesh) in bodies {
+        let name = escape(name);
+        let product = writer.entity(format!(
+            "PRODUCT('{name}','{name}','',(#{product_context}))"
+        ));
+        let formation = writer.entity(format!("PRODUCT_DEFINITION_FORMATION('','',#{product})"));
+        let definition = writer.entity(format!(
+            "PRODUCT_DEFINITION('design','',#{formation},#{definition_context})"
+        ));
+        let shape = writer.entity(format!("PRODUCT_DEFINITION_SHAPE('','',#{definition})"));
+
+        let points: Vec<u64> = mesh
+            .positions
+            .iter()
+            .map(|position| writer.point(Vec3::from(*position)))
+            .collect();
+        let mut faces = Vec::with_capacity(mesh.indices.len() / 3);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(mesh.positions[triangle[i] as usize]));
+            let normal = (b - a).cross(c - a);
+            let (Some(normal), Some(along)) = (normal.try_normalize(), (b - a).try_normalize())
+            else {
+                continue; // Degenerate triangles have no plane.
+            };
+            let [a, b, c] = [0, 1, 2].map(|i| points[triangle[i] as usize]);
+            let polygon = writer.entity(format!("POLY_LOOP('',(#{a},#{b},#{c}))"));
+            let bound = writer.entity(format!("FACE_OUTER_BOUND('',#{polygon},.T.)"));
+            let normal = writer.direction(normal);
+            let along = writer.direction(along);
+            let axis = writer.entity(format!("AXIS2_PLACEMENT_3D('',#{a},#{normal},#{along})"));
+            let plane = writer.entity(format!("PLANE('',#{axis})"));
+            faces.push(writer.entity(format!("FACE_SURFACE('',(#{bound}),#{plane},.T.)")));
+        }
+        let shell = writer.entity(format!("CLOSED_SHELL('',({}))", references(&faces)));
+        let brep = writer.entity(format!("FACETED_BREP('{name}',#{shell})"));
+        let representation = writer.entity(format!(
+            "FACETED_BREP_SHAPE_REPRESENTATION('{name}',(#{brep},#{placement}),#{geometry})"
+        ));
+        writer.entity(format!(
+            "SHAPE_DEFINITION_REPRESENTATION(#{shape},#{representation})"
+        ));
+    }
+
+    let mut out = String::new();
+    out.push_str("ISO-10303-21;\nHEADER;\n");
+    out.push_str("FILE_DESCRIPTION(('faceted geometry'),'2;1');\n");
+    out.push_str("FILE_NAME('','',(''),(''),'printCAD','printCAD','');\n");
+    out.push_str("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));\n");
+    out.push_str("ENDSEC;\nDATA;\n");
+    out.push_str(&writer.data);
+    out.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+    out
+}
+
+/// Entities written so far, numbered from 1.
+#[derive(Default)]
+struct Writer {
+    data: String,
+    next: u64,
+}
+
+impl Writer {
+    /// Write `#id=record;` and return the id.
+    fn entity(&mut self, record: String) -> u64 {
+        self.next += 1;
+        let _ = writeln!(self.data, "#{}={record};", self.next);
+        self.next
+    }
+
+    fn point(&mut self, point: Vec3) -> u64 {
+        self.entity(format!("CARTESIAN_POINT('',{})", coordinates(point)))
+    }
+
+    fn direction(&mut self, direction: Vec3) -> u64 {
+        self.entity(format!("DIRECTION('',{})", coordinates(direction)))
+    }
+}
+
+fn coordinates(vector: Vec3) -> String {
+    let [x, y, z] = vector.to_array().map(real);
+    format!("({x},{y},{z})")
+}
+
+/// A real as STEP writes it: always with a decimal point (`1.`, `1.5E-07`).
+fn real(value: f32) -> String {
+    let text = format!("{value:?}").to_uppercase();
+    match text.split_once('E') {
+        Some((mantissa, exponent)) if !mantissa.contains('.') => format!("{mantissa}.E{exponent}"),
+        _ => text,
+    }
+}
+
+fn references(ids: &[u64]) -> String {
+    ids.iter()
+        .map(|id| format!("#{id}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Text for a quoted STEP string: quotes and backslashes doubled, and
+/// characters outside printable ASCII replaced.
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\'' => "''".to_string(),
+            '\\' => "\\\\".to_string(),
+            ' '..='~' => c.to_string(),
+            _ => "_".to_string(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Reference(u64),
+    Text(String),
+    Number(f64),
+    /// `.T.`, `.MILLI.`, and other enumeration values.
+    Enumeration(String),
+    List(Vec<Value>),
+    /// A typed value such as `LENGTH_MEASURE(1.0)`.
+    Typed(String, Vec<Value>),
+    /// `$` or `*`.
+    Unset,
+}
+
+/// Entity records by id: one per type, several for complex entities.
+type Entities = HashMap<u64, Vec<(String, Vec<Value>)>>;
+
+struct Structure<'a> {
+    entities: &'a Entities,
+    /// Placement of each assembly usage that has one.
+    placements: HashMap<u64, Mat4>,
+}
+
+impl Structure<'_> {
+    /// Ids of the entities with a record of `kind`, in file order.
+    fn ids(&self, kind: &str) -> Vec<u64> {
+        let mut ids: Vec<u64> = self
+            .entities
+            .iter()
+            .filter(|(_, records)| records.iter().any(|(name, _)| name == kind))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn record(&self, id: u64, kind: &str) -> Option<&[Value]> {
+        self.entities
+            .get(&id)?
+            .iter()
+            .find(|(name, _)| name == kind)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    fn reference(&self, id: u64, kind: &str, index: usize) -> Option<u64> {
+        match self.record(id, kind)?.get(index)? {
+            Value::Reference(target) => Some(*target),
+            _ => None,
+        }
+    }
+
+    /// Name of the product a product definition is of.
+    fn product_name(&self, definition: u64) -> String {
+        let formation = self.reference(definition, "PRODUCT_DEFINITION", 2);
+        let product = formation.and_then(|formation| {
+            self.reference(formation, "PRODUCT_DEFINITION_FORMATION", 2)
+                .or_else(|| {
+                    self.reference(
+                        formation,
+                        "PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE",
+                        2,
+                    )
+                })
+        });
+        product
+            .and_then(|product| self.record(product, "PRODUCT"))
+            .and_then(|values| name_of(values, 1).or_else(|| name_of(values, 0)))
+            .unwrap_or_else(|| format!("Product #{definition}"))
+    }
+
+    fn product(
+        &self,
+        definition: u64,
+        usages: &[u64],
+        depth: usize,
+    ) -> Result<StepProduct, StepError> {
+        let mut components = Vec::new();
+        if depth < MAX_DEPTH {
+            for &usage in usages {
+                let kind = "NEXT_ASSEMBLY_USAGE_OCCURRENCE";
+                if self.reference(usage, kind, 3) != Some(definition) {
+                    continue;
+                }
+                let Some(child) = self.reference(usage, kind, 4) else {
+                    continue;
+                };
+                let values = self.record(usage, kind).unwrap_or_default();
+                let product = self.product(child, usages, depth + 1)?;
+                let name = name_of(values, 1)
+                    .or_else(|| name_of(values, 0))
+                    .unwrap_or_else(|| product.name.clone());
+                components.push(StepComponent {
+                    name,
+                    placement: self
+                        .placements
+                        .get(&usage)
+                        .copied()
+                        .unwrap_or(Mat4::IDENTITY),
+                    product,
+                });
+            }
+        }
+        Ok(StepProduct {
+            name: self.product_name(definition),
+            components,
+        })
+    }
+
+    /// Placements of assembly usages: the transformation of the shape
+    /// representation relationship attached to each.
+    fn placements(&self) -> Result<HashMap<u64, Mat4>, StepError> {
+        let mut placements = HashMap::new();
+        for shape in self.ids("CONTEXT_DEPENDENT_SHAPE_REPRESENTATION") {
+            let kind = "CONTEXT_DEPENDENT_SHAPE_REPRESENTATION";
+            let usage = self.reference(shape, kind, 1).and_then(|product_shape| {
+                self.reference(product_shape, "PRODUCT_DEFINITION_SHAPE", 2)
+            });
+            let Some(usage) = usage else {
+                continue;
+            };
+            let transformation = self.reference(shape, kind, 0).and_then(|relationship| {
+                self.reference(
+                    relationship,
+                    "REPRESENTATION_RELATIONSHIP_WITH_TRANSFORMATION",
+                    0,
+                )
+            });
+            let Some(transformation) = transformation else {
+                continue;
+            };
+            let item = |index| {
+                self.reference(transformation, "ITEM_DEFINED_TRANSFORMATION", index)
+                    .map(|axis| self.axis_placement(axis))
+                    .transpose()
+            };
+            // Maps the first placement (in the part) onto the second (in
+            // the assembly).
+            let from = item(2)?.unwrap_or(Mat4::IDENTITY);
+            let to = item(3)?.unwrap_or(Mat4::IDENTITY);
+            placements.insert(usage, to * from.inverse());
+        }
+        Ok(placements)
+    }
+
+    fn axis_placement(&self, id: u64) -> Result<Mat4, StepError> {
+        let kind = "AXIS2_PLACEMENT_3D";
+        let invalid = |message: &str| StepError::Parse {
+            id,
+            message: message.to_string(),
+        };
+        let location = self
+            .reference(id, kind, 1)
+            .and_then(|point| self.vector(point, "CARTESIAN_POINT"))
+            .ok_or_else(|| invalid("placement has no location"))?;
+        let direction = |index| {
+            self.reference(id, kind, index)
+                .and_then(|direction| self.vector(direction, "DIRECTION"))
+                .and_then(Vec3::try_normalize)
+        };
+        let z = direction(2).unwrap_or(Vec3::Z);
+        let x = direction(3)
+            .map(|x| x - z * x.dot(z))
+            .and_then(Vec3::try_normalize)
+            .unwrap_or_else(|| z.any_orthonormal_vector());
+        let y = z.cross(x);
+        Ok(Mat4::from_cols(
+            x.extend(0.0),
+            y.extend(0.0),
+            z.extend(0.0),
+            location.extend(1.0),
+        ))
+    }
+
+    /// Add the triangles of a triangulated face or surface set to `mesh`.
+    fn triangulated_set(&self, id: u64, kind: &str, mesh: &mut TriMesh) -> Result<(), StepError> {
+        let invalid = |message: &str| StepError::Parse {
+            id,
+            message: message.to_string(),
+        };
+        let values = self.record(id, kind).unwrap_or_default();
+        let list = self
+            .reference(id, kind, 1)
+            .and_then(|list| self.record(list, "COORDINATES_LIST"))
+            .and_then(|values| match values.get(2) {
+                Some(Value::List(points)) => Some(points),
+                _ => None,
+            })
+            .ok_or_else(|| invalid("face set has no coordinates"))?;
+        let base = mesh.positions.len() as u32;
+        for point in list {
+            let Value::List(coordinates) = point else {
+                return Err(invalid("coordinate is not a list"));
+            };
+            let mut position = [0.0; 3];
+            for (component, value) in position.iter_mut().zip(coordinates) {
+                let Value::Number(number) = value else {
+                    return Err(invalid("coordinate is not a number"));
+                };
+                *component = *number as f32;
+            }
+            mesh.positions.push(position);
+        }
+        let integers = |value: &Value| -> Vec<usize> {
+            match value {
+                Value::List(items) => items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Value::Number(number) => Some(*number as usize),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+        // The last two attributes are `pnindex` and the triangles, which
+        // index the point list through `pnindex` when it is given.
+        let (Some(Value::List(triangles)), Some(pnindex)) = (
+            values.last(),
+            values.len().checked_sub(2).map(|i| &values[i]),
+        ) else {
+            return Err(invalid("face set has no triangles"));
+        };
+        let pnindex = integers(pnindex);
+        for triangle in triangles {
+            let corners = integers(triangle);
+            if corners.len() != 3 {
+                return Err(invalid("triangle without three corners"));
+            }
+            for corner in corners {
+                let point = if pnindex.is_empty() {
+                    corner
+                } else {
+                    *pnindex
+                        .get(corner.wrapping_sub(1))
+                        .ok_or_else(|| invalid("triangle corner out of range"))?
+                };
+                if point == 0 || point > list.len() {
+                    return Err(invalid("triangle corner out of range"));
+                }
+                mesh.indices.push(base + point as u32 - 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Coordinates of a `CARTESIAN_POINT` or `DIRECTION`.
+    fn vector(&self, id: u64, kind: &str) -> Option<Vec3> {
+        let Value::List(values) = self.record(id, kind)?.get(1)? else {
+            return None;
+        };
+        let mut coordinates = [0.0; 3];
+        for (coordinate, value) in coordinates.iter_mut().zip(values) {
+            let Value::Number(number) = value else {
+                return None;
+            };
+            *coordinate = *number as f32;
+        }
+        Some(Vec3::from(coordinates))
+    }
+}
+
+/// A non-empty text argument.
+fn name_of(values: &[Value], index: usize) -> Option<String> {
+    match values.get(index)? {
+        Value::Text(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// Entities of the DATA section.
+fn parse_entities(text: &str) -> Result<Entities, StepError> {
+    let start = text.find("DATA;").ok_or(StepError::NotStep)? + "DATA;".len();
+    let mut parser = Parser {
+        chars: text[start..].chars().peekable(),
+    };
+    let mut entities = Entities::new();
+    loop {
+        parser.skip_space();
+        match parser.chars.peek() {
+            Some('#') => {
+                parser.chars.next();
+                let id = parser.integer();
+                let invalid = |message: &str| StepError::Parse {
+                    id,
+                    message: message.to_string(),
+                };
+                parser.skip_space();
+                if parser.chars.next() != Some('=') {
+                    return Err(invalid("expected '='"));
+                }
+                parser.skip_space();
+                let mut records = Vec::new();
+                if parser.chars.peek() == Some(&'(') {
+                    // Complex entity: several records in parentheses.
+                    parser.chars.next();
+                    loop {
+                        parser.skip_space();
+                        if parser.chars.peek() == Some(&')') {
+                            parser.chars.next();
+                            break;
+                        }
+                        let name = parser.keyword();
+                        if name.is_empty() {
+                            return Err(invalid("expected an entity type"));
+                        }
+                        records.push((
+                            name,
+                            parser.arguments().ok_or_else(|| invalid("bad arguments"))?,
+                        ));
+                    }
+                } else {
+                    let name = parser.keyword();
+                    records.push((
+                        name,
+                        parser.arguments().ok_or_else(|| invalid("bad arguments"))?,
+                    ));
+                }
+                parser.skip_space();
+                if parser.chars.next() != Some(';') {
+                    return Err(invalid("expected ';'"));
+                }
+                entities.insert(id, records);
+            }
+            // ENDSEC, or the end of the file.
+            _ => break,
+        }
+    }
+    Ok(entities)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Skip whitespace and `/* */` comments.
+    fn skip_space(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    self.chars.next();
+                    if self.chars.peek() == Some(&'*') {
+                        let mut previous = ' ';
+                        for c in self.chars.by_ref() {
+                            if previous == '*' && c == '/' {
+                                break;
+                            }
+                            previous = c;
+                        }
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn integer(&mut self) -> u64 {
+        let mut value = 0u64;
+        while let Some(digit) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+            value = value.saturating_mul(10).saturating_add(digit as u64);
+            self.chars.next();
+        }
+        value
+    }
+
+    fn keyword(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            name.push(c.to_ascii_uppercase());
+            self.chars.next();
+        }
+        name
+    }
+
+    /// A parenthesized, comma-separated list of values.
+    fn arguments(&mut self) -> Option<Vec<Value>> {
+        self.skip_space();
+        if self.chars.next() != Some('(') {
+            return None;
+        }
+        let mut values = Vec::new();
+        loop {
+            self.skip_space();
+            match self.chars.peek()? {
+                ')' => {
+                    self.chars.next();
+                    return Some(values);
+                }
+                ',' => {
+                    self.chars.next();
+                }
+                _ => values.push(self.value()?),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        Some(match *self.chars.peek()? {
+            '#' => {
+                self.chars.next();
+                Value::Reference(self.integer())
+            }
+            '\'' => {
+                self.chars.next();
+                let mut text = String::new();
+                loop {
+                    match self.chars.next()? {
+                        // A doubled quote is a quote in the text.
+                        '\'' if self.chars.peek() == Some(&'\'') => {
+                            self.chars.next();
+                            text.push('\'');
+                        }
+                        '\'' => break,
+                        c => text.push(c),
+                    }
+                }
+                Value::Text(text)
+            }
+            '.' => {
+                self.chars.next();
+                let mut name = String::new();
+                loop {
+                    match self.chars.next()? {
+                        '.' => break,
+                        c => name.push(c),
+                    }
+                }
+                Value::Enumeration(name)
+            }
+            '(' => Value::List(self.arguments()?),
+            '$' | '*' => {
+                self.chars.next();
+                Value::Unset
+            }
+            c if c.is_ascii_alphabetic() => {
+                let name = self.keyword();
+                Value::Typed(name, self.arguments()?)
+            }
+            _ => {
+                let mut number = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'E' | 'e')) {
+                        break;
+                    }
+                    number.push(c);
+                    self.chars.next();
+                }
+                // Binary and other values the structure never needs.
+                if number.is_empty() {
+                    while self.chars.peek().is_some_and(|c| !matches!(c, ',' | ')')) {
+                        self.chars.next();
+                    }
+                    return Some(Value::Unset);
+                }
+                Value::Number(number.parse().ok()?)
+            }
+        })
+    }
+}
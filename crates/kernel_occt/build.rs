@@ -0,0 +1,56 @@
+//! Compiles the C++ glue to OpenCASCADE (`occt/shim.cpp`) and links the OCCT
+//! libraries when the `occt` feature is on. OCCT 7.6 or newer is looked up in
+//! `OCCT_INCLUDE_DIR` and `OCCT_LIB_DIR`, defaulting to where distribution
+//! packages install it.
+
+fn main() {
+    #[cfg(feature = "occt")]
+    occt::build();
+}
+
+#[cfg(feature = "occt")]
+mod occt {
+    use std::env;
+
+    const DEFAULT_INCLUDE_DIR: &str = "/usr/include/opencascade";
+
+    /// Toolkits the shim uses, with the ones they depend on.
+    const LIBRARIES: &[&str] = &[
+        "TKernel",
+        "TKMath",
+        "TKG2d",
+        "TKG3d",
+        "TKGeomBase",
+        "TKBRep",
+        "TKGeomAlgo",
+        "TKTopAlgo",
+        "TKPrim",
+        "TKBO",
+        "TKBool",
+        "TKShHealing",
+        "TKFillet",
+        "TKMesh",
+    ];
+
+    pub fn build() {
+        println!("cargo:rerun-if-changed=occt/shim.cpp");
+        println!("cargo:rerun-if-env-changed=OCCT_INCLUDE_DIR");
+        println!("cargo:rerun-if-env-changed=OCCT_LIB_DIR");
+
+        let include =
+            env::var("OCCT_INCLUDE_DIR").unwrap_or_else(|_| DEFAULT_INCLUDE_DIR.to_string());
+        cc::Build::new()
+            .cpp(true)
+            .std("c++17")
+            .include(include)
+            .file("occt/shim.cpp")
+            .compile("printcad_occt");
+
+        if let Ok(dir) = env::var("OCCT_LIB_DIR") {
+            println!("cargo:rustc-link-search=native={dir}");
+        }
+        for library in LIBRARIES {
+            println!("cargo:rustc-link-lib=dylib={library}");
+        }
+    }
+}
@@ -0,0 +1,299 @@
+// C interface to the OpenCASCADE operations `kernel_occt` uses (see
+// src/occt.rs for the Rust side). Shapes are handed out as opaque pointers
+// owned by the caller; a failed operation returns null (or false) and leaves
+// its message for pc_last_error. Needs OCCT 7.6 or newer.
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <exception>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include <BRepAlgoAPI_Common.hxx>
+#include <BRepAlgoAPI_Cut.hxx>
+#include <BRepAlgoAPI_Fuse.hxx>
+#include <BRepBuilderAPI_MakeFace.hxx>
+#include <BRepBuilderAPI_MakePolygon.hxx>
+#include <BRepFilletAPI_MakeFillet.hxx>
+#include <BRepMesh_IncrementalMesh.hxx>
+#include <BRepPrimAPI_MakePrism.hxx>
+#include <BRepPrimAPI_MakeRevol.hxx>
+#include <BRep_Builder.hxx>
+#include <BRep_Tool.hxx>
+#include <Poly_Triangulation.hxx>
+#include <Standard_Failure.hxx>
+#include <Standard_Version.hxx>
+#include <TopExp.hxx>
+#include <TopExp_Explorer.hxx>
+#include <TopLoc_Location.hxx>
+#include <TopTools_IndexedDataMapOfShapeListOfShape.hxx>
+#include <TopoDS.hxx>
+#include <TopoDS_Compound.hxx>
+#include <TopoDS_Edge.hxx>
+#include <TopoDS_Face.hxx>
+#include <TopoDS_Shape.hxx>
+#include <TopoDS_Wire.hxx>
+#include <gp.hxx>
+#include <gp_Ax1.hxx>
+#include <gp_Dir.hxx>
+#include <gp_Pnt.hxx>
+#include <gp_Trsf.hxx>
+#include <gp_Vec.hxx>
+
+struct pc_shape {
+    TopoDS_Shape shape;
+};
+
+// Triangles of a tessellated shape: three floats per position and normal,
+// three indices per triangle. Freed with pc_mesh_free.
+struct pc_mesh {
+    float* positions;
+    float* normals;
+    size_t vertex_count;
+    uint32_t* indices;
+    size_t index_count;
+};
+
+namespace {
+
+thread_local std::string last_error;
+
+// Run an operation building a shape, turning its exceptions into a null
+// result and a message.
+template <typename Operation>
+pc_shape* guarded(Operation&& operation) {
+    try {
+        TopoDS_Shape shape = operation();
+        if (shape.IsNull()) {
+            last_error = "the operation produced no shape";
+            return nullptr;
+        }
+        return new pc_shape{shape};
+    } catch (const Standard_Failure& failure) {
+        const char* message = failure.GetMessageString();
+        last_error = message && *message ? message : failure.DynamicType()->Name();
+    } catch (const std::exception& error) {
+        last_error = error.what();
+    } catch (...) {
+        last_error = "unknown OpenCASCADE error";
+    }
+    return nullptr;
+}
+
+template <typename Builder>
+TopoDS_Shape built(Builder& builder, const char* failure) {
+    if (!builder.IsDone()) {
+        throw std::runtime_error(failure);
+    }
+    return builder.Shape();
+}
+
+}  // namespace
+
+extern "C" {
+
+const char* pc_version() {
+    return OCC_VERSION_COMPLETE;
+}
+
+const char* pc_last_error() {
+    return last_error.c_str();
+}
+
+void pc_shape_free(pc_shape* shape) {
+    delete shape;
+}
+
+// Planar faces from closed polygons: `region_loops[r]` loops make up face r,
+// the first its outer boundary and the others holes wound the other way.
+// `loop_lengths` gives the points of each loop, which follow one another in
+// `points` (x, y, z), without repeating the first point.
+pc_shape* pc_make_profile(const float* points,
+                          const size_t* loop_lengths,
+                          const size_t* region_loops,
+                          size_t region_count) {
+    return guarded([&] {
+        BRep_Builder builder;
+        TopoDS_Compound compound;
+        builder.MakeCompound(compound);
+        size_t point = 0;
+        size_t loop = 0;
+        for (size_t region = 0; region < region_count; ++region) {
+            TopoDS_Face face;
+            for (size_t i = 0; i < region_loops[region]; ++i, ++loop) {
+                BRepBuilderAPI_MakePolygon polygon;
+                for (size_t j = 0; j < loop_lengths[loop]; ++j, ++point) {
+                    const float* p = points + 3 * point;
+                    polygon.Add(gp_Pnt(p[0], p[1], p[2]));
+                }
+                polygon.Close();
+                TopoDS_Wire wire = polygon.Wire();
+                if (i == 0) {
+                    BRepBuilderAPI_MakeFace outer(wire, Standard_True);
+                    face = TopoDS::Face(built(outer, "a profile loop is not planar"));
+                } else {
+                    BRepBuilderAPI_MakeFace holed(face);
+                    holed.Add(wire);
+                    face = TopoDS::Face(built(holed, "a profile hole could not be cut"));
+                }
+            }
+            builder.Add(compound, face);
+        }
+        return TopoDS_Shape(compound);
+    });
+}
+
+pc_shape* pc_extrude(const pc_shape* profile, double dx, double dy, double dz) {
+    return guarded([&] {
+        BRepPrimAPI_MakePrism prism(profile->shape, gp_Vec(dx, dy, dz));
+        return built(prism, "the profile could not be extruded");
+    });
+}
+
+// Turn `profile` by `angle` radians around the axis through `origin` along
+// `direction`, by the right-hand rule.
+pc_shape* pc_revolve(const pc_shape* profile,
+                     const double* origin,
+                     const double* direction,
+                     double angle) {
+    return guarded([&] {
+        gp_Ax1 axis(gp_Pnt(origin[0], origin[1], origin[2]),
+                    gp_Dir(direction[0], direction[1], direction[2]));
+        BRepPrimAPI_MakeRevol revol(profile->shape, axis, angle);
+        return built(revol, "the profile could not be revolved");
+    });
+}
+
+// 0 union, 1 difference (target minus tool), 2 intersection.
+pc_shape* pc_boolean(int op, const pc_shape* target, const pc_shape* tool) {
+    return guarded([&]() -> TopoDS_Shape {
+        switch (op) {
+            case 0: {
+                BRepAlgoAPI_Fuse fuse(target->shape, tool->shape);
+                return built(fuse, "the union failed");
+            }
+            case 1: {
+                BRepAlgoAPI_Cut cut(target->shape, tool->shape);
+                return built(cut, "the difference failed");
+            }
+            case 2: {
+                BRepAlgoAPI_Common common(target->shape, tool->shape);
+                return built(common, "the intersection failed");
+            }
+            default:
+                throw std::invalid_argument("unknown boolean operation");
+        }
+    });
+}
+
+// Round every edge between two faces; seams and degenerate edges have none.
+pc_shape* pc_fillet(const pc_shape* body, double radius) {
+    return guarded([&] {
+        TopTools_IndexedDataMapOfShapeListOfShape edge_faces;
+        TopExp::MapShapesAndAncestors(body->shape, TopAbs_EDGE, TopAbs_FACE, edge_faces);
+        BRepFilletAPI_MakeFillet fillet(body->shape);
+        for (int i = 1; i <= edge_faces.Extent(); ++i) {
+            const TopoDS_Edge& edge = TopoDS::Edge(edge_faces.FindKey(i));
+            const TopTools_ListOfShape& faces = edge_faces.FindFromIndex(i);
+            if (BRep_Tool::Degenerated(edge) || faces.Extent() < 2 ||
+                faces.First().IsSame(faces.Last())) {
+                continue;
+            }
+            fillet.Add(radius, edge);
+        }
+        if (fillet.NbContours() == 0) {
+            throw std::runtime_error("the body has no edges to fillet");
+        }
+        fillet.Build();
+        return built(fillet, "the fillet radius does not fit the edges");
+    });
+}
+
+// Mesh the faces of `shape` with `BRepMesh_IncrementalMesh` and copy each
+// face's Poly_Triangulation, with the face's location applied and its
+// triangles wound outward, into `out`. Vertices are not shared between
+// triangles, so each carries its face normal.
+bool pc_tessellate(const pc_shape* shape,
+                   double deflection,
+                   double angle,
+                   pc_mesh* out) {
+    try {
+        BRepMesh_IncrementalMesh mesher(shape->shape, deflection, Standard_False, angle,
+                                        Standard_True);
+        if (!mesher.IsDone()) {
+            throw std::runtime_error("the shape could not be meshed");
+        }
+        std::vector<float> positions;
+        std::vector<float> normals;
+        for (TopExp_Explorer faces(shape->shape, TopAbs_FACE); faces.More(); faces.Next()) {
+            const TopoDS_Face& face = TopoDS::Face(faces.Current());
+            TopLoc_Location location;
+            Handle(Poly_Triangulation) triangulation = BRep_Tool::Triangulation(face, location);
+            if (triangulation.IsNull()) {
+                continue;
+            }
+            const gp_Trsf transform = location.Transformation();
+            const bool reversed = face.Orientation() == TopAbs_REVERSED;
+            for (int t = 1; t <= triangulation->NbTriangles(); ++t) {
+                int n1 = 0;
+                int n2 = 0;
+                int n3 = 0;
+                triangulation->Triangle(t).Get(n1, n2, n3);
+                if (reversed) {
+                    std::swap(n2, n3);
+                }
+                const gp_Pnt corners[3] = {
+                    triangulation->Node(n1).Transformed(transform),
+                    triangulation->Node(n2).Transformed(transform),
+                    triangulation->Node(n3).Transformed(transform),
+                };
+                gp_Vec normal = gp_Vec(corners[0], corners[1]).Crossed(gp_Vec(corners[0], corners[2]));
+                if (normal.Magnitude() <= gp::Resolution()) {
+                    continue;
+                }
+                normal.Normalize();
+                for (const gp_Pnt& corner : corners) {
+                    positions.insert(positions.end(), {static_cast<float>(corner.X()),
+                                                       static_cast<float>(corner.Y()),
+                                                       static_cast<float>(corner.Z())});
+                    normals.insert(normals.end(), {static_cast<float>(normal.X()),
+                                                   static_cast<float>(normal.Y()),
+                                                   static_cast<float>(normal.Z())});
+                }
+            }
+        }
+
+        const size_t vertex_count = positions.size() / 3;
+        out->vertex_count = vertex_count;
+        out->index_count = vertex_count;
+        out->positions = new float[positions.size()];
+        out->normals = new float[normals.size()];
+        out->indices = new uint32_t[vertex_count];
+        std::copy(positions.begin(), positions.end(), out->positions);
+        std::copy(normals.begin(), normals.end(), out->normals);
+        for (size_t i = 0; i < vertex_count; ++i) {
+            out->indices[i] = static_cast<uint32_t>(i);
+        }
+        return true;
+    } catch (const Standard_Failure& failure) {
+        const char* message = failure.GetMessageString();
+        last_error = message && *message ? message : failure.DynamicType()->Name();
+    } catch (const std::exception& error) {
+        last_error = error.what();
+    } catch (...) {
+        last_error = "unknown OpenCASCADE error";
+    }
+    return false;
+}
+
+void pc_mesh_free(pc_mesh* mesh) {
+    delete[] mesh->positions;
+    delete[] mesh->normals;
+    delete[] mesh->indices;
+    *mesh = pc_mesh{};
+}
+
+}  // extern "C"
@@ -1,12 +1,32 @@
+#[cfg(feature = "occt")]
+mod occt;
+mod revolve;
+
+#[cfg(feature = "occt")]
+use std::collections::HashMap;
+
+#[cfg(feature = "occt")]
+use kernel_api::BooleanOp;
 use kernel_api::{
-    BodyHandle, Kernel, KernelError, KernelResult, RebuildRequest, RebuildResponse,
-    TessellationSettings, TriMesh,
+    Axis, BodyHandle, CancellationToken, Diagnostic, Kernel, KernelError, KernelResult, Profile,
+    RebuildRequest, RebuildResponse, TessellationSettings, TriMesh,
 };
 use tracing::info;
 
-/// Placeholder OCCT-backed kernel implementation.
+/// OpenCASCADE-backed kernel.
+///
+/// The bindings are built with the `occt` feature, which needs OCCT 7.6 or
+/// newer installed (see `build.rs`). Without it the kernel is a stub: rebuilds
+/// hand out placeholder handles, tessellation returns empty meshes, revolve is
+/// a faceted sweep in Rust, and the other operations are unsupported.
 pub struct OcctKernel {
     initialized: bool,
+    /// Solids by handle.
+    #[cfg(feature = "occt")]
+    shapes: HashMap<BodyHandle, occt::Shape>,
+    /// Next handle to hand out; handles are never reused.
+    #[cfg(feature = "occt")]
+    next_handle: u64,
 }
 
 impl Default for OcctKernel {
@@ -17,7 +37,13 @@ impl Default for OcctKernel {
 
 impl OcctKernel {
     pub fn new() -> Self {
-        Self { initialized: false }
+        Self {
+            initialized: false,
+            #[cfg(feature = "occt")]
+            shapes: HashMap::new(),
+            #[cfg(feature = "occt")]
+            next_handle: 1,
+        }
     }
 
     pub fn is_initialized(&self) -> bool {
@@ -25,6 +51,46 @@ impl OcctKernel {
     }
 }
 
+#[cfg(feature = "occt")]
+impl OcctKernel {
+    fn ensure_initialized(&self) -> KernelResult<()> {
+        if self.initialized {
+            Ok(())
+        } else {
+            Err(KernelError::NotInitialized)
+        }
+    }
+
+    fn keep(&mut self, shape: occt::Shape) -> BodyHandle {
+        let handle = BodyHandle(self.next_handle);
+        self.next_handle += 1;
+        self.shapes.insert(handle, shape);
+        handle
+    }
+
+    fn shape(&self, body: BodyHandle) -> KernelResult<&occt::Shape> {
+        self.ensure_initialized()?;
+        self.shapes
+            .get(&body)
+            .ok_or_else(|| KernelError::InvalidInput(format!("no body with handle {}", body.0)))
+    }
+
+    fn revolved(
+        &self,
+        profile: &Profile,
+        axis: &Axis,
+        angle_deg: f32,
+    ) -> KernelResult<occt::Shape> {
+        self.ensure_initialized()?;
+        if !(angle_deg > 0.0 && angle_deg <= 360.0 + 1e-3) {
+            return Err(KernelError::InvalidInput(
+                "revolve angle must be between 0 and 360 degrees".to_string(),
+            ));
+        }
+        occt::Shape::profile(profile)?.revolve(axis, angle_deg)
+    }
+}
+
 impl Kernel for OcctKernel {
     fn name(&self) -> &str {
         "OpenCascade"
@@ -32,31 +98,69 @@ impl Kernel for OcctKernel {
 
     fn initialize(&mut self) -> KernelResult<()> {
         if !self.initialized {
+            #[cfg(feature = "occt")]
+            info!("Initializing OCCT kernel {}", occt::version());
+            #[cfg(not(feature = "occt"))]
             info!("Initializing OCCT kernel (stub)");
-            // Actual FFI wires will live here once bindings land.
             self.initialized = true;
         }
         Ok(())
     }
 
-    fn rebuild(&mut self, request: &RebuildRequest) -> KernelResult<RebuildResponse> {
+    fn rebuild(
+        &mut self,
+        request: &RebuildRequest,
+        cancel: &CancellationToken,
+    ) -> KernelResult<RebuildResponse> {
         if !self.initialized {
             return Err(KernelError::NotInitialized);
         }
 
-        let generated_handles = request
-            .dirty_features
-            .iter()
-            .enumerate()
-            .map(|(index, _)| BodyHandle(index as u64 + 1))
-            .collect();
+        // With OCCT, features build their bodies through the operations
+        // below and release them when tessellated; bodies a cancelled or
+        // failed build left behind are dropped before the features build
+        // theirs again.
+        #[cfg(feature = "occt")]
+        {
+            cancel.check()?;
+            tracing::debug!("Rebuilding {} features", request.dirty_features.len());
+            let mut diagnostics = Vec::new();
+            if !self.shapes.is_empty() {
+                diagnostics.push(Diagnostic::info(format!(
+                    "Dropped {} bodies left by earlier builds",
+                    self.shapes.len()
+                )));
+                self.shapes.clear();
+            }
+            Ok(RebuildResponse {
+                updated_bodies: Vec::new(),
+                diagnostics,
+            })
+        }
+
+        #[cfg(not(feature = "occt"))]
+        {
+            let mut generated_handles = Vec::with_capacity(request.dirty_features.len());
+            for index in 0..request.dirty_features.len() {
+                // OCCT operations can't be interrupted; stop between features.
+                cancel.check()?;
+                generated_handles.push(BodyHandle(index as u64 + 1));
+            }
 
-        Ok(RebuildResponse {
-            updated_bodies: generated_handles,
-            diagnostics: vec!["OCCT kernel stub executed".to_string()],
-        })
+            Ok(RebuildResponse {
+                updated_bodies: generated_handles,
+                diagnostics: vec![Diagnostic::info("OCCT kernel stub executed")],
+            })
+        }
     }
 
+    #[cfg(feature = "occt")]
+    fn tessellate(&self, body: BodyHandle, detail: &TessellationSettings) -> KernelResult<TriMesh> {
+        self.shape(body)?
+            .tessellate(detail.chord_tolerance, detail.angular_tolerance_deg)
+    }
+
+    #[cfg(not(feature = "occt"))]
     fn tessellate(
         &self,
         _body: BodyHandle,
@@ -68,4 +172,156 @@ impl Kernel for OcctKernel {
 
         Ok(TriMesh::default())
     }
+
+    #[cfg(feature = "occt")]
+    fn revolve(
+        &mut self,
+        profile: &Profile,
+        axis: &Axis,
+        angle_deg: f32,
+        detail: &TessellationSettings,
+    ) -> KernelResult<TriMesh> {
+        self.revolved(profile, axis, angle_deg)?
+            .tessellate(detail.chord_tolerance, detail.angular_tolerance_deg)
+    }
+
+    #[cfg(not(feature = "occt"))]
+    fn revolve(
+        &mut self,
+        profile: &Profile,
+        axis: &Axis,
+        angle_deg: f32,
+        detail: &TessellationSettings,
+    ) -> KernelResult<TriMesh> {
+        if !self.initialized {
+            return Err(KernelError::NotInitialized);
+        }
+
+        revolve::revolve(profile, axis, angle_deg, detail)
+    }
+
+    #[cfg(feature = "occt")]
+    fn extrude(&mut self, profile: &Profile, direction: [f32; 3]) -> KernelResult<BodyHandle> {
+        self.ensure_initialized()?;
+        let shape = occt::Shape::profile(profile)?.extrude(direction)?;
+        Ok(self.keep(shape))
+    }
+
+    #[cfg(feature = "occt")]
+    fn revolve_body(
+        &mut self,
+        profile: &Profile,
+        axis: &Axis,
+        angle_deg: f32,
+    ) -> KernelResult<BodyHandle> {
+        let shape = self.revolved(profile, axis, angle_deg)?;
+        Ok(self.keep(shape))
+    }
+
+    #[cfg(feature = "occt")]
+    fn boolean(
+        &mut self,
+        op: BooleanOp,
+        target: BodyHandle,
+        tool: BodyHandle,
+    ) -> KernelResult<BodyHandle> {
+        let shape = self.shape(target)?.boolean(op, self.shape(tool)?)?;
+        Ok(self.keep(shape))
+    }
+
+    #[cfg(feature = "occt")]
+    fn fillet(&mut self, body: BodyHandle, radius: f32) -> KernelResult<BodyHandle> {
+        if radius.is_nan() || radius <= 0.0 {
+            return Err(KernelError::InvalidInput(
+                "fillet radius must be positive".to_string(),
+            ));
+        }
+        let shape = self.shape(body)?.fillet(radius)?;
+        Ok(self.keep(shape))
+    }
+
+    #[cfg(feature = "occt")]
+    fn release(&mut self, body: BodyHandle) {
+        self.shapes.remove(&body);
+    }
+}
+
+#[cfg(all(test, feature = "occt"))]
+mod tests {
+    use super::*;
+
+    fn initialized() -> OcctKernel {
+        let mut kernel = OcctKernel::new();
+        kernel.initialize().unwrap();
+        kernel
+    }
+
+    /// Square from `min` to `max` on the XY plane.
+    fn square(min: f32, max: f32) -> Profile {
+        Profile {
+            loops: vec![vec![
+                [min, min, 0.0],
+                [max, min, 0.0],
+                [max, max, 0.0],
+                [min, max, 0.0],
+            ]],
+        }
+    }
+
+    /// Volume enclosed by a closed mesh.
+    fn volume(mesh: &TriMesh) -> f32 {
+        let volume: f32 = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] =
+                    [0, 1, 2].map(|i| glam::Vec3::from(mesh.positions[triangle[i] as usize]));
+                a.dot(b.cross(c)) / 6.0
+            })
+            .sum();
+        volume.abs()
+    }
+
+    #[test]
+    fn extruded_block_is_cut_and_filleted() {
+        let mut kernel = initialized();
+        let detail = TessellationSettings::default();
+        let block = kernel
+            .extrude(&square(0.0, 10.0), [0.0, 0.0, 10.0])
+            .unwrap();
+        let hole = kernel.extrude(&square(2.0, 8.0), [0.0, 0.0, 10.0]).unwrap();
+        let cut = kernel.boolean(BooleanOp::Difference, block, hole).unwrap();
+        let rounded = kernel.fillet(block, 1.0).unwrap();
+
+        let block_volume = volume(&kernel.tessellate(block, &detail).unwrap());
+        assert!((block_volume - 1000.0).abs() < 1.0, "{block_volume}");
+        let cut_volume = volume(&kernel.tessellate(cut, &detail).unwrap());
+        assert!((cut_volume - 640.0).abs() < 1.0, "{cut_volume}");
+        // Rounding the twelve edges takes about 2 mm³ off each.
+        let rounded_volume = volume(&kernel.tessellate(rounded, &detail).unwrap());
+        assert!(
+            rounded_volume > 960.0 && rounded_volume < 990.0,
+            "{rounded_volume}"
+        );
+
+        for body in [block, hole, cut, rounded] {
+            kernel.release(body);
+        }
+        assert!(kernel.tessellate(block, &detail).is_err());
+    }
+
+    #[test]
+    fn rebuild_drops_bodies_left_behind() {
+        let mut kernel = initialized();
+        let block = kernel.extrude(&square(0.0, 1.0), [0.0, 0.0, 1.0]).unwrap();
+        let request = RebuildRequest {
+            dirty_features: vec!["block".to_string()],
+            propagate: false,
+        };
+        let response = kernel.rebuild(&request, &CancellationToken::new()).unwrap();
+        assert_eq!(response.diagnostics.len(), 1);
+        assert!(kernel
+            .tessellate(block, &TessellationSettings::default())
+            .is_err());
+    }
 }
@@ -0,0 +1,233 @@
+//! OpenCASCADE bindings, through the C functions of `occt/shim.cpp` that
+//! `build.rs` compiles when the `occt` feature is on.
+
+use std::ffi::{c_char, c_int, CStr};
+use std::ptr::NonNull;
+
+use glam::{Vec2, Vec3};
+use kernel_api::{Axis, BooleanOp, KernelError, KernelResult, Profile, TriMesh};
+
+use crate::revolve::{newell_normal, open_loop, regions, PLANE_TOLERANCE};
+
+#[repr(C)]
+struct RawShape {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct RawMesh {
+    positions: *mut f32,
+    normals: *mut f32,
+    vertex_count: usize,
+    indices: *mut u32,
+    index_count: usize,
+}
+
+extern "C" {
+    fn pc_version() -> *const c_char;
+    fn pc_last_error() -> *const c_char;
+    fn pc_shape_free(shape: *mut RawShape);
+    fn pc_make_profile(
+        points: *const f32,
+        loop_lengths: *const usize,
+        region_loops: *const usize,
+        region_count: usize,
+    ) -> *mut RawShape;
+    fn pc_extrude(profile: *const RawShape, dx: f64, dy: f64, dz: f64) -> *mut RawShape;
+    fn pc_revolve(
+        profile: *const RawShape,
+        origin: *const f64,
+        direction: *const f64,
+        angle: f64,
+    ) -> *mut RawShape;
+    fn pc_boolean(op: c_int, target: *const RawShape, tool: *const RawShape) -> *mut RawShape;
+    fn pc_fillet(body: *const RawShape, radius: f64) -> *mut RawShape;
+    fn pc_tessellate(
+        shape: *const RawShape,
+        deflection: f64,
+        angle: f64,
+        out: *mut RawMesh,
+    ) -> bool;
+    fn pc_mesh_free(mesh: *mut RawMesh);
+}
+
+/// Version of the OCCT libraries linked in.
+pub(crate) fn version() -> String {
+    // SAFETY: pc_version returns a static NUL-terminated string.
+    unsafe { CStr::from_ptr(pc_version()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Error for a failed shim call, with the message it left.
+fn last_error(operation: &str) -> KernelError {
+    // SAFETY: pc_last_error returns the calling thread's NUL-terminated
+    // message, valid until its next call.
+    let message = unsafe { CStr::from_ptr(pc_last_error()) }.to_string_lossy();
+    KernelError::InvalidInput(format!("{operation} failed: {message}"))
+}
+
+/// An OCCT shape (`TopoDS_Shape`), freed on drop.
+pub(crate) struct Shape(NonNull<RawShape>);
+
+// SAFETY: a shape is only used by the kernel that owns it, one call at a time,
+// and OCCT's reference counts are atomic.
+unsafe impl Send for Shape {}
+
+impl Drop for Shape {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from the shim and is freed only here.
+        unsafe { pc_shape_free(self.0.as_ptr()) }
+    }
+}
+
+impl Shape {
+    fn from_raw(raw: *mut RawShape, operation: &str) -> KernelResult<Self> {
+        NonNull::new(raw)
+            .map(Self)
+            .ok_or_else(|| last_error(operation))
+    }
+
+    fn as_ptr(&self) -> *const RawShape {
+        self.0.as_ptr()
+    }
+
+    /// Planar faces bounded by the loops of `profile`.
+    pub(crate) fn profile(profile: &Profile) -> KernelResult<Self> {
+        let faces = profile_faces(profile)?;
+        let mut points = Vec::new();
+        let mut loop_lengths = Vec::new();
+        let region_loops: Vec<usize> = faces.iter().map(Vec::len).collect();
+        for boundary in faces.iter().flatten() {
+            loop_lengths.push(boundary.len());
+            points.extend(boundary.iter().flat_map(|point| point.to_array()));
+        }
+        // SAFETY: the lengths describe exactly the points passed.
+        let raw = unsafe {
+            pc_make_profile(
+                points.as_ptr(),
+                loop_lengths.as_ptr(),
+                region_loops.as_ptr(),
+                region_loops.len(),
+            )
+        };
+        Self::from_raw(raw, "profile")
+    }
+
+    pub(crate) fn extrude(&self, direction: [f32; 3]) -> KernelResult<Self> {
+        let [dx, dy, dz] = direction.map(f64::from);
+        // SAFETY: `self` is a live shape.
+        let raw = unsafe { pc_extrude(self.as_ptr(), dx, dy, dz) };
+        Self::from_raw(raw, "extrude")
+    }
+
+    pub(crate) fn revolve(&self, axis: &Axis, angle_deg: f32) -> KernelResult<Self> {
+        let origin = axis.origin.map(f64::from);
+        let direction = axis.direction.map(f64::from);
+        let angle = f64::from(angle_deg.min(360.0)).to_radians();
+        // SAFETY: `self` is a live shape and the axis arrays hold three values.
+        let raw = unsafe { pc_revolve(self.as_ptr(), origin.as_ptr(), direction.as_ptr(), angle) };
+        Self::from_raw(raw, "revolve")
+    }
+
+    pub(crate) fn boolean(&self, op: BooleanOp, tool: &Shape) -> KernelResult<Self> {
+        let code = match op {
+            BooleanOp::Union => 0,
+            BooleanOp::Difference => 1,
+            BooleanOp::Intersection => 2,
+        };
+        // SAFETY: both shapes are live.
+        let raw = unsafe { pc_boolean(code, self.as_ptr(), tool.as_ptr()) };
+        Self::from_raw(raw, "boolean")
+    }
+
+    pub(crate) fn fillet(&self, radius: f32) -> KernelResult<Self> {
+        // SAFETY: `self` is a live shape.
+        let raw = unsafe { pc_fillet(self.as_ptr(), f64::from(radius)) };
+        Self::from_raw(raw, "fillet")
+    }
+
+    /// Triangles of the shape's faces, within `deflection` of the surface and
+    /// `angle_deg` between neighbouring facets.
+    pub(crate) fn tessellate(&self, deflection: f32, angle_deg: f32) -> KernelResult<TriMesh> {
+        let mut raw = RawMesh {
+            positions: std::ptr::null_mut(),
+            normals: std::ptr::null_mut(),
+            vertex_count: 0,
+            indices: std::ptr::null_mut(),
+            index_count: 0,
+        };
+        let deflection = f64::from(deflection.max(1e-4));
+        let angle = f64::from(angle_deg.max(1.0)).to_radians();
+        // SAFETY: `self` is a live shape; on success the shim fills `raw`
+        // with arrays of the lengths it gives, freed below.
+        unsafe {
+            if !pc_tessellate(self.as_ptr(), deflection, angle, &mut raw) {
+                return Err(last_error("tessellation"));
+            }
+            let vertices = |values: *mut f32| {
+                std::slice::from_raw_parts(values, raw.vertex_count * 3)
+                    .chunks_exact(3)
+                    .map(|v| [v[0], v[1], v[2]])
+                    .collect()
+            };
+            let mesh = TriMesh {
+                positions: vertices(raw.positions),
+                normals: vertices(raw.normals),
+                indices: std::slice::from_raw_parts(raw.indices, raw.index_count).to_vec(),
+            };
+            pc_mesh_free(&mut raw);
+            Ok(mesh)
+        }
+    }
+}
+
+/// Loops of `profile` grouped into faces: an outer loop, counterclockwise
+/// around the profile's normal, followed by the holes directly inside it,
+/// clockwise.
+fn profile_faces(profile: &Profile) -> KernelResult<Vec<Vec<Vec<Vec3>>>> {
+    let invalid = |message: &str| KernelError::InvalidInput(message.to_string());
+    let loops: Vec<Vec<Vec3>> = profile
+        .loops
+        .iter()
+        .map(|points| open_loop(points))
+        .filter(|points| points.len() >= 3)
+        .collect();
+    let normal = loops
+        .iter()
+        .find_map(|points| newell_normal(points).try_normalize())
+        .ok_or_else(|| invalid("profile has no closed loop"))?;
+    let base = loops[0][0];
+    if !loops
+        .iter()
+        .flatten()
+        .all(|point| (*point - base).dot(normal).abs() < PLANE_TOLERANCE)
+    {
+        return Err(invalid("profile is not planar"));
+    }
+
+    let (u, v) = normal.any_orthonormal_pair();
+    let flat = loops
+        .iter()
+        .map(|points| {
+            points
+                .iter()
+                .map(|p| Vec2::new((*p - base).dot(u), (*p - base).dot(v)))
+                .collect()
+        })
+        .collect();
+    Ok(regions(flat)
+        .into_iter()
+        .map(|region| {
+            region
+                .into_iter()
+                .map(|points| {
+                    points
+                        .into_iter()
+                        .map(|p| base + u * p.x + v * p.y)
+                        .collect()
+                })
+                .collect()
+        })
+        .collect())
+}
@@ -0,0 +1,326 @@
+//! Revolve as a faceted sweep of the profile, used when the kernel is built
+//! without OCCT (the `occt` feature), and profile helpers for the bindings.
+
+// With OCCT, revolves are built by `BRepPrimAPI_MakeRevol` instead.
+#![cfg_attr(feature = "occt", allow(dead_code))]
+
+use glam::{Vec2, Vec3};
+use kernel_api::{Axis, KernelError, KernelResult, Profile, TessellationSettings, TriMesh};
+
+/// Largest distance of the profile and the axis from the profile's plane,
+/// and of the profile across the axis, in model units.
+pub(crate) const PLANE_TOLERANCE: f32 = 1e-3;
+
+/// Most steps a sweep is split into.
+const MAX_STEPS: usize = 360;
+
+pub(crate) fn revolve(
+    profile: &Profile,
+    axis: &Axis,
+    angle_deg: f32,
+    detail: &TessellationSettings,
+) -> KernelResult<TriMesh> {
+    let invalid = |message: &str| KernelError::InvalidInput(message.to_string());
+    if !(angle_deg > 0.0 && angle_deg <= 360.0 + PLANE_TOLERANCE) {
+        return Err(invalid("revolve angle must be between 0 and 360 degrees"));
+    }
+    let direction = Vec3::from(axis.direction)
+        .try_normalize()
+        .ok_or_else(|| invalid("revolve axis has no direction"))?;
+    let origin = Vec3::from(axis.origin);
+    let loops: Vec<Vec<Vec3>> = profile
+        .loops
+        .iter()
+        .map(|points| open_loop(points))
+        .filter(|points| points.len() >= 3)
+        .collect();
+    let normal = loops
+        .iter()
+        .find_map(|points| newell_normal(points).try_normalize())
+        .ok_or_else(|| invalid("revolve profile has no closed loop"))?;
+
+    let base = loops[0][0];
+    let in_plane = loops
+        .iter()
+        .flatten()
+        .chain([&origin])
+        .all(|point| (*point - base).dot(normal).abs() < PLANE_TOLERANCE);
+    if !in_plane || direction.dot(normal).abs() > PLANE_TOLERANCE {
+        return Err(invalid("revolve axis must lie in the profile's plane"));
+    }
+
+    // Profile coordinates: distance from the axis and height along it.
+    let mut radial = normal.cross(direction).normalize();
+    let offsets = || loops.iter().flatten().map(|p| (*p - origin).dot(radial));
+    if offsets().all(|s| s <= PLANE_TOLERANCE) {
+        radial = -radial;
+    } else if !offsets().all(|s| s >= -PLANE_TOLERANCE) {
+        return Err(invalid("revolve profile crosses the axis"));
+    }
+    let flat: Vec<Vec<Vec2>> = loops
+        .iter()
+        .map(|points| {
+            points
+                .iter()
+                .map(|p| {
+                    let offset = *p - origin;
+                    Vec2::new(offset.dot(radial).max(0.0), offset.dot(direction))
+                })
+                .collect()
+        })
+        .collect();
+    let regions = regions(flat);
+
+    let full = angle_deg >= 360.0 - PLANE_TOLERANCE;
+    let angle = angle_deg.min(360.0).to_radians();
+    let max_radius = regions
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|p| p.x)
+        .fold(0.0, f32::max);
+    let mut step = detail.angular_tolerance_deg.max(1.0).to_radians();
+    if detail.chord_tolerance > 0.0 && detail.chord_tolerance < max_radius {
+        step = step.min(2.0 * (1.0 - detail.chord_tolerance / max_radius).acos());
+    }
+    let steps = ((angle / step).ceil() as usize).clamp(if full { 3 } else { 1 }, MAX_STEPS);
+
+    let tangent = direction.cross(radial);
+    let place = |p: Vec2, turn: f32| {
+        origin + direction * p.y + (radial * turn.cos() + tangent * turn.sin()) * p.x
+    };
+    let turn = |k: usize| angle * k as f32 / steps as f32;
+
+    let mut triangles = Vec::new();
+    for region in &regions {
+        for points in region {
+            for (i, &a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                for k in 0..steps {
+                    let (a0, b0) = (place(a, turn(k)), place(b, turn(k)));
+                    let (a1, b1) = (place(a, turn(k + 1)), place(b, turn(k + 1)));
+                    triangles.push([a0, b1, b0]);
+                    triangles.push([a0, a1, b1]);
+                }
+            }
+        }
+        if !full {
+            let (outer, holes) = region.split_first().expect("regions have an outer loop");
+            for [a, b, c] in triangulate(outer, holes) {
+                triangles.push([place(a, 0.0), place(b, 0.0), place(c, 0.0)]);
+                triangles.push([place(a, angle), place(c, angle), place(b, angle)]);
+            }
+        }
+    }
+
+    let mut mesh = TriMesh::default();
+    for corners in triangles {
+        let [a, b, c] = corners;
+        // Faces touching the axis shrink to nothing there.
+        let Some(normal) = (b - a).cross(c - a).try_normalize() else {
+            continue;
+        };
+        for corner in corners {
+            mesh.indices.push(mesh.positions.len() as u32);
+            mesh.positions.push(corner.to_array());
+            mesh.normals.push(normal.to_array());
+        }
+    }
+    Ok(mesh)
+}
+
+/// Points of a closed loop without repeats, the last not repeating the first.
+pub(crate) fn open_loop(points: &[[f32; 3]]) -> Vec<Vec3> {
+    let mut open: Vec<Vec3> = Vec::with_capacity(points.len());
+    for point in points.iter().map(|p| Vec3::from(*p)) {
+        if open
+            .last()
+            .map_or(true, |last| last.distance(point) > f32::EPSILON)
+        {
+            open.push(point);
+        }
+    }
+    while open.len() > 1 && open[0].distance(open[open.len() - 1]) <= f32::EPSILON {
+        open.pop();
+    }
+    open
+}
+
+/// Normal of a polygon scaled by its area (Newell's method).
+pub(crate) fn newell_normal(points: &[Vec3]) -> Vec3 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*a - *b).cross(*a + *b) * 0.5)
+        .sum()
+}
+
+/// Twice the signed area of a polygon, positive when counterclockwise.
+fn signed_area(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum()
+}
+
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Group loops into regions: an outer loop, counterclockwise, followed by
+/// the holes directly inside it, clockwise.
+pub(crate) fn regions(loops: Vec<Vec<Vec2>>) -> Vec<Vec<Vec<Vec2>>> {
+    let containers: Vec<Vec<usize>> = loops
+        .iter()
+        .enumerate()
+        .map(|(i, inner)| {
+            (0..loops.len())
+                .filter(|&j| j != i && contains(&loops[j], inner[0]))
+                .collect()
+        })
+        .collect();
+    let depth = |i: usize| containers[i].len();
+    let mut regions: Vec<(usize, Vec<Vec<Vec2>>)> = Vec::new();
+    for (i, mut points) in loops.iter().cloned().enumerate() {
+        if depth(i) % 2 == 0 {
+            if signed_area(&points) < 0.0 {
+                points.reverse();
+            }
+            regions.push((i, vec![points]));
+        }
+    }
+    for (i, mut points) in loops.into_iter().enumerate() {
+        if depth(i) % 2 == 0 {
+            continue;
+        }
+        if signed_area(&points) > 0.0 {
+            points.reverse();
+        }
+        let parent = containers[i].iter().find(|&&j| depth(j) + 1 == depth(i));
+        if let Some(region) = regions.iter_mut().find(|(j, _)| Some(j) == parent) {
+            region.1.push(points);
+        }
+    }
+    regions.into_iter().map(|(_, region)| region).collect()
+}
+
+/// Triangles covering `outer` (counterclockwise) minus `holes` (clockwise),
+/// counterclockwise.
+fn triangulate(outer: &[Vec2], holes: &[Vec<Vec2>]) -> Vec<[Vec2; 3]> {
+    let mut polygon = outer.to_vec();
+    let rightmost = |points: &[Vec2]| points.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+    let mut holes: Vec<&Vec<Vec2>> = holes.iter().collect();
+    // Bridging the rightmost hole first keeps later bridges from crossing it.
+    holes.sort_by(|a, b| rightmost(b).total_cmp(&rightmost(a)));
+    for hole in holes {
+        bridge(&mut polygon, hole);
+    }
+    ear_clip(polygon)
+}
+
+/// Join `hole` into `polygon` along a cut from the hole's rightmost point to
+/// a polygon point it can see, making one polygon of the two.
+fn bridge(polygon: &mut Vec<Vec2>, hole: &[Vec2]) {
+    let Some((m, &start)) = hole
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.x.total_cmp(&b.x))
+    else {
+        return;
+    };
+    // Nearest polygon edge to the right of the hole, and its right end.
+    let mut nearest: Option<(f32, usize)> = None;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.y > start.y) == (b.y > start.y) {
+            continue;
+        }
+        let x = a.x + (start.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x >= start.x && nearest.map_or(true, |(nearest_x, _)| x < nearest_x) {
+            let end = if a.x > b.x {
+                i
+            } else {
+                (i + 1) % polygon.len()
+            };
+            nearest = Some((x, end));
+        }
+    }
+    let Some((x, mut target)) = nearest else {
+        return;
+    };
+    // A polygon point inside the triangle between the hole, the edge, and
+    // the end would block the cut; go to the one closest to the ray instead.
+    let hit = Vec2::new(x, start.y);
+    let end = polygon[target];
+    let blocking = (0..polygon.len())
+        .filter(|&i| i != target && inside_triangle(polygon[i], start, hit, end))
+        .min_by(|&i, &j| {
+            let angle = |p: Vec2| ((p.y - start.y) / (p.x - start.x)).abs();
+            angle(polygon[i]).total_cmp(&angle(polygon[j]))
+        });
+    if let Some(i) = blocking {
+        target = i;
+    }
+    let mut joined = polygon[..=target].to_vec();
+    joined.extend(hole[m..].iter().chain(&hole[..=m]));
+    joined.extend_from_slice(&polygon[target..]);
+    *polygon = joined;
+}
+
+fn inside_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    if p == a || p == b || p == c {
+        return false;
+    }
+    let (d1, d2, d3) = (
+        (b - a).perp_dot(p - a),
+        (c - b).perp_dot(p - b),
+        (a - c).perp_dot(p - c),
+    );
+    (d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0) || (d1 <= 0.0 && d2 <= 0.0 && d3 <= 0.0)
+}
+
+/// Triangulate a simple counterclockwise polygon by cutting off ears.
+fn ear_clip(mut polygon: Vec<Vec2>) -> Vec<[Vec2; 3]> {
+    let mut triangles = Vec::new();
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let corners = |i: usize| (polygon[(i + n - 1) % n], polygon[i], polygon[(i + 1) % n]);
+        let turn = |i: usize| {
+            let (a, b, c) = corners(i);
+            (b - a).perp_dot(c - b)
+        };
+        // Points in line with their neighbours add nothing.
+        if let Some(i) = (0..n).find(|&i| turn(i).abs() <= f32::EPSILON) {
+            polygon.remove(i);
+            continue;
+        }
+        let is_ear = |i: usize| {
+            let (a, b, c) = corners(i);
+            turn(i) > 0.0 && !polygon.iter().any(|&p| inside_triangle(p, a, b, c))
+        };
+        let Some(i) = (0..n)
+            .find(|&i| is_ear(i))
+            .or_else(|| (0..n).find(|&i| turn(i) > 0.0))
+        else {
+            break;
+        };
+        let (a, b, c) = corners(i);
+        triangles.push([a, b, c]);
+        polygon.remove(i);
+    }
+    if let [a, b, c] = polygon[..] {
+        if (b - a).perp_dot(c - b) > 0.0 {
+            triangles.push([a, b, c]);
+        }
+    }
+    triangles
+}
@@ -3,6 +3,12 @@
 layout(location = 0) in vec3 v_world_pos;
 layout(location = 1) in vec3 v_normal;
 layout(location = 2) in vec3 v_color;
+// xyz = stripe axis, w = stripes per half turn (0 = regular shading)
+layout(location = 3) flat in vec4 v_zebra;
+// 1 = opaque; lower values are blended by the translucent pipeline
+layout(location = 4) flat in float v_opacity;
+// 1 = cut by the clip plane, 0 = always drawn
+layout(location = 5) flat in float v_clip;
 
 layout(location = 0) out vec4 out_color;
 
@@ -19,6 +25,7 @@ layout(push_constant) uniform PushConstants {
     Light light_back;
     Light light_fill;
     vec4 ambient;  // rgb = ambient color * intensity
+    vec4 clip_plane;  // fragments with dot(xyz, pos) < w are cut away
 } pc;
 
 vec3 compute_light(Light light, vec3 normal) {
@@ -32,8 +39,26 @@ vec3 compute_light(Light light, vec3 normal) {
     return color * intensity * ndotl;
 }
 
+// Black and white bands of an environment reflected in the surface: the
+// bands bend smoothly across tangent-continuous joins and kink at creases.
+vec3 zebra(vec3 normal) {
+    vec3 view = normalize(v_world_pos - pc.camera_pos.xyz);
+    vec3 reflected = reflect(view, normal);
+    float angle = acos(clamp(dot(reflected, normalize(v_zebra.xyz)), -1.0, 1.0));
+    float band = fract(angle / 3.14159265 * v_zebra.w);
+    return band < 0.5 ? vec3(0.05) : vec3(0.95);
+}
+
 void main() {
-    vec3 normal = normalize(v_normal);
+    if (v_clip > 0.5 && dot(pc.clip_plane.xyz, v_world_pos) < pc.clip_plane.w) {
+        discard;
+    }
+    // Back faces are only drawn for translucent bodies; light them as seen.
+    vec3 normal = gl_FrontFacing ? normalize(v_normal) : -normalize(v_normal);
+    if (v_zebra.w > 0.0) {
+        out_color = vec4(zebra(normal), v_opacity);
+        return;
+    }
     
     // Compute contribution from each light
     vec3 main_contrib = compute_light(pc.light_main, normal);
@@ -44,5 +69,5 @@ void main() {
     vec3 lighting = pc.ambient.rgb + main_contrib + back_contrib + fill_contrib;
     
     vec3 color = clamp(v_color * lighting, 0.0, 1.0);
-    out_color = vec4(color, 1.0);
+    out_color = vec4(color, v_opacity);
 }
@@ -3,10 +3,16 @@
 layout(location = 0) in vec3 in_pos;
 layout(location = 1) in vec3 in_normal;
 layout(location = 2) in vec3 in_color;
+layout(location = 3) in vec4 in_zebra;
+layout(location = 4) in float in_opacity;
+layout(location = 5) in float in_clip;
 
 layout(location = 0) out vec3 v_world_pos;
 layout(location = 1) out vec3 v_normal;
 layout(location = 2) out vec3 v_color;
+layout(location = 3) flat out vec4 v_zebra;
+layout(location = 4) flat out float v_opacity;
+layout(location = 5) flat out float v_clip;
 
 // Light structure (must match fragment shader)
 struct Light {
@@ -21,11 +27,15 @@ layout(push_constant) uniform PushConstants {
     Light light_back;
     Light light_fill;
     vec4 ambient;
+    vec4 clip_plane;
 } pc;
 
 void main() {
     v_world_pos = in_pos;
     v_normal = normalize(in_normal);
     v_color = in_color;
+    v_zebra = in_zebra;
+    v_opacity = in_opacity;
+    v_clip = in_clip;
     gl_Position = pc.view_proj * vec4(in_pos, 1.0);
 }
@@ -1,13 +1,19 @@
 #version 450
 
+layout(location = 0) in vec3 v_world_pos;
+
 layout(location = 0) out uvec4 out_id;
 
 layout(push_constant) uniform PushConstants {
     mat4 view_proj;
     uvec4 object_id;  // Object ID encoded as 4 uints (UUID = 128 bits)
+    vec4 clip_plane;  // fragments with dot(xyz, pos) < w are cut away
 } pc;
 
 void main() {
+    if (dot(pc.clip_plane.xyz, v_world_pos) < pc.clip_plane.w) {
+        discard;
+    }
     // Output the object ID directly - the depth is automatically written to depth buffer
     out_id = pc.object_id;
 }
@@ -4,12 +4,16 @@ layout(location = 0) in vec3 in_pos;
 layout(location = 1) in vec3 in_normal;
 layout(location = 2) in vec3 in_color;
 
+layout(location = 0) out vec3 v_world_pos;
+
 layout(push_constant) uniform PushConstants {
     mat4 view_proj;
     uvec4 object_id;  // Object ID encoded as 4 uints (UUID = 128 bits)
+    vec4 clip_plane;  // fragments with dot(xyz, pos) < w are cut away
 } pc;
 
 void main() {
+    v_world_pos = in_pos;
     gl_Position = pc.view_proj * vec4(in_pos, 1.0);
 }
 
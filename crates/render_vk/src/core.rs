@@ -16,8 +16,8 @@ use winit::window::Window;
 use crate::{
     find_depth_format, get_max_usable_sample_count, identity_matrix, is_srgb_format, map_egui_err,
     mesh::MeshRenderer, msaa_samples_to_vk, picking::PickRenderer, surface, util::find_memory_type,
-    FrameSubmission, PickResult, RenderError, RenderSettings, ViewportRect, MAX_FRAMES_IN_FLIGHT,
-    VALIDATION_LAYER,
+    FrameSubmission, MeshMemory, PickResult, RenderError, RenderSettings, ViewportRect,
+    MAX_FRAMES_IN_FLIGHT, VALIDATION_LAYER,
 };
 
 pub(crate) struct RendererCore {
@@ -72,6 +72,7 @@ pub(crate) struct RendererCore {
     last_pick_result: PickResult,
     // View-projection and viewport used for the last picking pass that was submitted
     // (used for unprojection when reading back the pick result)
+    pending_pick_render_origin: [f64; 3],
     pending_pick_view_proj: [[f32; 4]; 4],
     pending_pick_viewport_rect: ViewportRect,
 }
@@ -209,6 +210,7 @@ impl RendererCore {
             last_frame_bodies: Vec::new(),
             pending_pick: None,
             last_pick_result: PickResult::default(),
+            pending_pick_render_origin: [0.0; 3],
             pending_pick_view_proj: identity_matrix(),
             pending_pick_viewport_rect: ViewportRect::default(),
         };
@@ -301,6 +303,17 @@ impl RendererCore {
         &self.available_gpus
     }
 
+    pub(crate) fn mesh_memory(&self) -> MeshMemory {
+        let bodies = self
+            .mesh_renderer
+            .as_ref()
+            .map(|renderer| renderer.uploaded().to_vec())
+            .unwrap_or_default();
+        let allocated = self.mesh_renderer.as_ref().map_or(0, |r| r.allocated())
+            + self.pick_renderer.as_ref().map_or(0, |r| r.allocated());
+        MeshMemory { bodies, allocated }
+    }
+
     pub(crate) fn request_pick(&mut self, x: u32, y: u32) {
         self.pending_pick = Some((x, y));
     }
@@ -444,6 +457,7 @@ impl RendererCore {
                     self.graphics_queue,
                     x,
                     y,
+                    self.pending_pick_render_origin,
                     self.pending_pick_view_proj,
                     &self.pending_pick_viewport_rect,
                 ) {
@@ -628,6 +642,7 @@ impl RendererCore {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_image(
         &self,
         width: u32,
@@ -1015,13 +1030,16 @@ impl RendererCore {
                 &self.device,
                 command_buffer,
                 &frame.bodies,
+                frame.render_origin,
                 frame.view_proj,
                 frame.viewport_rect.as_ref(),
+                frame.clip_plane.as_ref(),
                 &self.memory_properties,
             )?;
 
             // Store the view_proj used for this picking pass
             // When this frame completes, these become the "current" pick matrices
+            self.pending_pick_render_origin = frame.render_origin;
             self.pending_pick_view_proj = frame.view_proj;
             self.pending_pick_viewport_rect = frame.viewport_rect.unwrap_or(ViewportRect {
                 x: 0,
@@ -1093,9 +1111,11 @@ impl RendererCore {
                 self.swapchain_extent,
                 frame.viewport_rect.as_ref(),
                 &frame.bodies,
+                frame.render_origin,
                 frame.view_proj,
                 frame.camera_pos,
                 &frame.lighting,
+                frame.clip_plane.as_ref(),
             )?;
         }
 
@@ -1479,10 +1499,7 @@ fn choose_surface_format(available_formats: &[vk::SurfaceFormatKHR]) -> vk::Surf
 }
 
 fn choose_present_mode(available_present_modes: &[vk::PresentModeKHR]) -> vk::PresentModeKHR {
-    if available_present_modes
-        .iter()
-        .any(|&mode| mode == vk::PresentModeKHR::MAILBOX)
-    {
+    if available_present_modes.contains(&vk::PresentModeKHR::MAILBOX) {
         vk::PresentModeKHR::MAILBOX
     } else {
         vk::PresentModeKHR::FIFO
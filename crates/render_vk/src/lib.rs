@@ -123,10 +123,14 @@ fn get_max_usable_sample_count(
 pub struct PickResult {
     /// The UUID of the picked body, if any
     pub body_id: Option<Uuid>,
-    /// The 3D world position under the cursor (if geometry was hit)
-    pub world_position: Option<[f32; 3]>,
+    /// The 3D world position under the cursor (if geometry was hit), in
+    /// double precision like the body positions
+    pub world_position: Option<[f64; 3]>,
     /// Depth value (0.0 = near, 1.0 = far)
     pub depth: f32,
+    /// Normal of the surface under the cursor, facing the viewer (None when
+    /// the neighbouring pixels miss the geometry)
+    pub normal: Option<[f32; 3]>,
 }
 
 /// Trait used by the app shell to talk to any renderer implementation.
@@ -158,6 +162,15 @@ impl Default for RenderSettings {
     }
 }
 
+/// GPU memory used for body meshes, as of the last frame.
+#[derive(Debug, Clone, Default)]
+pub struct MeshMemory {
+    /// Vertex and index bytes drawn per body (picking keeps a second copy).
+    pub bodies: Vec<(Uuid, usize)>,
+    /// Bytes allocated for the mesh and picking buffers.
+    pub allocated: usize,
+}
+
 /// Highlight state for a body
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum HighlightState {
@@ -168,13 +181,44 @@ pub enum HighlightState {
     HoveredAndSelected,
 }
 
+/// How a body's surface is shaded.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Shading {
+    /// Body color under the scene lights.
+    #[default]
+    Lit,
+    /// Reflection of black and white bands around `axis` (`stripes` bands
+    /// per half turn), for judging how smoothly surfaces join.
+    Zebra { axis: [f32; 3], stripes: f32 },
+}
+
 /// Render-ready body (mesh + unique identifier for future picking).
 #[derive(Clone)]
 pub struct BodySubmission {
     pub id: Uuid,
+    /// Geometry relative to `offset`.
     pub mesh: TriMesh,
+    /// World position the mesh is relative to (its body's frame). Vertices
+    /// are moved next to the camera in double precision before upload.
+    pub offset: [f64; 3],
     pub color: [f32; 3],
     pub highlight: HighlightState,
+    pub shading: Shading,
+    /// 1.0 is opaque; below that the body is blended over what is behind it.
+    pub opacity: f32,
+    /// Whether the frame's clip plane cuts this body (false for helpers such as
+    /// the faces filling a section, which lie on the plane).
+    pub clipped: bool,
+}
+
+/// Plane cutting the clipped bodies of a frame: what lies behind it (opposite
+/// `normal`) is neither drawn nor picked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    /// A point on the plane, in world space.
+    pub point: [f64; 3],
+    /// Unit normal pointing to the part that is kept.
+    pub normal: [f32; 3],
 }
 
 impl fmt::Debug for BodySubmission {
@@ -199,26 +243,35 @@ pub struct ViewportRect {
 /// Minimal scene data required to emit a frame.
 pub struct FrameSubmission {
     pub bodies: Vec<BodySubmission>,
+    /// World position vertices are made relative to (in double precision)
+    /// before upload, so parts far from the origin don't jitter on the GPU.
+    /// Usually the eye; `view_proj` maps positions relative to it.
+    pub render_origin: [f64; 3],
     pub view_proj: [[f32; 4]; 4],
-    pub camera_pos: [f32; 3],
+    /// Eye position in world space.
+    pub camera_pos: [f64; 3],
     pub lighting: LightingData,
     pub egui: Option<EguiSubmission>,
     /// The 3D viewport rect (area where mesh should be rendered)
     pub viewport_rect: Option<ViewportRect>,
     /// Screen-space overlays (constant-thickness lines rendered in 2D screen coordinates)
     pub screen_space_overlays: Vec<ScreenSpaceOverlay>,
+    /// Section plane cutting the clipped bodies (None draws them whole)
+    pub clip_plane: Option<ClipPlane>,
 }
 
 impl Default for FrameSubmission {
     fn default() -> Self {
         Self {
             bodies: Vec::new(),
+            render_origin: [0.0; 3],
             view_proj: identity_matrix(),
             camera_pos: [0.0, 0.0, 5.0],
             lighting: LightingData::default(),
             egui: None,
             viewport_rect: None,
             screen_space_overlays: Vec::new(),
+            clip_plane: None,
         }
     }
 }
@@ -227,6 +280,7 @@ impl fmt::Debug for FrameSubmission {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FrameSubmission")
             .field("body_count", &self.bodies.len())
+            .field("render_origin", &self.render_origin)
             .field("view_proj", &self.view_proj)
             .field("camera_pos", &self.camera_pos)
             .field(
@@ -283,6 +337,13 @@ impl VulkanRenderer {
         self.core.as_ref().map(|c| c.available_gpus())
     }
 
+    pub fn mesh_memory(&self) -> MeshMemory {
+        self.core
+            .as_ref()
+            .map(|c| c.mesh_memory())
+            .unwrap_or_default()
+    }
+
     fn ensure_swapchain(&mut self) -> Result<(), RenderError> {
         let core = self.core.as_mut().ok_or(RenderError::NotReady)?;
         if let Some(extent) = self.pending_extent {
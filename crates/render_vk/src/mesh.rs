@@ -1,8 +1,11 @@
 use ash::vk;
+use kernel_api::TriMesh;
 use std::mem::size_of;
+use uuid::Uuid;
 
 use crate::{
-    util::create_buffer, BodySubmission, HighlightState, RenderError, ViewportRect, MESH_FRAG_SPV,
+    util::{clip_plane_vector, create_buffer, relative_to, resized_capacity},
+    BodySubmission, ClipPlane, HighlightState, RenderError, Shading, ViewportRect, MESH_FRAG_SPV,
     MESH_VERT_SPV,
 };
 
@@ -13,6 +16,11 @@ pub(crate) struct MeshVertex {
     position: [f32; 3],
     normal: [f32; 3],
     color: [f32; 3],
+    /// Zebra stripe axis and count; no stripes when the count is zero.
+    zebra: [f32; 4],
+    opacity: f32,
+    /// 1 where the frame's clip plane applies, 0 where it doesn't.
+    clip: f32,
 }
 
 impl MeshVertex {
@@ -21,10 +29,80 @@ impl MeshVertex {
             position,
             normal,
             color,
+            zebra: [0.0; 4],
+            opacity: 1.0,
+            clip: 0.0,
         }
     }
 }
 
+fn is_translucent(body: &BodySubmission) -> bool {
+    body.opacity < 1.0
+}
+
+/// Opaque bodies in submission order, then translucent ones.
+fn draw_order(bodies: &[BodySubmission]) -> Vec<&BodySubmission> {
+    let (mut order, mut translucent): (Vec<_>, Vec<_>) =
+        bodies.iter().partition(|body| !is_translucent(body));
+    order.append(&mut translucent);
+    order
+}
+
+/// Triangles of `mesh` as indices into its positions.
+fn triangles(mesh: &TriMesh) -> Vec<[u32; 3]> {
+    if mesh.indices.is_empty() {
+        (0..(mesh.positions.len() / 3) as u32)
+            .map(|t| [3 * t, 3 * t + 1, 3 * t + 2])
+            .collect()
+    } else {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect()
+    }
+}
+
+/// Indices of the triangles of the translucent `bodies` (whose vertices are
+/// uploaded from `base_vertex` on, in order) from the farthest to the
+/// nearest, so each blends over what is behind it, including the far side
+/// of the same body and bodies inside it.
+fn sorted_translucent_indices(
+    bodies: &[&BodySubmission],
+    mut base_vertex: u32,
+    camera_pos: [f64; 3],
+) -> Vec<u32> {
+    let mut sorted: Vec<(f32, [u32; 3])> = Vec::new();
+    for body in bodies {
+        // Compared in the body's frame, like its positions.
+        let camera = glam::Vec3::from(relative_to([0.0; 3], camera_pos, body.offset));
+        let positions = &body.mesh.positions;
+        sorted.extend(triangles(&body.mesh).into_iter().map(|triangle| {
+            let centroid = triangle
+                .iter()
+                .map(|&i| glam::Vec3::from(positions[i as usize]))
+                .sum::<glam::Vec3>()
+                / 3.0;
+            (
+                centroid.distance_squared(camera),
+                triangle.map(|i| base_vertex + i),
+            )
+        }));
+        base_vertex += positions.len() as u32;
+    }
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    sorted
+        .into_iter()
+        .flat_map(|(_, triangle)| triangle)
+        .collect()
+}
+
+fn zebra_attribute(shading: Shading) -> [f32; 4] {
+    match shading {
+        Shading::Lit => [0.0; 4],
+        Shading::Zebra { axis, stripes } => [axis[0], axis[1], axis[2], stripes],
+    }
+}
+
 fn apply_highlight_color(base: [f32; 3], highlight: HighlightState) -> [f32; 3] {
     match highlight {
         HighlightState::None => base,
@@ -85,10 +163,16 @@ struct MeshPushConstants {
     light_back: GpuLight,
     light_fill: GpuLight,
     ambient: [f32; 4],
+    clip_plane: [f32; 4],
 }
 
 impl MeshPushConstants {
-    fn new(view_proj: [[f32; 4]; 4], camera_pos: [f32; 3], lights: &LightingData) -> Self {
+    fn new(
+        view_proj: [[f32; 4]; 4],
+        camera_pos: [f32; 3],
+        lights: &LightingData,
+        clip_plane: [f32; 4],
+    ) -> Self {
         Self {
             view_proj,
             camera_pos: [camera_pos[0], camera_pos[1], camera_pos[2], 1.0],
@@ -101,6 +185,7 @@ impl MeshPushConstants {
                 lights.ambient_color[2] * lights.ambient_intensity,
                 1.0,
             ],
+            clip_plane,
         }
     }
 }
@@ -114,8 +199,12 @@ pub(crate) struct MeshRenderer {
     index_buffer: vk::Buffer,
     index_memory: vk::DeviceMemory,
     index_capacity: usize,
+    /// Vertex and index bytes uploaded per body in the last frame.
+    uploaded: Vec<(Uuid, usize)>,
     pipeline_layout: vk::PipelineLayout,
     pipeline: vk::Pipeline,
+    /// Blends translucent bodies over the scene without writing depth.
+    blend_pipeline: vk::Pipeline,
     msaa_samples: vk::SampleCountFlags,
 }
 
@@ -132,7 +221,10 @@ impl MeshRenderer {
             unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
         let pipeline_layout = create_mesh_pipeline_layout(&device)?;
-        let pipeline = create_mesh_pipeline(&device, render_pass, pipeline_layout, msaa_samples)?;
+        let pipeline =
+            create_mesh_pipeline(&device, render_pass, pipeline_layout, msaa_samples, false)?;
+        let blend_pipeline =
+            create_mesh_pipeline(&device, render_pass, pipeline_layout, msaa_samples, true)?;
 
         Ok(Self {
             device,
@@ -143,8 +235,10 @@ impl MeshRenderer {
             index_buffer: vk::Buffer::null(),
             index_memory: vk::DeviceMemory::null(),
             index_capacity: 0,
+            uploaded: Vec::new(),
             pipeline_layout,
             pipeline,
+            blend_pipeline,
             msaa_samples,
         })
     }
@@ -156,6 +250,7 @@ impl MeshRenderer {
     ) -> Result<(), RenderError> {
         unsafe {
             self.device.destroy_pipeline(self.pipeline, None);
+            self.device.destroy_pipeline(self.blend_pipeline, None);
         }
         self.msaa_samples = msaa_samples;
         self.pipeline = create_mesh_pipeline(
@@ -163,21 +258,33 @@ impl MeshRenderer {
             render_pass,
             self.pipeline_layout,
             msaa_samples,
+            false,
+        )?;
+        self.blend_pipeline = create_mesh_pipeline(
+            &self.device,
+            render_pass,
+            self.pipeline_layout,
+            msaa_samples,
+            true,
         )?;
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw(
         &mut self,
         command_buffer: vk::CommandBuffer,
         swapchain_extent: vk::Extent2D,
         viewport_rect: Option<&ViewportRect>,
         bodies: &[BodySubmission],
+        render_origin: [f64; 3],
         view_proj: [[f32; 4]; 4],
-        camera_pos: [f32; 3],
+        camera_pos: [f64; 3],
         lighting: &LightingData,
+        clip_plane: Option<&ClipPlane>,
     ) -> Result<(), RenderError> {
-        let index_count = self.upload_meshes(bodies)?;
+        let order = draw_order(bodies);
+        let (opaque_count, index_count) = self.upload_meshes(&order, render_origin, camera_pos)?;
         if index_count == 0 {
             return Ok(());
         }
@@ -232,7 +339,9 @@ impl MeshRenderer {
                 0,
                 vk::IndexType::UINT32,
             );
-            let push = MeshPushConstants::new(view_proj, camera_pos, lighting);
+            let camera_pos = relative_to([0.0; 3], camera_pos, render_origin);
+            let clip_plane = clip_plane_vector(clip_plane, render_origin);
+            let push = MeshPushConstants::new(view_proj, camera_pos, lighting, clip_plane);
             let push_bytes = std::slice::from_raw_parts(
                 &push as *const _ as *const u8,
                 size_of::<MeshPushConstants>(),
@@ -244,35 +353,93 @@ impl MeshRenderer {
                 0,
                 push_bytes,
             );
-            self.device
-                .cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+            if opaque_count > 0 {
+                self.device
+                    .cmd_draw_indexed(command_buffer, opaque_count, 1, 0, 0, 0);
+            }
+            if index_count > opaque_count {
+                // Same layout, so the push constants carry over.
+                self.device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    self.blend_pipeline,
+                );
+                self.device.cmd_draw_indexed(
+                    command_buffer,
+                    index_count - opaque_count,
+                    1,
+                    opaque_count,
+                    0,
+                    0,
+                );
+            }
         }
 
         Ok(())
     }
 
-    fn upload_meshes(&mut self, bodies: &[BodySubmission]) -> Result<u32, RenderError> {
-        let vertex_count: usize = bodies.iter().map(|b| b.mesh.positions.len()).sum();
-        if vertex_count == 0 {
-            return Ok(0);
-        }
-        let index_count: usize = bodies
+    /// Vertex and index bytes uploaded per body in the last frame.
+    pub fn uploaded(&self) -> &[(Uuid, usize)] {
+        &self.uploaded
+    }
+
+    /// Bytes allocated for the vertex and index buffers.
+    pub fn allocated(&self) -> usize {
+        self.vertex_capacity + self.index_capacity
+    }
+
+    /// Upload `bodies` in draw order, returning the number of indices of the
+    /// opaque bodies (which come first) and of all bodies. The triangles of
+    /// the translucent bodies are sorted back to front as seen from
+    /// `camera_pos`.
+    fn upload_meshes(
+        &mut self,
+        bodies: &[&BodySubmission],
+        render_origin: [f64; 3],
+        camera_pos: [f64; 3],
+    ) -> Result<(u32, u32), RenderError> {
+        self.uploaded = bodies
             .iter()
             .map(|body| {
                 let mesh = &body.mesh;
-                if mesh.indices.is_empty() {
+                let indices = if mesh.indices.is_empty() {
                     (mesh.positions.len() / 3) * 3
                 } else {
                     mesh.indices.len()
-                }
+                };
+                let bytes =
+                    mesh.positions.len() * size_of::<MeshVertex>() + indices * size_of::<u32>();
+                (body.id, bytes)
             })
-            .sum();
+            .collect();
+        let vertex_count: usize = bodies.iter().map(|b| b.mesh.positions.len()).sum();
+        let indices = |body: &BodySubmission| {
+            let mesh = &body.mesh;
+            if mesh.indices.is_empty() {
+                (mesh.positions.len() / 3) * 3
+            } else {
+                mesh.indices.len()
+            }
+        };
+        let opaque_bodies = bodies
+            .iter()
+            .take_while(|body| !is_translucent(body))
+            .count();
+        let (opaque, translucent) = bodies.split_at(opaque_bodies);
+        let opaque_count: usize = opaque.iter().map(|body| indices(body)).sum();
+        let opaque_vertices: usize = opaque.iter().map(|b| b.mesh.positions.len()).sum();
+        let translucent_indices =
+            sorted_translucent_indices(translucent, opaque_vertices as u32, camera_pos);
+        let index_count = opaque_count + translucent_indices.len();
 
         let vertex_bytes = vertex_count * size_of::<MeshVertex>();
         let index_bytes = index_count * size_of::<u32>();
 
         self.ensure_vertex_capacity(vertex_bytes)?;
         self.ensure_index_capacity(index_bytes)?;
+        if vertex_count == 0 {
+            return Ok((0, 0));
+        }
 
         unsafe {
             let vertex_ptr = self
@@ -290,9 +457,16 @@ impl MeshRenderer {
             for body in bodies {
                 let mesh = &body.mesh;
                 let final_color = apply_highlight_color(body.color, body.highlight);
+                let zebra = zebra_attribute(body.shading);
                 for (i, position) in mesh.positions.iter().enumerate() {
                     let normal = mesh.normals.get(i).cloned().unwrap_or([0.0, 1.0, 0.0]);
-                    vertex_slice[v_offset] = MeshVertex::new(*position, normal, final_color);
+                    let position = relative_to(*position, body.offset, render_origin);
+                    vertex_slice[v_offset] = MeshVertex {
+                        zebra,
+                        opacity: body.opacity,
+                        clip: if body.clipped { 1.0 } else { 0.0 },
+                        ..MeshVertex::new(position, normal, final_color)
+                    };
                     v_offset += 1;
                 }
             }
@@ -311,7 +485,7 @@ impl MeshRenderer {
 
             let mut i_offset = 0usize;
             let mut base_vertex = 0u32;
-            for body in bodies {
+            for body in opaque {
                 let mesh = &body.mesh;
                 if mesh.indices.is_empty() {
                     for i in 0..mesh.positions.len() {
@@ -326,19 +500,21 @@ impl MeshRenderer {
                 }
                 base_vertex += mesh.positions.len() as u32;
             }
+            index_slice[i_offset..].copy_from_slice(&translucent_indices);
             self.device.unmap_memory(self.index_memory);
         }
 
-        Ok(index_count as u32)
+        Ok((opaque_count as u32, index_count as u32))
     }
 
     fn ensure_vertex_capacity(&mut self, required: usize) -> Result<(), RenderError> {
-        if required <= self.vertex_capacity {
+        let Some(new_capacity) = resized_capacity(self.vertex_capacity, required) else {
             return Ok(());
-        }
-        let new_capacity = required.next_power_of_two().max(1024);
+        };
         if self.vertex_buffer != vk::Buffer::null() {
             unsafe {
+                // The buffer may still be read by a frame in flight.
+                self.device.device_wait_idle().map_err(RenderError::from)?;
                 self.device.destroy_buffer(self.vertex_buffer, None);
                 self.device.free_memory(self.vertex_memory, None);
             }
@@ -357,12 +533,13 @@ impl MeshRenderer {
     }
 
     fn ensure_index_capacity(&mut self, required: usize) -> Result<(), RenderError> {
-        if required <= self.index_capacity {
+        let Some(new_capacity) = resized_capacity(self.index_capacity, required) else {
             return Ok(());
-        }
-        let new_capacity = required.next_power_of_two().max(1024);
+        };
         if self.index_buffer != vk::Buffer::null() {
             unsafe {
+                // The buffer may still be read by a frame in flight.
+                self.device.device_wait_idle().map_err(RenderError::from)?;
                 self.device.destroy_buffer(self.index_buffer, None);
                 self.device.free_memory(self.index_memory, None);
             }
@@ -383,6 +560,7 @@ impl MeshRenderer {
     pub fn destroy(self) {
         unsafe {
             self.device.destroy_pipeline(self.pipeline, None);
+            self.device.destroy_pipeline(self.blend_pipeline, None);
             self.device
                 .destroy_pipeline_layout(self.pipeline_layout, None);
             self.device.destroy_buffer(self.vertex_buffer, None);
@@ -398,6 +576,7 @@ fn create_mesh_pipeline(
     render_pass: vk::RenderPass,
     layout: vk::PipelineLayout,
     msaa_samples: vk::SampleCountFlags,
+    translucent: bool,
 ) -> Result<vk::Pipeline, RenderError> {
     let vert_module = create_shader_module(device, MESH_VERT_SPV)?;
     let frag_module = create_shader_module(device, MESH_FRAG_SPV)?;
@@ -435,6 +614,21 @@ fn create_mesh_pipeline(
             .location(2)
             .format(vk::Format::R32G32B32_SFLOAT)
             .offset(24),
+        vk::VertexInputAttributeDescription::default()
+            .binding(0)
+            .location(3)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(36),
+        vk::VertexInputAttributeDescription::default()
+            .binding(0)
+            .location(4)
+            .format(vk::Format::R32_SFLOAT)
+            .offset(52),
+        vk::VertexInputAttributeDescription::default()
+            .binding(0)
+            .location(5)
+            .format(vk::Format::R32_SFLOAT)
+            .offset(56),
     ];
 
     let binding_descs = [binding_desc];
@@ -455,7 +649,12 @@ fn create_mesh_pipeline(
         .rasterizer_discard_enable(false)
         .polygon_mode(vk::PolygonMode::FILL)
         .line_width(1.0)
-        .cull_mode(vk::CullModeFlags::BACK)
+        // Translucent bodies show their far side through the near one.
+        .cull_mode(if translucent {
+            vk::CullModeFlags::NONE
+        } else {
+            vk::CullModeFlags::BACK
+        })
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
         .depth_bias_enable(false);
 
@@ -465,14 +664,20 @@ fn create_mesh_pipeline(
 
     let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::default()
         .depth_test_enable(true)
-        .depth_write_enable(true)
+        .depth_write_enable(!translucent)
         .depth_compare_op(vk::CompareOp::LESS)
         .depth_bounds_test_enable(false)
         .stencil_test_enable(false);
 
     let color_blend_attachment = vk::PipelineColorBlendAttachmentState::default()
         .color_write_mask(vk::ColorComponentFlags::RGBA)
-        .blend_enable(false);
+        .blend_enable(translucent)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .alpha_blend_op(vk::BlendOp::ADD);
 
     let color_blend_attachments = [color_blend_attachment];
     let color_blending = vk::PipelineColorBlendStateCreateInfo::default()
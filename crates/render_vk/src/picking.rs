@@ -6,8 +6,11 @@ use uuid::Uuid;
 use crate::{
     create_shader_module,
     mesh::MeshVertex,
-    util::{create_buffer, create_image, create_image_view},
-    BodySubmission, PickResult, RenderError, ViewportRect, PICK_FRAG_SPV, PICK_VERT_SPV,
+    util::{
+        clip_plane_vector, create_buffer, create_image, create_image_view, relative_to,
+        resized_capacity,
+    },
+    BodySubmission, ClipPlane, PickResult, RenderError, ViewportRect, PICK_FRAG_SPV, PICK_VERT_SPV,
 };
 
 /// Push constants for the picking shader
@@ -16,6 +19,7 @@ use crate::{
 struct PickPushConstants {
     view_proj: [[f32; 4]; 4],
     object_id: [u32; 4], // UUID encoded as 4 u32s
+    clip_plane: [f32; 4],
 }
 
 /// GPU-based picking renderer that renders object IDs to an offscreen buffer
@@ -359,17 +363,20 @@ impl PickRenderer {
     }
 
     /// Record commands to render picking pass
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn record_commands(
         &mut self,
         device: &ash::Device,
         command_buffer: vk::CommandBuffer,
         bodies: &[BodySubmission],
+        render_origin: [f64; 3],
         view_proj: [[f32; 4]; 4],
         viewport_rect: Option<&ViewportRect>,
+        clip_plane: Option<&ClipPlane>,
         memory_properties: &vk::PhysicalDeviceMemoryProperties,
     ) -> Result<(), RenderError> {
         // Upload mesh data
-        self.upload_meshes(device, bodies, memory_properties)?;
+        self.upload_meshes(device, bodies, render_origin, memory_properties)?;
 
         // Begin render pass
         let clear_values = [
@@ -455,6 +462,8 @@ impl PickRenderer {
                 );
 
                 // Draw each body with its unique ID
+                let clip_plane = clip_plane_vector(clip_plane, render_origin);
+                let unclipped = clip_plane_vector(None, render_origin);
                 let mut index_offset = 0u32;
                 for body in bodies {
                     let index_count = if body.mesh.indices.is_empty() {
@@ -466,6 +475,7 @@ impl PickRenderer {
                     let push = PickPushConstants {
                         view_proj,
                         object_id: Self::uuid_to_u32s(body.id),
+                        clip_plane: if body.clipped { clip_plane } else { unclipped },
                     };
                     let push_bytes = std::slice::from_raw_parts(
                         &push as *const _ as *const u8,
@@ -490,6 +500,7 @@ impl PickRenderer {
     }
 
     /// Read back the pick result at a specific pixel
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn read_pick_result(
         &self,
         device: &ash::Device,
@@ -497,6 +508,7 @@ impl PickRenderer {
         queue: vk::Queue,
         x: u32,
         y: u32,
+        render_origin: [f64; 3],
         view_proj: [[f32; 4]; 4],
         viewport: &ViewportRect,
     ) -> Result<PickResult, RenderError> {
@@ -551,34 +563,53 @@ impl PickRenderer {
                 &[id_region],
             );
 
-            // Copy single pixel from depth image to staging buffer (offset 32 for alignment)
-            let depth_region = vk::BufferImageCopy::default()
-                .buffer_offset(32)
-                .buffer_row_length(0)
-                .buffer_image_height(0)
-                .image_subresource(vk::ImageSubresourceLayers {
-                    aspect_mask: vk::ImageAspectFlags::DEPTH,
-                    mip_level: 0,
-                    base_array_layer: 0,
-                    layer_count: 1,
-                })
-                .image_offset(vk::Offset3D {
-                    x: x as i32,
-                    y: y as i32,
-                    z: 0,
-                })
-                .image_extent(vk::Extent3D {
-                    width: 1,
-                    height: 1,
-                    depth: 1,
-                });
+            // Copy the depth of the pixel (offset 32 for alignment) and of its
+            // right and lower neighbours (offsets 36 and 40), which give the
+            // surface normal; at the image border the left or upper neighbour
+            // is used instead.
+            let depth_region = |offset: u64, x: u32, y: u32| {
+                vk::BufferImageCopy::default()
+                    .buffer_offset(offset)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::DEPTH,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .image_offset(vk::Offset3D {
+                        x: x as i32,
+                        y: y as i32,
+                        z: 0,
+                    })
+                    .image_extent(vk::Extent3D {
+                        width: 1,
+                        height: 1,
+                        depth: 1,
+                    })
+            };
+            let neighbour_x = if x + 1 < self.extent.width {
+                x + 1
+            } else {
+                x.saturating_sub(1)
+            };
+            let neighbour_y = if y + 1 < self.extent.height {
+                y + 1
+            } else {
+                y.saturating_sub(1)
+            };
 
             device.cmd_copy_image_to_buffer(
                 command_buffer,
                 self.depth_image,
                 vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
                 self.staging_buffer,
-                &[depth_region],
+                &[
+                    depth_region(32, x, y),
+                    depth_region(36, neighbour_x, y),
+                    depth_region(40, x, neighbour_y),
+                ],
             );
 
             device
@@ -595,9 +626,9 @@ impl PickRenderer {
 
             device.free_command_buffers(command_pool, &[command_buffer]);
 
-            // Read back the data (ID at offset 0, depth at offset 32)
+            // Read back the data (ID at offset 0, depths from offset 32)
             let data_ptr = device
-                .map_memory(self.staging_memory, 0, 36, vk::MemoryMapFlags::empty())
+                .map_memory(self.staging_memory, 0, 44, vk::MemoryMapFlags::empty())
                 .map_err(RenderError::from)? as *const u32;
 
             let id_values = [
@@ -609,6 +640,8 @@ impl PickRenderer {
 
             // Read depth at offset 32 (8 u32s from start)
             let depth = *((data_ptr.add(8)) as *const f32);
+            let depth_x = *((data_ptr.add(9)) as *const f32);
+            let depth_y = *((data_ptr.add(10)) as *const f32);
 
             device.unmap_memory(self.staging_memory);
 
@@ -621,12 +654,46 @@ impl PickRenderer {
 
             // Compute world position by unprojecting the screen coordinates with depth
             // The screen coordinates are in window space, we need to convert to viewport-relative
-            let world_pos = Self::unproject(x as f32, y as f32, depth, viewport, view_proj);
+            let relative = Self::unproject(x as f32, y as f32, depth, viewport, view_proj);
+            let world_pos = [0, 1, 2].map(|i| relative[i] as f64 + render_origin[i]);
+
+            // The surface through the pixel and its neighbours; a neighbour on
+            // the background leaves the normal unknown.
+            let normal = (depth_x < 1.0 && depth_y < 1.0 && neighbour_x != x && neighbour_y != y)
+                .then(|| {
+                    let point = glam::Vec3::from(relative);
+                    let along_x = glam::Vec3::from(Self::unproject(
+                        neighbour_x as f32,
+                        y as f32,
+                        depth_x,
+                        viewport,
+                        view_proj,
+                    )) - point;
+                    let along_y = glam::Vec3::from(Self::unproject(
+                        x as f32,
+                        neighbour_y as f32,
+                        depth_y,
+                        viewport,
+                        view_proj,
+                    )) - point;
+                    let normal = along_x.cross(along_y).try_normalize()?;
+                    let eye = glam::Vec3::from(Self::unproject(
+                        x as f32, y as f32, 0.0, viewport, view_proj,
+                    ));
+                    let facing = if normal.dot(eye - point) < 0.0 {
+                        -normal
+                    } else {
+                        normal
+                    };
+                    Some(facing.to_array())
+                })
+                .flatten();
 
             Ok(PickResult {
                 body_id: Some(uuid),
                 world_position: Some(world_pos),
                 depth,
+                normal,
             })
         }
     }
@@ -671,12 +738,10 @@ impl PickRenderer {
         &mut self,
         device: &ash::Device,
         bodies: &[BodySubmission],
+        render_origin: [f64; 3],
         memory_properties: &vk::PhysicalDeviceMemoryProperties,
     ) -> Result<(), RenderError> {
         let vertex_count: usize = bodies.iter().map(|b| b.mesh.positions.len()).sum();
-        if vertex_count == 0 {
-            return Ok(());
-        }
         let index_count: usize = bodies
             .iter()
             .map(|body| {
@@ -694,6 +759,9 @@ impl PickRenderer {
 
         self.ensure_vertex_capacity(device, vertex_bytes, memory_properties)?;
         self.ensure_index_capacity(device, index_bytes, memory_properties)?;
+        if vertex_count == 0 {
+            return Ok(());
+        }
 
         unsafe {
             let vertex_ptr = device
@@ -711,7 +779,8 @@ impl PickRenderer {
                 let mesh = &body.mesh;
                 for (i, position) in mesh.positions.iter().enumerate() {
                     let normal = mesh.normals.get(i).cloned().unwrap_or([0.0, 1.0, 0.0]);
-                    vertex_slice[v_offset] = MeshVertex::new(*position, normal, body.color);
+                    let position = relative_to(*position, body.offset, render_origin);
+                    vertex_slice[v_offset] = MeshVertex::new(position, normal, body.color);
                     v_offset += 1;
                 }
             }
@@ -756,12 +825,13 @@ impl PickRenderer {
         required: usize,
         memory_properties: &vk::PhysicalDeviceMemoryProperties,
     ) -> Result<(), RenderError> {
-        if required <= self.vertex_capacity {
+        let Some(new_capacity) = resized_capacity(self.vertex_capacity, required) else {
             return Ok(());
-        }
-        let new_capacity = required.next_power_of_two().max(1024);
+        };
         if self.vertex_buffer != vk::Buffer::null() {
             unsafe {
+                // The buffer may still be read by a frame in flight.
+                device.device_wait_idle().map_err(RenderError::from)?;
                 device.destroy_buffer(self.vertex_buffer, None);
                 device.free_memory(self.vertex_memory, None);
             }
@@ -785,12 +855,13 @@ impl PickRenderer {
         required: usize,
         memory_properties: &vk::PhysicalDeviceMemoryProperties,
     ) -> Result<(), RenderError> {
-        if required <= self.index_capacity {
+        let Some(new_capacity) = resized_capacity(self.index_capacity, required) else {
             return Ok(());
-        }
-        let new_capacity = required.next_power_of_two().max(1024);
+        };
         if self.index_buffer != vk::Buffer::null() {
             unsafe {
+                // The buffer may still be read by a frame in flight.
+                device.device_wait_idle().map_err(RenderError::from)?;
                 device.destroy_buffer(self.index_buffer, None);
                 device.free_memory(self.index_memory, None);
             }
@@ -808,6 +879,11 @@ impl PickRenderer {
         Ok(())
     }
 
+    /// Bytes allocated for the vertex and index buffers.
+    pub(crate) fn allocated(&self) -> usize {
+        self.vertex_capacity + self.index_capacity
+    }
+
     pub(crate) fn destroy(self, device: &ash::Device) {
         unsafe {
             device.destroy_pipeline(self.pipeline, None);
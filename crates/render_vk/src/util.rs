@@ -1,7 +1,8 @@
 use ash::vk;
 
-use crate::RenderError;
+use crate::{ClipPlane, RenderError};
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_image(
     device: &ash::Device,
     width: u32,
@@ -101,6 +102,32 @@ pub(crate) fn create_buffer(
     Ok((buffer, memory))
 }
 
+/// `plane` as the `xyz · p >= w` test the shaders keep fragments by, for
+/// positions relative to `origin`. Without a plane every fragment passes.
+pub(crate) fn clip_plane_vector(plane: Option<&ClipPlane>, origin: [f64; 3]) -> [f32; 4] {
+    let Some(plane) = plane else {
+        return [0.0, 0.0, 0.0, -1.0];
+    };
+    let [x, y, z] = plane.normal;
+    let point = [0, 1, 2].map(|i| (plane.point[i] - origin[i]) as f32);
+    [x, y, z, x * point[0] + y * point[1] + z * point[2]]
+}
+
+/// `position`, relative to a body frame at `offset`, relative to `origin`
+/// instead; both differences are taken in double precision.
+pub(crate) fn relative_to(position: [f32; 3], offset: [f64; 3], origin: [f64; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| (position[i] as f64 + offset[i] - origin[i]) as f32)
+}
+
+/// Capacity to reallocate a growable buffer with so it holds `required`
+/// bytes, or None to keep the current one. Buffers grow to the next power of
+/// two and shrink again once less than a quarter is used, so memory freed by
+/// a smaller (or partly evicted) scene goes back to the driver.
+pub(crate) fn resized_capacity(current: usize, required: usize) -> Option<usize> {
+    let fitted = required.next_power_of_two().max(1024);
+    (required > current || (required <= current / 4 && fitted < current)).then_some(fitted)
+}
+
 pub(crate) fn find_memory_type(
     type_filter: u32,
     properties: vk::MemoryPropertyFlags,
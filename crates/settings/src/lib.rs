@@ -1,18 +1,38 @@
-use axes::AxisPreset;
+use axes::{AxisPreset, AxisSystem};
 use directories::ProjectDirs;
+use kernel_api::{CancellationToken, ExchangeFormat, ExchangeOptions};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::{
+    ffi::OsString,
     fs::{self, File},
-    io::BufReader,
+    io::{BufReader, Read},
     path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
+use units::{LengthUnit, UnitFormat};
+
+mod migration;
+
+pub use migration::SETTINGS_VERSION;
 
 const QUALIFIER: &str = "com";
 const ORGANIZATION: &str = "printcad";
 const APPLICATION: &str = "printcad";
 const SETTINGS_FILE: &str = "settings.json";
 const RECENT_FILE_INFO: &str = "recent.json";
+const RECENT_DOCUMENTS: &str = "recent_documents.json";
+const SESSION_FILE: &str = "session.json";
+/// Number of documents kept in the recent documents list.
+pub const MAX_RECENT_DOCUMENTS: usize = 10;
+const PROFILES_DIR: &str = "profiles";
+const PROFILE_EXTENSION: &str = "json";
+const UNREADABLE_SUFFIX: &str = "unreadable";
+const RECOVERY_SUFFIX: &str = "autosave.prtcad";
 
 #[derive(Debug, Error)]
 pub enum SettingsError {
@@ -22,38 +42,94 @@ pub enum SettingsError {
     Io(#[from] std::io::Error),
     #[error("invalid settings file: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("invalid profile name `{0}`")]
+    InvalidProfileName(String),
+    #[error("profile `{0}` not found")]
+    ProfileNotFound(String),
+    #[error("file watcher error: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("settings version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("`{command}` failed: {message}")]
+    HookFailed { command: String, message: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserSettings {
+    /// Format version of the file these settings were read from; see [`SETTINGS_VERSION`]
+    #[serde(default)]
+    pub settings_version: u32,
     pub camera: CameraSettings,
     pub lighting: LightingSettings,
     pub rendering: RenderingSettings,
+    #[serde(default)]
+    pub input: InputSettings,
+    #[serde(default)]
+    pub snapping: SnappingSettings,
+    #[serde(default)]
+    pub units: UnitsSettings,
+    #[serde(default)]
+    pub autosave: AutosaveSettings,
+    #[serde(default)]
+    pub exchange: ExchangeSettings,
+    #[serde(default)]
+    pub printers: PrinterSettings,
+    #[serde(default)]
+    pub library: LibrarySettings,
+    #[serde(default)]
+    pub panels: PanelLayout,
     /// Preferred GPU name substring for Vulkan device selection (None = automatic)
     pub preferred_gpu: Option<String>,
     /// Optional FPS cap. 0.0 = uncapped (driven by vsync / driver).
     pub fps_cap: f32,
+    /// Name of the profile these settings were last loaded from or saved as (None = unsaved)
+    #[serde(default)]
+    pub active_profile: Option<String>,
 }
 
 impl Default for UserSettings {
     fn default() -> Self {
         Self {
+            settings_version: SETTINGS_VERSION,
             camera: CameraSettings::default(),
             lighting: LightingSettings::default(),
             rendering: RenderingSettings::default(),
+            input: InputSettings::default(),
+            snapping: SnappingSettings::default(),
+            units: UnitsSettings::default(),
+            autosave: AutosaveSettings::default(),
+            exchange: ExchangeSettings::default(),
+            printers: PrinterSettings::default(),
+            library: LibrarySettings::default(),
+            panels: PanelLayout::default(),
             preferred_gpu: None,
             fps_cap: 0.0,
+            active_profile: None,
         }
     }
 }
 
 /// Rendering quality settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenderingSettings {
     /// MSAA sample count (1 = disabled, 2, 4, or 8)
     pub msaa_samples: u8,
     /// Whether to show the in-app log panel at the bottom of the viewport
     pub show_log_panel: bool,
+    /// Memory for cached body meshes before the least recently viewed are
+    /// dropped, in MiB (0 = unlimited)
+    pub mesh_memory_budget_mb: u32,
+    /// Draw a triad with labeled arrows at the world origin
+    pub show_origin_triad: bool,
+    /// Draw the world axes as lines through the origin across the whole scene
+    pub show_axis_lines: bool,
+    /// Draw a scale bar in the corner of the viewport
+    pub show_scale_bar: bool,
+    /// Draw rulers along the top and left edges of the viewport
+    pub show_rulers: bool,
+    /// Section plane cutting the bodies in the viewport
+    pub section: SectionSettings,
 }
 
 impl Default for RenderingSettings {
@@ -61,12 +137,918 @@ impl Default for RenderingSettings {
         Self {
             msaa_samples: 4, // 4x MSAA by default
             show_log_panel: false,
+            mesh_memory_budget_mb: 2048,
+            show_origin_triad: true,
+            show_axis_lines: false,
+            show_scale_bar: true,
+            show_rulers: false,
+            section: SectionSettings::default(),
+        }
+    }
+}
+
+/// Section view: bodies are cut by a plane normal to a world axis (or aligned to the view or a
+/// datum plane), and the cut faces can be filled so solids don't look hollow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SectionSettings {
+    pub enabled: bool,
+    /// World axis the section plane is normal to
+    pub axis: SectionAxis,
+    /// Plane aligned to the view or taken from a datum plane, used instead of `axis` when set
+    pub custom_plane: Option<SectionPlane>,
+    /// Position of the plane along its normal (mm)
+    pub offset: f32,
+    /// Keep the part below the plane instead of the part above it
+    pub flip: bool,
+    pub fill: SectionFill,
+    /// Distance between hatch lines (mm)
+    pub hatch_spacing: f32,
+}
+
+impl Default for SectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            axis: SectionAxis::X,
+            custom_plane: None,
+            offset: 0.0,
+            flip: false,
+            fill: SectionFill::Hatched,
+            hatch_spacing: 2.0,
+        }
+    }
+}
+
+impl SectionSettings {
+    /// Point on the section plane and the unit normal pointing to the part that is kept, with the
+    /// offset and flip applied. The point is in world coordinates, in double precision like body
+    /// positions.
+    pub fn plane(&self) -> ([f64; 3], [f32; 3]) {
+        let (origin, normal) = match &self.custom_plane {
+            Some(plane) => (plane.origin, plane.normal),
+            None => ([0.0; 3], self.axis.direction()),
+        };
+        let length = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let normal = if length > f32::EPSILON {
+            normal.map(|c| c / length)
+        } else {
+            SectionAxis::X.direction()
+        };
+        let origin = [0, 1, 2].map(|i| origin[i] + f64::from(normal[i] * self.offset));
+        let normal = if self.flip {
+            normal.map(|c| -c)
+        } else {
+            normal
+        };
+        (origin, normal)
+    }
+}
+
+/// Section plane placed in space rather than normal to a world axis
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SectionPlane {
+    /// Point on the plane, in world coordinates
+    pub origin: [f64; 3],
+    /// Normal pointing to the part that is kept
+    pub normal: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum SectionAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl SectionAxis {
+    pub const ALL: [SectionAxis; 3] = [SectionAxis::X, SectionAxis::Y, SectionAxis::Z];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SectionAxis::X => "X",
+            SectionAxis::Y => "Y",
+            SectionAxis::Z => "Z",
+        }
+    }
+
+    /// Unit vector along the axis
+    pub fn direction(&self) -> [f32; 3] {
+        match self {
+            SectionAxis::X => [1.0, 0.0, 0.0],
+            SectionAxis::Y => [0.0, 1.0, 0.0],
+            SectionAxis::Z => [0.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// How the cut faces of a section are filled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum SectionFill {
+    /// Cut faces are left open
+    None,
+    Solid,
+    Hatched,
+}
+
+impl SectionFill {
+    pub const ALL: [SectionFill; 3] = [SectionFill::None, SectionFill::Solid, SectionFill::Hatched];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SectionFill::None => "None",
+            SectionFill::Solid => "Solid",
+            SectionFill::Hatched => "Hatched",
+        }
+    }
+}
+
+/// Panels of the main window that can be docked or floated
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PanelId {
+    /// Model tree and the workbench's tools
+    Model,
+    /// Workbench properties and task forms
+    Properties,
+    Log,
+    /// Named parameters of the document
+    Parameters,
+    /// Parts of the library folder
+    Library,
+    /// Analyses of the active workbench, such as the print checks
+    Analysis,
+    /// Bill of materials of the document's bodies
+    Bom,
+}
+
+impl PanelId {
+    pub const ALL: [PanelId; 7] = [
+        PanelId::Model,
+        PanelId::Properties,
+        PanelId::Log,
+        PanelId::Parameters,
+        PanelId::Library,
+        PanelId::Analysis,
+        PanelId::Bom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PanelId::Model => "Model",
+            PanelId::Properties => "Properties",
+            PanelId::Log => "Log",
+            PanelId::Parameters => "Parameters",
+            PanelId::Library => "Part Library",
+            PanelId::Analysis => "Analysis",
+            PanelId::Bom => "Bill of Materials",
+        }
+    }
+
+    /// Where the panel is in the default layout
+    pub fn default_dock(&self) -> PanelDock {
+        match self {
+            PanelId::Model => PanelDock::Left,
+            PanelId::Properties | PanelId::Analysis => PanelDock::Right,
+            PanelId::Log => PanelDock::Bottom,
+            PanelId::Parameters | PanelId::Library | PanelId::Bom => PanelDock::Floating,
+        }
+    }
+
+    /// Whether the panel is opened from the menus and can be closed, rather than shown whenever
+    /// it has something to show
+    pub fn closable(&self) -> bool {
+        matches!(self, PanelId::Parameters | PanelId::Library | PanelId::Bom)
+    }
+}
+
+/// Side of the main window a panel is docked to, or its own window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PanelDock {
+    Left,
+    Right,
+    Bottom,
+    Floating,
+}
+
+impl PanelDock {
+    /// Sides of the main window, in the order they are laid out
+    pub const SIDES: [PanelDock; 3] = [PanelDock::Left, PanelDock::Right, PanelDock::Bottom];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PanelDock::Left => "Left",
+            PanelDock::Right => "Right",
+            PanelDock::Bottom => "Bottom",
+            PanelDock::Floating => "Floating",
+        }
+    }
+}
+
+/// Where each panel is placed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PanelPlacement {
+    pub panel: PanelId,
+    pub dock: PanelDock,
+}
+
+/// Arrangement of the dockable panels: panels docked to the same side are tabs, in list order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub panels: Vec<PanelPlacement>,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            panels: PanelId::ALL
+                .iter()
+                .map(|&panel| PanelPlacement {
+                    panel,
+                    dock: panel.default_dock(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PanelLayout {
+    /// Placements with duplicates dropped and panels missing from the list (e.g. added in a
+    /// later version) at their default dock
+    fn placements(&self) -> Vec<PanelPlacement> {
+        let mut placements: Vec<PanelPlacement> = Vec::new();
+        for placement in &self.panels {
+            if !placements.iter().any(|p| p.panel == placement.panel) {
+                placements.push(*placement);
+            }
+        }
+        for panel in PanelId::ALL {
+            if !placements.iter().any(|p| p.panel == panel) {
+                placements.push(PanelPlacement {
+                    panel,
+                    dock: panel.default_dock(),
+                });
+            }
+        }
+        placements
+    }
+
+    pub fn dock(&self, panel: PanelId) -> PanelDock {
+        self.placements()
+            .iter()
+            .find(|p| p.panel == panel)
+            .map_or(panel.default_dock(), |p| p.dock)
+    }
+
+    /// Panels placed at `dock`, in tab order
+    pub fn panels(&self, dock: PanelDock) -> Vec<PanelId> {
+        self.placements()
+            .iter()
+            .filter(|p| p.dock == dock)
+            .map(|p| p.panel)
+            .collect()
+    }
+
+    /// Place `panel` at `dock`, as the tab before `before` if that panel is there, or last
+    pub fn move_panel(&mut self, panel: PanelId, dock: PanelDock, before: Option<PanelId>) {
+        let mut placements = self.placements();
+        placements.retain(|p| p.panel != panel);
+        let index = before
+            .and_then(|before| {
+                placements
+                    .iter()
+                    .position(|p| p.panel == before && p.dock == dock)
+            })
+            .unwrap_or(placements.len());
+        placements.insert(index, PanelPlacement { panel, dock });
+        self.panels = placements;
+    }
+}
+
+/// Display units and precision for lengths shown in the UI
+///
+/// Documents always store millimetres; these only affect presentation and parsing of typed values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnitsSettings {
+    pub display_unit: LengthUnit,
+    /// Digits shown after the decimal point
+    pub decimal_places: u8,
+    /// Show inch values as fractions instead of decimals
+    pub inch_fractions: bool,
+    /// Smallest fraction shown when `inch_fractions` is enabled (e.g. 16 = 1/16")
+    pub fraction_denominator: u32,
+}
+
+impl Default for UnitsSettings {
+    fn default() -> Self {
+        Self {
+            display_unit: LengthUnit::Millimeter,
+            decimal_places: 2,
+            inch_fractions: false,
+            fraction_denominator: 16,
+        }
+    }
+}
+
+impl UnitsSettings {
+    pub fn format(&self) -> UnitFormat {
+        UnitFormat {
+            unit: self.display_unit,
+            decimals: self.decimal_places,
+            fraction_denominator: self.inch_fractions.then_some(self.fraction_denominator),
+        }
+    }
+}
+
+/// Autosave interval and rolling backup copies kept when a document is saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutosaveSettings {
+    /// Periodically write a recovery copy of documents that already have a file on disk and have
+    /// unsaved changes; the document file itself is only written by an explicit save
+    pub enabled: bool,
+    /// Seconds between autosaves
+    pub interval_secs: u32,
+    /// Number of rolling backups (`file.prtcad.bak1` … `bakN`) kept per document; 0 disables backups
+    pub backup_count: u32,
+    /// Directory for backup copies (None = next to the document)
+    pub backup_dir: Option<PathBuf>,
+    /// Steps run in the background after each successful save
+    #[serde(default)]
+    pub hooks: Vec<SaveHook>,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 300,
+            backup_count: 3,
+            backup_dir: None,
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl AutosaveSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.interval_secs.max(1)))
+    }
+
+    /// Path of the recovery copy autosave writes for `document` (`name.autosave.prtcad`), in the
+    /// backup directory if one is set, otherwise next to the document. In the backup directory the
+    /// name also carries a [`document_tag`], so documents of the same name in different folders
+    /// keep their own copies.
+    pub fn recovery_path(&self, document: &Path) -> PathBuf {
+        let file_name = document
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled");
+        let lowered = file_name.to_ascii_lowercase();
+        let stem_len = [".prtcad.zst", ".prtcad.gz", ".prtcad", ".json"]
+            .iter()
+            .find_map(|suffix| lowered.strip_suffix(suffix))
+            .map_or(file_name.len(), str::len);
+        let stem = &file_name[..stem_len];
+        match &self.backup_dir {
+            Some(dir) => dir.join(format!(
+                "{stem}-{}.{RECOVERY_SUFFIX}",
+                document_tag(document)
+            )),
+            None => document.with_file_name(format!("{stem}.{RECOVERY_SUFFIX}")),
+        }
+    }
+
+    /// Path of the `index`-th backup (1 = most recent) of `document`; tagged like
+    /// [`Self::recovery_path`] in the backup directory.
+    pub fn backup_path(&self, document: &Path, index: u32) -> PathBuf {
+        let mut file_name = document.file_name().map(OsString::from).unwrap_or_default();
+        match &self.backup_dir {
+            Some(dir) => {
+                file_name.push(format!("-{}.bak{index}", document_tag(document)));
+                dir.join(file_name)
+            }
+            None => {
+                file_name.push(format!(".bak{index}"));
+                document.with_file_name(file_name)
+            }
+        }
+    }
+
+    /// Shift existing backups of `document` up by one (dropping the oldest) and copy the current
+    /// file into `bak1`. Does nothing if backups are disabled or the document does not exist yet.
+    pub fn rotate_backups(&self, document: &Path) -> Result<(), SettingsError> {
+        if self.backup_count == 0 || !document.exists() {
+            return Ok(());
+        }
+        if let Some(dir) = &self.backup_dir {
+            fs::create_dir_all(dir)?;
+        }
+
+        let oldest = self.backup_path(document, self.backup_count);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.backup_count).rev() {
+            let from = self.backup_path(document, index);
+            if from.exists() {
+                fs::rename(&from, self.backup_path(document, index + 1))?;
+            }
+        }
+        fs::copy(document, self.backup_path(document, 1))?;
+        Ok(())
+    }
+}
+
+/// Placeholder replaced by the saved document's path in a hook command.
+pub const HOOK_FILE_PLACEHOLDER: &str = "{file}";
+/// How often a running hook command is checked for having finished, timed out, or been cancelled.
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Step run after a document is saved, e.g. to keep copies in a cloud-synced folder or to upload
+/// the file with a tool such as rclone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveHook {
+    pub enabled: bool,
+    pub action: SaveHookAction,
+    /// Timestamped copies of each document kept in the folder; 0 keeps all
+    pub keep_copies: u32,
+    /// Copies older than this many days are deleted; 0 keeps them regardless of age
+    pub max_age_days: u32,
+    /// Seconds a command may run before it is killed; 0 lets it run as long as it takes
+    pub timeout_secs: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SaveHookAction {
+    /// Copy the saved file into `folder` as `name-YYYYMMDD-HHMMSS-mmm.prtcad` (UTC, milliseconds)
+    CopyToFolder { folder: PathBuf },
+    /// Run a program; arguments are split on whitespace (double quotes group them) and
+    /// [`HOOK_FILE_PLACEHOLDER`] is replaced by the saved file's path
+    Command { command: String },
+}
+
+impl Default for SaveHook {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            action: SaveHookAction::CopyToFolder {
+                folder: PathBuf::new(),
+            },
+            keep_copies: 10,
+            max_age_days: 0,
+            timeout_secs: 120,
+        }
+    }
+}
+
+impl SaveHook {
+    /// Run the hook for the freshly saved `document`, blocking until it is done. A command is
+    /// killed when it outlives [`Self::timeout_secs`] or `cancel` is set. Returns a short
+    /// description of what was done for the log.
+    pub fn run(
+        &self,
+        document: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, SettingsError> {
+        match &self.action {
+            SaveHookAction::CopyToFolder { folder } => {
+                if folder.as_os_str().is_empty() {
+                    return Err(SettingsError::HookFailed {
+                        command: "copy".to_string(),
+                        message: "no backup folder set".to_string(),
+                    });
+                }
+                fs::create_dir_all(folder)?;
+                let (stem, extension) = split_document_name(document);
+                // Saves within the same millisecond get the next free one, so no copy is
+                // overwritten and the names still sort by age.
+                let mut time = SystemTime::now();
+                let copy = loop {
+                    let copy = folder.join(format!("{stem}-{}{extension}", timestamp(time)));
+                    if !copy.exists() {
+                        break copy;
+                    }
+                    time += Duration::from_millis(1);
+                };
+                fs::copy(document, &copy)?;
+                let removed = self.prune_copies(folder, &stem, &extension)?;
+                Ok(match removed {
+                    0 => format!("Copied to {}", copy.display()),
+                    n => format!("Copied to {} and removed {n} old copies", copy.display()),
+                })
+            }
+            SaveHookAction::Command { command } => {
+                let mut words = split_command(command)
+                    .into_iter()
+                    .map(|word| word.replace(HOOK_FILE_PLACEHOLDER, &document.to_string_lossy()));
+                let failed = |message: String| SettingsError::HookFailed {
+                    command: command.clone(),
+                    message,
+                };
+                let program = words
+                    .next()
+                    .ok_or_else(|| failed("no command set".to_string()))?;
+                let mut child = Command::new(&program)
+                    .args(words)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|err| failed(err.to_string()))?;
+                // Drain stderr on the side so a chatty command can't block on a full pipe.
+                let stderr = child.stderr.take().map(|mut pipe| {
+                    thread::spawn(move || {
+                        let mut text = String::new();
+                        let _ = pipe.read_to_string(&mut text);
+                        text
+                    })
+                });
+                let timeout = (self.timeout_secs > 0)
+                    .then(|| Duration::from_secs(u64::from(self.timeout_secs)));
+                let started = Instant::now();
+                let status = loop {
+                    if let Some(status) = child.try_wait()? {
+                        break status;
+                    }
+                    let stopped = if cancel.is_cancelled() {
+                        Some("cancelled".to_string())
+                    } else {
+                        timeout
+                            .filter(|&timeout| started.elapsed() >= timeout)
+                            .map(|_| format!("timed out after {} s", self.timeout_secs))
+                    };
+                    if let Some(message) = stopped {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(failed(message));
+                    }
+                    thread::sleep(HOOK_POLL_INTERVAL);
+                };
+                if !status.success() {
+                    let stderr = stderr
+                        .and_then(|reader| reader.join().ok())
+                        .unwrap_or_default();
+                    return Err(failed(match stderr.trim().lines().last() {
+                        Some(line) => format!("{status}: {line}"),
+                        None => status.to_string(),
+                    }));
+                }
+                Ok(format!("Ran {program}"))
+            }
+        }
+    }
+
+    /// Delete the copies of a document in `folder` beyond the retention limits, newest kept first.
+    fn prune_copies(
+        &self,
+        folder: &Path,
+        stem: &str,
+        extension: &str,
+    ) -> Result<usize, SettingsError> {
+        let mut copies: Vec<(String, PathBuf)> = fs::read_dir(folder)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let stamp = name
+                    .strip_prefix(stem)?
+                    .strip_prefix('-')?
+                    .strip_suffix(extension)?;
+                is_timestamp(stamp).then(|| (stamp.to_string(), entry.path()))
+            })
+            .collect();
+        copies.sort_by(|a, b| b.0.cmp(&a.0));
+
+        // Timestamps sort like the times they stand for (copies named to the second by earlier
+        // versions just before those of the same second with milliseconds), so the age limit is
+        // a string comparison.
+        let cutoff = (self.max_age_days > 0).then(|| {
+            let age = Duration::from_secs(u64::from(self.max_age_days) * 24 * 60 * 60);
+            timestamp(SystemTime::now().checked_sub(age).unwrap_or(UNIX_EPOCH))
+        });
+        let mut removed = 0;
+        for (index, (stamp, path)) in copies.iter().enumerate() {
+            // The copy just made is always kept.
+            let too_many = self.keep_copies > 0 && index >= self.keep_copies as usize;
+            let too_old = cutoff.as_ref().is_some_and(|cutoff| stamp < cutoff);
+            if index > 0 && (too_many || too_old) {
+                fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Short hash (8 hex digits) of the canonical path of `document`, telling apart files of the same
+/// name in different folders. FNV-1a, so it stays the same across builds.
+fn document_tag(document: &Path) -> String {
+    let path = fs::canonicalize(document).unwrap_or_else(|_| document.to_path_buf());
+    let hash = path
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:08x}", (hash >> 32) ^ (hash & 0xffff_ffff))
+}
+
+/// File name of `document` split before its document extension (`part` and `.prtcad.zst`).
+fn split_document_name(document: &Path) -> (String, String) {
+    let name = document
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lowered = name.to_ascii_lowercase();
+    let extension_len = [".prtcad.zst", ".prtcad.gz", ".prtcad", ".json"]
+        .iter()
+        .find(|extension| lowered.ends_with(*extension))
+        .map(|extension| extension.len())
+        .or_else(|| name.rfind('.').map(|dot| name.len() - dot))
+        .unwrap_or(0);
+    let (stem, extension) = name.split_at(name.len() - extension_len);
+    (stem.to_string(), extension.to_string())
+}
+
+/// UTC time as `YYYYMMDD-HHMMSS-mmm`.
+fn timestamp(time: SystemTime) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (secs, millis) = (elapsed.as_secs(), elapsed.subsec_millis());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{millis:03}",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// Whether `text` is a [`timestamp`], or one without milliseconds as earlier versions wrote.
+fn is_timestamp(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    (bytes.len() == 15 || bytes.len() == 19)
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            8 | 15 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+/// Split a command line on whitespace, keeping double-quoted parts together.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = None::<String>;
+    let mut quoted = false;
+    for ch in command.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            ch if ch.is_whitespace() && !quoted => words.extend(word.take()),
+            ch => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Folder of reusable parts shown in the Part Library
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibrarySettings {
+    /// Folder scanned for `.prtcad`, STEP, and STL files (None = no library)
+    pub folder: Option<PathBuf>,
+}
+
+/// Axis and unit conventions assumed for each import/export format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExchangeSettings {
+    pub stl: ExchangeOptions,
+    pub step: ExchangeOptions,
+    pub obj: ExchangeOptions,
+    pub ply: ExchangeOptions,
+    pub gltf: ExchangeOptions,
+    pub usd: ExchangeOptions,
+    pub amf: ExchangeOptions,
+}
+
+impl Default for ExchangeSettings {
+    fn default() -> Self {
+        Self {
+            stl: ExchangeFormat::Stl.default_options(),
+            step: ExchangeFormat::Step.default_options(),
+            obj: ExchangeFormat::Obj.default_options(),
+            ply: ExchangeFormat::Ply.default_options(),
+            gltf: ExchangeFormat::Gltf.default_options(),
+            usd: ExchangeFormat::Usd.default_options(),
+            amf: ExchangeFormat::Amf.default_options(),
+        }
+    }
+}
+
+impl ExchangeSettings {
+    pub fn options(&self, format: ExchangeFormat) -> ExchangeOptions {
+        match format {
+            ExchangeFormat::Stl => self.stl,
+            ExchangeFormat::Step => self.step,
+            ExchangeFormat::Obj => self.obj,
+            ExchangeFormat::Ply => self.ply,
+            ExchangeFormat::Gltf => self.gltf,
+            ExchangeFormat::Usd => self.usd,
+            ExchangeFormat::Amf => self.amf,
+        }
+    }
+
+    pub fn options_mut(&mut self, format: ExchangeFormat) -> &mut ExchangeOptions {
+        match format {
+            ExchangeFormat::Stl => &mut self.stl,
+            ExchangeFormat::Step => &mut self.step,
+            ExchangeFormat::Obj => &mut self.obj,
+            ExchangeFormat::Ply => &mut self.ply,
+            ExchangeFormat::Gltf => &mut self.gltf,
+            ExchangeFormat::Usd => &mut self.usd,
+            ExchangeFormat::Amf => &mut self.amf,
+        }
+    }
+}
+
+/// Printer profiles used by the Print Preparation workbench
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrinterSettings {
+    pub profiles: Vec<PrinterProfile>,
+    /// Index into `profiles` of the printer prepared for
+    pub active: usize,
+}
+
+impl Default for PrinterSettings {
+    fn default() -> Self {
+        Self {
+            profiles: vec![PrinterProfile::default()],
+            active: 0,
+        }
+    }
+}
+
+impl PrinterSettings {
+    pub fn active_profile(&self) -> Option<&PrinterProfile> {
+        self.profiles.get(self.active)
+    }
+}
+
+/// Build volume of one printer (all sizes in mm)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrinterProfile {
+    pub name: String,
+    /// Bed width along X (diameter for circular beds)
+    pub bed_width: f32,
+    /// Bed depth along Y (ignored for circular beds)
+    pub bed_depth: f32,
+    pub max_height: f32,
+    pub shape: BedShape,
+    pub origin: BedOrigin,
+}
+
+impl Default for PrinterProfile {
+    fn default() -> Self {
+        Self {
+            name: "Generic 220×220".to_string(),
+            bed_width: 220.0,
+            bed_depth: 220.0,
+            max_height: 250.0,
+            shape: BedShape::Rectangular,
+            origin: BedOrigin::FrontLeftCorner,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BedShape {
+    Rectangular,
+    Circular,
+}
+
+impl BedShape {
+    pub const ALL: [BedShape; 2] = [BedShape::Rectangular, BedShape::Circular];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BedShape::Rectangular => "Rectangular",
+            BedShape::Circular => "Circular (delta)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BedOrigin {
+    FrontLeftCorner,
+    Center,
+}
+
+impl BedOrigin {
+    pub const ALL: [BedOrigin; 2] = [BedOrigin::FrontLeftCorner, BedOrigin::Center];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BedOrigin::FrontLeftCorner => "Front-left corner",
+            BedOrigin::Center => "Bed center",
+        }
+    }
+}
+
+/// Keyboard and tool behavior in the viewport
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// Activate workbench tools with their keyboard shortcuts (e.g. L for the sketch line)
+    pub tool_shortcuts: bool,
+    /// Keep a drawing or placement tool active after it finishes an element
+    pub sticky_tools: bool,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            tool_shortcuts: true,
+            sticky_tools: true,
+        }
+    }
+}
+
+/// Grid and snapping settings used by the sketcher and placement tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnappingSettings {
+    /// Grid spacing in document units (mm)
+    pub grid_spacing: f32,
+    /// Draw the grid on the plane of the sketch being edited
+    pub show_grid: bool,
+    /// Grid cells between emphasized major grid lines (1 = no major lines)
+    pub grid_subdivisions: u32,
+    /// Snap picked points to the nearest grid intersection
+    pub snap_to_grid: bool,
+    /// Snap picked points to existing geometry
+    pub object_snap: bool,
+    /// Enabled object snap kinds, highest priority first
+    pub object_snap_priorities: Vec<ObjectSnapKind>,
+    /// Cursor distance in pixels within which an object snap engages
+    pub snap_radius_px: f32,
+}
+
+impl Default for SnappingSettings {
+    fn default() -> Self {
+        Self {
+            grid_spacing: 1.0,
+            show_grid: true,
+            grid_subdivisions: 10,
+            snap_to_grid: false,
+            object_snap: true,
+            object_snap_priorities: ObjectSnapKind::ALL.to_vec(),
+            snap_radius_px: 10.0,
+        }
+    }
+}
+
+/// Kinds of geometry an object snap can lock onto
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ObjectSnapKind {
+    Endpoint,
+    Midpoint,
+    Center,
+    Intersection,
+}
+
+impl ObjectSnapKind {
+    pub const ALL: [ObjectSnapKind; 4] = [
+        ObjectSnapKind::Endpoint,
+        ObjectSnapKind::Midpoint,
+        ObjectSnapKind::Center,
+        ObjectSnapKind::Intersection,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ObjectSnapKind::Endpoint => "Endpoint",
+            ObjectSnapKind::Midpoint => "Midpoint",
+            ObjectSnapKind::Center => "Center",
+            ObjectSnapKind::Intersection => "Intersection",
         }
     }
 }
 
 /// Settings for the 3D viewport lighting system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LightingSettings {
     pub main_light: LightSource,
     pub backlight: LightSource,
@@ -106,7 +1088,7 @@ impl Default for LightingSettings {
 }
 
 /// A single light source with direction defined by angles
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LightSource {
     pub enabled: bool,
     /// Horizontal angle in degrees (0 = front, 90 = right, -90 = left, 180 = back)
@@ -133,7 +1115,8 @@ impl LightSource {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CameraSettings {
     pub orbit_button: MouseButtonSetting,
     pub pan_button: MouseButtonSetting,
@@ -145,6 +1128,12 @@ pub struct CameraSettings {
     pub projection: ProjectionMode,
     pub fov_degrees: f32,
     pub axis_preset: AxisPreset,
+    /// User-defined axis system; takes precedence over `axis_preset` when set
+    #[serde(default)]
+    pub custom_axes: Option<AxisSystem>,
+    /// Duration of animated view changes (snap views, fit, zoom to) in
+    /// milliseconds; 0 jumps instantly.
+    pub transition_ms: u32,
 }
 
 impl Default for CameraSettings {
@@ -160,10 +1149,25 @@ impl Default for CameraSettings {
             projection: ProjectionMode::Perspective,
             fov_degrees: 50.0,
             axis_preset: AxisPreset::default(),
+            custom_axes: None,
+            transition_ms: 250,
         }
     }
 }
 
+impl CameraSettings {
+    /// The axis system in effect: the custom one if set, otherwise the preset.
+    pub fn axis_system(&self) -> AxisSystem {
+        self.custom_axes
+            .unwrap_or_else(|| self.axis_preset.axis_system())
+    }
+
+    /// Duration of animated view changes in seconds.
+    pub fn transition_secs(&self) -> f32 {
+        self.transition_ms as f32 / 1000.0
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ProjectionMode {
     Perspective,
@@ -177,6 +1181,88 @@ pub enum MouseButtonSetting {
     Right,
 }
 
+/// A named snapshot of the user settings.
+///
+/// Profiles are stored in the `profiles/` directory next to `settings.json` and use the same
+/// format when exported, so an exported file can be imported on another machine as-is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub settings: UserSettings,
+}
+
+impl SettingsProfile {
+    pub fn new(name: impl Into<String>, settings: &UserSettings) -> Self {
+        let mut settings = settings.clone();
+        // The active profile is per-installation state, not part of the snapshot.
+        settings.active_profile = None;
+        Self {
+            name: name.into(),
+            settings,
+        }
+    }
+
+    /// Read a profile from an arbitrary JSON file (e.g. one produced by [`Self::write_to`]).
+    pub fn read_from(path: &Path) -> Result<Self, SettingsError> {
+        let file = File::open(path)?;
+        let mut value: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+        if let Some(settings) = value.get_mut("settings") {
+            migration::migrate(settings)?;
+        }
+        let profile: SettingsProfile = serde_json::from_value(value)?;
+        validate_profile_name(&profile.name)?;
+        Ok(profile)
+    }
+
+    /// Write the profile as pretty-printed JSON to an arbitrary path.
+    pub fn write_to(&self, path: &Path) -> Result<(), SettingsError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// What was open when the application last exited, offered for restoring on the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// The open document's file.
+    pub document: PathBuf,
+    /// Id of the active workbench.
+    pub workbench: String,
+    pub camera: CameraPose,
+}
+
+/// Viewport camera placement: it looks at `target` from `radius` away, turned by `orientation`
+/// (a quaternion, `[x, y, z, w]`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraPose {
+    pub target: [f64; 3],
+    pub radius: f32,
+    pub orientation: [f32; 4],
+    /// Half the height of the view when it is orthographic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ortho_half_height: Option<f32>,
+}
+
+/// Profile names double as file names, so keep them to a portable character set.
+fn validate_profile_name(name: &str) -> Result<(), SettingsError> {
+    let trimmed = name.trim();
+    let valid = !trimmed.is_empty()
+        && trimmed == name
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '(' | ')'));
+    if valid {
+        Ok(())
+    } else {
+        Err(SettingsError::InvalidProfileName(name.to_string()))
+    }
+}
+
 pub struct SettingsStore {
     path: PathBuf,
 }
@@ -195,10 +1281,27 @@ impl SettingsStore {
         if !self.path.exists() {
             return Ok(UserSettings::default());
         }
-        let file = File::open(&self.path)?;
-        let reader = BufReader::new(file);
-        let settings = serde_json::from_reader(reader)?;
-        Ok(settings)
+        let result = Self::read_settings(&self.path);
+        if result.is_err() {
+            // The caller falls back to defaults and will overwrite the file on the next save;
+            // keep a copy so the user's settings aren't lost.
+            let _ = fs::copy(&self.path, self.unreadable_path());
+        }
+        result
+    }
+
+    fn read_settings(path: &Path) -> Result<UserSettings, SettingsError> {
+        let file = File::open(path)?;
+        let mut value: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+        migration::migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Where a copy of a settings file that failed to load is kept.
+    pub fn unreadable_path(&self) -> PathBuf {
+        let mut file_name = OsString::from(SETTINGS_FILE);
+        file_name.push(format!(".{UNREADABLE_SUFFIX}"));
+        self.path.with_file_name(file_name)
     }
 
     pub fn save(&self, settings: &UserSettings) -> Result<(), SettingsError> {
@@ -214,6 +1317,173 @@ impl SettingsStore {
         &self.path
     }
 
+    /// Directory holding the saved settings profiles.
+    pub fn profiles_dir(&self) -> PathBuf {
+        self.path
+            .parent()
+            .map(|dir| dir.join(PROFILES_DIR))
+            .unwrap_or_else(|| PathBuf::from(PROFILES_DIR))
+    }
+
+    fn profile_path(&self, name: &str) -> Result<PathBuf, SettingsError> {
+        validate_profile_name(name)?;
+        Ok(self
+            .profiles_dir()
+            .join(format!("{name}.{PROFILE_EXTENSION}")))
+    }
+
+    /// Names of all saved profiles, sorted alphabetically.
+    pub fn list_profiles(&self) -> Result<Vec<String>, SettingsError> {
+        let dir = self.profiles_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(PROFILE_EXTENSION) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(stem.to_string());
+            }
+        }
+        names.sort_by_key(|name| name.to_lowercase());
+        Ok(names)
+    }
+
+    /// Save `settings` as the profile `name`, overwriting any existing profile with that name.
+    pub fn save_profile(&self, name: &str, settings: &UserSettings) -> Result<(), SettingsError> {
+        let path = self.profile_path(name)?;
+        SettingsProfile::new(name, settings).write_to(&path)
+    }
+
+    /// Load the settings stored in profile `name`.
+    pub fn load_profile(&self, name: &str) -> Result<UserSettings, SettingsError> {
+        let path = self.profile_path(name)?;
+        if !path.exists() {
+            return Err(SettingsError::ProfileNotFound(name.to_string()));
+        }
+        let profile = SettingsProfile::read_from(&path)?;
+        let mut settings = profile.settings;
+        settings.active_profile = Some(name.to_string());
+        Ok(settings)
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<(), SettingsError> {
+        let path = self.profile_path(name)?;
+        if !path.exists() {
+            return Err(SettingsError::ProfileNotFound(name.to_string()));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Import a profile file exported from another machine and store it under its own name, or
+    /// under "Name (2)", "Name (3)", ... if a profile with that name already exists.
+    ///
+    /// Returns the name the profile was stored under.
+    pub fn import_profile(&self, path: &Path) -> Result<String, SettingsError> {
+        let profile = SettingsProfile::read_from(path)?;
+        let name = self.unused_profile_name(&profile.name)?;
+        self.save_profile(&name, &profile.settings)?;
+        Ok(name)
+    }
+
+    /// `name`, or the first of "name (2)", "name (3)", ... that no saved profile uses.
+    fn unused_profile_name(&self, name: &str) -> Result<String, SettingsError> {
+        let existing = self.list_profiles()?;
+        let taken = |candidate: &str| {
+            existing
+                .iter()
+                .any(|profile| profile.eq_ignore_ascii_case(candidate))
+        };
+        if !taken(name) {
+            return Ok(name.to_string());
+        }
+        Ok((2..)
+            .map(|n| format!("{name} ({n})"))
+            .find(|candidate| !taken(candidate))
+            .expect("unbounded range"))
+    }
+
+    /// Documents opened or saved most recently, newest first.
+    pub fn recent_documents(&self) -> Result<Vec<PathBuf>, SettingsError> {
+        let path = self.path.with_file_name(RECENT_DOCUMENTS);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Move `document` to the top of the recent documents list and return the new list.
+    pub fn add_recent_document(&self, document: &Path) -> Result<Vec<PathBuf>, SettingsError> {
+        let mut documents = self.recent_documents().unwrap_or_default();
+        documents.retain(|path| path != document);
+        documents.insert(0, document.to_path_buf());
+        documents.truncate(MAX_RECENT_DOCUMENTS);
+        let file = File::create(self.path.with_file_name(RECENT_DOCUMENTS))?;
+        serde_json::to_writer_pretty(file, &documents)?;
+        Ok(documents)
+    }
+
+    /// Session recorded at the last exit, if any.
+    pub fn session(&self) -> Result<Option<SessionState>, SettingsError> {
+        let path = self.path.with_file_name(SESSION_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path)?;
+        Ok(Some(serde_json::from_reader(BufReader::new(file))?))
+    }
+
+    /// Record the session to offer on the next launch; `None` forgets the recorded one.
+    pub fn save_session(&self, session: Option<&SessionState>) -> Result<(), SettingsError> {
+        let path = self.path.with_file_name(SESSION_FILE);
+        match session {
+            Some(session) => {
+                let file = File::create(path)?;
+                serde_json::to_writer_pretty(file, session)?;
+            }
+            None if path.exists() => fs::remove_file(path)?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Directory for sockets and token files only the user may open: the
+    /// platform's runtime directory where it has one, else the local data
+    /// directory, never a shared temp directory. On Unix it is created, or
+    /// tightened, to mode 0700; only its owner can do that, so one made by
+    /// another user is refused.
+    pub fn runtime_dir() -> Result<PathBuf, SettingsError> {
+        let dirs = ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION)
+            .ok_or(SettingsError::MissingProjectDirs)?;
+        let dir = dirs.runtime_dir().unwrap_or(dirs.data_local_dir());
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        match builder.create(dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::AlreadyExists => return Err(err.into()),
+            _ => {}
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if fs::symlink_metadata(dir)?.file_type().is_symlink() {
+                return Err(
+                    std::io::Error::other(format!("{} is a symlink", dir.display())).into(),
+                );
+            }
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(dir.to_path_buf())
+    }
+
     pub fn recent_file_path() -> Result<PathBuf, SettingsError> {
         let dirs = ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION)
             .ok_or(SettingsError::MissingProjectDirs)?;
@@ -230,3 +1500,127 @@ impl Clone for SettingsStore {
         }
     }
 }
+
+/// Watches `settings.json` for edits made outside the application (text editors, dotfile sync).
+///
+/// The parent directory is watched rather than the file itself because many editors save by
+/// writing a temporary file and renaming it over the original.
+pub struct SettingsWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<notify::Event>>,
+    file_name: OsString,
+}
+
+impl SettingsWatcher {
+    pub fn new(store: &SettingsStore) -> Result<Self, SettingsError> {
+        let path = store.path();
+        let dir = path.parent().ok_or(SettingsError::MissingProjectDirs)?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| OsString::from(SETTINGS_FILE));
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            file_name,
+        })
+    }
+
+    /// Drain pending watcher events and report whether the settings file was written or replaced.
+    ///
+    /// Never blocks; intended to be polled once per frame.
+    pub fn take_changed(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.rx.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if !(event.kind.is_modify() || event.kind.is_create()) {
+                continue;
+            }
+            changed |= event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(self.file_name.as_os_str()));
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Store in a fresh directory under the system temp dir.
+    fn temp_store(test: &str) -> SettingsStore {
+        let dir =
+            std::env::temp_dir().join(format!("printcad-settings-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        SettingsStore {
+            path: dir.join(SETTINGS_FILE),
+        }
+    }
+
+    #[test]
+    fn import_profile_keeps_existing_profile_with_same_name() {
+        let store = temp_store("import");
+        let existing = UserSettings {
+            fps_cap: 30.0,
+            ..UserSettings::default()
+        };
+        store.save_profile("Shop", &existing).unwrap();
+
+        let imported = UserSettings {
+            fps_cap: 60.0,
+            ..UserSettings::default()
+        };
+        let file = store.path().with_file_name("exported.json");
+        SettingsProfile::new("Shop", &imported)
+            .write_to(&file)
+            .unwrap();
+
+        assert_eq!(store.import_profile(&file).unwrap(), "Shop (2)");
+        assert_eq!(store.import_profile(&file).unwrap(), "Shop (3)");
+        assert_eq!(store.load_profile("Shop").unwrap().fps_cap, 30.0);
+        assert_eq!(store.load_profile("Shop (2)").unwrap().fps_cap, 60.0);
+        assert_eq!(
+            store.list_profiles().unwrap(),
+            vec!["Shop", "Shop (2)", "Shop (3)"]
+        );
+
+        fs::remove_dir_all(store.path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn copy_hook_keeps_copies_saved_in_quick_succession() {
+        let dir = temp_store("copies").path().parent().unwrap().to_path_buf();
+        let document = dir.join("part.prtcad");
+        let folder = dir.join("copies");
+        let hook = SaveHook {
+            action: SaveHookAction::CopyToFolder {
+                folder: folder.clone(),
+            },
+            keep_copies: 3,
+            ..SaveHook::default()
+        };
+        for save in 0..5 {
+            fs::write(&document, format!("save {save}")).unwrap();
+            hook.run(&document, &CancellationToken::new()).unwrap();
+        }
+
+        let mut copies: Vec<String> = fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        copies.sort();
+        assert_eq!(copies, ["save 2", "save 3", "save 4"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
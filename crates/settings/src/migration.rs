@@ -0,0 +1,49 @@
+//! Upgrades `settings.json` files written by older versions of printCAD.
+//!
+//! Migrations run on the raw JSON before it is deserialized, so renamed or restructured fields
+//! can be carried over instead of silently falling back to their defaults. To change the format:
+//! bump [`SETTINGS_VERSION`] and append a step to [`MIGRATIONS`] that rewrites the previous layout.
+
+use serde_json::{Map, Value};
+
+use crate::SettingsError;
+
+/// Current version of the settings format written by this build.
+pub const SETTINGS_VERSION: u32 = 1;
+
+const VERSION_KEY: &str = "settings_version";
+
+/// `MIGRATIONS[n]` upgrades a settings object from version `n` to `n + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SETTINGS_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Bring a settings JSON object up to [`SETTINGS_VERSION`].
+///
+/// Files without a `settings_version` field predate versioning and are treated as version 0.
+pub(crate) fn migrate(value: &mut Value) -> Result<(), SettingsError> {
+    let Some(object) = value.as_object_mut() else {
+        // Not an object; let deserialization report the error.
+        return Ok(());
+    };
+
+    let found = object
+        .get(VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+        .min(u64::from(u32::MAX)) as u32;
+    if found > SETTINGS_VERSION {
+        return Err(SettingsError::UnsupportedVersion {
+            found,
+            supported: SETTINGS_VERSION,
+        });
+    }
+
+    for step in &MIGRATIONS[found as usize..] {
+        step(object);
+    }
+    object.insert(VERSION_KEY.to_string(), Value::from(SETTINGS_VERSION));
+    Ok(())
+}
+
+/// Version 0 is the unversioned layout; every field added since then has a serde default, so
+/// only the version stamp changes.
+fn migrate_v0_to_v1(_settings: &mut Map<String, Value>) {}
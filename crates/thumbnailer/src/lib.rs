@@ -0,0 +1,143 @@
+//! Preview images of printCAD documents and meshes.
+//!
+//! Previews are rendered offscreen by a small software rasterizer, so they can
+//! be made on background threads, when saving, and by the file-manager
+//! thumbnailer without a GPU. Documents store theirs in the archive as
+//! `thumbnail.png`; older documents are rendered from their mesh bodies.
+
+use std::path::Path;
+
+use core_document::{BodyMesh, Document, DocumentError};
+use glam::{Vec2, Vec3};
+use kernel_api::TriMesh;
+use thiserror::Error;
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+
+/// Width and height of the previews saved in documents (pixels).
+pub const THUMBNAIL_SIZE: u32 = 128;
+const THUMBNAIL_COLOR: [f32; 3] = [0.62, 0.72, 0.86];
+
+#[derive(Debug, Error)]
+pub enum ThumbnailError {
+    #[error(transparent)]
+    Document(#[from] DocumentError),
+    #[error("invalid thumbnail image: {0}")]
+    Png(String),
+}
+
+/// Flat-shaded isometric view of the meshes, `size` pixels square on a
+/// transparent background. None when there is nothing to draw.
+pub fn render(meshes: &[TriMesh], up: Vec3, size: u32) -> Option<Pixmap> {
+    let up = up.normalize_or_zero();
+    let side = up.any_orthonormal_vector();
+    let front = up.cross(side);
+    let view = (side - front + up).normalize();
+    let right = up.cross(view).normalize();
+    let screen_up = view.cross(right);
+
+    let project = |p: [f32; 3]| {
+        let p = Vec3::from(p);
+        (Vec2::new(p.dot(right), p.dot(screen_up)), p.dot(view))
+    };
+    let (min, max) = meshes
+        .iter()
+        .flat_map(|mesh| mesh.positions.iter())
+        .map(|&p| project(p).0)
+        .fold(None, |acc: Option<(Vec2, Vec2)>, p| match acc {
+            None => Some((p, p)),
+            Some((min, max)) => Some((min.min(p), max.max(p))),
+        })?;
+    let extent = (max - min).max_element();
+    if extent <= 0.0 {
+        return None;
+    }
+    let mut image = Pixmap::new(size, size)?;
+    let width = size as usize;
+    let size = size as f32;
+    let scale = size * 0.9 / extent;
+    let offset = Vec2::splat(size / 2.0) - (min + max) / 2.0 * scale;
+    // Screen y grows downwards.
+    let to_pixel = |p: Vec2| {
+        let p = p * scale + offset;
+        Vec2::new(p.x, size - p.y)
+    };
+
+    let mut depth = vec![f32::NEG_INFINITY; width * width];
+    let pixels = image.pixels_mut();
+    for mesh in meshes {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            let [a, b, c] = corners.map(Vec3::from);
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            let shade = 0.3 + 0.7 * normal.dot(view).abs();
+            let [r, g, b] = THUMBNAIL_COLOR.map(|channel| (channel * shade * 255.0) as u8);
+            let color = PremultipliedColorU8::from_rgba(r, g, b, 255)
+                .expect("opaque colors are premultiplied");
+
+            let projected = corners.map(project);
+            let [p0, p1, p2] = projected.map(|(p, _)| to_pixel(p));
+            let area = (p1 - p0).perp_dot(p2 - p0);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            let low = p0.min(p1).min(p2).max(Vec2::ZERO);
+            let high = p0.max(p1).max(p2).min(Vec2::splat(size - 1.0));
+            for y in low.y as usize..=high.y as usize {
+                for x in low.x as usize..=high.x as usize {
+                    let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let w0 = (p2 - p1).perp_dot(p - p1) / area;
+                    let w1 = (p0 - p2).perp_dot(p - p2) / area;
+                    let w2 = 1.0 - w0 - w1;
+                    if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                        continue;
+                    }
+                    let z = w0 * projected[0].1 + w1 * projected[1].1 + w2 * projected[2].1;
+                    let index = y * width + x;
+                    if z > depth[index] {
+                        depth[index] = z;
+                        pixels[index] = color;
+                    }
+                }
+            }
+        }
+    }
+    Some(image)
+}
+
+/// Meshes of `bodies` relative to the first body's position, so previews of
+/// documents far from the origin keep their detail.
+pub fn body_meshes(bodies: &[BodyMesh]) -> Vec<TriMesh> {
+    let origin = bodies.first().map_or([0.0; 3], |body| body.position);
+    bodies
+        .iter()
+        .map(|body| {
+            let mut mesh = body.mesh.clone();
+            mesh.to_frame([0, 1, 2].map(|i| origin[i] - body.position[i]));
+            mesh
+        })
+        .collect()
+}
+
+/// Preview of a `.prtcad` file: the one saved in it, or else one rendered
+/// `size` pixels square from its mesh bodies. None if it has nothing to show.
+pub fn document_preview(path: &Path, size: u32) -> Result<Option<Pixmap>, ThumbnailError> {
+    if let Some(png) = Document::read_thumbnail(path)? {
+        return Pixmap::decode_png(&png)
+            .map(Some)
+            .map_err(|err| ThumbnailError::Png(err.to_string()));
+    }
+    let mut document = Document::load_from_file(path)?;
+    let up = document
+        .authoring_axes()
+        .map_or(Vec3::Z, |axes| axes.up_vec());
+    let bodies = wb_mesh::MeshCache::default().body_meshes(&mut document);
+    let meshes = body_meshes(&bodies);
+    Ok(render(&meshes, up, size))
+}
+
+/// PNG encoding of a preview.
+pub fn encode_png(image: &Pixmap) -> Result<Vec<u8>, ThumbnailError> {
+    image
+        .encode_png()
+        .map_err(|err| ThumbnailError::Png(err.to_string()))
+}
@@ -0,0 +1,102 @@
+//! `printcad-thumbnailer`: writes the preview of a `.prtcad` file as a PNG, for
+//! file managers that run an external thumbnailer (see `packaging/linux`).
+//!
+//! Usage: `printcad-thumbnailer [-s SIZE] INPUT OUTPUT`, where INPUT is a path
+//! or a `file://` URI. Exits with an error when the document has no preview.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use tiny_skia::{FilterQuality, Pixmap, PixmapPaint, Transform};
+
+const USAGE: &str = "usage: printcad-thumbnailer [-s SIZE] INPUT OUTPUT";
+
+fn main() -> Result<()> {
+    let mut size = thumbnailer::THUMBNAIL_SIZE;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-s" | "--size" => {
+                let value = args.next().context(USAGE)?;
+                size = value
+                    .parse()
+                    .with_context(|| format!("invalid size `{value}`"))?;
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [input, output] = <[String; 2]>::try_from(paths).ok().context(USAGE)?;
+    let input = path_from_arg(&input);
+
+    let Some(preview) = thumbnailer::document_preview(&input, size)
+        .with_context(|| format!("failed to read {}", input.display()))?
+    else {
+        bail!("{} has no bodies to preview", input.display());
+    };
+    let png = thumbnailer::encode_png(&fit(preview, size.max(1)))?;
+    std::fs::write(&output, png).with_context(|| format!("failed to write {output}"))?;
+    Ok(())
+}
+
+/// Path of a command-line argument that may be a `file://` URI.
+fn path_from_arg(arg: &str) -> PathBuf {
+    match arg.strip_prefix("file://") {
+        Some(uri_path) => PathBuf::from(percent_decode(uri_path)),
+        None => PathBuf::from(arg),
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Shrink a preview saved at a larger size than requested.
+fn fit(image: Pixmap, size: u32) -> Pixmap {
+    let largest = image.width().max(image.height());
+    if largest <= size {
+        return image;
+    }
+    let scale = size as f32 / largest as f32;
+    let width = ((image.width() as f32 * scale).round() as u32).max(1);
+    let height = ((image.height() as f32 * scale).round() as u32).max(1);
+    let Some(mut scaled) = Pixmap::new(width, height) else {
+        return image;
+    };
+    let paint = PixmapPaint {
+        quality: FilterQuality::Bicubic,
+        ..PixmapPaint::default()
+    };
+    scaled.draw_pixmap(
+        0,
+        0,
+        image.as_ref(),
+        &paint,
+        Transform::from_scale(scale, scale),
+        None,
+    );
+    scaled
+}
@@ -0,0 +1,215 @@
+//! Length units and user-facing number formatting.
+//!
+//! Document geometry is always stored in millimetres; the types here only control how
+//! lengths are displayed and how typed values are interpreted.
+
+use serde::{Deserialize, Serialize};
+
+const MM_PER_INCH: f32 = 25.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LengthUnit {
+    #[default]
+    Millimeter,
+    Centimeter,
+    Meter,
+    Inch,
+    Foot,
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 5] = [
+        LengthUnit::Millimeter,
+        LengthUnit::Centimeter,
+        LengthUnit::Meter,
+        LengthUnit::Inch,
+        LengthUnit::Foot,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            LengthUnit::Millimeter => "Millimeters (mm)",
+            LengthUnit::Centimeter => "Centimeters (cm)",
+            LengthUnit::Meter => "Meters (m)",
+            LengthUnit::Inch => "Inches (in)",
+            LengthUnit::Foot => "Feet (ft)",
+        }
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            LengthUnit::Millimeter => "mm",
+            LengthUnit::Centimeter => "cm",
+            LengthUnit::Meter => "m",
+            LengthUnit::Inch => "in",
+            LengthUnit::Foot => "ft",
+        }
+    }
+
+    pub const fn mm_per_unit(self) -> f32 {
+        match self {
+            LengthUnit::Millimeter => 1.0,
+            LengthUnit::Centimeter => 10.0,
+            LengthUnit::Meter => 1000.0,
+            LengthUnit::Inch => MM_PER_INCH,
+            LengthUnit::Foot => MM_PER_INCH * 12.0,
+        }
+    }
+
+    /// Convert a length in millimetres to this unit.
+    pub fn from_mm(self, mm: f32) -> f32 {
+        mm / self.mm_per_unit()
+    }
+
+    /// Convert a length in this unit to millimetres.
+    pub fn to_mm(self, value: f32) -> f32 {
+        value * self.mm_per_unit()
+    }
+
+    /// Parse a unit suffix such as `mm`, `in`, or `"`.
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol.trim().to_ascii_lowercase().as_str() {
+            "mm" => Some(LengthUnit::Millimeter),
+            "cm" => Some(LengthUnit::Centimeter),
+            "m" => Some(LengthUnit::Meter),
+            "in" | "\"" => Some(LengthUnit::Inch),
+            "ft" | "'" => Some(LengthUnit::Foot),
+            _ => None,
+        }
+    }
+}
+
+/// How lengths are presented to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitFormat {
+    pub unit: LengthUnit,
+    /// Digits after the decimal point.
+    pub decimals: u8,
+    /// Show inch values as fractions with this denominator (e.g. 16 → 1/16"). Ignored for
+    /// other units.
+    pub fraction_denominator: Option<u32>,
+}
+
+impl Default for UnitFormat {
+    fn default() -> Self {
+        Self {
+            unit: LengthUnit::Millimeter,
+            decimals: 2,
+            fraction_denominator: None,
+        }
+    }
+}
+
+impl UnitFormat {
+    /// Format a length (given in millimetres) with its unit symbol, e.g. `12.50 mm`.
+    pub fn format_length(&self, mm: f32) -> String {
+        format!("{} {}", self.format_value(mm), self.unit.symbol())
+    }
+
+    /// Format a length (given in millimetres) without a unit symbol.
+    pub fn format_value(&self, mm: f32) -> String {
+        let value = self.unit.from_mm(mm);
+        match (self.unit, self.fraction_denominator) {
+            (LengthUnit::Inch, Some(denominator)) if denominator > 1 => {
+                format_fraction(value, denominator)
+            }
+            _ => format!("{:.*}", self.decimals as usize, value),
+        }
+    }
+
+    /// Format an area (given in square millimetres) in the display unit, e.g. `3.20 cm²`.
+    pub fn format_area(&self, mm2: f32) -> String {
+        let scale = self.unit.mm_per_unit();
+        format!(
+            "{:.*} {}²",
+            self.decimals as usize,
+            mm2 / (scale * scale),
+            self.unit.symbol()
+        )
+    }
+
+    /// Format a volume (given in cubic millimetres) in the display unit, e.g. `1.50 cm³`.
+    pub fn format_volume(&self, mm3: f32) -> String {
+        let scale = self.unit.mm_per_unit();
+        format!(
+            "{:.*} {}³",
+            self.decimals as usize,
+            mm3 / (scale * scale * scale),
+            self.unit.symbol()
+        )
+    }
+
+    /// Parse a user-typed length and return it in millimetres.
+    ///
+    /// Accepts decimals (`12.5`), fractions (`3/8`, `1 3/8`), and an optional unit suffix
+    /// (`12.5 mm`, `1 3/8"`). Values without a suffix are read in the display unit.
+    pub fn parse_length(&self, text: &str) -> Option<f32> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| c.is_ascii_alphabetic() || c == '"' || c == '\'')
+            .unwrap_or(text.len());
+        let (number, suffix) = text.split_at(split);
+        let unit = if suffix.trim().is_empty() {
+            self.unit
+        } else {
+            LengthUnit::from_symbol(suffix)?
+        };
+        Some(unit.to_mm(parse_number(number)?))
+    }
+}
+
+/// Parse `12.5`, `3/8`, or `1 3/8` (optionally negative).
+fn parse_number(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
+    };
+    let mut parts = text.split_whitespace();
+    let first = parts.next()?;
+    let second = parts.next();
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let fraction = |s: &str| -> Option<f32> {
+        let (num, den) = s.split_once('/')?;
+        let den: f32 = den.trim().parse().ok()?;
+        (den != 0.0).then_some(num.trim().parse::<f32>().ok()? / den)
+    };
+
+    let magnitude = match second {
+        Some(frac) => first.parse::<f32>().ok()? + fraction(frac)?,
+        None if first.contains('/') => fraction(first)?,
+        None => first.parse::<f32>().ok()?,
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// Format an inch value as a reduced mixed fraction, e.g. `1 3/8`.
+fn format_fraction(value: f32, denominator: u32) -> String {
+    let total = (value.abs() * denominator as f32).round() as u64;
+    let sign = if value < 0.0 && total > 0 { "-" } else { "" };
+    let denominator = denominator as u64;
+    let whole = total / denominator;
+    let mut num = total % denominator;
+    let mut den = denominator;
+    if num == 0 {
+        return format!("{sign}{whole}");
+    }
+    let divisor = gcd(num, den);
+    num /= divisor;
+    den /= divisor;
+    if whole == 0 {
+        format!("{sign}{num}/{den}")
+    } else {
+        format!("{sign}{whole} {num}/{den}")
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
@@ -1,11 +1,24 @@
 use core_document::{DocumentResult, DocumentService, Workbench};
+use wb_annotation::AnnotationWorkbench;
+use wb_drawing::DrawingWorkbench;
+use wb_inspection::InspectionWorkbench;
+use wb_mesh::MeshWorkbench;
 use wb_part::PartDesignWorkbench;
+use wb_print::PrintWorkbench;
 use wb_sketch::SketchWorkbench;
+use wb_spreadsheet::SpreadsheetWorkbench;
 
 // Use the core_document macro to define a helper that registers all built-in
 // workbenches and records their descriptors for the UI.
-core_document::define_workbenches!(SketchWorkbench, PartDesignWorkbench);
+core_document::define_workbenches!(
+    SketchWorkbench,
+    PartDesignWorkbench,
+    MeshWorkbench,
+    DrawingWorkbench,
+    AnnotationWorkbench,
+    InspectionWorkbench,
+    SpreadsheetWorkbench,
+    PrintWorkbench
+);
 
 pub use core_document::registration::REGISTERED_WORKBENCHES;
-
-
@@ -0,0 +1,136 @@
+//! Annotation features: a note with a leader pointing at the model.
+
+use core_document::{
+    DocumentResult, FeatureError, FeatureId, GeometryRef, WorkbenchFeature, WorkbenchId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::WORKBENCH_ID;
+
+/// Longest feature-tree name taken from the note text.
+const NAME_LENGTH: usize = 32;
+
+/// What an annotation is about, which sets its color and starting text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    #[default]
+    Note,
+    /// Hole or thread callout (size, pitch, post-processing).
+    Thread,
+    /// Print settings for the part (orientation, infill, supports).
+    PrintSetting,
+    /// Assembly instructions.
+    Assembly,
+}
+
+impl AnnotationKind {
+    pub const ALL: [AnnotationKind; 4] = [
+        AnnotationKind::Note,
+        AnnotationKind::Thread,
+        AnnotationKind::PrintSetting,
+        AnnotationKind::Assembly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AnnotationKind::Note => "Note",
+            AnnotationKind::Thread => "Hole / thread",
+            AnnotationKind::PrintSetting => "Print setting",
+            AnnotationKind::Assembly => "Assembly",
+        }
+    }
+
+    /// Text a new annotation of this kind starts with.
+    pub fn default_text(self) -> &'static str {
+        match self {
+            AnnotationKind::Note => "Note",
+            AnnotationKind::Thread => "M3 × 0.5 — tap after printing",
+            AnnotationKind::PrintSetting => "4 walls, 40 % infill, no supports",
+            AnnotationKind::Assembly => "Press-fit the bearing from this side",
+        }
+    }
+
+    pub fn color(self) -> [f32; 3] {
+        match self {
+            AnnotationKind::Note => [0.95, 0.85, 0.35],
+            AnnotationKind::Thread => [0.4, 0.75, 1.0],
+            AnnotationKind::PrintSetting => [0.45, 0.9, 0.5],
+            AnnotationKind::Assembly => [0.95, 0.6, 0.3],
+        }
+    }
+}
+
+/// A note shown in the viewport next to the point of the model it is about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationFeature {
+    pub kind: AnnotationKind,
+    pub text: String,
+    /// Point the leader points at (world space, mm).
+    pub anchor: [f32; 3],
+    /// Offset from the anchor to the end of the leader, where the text sits.
+    pub leader: [f32; 3],
+    /// Face or edge the note is about, when one was picked.
+    #[serde(default)]
+    pub attachment: Option<GeometryRef>,
+}
+
+impl AnnotationFeature {
+    pub fn new(kind: AnnotationKind, anchor: [f32; 3], leader: [f32; 3]) -> Self {
+        Self {
+            kind,
+            text: kind.default_text().to_string(),
+            anchor,
+            leader,
+            attachment: None,
+        }
+    }
+
+    /// Where the text sits (world space).
+    pub fn label_position(&self) -> [f32; 3] {
+        [
+            self.anchor[0] + self.leader[0],
+            self.anchor[1] + self.leader[1],
+            self.anchor[2] + self.leader[2],
+        ]
+    }
+
+    /// Feature-tree name: the first line of the text, shortened.
+    pub fn display_name(&self) -> String {
+        let line = self.text.lines().next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return self.kind.label().to_string();
+        }
+        match line.char_indices().nth(NAME_LENGTH) {
+            Some((end, _)) => format!("{}…", &line[..end]),
+            None => line.to_string(),
+        }
+    }
+}
+
+impl WorkbenchFeature for AnnotationFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("AnnotationFeature should always serialize")
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        serde_json::from_value(value.clone()).map_err(|e| {
+            core_document::DocumentError::Feature(FeatureError::Deserialization(e.to_string()))
+        })
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        self.attachment
+            .as_ref()
+            .map(GeometryRef::features)
+            .unwrap_or_default()
+    }
+
+    fn name(&self) -> &str {
+        &self.text
+    }
+}
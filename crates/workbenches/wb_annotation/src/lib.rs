@@ -0,0 +1,130 @@
+pub mod feature;
+#[cfg(feature = "egui")]
+mod panel;
+
+use core_document::{
+    FeatureId, InputResult, MouseButton, ToolDescriptor, Workbench, WorkbenchContext,
+    WorkbenchDescriptor, WorkbenchFeature, WorkbenchInputEvent, WorkbenchRuntimeContext,
+};
+pub use feature::{AnnotationFeature, AnnotationKind};
+use glam::Vec3;
+
+/// Workbench identifier.
+pub const WORKBENCH_ID: &str = "wb.annotation";
+
+/// Leader length of a new annotation, as a fraction of the camera distance.
+const LEADER_FRACTION: f32 = 0.12;
+
+/// Annotation workbench: notes with leaders attached to the model, for thread
+/// callouts, print settings, or assembly instructions. The notes are stored
+/// as features and shown in every workbench.
+#[derive(Default)]
+pub struct AnnotationWorkbench {
+    /// Kind of the next annotation placed.
+    kind: AnnotationKind,
+}
+
+impl Workbench for AnnotationWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Annotation",
+            "Attach notes, thread callouts, and print instructions to the model.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new(
+            "annotation.add",
+            "Add Note",
+            Some("annotate"),
+        ));
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Annotation workbench activated");
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Annotation workbench deactivated");
+    }
+
+    fn on_input(
+        &mut self,
+        event: &WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> InputResult {
+        if active_tool != Some("annotation.add") {
+            return InputResult::ignored();
+        }
+        let WorkbenchInputEvent::MousePress {
+            button: MouseButton::Left,
+            ..
+        } = event
+        else {
+            return InputResult::ignored();
+        };
+        let Some(anchor) = ctx.hovered_world_pos else {
+            ctx.log_warn("Click on the model to place the note");
+            return InputResult::consumed();
+        };
+        let body = ctx.hovered_body().or_else(|| ctx.selected_body());
+        let leader = default_leader(ctx, anchor);
+        let annotation = AnnotationFeature::new(self.kind, ctx.to_body(body, anchor), leader);
+        let name = annotation.display_name();
+        match ctx
+            .document
+            .add_feature_in_body(annotation, name.clone(), body)
+        {
+            Ok(id) => {
+                ctx.active_document_object = Some(id);
+                ctx.log_info(format!("Added annotation: {name}"));
+            }
+            Err(err) => ctx.log_error(format!("Failed to add annotation: {err}")),
+        }
+        InputResult::consumed()
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+}
+
+/// Annotation features of the document, oldest first.
+pub fn annotations(document: &core_document::Document) -> Vec<(FeatureId, AnnotationFeature)> {
+    let mut nodes: Vec<_> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == WORKBENCH_ID)
+        .collect();
+    nodes.sort_by_key(|(_, node)| node.created_at);
+    nodes
+        .into_iter()
+        .filter_map(|(id, node)| {
+            AnnotationFeature::from_json(&node.data)
+                .ok()
+                .map(|annotation| (*id, annotation))
+        })
+        .collect()
+}
+
+/// Leader pointing up and to the right on screen, scaled to the view.
+fn default_leader(ctx: &WorkbenchRuntimeContext, anchor: [f32; 3]) -> [f32; 3] {
+    let eye = Vec3::from(ctx.camera_position);
+    let view = Vec3::from(ctx.camera_target) - eye;
+    let up = ctx.axes.up_vec();
+    let right = view
+        .cross(up)
+        .try_normalize()
+        .unwrap_or(ctx.axes.right_vec());
+    let screen_up = right.cross(view).normalize_or(up);
+    let length = (Vec3::from(anchor) - eye).length() * LEADER_FRACTION;
+    ((right * 0.6 + screen_up).normalize() * length).to_array()
+}
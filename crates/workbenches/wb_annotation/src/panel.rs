@@ -0,0 +1,103 @@
+//! Right-panel UI of the Annotation workbench.
+
+use core_document::{FeatureId, WorkbenchFeature, WorkbenchRuntimeContext};
+
+use crate::{AnnotationFeature, AnnotationKind, AnnotationWorkbench, WORKBENCH_ID};
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut AnnotationWorkbench,
+) {
+    ui.heading("Annotations");
+    ui.horizontal(|ui| {
+        ui.label("New:");
+        kind_combo(ui, "annotation_new_kind", &mut workbench.kind);
+    });
+    ui.weak("Choose Add Note, then click on the model.");
+
+    let annotations = crate::annotations(ctx.document);
+    if annotations.is_empty() {
+        return;
+    }
+    ui.separator();
+    for (id, annotation) in &annotations {
+        let selected = ctx.active_document_object == Some(*id);
+        if ui
+            .selectable_label(selected, annotation.display_name())
+            .clicked()
+        {
+            ctx.active_document_object = Some(*id);
+        }
+    }
+
+    let Some(id) = ctx.active_document_object.filter(|id| {
+        ctx.document
+            .get_feature_meta(*id)
+            .is_some_and(|node| node.workbench_id.as_str() == WORKBENCH_ID)
+    }) else {
+        return;
+    };
+    let Some(mut annotation) = annotations
+        .into_iter()
+        .find(|(candidate, _)| *candidate == id)
+        .map(|(_, annotation)| annotation)
+    else {
+        return;
+    };
+
+    ui.separator();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Kind:");
+        let before = annotation.kind;
+        kind_combo(ui, "annotation_kind", &mut annotation.kind);
+        if annotation.kind != before {
+            // Replace the text only while it is still the template.
+            if annotation.text == before.default_text() {
+                annotation.text = annotation.kind.default_text().to_string();
+            }
+            changed = true;
+        }
+    });
+    changed |= ui
+        .add(
+            egui::TextEdit::multiline(&mut annotation.text)
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        )
+        .changed();
+    ui.label("Text offset:");
+    ui.horizontal(|ui| {
+        for (axis, value) in ["X", "Y", "Z"].into_iter().zip(&mut annotation.leader) {
+            ui.label(axis);
+            changed |= ui
+                .add(egui::DragValue::new(value).speed(0.5).suffix(" mm"))
+                .changed();
+        }
+    });
+    if changed {
+        update(ctx, id, &annotation);
+    }
+}
+
+fn kind_combo(ui: &mut egui::Ui, salt: &str, kind: &mut AnnotationKind) {
+    egui::ComboBox::from_id_salt(salt)
+        .selected_text(kind.label())
+        .show_ui(ui, |ui| {
+            for option in AnnotationKind::ALL {
+                ui.selectable_value(kind, option, option.label());
+            }
+        });
+}
+
+/// Store an edited annotation, renaming its tree entry after the text.
+fn update(ctx: &mut WorkbenchRuntimeContext, id: FeatureId, annotation: &AnnotationFeature) {
+    if let Err(err) = ctx.document.update_feature_data(id, annotation.to_json()) {
+        ctx.log_error(format!("Failed to update annotation: {err}"));
+        return;
+    }
+    if let Some(node) = ctx.document.feature_tree_mut().get_node_mut(id) {
+        node.name = annotation.display_name();
+    }
+}
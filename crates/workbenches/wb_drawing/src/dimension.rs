@@ -0,0 +1,112 @@
+//! Linear dimensions between two points of a view.
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Direction a linear dimension measures in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DimensionKind {
+    #[default]
+    Horizontal,
+    Vertical,
+    /// Along the line through both points.
+    Aligned,
+}
+
+impl DimensionKind {
+    pub const ALL: [DimensionKind; 3] = [
+        DimensionKind::Horizontal,
+        DimensionKind::Vertical,
+        DimensionKind::Aligned,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DimensionKind::Horizontal => "Horizontal",
+            DimensionKind::Vertical => "Vertical",
+            DimensionKind::Aligned => "Aligned",
+        }
+    }
+}
+
+/// A dimension attached to a view. Points are in view coordinates (model mm),
+/// so the dimension follows the view when it is moved or rescaled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dimension {
+    pub view: u32,
+    pub kind: DimensionKind,
+    pub a: [f32; 2],
+    pub b: [f32; 2],
+    /// Distance (sheet mm) from the first point to the dimension line.
+    pub offset: f32,
+}
+
+/// Default distance (sheet mm) between a new dimension line and its first point.
+pub const DEFAULT_OFFSET: f32 = 10.0;
+
+impl Dimension {
+    /// Measuring direction (unit length) and the side the dimension line is
+    /// offset to.
+    fn axes(&self) -> (Vec2, Vec2) {
+        match self.kind {
+            DimensionKind::Horizontal => (Vec2::X, Vec2::Y),
+            DimensionKind::Vertical => (Vec2::Y, Vec2::NEG_X),
+            DimensionKind::Aligned => {
+                let along = (Vec2::from(self.b) - Vec2::from(self.a)).normalize_or(Vec2::X);
+                (along, along.perp())
+            }
+        }
+    }
+
+    /// Measured model distance (mm).
+    pub fn value(&self) -> f32 {
+        let (along, _) = self.axes();
+        (Vec2::from(self.b) - Vec2::from(self.a)).dot(along).abs()
+    }
+
+    /// Sheet geometry of the dimension, given where its two points are on the
+    /// sheet.
+    pub fn layout(&self, a: Vec2, b: Vec2) -> DimensionLayout {
+        let (along, side) = self.axes();
+        let line_a = a + side * self.offset;
+        let line_b = line_a + along * (b - a).dot(along);
+        let extension = |point: Vec2, foot: Vec2| {
+            let direction = (foot - point).normalize_or_zero();
+            [
+                point + direction * EXTENSION_GAP,
+                foot + direction * EXTENSION_OVERSHOOT,
+            ]
+        };
+        let mut angle = along.y.atan2(along.x);
+        // Keep text readable from the bottom or right edge of the sheet.
+        if angle > std::f32::consts::FRAC_PI_2 + 1e-3 || angle <= -std::f32::consts::FRAC_PI_2 {
+            angle -= std::f32::consts::PI.copysign(angle);
+        }
+        let text_side = Vec2::from_angle(angle).perp();
+        DimensionLayout {
+            extension_lines: [extension(a, line_a), extension(b, line_b)],
+            dimension_line: [line_a, line_b],
+            text_position: (line_a + line_b) * 0.5 + text_side * TEXT_GAP,
+            text_angle: angle,
+        }
+    }
+}
+
+/// Gap between a measured point and its extension line (sheet mm).
+const EXTENSION_GAP: f32 = 1.0;
+/// Extension lines run this far past the dimension line (sheet mm).
+const EXTENSION_OVERSHOOT: f32 = 2.0;
+/// Distance between the dimension line and its text baseline (sheet mm).
+const TEXT_GAP: f32 = 1.0;
+
+/// Lines and text placement of a dimension on the sheet.
+#[derive(Debug, Clone, Copy)]
+pub struct DimensionLayout {
+    pub extension_lines: [[Vec2; 2]; 2],
+    pub dimension_line: [Vec2; 2],
+    /// Middle of the text baseline.
+    pub text_position: Vec2,
+    /// Text direction (radians, counter-clockwise from the sheet's x axis).
+    pub text_angle: f32,
+}
@@ -0,0 +1,122 @@
+//! DXF (R12, ASCII) output of a drawing sheet, one layer per line style.
+
+use std::fmt::Write;
+
+use glam::Vec2;
+
+use crate::render::{LineStyle, SheetGraphics, TextAlign};
+
+/// Layer holding all text.
+const TEXT_LAYER: &str = "TEXT";
+
+pub fn to_dxf(graphics: &SheetGraphics) -> String {
+    let mut dxf = Dxf::default();
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "HEADER");
+    dxf.pair(9, "$ACADVER");
+    dxf.pair(1, "AC1009");
+    dxf.pair(9, "$EXTMIN");
+    dxf.point(10, Vec2::ZERO);
+    dxf.pair(9, "$EXTMAX");
+    dxf.point(10, Vec2::from(graphics.size));
+    dxf.pair(0, "ENDSEC");
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "TABLES");
+    dxf.pair(0, "TABLE");
+    dxf.pair(2, "LTYPE");
+    dxf.pair(70, 2);
+    dxf.line_type("CONTINUOUS", "Solid line", &[]);
+    let [dash, gap] = LineStyle::Hidden.dash().unwrap_or([3.0, 1.5]);
+    dxf.line_type("DASHED", "Dashed line", &[dash, -gap]);
+    dxf.pair(0, "ENDTAB");
+    dxf.pair(0, "TABLE");
+    dxf.pair(2, "LAYER");
+    dxf.pair(70, LineStyle::ALL.len() + 1);
+    for style in LineStyle::ALL {
+        let line_type = if style.dash().is_some() {
+            "DASHED"
+        } else {
+            "CONTINUOUS"
+        };
+        dxf.layer(style.layer_name(), line_type);
+    }
+    dxf.layer(TEXT_LAYER, "CONTINUOUS");
+    dxf.pair(0, "ENDTAB");
+    dxf.pair(0, "ENDSEC");
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "ENTITIES");
+    for line in &graphics.lines {
+        dxf.pair(0, "LINE");
+        dxf.pair(8, line.style.layer_name());
+        dxf.point(10, line.a);
+        dxf.point(11, line.b);
+    }
+    for arrow in &graphics.arrows {
+        // SOLID takes four corners; repeating the last one gives a triangle.
+        dxf.pair(0, "SOLID");
+        dxf.pair(8, LineStyle::Dimension.layer_name());
+        dxf.point(10, arrow[0]);
+        dxf.point(11, arrow[1]);
+        dxf.point(12, arrow[2]);
+        dxf.point(13, arrow[2]);
+    }
+    for text in &graphics.texts {
+        dxf.pair(0, "TEXT");
+        dxf.pair(8, TEXT_LAYER);
+        dxf.point(10, text.position);
+        dxf.pair(40, format!("{:.3}", text.height));
+        dxf.pair(1, &text.text);
+        if text.angle != 0.0 {
+            dxf.pair(50, format!("{:.3}", text.angle.to_degrees()));
+        }
+        if text.align == TextAlign::Center {
+            // Centered text is placed by its alignment point.
+            dxf.pair(72, 1);
+            dxf.point(11, text.position);
+        }
+    }
+    dxf.pair(0, "ENDSEC");
+    dxf.pair(0, "EOF");
+    dxf.out
+}
+
+#[derive(Default)]
+struct Dxf {
+    out: String,
+}
+
+impl Dxf {
+    fn pair(&mut self, code: u32, value: impl std::fmt::Display) {
+        let _ = write!(self.out, "{code:>3}\n{value}\n");
+    }
+
+    fn point(&mut self, code: u32, point: Vec2) {
+        self.pair(code, format!("{:.4}", point.x));
+        self.pair(code + 10, format!("{:.4}", point.y));
+        self.pair(code + 20, "0.0");
+    }
+
+    fn line_type(&mut self, name: &str, description: &str, pattern: &[f32]) {
+        self.pair(0, "LTYPE");
+        self.pair(2, name);
+        self.pair(70, 0);
+        self.pair(3, description);
+        self.pair(72, 65);
+        self.pair(73, pattern.len());
+        let total: f32 = pattern.iter().map(|length| length.abs()).sum();
+        self.pair(40, format!("{total:.3}"));
+        for length in pattern {
+            self.pair(49, format!("{length:.3}"));
+        }
+    }
+
+    fn layer(&mut self, name: &str, line_type: &str) {
+        self.pair(0, "LAYER");
+        self.pair(2, name);
+        self.pair(70, 0);
+        self.pair(62, 7);
+        self.pair(6, line_type);
+    }
+}
@@ -0,0 +1,71 @@
+//! Writing a drawing sheet to PDF, SVG or DXF.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::render::SheetGraphics;
+use crate::{dxf, pdf, svg};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Pdf,
+    Svg,
+    Dxf,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Pdf, ExportFormat::Svg, ExportFormat::Dxf];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Dxf => "DXF",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Dxf => "dxf",
+        }
+    }
+
+    /// `FileOpenRequest` id of the save dialog for this format.
+    pub fn request_id(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "drawing.export_pdf",
+            ExportFormat::Svg => "drawing.export_svg",
+            ExportFormat::Dxf => "drawing.export_dxf",
+        }
+    }
+
+    pub fn from_request_id(id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.request_id() == id)
+    }
+}
+
+/// Write `graphics` to `path` in `format`.
+pub fn export(
+    graphics: &SheetGraphics,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<(), ExportError> {
+    let bytes = match format {
+        ExportFormat::Pdf => pdf::to_pdf(graphics),
+        ExportFormat::Svg => svg::to_svg(graphics).into_bytes(),
+        ExportFormat::Dxf => dxf::to_dxf(graphics).into_bytes(),
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
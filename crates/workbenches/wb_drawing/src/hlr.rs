@@ -0,0 +1,399 @@
+//! Hidden-line projection of tessellated bodies.
+//!
+//! The kernel does not provide exact hidden-line removal yet, so views are
+//! built from the body's display mesh: feature edges (open borders, sharp
+//! creases and silhouettes) are projected onto the paper and split into
+//! visible and hidden parts by testing sample points against the triangles in
+//! front of them. Section views clip the mesh at the section plane first and
+//! outline the cut.
+
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+use kernel_api::TriMesh;
+
+use crate::view::ViewBasis;
+
+/// Faces meeting at more than this angle (degrees) form a visible crease.
+const CREASE_ANGLE_DEG: f32 = 20.0;
+
+/// Vertices closer than this (mm) are treated as the same vertex.
+const WELD_TOLERANCE: f32 = 1e-4;
+
+/// Edges are tested for visibility in steps of about this fraction of the
+/// view's size, and never with more than `MAX_SAMPLES` steps.
+const SAMPLE_FRACTION: f32 = 1.0 / 200.0;
+const MAX_SAMPLES: usize = 64;
+
+/// Grid cells per side of the occlusion lookup grid (at most).
+const MAX_GRID: usize = 128;
+
+/// A body projected onto the paper, in view coordinates: model millimetres
+/// around the center of the body's projected bounds.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectedView {
+    pub visible: Vec<[Vec2; 2]>,
+    pub hidden: Vec<[Vec2; 2]>,
+    /// Outlines of the cut of a section view (closed loops, even-odd filled).
+    pub sections: Vec<Vec<Vec2>>,
+    /// Corners of the projected bounds.
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Edge end points that dimensions can snap to.
+    pub snap_points: Vec<Vec2>,
+}
+
+impl ProjectedView {
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+}
+
+/// Mesh in view space: x/y on the paper, z towards the viewer.
+struct ViewMesh {
+    points: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+    /// Vertices created on the section plane.
+    on_plane: Vec<bool>,
+}
+
+/// Project `mesh` (world space) with `basis`. `section` is the depth of the
+/// section plane measured from the center of the body towards the viewer.
+pub fn project(mesh: &TriMesh, basis: &ViewBasis, section: Option<f32>) -> ProjectedView {
+    let projected: Vec<Vec3> = mesh
+        .positions
+        .iter()
+        .map(|&p| {
+            let (paper, depth) = basis.project(Vec3::from(p));
+            paper.extend(depth)
+        })
+        .collect();
+    let Some((min, max)) = projected
+        .iter()
+        .fold(None, |acc: Option<(Vec3, Vec3)>, &p| {
+            Some(acc.map_or((p, p), |(min, max)| (min.min(p), max.max(p))))
+        })
+    else {
+        return ProjectedView::default();
+    };
+    let center = (min + max) * 0.5;
+    let mut view_mesh = weld(projected.iter().map(|&p| p - center), &mesh.indices);
+    let mut sections = Vec::new();
+    if let Some(depth) = section {
+        let segments = clip_behind(&mut view_mesh, depth);
+        sections = chain_loops(&view_mesh.points, &segments);
+    }
+
+    let diagonal = (max - min).length().max(1e-3);
+    let occluders = Occluders::new(&view_mesh, diagonal);
+    let mut result = ProjectedView {
+        min: (min - center).truncate(),
+        max: (max - center).truncate(),
+        ..ProjectedView::default()
+    };
+    let mut snaps: HashMap<[i64; 2], Vec2> = HashMap::new();
+    let step = diagonal * SAMPLE_FRACTION;
+    for (a, b) in feature_edges(&view_mesh) {
+        let (pa, pb) = (view_mesh.points[a as usize], view_mesh.points[b as usize]);
+        let length = pa.truncate().distance(pb.truncate());
+        if length <= WELD_TOLERANCE {
+            continue;
+        }
+        for point in [pa, pb] {
+            snaps.insert(key2(point.truncate()), point.truncate());
+        }
+        let samples = ((length / step).ceil() as usize).clamp(1, MAX_SAMPLES);
+        let mut run_start = pa;
+        let mut run_hidden = None;
+        for k in 0..samples {
+            let t0 = k as f32 / samples as f32;
+            let t1 = (k + 1) as f32 / samples as f32;
+            let middle = pa.lerp(pb, (t0 + t1) * 0.5);
+            let hidden = occluders.hides(middle, &sections);
+            if run_hidden.is_some_and(|previous| previous != hidden) {
+                let split = pa.lerp(pb, t0);
+                push_run(&mut result, run_start, split, run_hidden == Some(true));
+                run_start = split;
+            }
+            run_hidden = Some(hidden);
+        }
+        push_run(&mut result, run_start, pb, run_hidden == Some(true));
+    }
+    for outline in &sections {
+        for &point in outline {
+            snaps.insert(key2(point), point);
+        }
+    }
+    result.sections = sections;
+    result.snap_points = snaps.into_values().collect();
+    result
+}
+
+fn push_run(view: &mut ProjectedView, from: Vec3, to: Vec3, hidden: bool) {
+    let segment = [from.truncate(), to.truncate()];
+    if hidden {
+        view.hidden.push(segment);
+    } else {
+        view.visible.push(segment);
+    }
+}
+
+fn key2(point: Vec2) -> [i64; 2] {
+    point
+        .to_array()
+        .map(|c| (c / WELD_TOLERANCE).round() as i64)
+}
+
+/// Merge coincident vertices and drop degenerate triangles.
+fn weld(points: impl Iterator<Item = Vec3>, indices: &[u32]) -> ViewMesh {
+    let mut mesh = ViewMesh {
+        points: Vec::new(),
+        triangles: Vec::new(),
+        on_plane: Vec::new(),
+    };
+    let mut lookup: HashMap<[i64; 3], u32> = HashMap::new();
+    let remap: Vec<u32> = points
+        .map(|p| {
+            let key = p.to_array().map(|c| (c / WELD_TOLERANCE).round() as i64);
+            *lookup.entry(key).or_insert_with(|| {
+                mesh.points.push(p);
+                mesh.on_plane.push(false);
+                (mesh.points.len() - 1) as u32
+            })
+        })
+        .collect();
+    for triangle in indices.chunks_exact(3) {
+        let Some(&[a, b, c]) = triangle
+            .iter()
+            .map(|&i| remap.get(i as usize).copied())
+            .collect::<Option<Vec<u32>>>()
+            .as_deref()
+        else {
+            continue;
+        };
+        if a != b && b != c && c != a {
+            mesh.triangles.push([a, b, c]);
+        }
+    }
+    mesh
+}
+
+/// Remove the parts of the mesh in front of the plane at `depth` and return
+/// the segments (vertex pairs) where triangles were cut.
+fn clip_behind(mesh: &mut ViewMesh, depth: f32) -> Vec<(u32, u32)> {
+    let mut cuts: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut segments = Vec::new();
+    let mut triangles = Vec::with_capacity(mesh.triangles.len());
+    for triangle in std::mem::take(&mut mesh.triangles) {
+        let kept = triangle.map(|v| mesh.points[v as usize].z <= depth);
+        if kept.iter().all(|&k| k) {
+            triangles.push(triangle);
+            continue;
+        }
+        if !kept.iter().any(|&k| k) {
+            continue;
+        }
+        let mut piece = Vec::with_capacity(4);
+        let mut crossing = Vec::with_capacity(2);
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            if kept[i] {
+                piece.push(a);
+            }
+            if kept[i] != kept[(i + 1) % 3] {
+                let key = (a.min(b), a.max(b));
+                let cut = *cuts.entry(key).or_insert_with(|| {
+                    let (pa, pb) = (mesh.points[key.0 as usize], mesh.points[key.1 as usize]);
+                    let t = (depth - pa.z) / (pb.z - pa.z);
+                    mesh.points.push(pa.lerp(pb, t));
+                    mesh.on_plane.push(true);
+                    (mesh.points.len() - 1) as u32
+                });
+                piece.push(cut);
+                crossing.push(cut);
+            }
+        }
+        for k in 1..piece.len().saturating_sub(1) {
+            triangles.push([piece[0], piece[k], piece[k + 1]]);
+        }
+        if let [a, b] = crossing[..] {
+            segments.push((a, b));
+        }
+    }
+    mesh.triangles = triangles;
+    segments
+}
+
+/// Join cut segments into outlines.
+fn chain_loops(points: &[Vec3], segments: &[(u32, u32)]) -> Vec<Vec<Vec2>> {
+    let mut links: HashMap<u32, Vec<u32>> = HashMap::new();
+    for &(a, b) in segments {
+        links.entry(a).or_default().push(b);
+        links.entry(b).or_default().push(a);
+    }
+    let mut loops = Vec::new();
+    while let Some(&start) = links.keys().next() {
+        let mut outline = vec![start];
+        let mut current = start;
+        while let Some(next) = links.get_mut(&current).and_then(|around| around.pop()) {
+            if let Some(back) = links.get_mut(&next) {
+                if let Some(index) = back.iter().position(|&v| v == current) {
+                    back.swap_remove(index);
+                }
+            }
+            if next == start {
+                break;
+            }
+            outline.push(next);
+            current = next;
+        }
+        links.retain(|_, around| !around.is_empty());
+        links.remove(&start);
+        if outline.len() >= 3 {
+            loops.push(
+                outline
+                    .iter()
+                    .map(|&v| points[v as usize].truncate())
+                    .collect(),
+            );
+        }
+    }
+    loops
+}
+
+/// Edges drawn in the view: open borders (except the cut of a section),
+/// creases sharper than `CREASE_ANGLE_DEG`, and silhouettes.
+fn feature_edges(mesh: &ViewMesh) -> Vec<(u32, u32)> {
+    let normals: Vec<Vec3> = mesh
+        .triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|v| mesh.points[v as usize]);
+            (b - a).cross(c - a).normalize_or_zero()
+        })
+        .collect();
+    let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (index, triangle) in mesh.triangles.iter().enumerate() {
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            edges.entry((a.min(b), a.max(b))).or_default().push(index);
+        }
+    }
+    let crease = CREASE_ANGLE_DEG.to_radians().cos();
+    let mut result: Vec<(u32, u32)> = edges
+        .into_iter()
+        .filter(|((a, b), faces)| match faces[..] {
+            [_] => !(mesh.on_plane[*a as usize] && mesh.on_plane[*b as usize]),
+            [f, g] => {
+                let (n, m) = (normals[f], normals[g]);
+                n.dot(m) < crease || (n.z > 0.0) != (m.z > 0.0)
+            }
+            _ => true,
+        })
+        .map(|(edge, _)| edge)
+        .collect();
+    result.sort_unstable();
+    result
+}
+
+/// Triangles bucketed by their paper bounds for occlusion tests.
+struct Occluders<'a> {
+    mesh: &'a ViewMesh,
+    min: Vec2,
+    cell: Vec2,
+    size: usize,
+    cells: Vec<Vec<usize>>,
+    /// Points must be this much (mm) behind a triangle to count as hidden.
+    depth_tolerance: f32,
+}
+
+impl<'a> Occluders<'a> {
+    fn new(mesh: &'a ViewMesh, diagonal: f32) -> Self {
+        let (min, max) = mesh.points.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), p| (min.min(p.truncate()), max.max(p.truncate())),
+        );
+        let size = ((mesh.triangles.len() as f32).sqrt().ceil() as usize).clamp(1, MAX_GRID);
+        let cell = ((max - min) / size as f32).max(Vec2::splat(1e-6));
+        let mut occluders = Self {
+            mesh,
+            min,
+            cell,
+            size,
+            cells: vec![Vec::new(); size * size],
+            depth_tolerance: diagonal * 1e-3,
+        };
+        for (index, triangle) in mesh.triangles.iter().enumerate() {
+            let corners = triangle.map(|v| mesh.points[v as usize].truncate());
+            let lo = occluders.cell_of(corners[0].min(corners[1]).min(corners[2]));
+            let hi = occluders.cell_of(corners[0].max(corners[1]).max(corners[2]));
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    occluders.cells[y * size + x].push(index);
+                }
+            }
+        }
+        occluders
+    }
+
+    fn cell_of(&self, point: Vec2) -> [usize; 2] {
+        let cell = ((point - self.min) / self.cell).floor();
+        [
+            (cell.x.max(0.0) as usize).min(self.size - 1),
+            (cell.y.max(0.0) as usize).min(self.size - 1),
+        ]
+    }
+
+    /// Whether something lies in front of `point`: a triangle covering it, or
+    /// the cut face of a section view.
+    fn hides(&self, point: Vec3, sections: &[Vec<Vec2>]) -> bool {
+        let paper = point.truncate();
+        if sections
+            .iter()
+            .filter(|outline| contains(outline, paper))
+            .count()
+            % 2
+            == 1
+        {
+            return true;
+        }
+        let [x, y] = self.cell_of(paper);
+        self.cells[y * self.size + x].iter().any(|&index| {
+            let [a, b, c] = self.mesh.triangles[index].map(|v| self.mesh.points[v as usize]);
+            let Some(depth) = depth_at(a, b, c, paper) else {
+                return false;
+            };
+            depth > point.z + self.depth_tolerance
+        })
+    }
+}
+
+/// Depth of triangle `abc` above `point`, if the point lies strictly inside
+/// it (points on its edges are not covered, so an edge is never hidden by the
+/// faces it bounds).
+fn depth_at(a: Vec3, b: Vec3, c: Vec3, point: Vec2) -> Option<f32> {
+    let (a2, b2, c2) = (a.truncate(), b.truncate(), c.truncate());
+    let area = (b2 - a2).perp_dot(c2 - a2);
+    if area.abs() <= f32::EPSILON {
+        return None;
+    }
+    let u = (c2 - b2).perp_dot(point - b2) / area;
+    let v = (a2 - c2).perp_dot(point - c2) / area;
+    let w = 1.0 - u - v;
+    const INSIDE: f32 = 1e-4;
+    (u > INSIDE && v > INSIDE && w > INSIDE).then_some(a.z * u + b.z * v + c.z * w)
+}
+
+/// Point-in-polygon test (even-odd rule).
+pub(crate) fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
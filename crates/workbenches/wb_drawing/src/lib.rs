@@ -0,0 +1,261 @@
+pub mod dimension;
+pub mod dxf;
+pub mod export;
+pub mod hlr;
+#[cfg(feature = "egui")]
+mod panel;
+pub mod pdf;
+pub mod render;
+pub mod sheet;
+pub mod svg;
+pub mod view;
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use core_document::{
+    BodyId, ToolDescriptor, Workbench, WorkbenchContext, WorkbenchDescriptor,
+    WorkbenchRuntimeContext,
+};
+use dimension::DimensionKind;
+use export::ExportFormat;
+use glam::Vec2;
+use hlr::ProjectedView;
+use sheet::{Drawing, MARGIN};
+use view::{DrawingView, Projection};
+
+/// Workbench identifier (also the key of the document's drawing storage).
+pub const WORKBENCH_ID: &str = "wb.drawing";
+
+/// Drawing workbench: 2D orthographic and section views of bodies on a sheet
+/// with a title block and dimensions, exported to PDF, SVG or DXF.
+pub struct DrawingWorkbench {
+    /// View edited in the right panel.
+    selected_view: Option<u32>,
+    /// Projection used by "Add View".
+    new_view_projection: Projection,
+    /// Show the sheet preview window.
+    show_sheet: bool,
+    /// Dimension being placed by clicking two points on the sheet.
+    dimension_kind: Option<DimensionKind>,
+    /// First point of the dimension being placed (view id, view coordinates).
+    pending_point: Option<(u32, [f32; 2])>,
+    /// View being dragged on the sheet.
+    dragged_view: Option<u32>,
+    /// Projected geometry of each view, with the key it was computed for.
+    projections: HashMap<u32, ProjectedView>,
+    projection_keys: HashMap<u32, u64>,
+}
+
+impl Default for DrawingWorkbench {
+    fn default() -> Self {
+        Self {
+            selected_view: None,
+            new_view_projection: Projection::Front,
+            show_sheet: true,
+            dimension_kind: None,
+            pending_point: None,
+            dragged_view: None,
+            projections: HashMap::new(),
+            projection_keys: HashMap::new(),
+        }
+    }
+}
+
+impl Workbench for DrawingWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Drawing",
+            "Lay out dimensioned 2D views of bodies on a sheet and export them.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new_action(
+            "drawing.add_view",
+            "Add View",
+            Some("views"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "drawing.dimension",
+            "Dimension",
+            Some("annotation"),
+        ));
+        for format in ExportFormat::ALL {
+            context.register_tool(ToolDescriptor::new_action(
+                format.request_id(),
+                format!("Export {}…", format.label()),
+                Some("export"),
+            ));
+        }
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Drawing workbench activated");
+        self.show_sheet = true;
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Drawing workbench deactivated");
+        self.dimension_kind = None;
+        self.pending_point = None;
+    }
+
+    fn on_input(
+        &mut self,
+        _event: &core_document::WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> core_document::InputResult {
+        match active_tool {
+            Some("drawing.add_view") => {
+                let bodies = selected_bodies(ctx);
+                if bodies.is_empty() {
+                    ctx.log_warn("Select a body to add a view of");
+                }
+                for body in bodies {
+                    self.add_view(ctx, body, self.new_view_projection);
+                }
+                core_document::InputResult::consumed()
+            }
+            Some("drawing.dimension") => {
+                self.dimension_kind = Some(self.dimension_kind.unwrap_or_default());
+                self.pending_point = None;
+                self.show_sheet = true;
+                core_document::InputResult::consumed()
+            }
+            Some(tool) => match ExportFormat::from_request_id(tool) {
+                Some(format) => {
+                    request_export_file(ctx, format);
+                    core_document::InputResult::consumed()
+                }
+                None => core_document::InputResult::ignored(),
+            },
+            None => core_document::InputResult::ignored(),
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+
+    fn on_file_opened(
+        &mut self,
+        request_id: &str,
+        path: &std::path::Path,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) {
+        let Some(format) = ExportFormat::from_request_id(request_id) else {
+            return;
+        };
+        let drawing = Drawing::load(ctx.document);
+        self.update_projections(&drawing, ctx);
+        let graphics = render::render(&drawing, &self.projections, &ctx.units);
+        match export::export(&graphics, format, path) {
+            Ok(()) => ctx.log_info(format!("Exported drawing to {}", path.display())),
+            Err(err) => ctx.log_error(format!("Failed to export {}: {err}", path.display())),
+        }
+    }
+}
+
+/// Ask the host where to save the exported sheet.
+/// Bodies to add views of: every selected body, or the body of the selected feature.
+fn selected_bodies(ctx: &WorkbenchRuntimeContext) -> Vec<BodyId> {
+    let bodies: Vec<BodyId> = ctx.selection.bodies().collect();
+    if bodies.is_empty() {
+        ctx.selected_body().into_iter().collect()
+    } else {
+        bodies
+    }
+}
+
+fn request_export_file(ctx: &mut WorkbenchRuntimeContext, format: ExportFormat) {
+    let title = Drawing::load(ctx.document).title_block.title;
+    let stem = if title.trim().is_empty() {
+        "drawing".to_string()
+    } else {
+        title.trim().to_string()
+    };
+    ctx.file_open_request = Some(core_document::FileOpenRequest {
+        id: format.request_id().to_string(),
+        filter_name: format!("{} drawing", format.label()),
+        extensions: vec![format.extension().to_string()],
+        save_name: Some(format!("{stem}.{}", format.extension())),
+        folder: false,
+    });
+}
+
+impl DrawingWorkbench {
+    /// Add a view of `body` at the largest standard scale that fits one cell
+    /// of the sheet.
+    fn add_view(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        body: BodyId,
+        projection: Projection,
+    ) {
+        let Some(body_mesh) = ctx.body_meshes.iter().find(|m| m.body == body) else {
+            ctx.log_warn("The selected body has no geometry to draw");
+            return;
+        };
+        let extent = hlr::project(&body_mesh.mesh, &projection.basis(&ctx.axes), None).size();
+        let mut drawing = Drawing::load(ctx.document);
+        let [width, height] = drawing.sheet.size_mm();
+        let cell = Vec2::new(width - 2.0 * MARGIN, height - 2.0 * MARGIN) / Vec2::new(3.0, 2.0);
+        let scale = view::fitting_scale(extent, cell * 0.8);
+        let id = drawing.add_view(body, projection, scale);
+        drawing.save(ctx.document);
+        self.selected_view = Some(id);
+        ctx.log_info(format!(
+            "Added {} view at {}",
+            projection.label().to_lowercase(),
+            view::scale_label(scale)
+        ));
+    }
+
+    /// Recompute the projections of views whose body, direction or geometry
+    /// changed, and drop those of removed views.
+    fn update_projections(&mut self, drawing: &Drawing, ctx: &WorkbenchRuntimeContext) {
+        self.projections
+            .retain(|id, _| drawing.views.iter().any(|view| view.id == *id));
+        self.projection_keys
+            .retain(|id, _| drawing.views.iter().any(|view| view.id == *id));
+        for view in &drawing.views {
+            let Some(body_mesh) = ctx.body_meshes.iter().find(|m| m.body == view.body) else {
+                self.projections.remove(&view.id);
+                self.projection_keys.remove(&view.id);
+                continue;
+            };
+            let key = projection_key(view, &body_mesh.mesh, &ctx.axes);
+            if self.projection_keys.get(&view.id) == Some(&key) {
+                continue;
+            }
+            let basis = view.projection.basis(&ctx.axes);
+            self.projections
+                .insert(view.id, hlr::project(&body_mesh.mesh, &basis, view.section));
+            self.projection_keys.insert(view.id, key);
+        }
+    }
+}
+
+/// Fingerprint of everything a view's projection depends on.
+fn projection_key(view: &DrawingView, mesh: &kernel_api::TriMesh, axes: &axes::AxisSystem) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    view.projection.hash(&mut hasher);
+    view.section.map(f32::to_bits).hash(&mut hasher);
+    for vector in [axes.right_vec(), axes.up_vec(), axes.forward_vec()] {
+        vector.to_array().map(f32::to_bits).hash(&mut hasher);
+    }
+    mesh.indices.hash(&mut hasher);
+    for position in &mesh.positions {
+        position.map(f32::to_bits).hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,410 @@
+//! Right-panel UI of the Drawing workbench and the sheet preview window.
+
+use core_document::{BodyId, WorkbenchRuntimeContext};
+use glam::Vec2;
+
+use crate::dimension::{self, Dimension, DimensionKind};
+use crate::export::ExportFormat;
+use crate::render::{self, LineStyle, SheetGraphics, TextAlign};
+use crate::sheet::{Drawing, SheetSize};
+use crate::view::{self, Projection};
+use crate::DrawingWorkbench;
+
+/// Snap points further than this (screen points) from the cursor are ignored.
+const SNAP_RADIUS: f32 = 8.0;
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut DrawingWorkbench,
+) {
+    let mut drawing = Drawing::load(ctx.document);
+    let mut changed = false;
+    workbench.selected_view = workbench
+        .selected_view
+        .filter(|id| drawing.view(*id).is_some());
+
+    ui.heading("Sheet");
+    egui::ComboBox::from_label("Size")
+        .selected_text(drawing.sheet.label())
+        .show_ui(ui, |ui| {
+            for size in SheetSize::ALL {
+                changed |= ui
+                    .selectable_value(&mut drawing.sheet, size, size.label())
+                    .changed();
+            }
+        });
+    ui.checkbox(&mut workbench.show_sheet, "Show sheet");
+
+    egui::CollapsingHeader::new("Title block").show(ui, |ui| {
+        let block = &mut drawing.title_block;
+        egui::Grid::new("drawing_title_block")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (label, value) in [
+                    ("Title", &mut block.title),
+                    ("Drawn by", &mut block.author),
+                    ("Date", &mut block.date),
+                    ("Drawing no.", &mut block.drawing_number),
+                    ("Material", &mut block.material),
+                ] {
+                    ui.label(label);
+                    changed |= ui.text_edit_singleline(value).changed();
+                    ui.end_row();
+                }
+            });
+    });
+
+    ui.separator();
+    ui.heading("Views");
+    let body_name = |id: BodyId| {
+        ctx.document
+            .bodies()
+            .iter()
+            .find(|body| body.id == id)
+            .map(|body| body.name.clone())
+            .unwrap_or_else(|| "(deleted body)".to_string())
+    };
+    for view in &drawing.views {
+        let is_selected = workbench.selected_view == Some(view.id);
+        if ui
+            .selectable_label(
+                is_selected,
+                format!("{} — {}", view.label(), body_name(view.body)),
+            )
+            .clicked()
+        {
+            workbench.selected_view = Some(view.id);
+        }
+    }
+    let bodies = crate::selected_bodies(ctx);
+    let add_clicked = ui
+        .horizontal(|ui| {
+            projection_combo(ui, "drawing_new_view", &mut workbench.new_view_projection);
+            ui.add_enabled(
+                !bodies.is_empty(),
+                egui::Button::new(if bodies.len() > 1 {
+                    "Add views of selected bodies"
+                } else {
+                    "Add view of selected body"
+                }),
+            )
+            .clicked()
+        })
+        .inner;
+    if add_clicked {
+        for body in bodies {
+            workbench.add_view(ctx, body, workbench.new_view_projection);
+        }
+        drawing = Drawing::load(ctx.document);
+    }
+
+    if let Some(id) = workbench.selected_view {
+        ui.add_space(4.0);
+        let mut remove = false;
+        if let Some(view) = drawing.view_mut(id) {
+            ui.horizontal(|ui| {
+                ui.label("Direction:");
+                changed |= projection_combo(ui, "drawing_view_projection", &mut view.projection);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scale:");
+                egui::ComboBox::from_id_salt("drawing_view_scale")
+                    .selected_text(view::scale_label(view.scale))
+                    .show_ui(ui, |ui| {
+                        for scale in view::STANDARD_SCALES {
+                            changed |= ui
+                                .selectable_value(&mut view.scale, scale, view::scale_label(scale))
+                                .changed();
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Position:");
+                for coordinate in &mut view.position {
+                    changed |= ui
+                        .add(egui::DragValue::new(coordinate).speed(0.5).suffix(" mm"))
+                        .changed();
+                }
+            });
+            changed |= ui.checkbox(&mut view.show_hidden, "Hidden lines").changed();
+            ui.horizontal(|ui| {
+                let mut sectioned = view.section.is_some();
+                if ui.checkbox(&mut sectioned, "Section at").changed() {
+                    view.section = sectioned.then_some(0.0);
+                    changed = true;
+                }
+                if let Some(depth) = &mut view.section {
+                    changed |= ui
+                        .add(egui::DragValue::new(depth).speed(0.1).suffix(" mm"))
+                        .on_hover_text(
+                            "Depth of the cut from the body's center, towards the viewer",
+                        )
+                        .changed();
+                }
+            });
+            remove = ui.button("Remove view").clicked();
+        }
+        if remove {
+            drawing.remove_view(id);
+            workbench.selected_view = None;
+            changed = true;
+        }
+    }
+
+    ui.separator();
+    ui.heading("Dimensions");
+    ui.horizontal(|ui| {
+        for kind in DimensionKind::ALL {
+            let active = workbench.dimension_kind == Some(kind);
+            if ui.selectable_label(active, kind.label()).clicked() {
+                workbench.dimension_kind = (!active).then_some(kind);
+                workbench.pending_point = None;
+            }
+        }
+    });
+    if workbench.dimension_kind.is_some() {
+        ui.weak(if workbench.pending_point.is_some() {
+            "Click the second point in the same view."
+        } else {
+            "Click two edge points of a view on the sheet."
+        });
+    }
+    let mut removed = None;
+    for (index, dimension) in drawing.dimensions.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} {}",
+                dimension.kind.label(),
+                ctx.units.format_length(dimension.value())
+            ));
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut dimension.offset)
+                        .speed(0.5)
+                        .suffix(" mm"),
+                )
+                .on_hover_text("Distance of the dimension line from the first point")
+                .changed();
+            if ui.small_button("✖").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        drawing.dimensions.remove(index);
+        changed = true;
+    }
+
+    ui.separator();
+    ui.heading("Export");
+    ui.horizontal(|ui| {
+        for format in ExportFormat::ALL {
+            if ui.button(format.label()).clicked() {
+                crate::request_export_file(ctx, format);
+            }
+        }
+    });
+
+    workbench.update_projections(&drawing, ctx);
+    if workbench.show_sheet {
+        let graphics = render::render(&drawing, &workbench.projections, &ctx.units);
+        let mut open = true;
+        egui::Window::new("Drawing Sheet")
+            .open(&mut open)
+            .default_size([640.0, 460.0])
+            .resizable(true)
+            .show(ui.ctx(), |ui| {
+                changed |= sheet_view(ui, workbench, &mut drawing, &graphics);
+            });
+        workbench.show_sheet = open;
+    }
+
+    if changed {
+        drawing.save(ctx.document);
+    }
+}
+
+fn projection_combo(ui: &mut egui::Ui, id: &str, projection: &mut Projection) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(projection.label())
+        .show_ui(ui, |ui| {
+            for option in Projection::ALL {
+                changed |= ui
+                    .selectable_value(projection, option, option.label())
+                    .changed();
+            }
+        });
+    changed
+}
+
+/// Paint the sheet and handle dragging views and placing dimensions.
+/// Returns whether the drawing changed.
+fn sheet_view(
+    ui: &mut egui::Ui,
+    workbench: &mut DrawingWorkbench,
+    drawing: &mut Drawing,
+    graphics: &SheetGraphics,
+) -> bool {
+    let [width, height] = graphics.size;
+    let available = ui.available_size();
+    let pixels_per_mm = (available.x / width).min(available.y / height).max(0.1);
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(width, height) * pixels_per_mm,
+        egui::Sense::click_and_drag(),
+    );
+    let to_screen = |p: Vec2| rect.min + egui::vec2(p.x, height - p.y) * pixels_per_mm;
+    let to_sheet = |p: egui::Pos2| {
+        let offset = (p - rect.min) / pixels_per_mm;
+        Vec2::new(offset.x, height - offset.y)
+    };
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+    let ink = egui::Color32::BLACK;
+    for line in &graphics.lines {
+        let stroke = egui::Stroke::new((line.style.width() * pixels_per_mm).max(1.0), ink);
+        let points = [to_screen(line.a), to_screen(line.b)];
+        match line.style.dash() {
+            Some([dash, gap]) if line.style == LineStyle::Hidden => {
+                painter.extend(egui::Shape::dashed_line(
+                    &points,
+                    stroke,
+                    dash * pixels_per_mm,
+                    gap * pixels_per_mm,
+                ));
+            }
+            _ => {
+                painter.line_segment(points, stroke);
+            }
+        }
+    }
+    for arrow in &graphics.arrows {
+        painter.add(egui::Shape::convex_polygon(
+            arrow.iter().map(|&p| to_screen(p)).collect(),
+            ink,
+            egui::Stroke::NONE,
+        ));
+    }
+    for text in &graphics.texts {
+        let galley = painter.layout_no_wrap(
+            text.text.clone(),
+            egui::FontId::proportional(text.font_size() * pixels_per_mm),
+            ink,
+        );
+        // egui places text by its top left corner and rotates clockwise.
+        let size = galley.size();
+        let offset = match text.align {
+            TextAlign::Left => egui::vec2(0.0, -size.y * 0.8),
+            TextAlign::Center => egui::vec2(-size.x * 0.5, -size.y * 0.8),
+        };
+        let rotation = egui::emath::Rot2::from_angle(-text.angle);
+        let position = to_screen(text.position) + rotation * offset;
+        painter.add(egui::epaint::TextShape::new(position, galley, ink).with_angle(-text.angle));
+    }
+
+    // Outline the selected view.
+    let view_bounds = |id: u32| {
+        let view = drawing.view(id)?;
+        let projection = workbench.projections.get(&id)?;
+        Some((view.to_sheet(projection.min), view.to_sheet(projection.max)))
+    };
+    if let Some((min, max)) = workbench.selected_view.and_then(view_bounds) {
+        painter.rect_stroke(
+            egui::Rect::from_two_pos(to_screen(min), to_screen(max)).expand(4.0),
+            2.0,
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 130, 220)),
+            egui::StrokeKind::Outside,
+        );
+    }
+
+    let pointer = response.hover_pos().or(response.interact_pointer_pos());
+    let mut changed = false;
+    if let Some(kind) = workbench.dimension_kind {
+        // Snap to the nearest edge point of any view.
+        let snap = pointer.and_then(|pointer| {
+            drawing
+                .views
+                .iter()
+                .filter_map(|view| Some((view, workbench.projections.get(&view.id)?)))
+                .flat_map(|(view, projection)| {
+                    projection
+                        .snap_points
+                        .iter()
+                        .map(move |&point| (view.id, point, to_screen(view.to_sheet(point))))
+                })
+                .map(|(id, point, screen)| (id, point, screen.distance(pointer)))
+                .filter(|(_, _, distance)| *distance <= SNAP_RADIUS)
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+        });
+        let accent = egui::Color32::from_rgb(220, 110, 40);
+        if let Some((view_id, point)) = workbench.pending_point {
+            if let Some(view) = drawing.view(view_id) {
+                painter.circle_filled(to_screen(view.to_sheet(Vec2::from(point))), 3.0, accent);
+            }
+        }
+        if let Some((view_id, point, _)) = snap {
+            if let Some(view) = drawing.view(view_id) {
+                painter.circle_stroke(
+                    to_screen(view.to_sheet(point)),
+                    5.0,
+                    egui::Stroke::new(1.5, accent),
+                );
+            }
+            if response.clicked() {
+                match workbench.pending_point {
+                    Some((first_view, first)) if first_view == view_id => {
+                        drawing.dimensions.push(Dimension {
+                            view: view_id,
+                            kind,
+                            a: first,
+                            b: point.to_array(),
+                            offset: dimension::DEFAULT_OFFSET,
+                        });
+                        workbench.pending_point = None;
+                        changed = true;
+                    }
+                    _ => workbench.pending_point = Some((view_id, point.to_array())),
+                }
+            }
+        }
+        if response.secondary_clicked() {
+            workbench.pending_point = None;
+            workbench.dimension_kind = None;
+        }
+        return changed;
+    }
+
+    let view_at = |point: Vec2| {
+        drawing.views.iter().rev().map(|view| view.id).find(|&id| {
+            view_bounds(id).is_some_and(|(min, max)| {
+                let (lo, hi) = (min.min(max), min.max(max));
+                point.cmpge(lo).all() && point.cmple(hi).all()
+            })
+        })
+    };
+    if response.clicked() {
+        workbench.selected_view = pointer.and_then(|p| view_at(to_sheet(p)));
+    }
+    if response.drag_started() {
+        workbench.dragged_view = pointer.and_then(|p| view_at(to_sheet(p)));
+        if workbench.dragged_view.is_some() {
+            workbench.selected_view = workbench.dragged_view;
+        }
+    }
+    if let Some(id) = workbench.dragged_view {
+        let delta = response.drag_delta() / pixels_per_mm;
+        if delta != egui::Vec2::ZERO {
+            if let Some(view) = drawing.view_mut(id) {
+                view.position[0] += delta.x;
+                view.position[1] -= delta.y;
+                changed = true;
+            }
+        }
+        if response.drag_stopped() {
+            workbench.dragged_view = None;
+        }
+    }
+    changed
+}
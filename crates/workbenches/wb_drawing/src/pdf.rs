@@ -0,0 +1,125 @@
+//! Single-page vector PDF output of a drawing sheet.
+//!
+//! Text uses the standard Helvetica font, which every PDF reader provides, so
+//! nothing has to be embedded.
+
+use std::fmt::Write;
+
+use glam::Vec2;
+
+use crate::render::{text_width, LineStyle, SheetGraphics, TextAlign};
+
+/// PDF points per millimetre.
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+
+pub fn to_pdf(graphics: &SheetGraphics) -> Vec<u8> {
+    let content = page_content(graphics);
+    let [width, height] = graphics.size.map(|mm| mm * POINTS_PER_MM);
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width:.2} {height:.2}] \
+             /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>"
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    let mut pdf: Vec<u8> = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", index + 1).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+/// Drawing operators of the page, in points.
+fn page_content(graphics: &SheetGraphics) -> String {
+    let point = |p: Vec2| p * POINTS_PER_MM;
+    let mut out = String::from("1 J 1 j\n");
+    for style in LineStyle::ALL {
+        let _ = writeln!(out, "{:.3} w", style.width() * POINTS_PER_MM);
+        match style.dash() {
+            Some([dash, gap]) => {
+                let _ = writeln!(
+                    out,
+                    "[{:.2} {:.2}] 0 d",
+                    dash * POINTS_PER_MM,
+                    gap * POINTS_PER_MM
+                );
+            }
+            None => out.push_str("[] 0 d\n"),
+        }
+        for line in graphics.lines.iter().filter(|line| line.style == style) {
+            let (a, b) = (point(line.a), point(line.b));
+            let _ = writeln!(out, "{:.2} {:.2} m {:.2} {:.2} l S", a.x, a.y, b.x, b.y);
+        }
+    }
+    for arrow in &graphics.arrows {
+        let [a, b, c] = arrow.map(point);
+        let _ = writeln!(
+            out,
+            "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l f",
+            a.x, a.y, b.x, b.y, c.x, c.y
+        );
+    }
+    for text in &graphics.texts {
+        let size = text.font_size();
+        let direction = Vec2::from_angle(text.angle);
+        let start = match text.align {
+            TextAlign::Left => text.position,
+            TextAlign::Center => text.position - direction * text_width(&text.text, size) * 0.5,
+        };
+        let origin = point(start);
+        let _ = writeln!(
+            out,
+            "BT /F1 {:.2} Tf {:.4} {:.4} {:.4} {:.4} {:.2} {:.2} Tm ({}) Tj ET",
+            size * POINTS_PER_MM,
+            direction.x,
+            direction.y,
+            -direction.y,
+            direction.x,
+            origin.x,
+            origin.y,
+            escape(&text.text)
+        );
+    }
+    out
+}
+
+/// PDF string literal contents in WinAnsi encoding (characters outside
+/// Latin-1 become '?').
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,354 @@
+//! Sheet contents as plain lines, arrowheads and text, shared by the on-screen
+//! preview and every export format.
+//!
+//! Coordinates are sheet millimetres with the origin in the lower left corner
+//! and y pointing up.
+
+use std::collections::HashMap;
+
+use core_document::UnitFormat;
+use glam::Vec2;
+
+use crate::hlr::ProjectedView;
+use crate::sheet::{Drawing, MARGIN, TITLE_BLOCK_SIZE};
+use crate::view::scale_label;
+
+/// Spacing (sheet mm) of the hatch lines filling section cuts.
+const HATCH_SPACING: f32 = 2.5;
+/// Arrowhead length and half width (sheet mm).
+const ARROW_LENGTH: f32 = 3.0;
+const ARROW_HALF_WIDTH: f32 = 0.6;
+/// Text heights (sheet mm).
+pub const TEXT_HEIGHT: f32 = 3.5;
+const TITLE_HEIGHT: f32 = 5.0;
+const LABEL_HEIGHT: f32 = 2.5;
+/// Cap height of a sans-serif font relative to its font size; text heights
+/// on the sheet are cap heights.
+const CAP_HEIGHT: f32 = 0.72;
+
+/// How a line is drawn (each style is also a DXF layer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineStyle {
+    /// Drawing frame and title block.
+    Frame,
+    Visible,
+    Hidden,
+    /// Hatching of section cuts.
+    Hatch,
+    /// Dimension and extension lines.
+    Dimension,
+}
+
+impl LineStyle {
+    pub const ALL: [LineStyle; 5] = [
+        LineStyle::Frame,
+        LineStyle::Visible,
+        LineStyle::Hidden,
+        LineStyle::Hatch,
+        LineStyle::Dimension,
+    ];
+
+    /// Pen width in sheet millimetres.
+    pub fn width(self) -> f32 {
+        match self {
+            LineStyle::Frame | LineStyle::Visible => 0.5,
+            LineStyle::Hidden => 0.35,
+            LineStyle::Hatch | LineStyle::Dimension => 0.25,
+        }
+    }
+
+    /// Dash and gap lengths (sheet mm) for dashed lines.
+    pub fn dash(self) -> Option<[f32; 2]> {
+        match self {
+            LineStyle::Hidden => Some([3.0, 1.5]),
+            _ => None,
+        }
+    }
+
+    pub fn layer_name(self) -> &'static str {
+        match self {
+            LineStyle::Frame => "FRAME",
+            LineStyle::Visible => "VISIBLE",
+            LineStyle::Hidden => "HIDDEN",
+            LineStyle::Hatch => "HATCH",
+            LineStyle::Dimension => "DIMENSIONS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SheetLine {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub style: LineStyle,
+}
+
+/// Horizontal placement of text relative to its anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+}
+
+#[derive(Debug, Clone)]
+pub struct SheetText {
+    /// Anchor on the baseline.
+    pub position: Vec2,
+    pub height: f32,
+    /// Radians, counter-clockwise.
+    pub angle: f32,
+    pub align: TextAlign,
+    pub text: String,
+}
+
+impl SheetText {
+    /// Font size (sheet mm) that gives the text its cap height.
+    pub fn font_size(&self) -> f32 {
+        self.height / CAP_HEIGHT
+    }
+}
+
+/// Everything drawn on a sheet.
+#[derive(Debug, Clone, Default)]
+pub struct SheetGraphics {
+    pub size: [f32; 2],
+    pub lines: Vec<SheetLine>,
+    /// Filled arrowheads (tip first).
+    pub arrows: Vec<[Vec2; 3]>,
+    pub texts: Vec<SheetText>,
+}
+
+impl SheetGraphics {
+    fn line(&mut self, a: Vec2, b: Vec2, style: LineStyle) {
+        self.lines.push(SheetLine { a, b, style });
+    }
+
+    fn rect(&mut self, min: Vec2, max: Vec2, style: LineStyle) {
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        for i in 0..4 {
+            self.line(corners[i], corners[(i + 1) % 4], style);
+        }
+    }
+
+    fn text(&mut self, position: Vec2, height: f32, align: TextAlign, text: impl Into<String>) {
+        self.texts.push(SheetText {
+            position,
+            height,
+            angle: 0.0,
+            align,
+            text: text.into(),
+        });
+    }
+
+    /// Arrowhead with its tip at `tip`, pointing along `direction`.
+    fn arrow(&mut self, tip: Vec2, direction: Vec2) {
+        let direction = direction.normalize_or_zero();
+        let base = tip - direction * ARROW_LENGTH;
+        let side = direction.perp() * ARROW_HALF_WIDTH;
+        self.arrows.push([tip, base + side, base - side]);
+    }
+}
+
+/// Lay out the frame, title block, views and dimensions of `drawing`.
+/// `projections` holds the projected geometry of each view by view id.
+pub fn render(
+    drawing: &Drawing,
+    projections: &HashMap<u32, ProjectedView>,
+    units: &UnitFormat,
+) -> SheetGraphics {
+    let mut graphics = SheetGraphics {
+        size: drawing.sheet.size_mm(),
+        ..SheetGraphics::default()
+    };
+    let [width, height] = graphics.size;
+    graphics.rect(
+        Vec2::splat(MARGIN),
+        Vec2::new(width - MARGIN, height - MARGIN),
+        LineStyle::Frame,
+    );
+    title_block(&mut graphics, drawing, units);
+
+    for view in &drawing.views {
+        let Some(projection) = projections.get(&view.id) else {
+            continue;
+        };
+        let to_sheet = |point: Vec2| view.to_sheet(point);
+        for [a, b] in &projection.visible {
+            graphics.line(to_sheet(*a), to_sheet(*b), LineStyle::Visible);
+        }
+        if view.show_hidden {
+            for [a, b] in &projection.hidden {
+                graphics.line(to_sheet(*a), to_sheet(*b), LineStyle::Hidden);
+            }
+        }
+        let outlines: Vec<Vec<Vec2>> = projection
+            .sections
+            .iter()
+            .map(|outline| outline.iter().map(|&p| to_sheet(p)).collect())
+            .collect();
+        for outline in &outlines {
+            for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+                graphics.line(*a, *b, LineStyle::Visible);
+            }
+        }
+        for [a, b] in hatch(&outlines) {
+            graphics.line(a, b, LineStyle::Hatch);
+        }
+        let label_position = to_sheet(Vec2::new(0.0, projection.min.y)) - Vec2::Y * 6.0;
+        graphics.text(
+            label_position,
+            LABEL_HEIGHT,
+            TextAlign::Center,
+            format!(
+                "{} ({})",
+                view.label().to_uppercase(),
+                scale_label(view.scale)
+            ),
+        );
+    }
+
+    for dimension in &drawing.dimensions {
+        let Some(view) = drawing.view(dimension.view) else {
+            continue;
+        };
+        let a = view.to_sheet(Vec2::from(dimension.a));
+        let b = view.to_sheet(Vec2::from(dimension.b));
+        let layout = dimension.layout(a, b);
+        for [from, to] in layout.extension_lines {
+            graphics.line(from, to, LineStyle::Dimension);
+        }
+        let [start, end] = layout.dimension_line;
+        graphics.line(start, end, LineStyle::Dimension);
+        if start.distance(end) > f32::EPSILON {
+            graphics.arrow(start, start - end);
+            graphics.arrow(end, end - start);
+        }
+        graphics.texts.push(SheetText {
+            position: layout.text_position,
+            height: TEXT_HEIGHT,
+            angle: layout.text_angle,
+            align: TextAlign::Center,
+            text: units.format_value(dimension.value()),
+        });
+    }
+    graphics
+}
+
+fn title_block(graphics: &mut SheetGraphics, drawing: &Drawing, units: &UnitFormat) {
+    let [width, _] = graphics.size;
+    let block_width = TITLE_BLOCK_SIZE[0].min(width - 2.0 * MARGIN);
+    let max = Vec2::new(width - MARGIN, MARGIN + TITLE_BLOCK_SIZE[1]);
+    let min = Vec2::new(max.x - block_width, MARGIN);
+    graphics.rect(min, max, LineStyle::Frame);
+
+    // Title across the top row, details in a 3 × 2 grid below it.
+    let row = TITLE_BLOCK_SIZE[1] / 3.0;
+    let title_bottom = max.y - row;
+    graphics.line(
+        Vec2::new(min.x, title_bottom),
+        Vec2::new(max.x, title_bottom),
+        LineStyle::Frame,
+    );
+    graphics.line(
+        Vec2::new(min.x, MARGIN + row),
+        Vec2::new(max.x, MARGIN + row),
+        LineStyle::Frame,
+    );
+    let column = block_width / 3.0;
+    for i in 1..3 {
+        let x = min.x + column * i as f32;
+        graphics.line(
+            Vec2::new(x, MARGIN),
+            Vec2::new(x, title_bottom),
+            LineStyle::Frame,
+        );
+    }
+    let block = &drawing.title_block;
+    graphics.text(
+        Vec2::new(min.x + 2.0, title_bottom + (row - TITLE_HEIGHT) * 0.5),
+        TITLE_HEIGHT,
+        TextAlign::Left,
+        block.title.clone(),
+    );
+    let mut scales: Vec<String> = Vec::new();
+    for view in &drawing.views {
+        let label = scale_label(view.scale);
+        if !scales.contains(&label) {
+            scales.push(label);
+        }
+    }
+    let cells = [
+        ("Drawn by", block.author.clone()),
+        ("Date", block.date.clone()),
+        ("Drawing no.", block.drawing_number.clone()),
+        ("Material", block.material.clone()),
+        ("Scale", scales.join(", ")),
+        (
+            "Units / Sheet",
+            format!("{} / {}", units.unit.symbol(), drawing.sheet.label()),
+        ),
+    ];
+    for (index, (caption, value)) in cells.into_iter().enumerate() {
+        let x = min.x + column * (index % 3) as f32 + 1.5;
+        let top = title_bottom - row * (index / 3) as f32;
+        graphics.text(
+            Vec2::new(x, top - LABEL_HEIGHT - 1.0),
+            LABEL_HEIGHT * 0.8,
+            TextAlign::Left,
+            caption,
+        );
+        graphics.text(
+            Vec2::new(x, top - row + 1.5),
+            LABEL_HEIGHT,
+            TextAlign::Left,
+            value,
+        );
+    }
+}
+
+/// 45° hatch lines clipped to the inside of the outlines (even-odd rule).
+fn hatch(outlines: &[Vec<Vec2>]) -> Vec<[Vec2; 2]> {
+    // Rotate so the hatch lines are horizontal, clip, and rotate back.
+    let rotation = Vec2::from_angle(-std::f32::consts::FRAC_PI_4);
+    let back = Vec2::from_angle(std::f32::consts::FRAC_PI_4);
+    let rotated: Vec<Vec<Vec2>> = outlines
+        .iter()
+        .map(|outline| outline.iter().map(|&p| rotation.rotate(p)).collect())
+        .collect();
+    let Some((low, high)) = rotated
+        .iter()
+        .flatten()
+        .fold(None, |acc: Option<(f32, f32)>, p| {
+            Some(acc.map_or((p.y, p.y), |(low, high)| (low.min(p.y), high.max(p.y))))
+        })
+    else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let mut y = (low / HATCH_SPACING).ceil() * HATCH_SPACING;
+    while y < high {
+        let mut crossings: Vec<f32> = Vec::new();
+        for outline in &rotated {
+            for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+                if (a.y > y) != (b.y > y) {
+                    crossings.push(a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x));
+                }
+            }
+        }
+        crossings.sort_by(f32::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            lines.push([
+                back.rotate(Vec2::new(pair[0], y)),
+                back.rotate(Vec2::new(pair[1], y)),
+            ]);
+        }
+        y += HATCH_SPACING;
+    }
+    lines
+}
+
+/// Approximate width of `text` at `font_size` (proportional sans-serif font),
+/// used where the output format cannot center text itself.
+pub fn text_width(text: &str, font_size: f32) -> f32 {
+    text.chars().count() as f32 * font_size * 0.55
+}
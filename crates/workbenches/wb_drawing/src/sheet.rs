@@ -0,0 +1,155 @@
+//! The drawing sheet: paper size, title block, and the views and dimensions
+//! placed on it, stored in the document's `wb.drawing` storage.
+
+use core_document::{BodyId, Document, WorkbenchId};
+use serde::{Deserialize, Serialize};
+
+use crate::dimension::Dimension;
+use crate::view::{DrawingView, Projection};
+use crate::WORKBENCH_ID;
+
+/// Distance (mm) from the paper edge to the drawing frame.
+pub const MARGIN: f32 = 10.0;
+
+/// Size (mm) of the title block in the lower right corner of the frame.
+pub const TITLE_BLOCK_SIZE: [f32; 2] = [180.0, 32.0];
+
+/// Standard paper sizes, used in landscape orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetSize {
+    A4,
+    #[default]
+    A3,
+    A2,
+    A1,
+    A0,
+    Letter,
+    Tabloid,
+}
+
+impl SheetSize {
+    pub const ALL: [SheetSize; 7] = [
+        SheetSize::A4,
+        SheetSize::A3,
+        SheetSize::A2,
+        SheetSize::A1,
+        SheetSize::A0,
+        SheetSize::Letter,
+        SheetSize::Tabloid,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SheetSize::A4 => "A4",
+            SheetSize::A3 => "A3",
+            SheetSize::A2 => "A2",
+            SheetSize::A1 => "A1",
+            SheetSize::A0 => "A0",
+            SheetSize::Letter => "Letter",
+            SheetSize::Tabloid => "Tabloid",
+        }
+    }
+
+    /// Width and height in millimetres (landscape).
+    pub fn size_mm(self) -> [f32; 2] {
+        match self {
+            SheetSize::A4 => [297.0, 210.0],
+            SheetSize::A3 => [420.0, 297.0],
+            SheetSize::A2 => [594.0, 420.0],
+            SheetSize::A1 => [841.0, 594.0],
+            SheetSize::A0 => [1189.0, 841.0],
+            SheetSize::Letter => [279.4, 215.9],
+            SheetSize::Tabloid => [431.8, 279.4],
+        }
+    }
+}
+
+/// Text shown in the title block.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleBlock {
+    pub title: String,
+    pub author: String,
+    pub drawing_number: String,
+    pub date: String,
+    pub material: String,
+}
+
+/// Everything placed on the drawing sheet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Drawing {
+    #[serde(default)]
+    pub sheet: SheetSize,
+    #[serde(default)]
+    pub title_block: TitleBlock,
+    #[serde(default)]
+    pub views: Vec<DrawingView>,
+    #[serde(default)]
+    pub dimensions: Vec<Dimension>,
+    /// Id given to the next view (ids are never reused so dimensions keep
+    /// pointing at the right view).
+    #[serde(default)]
+    next_view_id: u32,
+}
+
+impl Drawing {
+    /// Load the drawing stored in the document (an empty sheet if none was saved).
+    pub fn load(document: &Document) -> Self {
+        document
+            .get_workbench_storage(&WorkbenchId::from(WORKBENCH_ID))
+            .and_then(|storage| serde_json::from_value(storage.data.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store the drawing in the document (marks the document dirty).
+    pub fn save(&self, document: &mut Document) {
+        if let Ok(data) = serde_json::to_value(self) {
+            document.set_workbench_storage(WorkbenchId::from(WORKBENCH_ID), data);
+        }
+    }
+
+    pub fn view(&self, id: u32) -> Option<&DrawingView> {
+        self.views.iter().find(|view| view.id == id)
+    }
+
+    pub fn view_mut(&mut self, id: u32) -> Option<&mut DrawingView> {
+        self.views.iter_mut().find(|view| view.id == id)
+    }
+
+    /// Add a view of `body` and return its id. The view is put in the next
+    /// free cell of a 3 × 2 grid over the area above the title block.
+    pub fn add_view(&mut self, body: BodyId, projection: Projection, scale: f32) -> u32 {
+        let id = self.next_view_id;
+        self.next_view_id += 1;
+        let slot = self.views.len() % 6;
+        let [width, height] = self.sheet.size_mm();
+        let cell = [(width - 2.0 * MARGIN) / 3.0, (height - 2.0 * MARGIN) / 2.0];
+        let position = [
+            MARGIN + cell[0] * ((slot % 3) as f32 + 0.5),
+            height - MARGIN - cell[1] * ((slot / 3) as f32 + 0.5),
+        ];
+        self.views.push(DrawingView {
+            id,
+            body,
+            projection,
+            section: None,
+            position,
+            scale,
+            show_hidden: true,
+        });
+        id
+    }
+
+    /// Remove a view together with its dimensions.
+    pub fn remove_view(&mut self, id: u32) {
+        self.views.retain(|view| view.id != id);
+        self.dimensions.retain(|dimension| dimension.view != id);
+    }
+
+    /// Region (min, max corner in sheet mm) inside the frame where views go.
+    pub fn frame(&self) -> ([f32; 2], [f32; 2]) {
+        let [width, height] = self.sheet.size_mm();
+        ([MARGIN, MARGIN], [width - MARGIN, height - MARGIN])
+    }
+}
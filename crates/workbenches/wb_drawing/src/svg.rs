@@ -0,0 +1,96 @@
+//! SVG output of a drawing sheet (one user unit per millimetre).
+
+use std::fmt::Write;
+
+use glam::Vec2;
+
+use crate::render::{LineStyle, SheetGraphics, TextAlign};
+
+pub fn to_svg(graphics: &SheetGraphics) -> String {
+    let [width, height] = graphics.size;
+    // SVG's y axis points down.
+    let flip = |p: Vec2| Vec2::new(p.x, height - p.y);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}mm" height="{height}mm" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect x="0" y="0" width="{width}" height="{height}" fill="white"/>"#
+    );
+    for style in LineStyle::ALL {
+        let dash = style
+            .dash()
+            .map(|[dash, gap]| format!(r#" stroke-dasharray="{dash} {gap}""#))
+            .unwrap_or_default();
+        let _ = writeln!(
+            svg,
+            r#"<g id="{}" stroke="black" stroke-width="{}" stroke-linecap="round"{dash}>"#,
+            style.layer_name().to_lowercase(),
+            style.width()
+        );
+        for line in graphics.lines.iter().filter(|line| line.style == style) {
+            let (a, b) = (flip(line.a), flip(line.b));
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}"/>"#,
+                a.x, a.y, b.x, b.y
+            );
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+
+    let _ = writeln!(svg, r#"<g id="arrows" fill="black">"#);
+    for arrow in &graphics.arrows {
+        let points: Vec<String> = arrow
+            .iter()
+            .map(|&p| {
+                let p = flip(p);
+                format!("{:.3},{:.3}", p.x, p.y)
+            })
+            .collect();
+        let _ = writeln!(svg, r#"<polygon points="{}"/>"#, points.join(" "));
+    }
+    let _ = writeln!(svg, "</g>");
+
+    let _ = writeln!(
+        svg,
+        r#"<g id="text" font-family="sans-serif" fill="black">"#
+    );
+    for text in &graphics.texts {
+        let p = flip(text.position);
+        let anchor = match text.align {
+            TextAlign::Left => "start",
+            TextAlign::Center => "middle",
+        };
+        let rotate = if text.angle == 0.0 {
+            String::new()
+        } else {
+            format!(
+                r#" transform="rotate({:.3} {:.3} {:.3})""#,
+                -text.angle.to_degrees(),
+                p.x,
+                p.y
+            )
+        };
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.3}" y="{:.3}" font-size="{:.3}" text-anchor="{anchor}"{rotate}>{}</text>"#,
+            p.x,
+            p.y,
+            text.font_size(),
+            escape(&text.text)
+        );
+    }
+    let _ = writeln!(svg, "</g>");
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
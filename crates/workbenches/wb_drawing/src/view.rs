@@ -0,0 +1,148 @@
+//! Drawing views: which body is shown, from which direction, and where on the sheet.
+
+use axes::AxisSystem;
+use core_document::BodyId;
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Scales offered when adding a view, largest first.
+pub const STANDARD_SCALES: [f32; 9] = [10.0, 5.0, 2.0, 1.0, 0.5, 0.2, 0.1, 0.05, 0.02];
+
+/// Direction a view looks at the body from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Projection {
+    #[default]
+    Front,
+    Back,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    Isometric,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 7] = [
+        Projection::Front,
+        Projection::Back,
+        Projection::Top,
+        Projection::Bottom,
+        Projection::Left,
+        Projection::Right,
+        Projection::Isometric,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Projection::Front => "Front",
+            Projection::Back => "Back",
+            Projection::Top => "Top",
+            Projection::Bottom => "Bottom",
+            Projection::Left => "Left",
+            Projection::Right => "Right",
+            Projection::Isometric => "Isometric",
+        }
+    }
+
+    /// Paper axes of the view in world space, following the active axis convention.
+    pub fn basis(self, axes: &AxisSystem) -> ViewBasis {
+        let (right, up, forward) = (axes.right_vec(), axes.up_vec(), axes.forward_vec());
+        let (view_right, view_up) = match self {
+            Projection::Front => (right, up),
+            Projection::Back => (-right, up),
+            Projection::Top => (right, -forward),
+            Projection::Bottom => (right, forward),
+            Projection::Left => (forward, up),
+            Projection::Right => (-forward, up),
+            Projection::Isometric => (
+                (right - forward).normalize(),
+                (up * 2.0 - right - forward).normalize(),
+            ),
+        };
+        ViewBasis {
+            right: view_right,
+            up: view_up,
+            toward: view_right.cross(view_up),
+        }
+    }
+}
+
+/// Orthographic projection frame: `toward` points from the body to the viewer.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewBasis {
+    pub right: Vec3,
+    pub up: Vec3,
+    pub toward: Vec3,
+}
+
+impl ViewBasis {
+    /// Paper position and depth (larger is closer to the viewer) of a world point.
+    pub fn project(&self, point: Vec3) -> (Vec2, f32) {
+        (
+            Vec2::new(point.dot(self.right), point.dot(self.up)),
+            point.dot(self.toward),
+        )
+    }
+}
+
+/// One view placed on the sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawingView {
+    pub id: u32,
+    pub body: BodyId,
+    pub projection: Projection,
+    /// Depth (mm, towards the viewer from the body's center) of a section
+    /// plane; material in front of it is removed.
+    #[serde(default)]
+    pub section: Option<f32>,
+    /// Center of the view on the sheet (mm from the lower left corner).
+    pub position: [f32; 2],
+    /// Sheet millimetres per model millimetre.
+    pub scale: f32,
+    /// Draw hidden edges dashed.
+    #[serde(default)]
+    pub show_hidden: bool,
+}
+
+impl DrawingView {
+    pub fn label(&self) -> String {
+        match self.section {
+            Some(_) => format!("Section {}", self.projection.label()),
+            None => self.projection.label().to_string(),
+        }
+    }
+
+    /// Sheet position of a point in view coordinates (model mm around the view center).
+    pub fn to_sheet(&self, local: Vec2) -> Vec2 {
+        Vec2::from(self.position) + local * self.scale
+    }
+
+    /// View coordinates of a sheet position.
+    pub fn to_local(&self, sheet: Vec2) -> Vec2 {
+        (sheet - Vec2::from(self.position)) / self.scale
+    }
+}
+
+/// Scale written the usual way, e.g. "1:2" or "5:1".
+pub fn scale_label(scale: f32) -> String {
+    let format = |value: f32| {
+        let text = format!("{value:.2}");
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    };
+    if scale >= 1.0 {
+        format!("{}:1", format(scale))
+    } else {
+        format!("1:{}", format(1.0 / scale))
+    }
+}
+
+/// Largest standard scale at which a body of `extent` (model mm) fits in
+/// `space` (sheet mm).
+pub fn fitting_scale(extent: Vec2, space: Vec2) -> f32 {
+    STANDARD_SCALES
+        .iter()
+        .copied()
+        .find(|&scale| extent.x * scale <= space.x && extent.y * scale <= space.y)
+        .unwrap_or(STANDARD_SCALES[STANDARD_SCALES.len() - 1])
+}
@@ -0,0 +1,248 @@
+//! Deviation of a measured mesh (e.g. a 3D scan of a printed part) from the
+//! reference body, and the color map used to show it.
+
+use std::collections::HashMap;
+
+use core_document::bvh::Bvh;
+use glam::Vec3;
+use kernel_api::TriMesh;
+
+pub const DEFAULT_TOLERANCE: f32 = 0.1;
+pub const DEFAULT_RANGE: f32 = 1.0;
+
+/// Color bands on each side of the in-tolerance band.
+const SIDE_BANDS: usize = 4;
+const IN_TOLERANCE_COLOR: [f32; 3] = [0.3, 0.8, 0.4];
+/// From just above the tolerance to the top of the range.
+const EXCESS_COLORS: [[f32; 3]; SIDE_BANDS] = [
+    [0.75, 0.9, 0.3],
+    [0.95, 0.85, 0.2],
+    [0.95, 0.55, 0.15],
+    [0.9, 0.2, 0.15],
+];
+/// From just below the tolerance to the bottom of the range.
+const MISSING_COLORS: [[f32; 3]; SIDE_BANDS] = [
+    [0.3, 0.85, 0.85],
+    [0.3, 0.6, 0.95],
+    [0.2, 0.35, 0.9],
+    [0.35, 0.2, 0.75],
+];
+
+/// Signed distances of the measured mesh's vertices to the reference surface;
+/// positive where the measured part has excess material.
+#[derive(Debug, Clone, Default)]
+pub struct DeviationAnalysis {
+    /// Deviation of every vertex of the measured mesh, by index.
+    pub vertex_deviations: Vec<f32>,
+    /// Deviation of every distinct measured point (shared vertices counted once).
+    pub samples: Vec<f32>,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub rms: f32,
+    pub std_dev: f32,
+    /// Where the largest negative and positive deviations were found.
+    pub min_at: Vec3,
+    pub max_at: Vec3,
+}
+
+impl DeviationAnalysis {
+    /// Share of the measured points within ±`tolerance`.
+    pub fn within(&self, tolerance: f32) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let inside = self
+            .samples
+            .iter()
+            .filter(|deviation| deviation.abs() <= tolerance)
+            .count();
+        inside as f32 / self.samples.len() as f32
+    }
+}
+
+/// Compare `measured` against `reference`; None if either has no triangles.
+pub fn analyze(reference: &TriMesh, measured: &TriMesh) -> Option<DeviationAnalysis> {
+    let bvh = Bvh::from_mesh(reference);
+    if bvh.is_empty() || measured.positions.is_empty() {
+        return None;
+    }
+
+    // Meshes for display repeat vertices per face; measure each point once.
+    let mut distinct: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut points = Vec::new();
+    let vertex_samples: Vec<usize> = measured
+        .positions
+        .iter()
+        .map(|p| {
+            *distinct.entry(p.map(f32::to_bits)).or_insert_with(|| {
+                points.push(Vec3::from(*p));
+                points.len() - 1
+            })
+        })
+        .collect();
+    let samples: Vec<f32> = points
+        .iter()
+        .map(|&p| bvh.closest(p).map_or(0.0, |hit| hit.signed_distance(p)))
+        .collect();
+
+    let mut result = DeviationAnalysis {
+        vertex_deviations: vertex_samples.iter().map(|&i| samples[i]).collect(),
+        min: f32::INFINITY,
+        max: f32::NEG_INFINITY,
+        ..Default::default()
+    };
+    let (mut sum, mut sum_squares) = (0.0f64, 0.0f64);
+    for (&deviation, &point) in samples.iter().zip(&points) {
+        if deviation < result.min {
+            result.min = deviation;
+            result.min_at = point;
+        }
+        if deviation > result.max {
+            result.max = deviation;
+            result.max_at = point;
+        }
+        sum += f64::from(deviation);
+        sum_squares += f64::from(deviation) * f64::from(deviation);
+    }
+    let count = samples.len() as f64;
+    let mean = sum / count;
+    result.mean = mean as f32;
+    result.rms = (sum_squares / count).sqrt() as f32;
+    result.std_dev = (sum_squares / count - mean * mean).max(0.0).sqrt() as f32;
+    result.samples = samples;
+    Some(result)
+}
+
+/// One band of the color scale.
+#[derive(Debug, Clone, Copy)]
+pub struct Band {
+    /// Bounds of the band (infinite for the outermost bands).
+    pub low: f32,
+    pub high: f32,
+    pub color: [f32; 3],
+}
+
+/// Discrete color scale: green within ±tolerance, then bands up to ±range
+/// (warm colors for excess material, cool colors for missing material).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorScale {
+    pub tolerance: f32,
+    pub range: f32,
+}
+
+impl Default for ColorScale {
+    fn default() -> Self {
+        Self {
+            tolerance: DEFAULT_TOLERANCE,
+            range: DEFAULT_RANGE,
+        }
+    }
+}
+
+impl ColorScale {
+    fn step(&self) -> f32 {
+        ((self.range - self.tolerance) / SIDE_BANDS as f32).max(f32::EPSILON)
+    }
+
+    pub fn color(&self, deviation: f32) -> [f32; 3] {
+        if deviation.abs() <= self.tolerance {
+            return IN_TOLERANCE_COLOR;
+        }
+        let band =
+            (((deviation.abs() - self.tolerance) / self.step()) as usize).min(SIDE_BANDS - 1);
+        if deviation > 0.0 {
+            EXCESS_COLORS[band]
+        } else {
+            MISSING_COLORS[band]
+        }
+    }
+
+    /// All bands from the most positive to the most negative.
+    pub fn bands(&self) -> Vec<Band> {
+        let step = self.step();
+        let edge = |band: usize| self.tolerance + step * band as f32;
+        let outer = |band: usize, bound: f32| {
+            if band == SIDE_BANDS - 1 {
+                bound
+            } else {
+                edge(band + 1)
+            }
+        };
+        let mut bands: Vec<Band> = (0..SIDE_BANDS)
+            .rev()
+            .map(|band| Band {
+                low: edge(band),
+                high: outer(band, f32::INFINITY),
+                color: EXCESS_COLORS[band],
+            })
+            .collect();
+        bands.push(Band {
+            low: -self.tolerance,
+            high: self.tolerance,
+            color: IN_TOLERANCE_COLOR,
+        });
+        bands.extend((0..SIDE_BANDS).map(|band| Band {
+            low: -outer(band, f32::INFINITY),
+            high: -edge(band),
+            color: MISSING_COLORS[band],
+        }));
+        bands
+    }
+
+    /// The measured mesh split into one mesh per color, each triangle colored
+    /// by the mean deviation of its corners.
+    pub fn colored_meshes(
+        &self,
+        measured: &TriMesh,
+        analysis: &DeviationAnalysis,
+    ) -> Vec<(TriMesh, [f32; 3])> {
+        let mut meshes: Vec<(TriMesh, [f32; 3])> = Vec::new();
+        for tri in measured.indices.chunks_exact(3) {
+            let corners = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+            if corners
+                .iter()
+                .any(|&i| i >= measured.positions.len() || i >= analysis.vertex_deviations.len())
+            {
+                continue;
+            }
+            let positions = corners.map(|i| Vec3::from(measured.positions[i]));
+            let deviation = corners
+                .iter()
+                .map(|&i| analysis.vertex_deviations[i])
+                .sum::<f32>()
+                / 3.0;
+            let color = self.color(deviation);
+            let normal = (positions[1] - positions[0])
+                .cross(positions[2] - positions[0])
+                .normalize_or_zero();
+            let index = match meshes.iter().position(|(_, c)| *c == color) {
+                Some(index) => index,
+                None => {
+                    meshes.push((TriMesh::default(), color));
+                    meshes.len() - 1
+                }
+            };
+            let mesh = &mut meshes[index].0;
+            let base = mesh.positions.len() as u32;
+            for position in positions {
+                mesh.positions.push(position.to_array());
+                mesh.normals.push(normal.to_array());
+            }
+            mesh.indices.extend([base, base + 1, base + 2]);
+        }
+        meshes
+    }
+}
+
+/// Counts of samples in `bins` equal bins over [-range, range] (outliers are
+/// counted in the outermost bins).
+pub fn histogram(samples: &[f32], range: f32, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins.max(1)];
+    let width = 2.0 * range / counts.len() as f32;
+    for &deviation in samples {
+        let bin = ((deviation + range) / width).floor().max(0.0) as usize;
+        counts[bin.min(bins.max(1) - 1)] += 1;
+    }
+    counts
+}
@@ -0,0 +1,432 @@
+pub mod deviation;
+#[cfg(feature = "egui")]
+mod panel;
+pub mod surface;
+
+use std::hash::{Hash, Hasher};
+
+use core_document::{
+    BodyId, BodyMesh, FeatureId, JobId, ScreenSpaceOverlay, ToolDescriptor, Workbench,
+    WorkbenchContext, WorkbenchDescriptor, WorkbenchRuntimeContext, ZebraMesh,
+};
+use deviation::{ColorScale, DeviationAnalysis};
+use kernel_api::TriMesh;
+use surface::{CurvatureScale, StripeDirection, SurfaceAnalysis, SurfaceMode};
+
+/// Workbench identifier.
+pub const WORKBENCH_ID: &str = "wb.inspection";
+
+/// Screen colors of the markers at the largest deviations.
+const MAX_MARKER_COLOR: [f32; 3] = [0.95, 0.25, 0.2];
+const MIN_MARKER_COLOR: [f32; 3] = [0.25, 0.4, 0.95];
+/// Half size of the markers in pixels.
+const MARKER_SIZE: f32 = 8.0;
+
+/// Inspection workbench: compares a measured mesh (such as a 3D scan of a
+/// printed part, imported with the Mesh workbench) against the CAD body and
+/// shows the deviation as a color map with statistics, and shows the surface
+/// quality of bodies as curvature maps or zebra stripes.
+pub struct InspectionWorkbench {
+    /// Body the measurement is compared against.
+    reference: Option<BodyId>,
+    /// Body holding the measured mesh.
+    measured: Option<BodyId>,
+    scale: ColorScale,
+    show_map: bool,
+    /// Mark where the largest positive and negative deviations are.
+    show_extremes: bool,
+    /// Last comparison, with a fingerprint of the meshes it was computed from.
+    analysis: Option<(u64, DeviationAnalysis)>,
+    /// Comparison running in the background, with the fingerprint of its meshes.
+    job: Option<(u64, JobId)>,
+    /// Fingerprint of the meshes last sent for comparison (failed comparisons
+    /// are not retried until the meshes change).
+    requested: Option<u64>,
+    /// Measured mesh split by color for the current analysis and scale, in
+    /// the measured body's frame (the comparison is made there).
+    map: Vec<(TriMesh, [f32; 3])>,
+    map_scale: Option<ColorScale>,
+    surface_mode: SurfaceMode,
+    stripe_direction: StripeDirection,
+    stripes: f32,
+    curvature_scale: CurvatureScale,
+    /// Fit the curvature scale to the analyzed bodies.
+    fit_curvature_scale: bool,
+    /// Curvature of the inspected bodies, each in its own frame, with a
+    /// fingerprint of their meshes.
+    surface: Option<(u64, Vec<(BodyId, SurfaceAnalysis)>)>,
+    /// Curvature map for the current analysis, mode, and scale.
+    surface_map: Vec<(BodyId, TriMesh, [f32; 3])>,
+    surface_map_scale: Option<(SurfaceMode, CurvatureScale)>,
+}
+
+impl Default for InspectionWorkbench {
+    fn default() -> Self {
+        Self {
+            reference: None,
+            measured: None,
+            scale: ColorScale::default(),
+            show_map: true,
+            show_extremes: true,
+            analysis: None,
+            job: None,
+            requested: None,
+            map: Vec::new(),
+            map_scale: None,
+            surface_mode: SurfaceMode::Off,
+            stripe_direction: StripeDirection::Horizontal,
+            stripes: surface::DEFAULT_STRIPES,
+            curvature_scale: CurvatureScale::default(),
+            fit_curvature_scale: true,
+            surface: None,
+            surface_map: Vec::new(),
+            surface_map_scale: None,
+        }
+    }
+}
+
+impl Workbench for InspectionWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Inspection",
+            "Compare a scanned or measured mesh against the CAD body.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new_action(
+            "inspection.compare",
+            "Inspect Selected",
+            Some("deviation"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "inspection.swap",
+            "Swap Bodies",
+            Some("deviation"),
+        ));
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Inspection workbench activated");
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Inspection workbench deactivated");
+    }
+
+    fn on_frame(&mut self, _dt: f32, ctx: &mut WorkbenchRuntimeContext) {
+        self.update(ctx);
+        self.update_surface(ctx);
+    }
+
+    fn on_input(
+        &mut self,
+        _event: &core_document::WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> core_document::InputResult {
+        match active_tool {
+            Some("inspection.compare") => {
+                // With two bodies selected, the first is the reference.
+                let bodies: Vec<BodyId> = ctx.selection.bodies().collect();
+                if let [reference, measured] = bodies[..] {
+                    self.reference = Some(reference);
+                    self.measured = Some(measured);
+                    self.show_map = true;
+                    return core_document::InputResult::consumed();
+                }
+                let Some(selected) = ctx.selected_body() else {
+                    ctx.log_warn("Select the measured body to inspect");
+                    return core_document::InputResult::consumed();
+                };
+                self.measured = Some(selected);
+                if self
+                    .reference
+                    .map_or(true, |reference| reference == selected)
+                {
+                    self.reference = ctx
+                        .document
+                        .bodies()
+                        .iter()
+                        .map(|body| body.id)
+                        .find(|&id| id != selected);
+                }
+                self.show_map = true;
+                core_document::InputResult::consumed()
+            }
+            Some("inspection.swap") => {
+                std::mem::swap(&mut self.reference, &mut self.measured);
+                core_document::InputResult::consumed()
+            }
+            _ => core_document::InputResult::ignored(),
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+
+    fn get_overlay_meshes(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<(TriMesh, [f32; 3])> {
+        let mut meshes = Vec::new();
+        if self.show_map && self.analysis.is_some() {
+            let offset = ctx.body_offset(self.measured);
+            meshes.extend(
+                self.map
+                    .iter()
+                    .map(|(mesh, color)| (in_frame(mesh, offset), *color)),
+            );
+        }
+        if self.surface_mode.is_curvature() {
+            meshes.extend(
+                self.surface_map.iter().map(|(body, mesh, color)| {
+                    (in_frame(mesh, ctx.body_offset(Some(*body))), *color)
+                }),
+            );
+        }
+        meshes
+    }
+
+    fn get_zebra_meshes(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<ZebraMesh> {
+        if self.surface_mode != SurfaceMode::Zebra {
+            return Vec::new();
+        }
+        let axis = match self.stripe_direction {
+            StripeDirection::Horizontal => ctx.axes.up_vec(),
+            StripeDirection::Vertical => ctx.axes.right_vec(),
+        };
+        self.surface
+            .iter()
+            .flat_map(|(_, analyses)| analyses)
+            .map(|(body, analysis)| ZebraMesh {
+                mesh: in_frame(&analysis.smooth_mesh(), ctx.body_offset(Some(*body))),
+                axis: axis.to_array(),
+                stripes: self.stripes,
+            })
+            .collect()
+    }
+
+    fn get_screen_space_overlays(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<ScreenSpaceOverlay> {
+        let Some((_, analysis)) = self.analysis.as_ref().filter(|_| self.show_extremes) else {
+            return Vec::new();
+        };
+        let mut overlays = Vec::new();
+        let offset = glam::Vec3::from(ctx.body_offset(self.measured));
+        for (point, color) in [
+            (analysis.max_at, MAX_MARKER_COLOR),
+            (analysis.min_at, MIN_MARKER_COLOR),
+        ] {
+            let Some((x, y)) = ctx.world_to_viewport((point + offset).to_array()) else {
+                continue;
+            };
+            let s = MARKER_SIZE;
+            overlays.push(ScreenSpaceOverlay::new(
+                [x - s, y - s],
+                [x + s, y + s],
+                color,
+                2.0,
+            ));
+            overlays.push(ScreenSpaceOverlay::new(
+                [x - s, y + s],
+                [x + s, y - s],
+                color,
+                2.0,
+            ));
+        }
+        overlays
+    }
+}
+
+impl InspectionWorkbench {
+    /// Whether a comparison is running in the background.
+    fn is_comparing(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Recompare the bodies in the background when either mesh changed, pick
+    /// up finished comparisons, and rebuild the color map when the analysis or
+    /// the scale changed.
+    fn update(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let mesh = |id: Option<BodyId>| ctx.body_meshes.iter().find(|m| Some(m.body) == id);
+        let (Some(reference), Some(measured)) = (mesh(self.reference), mesh(self.measured)) else {
+            self.clear(ctx);
+            return;
+        };
+        if self.reference == self.measured {
+            self.clear(ctx);
+            return;
+        }
+        // The reference is compared in the measured body's frame: where the
+        // measured body's position lies relative to the reference's.
+        let frame = [0, 1, 2].map(|i| measured.position[i] - reference.position[i]);
+        let (reference, measured) = (&reference.mesh, &measured.mesh);
+        let key = meshes_key(reference, measured, frame);
+        if self.requested != Some(key) {
+            if let Some((_, job)) = self.job.take() {
+                ctx.document.jobs().cancel(job);
+            }
+            let (mut reference, measured) = (reference.clone(), measured.clone());
+            let job = ctx.document.jobs().spawn("Comparing meshes", move || {
+                reference.to_frame(frame);
+                deviation::analyze(&reference, &measured)
+            });
+            self.job = Some((key, job));
+            self.requested = Some(key);
+        }
+        if let Some((job_key, job)) = self.job {
+            // Checked before taking, so a job finishing in between isn't seen as cancelled.
+            let running = ctx.document.jobs().is_running(job);
+            match ctx.document.jobs().take::<Option<DeviationAnalysis>>(job) {
+                Some(Ok(analysis)) => {
+                    self.analysis = analysis.map(|a| (job_key, a));
+                    self.map_scale = None;
+                    self.job = None;
+                }
+                Some(Err(err)) => {
+                    ctx.log_error(format!("Deviation analysis failed: {err}"));
+                    self.analysis = None;
+                    self.map_scale = None;
+                    self.job = None;
+                }
+                // Cancelled from the status bar: compare again once a mesh changes.
+                None if !running => {
+                    ctx.log_info("Deviation analysis cancelled");
+                    self.analysis = None;
+                    self.map_scale = None;
+                    self.job = None;
+                }
+                None => {}
+            }
+        }
+        // The color map needs the analysis of the current meshes.
+        if self.analysis.as_ref().map(|(k, _)| *k) != Some(key) {
+            return;
+        }
+        if self.map_scale != Some(self.scale) {
+            self.map = match &self.analysis {
+                Some((_, analysis)) => self.scale.colored_meshes(measured, analysis),
+                None => Vec::new(),
+            };
+            self.map_scale = Some(self.scale);
+        }
+    }
+
+    /// Analyze the inspected bodies (the selected ones, else all) when their
+    /// meshes changed, and rebuild the curvature map when the analysis, the
+    /// mode, or the scale changed.
+    fn update_surface(&mut self, ctx: &WorkbenchRuntimeContext) {
+        if self.surface_mode == SurfaceMode::Off {
+            self.surface = None;
+            self.surface_map.clear();
+            self.surface_map_scale = None;
+            return;
+        }
+        let selected: Vec<BodyId> = ctx.selection.bodies().collect();
+        let meshes: Vec<&BodyMesh> = ctx
+            .body_meshes
+            .iter()
+            .filter(|m| selected.is_empty() || selected.contains(&m.body))
+            .collect();
+        let key = surface_key(&meshes);
+        if self.surface.as_ref().map(|(k, _)| *k) != Some(key) {
+            let analyses = meshes
+                .iter()
+                .filter_map(|mesh| Some((mesh.body, SurfaceAnalysis::new(&mesh.mesh)?)))
+                .collect();
+            self.surface = Some((key, analyses));
+            self.surface_map_scale = None;
+        }
+        if !self.surface_mode.is_curvature() {
+            return;
+        }
+        let analyses = self.surface.as_ref().map_or(&[][..], |(_, a)| a);
+        let refit = self.surface_map_scale.map(|(mode, _)| mode) != Some(self.surface_mode);
+        if self.fit_curvature_scale && refit {
+            let values: Vec<f32> = analyses
+                .iter()
+                .flat_map(|(_, analysis)| analysis.values(self.surface_mode))
+                .copied()
+                .collect();
+            self.curvature_scale = CurvatureScale::fitted(&values);
+        }
+        let scale = (self.surface_mode, self.curvature_scale);
+        if self.surface_map_scale != Some(scale) {
+            self.surface_map = analyses
+                .iter()
+                .flat_map(|(body, analysis)| {
+                    analysis
+                        .colored_meshes(self.surface_mode, &self.curvature_scale)
+                        .into_iter()
+                        .map(|(mesh, color)| (*body, mesh, color))
+                })
+                .collect();
+            self.surface_map_scale = Some(scale);
+        }
+    }
+
+    /// Drop the comparison and any comparison still running.
+    fn clear(&mut self, ctx: &WorkbenchRuntimeContext) {
+        if let Some((_, job)) = self.job.take() {
+            ctx.document.jobs().cancel(job);
+        }
+        self.requested = None;
+        self.analysis = None;
+        self.map.clear();
+        self.map_scale = None;
+    }
+}
+
+/// A mesh moved by `offset`, the position of its body's frame in the
+/// context's.
+fn in_frame(mesh: &TriMesh, offset: [f32; 3]) -> TriMesh {
+    let mut mesh = mesh.clone();
+    for position in &mut mesh.positions {
+        *position = [0, 1, 2].map(|i| position[i] + offset[i]);
+    }
+    mesh
+}
+
+/// Fingerprint of the compared meshes and where they lie relative to each
+/// other.
+fn meshes_key(reference: &TriMesh, measured: &TriMesh, frame: [f64; 3]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    frame.map(f64::to_bits).hash(&mut hasher);
+    for mesh in [reference, measured] {
+        mesh.indices.hash(&mut hasher);
+        for position in &mesh.positions {
+            position.map(f32::to_bits).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Fingerprint of the bodies analyzed for surface quality.
+fn surface_key(meshes: &[&BodyMesh]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for BodyMesh { body, mesh, .. } in meshes {
+        body.hash(&mut hasher);
+        mesh.indices.hash(&mut hasher);
+        for position in &mesh.positions {
+            position.map(f32::to_bits).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
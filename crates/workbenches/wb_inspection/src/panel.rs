@@ -0,0 +1,320 @@
+//! Right-panel UI of the Inspection workbench.
+
+use core_document::{BodyId, CameraFocusRequest, WorkbenchRuntimeContext};
+use glam::Vec3;
+
+use crate::deviation::{self, Band, DeviationAnalysis};
+use crate::surface::{StripeDirection, SurfaceMode};
+use crate::InspectionWorkbench;
+
+const HISTOGRAM_BINS: usize = 40;
+const HISTOGRAM_HEIGHT: f32 = 80.0;
+
+fn color32(color: [f32; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(
+        (color[0] * 255.0) as u8,
+        (color[1] * 255.0) as u8,
+        (color[2] * 255.0) as u8,
+    )
+}
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut InspectionWorkbench,
+) {
+    surface_section(ui, ctx, workbench);
+    ui.separator();
+
+    ui.heading("Deviation");
+    let bodies = ctx.document.bodies();
+    if bodies.len() < 2 {
+        ui.label("Import the measured mesh (e.g. with the Mesh workbench) next to the CAD body.");
+        return;
+    }
+    let name = |id: Option<BodyId>| {
+        id.and_then(|id| bodies.iter().find(|body| body.id == id))
+            .map_or("Choose body", |body| body.name.as_str())
+    };
+    for (label, salt, choice) in [
+        (
+            "Reference:",
+            "inspection_reference",
+            &mut workbench.reference,
+        ),
+        ("Measured:", "inspection_measured", &mut workbench.measured),
+    ] {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            egui::ComboBox::from_id_salt(salt)
+                .selected_text(name(*choice))
+                .show_ui(ui, |ui| {
+                    for body in bodies {
+                        ui.selectable_value(choice, Some(body.id), &body.name);
+                    }
+                });
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Tolerance: ±");
+        ui.add(
+            egui::DragValue::new(&mut workbench.scale.tolerance)
+                .range(0.0..=10.0)
+                .speed(0.005)
+                .suffix(" mm"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Color range: ±");
+        let min = workbench.scale.tolerance + 0.01;
+        ui.add(
+            egui::DragValue::new(&mut workbench.scale.range)
+                .range(min..=100.0)
+                .speed(0.01)
+                .suffix(" mm"),
+        );
+    });
+    workbench.scale.range = workbench.scale.range.max(workbench.scale.tolerance + 0.01);
+    ui.checkbox(&mut workbench.show_map, "Show color map");
+    ui.checkbox(&mut workbench.show_extremes, "Mark largest deviations");
+
+    let (reference, measured) = (workbench.reference, workbench.measured);
+    if reference.is_none() || measured.is_none() {
+        return;
+    }
+    if reference == measured {
+        ui.label("Choose two different bodies.");
+        return;
+    }
+    if workbench.is_comparing() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Comparing…");
+        });
+    }
+    let Some((_, analysis)) = &workbench.analysis else {
+        if !workbench.is_comparing() {
+            ui.label("Both bodies need geometry to compare.");
+        }
+        return;
+    };
+
+    ui.separator();
+    statistics(ui, ctx, analysis, workbench.scale.tolerance);
+
+    ui.separator();
+    ui.label("Legend:");
+    let format_bound = |value: f32| {
+        if value.is_infinite() {
+            None
+        } else {
+            let text = ctx.units.format_value(value);
+            Some(if value > 0.0 {
+                format!("+{text}")
+            } else {
+                text
+            })
+        }
+    };
+    legend(
+        ui,
+        &workbench.scale.bands(),
+        format_bound,
+        ctx.units.unit.symbol(),
+    );
+
+    ui.separator();
+    ui.label("Distribution:");
+    let range = ctx.units.format_length(workbench.scale.range);
+    histogram(ui, workbench, analysis).on_hover_text(format!(
+        "Deviations within ±{range}; values outside are counted in the end bars."
+    ));
+}
+
+/// Curvature maps and zebra stripes of the selected bodies (or all bodies).
+fn surface_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    workbench: &mut InspectionWorkbench,
+) {
+    ui.heading("Surface Quality");
+    egui::ComboBox::from_id_salt("inspection_surface_mode")
+        .selected_text(workbench.surface_mode.label())
+        .show_ui(ui, |ui| {
+            for mode in SurfaceMode::ALL {
+                ui.selectable_value(&mut workbench.surface_mode, mode, mode.label());
+            }
+        });
+    match workbench.surface_mode {
+        SurfaceMode::Off => return,
+        SurfaceMode::Zebra => {
+            ui.horizontal(|ui| {
+                ui.label("Stripes:");
+                for direction in StripeDirection::ALL {
+                    ui.selectable_value(
+                        &mut workbench.stripe_direction,
+                        direction,
+                        direction.label(),
+                    );
+                }
+            });
+            ui.add(egui::Slider::new(&mut workbench.stripes, 2.0..=40.0).text("Stripe count"));
+        }
+        SurfaceMode::GaussianCurvature | SurfaceMode::MeanCurvature => {
+            if ui
+                .checkbox(&mut workbench.fit_curvature_scale, "Fit color range")
+                .changed()
+                && workbench.fit_curvature_scale
+            {
+                workbench.surface_map_scale = None;
+            }
+            // Curvature is per mm (per mm² for Gaussian); show it per unit.
+            let (power, suffix) = if workbench.surface_mode == SurfaceMode::GaussianCurvature {
+                (2, "²")
+            } else {
+                (1, "")
+            };
+            let per_unit = ctx.units.unit.to_mm(1.0).powi(power);
+            let unit = format!("1/{}{suffix}", ctx.units.unit.symbol());
+            ui.add_enabled_ui(!workbench.fit_curvature_scale, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Color range: ±");
+                    let mut range = workbench.curvature_scale.range * per_unit;
+                    let speed = range * 0.01;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut range)
+                                .range(1e-6..=1e6)
+                                .speed(speed)
+                                .suffix(format!(" {unit}")),
+                        )
+                        .changed()
+                    {
+                        workbench.curvature_scale.range = range / per_unit;
+                    }
+                });
+            });
+            ui.label("Legend:");
+            let format_bound =
+                |value: f32| (!value.is_infinite()).then(|| format!("{:.4}", value * per_unit));
+            legend(ui, &workbench.curvature_scale.bands(), format_bound, &unit);
+        }
+    }
+    let analyzed = workbench.surface.as_ref().map_or(0, |(_, a)| a.len());
+    if analyzed == 0 {
+        ui.label("No body geometry to analyze.");
+    } else if ctx.selection.bodies().next().is_none() {
+        ui.label(format!(
+            "Showing all {analyzed} bodies; select bodies to limit it."
+        ));
+    }
+}
+
+/// Color swatches with the value range of each band.
+fn legend(
+    ui: &mut egui::Ui,
+    bands: &[Band],
+    format_bound: impl Fn(f32) -> Option<String>,
+    unit: &str,
+) {
+    for band in bands {
+        let text = match (format_bound(band.low), format_bound(band.high)) {
+            (Some(low), Some(high)) => format!("{low} … {high}"),
+            (Some(low), None) => format!("> {low}"),
+            (None, Some(high)) => format!("< {high}"),
+            (None, None) => String::new(),
+        };
+        ui.horizontal(|ui| {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, color32(band.color));
+            ui.label(format!("{text} {unit}"));
+        });
+    }
+}
+
+/// Min/max/mean/RMS figures, with buttons to look at the extremes.
+fn statistics(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    analysis: &DeviationAnalysis,
+    tolerance: f32,
+) {
+    let length = |value: f32| ctx.units.format_length(value);
+    let mut focus: Option<Vec3> = None;
+    egui::Grid::new("inspection_statistics")
+        .num_columns(3)
+        .show(ui, |ui| {
+            ui.label("Points:");
+            ui.label(analysis.samples.len().to_string());
+            ui.end_row();
+            for (label, value, at) in [
+                ("Max:", analysis.max, Some(analysis.max_at)),
+                ("Min:", analysis.min, Some(analysis.min_at)),
+                ("Mean:", analysis.mean, None),
+                ("RMS:", analysis.rms, None),
+                ("Std. dev.:", analysis.std_dev, None),
+            ] {
+                ui.label(label);
+                ui.label(length(value));
+                if let Some(at) = at {
+                    if ui.small_button("Zoom").clicked() {
+                        focus = Some(at);
+                    }
+                }
+                ui.end_row();
+            }
+            ui.label("In tolerance:");
+            ui.label(format!("{:.1} %", analysis.within(tolerance) * 100.0));
+            ui.end_row();
+        });
+    if let Some(at) = focus {
+        let size = (analysis.max - analysis.min).abs().max(1.0);
+        ctx.camera_focus_request = Some(CameraFocusRequest {
+            center: at.to_array(),
+            radius: size * 10.0,
+        });
+    }
+}
+
+/// Bar chart of the deviations over the color range, colored like the map.
+fn histogram(
+    ui: &mut egui::Ui,
+    workbench: &InspectionWorkbench,
+    analysis: &DeviationAnalysis,
+) -> egui::Response {
+    let range = workbench.scale.range;
+    let counts = deviation::histogram(&analysis.samples, range, HISTOGRAM_BINS);
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let width = ui.available_width();
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(width, HISTOGRAM_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let bar_width = rect.width() / counts.len() as f32;
+    for (bin, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let center = -range + (bin as f32 + 0.5) * 2.0 * range / counts.len() as f32;
+        let height = rect.height() * count as f32 / highest;
+        let x = rect.left() + bin as f32 * bar_width;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(x, rect.bottom() - height),
+                egui::pos2(x + bar_width - 1.0, rect.bottom()),
+            ),
+            0.0,
+            color32(workbench.scale.color(center)),
+        );
+    }
+    let zero = rect.center().x;
+    painter.line_segment(
+        [
+            egui::pos2(zero, rect.top()),
+            egui::pos2(zero, rect.bottom()),
+        ],
+        egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+    );
+    response
+}
@@ -0,0 +1,372 @@
+//! Surface quality of body meshes: discrete Gaussian and mean curvature shown
+//! as a color map, and zebra stripes, for judging how smooth surfaces such as
+//! lofts and fillets are and how they join their neighbours.
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+use kernel_api::TriMesh;
+
+use crate::deviation::Band;
+
+pub const DEFAULT_STRIPES: f32 = 12.0;
+
+/// How far (relative to the body size) the analysis meshes are lifted off
+/// the body along the normals, so they are drawn over it.
+const LIFT: f32 = 5e-4;
+
+/// Colors from the most negative to the most positive curvature; the middle
+/// one is flat.
+const CURVATURE_COLORS: [[f32; 3]; 9] = [
+    [0.35, 0.2, 0.75],
+    [0.2, 0.35, 0.9],
+    [0.3, 0.6, 0.95],
+    [0.3, 0.85, 0.85],
+    [0.3, 0.8, 0.4],
+    [0.75, 0.9, 0.3],
+    [0.95, 0.85, 0.2],
+    [0.95, 0.55, 0.15],
+    [0.9, 0.2, 0.15],
+];
+
+/// Share of the curvature values inside the fitted color range; the rest
+/// (sharp edges and corners) fall into the outermost bands.
+const FIT_PERCENTILE: f32 = 0.9;
+
+/// What the surface quality section shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurfaceMode {
+    #[default]
+    Off,
+    GaussianCurvature,
+    MeanCurvature,
+    Zebra,
+}
+
+impl SurfaceMode {
+    pub const ALL: [SurfaceMode; 4] = [
+        SurfaceMode::Off,
+        SurfaceMode::GaussianCurvature,
+        SurfaceMode::MeanCurvature,
+        SurfaceMode::Zebra,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SurfaceMode::Off => "Off",
+            SurfaceMode::GaussianCurvature => "Gaussian curvature",
+            SurfaceMode::MeanCurvature => "Mean curvature",
+            SurfaceMode::Zebra => "Zebra stripes",
+        }
+    }
+
+    /// Whether the mode shows a curvature color map.
+    pub fn is_curvature(self) -> bool {
+        matches!(
+            self,
+            SurfaceMode::GaussianCurvature | SurfaceMode::MeanCurvature
+        )
+    }
+}
+
+/// Which way zebra stripes run across the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StripeDirection {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+impl StripeDirection {
+    pub const ALL: [StripeDirection; 2] = [StripeDirection::Horizontal, StripeDirection::Vertical];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StripeDirection::Horizontal => "Horizontal",
+            StripeDirection::Vertical => "Vertical",
+        }
+    }
+}
+
+/// Discrete color scale over ±`range`, flat surfaces in the middle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvatureScale {
+    pub range: f32,
+}
+
+impl Default for CurvatureScale {
+    fn default() -> Self {
+        Self { range: 1.0 }
+    }
+}
+
+impl CurvatureScale {
+    fn step(&self) -> f32 {
+        (2.0 * self.range / CURVATURE_COLORS.len() as f32).max(f32::EPSILON)
+    }
+
+    pub fn color(&self, curvature: f32) -> [f32; 3] {
+        let band = ((curvature + self.range) / self.step()).floor().max(0.0) as usize;
+        CURVATURE_COLORS[band.min(CURVATURE_COLORS.len() - 1)]
+    }
+
+    /// All bands from the most positive to the most negative.
+    pub fn bands(&self) -> Vec<Band> {
+        let last = CURVATURE_COLORS.len() - 1;
+        let edge = |band: usize| -self.range + self.step() * band as f32;
+        CURVATURE_COLORS
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(band, &color)| Band {
+                low: if band == 0 {
+                    f32::NEG_INFINITY
+                } else {
+                    edge(band)
+                },
+                high: if band == last {
+                    f32::INFINITY
+                } else {
+                    edge(band + 1)
+                },
+                color,
+            })
+            .collect()
+    }
+
+    /// Scale holding most of `values`.
+    pub fn fitted(values: &[f32]) -> Self {
+        let mut magnitudes: Vec<f32> = values.iter().map(|v| v.abs()).collect();
+        if magnitudes.is_empty() {
+            return Self::default();
+        }
+        let index = ((magnitudes.len() - 1) as f32 * FIT_PERCENTILE) as usize;
+        let (_, &mut value, _) = magnitudes.select_nth_unstable_by(index, f32::total_cmp);
+        Self {
+            range: if value > 1e-6 { value } else { 1.0 },
+        }
+    }
+}
+
+/// Curvature of one body mesh, per distinct vertex.
+#[derive(Debug, Clone)]
+pub struct SurfaceAnalysis {
+    mesh: WeldedMesh,
+    normals: Vec<Vec3>,
+    /// Angle deficit over vertex area (1/mm²); positive on domes, negative
+    /// on saddles, zero on flat and cylindrical surfaces.
+    pub gaussian: Vec<f32>,
+    /// Average of the principal curvatures (1/mm); positive where convex.
+    pub mean: Vec<f32>,
+}
+
+impl SurfaceAnalysis {
+    /// None if the mesh has no triangles.
+    pub fn new(mesh: &TriMesh) -> Option<Self> {
+        let mesh = WeldedMesh::new(mesh);
+        if mesh.triangles.is_empty() {
+            return None;
+        }
+        let normals = mesh.vertex_normals();
+        let (gaussian, mean) = mesh.curvature();
+        Some(Self {
+            mesh,
+            normals,
+            gaussian,
+            mean,
+        })
+    }
+
+    /// Curvature values shown in `mode`.
+    pub fn values(&self, mode: SurfaceMode) -> &[f32] {
+        match mode {
+            SurfaceMode::GaussianCurvature => &self.gaussian,
+            SurfaceMode::MeanCurvature => &self.mean,
+            SurfaceMode::Off | SurfaceMode::Zebra => &[],
+        }
+    }
+
+    /// The mesh with smooth vertex normals, for zebra stripes.
+    pub fn smooth_mesh(&self) -> TriMesh {
+        let lift = self.lift();
+        let mut mesh = TriMesh::default();
+        for (position, normal) in self.mesh.positions.iter().zip(&self.normals) {
+            mesh.positions.push((*position + *normal * lift).to_array());
+            mesh.normals.push(normal.to_array());
+        }
+        for triangle in &self.mesh.triangles {
+            mesh.indices.extend(triangle.map(|i| i as u32));
+        }
+        mesh
+    }
+
+    /// The mesh split into one mesh per color, each triangle colored by the
+    /// mean curvature of its corners.
+    pub fn colored_meshes(
+        &self,
+        mode: SurfaceMode,
+        scale: &CurvatureScale,
+    ) -> Vec<(TriMesh, [f32; 3])> {
+        let values = self.values(mode);
+        if values.is_empty() {
+            return Vec::new();
+        }
+        let lift = self.lift();
+        let mut meshes: Vec<(TriMesh, [f32; 3])> = Vec::new();
+        for triangle in &self.mesh.triangles {
+            let value = triangle.iter().map(|&i| values[i]).sum::<f32>() / 3.0;
+            let color = scale.color(value);
+            let index = match meshes.iter().position(|(_, c)| *c == color) {
+                Some(index) => index,
+                None => {
+                    meshes.push((TriMesh::default(), color));
+                    meshes.len() - 1
+                }
+            };
+            let mesh = &mut meshes[index].0;
+            let base = mesh.positions.len() as u32;
+            for &i in triangle {
+                let normal = self.normals[i];
+                mesh.positions
+                    .push((self.mesh.positions[i] + normal * lift).to_array());
+                mesh.normals.push(normal.to_array());
+            }
+            mesh.indices.extend([base, base + 1, base + 2]);
+        }
+        meshes
+    }
+
+    fn lift(&self) -> f32 {
+        let (min, max) = self.mesh.positions.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), &p| (min.min(p), max.max(p)),
+        );
+        (max - min).length() * LIFT
+    }
+}
+
+/// A mesh with coincident vertices merged (display meshes repeat vertices
+/// per face).
+#[derive(Debug, Clone)]
+struct WeldedMesh {
+    positions: Vec<Vec3>,
+    triangles: Vec<[usize; 3]>,
+}
+
+impl WeldedMesh {
+    fn new(mesh: &TriMesh) -> Self {
+        let mut distinct: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let vertices: Vec<usize> = mesh
+            .positions
+            .iter()
+            .map(|p| {
+                *distinct.entry(p.map(f32::to_bits)).or_insert_with(|| {
+                    positions.push(Vec3::from(*p));
+                    positions.len() - 1
+                })
+            })
+            .collect();
+        let triangles = mesh
+            .indices
+            .chunks_exact(3)
+            .filter_map(|tri| {
+                let corners = [tri[0], tri[1], tri[2]].map(|i| vertices.get(i as usize).copied());
+                let [Some(a), Some(b), Some(c)] = corners else {
+                    return None;
+                };
+                (a != b && b != c && a != c).then_some([a, b, c])
+            })
+            .collect();
+        Self {
+            positions,
+            triangles,
+        }
+    }
+
+    fn corners(&self, triangle: &[usize; 3]) -> [Vec3; 3] {
+        triangle.map(|i| self.positions[i])
+    }
+
+    /// Area-weighted vertex normals.
+    fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+        for triangle in &self.triangles {
+            let [a, b, c] = self.corners(triangle);
+            let normal = (b - a).cross(c - a);
+            for &i in triangle {
+                normals[i] += normal;
+            }
+        }
+        normals.iter().map(|n| n.normalize_or_zero()).collect()
+    }
+
+    /// Gaussian and mean curvature at every vertex, from the angle deficit
+    /// and the dihedral angles of the edges around it, each over a third of
+    /// the area of the triangles around the vertex. Vertices on open
+    /// boundaries have no Gaussian curvature.
+    fn curvature(&self) -> (Vec<f32>, Vec<f32>) {
+        let count = self.positions.len();
+        let mut area = vec![0.0f32; count];
+        let mut angle_sum = vec![0.0f32; count];
+        let mut bending = vec![0.0f32; count];
+        let mut boundary = vec![false; count];
+
+        // Triangles on each edge, with the corner opposite it.
+        let mut edges: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        for (t, triangle) in self.triangles.iter().enumerate() {
+            let corners = self.corners(triangle);
+            let third = (corners[1] - corners[0])
+                .cross(corners[2] - corners[0])
+                .length()
+                / 6.0;
+            for k in 0..3 {
+                let (i, j, o) = (triangle[k], triangle[(k + 1) % 3], triangle[(k + 2) % 3]);
+                area[i] += third;
+                let (p, q, r) = (corners[k], corners[(k + 1) % 3], corners[(k + 2) % 3]);
+                angle_sum[i] += (q - p).angle_between(r - p);
+                edges.entry((i.min(j), i.max(j))).or_default().push((t, o));
+            }
+        }
+
+        let face_normal = |t: usize| {
+            let [a, b, c] = self.corners(&self.triangles[t]);
+            (b - a).cross(c - a).normalize_or_zero()
+        };
+        for (&(i, j), faces) in &edges {
+            match faces[..] {
+                [(first, _), (second, opposite)] => {
+                    let (n1, n2) = (face_normal(first), face_normal(second));
+                    let angle = n1.angle_between(n2);
+                    // Convex where the other triangle bends away behind the first.
+                    let convex = (self.positions[opposite] - self.positions[i]).dot(n1) < 0.0;
+                    let signed = if convex { angle } else { -angle };
+                    let length = self.positions[i].distance(self.positions[j]);
+                    if signed.is_finite() {
+                        bending[i] += signed * length;
+                        bending[j] += signed * length;
+                    }
+                }
+                [_] => {
+                    boundary[i] = true;
+                    boundary[j] = true;
+                }
+                // Non-manifold edges don't bend the surface in a defined way.
+                _ => {}
+            }
+        }
+
+        let mut gaussian = vec![0.0; count];
+        let mut mean = vec![0.0; count];
+        for v in 0..count {
+            if area[v] <= f32::EPSILON {
+                continue;
+            }
+            if !boundary[v] {
+                gaussian[v] = (std::f32::consts::TAU - angle_sum[v]) / area[v];
+            }
+            mean[v] = bending[v] / (4.0 * area[v]);
+        }
+        (gaussian, mean)
+    }
+}
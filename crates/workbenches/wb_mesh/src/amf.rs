@@ -0,0 +1,164 @@
+//! Writing bodies to AMF (Additive Manufacturing File Format), keeping each
+//! body's material and color so multi-material slicers can assign
+//! extruders from them.
+//!
+//! Every body becomes an `object` with one `volume`; bodies with a material
+//! point their volume at a `material` element carrying its name and
+//! density. Coordinates follow the export options: Z-up millimetres by
+//! default, as slicers expect.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use axes::AxisSystem;
+use core_document::{Material, MaterialId};
+use kernel_api::{ExchangeOptions, TriMesh};
+use thiserror::Error;
+use units::LengthUnit;
+
+use crate::mesh::IndexedMesh;
+
+/// Tool id, also the `FileOpenRequest` id of the save dialog.
+pub const EXPORT_REQUEST: &str = "mesh.export_amf";
+
+#[derive(Debug, Error)]
+pub enum AmfError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("there are no bodies with geometry to export")]
+    Empty,
+}
+
+/// A body to export, in document coordinates.
+#[derive(Debug, Clone)]
+pub struct AmfBody {
+    pub name: String,
+    pub mesh: TriMesh,
+    pub color: [f32; 3],
+    /// 1.0 is opaque.
+    pub opacity: f32,
+    pub material: Option<Material>,
+}
+
+/// Units AMF files can be in.
+pub const UNITS: [LengthUnit; 4] = [
+    LengthUnit::Millimeter,
+    LengthUnit::Meter,
+    LengthUnit::Inch,
+    LengthUnit::Foot,
+];
+
+/// Write `bodies` to `path`, converting from `document_axes`.
+pub fn export(
+    bodies: &[AmfBody],
+    options: &ExchangeOptions,
+    document_axes: AxisSystem,
+    path: &Path,
+) -> Result<(), AmfError> {
+    if bodies.iter().all(|body| body.mesh.indices.is_empty()) {
+        return Err(AmfError::Empty);
+    }
+    std::fs::write(path, to_amf(bodies, options, document_axes))?;
+    Ok(())
+}
+
+/// The AMF document holding `bodies`. A unit AMF has no name for is
+/// written as millimetres.
+pub fn to_amf(bodies: &[AmfBody], options: &ExchangeOptions, document_axes: AxisSystem) -> String {
+    let mut options = *options;
+    let unit = match options.file_unit {
+        LengthUnit::Meter => "meter",
+        LengthUnit::Inch => "inch",
+        LengthUnit::Foot => "feet",
+        LengthUnit::Millimeter | LengthUnit::Centimeter => {
+            options.file_unit = LengthUnit::Millimeter;
+            "millimeter"
+        }
+    };
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(out, "<amf unit=\"{unit}\" version=\"1.1\">");
+    out.push_str("  <metadata type=\"producer\">printCAD</metadata>\n");
+
+    // Material ids start at 1; some readers take 0 for "no material".
+    let mut materials: HashMap<MaterialId, usize> = HashMap::new();
+    for material in bodies.iter().filter_map(|body| body.material.as_ref()) {
+        if materials.contains_key(&material.id) {
+            continue;
+        }
+        let id = materials.len() + 1;
+        materials.insert(material.id.clone(), id);
+        let _ = writeln!(out, "  <material id=\"{id}\">");
+        let _ = writeln!(
+            out,
+            "    <metadata type=\"name\">{}</metadata>",
+            escape(&material.name)
+        );
+        let _ = writeln!(
+            out,
+            "    <metadata type=\"density\">{}</metadata>",
+            material.density
+        );
+        out.push_str("  </material>\n");
+    }
+
+    let mut object = 0;
+    for body in bodies {
+        let mut mesh = body.mesh.clone();
+        options.export_mesh(&mut mesh, document_axes);
+        let mesh = IndexedMesh::from_trimesh(&mesh);
+        if mesh.triangles.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "  <object id=\"{object}\">");
+        object += 1;
+        let _ = writeln!(
+            out,
+            "    <metadata type=\"name\">{}</metadata>",
+            escape(&body.name)
+        );
+        let [r, g, b] = body.color;
+        let _ = writeln!(
+            out,
+            "    <color><r>{r}</r><g>{g}</g><b>{b}</b><a>{}</a></color>",
+            body.opacity
+        );
+        out.push_str("    <mesh>\n      <vertices>\n");
+        for p in &mesh.positions {
+            let _ = writeln!(
+                out,
+                "        <vertex><coordinates><x>{}</x><y>{}</y><z>{}</z></coordinates></vertex>",
+                p.x, p.y, p.z
+            );
+        }
+        out.push_str("      </vertices>\n");
+        match body
+            .material
+            .as_ref()
+            .and_then(|material| materials.get(&material.id))
+        {
+            Some(id) => {
+                let _ = writeln!(out, "      <volume materialid=\"{id}\">");
+            }
+            None => out.push_str("      <volume>\n"),
+        }
+        for [a, b, c] in &mesh.triangles {
+            let _ = writeln!(
+                out,
+                "        <triangle><v1>{a}</v1><v2>{b}</v2><v3>{c}</v3></triangle>"
+            );
+        }
+        out.push_str("      </volume>\n    </mesh>\n  </object>\n");
+    }
+    out.push_str("</amf>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
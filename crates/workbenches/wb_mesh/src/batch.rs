@@ -0,0 +1,245 @@
+//! Exporting every body at once, for sending a multi-part print to a slicer:
+//! one file per body, named from a template, and optionally a plate file
+//! holding all of them where they sit in the document.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use axes::AxisSystem;
+use kernel_api::{ExchangeFormat, ExchangeOptions, TriMesh};
+use thiserror::Error;
+
+use crate::amf::{self, AmfBody, AmfError};
+use crate::stl::{self, StlError};
+use crate::usd::{self, UsdBody, UsdError, UsdFormat};
+
+/// Tool id, also the `FileOpenRequest` id of the folder dialog.
+pub const EXPORT_REQUEST: &str = "mesh.export_all";
+
+/// Placeholders: `{document}`, `{body}`, and `{index}` (counting from 1).
+pub const DEFAULT_TEMPLATE: &str = "{document} - {body}";
+
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error(transparent)]
+    Stl(#[from] StlError),
+    #[error(transparent)]
+    Amf(#[from] AmfError),
+    #[error(transparent)]
+    Usd(#[from] UsdError),
+    #[error("there are no bodies with geometry to export")]
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    Stl,
+    Amf,
+    Usd(UsdFormat),
+}
+
+impl BatchFormat {
+    pub const ALL: [BatchFormat; 4] = [
+        BatchFormat::Stl,
+        BatchFormat::Amf,
+        BatchFormat::Usd(UsdFormat::Usda),
+        BatchFormat::Usd(UsdFormat::Usdz),
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BatchFormat::Stl => "STL",
+            BatchFormat::Amf => "AMF",
+            BatchFormat::Usd(format) => format.label(),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            BatchFormat::Stl => "stl",
+            BatchFormat::Amf => "amf",
+            BatchFormat::Usd(format) => format.extension(),
+        }
+    }
+
+    /// Format of files with `path`'s extension, if it is one of these.
+    pub fn from_path(path: &Path) -> Option<BatchFormat> {
+        let extension = path.extension()?.to_str()?;
+        BatchFormat::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Format whose conventions in Settings → Import/Export apply.
+    pub fn exchange_format(self) -> ExchangeFormat {
+        match self {
+            BatchFormat::Stl => ExchangeFormat::Stl,
+            BatchFormat::Amf => ExchangeFormat::Amf,
+            BatchFormat::Usd(_) => ExchangeFormat::Usd,
+        }
+    }
+}
+
+/// What to write besides the folder it goes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchExport {
+    pub format: BatchFormat,
+    /// File name of each body, without the extension.
+    pub template: String,
+    /// Also write one file with every body.
+    pub plate: bool,
+}
+
+impl Default for BatchExport {
+    fn default() -> Self {
+        Self {
+            format: BatchFormat::Stl,
+            template: DEFAULT_TEMPLATE.to_string(),
+            plate: true,
+        }
+    }
+}
+
+impl BatchExport {
+    /// Write `bodies` (in document coordinates) to `folder`, returning the
+    /// number of files written. Files already there are replaced.
+    pub fn export(
+        &self,
+        bodies: &[AmfBody],
+        document: &str,
+        options: &ExchangeOptions,
+        document_axes: AxisSystem,
+        folder: &Path,
+    ) -> Result<usize, BatchError> {
+        let bodies: Vec<&AmfBody> = bodies
+            .iter()
+            .filter(|body| !body.mesh.indices.is_empty())
+            .collect();
+        if bodies.is_empty() {
+            return Err(BatchError::Empty);
+        }
+        let mut used = HashSet::new();
+        let mut written = 0;
+        for (index, body) in bodies.iter().enumerate() {
+            let stem = file_stem(&self.template, document, &body.name, index + 1);
+            let path = folder.join(unique_name(stem, self.format.extension(), &mut used));
+            write(
+                self.format,
+                &[body],
+                &body.name,
+                options,
+                document_axes,
+                &path,
+            )?;
+            written += 1;
+        }
+        if self.plate {
+            let stem = file_stem("{document} - plate", document, "", 0);
+            let path = folder.join(unique_name(stem, self.format.extension(), &mut used));
+            write(
+                self.format,
+                &bodies,
+                document,
+                options,
+                document_axes,
+                &path,
+            )?;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+/// Write the `bodies` with geometry (in document coordinates) together to
+/// one `format` file, returning how many there were.
+pub fn export_file(
+    bodies: &[AmfBody],
+    name: &str,
+    format: BatchFormat,
+    options: &ExchangeOptions,
+    document_axes: AxisSystem,
+    path: &Path,
+) -> Result<usize, BatchError> {
+    let bodies: Vec<&AmfBody> = bodies
+        .iter()
+        .filter(|body| !body.mesh.indices.is_empty())
+        .collect();
+    if bodies.is_empty() {
+        return Err(BatchError::Empty);
+    }
+    write(format, &bodies, name, options, document_axes, path)?;
+    Ok(bodies.len())
+}
+
+fn write(
+    format: BatchFormat,
+    bodies: &[&AmfBody],
+    name: &str,
+    options: &ExchangeOptions,
+    document_axes: AxisSystem,
+    path: &Path,
+) -> Result<(), BatchError> {
+    match format {
+        BatchFormat::Stl => {
+            let mut mesh = TriMesh::default();
+            for body in bodies {
+                mesh.append(&body.mesh);
+            }
+            options.export_mesh(&mut mesh, document_axes);
+            stl::write_stl(&mesh, name, path)?;
+        }
+        BatchFormat::Amf => {
+            let bodies: Vec<AmfBody> = bodies.iter().map(|body| (*body).clone()).collect();
+            amf::export(&bodies, options, document_axes, path)?;
+        }
+        BatchFormat::Usd(format) => {
+            let bodies: Vec<UsdBody> = bodies
+                .iter()
+                .map(|body| UsdBody {
+                    name: body.name.clone(),
+                    mesh: body.mesh.clone(),
+                    color: body.color,
+                    opacity: body.opacity,
+                })
+                .collect();
+            usd::export(&bodies, options, document_axes, format, path)?;
+        }
+    }
+    Ok(())
+}
+
+/// `template` with its placeholders filled in, and characters that aren't
+/// allowed in file names replaced by `_`.
+pub fn file_stem(template: &str, document: &str, body: &str, index: usize) -> String {
+    let document = match document.trim() {
+        "" => "scene",
+        name => name,
+    };
+    let stem: String = template
+        .replace("{document}", document)
+        .replace("{body}", body.trim())
+        .replace("{index}", &index.to_string())
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match stem.trim().trim_matches('.') {
+        "" => "body".to_string(),
+        stem => stem.to_string(),
+    }
+}
+
+/// `stem.extension`, numbered when an earlier file took that name (ignoring
+/// case, as some file systems do).
+fn unique_name(stem: String, extension: &str, used: &mut HashSet<String>) -> String {
+    let mut name = format!("{stem}.{extension}");
+    let mut number = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{stem} ({number}).{extension}");
+        number += 1;
+    }
+    name
+}
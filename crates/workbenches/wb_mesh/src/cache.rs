@@ -0,0 +1,569 @@
+//! Evaluation of mesh feature chains, cached between frames.
+//!
+//! Results are kept per feature until the feature is marked dirty (which also
+//! marks everything depending on it), so expensive operations such as
+//! remeshing only run again after one of their inputs changed.
+//!
+//! In the application, imported meshes are decoded on a worker thread the
+//! first time they are needed (see [`MeshCache::in_background`]), so opening a
+//! document with large imports doesn't stall the first frames; their bodies
+//! and everything built on them appear once loading finishes.
+//!
+//! With a memory budget set ([`MeshCache::set_budget`]), cached results above
+//! it are dropped, least recently viewed bodies first, and computed again once
+//! they are needed: earlier history steps when something after them changes,
+//! a body's own mesh when it comes back into view.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use core_document::{
+    BodyId, BodyMesh, Document, FeatureId, FeatureNode, JobId, JobQueue, WorkbenchFeature,
+};
+use glam::{Mat4, Vec3, Vec4};
+use kernel_api::{Diagnostic, TriMesh};
+
+use crate::feature::{MeshFeature, MeshOperation};
+use crate::mesh::IndexedMesh;
+use crate::WORKBENCH_ID;
+
+struct Entry {
+    mesh: Arc<IndexedMesh>,
+    /// Display mesh, built the first time the feature is a body's result.
+    display: Option<Arc<TriMesh>>,
+    /// Bounds of the display mesh, computed along with it.
+    bounds: Option<Bounds>,
+}
+
+impl Entry {
+    fn new(mesh: Arc<IndexedMesh>, display: Option<Arc<TriMesh>>) -> Self {
+        Self {
+            mesh,
+            display,
+            bounds: None,
+        }
+    }
+
+    /// Approximate memory held by the cached meshes, in bytes.
+    fn bytes(&self) -> usize {
+        let mesh = self.mesh.positions.len() * size_of::<Vec3>()
+            + self.mesh.triangles.len() * size_of::<[u32; 3]>();
+        let display = self.display.as_deref().map_or(0, trimesh_bytes);
+        mesh + display
+    }
+}
+
+/// Axis-aligned bounds of a display mesh: minimum and maximum corner.
+type Bounds = ([f32; 3], [f32; 3]);
+
+/// What the cache remembers about a body between frames.
+#[derive(Default)]
+struct BodyState {
+    /// Frame the body was last in view.
+    last_viewed: u64,
+    /// Bounds of its mesh, kept after eviction to tell when it's back in view.
+    bounds: Option<Bounds>,
+    /// Its mesh was evicted; it is computed again once the body is in view.
+    evicted: bool,
+}
+
+/// Body mesh saved with the document, checked against the body's history.
+struct Restored {
+    /// Last feature of the history when the mesh was checked.
+    tip: FeatureId,
+    /// The saved mesh and its bounds, if it matched the history.
+    mesh: Option<(Arc<TriMesh>, Bounds)>,
+}
+
+/// Result of a background load: the mesh and its display mesh.
+type Loaded = Result<(IndexedMesh, TriMesh), String>;
+
+/// Evaluated results of `wb.mesh` features.
+#[derive(Default)]
+pub struct MeshCache {
+    entries: HashMap<FeatureId, Entry>,
+    /// Decode imported meshes on worker threads instead of blocking.
+    background: bool,
+    /// Imports being loaded, with the queue of the document that started them.
+    loading: HashMap<FeatureId, (JobQueue, JobId)>,
+    /// Features waiting for a load this frame (themselves or an input).
+    waiting: HashSet<FeatureId>,
+    /// Imports whose load was cancelled; loaded again once marked dirty.
+    cancelled: HashSet<FeatureId>,
+    /// Memory budget for cached meshes in bytes (0 = unlimited).
+    budget: usize,
+    /// Camera of the current frame; None counts every body as in view.
+    view: Option<([f64; 3], [[f32; 4]; 4])>,
+    /// Number of `body_meshes` calls, used to order bodies by last view.
+    frame: u64,
+    bodies: HashMap<BodyId, BodyState>,
+    /// Meshes saved with the document, shown until a body's history is
+    /// computed.
+    restored: HashMap<BodyId, Restored>,
+}
+
+/// Mesh features of `body` in the order they were added.
+pub fn body_features(document: &Document, body: BodyId) -> Vec<&FeatureNode> {
+    let mut features: Vec<&FeatureNode> = document
+        .feature_tree()
+        .all_nodes()
+        .map(|(_, node)| node)
+        .filter(|node| node.workbench_id.as_str() == WORKBENCH_ID && node.body == Some(body))
+        .collect();
+    features.sort_by_key(|node| node.created_at);
+    features
+}
+
+/// Feature a mesh feature builds on, read without decoding the (possibly
+/// large) imported mesh.
+fn input_of(node: &FeatureNode) -> Option<FeatureId> {
+    node.data
+        .get("input")
+        .and_then(|input| serde_json::from_value(input.clone()).ok())
+        .flatten()
+}
+
+/// The last feature of a body's mesh history (the one no other feature builds on).
+pub fn tip_feature(document: &Document, body: BodyId) -> Option<FeatureId> {
+    let features = body_features(document, body);
+    let inputs: HashSet<FeatureId> = features.iter().filter_map(|node| input_of(node)).collect();
+    features
+        .iter()
+        .rev()
+        .map(|node| node.id)
+        .find(|id| !inputs.contains(id))
+}
+
+impl MeshCache {
+    /// Cache that loads imported meshes on the document's job queue. Until a
+    /// load finishes, the bodies depending on it have no mesh.
+    pub fn in_background() -> Self {
+        Self {
+            background: true,
+            ..Self::default()
+        }
+    }
+
+    /// Limit the memory held by cached meshes to `bytes` (0 = unlimited).
+    pub fn set_budget(&mut self, bytes: usize) {
+        self.budget = bytes;
+    }
+
+    /// Camera the next meshes are drawn with, as the world position the
+    /// view is relative to and the column-major view-projection from there;
+    /// used to tell which bodies are in view. Evicted bodies out of view are
+    /// left out of [`MeshCache::body_meshes`]; None brings them all back.
+    pub fn set_view(&mut self, view: Option<([f64; 3], [[f32; 4]; 4])>) {
+        self.view = view;
+    }
+
+    /// Approximate memory held by the cached meshes of each body, in bytes,
+    /// largest first.
+    pub fn memory(&self, document: &Document) -> Vec<(BodyId, usize)> {
+        let mut memory: HashMap<BodyId, usize> = HashMap::new();
+        for (id, entry) in &self.entries {
+            if let Some(body) = document.get_feature_meta(*id).and_then(|node| node.body) {
+                *memory.entry(body).or_default() += entry.bytes();
+            }
+        }
+        let mut memory: Vec<(BodyId, usize)> = memory.into_iter().collect();
+        memory.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        memory
+    }
+
+    /// Bounds of a feature's cached result, None until it has been computed.
+    pub fn feature_bounds(&self, id: FeatureId) -> Option<(Vec3, Vec3)> {
+        self.entries.get(&id)?.mesh.bounds()
+    }
+
+    /// Geometry of every body with mesh features, recomputing the features
+    /// that changed (their dirty flag is cleared and their status updated
+    /// afterwards). Bodies whose mesh was evicted are only included once
+    /// they're in view again or changed.
+    pub fn body_meshes(&mut self, document: &mut Document) -> Vec<BodyMesh> {
+        self.frame += 1;
+        self.waiting.clear();
+        let mut visited = HashSet::new();
+        let mut recomputed = Vec::new();
+        let mut diagnostics = Vec::new();
+        let bodies: Vec<BodyId> = document.bodies().iter().map(|body| body.id).collect();
+        let mut tips = HashMap::new();
+        let mut meshes = Vec::new();
+        for body in bodies {
+            let Some(tip) = tip_feature(document, body) else {
+                continue;
+            };
+            tips.insert(tip, body);
+            let position = document.body_position(body);
+            let state = self.bodies.entry(body).or_default();
+            let viewed = match (state.bounds, self.view) {
+                (Some(bounds), Some((origin, view_proj))) => {
+                    let offset = [0, 1, 2].map(|i| (position[i] - origin[i]) as f32);
+                    in_view(bounds, offset, view_proj)
+                }
+                _ => true,
+            };
+            if viewed {
+                state.last_viewed = self.frame;
+            }
+            let dirty = document
+                .get_feature_meta(tip)
+                .is_some_and(|node| node.dirty);
+            if state.evicted && !viewed && !dirty && !self.entries.contains_key(&tip) {
+                continue;
+            }
+            if !self.entries.contains_key(&tip) {
+                if let Some((mesh, bounds)) = self.restored_mesh(document, body, tip, dirty) {
+                    self.bodies.entry(body).or_default().bounds = Some(bounds);
+                    meshes.push(BodyMesh {
+                        body,
+                        mesh: mesh.as_ref().clone(),
+                        position,
+                    });
+                    continue;
+                }
+            }
+            if self
+                .evaluate(
+                    document,
+                    tip,
+                    &mut visited,
+                    &mut recomputed,
+                    &mut diagnostics,
+                )
+                .is_none()
+            {
+                continue;
+            }
+            if let Some(entry) = self.entries.get_mut(&tip) {
+                let display = entry
+                    .display
+                    .get_or_insert_with(|| Arc::new(entry.mesh.to_trimesh()));
+                let bounds = *entry.bounds.get_or_insert_with(|| bounds_of(display));
+                let state = self.bodies.entry(body).or_default();
+                state.bounds = Some(bounds);
+                state.evicted = false;
+                self.restored.remove(&body);
+                meshes.push(BodyMesh {
+                    body,
+                    mesh: display.as_ref().clone(),
+                    position,
+                });
+            }
+        }
+
+        // Forget features that were deleted or belong to another document.
+        self.entries
+            .retain(|id, _| document.get_feature_meta(*id).is_some());
+        self.loading.retain(|id, (jobs, job)| {
+            let keep = document.get_feature_meta(*id).is_some();
+            if !keep {
+                jobs.cancel(*job);
+            }
+            keep
+        });
+        self.cancelled
+            .retain(|id| document.get_feature_meta(*id).is_some());
+        self.bodies
+            .retain(|id, _| document.bodies().iter().any(|body| body.id == *id));
+        self.restored
+            .retain(|id, _| document.bodies().iter().any(|body| body.id == *id));
+        for id in &recomputed {
+            if let Some(node) = document.feature_tree_mut().get_node_mut(*id) {
+                node.dirty = false;
+            }
+        }
+        document.apply_diagnostics(&recomputed, &diagnostics);
+        self.evict(document, &tips);
+        meshes
+    }
+
+    /// Mesh of `body` saved with the document, while its history (ending at
+    /// `tip`) is unchanged since, so it needn't be computed yet. The saved
+    /// mesh is checked once per tip.
+    fn restored_mesh(
+        &mut self,
+        document: &Document,
+        body: BodyId,
+        tip: FeatureId,
+        dirty: bool,
+    ) -> Option<(Arc<TriMesh>, Bounds)> {
+        if dirty {
+            self.restored.remove(&body);
+            return None;
+        }
+        if self
+            .restored
+            .get(&body)
+            .map_or(true, |restored| restored.tip != tip)
+        {
+            let mesh = document
+                .cached_mesh(body.0, document.content_hash(tip))
+                .map(|mesh| (mesh.clone(), bounds_of(mesh)));
+            self.restored.insert(body, Restored { tip, mesh });
+        }
+        self.restored.get(&body)?.mesh.clone()
+    }
+
+    /// Store the mesh of every body in `document`, to be saved with it and
+    /// shown right away when the document is opened again.
+    pub fn persist(&self, document: &mut Document) {
+        let bodies: Vec<BodyId> = document.bodies().iter().map(|body| body.id).collect();
+        for body in bodies {
+            let Some(node) =
+                tip_feature(document, body).and_then(|tip| document.get_feature_meta(tip))
+            else {
+                continue;
+            };
+            if node.dirty {
+                continue;
+            }
+            let tip = node.id;
+            let computed = self
+                .entries
+                .get(&tip)
+                .and_then(|entry| entry.display.clone());
+            let restored = self
+                .restored
+                .get(&body)
+                .filter(|restored| restored.tip == tip)
+                .and_then(|restored| restored.mesh.as_ref())
+                .map(|(mesh, _)| mesh.clone());
+            if let Some(mesh) = computed.or(restored) {
+                let hash = document.content_hash(tip);
+                document.set_cached_mesh(body.0, hash, mesh);
+            }
+        }
+    }
+
+    /// Drop cached results until they fit the budget, least recently viewed
+    /// bodies first: earlier history steps before the bodies' own meshes,
+    /// which are only dropped for bodies out of view.
+    fn evict(&mut self, document: &Document, tips: &HashMap<FeatureId, BodyId>) {
+        if self.budget == 0 {
+            return;
+        }
+        let mut total: usize = self.entries.values().map(Entry::bytes).sum();
+        if total <= self.budget {
+            return;
+        }
+        let last_viewed = |body: Option<BodyId>| {
+            body.and_then(|body| self.bodies.get(&body))
+                .map_or(0, |state| state.last_viewed)
+        };
+        let mut candidates: Vec<(bool, u64, FeatureId)> = self
+            .entries
+            .keys()
+            .filter_map(|id| match tips.get(id) {
+                Some(body) => {
+                    let viewed = last_viewed(Some(*body));
+                    (viewed < self.frame).then_some((true, viewed, *id))
+                }
+                None => {
+                    let body = document.get_feature_meta(*id).and_then(|node| node.body);
+                    Some((false, last_viewed(body), *id))
+                }
+            })
+            .collect();
+        candidates.sort_by_key(|(is_tip, viewed, _)| (*is_tip, *viewed));
+        for (is_tip, _, id) in candidates {
+            if total <= self.budget {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&id) {
+                total -= entry.bytes();
+            }
+            if let Some(state) = tips.get(&id).and_then(|body| self.bodies.get_mut(body)) {
+                state.evicted = is_tip;
+            }
+        }
+    }
+
+    /// Result of a feature, computed from its inputs when missing or dirty.
+    pub fn evaluate(
+        &mut self,
+        document: &Document,
+        id: FeatureId,
+        visited: &mut HashSet<FeatureId>,
+        recomputed: &mut Vec<FeatureId>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<Arc<IndexedMesh>> {
+        let node = document.get_feature_meta(id)?;
+        if !visited.insert(id) {
+            // Already evaluated this frame, or a merge looping back into itself.
+            return self.entries.get(&id).map(|entry| entry.mesh.clone());
+        }
+        if !node.dirty {
+            if let Some(entry) = self.entries.get(&id) {
+                return Some(entry.mesh.clone());
+            }
+        }
+
+        let suppressed = node.suppressed;
+        let key = id.0.to_string();
+        if self.background && !suppressed && is_import(node) {
+            return self.load(document, node, recomputed, diagnostics);
+        }
+        // Failed features are recorded as recomputed so their status shows the
+        // error; having no cache entry, they are tried again next time.
+        let feature = match MeshFeature::from_json(&node.data) {
+            Ok(feature) => feature,
+            Err(err) => {
+                recomputed.push(id);
+                diagnostics.push(Diagnostic::error(key, format!("Unreadable feature: {err}")));
+                return None;
+            }
+        };
+        let input = match feature.input {
+            Some(input) => match self.evaluate(document, input, visited, recomputed, diagnostics) {
+                Some(mesh) => Some(mesh),
+                None if self.waiting.contains(&input) => {
+                    self.waiting.insert(id);
+                    return None;
+                }
+                None => {
+                    recomputed.push(id);
+                    diagnostics.push(Diagnostic::error(key, "The previous step failed"));
+                    return None;
+                }
+            },
+            None => None,
+        };
+        let mesh = if suppressed {
+            input.unwrap_or_default()
+        } else {
+            let source = match feature.operation {
+                MeshOperation::Merge { source } => {
+                    let mesh = self.evaluate(document, source, visited, recomputed, diagnostics);
+                    if mesh.is_none() && self.waiting.contains(&source) {
+                        self.waiting.insert(id);
+                        return None;
+                    }
+                    if mesh.is_none() {
+                        diagnostics.push(Diagnostic::warning(
+                            key.clone(),
+                            "The merged body is missing or failed; nothing was added",
+                        ));
+                    }
+                    mesh
+                }
+                _ => None,
+            };
+            let mesh = feature.operation.apply(input.as_deref(), source.as_deref());
+            if mesh.triangles.is_empty() {
+                diagnostics.push(Diagnostic::warning(key, "The result is empty"));
+            }
+            Arc::new(mesh)
+        };
+        self.entries.insert(id, Entry::new(mesh.clone(), None));
+        recomputed.push(id);
+        Some(mesh)
+    }
+
+    /// Result of an import feature once its background load finished; starts
+    /// the load the first time and returns None (marking the feature as
+    /// waiting) until then.
+    fn load(
+        &mut self,
+        document: &Document,
+        node: &FeatureNode,
+        recomputed: &mut Vec<FeatureId>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<Arc<IndexedMesh>> {
+        let id = node.id;
+        if let Some((jobs, job)) = self.loading.get(&id) {
+            if jobs.is_running(*job) {
+                self.waiting.insert(id);
+                return None;
+            }
+            let result = jobs.take::<Loaded>(*job);
+            self.loading.remove(&id);
+            recomputed.push(id);
+            let key = id.0.to_string();
+            let Some(result) = result else {
+                self.cancelled.insert(id);
+                diagnostics.push(Diagnostic::error(key, "Loading was cancelled"));
+                return None;
+            };
+            return match result.map_err(|err| err.to_string()).and_then(|r| r) {
+                Ok((mesh, display)) => {
+                    if mesh.triangles.is_empty() {
+                        diagnostics.push(Diagnostic::warning(key, "The result is empty"));
+                    }
+                    let mesh = Arc::new(mesh);
+                    self.entries
+                        .insert(id, Entry::new(mesh.clone(), Some(Arc::new(display))));
+                    Some(mesh)
+                }
+                Err(err) => {
+                    diagnostics.push(Diagnostic::error(key, format!("Unreadable feature: {err}")));
+                    None
+                }
+            };
+        }
+        if node.dirty {
+            self.cancelled.remove(&id);
+        } else if self.cancelled.contains(&id) {
+            return None;
+        }
+
+        let data = node.data.clone();
+        let jobs = document.jobs().clone();
+        let job = jobs.spawn(format!("Loading {}", node.name), move || -> Loaded {
+            let feature = MeshFeature::from_json(&data).map_err(|err| err.to_string())?;
+            let mesh = feature.operation.apply(None, None);
+            let display = mesh.to_trimesh();
+            Ok((mesh, display))
+        });
+        self.loading.insert(id, (jobs, job));
+        self.waiting.insert(id);
+        None
+    }
+}
+
+/// Whether a mesh feature is an import, read without decoding its mesh.
+fn is_import(node: &FeatureNode) -> bool {
+    node.data
+        .get("operation")
+        .and_then(|operation| operation.get("type"))
+        .and_then(|kind| kind.as_str())
+        == Some("import")
+}
+
+/// Approximate memory held by a display mesh, in bytes.
+fn trimesh_bytes(mesh: &TriMesh) -> usize {
+    (mesh.positions.len() + mesh.normals.len()) * size_of::<[f32; 3]>()
+        + mesh.indices.len() * size_of::<u32>()
+}
+
+fn bounds_of(mesh: &TriMesh) -> Bounds {
+    mesh.bounds().unwrap_or(([0.0; 3], [0.0; 3]))
+}
+
+/// Whether any part of `bounds`, moved by `offset`, may be visible through
+/// `view_proj`: false only when all corners lie outside the same clip plane
+/// (depth in 0..1).
+fn in_view((min, max): Bounds, offset: [f32; 3], view_proj: [[f32; 4]; 4]) -> bool {
+    let view_proj = Mat4::from_cols_array_2d(&view_proj);
+    let corners: Vec<Vec4> = (0..8)
+        .map(|i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            );
+            view_proj * (corner + Vec3::from(offset)).extend(1.0)
+        })
+        .collect();
+    let planes: [fn(Vec4) -> f32; 6] = [
+        |c| c.w + c.x,
+        |c| c.w - c.x,
+        |c| c.w + c.y,
+        |c| c.w - c.y,
+        |c| c.z,
+        |c| c.w - c.z,
+    ];
+    planes
+        .iter()
+        .all(|plane| corners.iter().any(|&corner| plane(corner) >= 0.0))
+}
@@ -0,0 +1,308 @@
+//! Parametric calibration prints for tuning a printer: a tolerance test
+//! (pegs and holes with increasing clearance), an overhang test (fins at
+//! increasing angles), and a temperature tower (one section per nozzle
+//! temperature).
+//!
+//! Parts are built from closed pieces that touch but are not united, which
+//! slicers merge when they slice. Everything stands on the XY plane.
+
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::hardware::{self, Ring};
+use crate::mesh::{self, IndexedMesh};
+
+/// Material around each hole of the tolerance test (mm).
+const TOLERANCE_WALL: f32 = 3.0;
+/// Space between the plate and the pegs of the tolerance test (mm).
+const PEG_GAP: f32 = 5.0;
+/// Depth of each overhang fin along Y, and the gap between fins (mm).
+const FIN_DEPTH: f32 = 8.0;
+const FIN_GAP: f32 = 1.0;
+/// Thickness of the wall the overhang fins stick out from, and of the base
+/// plates (mm).
+const WALL: f32 = 3.0;
+const FLOOR: f32 = 1.0;
+/// Width, depth, and pillar width of a temperature tower section (mm).
+const TOWER_WIDTH: f32 = 30.0;
+const TOWER_DEPTH: f32 = 10.0;
+const TOWER_PILLAR: f32 = 6.0;
+
+/// A generated calibration print.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CalibrationPart {
+    /// A plate with a row of round holes, each `step` wider than the last,
+    /// and one peg per hole.
+    Tolerance {
+        /// Peg diameter (mm).
+        peg_diameter: f32,
+        /// Clearance of the tightest hole (difference in diameter, mm).
+        min_clearance: f32,
+        step: f32,
+        count: u32,
+        /// Plate thickness (mm); the pegs are twice as tall.
+        thickness: f32,
+    },
+    /// Fins sticking out of a wall, their undersides from `min_angle` to
+    /// `max_angle` away from vertical.
+    Overhang {
+        /// Degrees.
+        min_angle: f32,
+        max_angle: f32,
+        step: f32,
+        /// Fin height (mm).
+        height: f32,
+    },
+    /// A stack of bridge-and-overhang sections, printed at `start_temp` at
+    /// the bottom and changed by `step` per section until `end_temp`.
+    TemperatureTower {
+        /// °C.
+        start_temp: f32,
+        end_temp: f32,
+        step: f32,
+        /// Height of each section (mm).
+        section_height: f32,
+    },
+}
+
+impl CalibrationPart {
+    pub const KINDS: [&'static str; 3] = ["Tolerance Test", "Overhang Test", "Temperature Tower"];
+
+    /// Default part of the kind at `index` in [`CalibrationPart::KINDS`].
+    pub fn of_kind(index: usize) -> Self {
+        match index {
+            0 => CalibrationPart::Tolerance {
+                peg_diameter: 10.0,
+                min_clearance: 0.1,
+                step: 0.1,
+                count: 5,
+                thickness: 5.0,
+            },
+            1 => CalibrationPart::Overhang {
+                min_angle: 20.0,
+                max_angle: 70.0,
+                step: 10.0,
+                height: 10.0,
+            },
+            _ => CalibrationPart::TemperatureTower {
+                start_temp: 230.0,
+                end_temp: 190.0,
+                step: 5.0,
+                section_height: 10.0,
+            },
+        }
+    }
+
+    /// Index of the part's kind in [`CalibrationPart::KINDS`].
+    pub fn kind(&self) -> usize {
+        match self {
+            CalibrationPart::Tolerance { .. } => 0,
+            CalibrationPart::Overhang { .. } => 1,
+            CalibrationPart::TemperatureTower { .. } => 2,
+        }
+    }
+
+    /// Name for the body and feature, e.g. `Tolerance Test 0.10–0.50 mm`.
+    pub fn name(&self) -> String {
+        match *self {
+            CalibrationPart::Tolerance { .. } => {
+                let clearances = self.clearances();
+                let first = clearances.first().copied().unwrap_or_default();
+                let last = clearances.last().copied().unwrap_or_default();
+                format!("Tolerance Test {first:.2}–{last:.2} mm")
+            }
+            CalibrationPart::Overhang { .. } => {
+                let angles = self.overhang_angles();
+                let first = angles.first().copied().unwrap_or_default();
+                let last = angles.last().copied().unwrap_or_default();
+                format!("Overhang Test {first:.0}–{last:.0}°")
+            }
+            CalibrationPart::TemperatureTower { .. } => {
+                let sections = self.tower_sections();
+                let first = sections.first().map_or(0.0, |(temp, _)| *temp);
+                let last = sections.last().map_or(0.0, |(temp, _)| *temp);
+                format!("Temperature Tower {first:.0}–{last:.0} °C")
+            }
+        }
+    }
+
+    /// Clearance of each hole of a tolerance test, in print order along X
+    /// (at most the wall around the holes).
+    pub fn clearances(&self) -> Vec<f32> {
+        match *self {
+            CalibrationPart::Tolerance {
+                min_clearance,
+                step,
+                count,
+                ..
+            } => (0..count.clamp(1, 20))
+                .map(|i| (min_clearance.max(0.0) + step.max(0.0) * i as f32).min(TOLERANCE_WALL))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Fin angles of an overhang test, in order along Y.
+    pub fn overhang_angles(&self) -> Vec<f32> {
+        match *self {
+            CalibrationPart::Overhang {
+                min_angle,
+                max_angle,
+                step,
+                ..
+            } => {
+                let (min, max) = (min_angle.clamp(0.0, 80.0), max_angle.clamp(0.0, 80.0));
+                let step = step.max(1.0);
+                let count = ((max - min) / step).floor().max(0.0) as usize + 1;
+                (0..count.min(20)).map(|i| min + step * i as f32).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Temperature and starting height of each temperature tower section,
+    /// bottom first. Set the slicer to change temperature at these heights.
+    pub fn tower_sections(&self) -> Vec<(f32, f32)> {
+        match *self {
+            CalibrationPart::TemperatureTower {
+                start_temp,
+                end_temp,
+                step,
+                section_height,
+            } => {
+                let step = step.abs().max(1.0);
+                let direction = if end_temp < start_temp { -1.0 } else { 1.0 };
+                let count = ((end_temp - start_temp).abs() / step).floor() as usize + 1;
+                (0..count.min(20))
+                    .map(|i| {
+                        let temp = start_temp + direction * step * i as f32;
+                        (temp, section_height.max(2.0) * i as f32)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Closed pieces of the part, combined into one mesh.
+    pub fn generate(&self) -> IndexedMesh {
+        let mut result = IndexedMesh::default();
+        match *self {
+            CalibrationPart::Tolerance {
+                peg_diameter,
+                thickness,
+                ..
+            } => {
+                let peg_radius = peg_diameter.max(1.0) / 2.0;
+                let thickness = thickness.max(1.0);
+                let tile = 2.0 * (peg_radius + TOLERANCE_WALL);
+                for (i, clearance) in self.clearances().into_iter().enumerate() {
+                    let x = tile * (i as f32 + 0.5);
+                    let hole = Ring::Round(peg_radius + clearance / 2.0);
+                    let square = Ring::Square(tile / 2.0);
+                    let plate = hardware::revolve(&[
+                        (hole, 0.0),
+                        (square, 0.0),
+                        (square, thickness),
+                        (hole, thickness),
+                        (hole, 0.0),
+                    ]);
+                    add_at(&mut result, &plate, Vec3::new(x, tile / 2.0, 0.0));
+                    let peg = hardware::revolve(&[
+                        (Ring::Axis, 0.0),
+                        (Ring::Round(peg_radius), 0.0),
+                        (Ring::Round(peg_radius), 2.0 * thickness),
+                        (Ring::Axis, 2.0 * thickness),
+                    ]);
+                    add_at(
+                        &mut result,
+                        &peg,
+                        Vec3::new(x, tile + PEG_GAP + peg_radius, 0.0),
+                    );
+                }
+            }
+            CalibrationPart::Overhang { height, .. } => {
+                let height = height.max(2.0);
+                let angles = self.overhang_angles();
+                let length = angles.len() as f32 * (FIN_DEPTH + FIN_GAP) - FIN_GAP;
+                let reaches: Vec<f32> = angles
+                    .iter()
+                    .map(|angle| height * angle.to_radians().tan())
+                    .collect();
+                let run = reaches.iter().copied().fold(0.0, f32::max);
+                mesh::merge(
+                    &mut result,
+                    &mesh::block(Vec3::new(-WALL, 0.0, 0.0), Vec3::new(run, length, FLOOR)),
+                );
+                mesh::merge(
+                    &mut result,
+                    &mesh::block(
+                        Vec3::new(-WALL, 0.0, FLOOR),
+                        Vec3::new(0.0, length, FLOOR + height),
+                    ),
+                );
+                for (i, reach) in reaches.into_iter().enumerate() {
+                    // The underside rises from the foot of the wall at the
+                    // fin's angle from vertical.
+                    let y = i as f32 * (FIN_DEPTH + FIN_GAP);
+                    let profile = [
+                        Vec2::new(0.0, FLOOR),
+                        Vec2::new(reach, FLOOR + height),
+                        Vec2::new(0.0, FLOOR + height),
+                    ];
+                    mesh::merge(&mut result, &mesh::prism(&profile, y, y + FIN_DEPTH));
+                }
+            }
+            CalibrationPart::TemperatureTower { section_height, .. } => {
+                let height = section_height.max(2.0);
+                let sections = self.tower_sections();
+                for &(_, z) in &sections {
+                    // Floor (which bridges the gap of the section below), two
+                    // pillars, and a 45° overhang on the outside of the left one.
+                    mesh::merge(
+                        &mut result,
+                        &mesh::block(
+                            Vec3::new(0.0, 0.0, z),
+                            Vec3::new(TOWER_WIDTH, TOWER_DEPTH, z + FLOOR),
+                        ),
+                    );
+                    for x in [0.0, TOWER_WIDTH - TOWER_PILLAR] {
+                        mesh::merge(
+                            &mut result,
+                            &mesh::block(
+                                Vec3::new(x, 0.0, z + FLOOR),
+                                Vec3::new(x + TOWER_PILLAR, TOWER_DEPTH, z + height),
+                            ),
+                        );
+                    }
+                    let overhang = height - FLOOR;
+                    let profile = [
+                        Vec2::new(0.0, z + FLOOR),
+                        Vec2::new(0.0, z + height),
+                        Vec2::new(-overhang, z + height),
+                    ];
+                    mesh::merge(&mut result, &mesh::prism(&profile, 0.0, TOWER_DEPTH));
+                }
+                let top = height * sections.len() as f32;
+                mesh::merge(
+                    &mut result,
+                    &mesh::block(
+                        Vec3::new(0.0, 0.0, top),
+                        Vec3::new(TOWER_WIDTH, TOWER_DEPTH, top + FLOOR),
+                    ),
+                );
+            }
+        }
+        result
+    }
+}
+
+/// Add `piece` to `mesh`, moved by `offset`.
+fn add_at(mesh: &mut IndexedMesh, piece: &IndexedMesh, offset: Vec3) {
+    let mut moved = piece.clone();
+    for position in &mut moved.positions {
+        *position += offset;
+    }
+    mesh::merge(mesh, &moved);
+}
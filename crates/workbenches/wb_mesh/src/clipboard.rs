@@ -0,0 +1,87 @@
+//! Meshes on the OS clipboard as text, for exchange with other CAD tools:
+//! ASCII STL, or STEP with faceted geometry. Pasted STEP B-rep surfaces are
+//! skipped until the kernel can tessellate them.
+
+use axes::AxisSystem;
+use kernel_api::step::{self, StepError};
+use kernel_api::{ExchangeFormat, ExchangeOptions, TriMesh};
+use thiserror::Error;
+use units::LengthUnit;
+
+use crate::stl::{self, StlError};
+
+#[derive(Debug, Error)]
+pub enum PasteError {
+    #[error(transparent)]
+    Stl(#[from] StlError),
+    #[error(transparent)]
+    Step(#[from] StepError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardFormat {
+    Stl,
+    Step,
+}
+
+impl ClipboardFormat {
+    pub const ALL: [ClipboardFormat; 2] = [ClipboardFormat::Stl, ClipboardFormat::Step];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClipboardFormat::Stl => "STL",
+            ClipboardFormat::Step => "STEP",
+        }
+    }
+
+    /// Format whose conventions in Settings → Import/Export apply.
+    pub fn exchange_format(self) -> ExchangeFormat {
+        match self {
+            ClipboardFormat::Stl => ExchangeFormat::Stl,
+            ClipboardFormat::Step => ExchangeFormat::Step,
+        }
+    }
+}
+
+/// The mesh in pasted text, in the text's coordinates, or None when the
+/// text is neither STL nor STEP data.
+pub fn read_pasted(text: &str) -> Option<(ClipboardFormat, Result<TriMesh, PasteError>)> {
+    let start = text.trim_start();
+    if start.starts_with("ISO-10303-21") {
+        let mesh = step::read_facets(text).map_err(PasteError::from);
+        Some((ClipboardFormat::Step, mesh))
+    } else if start.starts_with("solid") && text.contains("facet") {
+        let mesh = stl::parse_stl_text(text).map_err(PasteError::from);
+        Some((ClipboardFormat::Stl, mesh))
+    } else {
+        None
+    }
+}
+
+/// Text holding `bodies` (named meshes in document coordinates) mapped to
+/// the format's coordinates with `options`. STEP text is always in
+/// millimetres, the unit it declares.
+pub fn copy_text(
+    bodies: &[(String, TriMesh)],
+    format: ClipboardFormat,
+    options: &ExchangeOptions,
+    document_axes: AxisSystem,
+) -> String {
+    let mut options = *options;
+    if format == ClipboardFormat::Step {
+        options.file_unit = LengthUnit::Millimeter;
+    }
+    let meshes: Vec<(&str, TriMesh)> = bodies
+        .iter()
+        .map(|(name, mesh)| {
+            let mut mesh = mesh.clone();
+            options.export_mesh(&mut mesh, document_axes);
+            (name.as_str(), mesh)
+        })
+        .collect();
+    let meshes: Vec<(&str, &TriMesh)> = meshes.iter().map(|(name, mesh)| (*name, mesh)).collect();
+    match format {
+        ClipboardFormat::Stl => stl::to_ascii_stl(&meshes),
+        ClipboardFormat::Step => step::write_faceted(&meshes),
+    }
+}
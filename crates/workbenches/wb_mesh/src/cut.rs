@@ -0,0 +1,460 @@
+//! Cutting a mesh with a plane, optionally closing the cut with a flat cap,
+//! and cutting convex holes out of a closed mesh.
+
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+
+use crate::mesh::{edges_of, IndexedMesh};
+
+/// Vertices this close to the plane (mm) count as lying on it.
+const PLANE_TOLERANCE: f32 = 1e-4;
+
+/// Keep the part of `mesh` on the side `normal` points to. With `cap`, the
+/// openings left on the plane are filled so a closed mesh stays closed.
+pub fn plane_cut(mesh: &IndexedMesh, origin: Vec3, normal: Vec3, cap: bool) -> IndexedMesh {
+    let normal = normal.normalize_or_zero();
+    if normal == Vec3::ZERO {
+        return mesh.clone();
+    }
+    let split = split(mesh, origin, normal);
+    let mut result = split.front_mesh();
+    if cap {
+        let loops = cap_loops(&result, &split.sides);
+        let caps = cap_openings(&result, &loops, normal);
+        result.triangles.extend(caps);
+    }
+    result.compact();
+    result
+}
+
+/// Keep the part of `mesh` on the side `normal` points to, and return it
+/// together with the faces filling its openings on the plane (the section of
+/// a closed mesh), which face along `-normal`.
+pub fn section(mesh: &IndexedMesh, origin: Vec3, normal: Vec3) -> (IndexedMesh, IndexedMesh) {
+    let normal = normal.normalize_or_zero();
+    if normal == Vec3::ZERO {
+        return (mesh.clone(), IndexedMesh::default());
+    }
+    let split = split(mesh, origin, normal);
+    let mut kept = split.front_mesh();
+    let loops = cap_loops(&kept, &split.sides);
+    let mut cap = IndexedMesh {
+        positions: kept.positions.clone(),
+        triangles: cap_openings(&kept, &loops, normal),
+    };
+    kept.compact();
+    cap.compact();
+    (kept, cap)
+}
+
+/// Remove the convex region bounded by `planes` (a point on each and its
+/// normal pointing out of the region) from a closed mesh. The parts of the
+/// planes inside the mesh wall the hole, so the result stays closed.
+pub fn subtract_convex(mesh: &IndexedMesh, planes: &[(Vec3, Vec3)]) -> IndexedMesh {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Part {
+        /// Behind one of the planes so far: kept.
+        Outside,
+        /// In front of every plane so far.
+        Inside,
+        /// Cap closing the inside part where a plane cut it.
+        Wall,
+    }
+
+    // Split along each plane in turn and cap the part still inside, all in
+    // one mesh so the walls share their edges with the kept pieces.
+    let mut result = mesh.clone();
+    let mut parts = vec![Part::Inside; mesh.triangles.len()];
+    for &(origin, normal) in planes {
+        let inward = -normal.normalize_or_zero();
+        if inward == Vec3::ZERO {
+            continue;
+        }
+        let split = split(&result, origin, inward);
+        let mut inside = IndexedMesh {
+            positions: split.positions,
+            triangles: Vec::new(),
+        };
+        let (mut kept, mut kept_parts) = (Vec::new(), Vec::new());
+        for &(triangle, source) in &split.front {
+            if parts[source] == Part::Outside {
+                kept.push(triangle);
+            } else {
+                inside.triangles.push(triangle);
+                kept_parts.push(parts[source]);
+            }
+        }
+        let loops = cap_loops(&inside, &split.sides);
+        let caps = cap_openings(&inside, &loops, inward);
+        kept_parts.extend(caps.iter().map(|_| Part::Wall));
+        inside.triangles.extend(caps);
+        // Walls behind the plane are cut away with the rest of the region's
+        // outside; everything else behind it is kept.
+        let behind = split
+            .back
+            .iter()
+            .filter(|&&(_, source)| parts[source] != Part::Wall)
+            .map(|&(triangle, _)| triangle);
+        kept.extend(behind);
+        parts = vec![Part::Outside; kept.len()];
+        parts.extend(kept_parts);
+        kept.extend(inside.triangles);
+        result = IndexedMesh {
+            positions: inside.positions,
+            triangles: kept,
+        };
+    }
+
+    // Keep the outside, with the walls turned to face into the hole.
+    result.triangles = result
+        .triangles
+        .iter()
+        .zip(&parts)
+        .filter_map(|(&[a, b, c], part)| match part {
+            Part::Outside => Some([a, b, c]),
+            Part::Wall => Some([a, c, b]),
+            Part::Inside => None,
+        })
+        .collect();
+    result.compact();
+    result
+}
+
+/// A mesh split by a plane.
+struct Split {
+    /// The mesh's vertices followed by the points where edges cross the plane.
+    positions: Vec<Vec3>,
+    /// Pieces in front of the plane, each with the triangle it came from.
+    front: Vec<([u32; 3], usize)>,
+    /// Pieces behind the plane, each with the triangle it came from.
+    back: Vec<([u32; 3], usize)>,
+    /// Side of the plane each vertex is on (0 for on the plane).
+    sides: Vec<i8>,
+}
+
+impl Split {
+    fn front_mesh(&self) -> IndexedMesh {
+        IndexedMesh {
+            positions: self.positions.clone(),
+            triangles: self.front.iter().map(|&(triangle, _)| triangle).collect(),
+        }
+    }
+}
+
+/// Split the triangles of `mesh` where they cross the plane through `origin`
+/// with unit `normal`.
+fn split(mesh: &IndexedMesh, origin: Vec3, normal: Vec3) -> Split {
+    let distance = |p: Vec3| (p - origin).dot(normal);
+    let side = |p: Vec3| {
+        let d = distance(p);
+        if d < -PLANE_TOLERANCE {
+            -1
+        } else if d > PLANE_TOLERANCE {
+            1
+        } else {
+            0
+        }
+    };
+
+    let mut result = Split {
+        positions: mesh.positions.clone(),
+        front: Vec::new(),
+        back: Vec::new(),
+        sides: mesh.positions.iter().map(|&p| side(p)).collect(),
+    };
+    let mut cuts: HashMap<(u32, u32), u32> = HashMap::new();
+    for (source, triangle) in mesh.triangles.iter().enumerate() {
+        let corner_sides = triangle.map(|v| result.sides[v as usize]);
+        if corner_sides == [0; 3] {
+            // A face on the plane bounds the side it faces away from.
+            let [a, b, c] = triangle.map(|v| result.positions[v as usize]);
+            if (b - a).cross(c - a).dot(normal) < 0.0 {
+                result.front.push((*triangle, source));
+            } else {
+                result.back.push((*triangle, source));
+            }
+            continue;
+        }
+        if !corner_sides.contains(&1) {
+            result.back.push((*triangle, source));
+            continue;
+        }
+        if !corner_sides.contains(&-1) {
+            result.front.push((*triangle, source));
+            continue;
+        }
+        // Walk the outline, inserting the crossing point of each cut edge, and
+        // gather the corners on either side (those on the plane go to both).
+        let (mut front, mut back) = (Vec::with_capacity(4), Vec::with_capacity(4));
+        for (a, b) in edges_of(triangle) {
+            let (side_a, side_b) = (result.sides[a as usize], result.sides[b as usize]);
+            if side_a >= 0 {
+                front.push(a);
+            }
+            if side_a <= 0 {
+                back.push(a);
+            }
+            if side_a * side_b < 0 {
+                let key = (a.min(b), a.max(b));
+                let cut = *cuts.entry(key).or_insert_with(|| {
+                    let (pa, pb) = (
+                        result.positions[key.0 as usize],
+                        result.positions[key.1 as usize],
+                    );
+                    let t = distance(pa) / (distance(pa) - distance(pb));
+                    result.positions.push(pa.lerp(pb, t));
+                    result.sides.push(0);
+                    (result.positions.len() - 1) as u32
+                });
+                front.push(cut);
+                back.push(cut);
+            }
+        }
+        for (outline, pieces) in [(front, &mut result.front), (back, &mut result.back)] {
+            for k in 1..outline.len().saturating_sub(1) {
+                pieces.push(([outline[0], outline[k], outline[k + 1]], source));
+            }
+        }
+    }
+    result
+}
+
+/// Openings on the plane, as loops of vertices running opposite to the
+/// surface's border so the cap faces away from the kept part.
+fn cap_loops(mesh: &IndexedMesh, sides: &[i8]) -> Vec<Vec<u32>> {
+    let mut directed: HashMap<(u32, u32), usize> = HashMap::new();
+    for triangle in &mesh.triangles {
+        for edge in edges_of(triangle) {
+            *directed.entry(edge).or_default() += 1;
+        }
+    }
+    // Border edges lying on the plane, reversed: start -> end.
+    let mut next: HashMap<u32, u32> = directed
+        .keys()
+        .filter(|&&(a, b)| !directed.contains_key(&(b, a)))
+        .filter(|&&(a, b)| sides[a as usize] == 0 && sides[b as usize] == 0)
+        .map(|&(a, b)| (b, a))
+        .collect();
+
+    let mut loops = Vec::new();
+    while let Some(&start) = next.keys().next() {
+        let mut chain = vec![start];
+        let mut current = start;
+        let mut closed = false;
+        while let Some(following) = next.remove(&current) {
+            if following == start {
+                closed = true;
+                break;
+            }
+            chain.push(following);
+            current = following;
+        }
+        if closed && chain.len() >= 3 {
+            loops.push(chain);
+        }
+    }
+    loops
+}
+
+/// Triangles filling the loops, facing away from the kept part.
+fn cap_openings(mesh: &IndexedMesh, loops: &[Vec<u32>], normal: Vec3) -> Vec<[u32; 3]> {
+    // Plane coordinates in which a counter-clockwise loop faces along -normal.
+    let u = normal.any_orthonormal_vector();
+    let v = u.cross(normal);
+    fill_loops(loops, |vertex: u32| {
+        let p = mesh.positions[vertex as usize];
+        Vec2::new(p.dot(u), p.dot(v))
+    })
+}
+
+/// Triangles filling flat loops of vertices, counter-clockwise in the 2D
+/// coordinates given by `flat`. Loops running clockwise are holes inside
+/// another loop and are bridged into it before triangulating.
+pub(crate) fn fill_loops(loops: &[Vec<u32>], flat: impl Fn(u32) -> Vec2) -> Vec<[u32; 3]> {
+    let (mut outers, mut holes): (Vec<Vec<u32>>, Vec<Vec<u32>>) = (Vec::new(), Vec::new());
+    for ring in loops {
+        let points: Vec<Vec2> = ring.iter().map(|&i| flat(i)).collect();
+        if signed_area(&points) >= 0.0 {
+            outers.push(ring.clone());
+        } else {
+            holes.push(ring.clone());
+        }
+    }
+    // Smallest outer loops first, so a hole goes to the innermost loop around it.
+    outers.sort_by(|a, b| {
+        let area =
+            |ring: &Vec<u32>| signed_area(&ring.iter().map(|&i| flat(i)).collect::<Vec<_>>());
+        area(a).total_cmp(&area(b))
+    });
+    let mut assigned: Vec<Vec<Vec<u32>>> = vec![Vec::new(); outers.len()];
+    for hole in holes {
+        let probe = flat(hole[0]);
+        let owner = outers.iter().position(|outer| {
+            let points: Vec<Vec2> = outer.iter().map(|&i| flat(i)).collect();
+            contains(&points, probe)
+        });
+        if let Some(owner) = owner {
+            assigned[owner].push(hole);
+        }
+    }
+
+    let mut triangles = Vec::new();
+    for (mut outer, mut outer_holes) in outers.into_iter().zip(assigned) {
+        // Bridge holes from right to left so later bridges do not cross earlier ones.
+        outer_holes.sort_by(|a, b| {
+            let right = |ring: &Vec<u32>| ring.iter().map(|&i| flat(i).x).fold(f32::MIN, f32::max);
+            right(b).total_cmp(&right(a))
+        });
+        for (k, hole) in outer_holes.iter().enumerate() {
+            outer = bridge(&outer, hole, &outer_holes[k + 1..], &flat);
+        }
+        let points: Vec<Vec2> = outer.iter().map(|&i| flat(i)).collect();
+        for [a, b, c] in ear_clip(&points) {
+            triangles.push([outer[a], outer[b], outer[c]]);
+        }
+    }
+    triangles
+}
+
+pub(crate) fn signed_area(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum::<f32>()
+        * 0.5
+}
+
+/// Point-in-polygon test (even-odd rule).
+pub(crate) fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Whether segments `p1-p2` and `q1-q2` cross at a point inside both.
+fn segments_cross(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> bool {
+    let d1 = (p2 - p1).perp_dot(q1 - p1);
+    let d2 = (p2 - p1).perp_dot(q2 - p1);
+    let d3 = (q2 - q1).perp_dot(p1 - q1);
+    let d4 = (q2 - q1).perp_dot(p2 - q1);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Whether `point` lies on the segment `a-b` (within the plane tolerance).
+fn on_segment(point: Vec2, a: Vec2, b: Vec2) -> bool {
+    let edge = b - a;
+    let t = (point - a).dot(edge) / edge.length_squared().max(f32::MIN_POSITIVE);
+    (0.0..=1.0).contains(&t) && point.distance(a + edge * t) < PLANE_TOLERANCE
+}
+
+/// Join a hole to its outer loop with a pair of coincident edges from the
+/// hole's rightmost vertex to the nearest outer vertex it can see past the
+/// holes still to be joined.
+fn bridge(
+    outer: &[u32],
+    hole: &[u32],
+    others: &[Vec<u32>],
+    flat: &impl Fn(u32) -> Vec2,
+) -> Vec<u32> {
+    let start = (0..hole.len())
+        .max_by(|&a, &b| flat(hole[a]).x.total_cmp(&flat(hole[b]).x))
+        .unwrap_or(0);
+    let from = flat(hole[start]);
+    let edges: Vec<(Vec2, Vec2)> = [outer, hole]
+        .into_iter()
+        .chain(others.iter().map(Vec::as_slice))
+        .flat_map(|ring| {
+            ring.iter()
+                .zip(ring.iter().cycle().skip(1))
+                .map(|(&a, &b)| (flat(a), flat(b)))
+        })
+        .collect();
+    // A bridge running through another vertex would split the polygon there.
+    let visible = |to: Vec2| {
+        edges.iter().all(|&(a, b)| {
+            !segments_cross(from, to, a, b) && (a == from || a == to || !on_segment(a, from, to))
+        })
+    };
+    // Vertices of earlier bridges appear twice; pick the copy whose corner
+    // opens towards the hole.
+    let n = outer.len();
+    let facing = |i: usize| {
+        let p = flat(outer[i]);
+        let (next, previous) = (
+            flat(outer[(i + 1) % n]) - p,
+            flat(outer[(i + n - 1) % n]) - p,
+        );
+        let towards = from - p;
+        if next.perp_dot(previous) >= 0.0 {
+            next.perp_dot(towards) >= 0.0 && towards.perp_dot(previous) >= 0.0
+        } else {
+            !(previous.perp_dot(towards) > 0.0 && towards.perp_dot(next) > 0.0)
+        }
+    };
+    let distance = |i: usize| flat(outer[i]).distance_squared(from);
+    let target = (0..n)
+        .filter(|&i| facing(i) && visible(flat(outer[i])))
+        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+        .or_else(|| (0..outer.len()).min_by(|&a, &b| distance(a).total_cmp(&distance(b))))
+        .unwrap_or(0);
+
+    let mut merged = Vec::with_capacity(outer.len() + hole.len() + 2);
+    merged.extend_from_slice(&outer[..=target]);
+    merged.extend((0..=hole.len()).map(|k| hole[(start + k) % hole.len()]));
+    merged.push(outer[target]);
+    merged.extend_from_slice(&outer[target + 1..]);
+    merged
+}
+
+/// Triangulate a counter-clockwise polygon by clipping ears. Returns indices
+/// into `points`.
+fn ear_clip(points: &[Vec2]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&k| {
+            let (a, b, c) = (
+                remaining[(k + n - 1) % n],
+                remaining[k],
+                remaining[(k + 1) % n],
+            );
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            if (pb - pa).perp_dot(pc - pb) <= 0.0 {
+                return false;
+            }
+            // No other vertex may lie inside the ear (bridge duplicates are
+            // compared by position so they do not block it).
+            remaining.iter().all(|&other| {
+                let p = points[other];
+                p == pa
+                    || p == pb
+                    || p == pc
+                    || !((pb - pa).perp_dot(p - pa) >= 0.0
+                        && (pc - pb).perp_dot(p - pb) >= 0.0
+                        && (pa - pc).perp_dot(p - pc) >= 0.0)
+            })
+        });
+        // Degenerate outlines have no clean ear left; clip the first vertex anyway.
+        let k = ear.unwrap_or(0);
+        triangles.push([
+            remaining[(k + n - 1) % n],
+            remaining[k],
+            remaining[(k + 1) % n],
+        ]);
+        remaining.remove(k);
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
@@ -0,0 +1,323 @@
+//! Edge-collapse decimation and isotropic remeshing.
+//!
+//! Both work by collapsing edges (shortest first) into one vertex, refusing
+//! collapses that would fold triangles over or pinch the surface into a
+//! non-manifold shape. Remeshing also splits edges that are too long and then
+//! evens out the vertex spacing along the surface.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use glam::Vec3;
+
+use crate::mesh::{edges_of, IndexedMesh};
+
+/// A collapse is refused if it turns any triangle by more than about 60°.
+const MIN_NORMAL_DOT: f32 = 0.5;
+
+/// Split/collapse/relax rounds made by `remesh`.
+const REMESH_ITERATIONS: usize = 3;
+
+/// Upper bound on edge-splitting passes per round (each pass halves long edges).
+const MAX_SPLIT_PASSES: usize = 8;
+
+/// Edges waiting to be collapsed, shortest first. Lengths are non-negative, so
+/// their bit patterns sort like the values.
+type EdgeQueue = BinaryHeap<Reverse<(u32, u32, u32)>>;
+
+/// Mesh being simplified by edge collapses.
+struct Collapser {
+    positions: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    /// Triangles touching each vertex (dead ones are skipped when read).
+    around: Vec<Vec<usize>>,
+    boundary: Vec<bool>,
+    live: usize,
+}
+
+impl Collapser {
+    fn new(mesh: &IndexedMesh) -> Self {
+        let mut around = vec![Vec::new(); mesh.positions.len()];
+        for (index, triangle) in mesh.triangles.iter().enumerate() {
+            for &vertex in triangle {
+                around[vertex as usize].push(index);
+            }
+        }
+        Self {
+            positions: mesh.positions.clone(),
+            triangles: mesh.triangles.clone(),
+            alive: vec![true; mesh.triangles.len()],
+            around,
+            boundary: mesh.boundary_vertices(),
+            live: mesh.triangles.len(),
+        }
+    }
+
+    fn live_around(&self, vertex: u32) -> impl Iterator<Item = usize> + '_ {
+        self.around[vertex as usize]
+            .iter()
+            .copied()
+            .filter(|&t| self.alive[t])
+    }
+
+    fn neighbors(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self
+            .live_around(vertex)
+            .flat_map(|t| self.triangles[t])
+            .filter(|&v| v != vertex)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    fn length(&self, a: u32, b: u32) -> f32 {
+        self.positions[a as usize].distance(self.positions[b as usize])
+    }
+
+    fn push_edge(&self, queue: &mut EdgeQueue, a: u32, b: u32) {
+        queue.push(Reverse((self.length(a, b).to_bits(), a, b)));
+    }
+
+    /// Merge `b` into `a`, unless that would damage the surface or create an
+    /// edge longer than `max_length`. Returns whether the collapse happened.
+    fn collapse(&mut self, a: u32, b: u32, max_length: f32) -> bool {
+        let shared = self
+            .live_around(a)
+            .filter(|&t| self.triangles[t].contains(&b))
+            .count();
+        if a == b || shared == 0 {
+            return false;
+        }
+        // Link condition: the only vertices next to both ends are the tips of
+        // the triangles on the edge.
+        let (around_a, around_b) = (self.neighbors(a), self.neighbors(b));
+        let common = around_a.iter().filter(|v| around_b.contains(v)).count();
+        if common > shared {
+            return false;
+        }
+        let (pa, pb) = (self.positions[a as usize], self.positions[b as usize]);
+        let target = match (self.boundary[a as usize], self.boundary[b as usize]) {
+            (true, false) => pa,
+            (false, true) => pb,
+            (true, true) if shared > 1 => return false,
+            _ => (pa + pb) * 0.5,
+        };
+
+        let affected: Vec<usize> = self.live_around(a).chain(self.live_around(b)).collect();
+        for &t in &affected {
+            let triangle = self.triangles[t];
+            if triangle.contains(&a) && triangle.contains(&b) {
+                continue;
+            }
+            let before = triangle.map(|v| self.positions[v as usize]);
+            let after = triangle.map(|v| {
+                if v == a || v == b {
+                    target
+                } else {
+                    self.positions[v as usize]
+                }
+            });
+            let normal_before = (before[1] - before[0]).cross(before[2] - before[0]);
+            let normal_after = (after[1] - after[0]).cross(after[2] - after[0]);
+            if normal_after.length_squared() <= f32::EPSILON
+                || normal_before
+                    .normalize_or_zero()
+                    .dot(normal_after.normalize_or_zero())
+                    < MIN_NORMAL_DOT
+                || after
+                    .iter()
+                    .any(|&p| p != target && p.distance(target) > max_length)
+            {
+                return false;
+            }
+        }
+
+        self.positions[a as usize] = target;
+        self.boundary[a as usize] |= self.boundary[b as usize];
+        let moved = std::mem::take(&mut self.around[b as usize]);
+        for t in moved {
+            if !self.alive[t] {
+                continue;
+            }
+            if self.triangles[t].contains(&a) {
+                self.alive[t] = false;
+                self.live -= 1;
+            } else {
+                for vertex in &mut self.triangles[t] {
+                    if *vertex == b {
+                        *vertex = a;
+                    }
+                }
+                self.around[a as usize].push(t);
+            }
+        }
+        true
+    }
+
+    /// Collapse queued edges (shortest first) while `keep_going` allows, only
+    /// taking edges shorter than `max_collapse`.
+    fn run(
+        &mut self,
+        queue: &mut EdgeQueue,
+        max_collapse: f32,
+        max_length: f32,
+        keep_going: impl Fn(&Self) -> bool,
+    ) {
+        while keep_going(self) {
+            let Some(Reverse((bits, a, b))) = queue.pop() else {
+                break;
+            };
+            // Stale entries: the edge changed length since it was queued.
+            let length = self.length(a, b);
+            if length.to_bits() != bits || length >= max_collapse {
+                continue;
+            }
+            if self.collapse(a, b, max_length) {
+                for n in self.neighbors(a) {
+                    if self.length(a, n) < max_collapse {
+                        self.push_edge(queue, a, n);
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> IndexedMesh {
+        let mut mesh = IndexedMesh {
+            positions: self.positions,
+            triangles: self
+                .triangles
+                .into_iter()
+                .zip(self.alive)
+                .filter_map(|(triangle, alive)| alive.then_some(triangle))
+                .collect(),
+        };
+        mesh.compact();
+        mesh
+    }
+}
+
+fn queue_edges(collapser: &Collapser, mesh: &IndexedMesh, max_collapse: f32) -> EdgeQueue {
+    let mut queue = EdgeQueue::new();
+    for ((a, b), _) in mesh.edge_use() {
+        if collapser.length(a, b) < max_collapse {
+            collapser.push_edge(&mut queue, a, b);
+        }
+    }
+    queue
+}
+
+/// Reduce the triangle count to about `ratio` of the original by collapsing
+/// the shortest edges first.
+pub fn decimate(mesh: &IndexedMesh, ratio: f32) -> IndexedMesh {
+    let target = (mesh.triangles.len() as f32 * ratio.clamp(0.0, 1.0)).ceil() as usize;
+    let mut collapser = Collapser::new(mesh);
+    let mut queue = queue_edges(&collapser, mesh, f32::INFINITY);
+    collapser.run(&mut queue, f32::INFINITY, f32::INFINITY, |c| {
+        c.live > target
+    });
+    collapser.finish()
+}
+
+/// Rebuild the surface with edges close to `edge_length`: long edges are split,
+/// short ones collapsed, and vertices spread evenly along the surface.
+pub fn remesh(mesh: &IndexedMesh, edge_length: f32) -> IndexedMesh {
+    let (low, high) = (edge_length * 0.8, edge_length * 4.0 / 3.0);
+    let mut mesh = mesh.clone();
+    for _ in 0..REMESH_ITERATIONS {
+        for _ in 0..MAX_SPLIT_PASSES {
+            if !split_long_edges(&mut mesh, high) {
+                break;
+            }
+        }
+        let mut collapser = Collapser::new(&mesh);
+        let mut queue = queue_edges(&collapser, &mesh, low);
+        collapser.run(&mut queue, low, high, |_| true);
+        mesh = collapser.finish();
+        relax(&mut mesh);
+    }
+    mesh
+}
+
+/// Split every edge longer than `max_length` at its midpoint. Returns false if
+/// there was nothing to split.
+fn split_long_edges(mesh: &mut IndexedMesh, max_length: f32) -> bool {
+    let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+    for ((a, b), _) in mesh.edge_use() {
+        let (pa, pb) = (mesh.positions[a as usize], mesh.positions[b as usize]);
+        if pa.distance(pb) > max_length {
+            mesh.positions.push((pa + pb) * 0.5);
+            midpoints.insert((a, b), (mesh.positions.len() - 1) as u32);
+        }
+    }
+    if midpoints.is_empty() {
+        return false;
+    }
+    let midpoint = |a: u32, b: u32| midpoints.get(&(a.min(b), a.max(b))).copied();
+
+    let mut triangles = Vec::with_capacity(mesh.triangles.len() * 2);
+    for triangle in &mesh.triangles {
+        let split = edges_of(triangle).map(|(a, b)| midpoint(a, b));
+        match split.iter().filter(|m| m.is_some()).count() {
+            0 => triangles.push(*triangle),
+            3 => {
+                let [v0, v1, v2] = *triangle;
+                let [m0, m1, m2] = split.map(Option::unwrap);
+                triangles.extend([[v0, m0, m2], [v1, m1, m0], [v2, m2, m1], [m0, m1, m2]]);
+            }
+            count => {
+                // Rotate so edge 0 (and edge 1 for two splits) is the split one;
+                // rotating the corners keeps the winding.
+                let shift = (0..3)
+                    .find(|&s| split[s].is_some() && (count == 1 || split[(s + 1) % 3].is_some()))
+                    .unwrap_or(0);
+                let [v0, v1, v2] = [0, 1, 2].map(|i| triangle[(i + shift) % 3]);
+                let m0 = split[shift].unwrap_or(v0);
+                if count == 1 {
+                    triangles.extend([[v0, m0, v2], [m0, v1, v2]]);
+                } else {
+                    let m1 = split[(shift + 1) % 3].unwrap_or(v1);
+                    triangles.push([m0, v1, m1]);
+                    // Cut the remaining quad along its shorter diagonal.
+                    let p = |v: u32| mesh.positions[v as usize];
+                    if p(v0).distance(p(m1)) < p(m0).distance(p(v2)) {
+                        triangles.extend([[v0, m0, m1], [v0, m1, v2]]);
+                    } else {
+                        triangles.extend([[v0, m0, v2], [m0, m1, v2]]);
+                    }
+                }
+            }
+        }
+    }
+    mesh.triangles = triangles;
+    true
+}
+
+/// Move each interior vertex towards the centroid of its neighbours, within
+/// the tangent plane so the shape is kept.
+fn relax(mesh: &mut IndexedMesh) {
+    let neighbors = mesh.vertex_neighbors();
+    let boundary = mesh.boundary_vertices();
+    let normals = mesh.vertex_normals();
+    mesh.positions = mesh
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(vertex, &p)| {
+            let around = &neighbors[vertex];
+            if boundary[vertex] || around.is_empty() {
+                return p;
+            }
+            let centroid = around
+                .iter()
+                .map(|&n| mesh.positions[n as usize])
+                .sum::<Vec3>()
+                / around.len() as f32;
+            let offset = centroid - p;
+            let normal = normals[vertex];
+            p + (offset - normal * offset.dot(normal)) * 0.5
+        })
+        .collect();
+}
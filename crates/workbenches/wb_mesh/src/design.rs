@@ -0,0 +1,209 @@
+//! Parametric design elements for printed parts: screw bosses sized for
+//! heat-set inserts, cantilever snap fits, and living hinges. An element is
+//! placed on a face of a mesh body and added to the body's mesh.
+
+use glam::{Quat, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::hardware::{HardwarePart, MetricSize};
+use crate::mesh::{self, IndexedMesh};
+
+/// A generated design element. Elements are built standing on the XY plane
+/// with Z pointing out of the face they are placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DesignElement {
+    /// Cylindrical boss with a blind hole sized for a heat-set insert.
+    ScrewBoss {
+        size: MetricSize,
+        /// Boss height (mm); at least the insert depth.
+        height: f32,
+        /// Wall thickness around the hole (mm).
+        wall: f32,
+    },
+    /// Cantilever beam with a hook at its tip.
+    SnapFit {
+        /// Beam length out of the face (mm).
+        length: f32,
+        /// Beam thickness, in the direction it flexes (mm).
+        thickness: f32,
+        width: f32,
+        /// How far the hook sticks out of the beam (mm).
+        hook: f32,
+    },
+    /// Two flaps joined by a thin strip that bends.
+    LivingHinge {
+        /// Length of each flap (mm).
+        flap_length: f32,
+        width: f32,
+        /// Flap thickness (mm).
+        thickness: f32,
+        /// Thickness of the bending strip (mm), usually a few layers.
+        hinge_thickness: f32,
+        /// Length of the bending strip between the flaps (mm).
+        gap: f32,
+    },
+}
+
+impl DesignElement {
+    pub const KINDS: [&'static str; 3] = ["Screw Boss", "Snap Fit", "Living Hinge"];
+
+    /// Default element of the kind at `index` in [`DesignElement::KINDS`].
+    pub fn of_kind(index: usize) -> Self {
+        match index {
+            0 => match HardwarePart::of_kind(3, MetricSize::M3) {
+                HardwarePart::InsertBoss { size, height, wall } => {
+                    DesignElement::ScrewBoss { size, height, wall }
+                }
+                _ => unreachable!("kind 3 is the insert boss"),
+            },
+            1 => DesignElement::SnapFit {
+                length: 12.0,
+                thickness: 1.6,
+                width: 6.0,
+                hook: 1.0,
+            },
+            _ => DesignElement::LivingHinge {
+                flap_length: 15.0,
+                width: 20.0,
+                thickness: 2.0,
+                hinge_thickness: 0.4,
+                gap: 2.0,
+            },
+        }
+    }
+
+    /// Index of the element's kind in [`DesignElement::KINDS`].
+    pub fn kind(&self) -> usize {
+        match self {
+            DesignElement::ScrewBoss { .. } => 0,
+            DesignElement::SnapFit { .. } => 1,
+            DesignElement::LivingHinge { .. } => 2,
+        }
+    }
+
+    /// Name for the feature, e.g. `M3 Screw Boss`.
+    pub fn name(&self) -> String {
+        match self {
+            DesignElement::ScrewBoss { size, .. } => format!("{} Screw Boss", size.label()),
+            DesignElement::SnapFit { length, .. } => format!("Snap Fit {length} mm"),
+            DesignElement::LivingHinge { .. } => "Living Hinge".to_string(),
+        }
+    }
+
+    /// Closed mesh of the element in its own frame.
+    pub fn generate(&self) -> IndexedMesh {
+        match *self {
+            DesignElement::ScrewBoss { size, height, wall } => {
+                HardwarePart::InsertBoss { size, height, wall }.generate()
+            }
+            DesignElement::SnapFit {
+                length,
+                thickness,
+                width,
+                hook,
+            } => {
+                let (length, half_thickness) = (length.max(1.0), thickness.max(0.4) / 2.0);
+                let half_width = width.max(0.4) / 2.0;
+                let mut result = mesh::block(
+                    Vec3::new(-half_thickness, -half_width, 0.0),
+                    Vec3::new(half_thickness, half_width, length),
+                );
+                // Flat catch facing the face, and a ramp three times as long
+                // as the hook is deep leading up to it from the tip.
+                let hook = hook.max(0.1);
+                let catch = (length - 3.0 * hook).max(0.0);
+                let profile = [
+                    Vec2::new(half_thickness, catch),
+                    Vec2::new(half_thickness + hook, catch),
+                    Vec2::new(half_thickness, length),
+                ];
+                mesh::merge(&mut result, &mesh::prism(&profile, -half_width, half_width));
+                result
+            }
+            DesignElement::LivingHinge {
+                flap_length,
+                width,
+                thickness,
+                hinge_thickness,
+                gap,
+            } => {
+                let half_gap = gap.max(0.1) / 2.0;
+                let half_width = width.max(0.4) / 2.0;
+                let flap_length = flap_length.max(1.0);
+                let thickness = thickness.max(0.2);
+                let mut result = IndexedMesh::default();
+                for (from, to) in [
+                    (-half_gap - flap_length, -half_gap),
+                    (half_gap, half_gap + flap_length),
+                ] {
+                    mesh::merge(
+                        &mut result,
+                        &mesh::block(
+                            Vec3::new(from, -half_width, 0.0),
+                            Vec3::new(to, half_width, thickness),
+                        ),
+                    );
+                }
+                mesh::merge(
+                    &mut result,
+                    &mesh::block(
+                        Vec3::new(-half_gap, -half_width, 0.0),
+                        Vec3::new(half_gap, half_width, hinge_thickness.clamp(0.1, thickness)),
+                    ),
+                );
+                result
+            }
+        }
+    }
+
+    /// The element placed at `origin` on a face with outward `normal`, turned
+    /// by `rotation` degrees about the normal.
+    pub fn generate_at(&self, origin: Vec3, normal: Vec3, rotation: f32) -> IndexedMesh {
+        let normal = normal.try_normalize().unwrap_or(Vec3::Z);
+        let orientation =
+            Quat::from_rotation_arc(Vec3::Z, normal) * Quat::from_rotation_z(rotation.to_radians());
+        let mut result = self.generate();
+        for position in &mut result.positions {
+            *position = origin + orientation * *position;
+        }
+        result
+    }
+}
+
+/// Outward normal of the triangle of `mesh` nearest to `point`.
+pub fn face_normal_at(mesh: &IndexedMesh, point: Vec3) -> Option<Vec3> {
+    mesh.triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|i| mesh.positions[i as usize]);
+            (distance_to_triangle(point, a, b, c), triangle)
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .and_then(|(_, triangle)| mesh.face_normal(triangle).try_normalize())
+}
+
+/// Distance from `p` to the triangle `abc`.
+pub(crate) fn distance_to_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let normal = (b - a).cross(c - a);
+    let Some(unit) = normal.try_normalize() else {
+        return f32::INFINITY;
+    };
+    // Inside the triangle's prism the nearest point is on the plane,
+    // otherwise it lies on one of the edges.
+    let projected = p - unit * (p - a).dot(unit);
+    let inside = [(a, b), (b, c), (c, a)]
+        .iter()
+        .all(|&(from, to)| (to - from).cross(projected - from).dot(normal) >= 0.0);
+    if inside {
+        return (p - projected).length();
+    }
+    [(a, b), (b, c), (c, a)]
+        .iter()
+        .map(|&(from, to)| {
+            let edge = to - from;
+            let t = ((p - from).dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+            (p - (from + edge * t)).length()
+        })
+        .fold(f32::INFINITY, f32::min)
+}
@@ -0,0 +1,143 @@
+//! Units, scale, and axes of an import or export, confirmed in a task panel
+//! before it runs. The form starts from the format's conventions in
+//! Settings → Import/Export; imports also guess the unit from the size of
+//! the model.
+
+use std::path::PathBuf;
+
+use core_document::TaskPanel;
+use glam::Vec3;
+use kernel_api::{ExchangeOptions, TriMesh};
+use units::LengthUnit;
+
+use crate::batch::BatchExport;
+use crate::import::FileMesh;
+use crate::usd::UsdFormat;
+
+/// What runs once the options are confirmed.
+pub(crate) enum ExchangeKind {
+    /// A read mesh, still in the file's coordinates.
+    Import(FileMesh),
+    /// A mesh pasted from the clipboard, in the text's coordinates.
+    Paste(FileMesh),
+    Usd(UsdFormat),
+    Amf,
+    /// Every body, to the folder at the exchange's path.
+    Batch(BatchExport),
+}
+
+/// An import or export waiting for its options to be confirmed.
+pub(crate) struct PendingExchange {
+    pub(crate) path: PathBuf,
+    pub(crate) kind: ExchangeKind,
+    pub(crate) options: ExchangeOptions,
+    /// Unit in the settings, when the guessed one differs from it.
+    pub(crate) settings_unit: Option<LengthUnit>,
+}
+
+impl PendingExchange {
+    /// Import of `mesh` read from `path`, with the unit guessed from its size.
+    pub(crate) fn import(path: PathBuf, mesh: FileMesh, options: ExchangeOptions) -> Self {
+        Self::read(path, mesh, options, ExchangeKind::Import)
+    }
+
+    /// Paste of `mesh` as a body called `name`, with the unit guessed from
+    /// its size.
+    pub(crate) fn paste(name: &str, mesh: TriMesh, options: ExchangeOptions) -> Self {
+        Self::read(
+            PathBuf::from(name),
+            mesh.into(),
+            options,
+            ExchangeKind::Paste,
+        )
+    }
+
+    fn read(
+        path: PathBuf,
+        mesh: FileMesh,
+        options: ExchangeOptions,
+        kind: fn(FileMesh) -> ExchangeKind,
+    ) -> Self {
+        let size = file_size(&mesh.mesh).max_element();
+        let unit = guess_unit(size, options.file_unit);
+        Self {
+            path,
+            kind: kind(mesh),
+            options: ExchangeOptions {
+                file_unit: unit,
+                ..options
+            },
+            settings_unit: (unit != options.file_unit).then_some(options.file_unit),
+        }
+    }
+
+    pub(crate) fn export(path: PathBuf, kind: ExchangeKind, options: ExchangeOptions) -> Self {
+        Self {
+            path,
+            kind,
+            options,
+            settings_unit: None,
+        }
+    }
+
+    pub(crate) fn is_import(&self) -> bool {
+        matches!(self.kind, ExchangeKind::Import(_) | ExchangeKind::Paste(_))
+    }
+
+    /// Task panel asking for the options.
+    pub(crate) fn task(&self) -> TaskPanel {
+        let title = match self.kind {
+            ExchangeKind::Import(_) | ExchangeKind::Paste(_) => "Import Options",
+            ExchangeKind::Batch(_) => "Export All Bodies",
+            _ => "Export Options",
+        };
+        TaskPanel::new(title).with_ok_label(if self.is_import() { "Import" } else { "Export" })
+    }
+
+    #[cfg(feature = "egui")]
+    /// Units the file can be written in.
+    pub(crate) fn units(&self) -> &'static [LengthUnit] {
+        match &self.kind {
+            ExchangeKind::Amf => &crate::amf::UNITS,
+            ExchangeKind::Batch(batch) if batch.format == crate::batch::BatchFormat::Amf => {
+                &crate::amf::UNITS
+            }
+            ExchangeKind::Import(_)
+            | ExchangeKind::Paste(_)
+            | ExchangeKind::Usd(_)
+            | ExchangeKind::Batch(_) => &LengthUnit::ALL,
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Size the imported model will have in the document, in millimetres.
+    pub(crate) fn imported_size(&self, document_axes: axes::AxisSystem) -> Option<Vec3> {
+        let (ExchangeKind::Import(mesh) | ExchangeKind::Paste(mesh)) = &self.kind else {
+            return None;
+        };
+        let size = file_size(&mesh.mesh);
+        let imported = self.options.import_point(size.to_array(), document_axes);
+        Some(Vec3::from(imported).abs())
+    }
+}
+
+/// Extents of a mesh's bounds, in its own coordinates.
+fn file_size(mesh: &TriMesh) -> Vec3 {
+    mesh.bounds()
+        .map(|(min, max)| Vec3::from(max) - Vec3::from(min))
+        .unwrap_or(Vec3::ZERO)
+}
+
+/// Unit a model whose largest side is `size` was most likely drawn in. A
+/// model under one millimetre is taken to be in metres, and one over a
+/// hundred metres in millimetres; printable parts are rarely either.
+pub(crate) fn guess_unit(size: f32, unit: LengthUnit) -> LengthUnit {
+    let size_mm = size * unit.mm_per_unit();
+    if size_mm > 0.0 && size_mm < 1.0 {
+        LengthUnit::Meter
+    } else if size_mm > 100_000.0 {
+        LengthUnit::Millimeter
+    } else {
+        unit
+    }
+}
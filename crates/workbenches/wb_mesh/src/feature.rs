@@ -0,0 +1,293 @@
+//! Mesh features: an imported mesh and the operations applied to it, chained
+//! per body so the whole history can be replayed and edited.
+
+use std::path::{Path, PathBuf};
+
+use core_document::{DocumentResult, FeatureError, FeatureId, WorkbenchFeature, WorkbenchId};
+use glam::Vec3;
+use kernel_api::{ExchangeOptions, TriMesh};
+use serde::{Deserialize, Serialize};
+
+use crate::calibration::CalibrationPart;
+use crate::design::DesignElement;
+use crate::hardware::HardwarePart;
+use crate::knurl::Knurl;
+use crate::mesh::{self, IndexedMesh};
+use crate::text::TextLabel;
+use crate::transform::RigidTransform;
+use crate::{cut, decimate, WORKBENCH_ID};
+
+pub const DEFAULT_DECIMATE_RATIO: f32 = 0.5;
+pub const DEFAULT_EDGE_LENGTH: f32 = 1.0;
+pub const DEFAULT_SMOOTH_ITERATIONS: u32 = 5;
+pub const DEFAULT_SMOOTH_FACTOR: f32 = 0.5;
+
+/// One step in a mesh body's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MeshOperation {
+    /// Mesh read from a file (stored welded, without normals).
+    Import {
+        file_name: String,
+        mesh: TriMesh,
+        /// Path the file was read from, for updating the mesh when the file
+        /// changes (None for imports made before paths were kept).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        /// Units, scale, and axes the file was read with.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        options: Option<ExchangeOptions>,
+        /// Document position the file's coordinates were taken relative to
+        /// (the body's position when the file lies far from the origin).
+        #[serde(default)]
+        origin: [f64; 3],
+    },
+    /// Generated standard hardware (bolt, nut, washer, insert boss).
+    Hardware {
+        part: HardwarePart,
+    },
+    /// Generated calibration print (tolerance test, overhang test,
+    /// temperature tower).
+    Calibration {
+        part: CalibrationPart,
+    },
+    /// Add a design element (screw boss, snap fit, living hinge) standing on
+    /// the face at `origin` with outward `normal` (world space), turned by
+    /// `rotation` degrees about the normal.
+    DesignElement {
+        element: DesignElement,
+        origin: [f32; 3],
+        normal: [f32; 3],
+        rotation: f32,
+    },
+    /// Emboss or engrave text on the face at `origin` with outward `normal`
+    /// (world space), its baseline turned by `rotation` degrees about the
+    /// normal.
+    Text {
+        label: TextLabel,
+        origin: [f32; 3],
+        normal: [f32; 3],
+        rotation: f32,
+    },
+    /// Add a knurl texture on the face at `origin` with outward `normal`
+    /// (world space); a pad knurl is turned by `rotation` degrees about the
+    /// normal.
+    Knurl {
+        knurl: Knurl,
+        origin: [f32; 3],
+        normal: [f32; 3],
+        rotation: f32,
+    },
+    /// Keep about `ratio` of the triangles.
+    Decimate {
+        ratio: f32,
+    },
+    /// Rebuild with edges close to `edge_length` (mm).
+    Remesh {
+        edge_length: f32,
+    },
+    Smooth {
+        iterations: u32,
+        factor: f32,
+    },
+    /// Keep the part on the side `normal` points to (world space).
+    PlaneCut {
+        origin: [f32; 3],
+        normal: [f32; 3],
+        cap: bool,
+    },
+    FlipNormals,
+    /// Move and turn the mesh without changing its shape.
+    Transform {
+        transform: RigidTransform,
+    },
+    /// Add the mesh produced by another body's feature.
+    Merge {
+        source: FeatureId,
+    },
+}
+
+impl MeshOperation {
+    /// Import of `mesh` (welded, in document coordinates relative to
+    /// `origin`) read from `path` with `options`.
+    pub fn import(
+        path: &Path,
+        mesh: &IndexedMesh,
+        options: ExchangeOptions,
+        origin: [f64; 3],
+    ) -> Self {
+        MeshOperation::Import {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            mesh: mesh.to_stored(),
+            source: Some(path.display().to_string()),
+            options: Some(options),
+            origin,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MeshOperation::Import { .. } => "Import",
+            MeshOperation::Hardware { .. } => "Hardware",
+            MeshOperation::Calibration { .. } => "Calibration",
+            MeshOperation::DesignElement { .. } => "Design Element",
+            MeshOperation::Text { .. } => "Text",
+            MeshOperation::Knurl { .. } => "Knurl",
+            MeshOperation::Decimate { .. } => "Decimate",
+            MeshOperation::Remesh { .. } => "Remesh",
+            MeshOperation::Smooth { .. } => "Smooth",
+            MeshOperation::PlaneCut { .. } => "Plane Cut",
+            MeshOperation::FlipNormals => "Flip Normals",
+            MeshOperation::Transform { .. } => "Transform",
+            MeshOperation::Merge { .. } => "Merge",
+        }
+    }
+
+    /// Apply the operation to the mesh produced by the previous feature
+    /// (`source` is the evaluated merge source, if any).
+    pub fn apply(&self, input: Option<&IndexedMesh>, source: Option<&IndexedMesh>) -> IndexedMesh {
+        let input = || input.cloned().unwrap_or_default();
+        match self {
+            MeshOperation::Import { mesh, .. } => IndexedMesh::from_trimesh(mesh),
+            MeshOperation::Hardware { part } => part.generate(),
+            MeshOperation::Calibration { part } => part.generate(),
+            MeshOperation::DesignElement {
+                element,
+                origin,
+                normal,
+                rotation,
+            } => {
+                let mut result = input();
+                let placed =
+                    element.generate_at(Vec3::from(*origin), Vec3::from(*normal), *rotation);
+                mesh::merge(&mut result, &placed);
+                result
+            }
+            MeshOperation::Text {
+                label,
+                origin,
+                normal,
+                rotation,
+            } => label.apply(
+                &input(),
+                Vec3::from(*origin),
+                Vec3::from(*normal),
+                *rotation,
+            ),
+            MeshOperation::Knurl {
+                knurl,
+                origin,
+                normal,
+                rotation,
+            } => knurl.apply(
+                &input(),
+                Vec3::from(*origin),
+                Vec3::from(*normal),
+                *rotation,
+            ),
+            MeshOperation::Decimate { ratio } => decimate::decimate(&input(), *ratio),
+            MeshOperation::Remesh { edge_length } => {
+                decimate::remesh(&input(), edge_length.max(0.01))
+            }
+            MeshOperation::Smooth { iterations, factor } => {
+                let mut result = input();
+                mesh::smooth(&mut result, *iterations, *factor);
+                result
+            }
+            MeshOperation::PlaneCut {
+                origin,
+                normal,
+                cap,
+            } => cut::plane_cut(&input(), Vec3::from(*origin), Vec3::from(*normal), *cap),
+            MeshOperation::FlipNormals => {
+                let mut result = input();
+                mesh::flip_normals(&mut result);
+                result
+            }
+            MeshOperation::Transform { transform } => transform.apply(&input()),
+            MeshOperation::Merge { .. } => {
+                let mut result = input();
+                if let Some(source) = source {
+                    mesh::merge(&mut result, source);
+                }
+                result
+            }
+        }
+    }
+}
+
+/// A mesh feature stored in the document's feature tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshFeature {
+    pub name: String,
+    /// Feature whose result this one modifies (None for imports and generated
+    /// parts).
+    pub input: Option<FeatureId>,
+    pub operation: MeshOperation,
+}
+
+/// Where an import feature's mesh was read from and with which options, read
+/// from its data without decoding the (possibly large) mesh.
+pub fn import_source(data: &serde_json::Value) -> Option<(PathBuf, Option<ExchangeOptions>)> {
+    let operation = data.get("operation")?;
+    let source = operation.get("source")?.as_str()?;
+    let options = operation
+        .get("options")
+        .and_then(|options| serde_json::from_value(options.clone()).ok());
+    Some((PathBuf::from(source), options))
+}
+
+/// Document position an import feature's mesh is relative to.
+pub fn import_origin(data: &serde_json::Value) -> [f64; 3] {
+    data.get("operation")
+        .and_then(|operation| operation.get("origin"))
+        .and_then(|origin| serde_json::from_value(origin.clone()).ok())
+        .unwrap_or_default()
+}
+
+impl MeshFeature {
+    pub fn new(input: Option<FeatureId>, operation: MeshOperation) -> Self {
+        let name = match &operation {
+            MeshOperation::DesignElement { element, .. } => element.name(),
+            MeshOperation::Text { label, .. } => label.name(),
+            MeshOperation::Knurl { knurl, .. } => knurl.name(),
+            _ => operation.label().to_string(),
+        };
+        Self {
+            name,
+            input,
+            operation,
+        }
+    }
+}
+
+impl WorkbenchFeature for MeshFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("MeshFeature should always serialize")
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        serde_json::from_value(value.clone()).map_err(|e| {
+            core_document::DocumentError::Feature(FeatureError::Deserialization(e.to_string()))
+        })
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        let source = match self.operation {
+            MeshOperation::Merge { source } => Some(source),
+            _ => None,
+        };
+        self.input.into_iter().chain(source).collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
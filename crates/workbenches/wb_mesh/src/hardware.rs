@@ -0,0 +1,379 @@
+//! Parametric standard hardware: metric bolts, nuts, washers, and bosses for
+//! heat-set inserts.
+//!
+//! Dimensions follow ISO 4762 (socket head cap screws), ISO 4017 (hex head
+//! screws), ISO 4032 (hex nuts), and ISO 7089 (flat washers). Insert holes use
+//! the usual sizes for brass heat-set inserts. Threads are not modeled: shanks
+//! and holes are plain cylinders at the nominal diameter.
+
+use std::f32::consts::TAU;
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::{self, IndexedMesh};
+
+/// Segments around round parts (a multiple of 12 so hexagons and squares are
+/// exact).
+const SEGMENTS: usize = 48;
+
+/// Metric thread size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MetricSize {
+    M2,
+    M2_5,
+    M3,
+    M4,
+    M5,
+    M6,
+    M8,
+    M10,
+    M12,
+}
+
+/// Standard dimensions of one size (mm).
+struct SizeTable {
+    diameter: f32,
+    /// Socket head diameter and the hex key size of its socket.
+    socket_head_diameter: f32,
+    socket_key: f32,
+    /// Width across flats of hex heads and nuts.
+    hex_width: f32,
+    hex_head_height: f32,
+    nut_height: f32,
+    /// Washer inner and outer diameter and thickness.
+    washer: (f32, f32, f32),
+    /// Heat-set insert hole diameter and depth.
+    insert: (f32, f32),
+}
+
+impl MetricSize {
+    pub const ALL: [MetricSize; 9] = [
+        MetricSize::M2,
+        MetricSize::M2_5,
+        MetricSize::M3,
+        MetricSize::M4,
+        MetricSize::M5,
+        MetricSize::M6,
+        MetricSize::M8,
+        MetricSize::M10,
+        MetricSize::M12,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MetricSize::M2 => "M2",
+            MetricSize::M2_5 => "M2.5",
+            MetricSize::M3 => "M3",
+            MetricSize::M4 => "M4",
+            MetricSize::M5 => "M5",
+            MetricSize::M6 => "M6",
+            MetricSize::M8 => "M8",
+            MetricSize::M10 => "M10",
+            MetricSize::M12 => "M12",
+        }
+    }
+
+    /// Nominal diameter (mm).
+    pub fn diameter(self) -> f32 {
+        self.table().diameter
+    }
+
+    fn table(self) -> SizeTable {
+        #[rustfmt::skip]
+        let (diameter, socket_head_diameter, socket_key, hex_width, hex_head_height, nut_height, washer, insert) =
+            match self {
+                MetricSize::M2 => (2.0, 3.8, 1.5, 4.0, 1.4, 1.6, (2.2, 5.0, 0.3), (3.2, 4.0)),
+                MetricSize::M2_5 => (2.5, 4.5, 2.0, 5.0, 1.7, 2.0, (2.7, 6.0, 0.5), (3.6, 5.7)),
+                MetricSize::M3 => (3.0, 5.5, 2.5, 5.5, 2.0, 2.4, (3.2, 7.0, 0.5), (4.0, 5.7)),
+                MetricSize::M4 => (4.0, 7.0, 3.0, 7.0, 2.8, 3.2, (4.3, 9.0, 0.8), (5.6, 8.1)),
+                MetricSize::M5 => (5.0, 8.5, 4.0, 8.0, 3.5, 4.7, (5.3, 10.0, 1.0), (6.4, 9.5)),
+                MetricSize::M6 => (6.0, 10.0, 5.0, 10.0, 4.0, 5.2, (6.4, 12.0, 1.6), (8.0, 12.7)),
+                MetricSize::M8 => (8.0, 13.0, 6.0, 13.0, 5.3, 6.8, (8.4, 16.0, 1.6), (9.7, 12.7)),
+                MetricSize::M10 => (10.0, 16.0, 8.0, 16.0, 6.4, 8.4, (10.5, 20.0, 2.0), (12.0, 14.0)),
+                MetricSize::M12 => (12.0, 18.0, 10.0, 18.0, 7.5, 10.8, (13.0, 24.0, 2.5), (15.0, 16.0)),
+            };
+        SizeTable {
+            diameter,
+            socket_head_diameter,
+            socket_key,
+            hex_width,
+            hex_head_height,
+            nut_height,
+            washer,
+            insert,
+        }
+    }
+}
+
+/// Head style of a bolt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoltHead {
+    /// Cylindrical head with a hex socket (ISO 4762).
+    SocketCap,
+    /// Hex head (ISO 4017).
+    Hex,
+}
+
+impl BoltHead {
+    pub fn label(self) -> &'static str {
+        match self {
+            BoltHead::SocketCap => "Socket Head",
+            BoltHead::Hex => "Hex Head",
+        }
+    }
+}
+
+/// A generated hardware part. Parts stand upright on the XY plane, centered
+/// on the Z axis; bolts have their head on top.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HardwarePart {
+    Bolt {
+        size: MetricSize,
+        /// Length under the head (mm).
+        length: f32,
+        head: BoltHead,
+    },
+    Nut {
+        size: MetricSize,
+    },
+    Washer {
+        size: MetricSize,
+    },
+    /// Cylindrical boss with a blind hole sized for a heat-set insert.
+    InsertBoss {
+        size: MetricSize,
+        /// Boss height (mm); at least the hole depth.
+        height: f32,
+        /// Wall thickness around the hole (mm).
+        wall: f32,
+    },
+}
+
+impl HardwarePart {
+    pub const KINDS: [&'static str; 4] = ["Bolt", "Nut", "Washer", "Insert Boss"];
+
+    /// Default part of the kind at `index` in [`HardwarePart::KINDS`], keeping `size`.
+    pub fn of_kind(index: usize, size: MetricSize) -> Self {
+        match index {
+            0 => HardwarePart::Bolt {
+                size,
+                length: (size.diameter() * 4.0).round(),
+                head: BoltHead::SocketCap,
+            },
+            1 => HardwarePart::Nut { size },
+            2 => HardwarePart::Washer { size },
+            _ => {
+                let (hole, depth) = size.table().insert;
+                HardwarePart::InsertBoss {
+                    size,
+                    height: (depth + 2.0).round(),
+                    wall: (hole * 0.5).max(1.6),
+                }
+            }
+        }
+    }
+
+    /// Index of the part's kind in [`HardwarePart::KINDS`].
+    pub fn kind(&self) -> usize {
+        match self {
+            HardwarePart::Bolt { .. } => 0,
+            HardwarePart::Nut { .. } => 1,
+            HardwarePart::Washer { .. } => 2,
+            HardwarePart::InsertBoss { .. } => 3,
+        }
+    }
+
+    pub fn size(&self) -> MetricSize {
+        match *self {
+            HardwarePart::Bolt { size, .. }
+            | HardwarePart::Nut { size }
+            | HardwarePart::Washer { size }
+            | HardwarePart::InsertBoss { size, .. } => size,
+        }
+    }
+
+    pub fn set_size(&mut self, new_size: MetricSize) {
+        match self {
+            HardwarePart::Bolt { size, .. }
+            | HardwarePart::Nut { size }
+            | HardwarePart::Washer { size }
+            | HardwarePart::InsertBoss { size, .. } => *size = new_size,
+        }
+    }
+
+    /// Name for the body and feature, e.g. `M3×12 Socket Head Bolt`.
+    pub fn name(&self) -> String {
+        match self {
+            HardwarePart::Bolt { size, length, head } => {
+                format!("{}×{length} {} Bolt", size.label(), head.label())
+            }
+            HardwarePart::Nut { size } => format!("{} Hex Nut", size.label()),
+            HardwarePart::Washer { size } => format!("{} Washer", size.label()),
+            HardwarePart::InsertBoss { size, .. } => format!("{} Insert Boss", size.label()),
+        }
+    }
+
+    /// Closed triangle mesh of the part.
+    pub fn generate(&self) -> IndexedMesh {
+        let table = self.size().table();
+        let radius = table.diameter / 2.0;
+        let profile = match *self {
+            HardwarePart::Bolt { length, head, .. } => {
+                let length = length.max(1.0);
+                let mut profile = vec![
+                    (Ring::Axis, 0.0),
+                    (Ring::Round(radius), 0.0),
+                    (Ring::Round(radius), length),
+                ];
+                match head {
+                    BoltHead::SocketCap => {
+                        let top = length + table.diameter;
+                        let socket = Ring::Hex(table.socket_key / 2.0);
+                        let depth = table.diameter * 0.5;
+                        profile.extend([
+                            (Ring::Round(table.socket_head_diameter / 2.0), length),
+                            (Ring::Round(table.socket_head_diameter / 2.0), top),
+                            (socket, top),
+                            (socket, top - depth),
+                            (Ring::Axis, top - depth),
+                        ]);
+                    }
+                    BoltHead::Hex => {
+                        let top = length + table.hex_head_height;
+                        let hex = Ring::Hex(table.hex_width / 2.0);
+                        profile.extend([(hex, length), (hex, top), (Ring::Axis, top)]);
+                    }
+                }
+                profile
+            }
+            HardwarePart::Nut { .. } => {
+                let hex = Ring::Hex(table.hex_width / 2.0);
+                let height = table.nut_height;
+                vec![
+                    (Ring::Round(radius), 0.0),
+                    (hex, 0.0),
+                    (hex, height),
+                    (Ring::Round(radius), height),
+                    (Ring::Round(radius), 0.0),
+                ]
+            }
+            HardwarePart::Washer { .. } => {
+                let (inner, outer, thickness) = table.washer;
+                vec![
+                    (Ring::Round(inner / 2.0), 0.0),
+                    (Ring::Round(outer / 2.0), 0.0),
+                    (Ring::Round(outer / 2.0), thickness),
+                    (Ring::Round(inner / 2.0), thickness),
+                    (Ring::Round(inner / 2.0), 0.0),
+                ]
+            }
+            HardwarePart::InsertBoss { height, wall, .. } => {
+                let (hole, depth) = table.insert;
+                let height = height.max(depth + 0.4);
+                let outer = hole / 2.0 + wall.max(0.4);
+                vec![
+                    (Ring::Axis, 0.0),
+                    (Ring::Round(outer), 0.0),
+                    (Ring::Round(outer), height),
+                    (Ring::Round(hole / 2.0), height),
+                    (Ring::Round(hole / 2.0), height - depth),
+                    (Ring::Axis, height - depth),
+                ]
+            }
+        };
+        revolve(&profile)
+    }
+}
+
+/// Cross-section of a part at one height.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Ring {
+    /// A single point on the axis (closes the part).
+    Axis,
+    Round(f32),
+    /// Hexagon with the given distance from the center to its flats.
+    Hex(f32),
+    /// Axis-aligned square with the given distance from the center to its sides.
+    Square(f32),
+}
+
+impl Ring {
+    /// Distance from the axis in direction `angle`.
+    fn radius(self, angle: f32) -> f32 {
+        match self {
+            Ring::Axis => 0.0,
+            Ring::Round(radius) => radius,
+            Ring::Hex(apothem) => {
+                // Corners at multiples of 60°, flats halfway between.
+                let sector = TAU / 6.0;
+                let offset = angle.rem_euclid(sector) - sector / 2.0;
+                apothem / offset.cos()
+            }
+            Ring::Square(half) => half / angle.cos().abs().max(angle.sin().abs()),
+        }
+    }
+}
+
+/// Sweep a profile of cross-sections around the Z axis. Consecutive rings are
+/// joined by a band of triangles; a profile that ends where it started forms
+/// a closed loop (washers, nuts), otherwise it starts and ends on the axis.
+pub(crate) fn revolve(profile: &[(Ring, f32)]) -> IndexedMesh {
+    let mut mesh = IndexedMesh::default();
+    let closed = profile.len() > 2 && !matches!(profile[0].0, Ring::Axis);
+    let count = if closed {
+        profile.len() - 1
+    } else {
+        profile.len()
+    };
+    let rings: Vec<Vec<u32>> = profile[..count]
+        .iter()
+        .map(|&(ring, z)| {
+            if let Ring::Axis = ring {
+                mesh.positions.push(Vec3::new(0.0, 0.0, z));
+                return vec![(mesh.positions.len() - 1) as u32; SEGMENTS];
+            }
+            (0..SEGMENTS)
+                .map(|i| {
+                    let angle = TAU * i as f32 / SEGMENTS as f32;
+                    let radius = ring.radius(angle);
+                    mesh.positions
+                        .push(Vec3::new(radius * angle.cos(), radius * angle.sin(), z));
+                    (mesh.positions.len() - 1) as u32
+                })
+                .collect()
+        })
+        .collect();
+
+    let bands = if closed { count } else { count - 1 };
+    for band in 0..bands {
+        let (a, b) = (&rings[band], &rings[(band + 1) % count]);
+        for i in 0..SEGMENTS {
+            let j = (i + 1) % SEGMENTS;
+            for triangle in [[a[i], a[j], b[j]], [a[i], b[j], b[i]]] {
+                if triangle[0] != triangle[1]
+                    && triangle[1] != triangle[2]
+                    && triangle[2] != triangle[0]
+                {
+                    mesh.triangles.push(triangle);
+                }
+            }
+        }
+    }
+
+    // The winding depends on which way the profile runs; make normals point out.
+    let signed_volume: f32 = mesh
+        .triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|i| mesh.positions[i as usize]);
+            a.dot(b.cross(c))
+        })
+        .sum();
+    if signed_volume < 0.0 {
+        mesh::flip_normals(&mut mesh);
+    }
+    mesh
+}
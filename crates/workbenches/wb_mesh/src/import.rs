@@ -0,0 +1,113 @@
+//! Reading a mesh file for import, whichever supported format it is in.
+
+use std::path::Path;
+
+use axes::AxisSystem;
+use kernel_api::{ExchangeOptions, TriMesh};
+use thiserror::Error;
+
+use crate::obj::{self, ObjError};
+use crate::ply::{self, PlyError};
+use crate::stl::{self, StlError};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error(transparent)]
+    Stl(#[from] StlError),
+    #[error(transparent)]
+    Obj(#[from] ObjError),
+    #[error(transparent)]
+    Ply(#[from] PlyError),
+    #[error("not an STL, OBJ, or PLY file")]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    Stl,
+    Obj,
+    Ply,
+}
+
+impl MeshFormat {
+    pub const ALL: [MeshFormat; 3] = [MeshFormat::Stl, MeshFormat::Obj, MeshFormat::Ply];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MeshFormat::Stl => "stl",
+            MeshFormat::Obj => "obj",
+            MeshFormat::Ply => "ply",
+        }
+    }
+
+    /// Format of a file, from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+}
+
+/// Meshes whose file origin ends up farther than this from the document
+/// origin (in millimetres) are imported into a body placed there, rather
+/// than into single-precision coordinates that far out.
+const FAR_FROM_ORIGIN: f64 = 10_000.0;
+
+/// Mesh read from a file: positions relative to `origin`, a point in the
+/// file's coordinates near the mesh. Formats that store coordinates in
+/// double precision are read relative to it, so files far from their origin
+/// (survey or site data) keep their detail.
+#[derive(Debug, Clone, Default)]
+pub struct FileMesh {
+    pub mesh: TriMesh,
+    pub origin: [f64; 3],
+}
+
+impl From<TriMesh> for FileMesh {
+    fn from(mesh: TriMesh) -> Self {
+        Self {
+            mesh,
+            origin: [0.0; 3],
+        }
+    }
+}
+
+impl FileMesh {
+    /// Map the mesh into document coordinates with `options`. Returns the
+    /// mesh relative to where its body goes: the document origin, unless the
+    /// file's origin lands far from it.
+    pub fn into_document(
+        mut self,
+        options: &ExchangeOptions,
+        document_axes: AxisSystem,
+    ) -> (TriMesh, [f64; 3]) {
+        options.import_mesh(&mut self.mesh, document_axes);
+        let origin = options.import_offset(self.origin, document_axes);
+        if origin.iter().all(|value| value.abs() < FAR_FROM_ORIGIN) {
+            self.mesh.to_frame(origin.map(|value| -value));
+            return (self.mesh, [0.0; 3]);
+        }
+        (self.mesh, origin)
+    }
+}
+
+/// Where a file reader puts the origin of its mesh: the first vertex,
+/// rounded to whole file units.
+pub(crate) fn file_origin(first: [f64; 3]) -> [f64; 3] {
+    first.map(f64::round)
+}
+
+/// Read a mesh file in file coordinates, calling `progress` with the
+/// fraction done (0 to 1).
+pub fn read_mesh_with_progress(
+    path: &Path,
+    progress: impl FnMut(f32),
+) -> Result<FileMesh, ImportError> {
+    Ok(match MeshFormat::from_path(path) {
+        Some(MeshFormat::Stl) => stl::read_stl_with_progress(path, progress)?.into(),
+        Some(MeshFormat::Obj) => obj::read_obj_with_progress(path, progress)?,
+        Some(MeshFormat::Ply) => ply::read_ply_with_progress(path, progress)?,
+        None => return Err(ImportError::Unsupported),
+    })
+}
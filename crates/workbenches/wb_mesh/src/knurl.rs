@@ -0,0 +1,402 @@
+//! Knurled grip textures: a raised diamond or straight knurl around a round
+//! face, or a textured pad on a flat face. The texture is a closed solid
+//! added to the body's mesh, its grooves level with the face and its ridges
+//! standing out of it by the knurl depth.
+
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+
+use glam::{DMat3, DVec3, Quat, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::design::distance_to_triangle;
+use crate::mesh::{self, edges_of, IndexedMesh};
+
+/// Samples per pitch of the texture, enough to hit every ridge and groove.
+const SAMPLES_PER_PITCH: usize = 4;
+/// How far (mm) a pad reaches below the face, so it overlaps the body.
+const PAD_EMBED: f32 = 0.2;
+/// Largest |normal · axis| of a triangle on the side of a cylinder.
+const SIDE_TOLERANCE: f32 = 0.02;
+/// Vertices of a round face may be this far off the fitted circle, relative
+/// to its radius.
+const ROUNDNESS_TOLERANCE: f32 = 0.05;
+
+/// Texture of a knurl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnurlPattern {
+    /// Pyramids between two crossing sets of grooves.
+    Diamond,
+    /// Ridges along the cylinder axis (or the pad's length).
+    Straight,
+}
+
+impl KnurlPattern {
+    pub const ALL: [KnurlPattern; 2] = [KnurlPattern::Diamond, KnurlPattern::Straight];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KnurlPattern::Diamond => "Diamond",
+            KnurlPattern::Straight => "Straight",
+        }
+    }
+}
+
+/// Face a knurl covers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KnurlShape {
+    /// The whole round face that was clicked, end to end.
+    Cylinder,
+    /// A rectangle centered on the clicked point of a flat face (mm).
+    Pad { width: f32, length: f32 },
+}
+
+/// A knurl texture.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Knurl {
+    pub pattern: KnurlPattern,
+    pub shape: KnurlShape,
+    /// Distance between ridges (mm); rounded so a whole number fits around a
+    /// cylinder.
+    pub pitch: f32,
+    /// Height of the ridges above the face (mm).
+    pub depth: f32,
+}
+
+impl Default for Knurl {
+    fn default() -> Self {
+        Self {
+            pattern: KnurlPattern::Diamond,
+            shape: KnurlShape::Cylinder,
+            pitch: 2.0,
+            depth: 0.6,
+        }
+    }
+}
+
+impl Knurl {
+    /// Name for the feature, e.g. `Diamond Knurl`.
+    pub fn name(&self) -> String {
+        format!("{} Knurl", self.pattern.label())
+    }
+
+    /// `mesh` with the knurl added on the face at `origin` with outward
+    /// `normal`; a pad is turned by `rotation` degrees about the normal. The
+    /// mesh is returned unchanged if a cylinder knurl was not placed on a
+    /// round face.
+    pub fn apply(
+        &self,
+        mesh: &IndexedMesh,
+        origin: Vec3,
+        normal: Vec3,
+        rotation: f32,
+    ) -> IndexedMesh {
+        let texture = match self.shape {
+            KnurlShape::Cylinder => match cylinder_at(mesh, origin) {
+                Some(cylinder) => self.sleeve(&cylinder),
+                None => return mesh.clone(),
+            },
+            KnurlShape::Pad { width, length } => {
+                let normal = normal.try_normalize().unwrap_or(Vec3::Z);
+                let orientation = Quat::from_rotation_arc(Vec3::Z, normal)
+                    * Quat::from_rotation_z(rotation.to_radians());
+                let mut pad = self.pad(width.max(self.pitch()), length.max(self.pitch()));
+                for position in &mut pad.positions {
+                    *position = origin + orientation * *position;
+                }
+                pad
+            }
+        };
+        let mut result = mesh.clone();
+        mesh::merge(&mut result, &texture);
+        result
+    }
+
+    fn pitch(&self) -> f32 {
+        self.pitch.max(0.2)
+    }
+
+    /// Height of the texture, 0 (groove) to 1 (ridge), at `u` across and `v`
+    /// along the ridges, with `pitch_u`, `pitch_v` between them.
+    fn height(&self, u: f32, v: f32, pitch_u: f32, pitch_v: f32) -> f32 {
+        let triangle = |x: f32| 1.0 - 2.0 * (x - x.floor() - 0.5).abs();
+        match self.pattern {
+            KnurlPattern::Straight => triangle(u / pitch_u),
+            KnurlPattern::Diamond => {
+                let (a, b) = (u / pitch_u, v / pitch_v);
+                triangle(a + b).min(triangle(a - b))
+            }
+        }
+    }
+
+    /// Solid filling `cylinder` with the texture on its round face.
+    fn sleeve(&self, cylinder: &Cylinder) -> IndexedMesh {
+        let count = (TAU * cylinder.radius / self.pitch()).round().max(3.0);
+        let pitch_u = TAU * cylinder.radius / count;
+        let around = count as usize * SAMPLES_PER_PITCH;
+        let along = (cylinder.length / (self.pitch() / SAMPLES_PER_PITCH as f32))
+            .ceil()
+            .max(1.0) as usize;
+        let step = cylinder.length / along as f32;
+        let (e1, e2) = cylinder.axis.any_orthonormal_pair();
+
+        let mut result = IndexedMesh::default();
+        for j in 0..=along {
+            let v = j as f32 * step;
+            for i in 0..around {
+                let angle = TAU * i as f32 / around as f32;
+                let u = cylinder.radius * angle;
+                let radius = cylinder.radius
+                    + self.depth.max(0.0) * self.height(u, v, pitch_u, self.pitch());
+                let radial = e1 * angle.cos() + e2 * angle.sin();
+                result
+                    .positions
+                    .push(cylinder.base + cylinder.axis * v + radial * radius);
+            }
+        }
+        let index = |i: usize, j: usize| (j * around + i % around) as u32;
+        for j in 0..along {
+            for i in 0..around {
+                let (a, b) = (index(i, j), index(i + 1, j));
+                let (c, d) = (index(i + 1, j + 1), index(i, j + 1));
+                result.triangles.push([a, b, c]);
+                result.triangles.push([a, c, d]);
+            }
+        }
+        let bottom = result.positions.len() as u32;
+        result.positions.push(cylinder.base);
+        let top = bottom + 1;
+        result
+            .positions
+            .push(cylinder.base + cylinder.axis * cylinder.length);
+        for i in 0..around {
+            result
+                .triangles
+                .push([bottom, index(i + 1, 0), index(i, 0)]);
+            result
+                .triangles
+                .push([top, index(i, along), index(i + 1, along)]);
+        }
+        result
+    }
+
+    /// Textured rectangle standing on the XY plane, centered on the origin,
+    /// its ridges along Y.
+    fn pad(&self, width: f32, length: f32) -> IndexedMesh {
+        let spacing = self.pitch() / SAMPLES_PER_PITCH as f32;
+        let columns = (width / spacing).ceil() as usize;
+        let rows = (length / spacing).ceil() as usize;
+        let corner = Vec2::new(-width / 2.0, -length / 2.0);
+        let cell = Vec2::new(width / columns as f32, length / rows as f32);
+        let stride = (columns + 1) * (rows + 1);
+
+        let mut result = IndexedMesh::default();
+        for floor in [false, true] {
+            for j in 0..=rows {
+                for i in 0..=columns {
+                    let p = corner + cell * Vec2::new(i as f32, j as f32);
+                    let z = if floor {
+                        -PAD_EMBED
+                    } else {
+                        self.depth.max(0.0) * self.height(p.x, p.y, self.pitch(), self.pitch())
+                    };
+                    result.positions.push(p.extend(z));
+                }
+            }
+        }
+        let index = |i: usize, j: usize| (j * (columns + 1) + i) as u32;
+        let below = |vertex: u32| vertex + stride as u32;
+        for j in 0..rows {
+            for i in 0..columns {
+                let (a, b) = (index(i, j), index(i + 1, j));
+                let (c, d) = (index(i + 1, j + 1), index(i, j + 1));
+                result.triangles.push([a, b, c]);
+                result.triangles.push([a, c, d]);
+                result.triangles.push([below(a), below(c), below(b)]);
+                result.triangles.push([below(a), below(d), below(c)]);
+            }
+        }
+        // Walls around the border, counterclockwise seen from above.
+        let border: Vec<u32> = (0..columns)
+            .map(|i| index(i, 0))
+            .chain((0..rows).map(|j| index(columns, j)))
+            .chain((1..=columns).rev().map(|i| index(i, rows)))
+            .chain((1..=rows).rev().map(|j| index(0, j)))
+            .collect();
+        for (k, &p) in border.iter().enumerate() {
+            let q = border[(k + 1) % border.len()];
+            result.triangles.push([below(p), below(q), q]);
+            result.triangles.push([below(p), q, p]);
+        }
+        result
+    }
+}
+
+/// Round face of a mesh, as a cylinder running from `base` along `axis`.
+struct Cylinder {
+    base: Vec3,
+    axis: Vec3,
+    radius: f32,
+    length: f32,
+}
+
+/// The cylinder whose outer round face holds the triangle of `mesh` nearest
+/// to `point`. None if that triangle is not on a full round face.
+fn cylinder_at(mesh: &IndexedMesh, point: Vec3) -> Option<Cylinder> {
+    let normals: Vec<Option<Vec3>> = mesh
+        .triangles
+        .iter()
+        .map(|triangle| mesh.face_normal(triangle).try_normalize())
+        .collect();
+    let seed = mesh
+        .triangles
+        .iter()
+        .enumerate()
+        .map(|(t, triangle)| {
+            let [a, b, c] = triangle.map(|i| mesh.positions[i as usize]);
+            (distance_to_triangle(point, a, b, c), t)
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))?
+        .1;
+    let seed_normal = normals[seed]?;
+
+    let mut by_edge: HashMap<(u32, u32), usize> = HashMap::new();
+    for (t, triangle) in mesh.triangles.iter().enumerate() {
+        for edge in edges_of(triangle) {
+            by_edge.insert(edge, t);
+        }
+    }
+    let neighbors = |t: usize| {
+        edges_of(&mesh.triangles[t])
+            .into_iter()
+            .filter_map(|(a, b)| by_edge.get(&(b, a)).copied())
+    };
+
+    // The axis is across the normals of two neighboring facets; walk the
+    // seed's flat facet until one bends away from it.
+    let mut facet = vec![seed];
+    let mut seen = HashSet::from([seed]);
+    let mut axis = None;
+    let mut next = 0;
+    while next < facet.len() && axis.is_none() {
+        let t = facet[next];
+        next += 1;
+        for neighbor in neighbors(t) {
+            let Some(normal) = normals[neighbor] else {
+                continue;
+            };
+            let cos = normal.dot(seed_normal);
+            if cos > 1.0 - 1e-5 {
+                if seen.insert(neighbor) {
+                    facet.push(neighbor);
+                }
+            } else if cos > 0.5 {
+                axis = seed_normal.cross(normal).try_normalize();
+                break;
+            }
+        }
+    }
+    let axis = axis?;
+
+    // The round face: facets around the axis, each bending only a little
+    // from the one before.
+    let mut side = vec![seed];
+    let mut seen = HashSet::from([seed]);
+    let mut next = 0;
+    while next < side.len() {
+        let t = side[next];
+        next += 1;
+        let Some(normal) = normals[t] else {
+            continue;
+        };
+        for neighbor in neighbors(t) {
+            let Some(other) = normals[neighbor] else {
+                continue;
+            };
+            if other.dot(axis).abs() < SIDE_TOLERANCE
+                && other.dot(normal) > 0.5
+                && seen.insert(neighbor)
+            {
+                side.push(neighbor);
+            }
+        }
+    }
+    let vertices: Vec<Vec3> = side
+        .iter()
+        .flat_map(|&t| mesh.triangles[t])
+        .collect::<HashSet<u32>>()
+        .into_iter()
+        .map(|v| mesh.positions[v as usize])
+        .collect();
+
+    let (e1, e2) = axis.any_orthonormal_pair();
+    let flat: Vec<Vec2> = vertices
+        .iter()
+        .map(|p| Vec2::new(p.dot(e1), p.dot(e2)))
+        .collect();
+    let (center, radius) = fit_circle(&flat)?;
+    if flat
+        .iter()
+        .any(|p| (p.distance(center) - radius).abs() > ROUNDNESS_TOLERANCE * radius)
+    {
+        return None;
+    }
+    // The face must go all the way around, and face away from the axis.
+    let mut angles: Vec<f32> = flat.iter().map(|p| (*p - center).to_angle()).collect();
+    angles.sort_by(f32::total_cmp);
+    let widest_gap = angles
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .fold(TAU - (angles.last()? - angles.first()?), f32::max);
+    let seed_center = mesh.triangles[seed]
+        .iter()
+        .map(|&v| mesh.positions[v as usize])
+        .sum::<Vec3>()
+        / 3.0;
+    let outward = Vec2::new(seed_center.dot(e1), seed_center.dot(e2)) - center;
+    if widest_gap > TAU / 4.0
+        || outward.dot(Vec2::new(seed_normal.dot(e1), seed_normal.dot(e2))) <= 0.0
+    {
+        return None;
+    }
+
+    let heights = vertices.iter().map(|p| p.dot(axis));
+    let low = heights.clone().fold(f32::INFINITY, f32::min);
+    let high = heights.fold(f32::NEG_INFINITY, f32::max);
+    Some(Cylinder {
+        base: e1 * center.x + e2 * center.y + axis * low,
+        axis,
+        radius,
+        length: high - low,
+    })
+    .filter(|cylinder| cylinder.length > 0.0)
+}
+
+/// Least-squares circle through `points`: center and radius.
+fn fit_circle(points: &[Vec2]) -> Option<(Vec2, f32)> {
+    if points.len() < 3 {
+        return None;
+    }
+    // Solve x² + y² + Dx + Ey + F = 0 around the centroid, for precision.
+    let mean = points.iter().sum::<Vec2>() / points.len() as f32;
+    let mut normal = DMat3::ZERO;
+    let mut rhs = DVec3::ZERO;
+    for p in points {
+        let (x, y) = (f64::from(p.x - mean.x), f64::from(p.y - mean.y));
+        let row = DVec3::new(x, y, 1.0);
+        normal += DMat3::from_cols(row * x, row * y, row);
+        rhs -= row * (x * x + y * y);
+    }
+    if normal.determinant().abs() < 1e-12 {
+        return None;
+    }
+    let [d, e, f] = (normal.inverse() * rhs).to_array();
+    let radius = (d * d / 4.0 + e * e / 4.0 - f).sqrt();
+    let center = mean + Vec2::new((-d / 2.0) as f32, (-e / 2.0) as f32);
+    radius
+        .is_finite()
+        .then_some((center, radius as f32))
+        .filter(|(_, radius)| *radius > 0.0)
+}
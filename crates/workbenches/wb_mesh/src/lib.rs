@@ -0,0 +1,1255 @@
+pub mod amf;
+pub mod batch;
+pub mod cache;
+pub mod calibration;
+pub mod clipboard;
+pub mod cut;
+pub mod decimate;
+pub mod design;
+mod exchange;
+pub mod feature;
+pub mod hardware;
+pub mod import;
+pub mod knurl;
+pub mod mesh;
+pub mod obj;
+#[cfg(feature = "egui")]
+mod panel;
+pub mod ply;
+pub mod shell;
+pub mod stl;
+pub mod text;
+pub mod transform;
+pub mod usd;
+
+use std::path::{Path, PathBuf};
+
+use core_document::{
+    Body, BodyDisplay, BodyId, BodyMesh, Document, FeatureId, Gizmo, GizmoDrag, GizmoDragPhase,
+    GizmoKind, JobId, JobQueue, KeyCode, ScreenSpaceOverlay, TaskAction, TaskPanel, ToolDescriptor,
+    Workbench, WorkbenchContext, WorkbenchDescriptor, WorkbenchFeature, WorkbenchRuntimeContext,
+};
+use glam::Vec3;
+use kernel_api::{ExchangeFormat, ExchangeOptions, TriMesh};
+
+use amf::AmfBody;
+use batch::BatchExport;
+pub use cache::MeshCache;
+use calibration::CalibrationPart;
+use clipboard::ClipboardFormat;
+use design::DesignElement;
+use exchange::{ExchangeKind, PendingExchange};
+pub use feature::{MeshFeature, MeshOperation};
+use hardware::{HardwarePart, MetricSize};
+use import::{FileMesh, ImportError, MeshFormat};
+use knurl::Knurl;
+use mesh::IndexedMesh;
+#[cfg(feature = "egui")]
+use mesh::MeshStats;
+use text::TextLabel;
+use transform::RigidTransform;
+use usd::{UsdBody, UsdFormat};
+
+/// Workbench identifier (also the workbench id of mesh features).
+pub const WORKBENCH_ID: &str = "wb.mesh";
+
+/// `FileOpenRequest` id used to pick an STL file.
+const IMPORT_REQUEST: &str = "mesh.import";
+
+/// Id of the gizmo moving the selected body.
+const TRANSFORM_GIZMO: &str = "mesh.transform";
+
+/// Color of exported bodies without a display state color, as the viewport
+/// draws them.
+const BODY_COLOR: [f32; 3] = [0.7, 0.72, 0.75];
+
+/// Screen color of the plane cut preview.
+const CUT_PLANE_COLOR: [f32; 3] = [0.95, 0.55, 0.2];
+
+/// Mesh workbench: imports STL files as bodies and edits them with mesh
+/// operations recorded in the body's feature history.
+pub struct MeshWorkbench {
+    /// Body edited by the operations.
+    selected_body: Option<BodyId>,
+    decimate_ratio: f32,
+    edge_length: f32,
+    smooth_iterations: u32,
+    smooth_factor: f32,
+    /// World axis (0 = X, 1 = Y, 2 = Z) the cut plane is perpendicular to.
+    cut_axis: usize,
+    /// Position of the cut plane along its axis (mm).
+    cut_position: f32,
+    /// Keep the part below the plane instead of the part above it.
+    cut_flip: bool,
+    /// Close the opening left by the cut.
+    cut_cap: bool,
+    /// Draw the cut plane over the selected body.
+    show_cut_plane: bool,
+    /// Body whose mesh is added by "Merge Meshes".
+    merge_source: Option<BodyId>,
+    /// Stats of the selected body, kept while its triangle count is unchanged.
+    #[cfg(feature = "egui")]
+    stats: Option<(BodyId, usize, MeshStats)>,
+    /// Parameters of the next hardware part to insert.
+    hardware: HardwarePart,
+    /// Parameters of the next calibration print to insert.
+    calibration: CalibrationPart,
+    /// Parameters of the next design element to place.
+    design_element: DesignElement,
+    /// Turn of the next design element about the face normal (degrees).
+    design_rotation: f32,
+    /// Text and style of the next label to place.
+    text: TextLabel,
+    /// Turn of the next label's baseline about the face normal (degrees).
+    text_rotation: f32,
+    /// Pattern and size of the next knurl to place.
+    knurl: Knurl,
+    /// Turn of the next knurl pad about the face normal (degrees).
+    knurl_rotation: f32,
+    /// Mesh files being read on worker threads.
+    imports: Vec<PendingImport>,
+    /// Import or export whose units, scale, and axes are being confirmed.
+    exchange: Option<PendingExchange>,
+    /// Format and file names of the last "Export All Bodies".
+    batch: BatchExport,
+    /// Handles shown on the selected body to move or turn it (None hides them).
+    transform_handles: Option<GizmoKind>,
+    /// Transform step being dragged with the handles.
+    transform_drag: Option<TransformDrag>,
+}
+
+/// A drag of the transform handles, applied to the `Transform` step at the
+/// tip of the body's history.
+struct TransformDrag {
+    feature: FeatureId,
+    /// The step before the drag.
+    start: RigidTransform,
+    /// Whether the drag added the step (removed again if cancelled).
+    added: bool,
+    /// Where the gizmo was when grabbed; turns are about this point.
+    origin: Vec3,
+}
+
+/// A mesh file import running in the background.
+struct PendingImport {
+    /// Queue of the document the import was started from.
+    jobs: JobQueue,
+    job: JobId,
+    path: PathBuf,
+    stage: ImportStage,
+}
+
+enum ImportStage {
+    /// Reading the file, with the format's conventions from the settings;
+    /// the options task opens once it is read.
+    Reading(ExchangeOptions),
+    /// Converting the read mesh with the confirmed options into a feature.
+    Converting,
+    /// Reading the file again for "Update from Source", to replace the mesh
+    /// of this import feature.
+    Updating(FeatureId),
+}
+
+/// Result of a reading job: the mesh in the file's coordinates.
+type ReadResult = Result<FileMesh, ImportError>;
+
+/// Result of a converting job: the feature to add, its triangle count, and
+/// the position of the body it goes in.
+type ImportResult = (MeshFeature, usize, [f64; 3]);
+
+/// Result of an updating job: the import operation with the new mesh.
+type UpdateResult = Result<MeshOperation, ImportError>;
+
+impl Default for MeshWorkbench {
+    fn default() -> Self {
+        Self {
+            selected_body: None,
+            decimate_ratio: feature::DEFAULT_DECIMATE_RATIO,
+            edge_length: feature::DEFAULT_EDGE_LENGTH,
+            smooth_iterations: feature::DEFAULT_SMOOTH_ITERATIONS,
+            smooth_factor: feature::DEFAULT_SMOOTH_FACTOR,
+            cut_axis: 2,
+            cut_position: 0.0,
+            cut_flip: false,
+            cut_cap: true,
+            show_cut_plane: false,
+            merge_source: None,
+            #[cfg(feature = "egui")]
+            stats: None,
+            hardware: HardwarePart::of_kind(0, MetricSize::M3),
+            calibration: CalibrationPart::of_kind(0),
+            design_element: DesignElement::of_kind(0),
+            design_rotation: 0.0,
+            text: TextLabel::default(),
+            text_rotation: 0.0,
+            knurl: Knurl::default(),
+            knurl_rotation: 0.0,
+            imports: Vec::new(),
+            exchange: None,
+            batch: BatchExport::default(),
+            transform_handles: None,
+            transform_drag: None,
+        }
+    }
+}
+
+impl Workbench for MeshWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Mesh",
+            "Import STL, OBJ, and PLY files or generate standard hardware and calibration prints, and repair, simplify or cut mesh bodies.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new_action(
+            "mesh.import",
+            "Import Mesh…",
+            Some("file"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "mesh.update_source",
+            "Update from Source",
+            Some("file"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "mesh.hardware",
+            "Insert Hardware",
+            Some("file"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "mesh.calibration",
+            "Insert Calibration Part",
+            Some("file"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            amf::EXPORT_REQUEST,
+            "Export AMF…",
+            Some("file"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            batch::EXPORT_REQUEST,
+            "Export All Bodies…",
+            Some("file"),
+        ));
+        for format in UsdFormat::ALL {
+            context.register_tool(ToolDescriptor::new_action(
+                format.request_id(),
+                format!("Export {}…", format.label()),
+                Some("file"),
+            ));
+        }
+        for (id, label) in [
+            ("mesh.decimate", "Decimate"),
+            ("mesh.remesh", "Remesh"),
+            ("mesh.smooth", "Smooth"),
+            ("mesh.cut", "Plane Cut"),
+            ("mesh.flip", "Flip Normals"),
+            ("mesh.merge", "Merge Meshes"),
+        ] {
+            context.register_tool(ToolDescriptor::new_action(id, label, Some("operations")));
+        }
+        // P cycles through the placement tools.
+        for (id, label) in [
+            ("mesh.place_element", "Place Design Element"),
+            ("mesh.place_text", "Place Text"),
+            ("mesh.place_knurl", "Place Knurl"),
+        ] {
+            context.register_tool(
+                ToolDescriptor::new(id, label, Some("operations")).with_shortcut(KeyCode::P),
+            );
+        }
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Mesh workbench activated");
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Mesh workbench deactivated");
+    }
+
+    fn on_frame(&mut self, _dt: f32, ctx: &mut WorkbenchRuntimeContext) {
+        self.finish_imports(ctx);
+    }
+
+    fn on_input(
+        &mut self,
+        event: &core_document::WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> core_document::InputResult {
+        // "Place Design Element", "Place Text", and "Place Knurl" place one
+        // on the face under the cursor for each click on a mesh body (and
+        // stay active if tools are sticky).
+        if let Some(tool @ ("mesh.place_element" | "mesh.place_text" | "mesh.place_knurl")) =
+            active_tool
+        {
+            let core_document::WorkbenchInputEvent::MousePress {
+                button: core_document::MouseButton::Left,
+                ..
+            } = event
+            else {
+                return core_document::InputResult::ignored();
+            };
+            let placed = match tool {
+                "mesh.place_element" => self.place_design_element(ctx),
+                "mesh.place_text" => self.place_text(ctx),
+                _ => self.place_knurl(ctx),
+            };
+            return if placed {
+                core_document::InputResult::finished()
+            } else {
+                core_document::InputResult::consumed()
+            };
+        }
+
+        let operation = match active_tool {
+            Some("mesh.import") => {
+                request_mesh_file(ctx);
+                return core_document::InputResult::consumed();
+            }
+            Some("mesh.update_source") => {
+                let features = selected_imports(ctx);
+                if features.is_empty() {
+                    ctx.log_info("No imported meshes to update");
+                }
+                self.update_from_source(ctx, &features);
+                return core_document::InputResult::consumed();
+            }
+            Some("mesh.hardware") => {
+                self.insert_hardware(ctx);
+                return core_document::InputResult::consumed();
+            }
+            Some("mesh.calibration") => {
+                self.insert_calibration(ctx);
+                return core_document::InputResult::consumed();
+            }
+            Some(batch::EXPORT_REQUEST) => {
+                ctx.file_open_request = Some(core_document::FileOpenRequest {
+                    id: batch::EXPORT_REQUEST.to_string(),
+                    filter_name: String::new(),
+                    extensions: Vec::new(),
+                    save_name: None,
+                    folder: true,
+                });
+                return core_document::InputResult::consumed();
+            }
+            Some(amf::EXPORT_REQUEST) => {
+                request_export_file(ctx, amf::EXPORT_REQUEST, "AMF file", "amf");
+                return core_document::InputResult::consumed();
+            }
+            Some(tool) if UsdFormat::from_request_id(tool).is_some() => {
+                if let Some(format) = UsdFormat::from_request_id(tool) {
+                    request_export_file(
+                        ctx,
+                        format.request_id(),
+                        &format!("{} scene", format.label()),
+                        format.extension(),
+                    );
+                }
+                return core_document::InputResult::consumed();
+            }
+            Some("mesh.decimate") => MeshOperation::Decimate {
+                ratio: self.decimate_ratio,
+            },
+            Some("mesh.remesh") => MeshOperation::Remesh {
+                edge_length: self.edge_length,
+            },
+            Some("mesh.smooth") => MeshOperation::Smooth {
+                iterations: self.smooth_iterations,
+                factor: self.smooth_factor,
+            },
+            Some("mesh.cut") => self.cut_operation(),
+            Some("mesh.flip") => MeshOperation::FlipNormals,
+            Some("mesh.merge") => match self.merge_operation(ctx) {
+                Some(operation) => operation,
+                None => return core_document::InputResult::consumed(),
+            },
+            _ => return core_document::InputResult::ignored(),
+        };
+        self.add_operation(ctx, operation);
+        core_document::InputResult::consumed()
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        self.selected_body = self
+            .selected_body
+            .or(ctx.selected_body())
+            .filter(|id| ctx.document.bodies().iter().any(|body| body.id == *id));
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+
+    fn active_task(&self) -> Option<TaskPanel> {
+        self.exchange.as_ref().map(PendingExchange::task)
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_task(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::exchange_task(ui, ctx, self);
+    }
+
+    fn on_task_action(&mut self, action: TaskAction, ctx: &mut WorkbenchRuntimeContext) {
+        match action {
+            TaskAction::Ok | TaskAction::Apply => self.confirm_exchange(ctx),
+            TaskAction::Cancel => self.exchange = None,
+        }
+    }
+
+    fn get_screen_space_overlays(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<ScreenSpaceOverlay> {
+        if !self.show_cut_plane {
+            return Vec::new();
+        }
+        let Some(body_mesh) = self
+            .selected_body
+            .and_then(|body| ctx.body_meshes.iter().find(|m| m.body == body))
+        else {
+            return Vec::new();
+        };
+        let Some((min, max)) = mesh_bounds(&body_mesh.mesh) else {
+            return Vec::new();
+        };
+        cut_plane_overlays(
+            ctx,
+            Vec3::from(ctx.frame_offset(body_mesh.position)),
+            min,
+            max,
+            self.cut_axis,
+            self.cut_position,
+            self.cut_flip,
+        )
+    }
+
+    fn gizmos(&self, ctx: &WorkbenchRuntimeContext) -> Vec<Gizmo> {
+        let (Some(kind), Some(body)) = (self.transform_handles, self.selected_body) else {
+            return Vec::new();
+        };
+        match body_center(ctx, body) {
+            Some(center) => {
+                let center = center + Vec3::from(ctx.body_offset(Some(body)));
+                vec![Gizmo::new(TRANSFORM_GIZMO, kind, center.to_array())]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn on_gizmo_drag(&mut self, drag: &GizmoDrag, ctx: &mut WorkbenchRuntimeContext) {
+        if drag.gizmo != TRANSFORM_GIZMO {
+            return;
+        }
+        if drag.phase == GizmoDragPhase::Start {
+            self.transform_drag = self.begin_transform(ctx);
+            return;
+        }
+        let Some(state) = &self.transform_drag else {
+            return;
+        };
+        let transform = state.start.then(&drag.motion, state.origin);
+        match drag.phase {
+            GizmoDragPhase::Update => set_transform(ctx, state.feature, transform),
+            GizmoDragPhase::End if state.added && transform.is_identity() => {
+                remove_feature(ctx, state.feature);
+            }
+            GizmoDragPhase::End => {
+                set_transform(ctx, state.feature, transform);
+                ctx.log_info("Body moved");
+            }
+            GizmoDragPhase::Cancel if state.added => remove_feature(ctx, state.feature),
+            GizmoDragPhase::Cancel => set_transform(ctx, state.feature, state.start),
+            GizmoDragPhase::Start => {}
+        }
+        if matches!(drag.phase, GizmoDragPhase::End | GizmoDragPhase::Cancel) {
+            self.transform_drag = None;
+        }
+    }
+
+    fn on_file_opened(
+        &mut self,
+        request_id: &str,
+        path: &std::path::Path,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) {
+        let path_buf = path.to_path_buf();
+        if request_id == amf::EXPORT_REQUEST {
+            let options = ctx.exchange_options(ExchangeFormat::Amf);
+            let exchange = PendingExchange::export(path_buf, ExchangeKind::Amf, options);
+            self.open_exchange(exchange, ctx);
+            return;
+        }
+        if request_id == batch::EXPORT_REQUEST {
+            let options = ctx.exchange_options(self.batch.format.exchange_format());
+            let kind = ExchangeKind::Batch(self.batch.clone());
+            self.open_exchange(PendingExchange::export(path_buf, kind, options), ctx);
+            return;
+        }
+        if let Some(format) = UsdFormat::from_request_id(request_id) {
+            let options = ctx.exchange_options(ExchangeFormat::Usd);
+            let exchange = PendingExchange::export(path_buf, ExchangeKind::Usd(format), options);
+            self.open_exchange(exchange, ctx);
+            return;
+        }
+        if request_id != IMPORT_REQUEST {
+            return;
+        }
+        // Large files take seconds to read and weld; keep that off the event loop.
+        let options = ctx.exchange_options(match MeshFormat::from_path(path) {
+            Some(MeshFormat::Obj) => ExchangeFormat::Obj,
+            Some(MeshFormat::Ply) => ExchangeFormat::Ply,
+            Some(MeshFormat::Stl) | None => ExchangeFormat::Stl,
+        });
+        let jobs = ctx.document.jobs().clone();
+        let label = format!("Reading {}", file_name(path));
+        let job = jobs.spawn_with_progress(label, move |progress| -> ReadResult {
+            import::read_mesh_with_progress(&path_buf, |fraction| progress.set(fraction))
+        });
+        self.imports.push(PendingImport {
+            jobs,
+            job,
+            path: path.to_path_buf(),
+            stage: ImportStage::Reading(options),
+        });
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Ask the host for a mesh file to import.
+fn request_mesh_file(ctx: &mut WorkbenchRuntimeContext) {
+    ctx.file_open_request = Some(core_document::FileOpenRequest {
+        id: IMPORT_REQUEST.to_string(),
+        filter_name: "Mesh (STL, OBJ, PLY)".to_string(),
+        extensions: MeshFormat::ALL
+            .map(|format| format.extension().to_string())
+            .to_vec(),
+        save_name: None,
+        folder: false,
+    });
+}
+
+/// Ask the host where to save an export, named after the document.
+fn request_export_file(
+    ctx: &mut WorkbenchRuntimeContext,
+    request_id: &str,
+    filter_name: &str,
+    extension: &str,
+) {
+    let name = ctx.document.name().trim();
+    let stem = if name.is_empty() { "scene" } else { name };
+    ctx.file_open_request = Some(core_document::FileOpenRequest {
+        id: request_id.to_string(),
+        filter_name: filter_name.to_string(),
+        extensions: vec![extension.to_string()],
+        save_name: Some(format!("{stem}.{extension}")),
+        folder: false,
+    });
+}
+
+/// The selected bodies, or every visible one if none is selected, that have
+/// a mesh, made into what an exporter takes.
+fn exported_bodies<T>(
+    ctx: &WorkbenchRuntimeContext,
+    mut make: impl FnMut(&Body, &TriMesh, BodyDisplay) -> T,
+) -> Vec<T> {
+    let selected: Vec<BodyId> = ctx.selection.bodies().collect();
+    let display_states = ctx.document.display_states();
+    ctx.document
+        .bodies()
+        .iter()
+        .filter(|body| {
+            if selected.is_empty() {
+                display_states.body(body.id).visible
+            } else {
+                selected.contains(&body.id)
+            }
+        })
+        .filter_map(|body| {
+            let mesh = ctx.body_meshes.iter().find(|m| m.body == body.id)?;
+            Some(make(body, &world_mesh(mesh), display_states.body(body.id)))
+        })
+        .collect()
+}
+
+/// A body's mesh in world coordinates, as files are written in.
+fn world_mesh(body_mesh: &BodyMesh) -> TriMesh {
+    let mut mesh = body_mesh.mesh.clone();
+    mesh.to_world(body_mesh.position);
+    mesh
+}
+
+/// Every body of `document` with geometry in `body_meshes`, hidden or not,
+/// with its display color and material.
+pub fn document_bodies(document: &Document, body_meshes: &[BodyMesh]) -> Vec<AmfBody> {
+    let display_states = document.display_states();
+    document
+        .bodies()
+        .iter()
+        .filter_map(|body| {
+            let mesh = body_meshes
+                .iter()
+                .find(|m| m.body == body.id && !m.mesh.indices.is_empty())?;
+            let display = display_states.body(body.id);
+            Some(AmfBody {
+                name: body.name.clone(),
+                mesh: world_mesh(mesh),
+                color: display.color.unwrap_or(BODY_COLOR),
+                opacity: display.opacity,
+                material: document.body_material(body.id),
+            })
+        })
+        .collect()
+}
+
+fn log_export<E: std::fmt::Display>(
+    ctx: &mut WorkbenchRuntimeContext,
+    count: usize,
+    path: &Path,
+    result: Result<(), E>,
+) {
+    match result {
+        Ok(()) => ctx.log_info(format!(
+            "Exported {count} bod{} to {}",
+            if count == 1 { "y" } else { "ies" },
+            path.display()
+        )),
+        Err(err) => ctx.log_error(format!("Failed to export {}: {err}", path.display())),
+    }
+}
+
+/// Export to USD with the bodies' display state colors.
+fn export_usd(
+    ctx: &mut WorkbenchRuntimeContext,
+    format: UsdFormat,
+    path: &Path,
+    options: &ExchangeOptions,
+) {
+    let bodies = exported_bodies(ctx, |body, mesh, display| UsdBody {
+        name: body.name.clone(),
+        mesh: mesh.clone(),
+        color: display.color.unwrap_or(BODY_COLOR),
+        opacity: display.opacity,
+    });
+    let result = usd::export(&bodies, options, ctx.axes, format, path);
+    log_export(ctx, bodies.len(), path, result);
+}
+
+/// Import features of the selected bodies, or of every body if none is
+/// selected.
+fn selected_imports(ctx: &WorkbenchRuntimeContext) -> Vec<FeatureId> {
+    let selected: Vec<BodyId> = ctx.selection.bodies().collect();
+    ctx.document
+        .bodies()
+        .iter()
+        .filter(|body| selected.is_empty() || selected.contains(&body.id))
+        .flat_map(|body| cache::body_features(ctx.document, body.id))
+        .filter(|node| node.data["operation"]["type"] == "import")
+        .map(|node| node.id)
+        .collect()
+}
+
+/// Put the mesh read again from `path` into an import feature, keeping the
+/// feature's name and input.
+fn replace_import(
+    ctx: &mut WorkbenchRuntimeContext,
+    id: FeatureId,
+    operation: MeshOperation,
+    path: &Path,
+) {
+    let Some(mut feature) = ctx
+        .document
+        .get_feature_data(id)
+        .and_then(|data| MeshFeature::from_json(data).ok())
+    else {
+        ctx.log_warn(format!("{} was removed before it was read", path.display()));
+        return;
+    };
+    let triangles = match &operation {
+        MeshOperation::Import { mesh, .. } => mesh.indices.len() / 3,
+        _ => 0,
+    };
+    feature.operation = operation;
+    match ctx.document.update_feature_data(id, feature.to_json()) {
+        Ok(()) => {
+            ctx.document.mark_feature_dirty(id);
+            ctx.log_info(format!(
+                "Updated {} from {} ({triangles} triangles)",
+                feature.name,
+                path.display()
+            ));
+        }
+        Err(err) => ctx.log_error(format!("Failed to update {}: {err}", feature.name)),
+    }
+}
+
+/// Export every body with geometry, one file each, with the bodies'
+/// materials and display state colors.
+fn export_batch(
+    ctx: &mut WorkbenchRuntimeContext,
+    batch: &BatchExport,
+    folder: &Path,
+    options: &ExchangeOptions,
+) {
+    let bodies = document_bodies(ctx.document, ctx.body_meshes);
+    match batch.export(&bodies, ctx.document.name(), options, ctx.axes, folder) {
+        Ok(files) => ctx.log_info(format!(
+            "Exported {} bod{} to {files} {} file{} in {}",
+            bodies.len(),
+            if bodies.len() == 1 { "y" } else { "ies" },
+            batch.format.label(),
+            if files == 1 { "" } else { "s" },
+            folder.display()
+        )),
+        Err(err) => ctx.log_error(format!("Failed to export to {}: {err}", folder.display())),
+    }
+}
+
+/// Export to AMF with the bodies' materials and display state colors.
+fn export_amf(ctx: &mut WorkbenchRuntimeContext, path: &Path, options: &ExchangeOptions) {
+    let document = &*ctx.document;
+    let bodies = exported_bodies(ctx, |body, mesh, display| AmfBody {
+        name: body.name.clone(),
+        mesh: mesh.clone(),
+        color: display.color.unwrap_or(BODY_COLOR),
+        opacity: display.opacity,
+        material: document.body_material(body.id),
+    });
+    let result = amf::export(&bodies, options, ctx.axes, path);
+    log_export(ctx, bodies.len(), path, result);
+}
+
+/// Mesh body under the cursor, the point hit on it, and the outward normal
+/// of the face there.
+fn picked_face(ctx: &WorkbenchRuntimeContext) -> Option<(BodyId, Vec3, Vec3)> {
+    let body = ctx.hovered_body()?;
+    let point = Vec3::from(ctx.to_body(Some(body), ctx.hovered_world_pos?));
+    let mesh = &ctx.body_meshes.iter().find(|m| m.body == body)?.mesh;
+    let normal = design::face_normal_at(&IndexedMesh::from_trimesh(mesh), point)?;
+    Some((body, point, normal))
+}
+
+/// Middle of the bounding box of a body's displayed mesh, in the body's frame.
+fn body_center(ctx: &WorkbenchRuntimeContext, body: BodyId) -> Option<Vec3> {
+    let mesh = &ctx.body_meshes.iter().find(|m| m.body == body)?.mesh;
+    let (min, max) = mesh_bounds(mesh)?;
+    Some((min + max) / 2.0)
+}
+
+/// Replace the transform of the `Transform` step `id`.
+fn set_transform(ctx: &mut WorkbenchRuntimeContext, id: FeatureId, transform: RigidTransform) {
+    let Some(mut feature) = ctx
+        .document
+        .get_feature_data(id)
+        .and_then(|data| MeshFeature::from_json(data).ok())
+    else {
+        return;
+    };
+    feature.operation = MeshOperation::Transform { transform };
+    match ctx.document.update_feature_data(id, feature.to_json()) {
+        Ok(()) => ctx.document.mark_feature_dirty(id),
+        Err(err) => ctx.log_error(format!("Failed to move body: {err}")),
+    }
+}
+
+fn remove_feature(ctx: &mut WorkbenchRuntimeContext, id: FeatureId) {
+    if ctx.document.feature_tree_mut().remove_node(id).is_some() {
+        ctx.document.mark_dirty();
+    }
+}
+
+/// Bounding box of a display mesh.
+fn mesh_bounds(mesh: &kernel_api::TriMesh) -> Option<(Vec3, Vec3)> {
+    mesh.positions.iter().fold(None, |acc, &p| {
+        let p = Vec3::from(p);
+        match acc {
+            None => Some((p, p)),
+            Some((min, max)) => Some((min.min(p), max.max(p))),
+        }
+    })
+}
+
+/// Outline of the cut plane across the box `min`..`max` of a body whose frame
+/// is at `offset`, plus a tick showing the side that is kept.
+fn cut_plane_overlays(
+    ctx: &WorkbenchRuntimeContext,
+    offset: Vec3,
+    min: Vec3,
+    max: Vec3,
+    axis: usize,
+    position: f32,
+    flip: bool,
+) -> Vec<ScreenSpaceOverlay> {
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let corner = |a: f32, b: f32| {
+        let mut point = Vec3::ZERO;
+        point[axis] = position;
+        point[u] = a;
+        point[v] = b;
+        point
+    };
+    let corners = [
+        corner(min[u], min[v]),
+        corner(max[u], min[v]),
+        corner(max[u], max[v]),
+        corner(min[u], max[v]),
+    ];
+    let mut segments: Vec<(Vec3, Vec3)> =
+        (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect();
+    let center = (corners[0] + corners[2]) * 0.5;
+    let mut normal = Vec3::ZERO;
+    normal[axis] = if flip { -1.0 } else { 1.0 };
+    let reach = (max - min).max_element().max(1.0) * 0.2;
+    segments.push((center, center + normal * reach));
+
+    segments
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let start = ctx.world_to_viewport((a + offset).to_array())?;
+            let end = ctx.world_to_viewport((b + offset).to_array())?;
+            Some(ScreenSpaceOverlay::new(
+                [start.0, start.1],
+                [end.0, end.1],
+                CUT_PLANE_COLOR,
+                2.0,
+            ))
+        })
+        .collect()
+}
+
+impl MeshWorkbench {
+    fn cut_operation(&self) -> MeshOperation {
+        let mut origin = [0.0; 3];
+        origin[self.cut_axis] = self.cut_position;
+        let mut normal = [0.0; 3];
+        normal[self.cut_axis] = if self.cut_flip { -1.0 } else { 1.0 };
+        MeshOperation::PlaneCut {
+            origin,
+            normal,
+            cap: self.cut_cap,
+        }
+    }
+
+    /// Merge of the chosen source body's current mesh.
+    fn merge_operation(&self, ctx: &mut WorkbenchRuntimeContext) -> Option<MeshOperation> {
+        let source = self
+            .merge_source
+            .filter(|source| Some(*source) != self.selected_body)
+            .and_then(|source| cache::tip_feature(ctx.document, source));
+        match source {
+            Some(source) => Some(MeshOperation::Merge { source }),
+            None => {
+                ctx.log_warn("Choose another mesh body to merge");
+                None
+            }
+        }
+    }
+
+    /// Add the meshes of finished imports as new bodies.
+    fn finish_imports(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        for import in std::mem::take(&mut self.imports) {
+            if import.jobs.is_running(import.job) {
+                self.imports.push(import);
+                continue;
+            }
+            let path = import.path.display();
+            match import.stage {
+                ImportStage::Reading(options) => {
+                    match import.jobs.take::<ReadResult>(import.job) {
+                        Some(Ok(Ok(mesh))) => {
+                            let exchange = PendingExchange::import(import.path, mesh, options);
+                            self.open_exchange(exchange, ctx);
+                        }
+                        Some(Ok(Err(err))) => {
+                            ctx.log_error(format!("Failed to read {path}: {err}"))
+                        }
+                        Some(Err(err)) => ctx.log_error(format!("Failed to import {path}: {err}")),
+                        None => ctx.log_info(format!("Import of {path} cancelled")),
+                    }
+                    continue;
+                }
+                ImportStage::Updating(feature) => {
+                    match import.jobs.take::<UpdateResult>(import.job) {
+                        Some(Ok(Ok(operation))) => {
+                            replace_import(ctx, feature, operation, &import.path)
+                        }
+                        Some(Ok(Err(err))) => {
+                            ctx.log_error(format!("Failed to read {path}: {err}"))
+                        }
+                        Some(Err(err)) => ctx.log_error(format!("Failed to update {path}: {err}")),
+                        None => ctx.log_info(format!("Update from {path} cancelled")),
+                    }
+                    continue;
+                }
+                ImportStage::Converting => {}
+            }
+            match import.jobs.take::<ImportResult>(import.job) {
+                Some(Ok((feature, triangles, position))) => {
+                    let body_name = import
+                        .path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned());
+                    let body = ctx.document.create_body(body_name);
+                    ctx.document.set_body_position(body, position);
+                    let name = feature.name.clone();
+                    match ctx.document.add_feature_in_body(feature, name, Some(body)) {
+                        Ok(_) => {
+                            ctx.log_info(format!(
+                                "Imported {} ({triangles} triangles)",
+                                file_name(&import.path)
+                            ));
+                            self.selected_body = Some(body);
+                        }
+                        Err(err) => ctx.log_error(format!("Failed to add imported mesh: {err}")),
+                    }
+                }
+                Some(Err(err)) => ctx.log_error(format!("Failed to import {path}: {err}")),
+                None => ctx.log_info(format!("Import of {path} cancelled")),
+            }
+        }
+    }
+
+    /// Read the source files of import features again and replace their
+    /// meshes; the steps after them are recomputed once the files are read.
+    /// Each file is read with the units, scale, and axes it was imported with.
+    fn update_from_source(&mut self, ctx: &mut WorkbenchRuntimeContext, features: &[FeatureId]) {
+        for &id in features {
+            let Some(data) = ctx.document.get_feature_data(id) else {
+                continue;
+            };
+            let Some((path, options)) = feature::import_source(data) else {
+                let name = data["operation"]["file_name"].as_str().unwrap_or("mesh");
+                ctx.log_warn(format!(
+                    "{name} was imported before source paths were kept; import it again to update it"
+                ));
+                continue;
+            };
+            let options = options.unwrap_or_else(|| {
+                ctx.exchange_options(match MeshFormat::from_path(&path) {
+                    Some(MeshFormat::Obj) => ExchangeFormat::Obj,
+                    Some(MeshFormat::Ply) => ExchangeFormat::Ply,
+                    Some(MeshFormat::Stl) | None => ExchangeFormat::Stl,
+                })
+            });
+            // The mesh stays relative to the position it was first imported at.
+            let origin = feature::import_origin(data);
+            let axes = ctx.axes;
+            let jobs = ctx.document.jobs().clone();
+            let label = format!("Updating {}", file_name(&path));
+            let source = path.clone();
+            let job = jobs.spawn_with_progress(label, move |progress| -> UpdateResult {
+                let read = import::read_mesh_with_progress(&source, |fraction| {
+                    progress.set(fraction * 0.8);
+                })?;
+                let (mut mesh, read_origin) = read.into_document(&options, axes);
+                mesh.to_frame([0, 1, 2].map(|i| origin[i] - read_origin[i]));
+                let welded = IndexedMesh::from_trimesh(&mesh);
+                Ok(MeshOperation::import(&source, &welded, options, origin))
+            });
+            self.imports.push(PendingImport {
+                jobs,
+                job,
+                path,
+                stage: ImportStage::Updating(id),
+            });
+        }
+    }
+
+    /// Add the mesh in text pasted from the clipboard (ASCII STL or STEP) as
+    /// a new body, once its units are confirmed. Returns false when the text
+    /// isn't mesh data.
+    pub fn paste_text(&mut self, text: &str, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        let Some((format, mesh)) = clipboard::read_pasted(text) else {
+            return false;
+        };
+        match mesh {
+            Ok(mesh) => {
+                let options = ctx.exchange_options(format.exchange_format());
+                let name = format!("Pasted {}", format.label());
+                self.open_exchange(PendingExchange::paste(&name, mesh, options), ctx);
+            }
+            Err(err) => ctx.log_error(format!("Cannot paste {}: {err}", format.label())),
+        }
+        true
+    }
+
+    /// The selected bodies (or every visible one if none is selected) as
+    /// `format` text for the clipboard, with the number of bodies in it.
+    pub fn copy_text(
+        &self,
+        format: ClipboardFormat,
+        ctx: &WorkbenchRuntimeContext,
+    ) -> Option<(String, usize)> {
+        let bodies = exported_bodies(ctx, |body, mesh, _| (body.name.clone(), mesh.clone()));
+        if bodies.is_empty() {
+            return None;
+        }
+        let options = ctx.exchange_options(format.exchange_format());
+        let text = clipboard::copy_text(&bodies, format, &options, ctx.axes);
+        Some((text, bodies.len()))
+    }
+
+    /// Ask for the units, scale, and axes of an import or export. Without a
+    /// UI to ask in, the defaults are used right away.
+    fn open_exchange(&mut self, exchange: PendingExchange, ctx: &mut WorkbenchRuntimeContext) {
+        if cfg!(feature = "egui") {
+            self.exchange = Some(exchange);
+            return;
+        }
+        if let Some(unit) = exchange.settings_unit {
+            ctx.log_info(format!(
+                "Reading {} in {} instead of {}, judging by its size",
+                file_name(&exchange.path),
+                exchange.options.file_unit.label(),
+                unit.label()
+            ));
+        }
+        self.exchange = Some(exchange);
+        self.confirm_exchange(ctx);
+    }
+
+    /// Run the pending import or export with the options in its task panel.
+    fn confirm_exchange(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let Some(exchange) = self.exchange.take() else {
+            return;
+        };
+        let (path, options) = (exchange.path, exchange.options);
+        // Pasted meshes have no file to update them from.
+        let pasted = matches!(exchange.kind, ExchangeKind::Paste(_));
+        match exchange.kind {
+            ExchangeKind::Import(read) | ExchangeKind::Paste(read) => {
+                let axes = ctx.axes;
+                let jobs = ctx.document.jobs().clone();
+                let label = format!("Importing {}", file_name(&path));
+                let source = (!pasted).then(|| path.clone());
+                let name = file_name(&path);
+                let job = jobs.spawn_with_progress(label, move |progress| -> ImportResult {
+                    let (mesh, origin) = read.into_document(&options, axes);
+                    let welded = IndexedMesh::from_trimesh(&mesh);
+                    progress.set(0.9);
+                    let triangles = welded.triangles.len();
+                    let operation = match source {
+                        Some(source) => MeshOperation::import(&source, &welded, options, origin),
+                        None => MeshOperation::Import {
+                            file_name: name,
+                            mesh: welded.to_stored(),
+                            source: None,
+                            options: Some(options),
+                            origin,
+                        },
+                    };
+                    (MeshFeature::new(None, operation), triangles, origin)
+                });
+                self.imports.push(PendingImport {
+                    jobs,
+                    job,
+                    path,
+                    stage: ImportStage::Converting,
+                });
+            }
+            ExchangeKind::Usd(format) => export_usd(ctx, format, &path, &options),
+            ExchangeKind::Amf => export_amf(ctx, &path, &options),
+            ExchangeKind::Batch(batch) => {
+                export_batch(ctx, &batch, &path, &options);
+                self.batch = batch;
+            }
+        }
+    }
+
+    /// Add the configured hardware part as a new body.
+    fn insert_hardware(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let operation = MeshOperation::Hardware {
+            part: self.hardware,
+        };
+        self.insert_generated(ctx, self.hardware.name(), operation);
+    }
+
+    /// Add the configured calibration print as a new body.
+    fn insert_calibration(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let operation = MeshOperation::Calibration {
+            part: self.calibration,
+        };
+        self.insert_generated(ctx, self.calibration.name(), operation);
+    }
+
+    /// Add a new body holding a single generated feature.
+    fn insert_generated(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        name: String,
+        operation: MeshOperation,
+    ) {
+        let body = ctx.document.create_body(Some(name.clone()));
+        let feature = MeshFeature {
+            name: name.clone(),
+            input: None,
+            operation,
+        };
+        match ctx
+            .document
+            .add_feature_in_body(feature, name.clone(), Some(body))
+        {
+            Ok(_) => {
+                ctx.log_info(format!("Inserted {name}"));
+                self.selected_body = Some(body);
+            }
+            Err(err) => ctx.log_error(format!("Failed to insert {name}: {err}")),
+        }
+    }
+
+    /// Add the configured design element to the face of the mesh body under
+    /// the cursor. Returns whether there was a face to place it on.
+    fn place_design_element(&mut self, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        let Some((body, point, normal)) = picked_face(ctx) else {
+            ctx.log_warn("Click a face of a mesh body to place the element");
+            return false;
+        };
+        let operation = MeshOperation::DesignElement {
+            element: self.design_element,
+            origin: point.to_array(),
+            normal: normal.to_array(),
+            rotation: self.design_rotation,
+        };
+        self.selected_body = Some(body);
+        self.append_operation(ctx, body, operation);
+        true
+    }
+
+    /// Add the configured text label to the face of the mesh body under the
+    /// cursor. Returns whether it was placed.
+    fn place_text(&mut self, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        if self.text.text.trim().is_empty() {
+            ctx.log_warn("Enter the text to place first");
+            return false;
+        }
+        let Some((body, point, normal)) = picked_face(ctx) else {
+            ctx.log_warn("Click a face of a mesh body to place the text");
+            return false;
+        };
+        let operation = MeshOperation::Text {
+            label: self.text.clone(),
+            origin: point.to_array(),
+            normal: normal.to_array(),
+            rotation: self.text_rotation,
+        };
+        self.selected_body = Some(body);
+        self.append_operation(ctx, body, operation);
+        true
+    }
+
+    /// Add the configured knurl to the face of the mesh body under the cursor.
+    /// Returns whether there was a face to place it on.
+    fn place_knurl(&mut self, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        let Some((body, point, normal)) = picked_face(ctx) else {
+            ctx.log_warn("Click a face of a mesh body to place the knurl");
+            return false;
+        };
+        let operation = MeshOperation::Knurl {
+            knurl: self.knurl,
+            origin: point.to_array(),
+            normal: normal.to_array(),
+            rotation: self.knurl_rotation,
+        };
+        self.selected_body = Some(body);
+        self.append_operation(ctx, body, operation);
+        true
+    }
+
+    /// Append `operation` to the selected body's mesh history.
+    fn add_operation(&mut self, ctx: &mut WorkbenchRuntimeContext, operation: MeshOperation) {
+        let selected = self.selected_body.or(ctx.selected_body());
+        let Some(body) = selected else {
+            ctx.log_warn("Select a mesh body first");
+            return;
+        };
+        self.append_operation(ctx, body, operation);
+    }
+
+    /// Append `operation` to the mesh history of `body`.
+    fn append_operation(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        body: BodyId,
+        operation: MeshOperation,
+    ) {
+        let Some(tip) = cache::tip_feature(ctx.document, body) else {
+            ctx.log_warn("Only imported mesh bodies can be edited; import an STL first");
+            return;
+        };
+        let feature = MeshFeature::new(Some(tip), operation);
+        let name = feature.name.clone();
+        match ctx
+            .document
+            .add_feature_in_body(feature, name.clone(), Some(body))
+        {
+            Ok(_) => ctx.log_info(format!("{name} added")),
+            Err(err) => ctx.log_error(format!("Failed to add {name}: {err}")),
+        }
+    }
+
+    /// Start dragging the selected body: continue the `Transform` step at the
+    /// tip of its history, or add one.
+    fn begin_transform(&mut self, ctx: &mut WorkbenchRuntimeContext) -> Option<TransformDrag> {
+        let body = self.selected_body?;
+        let origin = body_center(ctx, body)?;
+        let tip = cache::tip_feature(ctx.document, body)?;
+        let tip_transform = ctx
+            .document
+            .get_feature_data(tip)
+            .and_then(|data| MeshFeature::from_json(data).ok())
+            .and_then(|feature| match feature.operation {
+                MeshOperation::Transform { transform } => Some(transform),
+                _ => None,
+            });
+        if let Some(start) = tip_transform {
+            return Some(TransformDrag {
+                feature: tip,
+                start,
+                added: false,
+                origin,
+            });
+        }
+        let start = RigidTransform::identity_at(origin);
+        let feature = MeshFeature::new(Some(tip), MeshOperation::Transform { transform: start });
+        let name = feature.name.clone();
+        match ctx.document.add_feature_in_body(feature, name, Some(body)) {
+            Ok(id) => Some(TransformDrag {
+                feature: id,
+                start,
+                added: true,
+                origin,
+            }),
+            Err(err) => {
+                ctx.log_error(format!("Failed to move body: {err}"));
+                None
+            }
+        }
+    }
+
+    /// Counts for the selected body's displayed mesh.
+    #[cfg(feature = "egui")]
+    fn stats(&mut self, ctx: &WorkbenchRuntimeContext, body: BodyId) -> Option<MeshStats> {
+        let mesh = &ctx.body_meshes.iter().find(|m| m.body == body)?.mesh;
+        let triangles = mesh.indices.len() / 3;
+        match self.stats {
+            Some((cached, count, stats)) if cached == body && count == triangles => Some(stats),
+            _ => {
+                let stats = IndexedMesh::from_trimesh(mesh).stats();
+                self.stats = Some((body, triangles, stats));
+                Some(stats)
+            }
+        }
+    }
+}
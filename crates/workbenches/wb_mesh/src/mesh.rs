@@ -0,0 +1,295 @@
+//! Indexed triangle meshes and the simple mesh operations (flip, merge, smooth).
+
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+use kernel_api::TriMesh;
+
+/// Vertices closer than this (mm) are welded into one when a mesh is imported.
+const WELD_TOLERANCE: f32 = 1e-4;
+
+/// Taubin smoothing pass band; the inflating step is derived from it so the
+/// mesh does not shrink the way plain Laplacian smoothing makes it.
+const TAUBIN_PASS_BAND: f32 = 0.1;
+
+/// Triangle mesh with shared vertices, the form every mesh operation works on.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedMesh {
+    pub positions: Vec<Vec3>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Counts shown for a mesh body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Edges used by a single triangle (holes in the surface).
+    pub open_edges: usize,
+    /// Edges shared by more than two triangles.
+    pub non_manifold_edges: usize,
+}
+
+impl MeshStats {
+    pub fn is_closed(&self) -> bool {
+        self.open_edges == 0 && self.non_manifold_edges == 0
+    }
+}
+
+impl IndexedMesh {
+    /// Weld coincident vertices of a tessellated mesh and drop degenerate triangles.
+    pub fn from_trimesh(mesh: &TriMesh) -> Self {
+        let mut welded = IndexedMesh::default();
+        let mut lookup: HashMap<[i64; 3], u32> = HashMap::new();
+        let remap: Vec<u32> = mesh
+            .positions
+            .iter()
+            .map(|&p| {
+                let key = p.map(|c| (c / WELD_TOLERANCE).round() as i64);
+                *lookup.entry(key).or_insert_with(|| {
+                    welded.positions.push(Vec3::from(p));
+                    (welded.positions.len() - 1) as u32
+                })
+            })
+            .collect();
+        for triangle in mesh.indices.chunks_exact(3) {
+            let Some(triangle) = [0, 1, 2].iter().try_fold([0u32; 3], |mut out, &i| {
+                out[i] = *remap.get(triangle[i] as usize)?;
+                Some(out)
+            }) else {
+                continue;
+            };
+            if triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[2] != triangle[0]
+            {
+                welded.triangles.push(triangle);
+            }
+        }
+        welded
+    }
+
+    /// Compact form for storing in the document (no normals, shared vertices).
+    pub fn to_stored(&self) -> TriMesh {
+        TriMesh {
+            positions: self.positions.iter().map(|p| p.to_array()).collect(),
+            normals: Vec::new(),
+            indices: self.triangles.iter().flatten().copied().collect(),
+        }
+    }
+
+    /// Flat-shaded mesh for display (three vertices per triangle).
+    pub fn to_trimesh(&self) -> TriMesh {
+        let mut mesh = TriMesh {
+            positions: Vec::with_capacity(self.triangles.len() * 3),
+            normals: Vec::with_capacity(self.triangles.len() * 3),
+            indices: Vec::with_capacity(self.triangles.len() * 3),
+        };
+        for triangle in &self.triangles {
+            let normal = self.face_normal(triangle).normalize_or_zero().to_array();
+            for &vertex in triangle {
+                mesh.indices.push(mesh.positions.len() as u32);
+                mesh.positions
+                    .push(self.positions[vertex as usize].to_array());
+                mesh.normals.push(normal);
+            }
+        }
+        mesh
+    }
+
+    /// Unnormalized normal of a triangle (its length is twice the area).
+    pub fn face_normal(&self, triangle: &[u32; 3]) -> Vec3 {
+        let [a, b, c] = triangle.map(|i| self.positions[i as usize]);
+        (b - a).cross(c - a)
+    }
+
+    /// Bounding box (None for an empty mesh).
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.positions.iter().fold(None, |acc, &p| match acc {
+            None => Some((p, p)),
+            Some((min, max)) => Some((min.min(p), max.max(p))),
+        })
+    }
+
+    /// Number of triangles using each undirected edge.
+    pub(crate) fn edge_use(&self) -> HashMap<(u32, u32), usize> {
+        let mut edges = HashMap::new();
+        for triangle in &self.triangles {
+            for (a, b) in edges_of(triangle) {
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        edges
+    }
+
+    /// Vertices on the border of a hole in the surface.
+    pub(crate) fn boundary_vertices(&self) -> Vec<bool> {
+        let mut boundary = vec![false; self.positions.len()];
+        for ((a, b), count) in self.edge_use() {
+            if count == 1 {
+                boundary[a as usize] = true;
+                boundary[b as usize] = true;
+            }
+        }
+        boundary
+    }
+
+    /// Vertices sharing an edge with each vertex.
+    pub(crate) fn vertex_neighbors(&self) -> Vec<Vec<u32>> {
+        let mut neighbors = vec![Vec::new(); self.positions.len()];
+        for triangle in &self.triangles {
+            for (a, b) in edges_of(triangle) {
+                neighbors[a as usize].push(b);
+                neighbors[b as usize].push(a);
+            }
+        }
+        for list in &mut neighbors {
+            list.sort_unstable();
+            list.dedup();
+        }
+        neighbors
+    }
+
+    /// Area-weighted normal of each vertex (unit length, or zero if unused).
+    pub(crate) fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+        for triangle in &self.triangles {
+            let normal = self.face_normal(triangle);
+            for &vertex in triangle {
+                normals[vertex as usize] += normal;
+            }
+        }
+        normals.iter().map(|n| n.normalize_or_zero()).collect()
+    }
+
+    /// Drop vertices no triangle uses.
+    pub(crate) fn compact(&mut self) {
+        let mut remap = vec![u32::MAX; self.positions.len()];
+        let mut positions = Vec::new();
+        for triangle in &mut self.triangles {
+            for vertex in triangle.iter_mut() {
+                let slot = &mut remap[*vertex as usize];
+                if *slot == u32::MAX {
+                    *slot = positions.len() as u32;
+                    positions.push(self.positions[*vertex as usize]);
+                }
+                *vertex = *slot;
+            }
+        }
+        self.positions = positions;
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        let edges = self.edge_use();
+        MeshStats {
+            vertices: self.positions.len(),
+            triangles: self.triangles.len(),
+            open_edges: edges.values().filter(|&&count| count == 1).count(),
+            non_manifold_edges: edges.values().filter(|&&count| count > 2).count(),
+        }
+    }
+}
+
+/// The three directed edges of a triangle.
+pub(crate) fn edges_of(triangle: &[u32; 3]) -> [(u32, u32); 3] {
+    [
+        (triangle[0], triangle[1]),
+        (triangle[1], triangle[2]),
+        (triangle[2], triangle[0]),
+    ]
+}
+
+/// Axis-aligned box between two corners.
+pub(crate) fn block(min: Vec3, max: Vec3) -> IndexedMesh {
+    let profile = [
+        Vec2::new(min.x, min.z),
+        Vec2::new(max.x, min.z),
+        Vec2::new(max.x, max.z),
+        Vec2::new(min.x, max.z),
+    ];
+    prism(&profile, min.y, max.y)
+}
+
+/// Convex polygon in the XZ plane (x, z), extruded along Y from `y0` to `y1`.
+pub(crate) fn prism(profile: &[Vec2], y0: f32, y1: f32) -> IndexedMesh {
+    let count = profile.len() as u32;
+    let mut mesh = IndexedMesh::default();
+    for y in [y0, y1] {
+        mesh.positions
+            .extend(profile.iter().map(|point| Vec3::new(point.x, y, point.y)));
+    }
+    for i in 1..count - 1 {
+        mesh.triangles.push([0, i + 1, i]);
+        mesh.triangles.push([count, count + i, count + i + 1]);
+    }
+    for i in 0..count {
+        let j = (i + 1) % count;
+        mesh.triangles.push([i, j, count + j]);
+        mesh.triangles.push([i, count + j, count + i]);
+    }
+
+    // The winding depends on which way the profile runs; make normals point out.
+    let signed_volume: f32 = mesh
+        .triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|i| mesh.positions[i as usize]);
+            a.dot(b.cross(c))
+        })
+        .sum();
+    if signed_volume < 0.0 {
+        flip_normals(&mut mesh);
+    }
+    mesh
+}
+
+/// Reverse the winding of every triangle so the surface faces the other way.
+pub fn flip_normals(mesh: &mut IndexedMesh) {
+    for triangle in &mut mesh.triangles {
+        triangle.swap(1, 2);
+    }
+}
+
+/// Add the triangles of `other` to `mesh`. The surfaces are only combined, not
+/// united: overlapping regions stay inside the result.
+pub fn merge(mesh: &mut IndexedMesh, other: &IndexedMesh) {
+    let offset = mesh.positions.len() as u32;
+    mesh.positions.extend_from_slice(&other.positions);
+    mesh.triangles.extend(
+        other
+            .triangles
+            .iter()
+            .map(|triangle| triangle.map(|vertex| vertex + offset)),
+    );
+}
+
+/// Taubin smoothing: alternating shrinking (`factor`) and inflating Laplacian
+/// steps. Vertices on open borders stay where they are.
+pub fn smooth(mesh: &mut IndexedMesh, iterations: u32, factor: f32) {
+    let neighbors = mesh.vertex_neighbors();
+    let boundary = mesh.boundary_vertices();
+    let factor = factor.clamp(0.01, 0.99);
+    let inflate = 1.0 / (TAUBIN_PASS_BAND - 1.0 / factor);
+    for _ in 0..iterations {
+        for weight in [factor, inflate] {
+            let moved: Vec<Vec3> = mesh
+                .positions
+                .iter()
+                .enumerate()
+                .map(|(vertex, &p)| {
+                    let around = &neighbors[vertex];
+                    if boundary[vertex] || around.is_empty() {
+                        return p;
+                    }
+                    let centroid = around
+                        .iter()
+                        .map(|&n| mesh.positions[n as usize])
+                        .sum::<Vec3>()
+                        / around.len() as f32;
+                    p + (centroid - p) * weight
+                })
+                .collect();
+            mesh.positions = moved;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Wavefront OBJ reading: vertices and faces only, polygons split into
+//! triangle fans. Groups, materials, texture coordinates, and normals are
+//! ignored.
+
+use std::path::Path;
+
+use kernel_api::TriMesh;
+use thiserror::Error;
+
+use crate::import::{file_origin, FileMesh};
+use crate::stl::Progress;
+
+#[derive(Debug, Error)]
+pub enum ObjError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("file contains no faces")]
+    Empty,
+}
+
+/// Read an OBJ file into an indexed mesh (without normals). Coordinates are
+/// parsed in double precision and stored relative to the first vertex.
+pub fn read_obj(path: &Path) -> Result<FileMesh, ObjError> {
+    read_obj_with_progress(path, |_| {})
+}
+
+/// [`read_obj`] that calls `progress` with the fraction done (0 to 1), about
+/// once per percent.
+pub fn read_obj_with_progress(
+    path: &Path,
+    progress: impl FnMut(f32),
+) -> Result<FileMesh, ObjError> {
+    let bytes = std::fs::read(path)?;
+    let mesh = parse(&String::from_utf8_lossy(&bytes), Progress::new(progress))?;
+    if mesh.mesh.indices.is_empty() {
+        return Err(ObjError::Empty);
+    }
+    Ok(mesh)
+}
+
+fn parse<F: FnMut(f32)>(text: &str, mut progress: Progress<F>) -> Result<FileMesh, ObjError> {
+    let mut mesh = TriMesh::default();
+    let mut origin = None;
+    let mut corners = Vec::new();
+    let mut parsed = 0;
+    for (index, line) in text.lines().enumerate() {
+        parsed += line.len() + 1;
+        if index % 16384 == 0 {
+            progress.report(parsed as f32 / text.len().max(1) as f32);
+        }
+        let error = |message: &str| ObjError::Parse {
+            line: index + 1,
+            message: message.to_string(),
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let mut position = [0.0_f64; 3];
+                for component in &mut position {
+                    *component = words
+                        .next()
+                        .and_then(|word| word.parse().ok())
+                        .ok_or_else(|| error("expected three coordinates"))?;
+                }
+                let origin = *origin.get_or_insert_with(|| file_origin(position));
+                mesh.positions
+                    .push([0, 1, 2].map(|i| (position[i] - origin[i]) as f32));
+            }
+            Some("f") => {
+                corners.clear();
+                for word in words {
+                    // `v`, `v/vt`, `v//vn`, or `v/vt/vn`; negative indices
+                    // count back from the latest vertex.
+                    let index: i64 = word
+                        .split('/')
+                        .next()
+                        .and_then(|index| index.parse().ok())
+                        .ok_or_else(|| error("expected vertex indices"))?;
+                    let count = mesh.positions.len() as i64;
+                    let resolved = if index < 0 { count + index } else { index - 1 };
+                    if !(0..count).contains(&resolved) {
+                        return Err(error("vertex index out of range"));
+                    }
+                    corners.push(resolved as u32);
+                }
+                if corners.len() < 3 {
+                    return Err(error("face has fewer than three vertices"));
+                }
+                for i in 1..corners.len() - 1 {
+                    mesh.indices
+                        .extend([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(FileMesh {
+        mesh,
+        origin: origin.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,1012 @@
+//! Right-panel UI of the Mesh workbench.
+
+use axes::AxisPreset;
+use core_document::{BodyId, FeatureId, GizmoKind, WorkbenchFeature, WorkbenchRuntimeContext};
+
+use crate::batch::{self, BatchExport, BatchFormat};
+use crate::cache;
+use crate::calibration::CalibrationPart;
+use crate::clipboard::ClipboardFormat;
+use crate::design::DesignElement;
+use crate::exchange::ExchangeKind;
+use crate::feature::{MeshFeature, MeshOperation};
+use crate::hardware::{BoltHead, HardwarePart, MetricSize};
+use crate::knurl::{Knurl, KnurlPattern, KnurlShape};
+use crate::text::{TextFont, TextLabel, TextMode};
+use crate::transform::RigidTransform;
+use crate::MeshWorkbench;
+
+const AXIS_LABELS: [&str; 3] = ["X", "Y", "Z"];
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut MeshWorkbench,
+) {
+    let mesh_bodies: Vec<(BodyId, String)> = ctx
+        .document
+        .bodies()
+        .iter()
+        .filter(|body| cache::tip_feature(ctx.document, body.id).is_some())
+        .map(|body| (body.id, body.name.clone()))
+        .collect();
+
+    ui.heading("Mesh Bodies");
+    if ui.button("Import Mesh…").clicked() {
+        crate::request_mesh_file(ctx);
+    }
+    clipboard(ui, ctx, workbench);
+    egui::CollapsingHeader::new("Standard Hardware")
+        .default_open(false)
+        .show(ui, |ui| {
+            hardware_fields(ui, "mesh_hardware", &mut workbench.hardware);
+            if ui
+                .button(format!("Insert {}", workbench.hardware.name()))
+                .clicked()
+            {
+                workbench.insert_hardware(ctx);
+            }
+        });
+    egui::CollapsingHeader::new("Calibration Parts")
+        .default_open(false)
+        .show(ui, |ui| {
+            calibration_fields(ui, "mesh_calibration", &mut workbench.calibration);
+            if ui
+                .button(format!("Insert {}", workbench.calibration.name()))
+                .clicked()
+            {
+                workbench.insert_calibration(ctx);
+            }
+        });
+    if mesh_bodies.is_empty() {
+        ui.label("No mesh bodies yet. Import an STL file or insert a generated part to start.");
+    }
+    for (id, name) in &mesh_bodies {
+        let is_selected = workbench.selected_body == Some(*id);
+        if ui.selectable_label(is_selected, name).clicked() {
+            workbench.selected_body = Some(*id);
+        }
+    }
+
+    let Some(body) = workbench
+        .selected_body
+        .filter(|body| mesh_bodies.iter().any(|(id, _)| id == body))
+    else {
+        return;
+    };
+
+    if let Some(stats) = workbench.stats(ctx, body) {
+        ui.separator();
+        ui.label(format!(
+            "{} triangles, {} vertices",
+            stats.triangles, stats.vertices
+        ));
+        if stats.is_closed() {
+            ui.label("✔ Closed surface");
+        } else {
+            ui.colored_label(
+                egui::Color32::from_rgb(230, 150, 60),
+                format!(
+                    "⚠ {} open edges, {} non-manifold edges",
+                    stats.open_edges, stats.non_manifold_edges
+                ),
+            );
+        }
+    }
+
+    ui.separator();
+    ui.heading("Operations");
+    ui.horizontal(|ui| {
+        ui.label("Handles:");
+        let handles = &mut workbench.transform_handles;
+        ui.selectable_value(handles, None, "Off");
+        ui.selectable_value(handles, Some(GizmoKind::Translate), "Move");
+        ui.selectable_value(handles, Some(GizmoKind::Rotate), "Rotate");
+    });
+    let mut operation = None;
+    ui.horizontal(|ui| {
+        ui.label("Keep:");
+        ui.add(
+            egui::Slider::new(&mut workbench.decimate_ratio, 0.05..=1.0)
+                .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+        );
+        if ui.button("Decimate").clicked() {
+            operation = Some(MeshOperation::Decimate {
+                ratio: workbench.decimate_ratio,
+            });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Edge length:");
+        ui.add(
+            egui::DragValue::new(&mut workbench.edge_length)
+                .range(0.05..=100.0)
+                .speed(0.05)
+                .suffix(" mm"),
+        );
+        if ui.button("Remesh").clicked() {
+            operation = Some(MeshOperation::Remesh {
+                edge_length: workbench.edge_length,
+            });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Passes:");
+        ui.add(egui::DragValue::new(&mut workbench.smooth_iterations).range(1..=100));
+        ui.label("Strength:");
+        ui.add(
+            egui::DragValue::new(&mut workbench.smooth_factor)
+                .range(0.05..=0.95)
+                .speed(0.01),
+        );
+        if ui.button("Smooth").clicked() {
+            operation = Some(MeshOperation::Smooth {
+                iterations: workbench.smooth_iterations,
+                factor: workbench.smooth_factor,
+            });
+        }
+    });
+
+    ui.add_space(4.0);
+    ui.label("Plane cut");
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("mesh_cut_axis")
+            .width(40.0)
+            .selected_text(AXIS_LABELS[workbench.cut_axis])
+            .show_ui(ui, |ui| {
+                for (axis, label) in AXIS_LABELS.iter().enumerate() {
+                    ui.selectable_value(&mut workbench.cut_axis, axis, *label);
+                }
+            });
+        ui.add(
+            egui::DragValue::new(&mut workbench.cut_position)
+                .speed(0.1)
+                .suffix(" mm"),
+        );
+        ui.checkbox(&mut workbench.cut_flip, "Keep below");
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut workbench.cut_cap, "Cap");
+        ui.checkbox(&mut workbench.show_cut_plane, "Show plane");
+        if ui.button("Cut").clicked() {
+            operation = Some(workbench.cut_operation());
+        }
+    });
+
+    ui.add_space(4.0);
+    ui.horizontal(|ui| {
+        if ui.button("Flip Normals").clicked() {
+            operation = Some(MeshOperation::FlipNormals);
+        }
+    });
+    ui.horizontal(|ui| {
+        let source_name = workbench
+            .merge_source
+            .and_then(|source| mesh_bodies.iter().find(|(id, _)| *id == source))
+            .map(|(_, name)| name.as_str())
+            .unwrap_or("Choose body");
+        egui::ComboBox::from_id_salt("mesh_merge_source")
+            .selected_text(source_name)
+            .show_ui(ui, |ui| {
+                for (id, name) in mesh_bodies.iter().filter(|(id, _)| *id != body) {
+                    ui.selectable_value(&mut workbench.merge_source, Some(*id), name);
+                }
+            });
+        if ui.button("Merge").clicked() {
+            operation = workbench.merge_operation(ctx);
+        }
+    });
+    if let Some(operation) = operation {
+        workbench.add_operation(ctx, operation);
+    }
+
+    ui.add_space(4.0);
+    egui::CollapsingHeader::new("Design Elements")
+        .default_open(false)
+        .show(ui, |ui| {
+            design_fields(ui, "mesh_design", &mut workbench.design_element);
+            ui.horizontal(|ui| {
+                ui.label("Rotation:");
+                ui.add(
+                    egui::DragValue::new(&mut workbench.design_rotation)
+                        .range(-180.0..=180.0)
+                        .speed(1.0)
+                        .suffix("°"),
+                );
+            });
+            ui.weak("Use Place Design Element, then click a face of a mesh body.");
+        });
+    egui::CollapsingHeader::new("Text")
+        .default_open(false)
+        .show(ui, |ui| {
+            text_fields(ui, "mesh_text", &mut workbench.text);
+            ui.horizontal(|ui| {
+                ui.label("Rotation:");
+                ui.add(
+                    egui::DragValue::new(&mut workbench.text_rotation)
+                        .range(-180.0..=180.0)
+                        .speed(1.0)
+                        .suffix("°"),
+                );
+            });
+            ui.weak("Use Place Text, then click a face of a mesh body. Engraved text must fit on a flat face.");
+        });
+    egui::CollapsingHeader::new("Knurling")
+        .default_open(false)
+        .show(ui, |ui| {
+            knurl_fields(ui, "mesh_knurl", &mut workbench.knurl);
+            if matches!(workbench.knurl.shape, KnurlShape::Pad { .. }) {
+                ui.horizontal(|ui| {
+                    ui.label("Rotation:");
+                    ui.add(
+                        egui::DragValue::new(&mut workbench.knurl_rotation)
+                            .range(-180.0..=180.0)
+                            .speed(1.0)
+                            .suffix("°"),
+                    );
+                });
+            }
+            ui.weak("Use Place Knurl, then click a face of a mesh body. A cylinder knurl covers the whole round face clicked.");
+        });
+
+    ui.separator();
+    ui.heading("History");
+    let features: Vec<(FeatureId, String, bool)> = cache::body_features(ctx.document, body)
+        .into_iter()
+        .map(|node| (node.id, node.name.clone(), node.suppressed))
+        .collect();
+    let edited = ctx.active_document_object;
+    for (id, name, suppressed) in &features {
+        ui.horizontal(|ui| {
+            let mut enabled = !suppressed;
+            if ui.checkbox(&mut enabled, "").changed() {
+                if let Some(node) = ctx.document.feature_tree_mut().get_node_mut(*id) {
+                    node.suppressed = !enabled;
+                }
+                ctx.document.mark_feature_dirty(*id);
+            }
+            if edited == Some(*id) {
+                ui.strong(name);
+            } else {
+                ui.label(name);
+            }
+        });
+    }
+    ui.weak("Select a step in the feature tree to edit its parameters.");
+
+    if let Some(id) = edited.filter(|id| features.iter().any(|(feature, ..)| feature == id)) {
+        edit_feature(ui, ctx, id);
+        import_source(ui, ctx, workbench, id);
+    }
+}
+
+/// Copy buttons, and Ctrl+C/Ctrl+V while no text field has focus: copying
+/// puts the selected bodies on the clipboard as STL, pasting adds STL or STEP
+/// text as a new body.
+fn clipboard(ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext, workbench: &mut MeshWorkbench) {
+    let mut copy = None;
+    ui.horizontal(|ui| {
+        for format in ClipboardFormat::ALL {
+            if ui.button(format!("Copy as {}", format.label())).clicked() {
+                copy = Some(format);
+            }
+        }
+    });
+    ui.weak("Ctrl+V pastes STL or STEP text as a new body.");
+
+    let mut pasted = None;
+    if ui.ctx().memory(|memory| memory.focused().is_none()) {
+        ui.input(|input| {
+            for event in &input.events {
+                match event {
+                    egui::Event::Copy if ctx.selection.bodies().next().is_some() => {
+                        copy = Some(ClipboardFormat::Stl);
+                    }
+                    egui::Event::Paste(text) => pasted = Some(text.clone()),
+                    _ => {}
+                }
+            }
+        });
+    }
+    if let Some(format) = copy {
+        match workbench.copy_text(format, ctx) {
+            Some((text, count)) => {
+                ui.ctx().copy_text(text);
+                ctx.log_info(format!(
+                    "Copied {count} bod{} as {}",
+                    if count == 1 { "y" } else { "ies" },
+                    format.label()
+                ));
+            }
+            None => ctx.log_info("No bodies with geometry to copy"),
+        }
+    }
+    if let Some(text) = pasted {
+        if !workbench.paste_text(&text, ctx) {
+            ctx.log_info("The clipboard holds no STL or STEP data");
+        }
+    }
+}
+
+/// Source file of an import feature, with a button to read it again.
+fn import_source(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut MeshWorkbench,
+    id: FeatureId,
+) {
+    let Some(data) = ctx.document.get_feature_data(id) else {
+        return;
+    };
+    if data["operation"]["type"] != "import" {
+        return;
+    }
+    ui.add_space(4.0);
+    match crate::feature::import_source(data) {
+        Some((path, _)) => {
+            ui.label(format!("Source: {}", path.display()));
+            let exists = path.exists();
+            let button = ui.add_enabled(exists, egui::Button::new("Update from Source"));
+            if button.clicked() {
+                workbench.update_from_source(ctx, &[id]);
+            }
+            if !exists {
+                ui.weak("The source file is missing.");
+            }
+        }
+        None => {
+            ui.weak("Imported before source paths were kept; import the file again to update it.");
+        }
+    }
+}
+
+/// Format and file names of "Export All Bodies". Returns whether the format
+/// changed.
+fn batch_fields(ui: &mut egui::Ui, batch: &mut BatchExport) -> bool {
+    let format = batch.format;
+    egui::Grid::new("mesh_batch_export")
+        .num_columns(2)
+        .spacing([8.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("Format");
+            egui::ComboBox::from_id_salt("mesh_batch_format")
+                .selected_text(batch.format.label())
+                .show_ui(ui, |ui| {
+                    for option in BatchFormat::ALL {
+                        ui.selectable_value(&mut batch.format, option, option.label());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("File names");
+            ui.text_edit_singleline(&mut batch.template)
+                .on_hover_text("{document}, {body}, and {index} are replaced");
+            ui.end_row();
+        });
+    ui.weak(format!(
+        "e.g. {}.{}",
+        batch::file_stem(&batch.template, "Assembly", "Bracket", 1),
+        batch.format.extension()
+    ));
+    ui.checkbox(&mut batch.plate, "Also write a plate file with every body");
+    batch.format != format
+}
+
+/// Units, scale, and axes of the pending import or export, shown in its task panel.
+pub fn exchange_task(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut MeshWorkbench,
+) {
+    let Some(exchange) = &mut workbench.exchange else {
+        return;
+    };
+    let name = exchange
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    ui.label(name);
+    ui.add_space(4.0);
+    if let ExchangeKind::Batch(batch) = &mut exchange.kind {
+        if batch_fields(ui, batch) {
+            exchange.options = ctx.exchange_options(batch.format.exchange_format());
+        }
+        ui.add_space(4.0);
+    }
+    let units = exchange.units();
+    let options = &mut exchange.options;
+    egui::Grid::new("mesh_exchange_options")
+        .num_columns(2)
+        .spacing([8.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("File unit");
+            egui::ComboBox::from_id_salt("mesh_exchange_unit")
+                .selected_text(options.file_unit.label())
+                .show_ui(ui, |ui| {
+                    for unit in units {
+                        ui.selectable_value(&mut options.file_unit, *unit, unit.label());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Scale");
+            ui.add(
+                egui::DragValue::new(&mut options.scale)
+                    .speed(0.01)
+                    .range(0.0001..=10_000.0),
+            );
+            ui.end_row();
+
+            ui.label("Axes");
+            let selected_text = match options.file_axes.preset() {
+                Some(preset) => preset.label().to_string(),
+                None => options.file_axes.label(),
+            };
+            egui::ComboBox::from_id_salt("mesh_exchange_axes")
+                .width(220.0)
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for preset in AxisPreset::ALL {
+                        ui.selectable_value(
+                            &mut options.file_axes,
+                            preset.axis_system(),
+                            preset.label(),
+                        );
+                    }
+                });
+            ui.end_row();
+        });
+    if let Some(unit) = exchange.settings_unit {
+        ui.weak(format!(
+            "Guessed from the model's size; Settings say {}.",
+            unit.label()
+        ));
+    }
+    if let Some(size) = exchange.imported_size(ctx.axes) {
+        let [x, y, z] = size.to_array().map(|mm| ctx.units.format_length(mm));
+        ui.label(format!("Size: {x} × {y} × {z}"));
+    }
+}
+
+/// Parameter fields of an existing operation; changes recompute it and every
+/// step after it.
+fn edit_feature(ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext, id: FeatureId) {
+    let Some(data) = ctx.document.get_feature_data(id) else {
+        return;
+    };
+    // Imports carry the whole mesh and have nothing to edit, so skip decoding them.
+    if data["operation"]["type"] == "import" {
+        return;
+    }
+    let Ok(mut feature) = MeshFeature::from_json(data) else {
+        return;
+    };
+
+    ui.add_space(4.0);
+    ui.label(format!("Edit {}", feature.operation.label()));
+    let changed = match &mut feature.operation {
+        MeshOperation::Decimate { ratio } => ui
+            .add(
+                egui::Slider::new(ratio, 0.05..=1.0)
+                    .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+            )
+            .changed(),
+        MeshOperation::Remesh { edge_length } => ui
+            .add(
+                egui::DragValue::new(edge_length)
+                    .range(0.05..=100.0)
+                    .speed(0.05)
+                    .suffix(" mm"),
+            )
+            .changed(),
+        MeshOperation::Smooth { iterations, factor } => {
+            ui.horizontal(|ui| {
+                let passes = ui
+                    .add(egui::DragValue::new(iterations).range(1..=100))
+                    .changed();
+                let strength = ui
+                    .add(egui::DragValue::new(factor).range(0.05..=0.95).speed(0.01))
+                    .changed();
+                passes || strength
+            })
+            .inner
+        }
+        MeshOperation::PlaneCut {
+            origin,
+            normal,
+            cap,
+        } => {
+            ui.horizontal(|ui| {
+                let mut changed = false;
+                for (axis, label) in AXIS_LABELS.iter().enumerate() {
+                    ui.label(*label);
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut origin[axis]).speed(0.1))
+                        .changed();
+                }
+                let mut flipped = normal.iter().sum::<f32>() < 0.0;
+                if ui.checkbox(&mut flipped, "Flip").changed() {
+                    *normal = normal.map(|c| -c);
+                    changed = true;
+                }
+                changed |= ui.checkbox(cap, "Cap").changed();
+                changed
+            })
+            .inner
+        }
+        MeshOperation::Hardware { part } => hardware_fields(ui, "mesh_edit_hardware", part),
+        MeshOperation::Calibration { part } => {
+            calibration_fields(ui, "mesh_edit_calibration", part)
+        }
+        MeshOperation::DesignElement {
+            element,
+            origin,
+            rotation,
+            ..
+        } => {
+            let changed = design_fields(ui, "mesh_edit_design", element);
+            placement_fields(ui, origin, rotation) || changed
+        }
+        MeshOperation::Text {
+            label,
+            origin,
+            rotation,
+            ..
+        } => {
+            let changed = text_fields(ui, "mesh_edit_text", label);
+            placement_fields(ui, origin, rotation) || changed
+        }
+        MeshOperation::Knurl {
+            knurl,
+            origin,
+            rotation,
+            ..
+        } => {
+            let changed = knurl_fields(ui, "mesh_edit_knurl", knurl);
+            placement_fields(ui, origin, rotation) || changed
+        }
+        MeshOperation::Transform { transform } => transform_fields(ui, transform),
+        MeshOperation::Import { .. } | MeshOperation::FlipNormals | MeshOperation::Merge { .. } => {
+            ui.weak("This step has no parameters.");
+            false
+        }
+    };
+
+    if changed {
+        let generated_name = match &feature.operation {
+            MeshOperation::Hardware { part } => Some(part.name()),
+            MeshOperation::Calibration { part } => Some(part.name()),
+            MeshOperation::DesignElement { element, .. } => Some(element.name()),
+            MeshOperation::Text { label, .. } => Some(label.name()),
+            MeshOperation::Knurl { knurl, .. } => Some(knurl.name()),
+            _ => None,
+        };
+        if let Some(name) = generated_name {
+            feature.name = name;
+            if let Some(node) = ctx.document.feature_tree_mut().get_node_mut(id) {
+                node.name = feature.name.clone();
+            }
+        }
+        match ctx.document.update_feature_data(id, feature.to_json()) {
+            Ok(()) => ctx.document.mark_feature_dirty(id),
+            Err(err) => ctx.log_error(format!("Failed to update {}: {err}", feature.name)),
+        }
+    }
+}
+
+/// Kind, size, and dimensions of a hardware part. Returns whether any changed.
+fn hardware_fields(ui: &mut egui::Ui, id_salt: &str, part: &mut HardwarePart) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Part:");
+            let mut kind = part.kind();
+            egui::ComboBox::from_id_salt((id_salt, "kind"))
+                .selected_text(HardwarePart::KINDS[kind])
+                .show_ui(ui, |ui| {
+                    for (index, label) in HardwarePart::KINDS.iter().enumerate() {
+                        ui.selectable_value(&mut kind, index, *label);
+                    }
+                });
+            if kind != part.kind() {
+                *part = HardwarePart::of_kind(kind, part.size());
+                changed = true;
+            }
+            ui.end_row();
+
+            ui.label("Size:");
+            let mut size = part.size();
+            egui::ComboBox::from_id_salt((id_salt, "size"))
+                .selected_text(size.label())
+                .show_ui(ui, |ui| {
+                    for option in MetricSize::ALL {
+                        ui.selectable_value(&mut size, option, option.label());
+                    }
+                });
+            if size != part.size() {
+                part.set_size(size);
+                changed = true;
+            }
+            ui.end_row();
+
+            match part {
+                HardwarePart::Bolt { length, head, .. } => {
+                    ui.label("Length:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(length)
+                                .range(1.0..=300.0)
+                                .speed(0.5)
+                                .suffix(" mm"),
+                        )
+                        .changed();
+                    ui.end_row();
+                    ui.label("Head:");
+                    ui.horizontal(|ui| {
+                        for option in [BoltHead::SocketCap, BoltHead::Hex] {
+                            changed |= ui.radio_value(head, option, option.label()).changed();
+                        }
+                    });
+                    ui.end_row();
+                }
+                HardwarePart::InsertBoss { height, wall, .. } => {
+                    ui.label("Height:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(height)
+                                .range(1.0..=200.0)
+                                .speed(0.1)
+                                .suffix(" mm"),
+                        )
+                        .changed();
+                    ui.end_row();
+                    ui.label("Wall:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(wall)
+                                .range(0.4..=20.0)
+                                .speed(0.05)
+                                .suffix(" mm"),
+                        )
+                        .changed();
+                    ui.end_row();
+                }
+                HardwarePart::Nut { .. } | HardwarePart::Washer { .. } => {}
+            }
+        });
+    changed
+}
+
+/// Kind and parameters of a calibration print. Returns whether any changed.
+fn calibration_fields(ui: &mut egui::Ui, id_salt: &str, part: &mut CalibrationPart) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Part:");
+            let mut kind = part.kind();
+            egui::ComboBox::from_id_salt((id_salt, "kind"))
+                .selected_text(CalibrationPart::KINDS[kind])
+                .show_ui(ui, |ui| {
+                    for (index, label) in CalibrationPart::KINDS.iter().enumerate() {
+                        ui.selectable_value(&mut kind, index, *label);
+                    }
+                });
+            if kind != part.kind() {
+                *part = CalibrationPart::of_kind(kind);
+                changed = true;
+            }
+            ui.end_row();
+
+            let mut row = |ui: &mut egui::Ui, label: &str, value: &mut f32, range, suffix| {
+                ui.label(label);
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(value)
+                            .range(range)
+                            .speed(0.05)
+                            .suffix(suffix),
+                    )
+                    .changed();
+                ui.end_row();
+            };
+            match part {
+                CalibrationPart::Tolerance {
+                    peg_diameter,
+                    min_clearance,
+                    step,
+                    count,
+                    thickness,
+                } => {
+                    row(ui, "Peg diameter:", peg_diameter, 1.0..=50.0, " mm");
+                    row(ui, "Smallest clearance:", min_clearance, 0.0..=2.0, " mm");
+                    row(ui, "Clearance step:", step, 0.0..=1.0, " mm");
+                    row(ui, "Thickness:", thickness, 1.0..=30.0, " mm");
+                    ui.label("Holes:");
+                    changed |= ui.add(egui::DragValue::new(count).range(1..=20)).changed();
+                    ui.end_row();
+                }
+                CalibrationPart::Overhang {
+                    min_angle,
+                    max_angle,
+                    step,
+                    height,
+                } => {
+                    row(ui, "From:", min_angle, 0.0..=80.0, "°");
+                    row(ui, "To:", max_angle, 0.0..=80.0, "°");
+                    row(ui, "Step:", step, 1.0..=40.0, "°");
+                    row(ui, "Fin height:", height, 2.0..=50.0, " mm");
+                }
+                CalibrationPart::TemperatureTower {
+                    start_temp,
+                    end_temp,
+                    step,
+                    section_height,
+                } => {
+                    row(ui, "Bottom:", start_temp, 150.0..=320.0, " °C");
+                    row(ui, "Top:", end_temp, 150.0..=320.0, " °C");
+                    row(ui, "Step:", step, 1.0..=50.0, " °C");
+                    row(ui, "Section height:", section_height, 2.0..=50.0, " mm");
+                }
+            }
+        });
+
+    // Heights to enter as temperature changes in the slicer.
+    let sections = part.tower_sections();
+    if !sections.is_empty() {
+        ui.weak("Temperature changes:");
+        for (temp, z) in sections {
+            ui.weak(format!("  from Z {z:.1} mm: {temp:.0} °C"));
+        }
+    }
+    changed
+}
+
+/// Position on the face and rotation about its normal of a placed element or
+/// label. Returns whether any changed.
+fn placement_fields(ui: &mut egui::Ui, origin: &mut [f32; 3], rotation: &mut f32) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for (axis, label) in AXIS_LABELS.iter().enumerate() {
+            ui.label(*label);
+            changed |= ui
+                .add(egui::DragValue::new(&mut origin[axis]).speed(0.1))
+                .changed();
+        }
+        ui.label("Rotation:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(rotation)
+                    .range(-180.0..=180.0)
+                    .speed(1.0)
+                    .suffix("°"),
+            )
+            .changed();
+        changed
+    })
+    .inner
+}
+
+/// Shift and turn of a transform step. Returns whether either changed.
+fn transform_fields(ui: &mut egui::Ui, transform: &mut RigidTransform) -> bool {
+    let mut changed = false;
+    egui::Grid::new("mesh_edit_transform")
+        .num_columns(4)
+        .show(ui, |ui| {
+            ui.label("Move:");
+            for value in &mut transform.translation {
+                changed |= ui
+                    .add(egui::DragValue::new(value).speed(0.5).suffix(" mm"))
+                    .changed();
+            }
+            ui.end_row();
+
+            ui.label("Rotate:");
+            for value in &mut transform.rotation_deg {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(value)
+                            .range(-360.0..=360.0)
+                            .speed(1.0)
+                            .suffix("°"),
+                    )
+                    .changed();
+            }
+            ui.end_row();
+        });
+    changed
+}
+
+/// Text, font, size, and depth of a text label. Returns whether any changed.
+fn text_fields(ui: &mut egui::Ui, id_salt: &str, label: &mut TextLabel) -> bool {
+    let mut changed = ui
+        .add(
+            egui::TextEdit::multiline(&mut label.text)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        )
+        .changed();
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Mode:");
+            ui.horizontal(|ui| {
+                for mode in [TextMode::Emboss, TextMode::Engrave] {
+                    changed |= ui
+                        .radio_value(&mut label.mode, mode, mode.label())
+                        .changed();
+                }
+            });
+            ui.end_row();
+            ui.label("Font:");
+            ui.horizontal(|ui| {
+                for font in TextFont::ALL {
+                    changed |= ui
+                        .radio_value(&mut label.font, font, font.label())
+                        .changed();
+                }
+            });
+            ui.end_row();
+            ui.label("Height:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut label.size)
+                        .range(0.5..=200.0)
+                        .speed(0.1)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+            ui.label("Depth:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut label.depth)
+                        .range(0.05..=50.0)
+                        .speed(0.05)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+        });
+    changed
+}
+
+/// Pattern, face, and size of a knurl. Returns whether any changed.
+fn knurl_fields(ui: &mut egui::Ui, id_salt: &str, knurl: &mut Knurl) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Pattern:");
+            ui.horizontal(|ui| {
+                for pattern in KnurlPattern::ALL {
+                    changed |= ui
+                        .radio_value(&mut knurl.pattern, pattern, pattern.label())
+                        .changed();
+                }
+            });
+            ui.end_row();
+            ui.label("Face:");
+            ui.horizontal(|ui| {
+                let round = matches!(knurl.shape, KnurlShape::Cylinder);
+                if ui.radio(round, "Round").clicked() && !round {
+                    knurl.shape = KnurlShape::Cylinder;
+                    changed = true;
+                }
+                if ui.radio(!round, "Flat").clicked() && round {
+                    knurl.shape = KnurlShape::Pad {
+                        width: 20.0,
+                        length: 20.0,
+                    };
+                    changed = true;
+                }
+            });
+            ui.end_row();
+
+            let mut row = |ui: &mut egui::Ui, label: &str, value: &mut f32, range| {
+                ui.label(label);
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(value)
+                            .range(range)
+                            .speed(0.05)
+                            .suffix(" mm"),
+                    )
+                    .changed();
+                ui.end_row();
+            };
+            row(ui, "Pitch:", &mut knurl.pitch, 0.2..=20.0);
+            row(ui, "Depth:", &mut knurl.depth, 0.05..=5.0);
+            if let KnurlShape::Pad { width, length } = &mut knurl.shape {
+                row(ui, "Width:", width, 1.0..=500.0);
+                row(ui, "Length:", length, 1.0..=500.0);
+            }
+        });
+    changed
+}
+
+/// Kind and dimensions of a design element. Returns whether any changed.
+fn design_fields(ui: &mut egui::Ui, id_salt: &str, element: &mut DesignElement) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id_salt)
+        .num_columns(2)
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Element:");
+            let mut kind = element.kind();
+            egui::ComboBox::from_id_salt((id_salt, "kind"))
+                .selected_text(DesignElement::KINDS[kind])
+                .show_ui(ui, |ui| {
+                    for (index, label) in DesignElement::KINDS.iter().enumerate() {
+                        ui.selectable_value(&mut kind, index, *label);
+                    }
+                });
+            if kind != element.kind() {
+                *element = DesignElement::of_kind(kind);
+                changed = true;
+            }
+            ui.end_row();
+
+            if let DesignElement::ScrewBoss { size, .. } = element {
+                ui.label("Insert:");
+                egui::ComboBox::from_id_salt((id_salt, "size"))
+                    .selected_text(size.label())
+                    .show_ui(ui, |ui| {
+                        for option in MetricSize::ALL {
+                            changed |= ui.selectable_value(size, option, option.label()).changed();
+                        }
+                    });
+                ui.end_row();
+            }
+
+            let mut row = |ui: &mut egui::Ui, label: &str, value: &mut f32, range| {
+                ui.label(label);
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(value)
+                            .range(range)
+                            .speed(0.05)
+                            .suffix(" mm"),
+                    )
+                    .changed();
+                ui.end_row();
+            };
+            match element {
+                DesignElement::ScrewBoss { height, wall, .. } => {
+                    row(ui, "Height:", height, 1.0..=200.0);
+                    row(ui, "Wall:", wall, 0.4..=20.0);
+                }
+                DesignElement::SnapFit {
+                    length,
+                    thickness,
+                    width,
+                    hook,
+                } => {
+                    row(ui, "Length:", length, 1.0..=100.0);
+                    row(ui, "Thickness:", thickness, 0.4..=10.0);
+                    row(ui, "Width:", width, 0.4..=100.0);
+                    row(ui, "Hook:", hook, 0.1..=10.0);
+                }
+                DesignElement::LivingHinge {
+                    flap_length,
+                    width,
+                    thickness,
+                    hinge_thickness,
+                    gap,
+                } => {
+                    row(ui, "Flap length:", flap_length, 1.0..=200.0);
+                    row(ui, "Width:", width, 0.4..=200.0);
+                    row(ui, "Thickness:", thickness, 0.2..=20.0);
+                    row(ui, "Hinge thickness:", hinge_thickness, 0.1..=5.0);
+                    row(ui, "Hinge length:", gap, 0.1..=20.0);
+                }
+            }
+        });
+    changed
+}
@@ -0,0 +1,319 @@
+//! PLY reading (ASCII and binary, either byte order): vertex positions and
+//! faces, polygons split into triangle fans. Other elements and properties
+//! (colors, normals, confidence values from scanners) are skipped.
+
+use std::path::Path;
+
+use kernel_api::TriMesh;
+use thiserror::Error;
+
+use crate::import::{file_origin, FileMesh};
+use crate::stl::Progress;
+
+#[derive(Debug, Error)]
+pub enum PlyError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("header: {0}")]
+    Header(String),
+    #[error("file ends before its last {0}")]
+    Truncated(String),
+    #[error("{0}")]
+    Parse(String),
+    #[error("file contains no faces")]
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(String, Scalar),
+    /// A count of type `.1` followed by that many values of type `.2`.
+    List(String, Scalar, Scalar),
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List(name, ..) => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Read a PLY file into an indexed mesh (without normals), relative to its
+/// first vertex so double-precision coordinates keep their detail.
+pub fn read_ply(path: &Path) -> Result<FileMesh, PlyError> {
+    read_ply_with_progress(path, |_| {})
+}
+
+/// [`read_ply`] that calls `progress` with the fraction done (0 to 1), about
+/// once per percent.
+pub fn read_ply_with_progress(
+    path: &Path,
+    progress: impl FnMut(f32),
+) -> Result<FileMesh, PlyError> {
+    let bytes = std::fs::read(path)?;
+    let mesh = parse(&bytes, Progress::new(progress))?;
+    if mesh.mesh.indices.is_empty() {
+        return Err(PlyError::Empty);
+    }
+    Ok(mesh)
+}
+
+fn parse<F: FnMut(f32)>(bytes: &[u8], mut progress: Progress<F>) -> Result<FileMesh, PlyError> {
+    let (encoding, elements, body) = parse_header(bytes)?;
+    let text;
+    let mut data = match encoding {
+        Encoding::Ascii => {
+            text = String::from_utf8_lossy(&bytes[body..]);
+            Data::Ascii(text.split_whitespace())
+        }
+        Encoding::LittleEndian | Encoding::BigEndian => Data::Binary {
+            bytes: &bytes[body..],
+            big_endian: encoding == Encoding::BigEndian,
+        },
+    };
+
+    let total: usize = elements.iter().map(|element| element.count).sum();
+    let mut done = 0;
+    let mut mesh = TriMesh::default();
+    let mut origin = None;
+    let mut list = Vec::new();
+    for element in &elements {
+        let truncated = || PlyError::Truncated(element.name.clone());
+        let position = ["x", "y", "z"].map(|axis| {
+            element
+                .properties
+                .iter()
+                .position(|property| matches!(property, Property::Scalar(name, _) if name == axis))
+        });
+        let is_vertex = element.name == "vertex";
+        let face_list = (element.name == "face")
+            .then(|| {
+                element.properties.iter().position(|property| {
+                    matches!(property, Property::List(..))
+                        && matches!(property.name(), "vertex_indices" | "vertex_index")
+                })
+            })
+            .flatten();
+        if is_vertex && position.iter().any(Option::is_none) {
+            return Err(PlyError::Header("vertices have no x, y, and z".to_string()));
+        }
+
+        for _ in 0..element.count {
+            if done % 4096 == 0 {
+                progress.report(done as f32 / total.max(1) as f32);
+            }
+            done += 1;
+            let mut point = [0.0_f64; 3];
+            for (i, property) in element.properties.iter().enumerate() {
+                let mut next = |scalar: Scalar| data.next(scalar)?.ok_or_else(truncated);
+                match property {
+                    Property::Scalar(_, scalar) => {
+                        let value = next(*scalar)?;
+                        if let Some(axis) = position.iter().position(|p| *p == Some(i)) {
+                            point[axis] = value;
+                        }
+                    }
+                    Property::List(_, count, item) => {
+                        let count = next(*count)? as usize;
+                        list.clear();
+                        for _ in 0..count {
+                            list.push(next(*item)?);
+                        }
+                        if face_list == Some(i) {
+                            add_face(&mut mesh, &list)?;
+                        }
+                    }
+                }
+            }
+            if is_vertex {
+                let origin = *origin.get_or_insert_with(|| file_origin(point));
+                mesh.positions
+                    .push([0, 1, 2].map(|i| (point[i] - origin[i]) as f32));
+            }
+        }
+    }
+    // Faces may come before vertices; check the indices once all are read.
+    let count = mesh.positions.len() as u32;
+    if mesh.indices.iter().any(|index| *index >= count) {
+        return Err(PlyError::Parse("vertex index out of range".to_string()));
+    }
+    Ok(FileMesh {
+        mesh,
+        origin: origin.unwrap_or_default(),
+    })
+}
+
+fn add_face(mesh: &mut TriMesh, corners: &[f64]) -> Result<(), PlyError> {
+    if corners.iter().any(|index| *index < 0.0) {
+        return Err(PlyError::Parse("negative vertex index".to_string()));
+    }
+    for i in 1..corners.len().saturating_sub(1) {
+        mesh.indices
+            .extend([corners[0], corners[i], corners[i + 1]].map(|index| index as u32));
+    }
+    Ok(())
+}
+
+/// The encoding, elements, and where the data starts.
+fn parse_header(bytes: &[u8]) -> Result<(Encoding, Vec<Element>, usize), PlyError> {
+    let header = |message: &str| PlyError::Header(message.to_string());
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or_else(|| header("no end_header line"))?;
+    let mut body = end + END.len();
+    if bytes.get(body) == Some(&b'\r') {
+        body += 1;
+    }
+    if bytes.get(body) == Some(&b'\n') {
+        body += 1;
+    }
+
+    let text = String::from_utf8_lossy(&bytes[..end]);
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(header("not a PLY file"));
+    }
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["format", format, _] => {
+                encoding = Some(match format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(header(&format!("unknown format \"{format}\""))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| header(&format!("bad count for element \"{name}\"")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let (Some(count), Some(item)) = (Scalar::parse(count), Scalar::parse(item)) else {
+                    return Err(header(&format!("unknown type of property \"{name}\"")));
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| header("property before any element"))?
+                    .properties
+                    .push(Property::List(name.to_string(), count, item));
+            }
+            ["property", scalar, name] => {
+                let scalar = Scalar::parse(scalar)
+                    .ok_or_else(|| header(&format!("unknown type of property \"{name}\"")))?;
+                elements
+                    .last_mut()
+                    .ok_or_else(|| header("property before any element"))?
+                    .properties
+                    .push(Property::Scalar(name.to_string(), scalar));
+            }
+            _ => {}
+        }
+    }
+    let encoding = encoding.ok_or_else(|| header("no format line"))?;
+    Ok((encoding, elements, body))
+}
+
+/// The data after the header.
+enum Data<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Data<'_> {
+    /// The next value, None at the end of the data.
+    fn next(&mut self, scalar: Scalar) -> Result<Option<f64>, PlyError> {
+        match self {
+            Data::Ascii(words) => words
+                .next()
+                .map(|word| {
+                    word.parse()
+                        .map_err(|_| PlyError::Parse(format!("\"{word}\" is not a number")))
+                })
+                .transpose(),
+            Data::Binary { bytes, big_endian } => {
+                let size = scalar.size();
+                if bytes.len() < size {
+                    return Ok(None);
+                }
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+                let mut raw = [0u8; 8];
+                raw[..size].copy_from_slice(value);
+                if *big_endian {
+                    raw[..size].reverse();
+                }
+                let [a, b, c, d, ..] = raw;
+                Ok(Some(match scalar {
+                    Scalar::I8 => a as i8 as f64,
+                    Scalar::U8 => a as f64,
+                    Scalar::I16 => i16::from_le_bytes([a, b]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([a, b]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([a, b, c, d]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([a, b, c, d]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([a, b, c, d]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(raw),
+                }))
+            }
+        }
+    }
+}
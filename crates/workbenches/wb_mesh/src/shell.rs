@@ -0,0 +1,131 @@
+//! Inner walls for hollowing a closed mesh.
+//!
+//! The wall is the surface lying a given depth inside the mesh. It is traced
+//! on a grid from the distance to the mesh (marching tetrahedra), so unlike
+//! moving each vertex along its normal it never folds over itself: thin parts
+//! are left solid and concave corners come out rounded. The grid's six
+//! tetrahedra per cell share their faces with the neighboring cells, so the
+//! traced surface is always closed; decimating it afterwards keeps it so.
+
+use std::collections::HashMap;
+
+use core_document::bvh::{Bvh, Triangle};
+use glam::{UVec3, Vec3};
+
+use crate::decimate;
+use crate::mesh::IndexedMesh;
+
+/// Grid cells along the longest side of the mesh.
+const GRID_RESOLUTION: f32 = 96.0;
+
+/// The traced surface is decimated to this fraction of its triangles; most of
+/// them are slivers where it passes close to a grid point.
+const KEPT_TRIANGLES: f32 = 0.1;
+
+/// Corners of the six tetrahedra a grid cell is split into, as offsets from
+/// its lowest corner. Each runs from that corner to the opposite one, so
+/// neighboring cells split their shared faces the same way.
+const TETRAHEDRA: [[[u32; 3]; 4]; 6] = [
+    [[0, 0, 0], [1, 0, 0], [1, 1, 0], [1, 1, 1]],
+    [[0, 0, 0], [1, 0, 0], [1, 0, 1], [1, 1, 1]],
+    [[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 1, 1]],
+    [[0, 0, 0], [0, 1, 0], [0, 1, 1], [1, 1, 1]],
+    [[0, 0, 0], [0, 0, 1], [1, 0, 1], [1, 1, 1]],
+    [[0, 0, 0], [0, 0, 1], [0, 1, 1], [1, 1, 1]],
+];
+
+/// Closed surface `depth` inside a closed mesh, facing outwards (empty when
+/// the mesh is nowhere thicker than twice the depth).
+pub fn inner_surface(mesh: &IndexedMesh, depth: f32) -> IndexedMesh {
+    let Some((min, max)) = mesh.bounds() else {
+        return IndexedMesh::default();
+    };
+    let bvh = Bvh::new(
+        mesh.triangles
+            .iter()
+            .filter_map(|triangle| Triangle::new(triangle.map(|v| mesh.positions[v as usize])))
+            .collect(),
+    );
+    if bvh.is_empty() || depth <= 0.0 {
+        return IndexedMesh::default();
+    }
+
+    // One cell of margin keeps the outermost samples outside the mesh.
+    let cell = (max - min).max_element() / GRID_RESOLUTION;
+    let origin = min - Vec3::splat(cell);
+    let counts = ((max - min) / cell).ceil().as_uvec3() + UVec3::splat(3);
+    let index = |p: UVec3| (p.x + counts.x * (p.y + counts.y * p.z)) as usize;
+    let point = |p: UVec3| origin + p.as_vec3() * cell;
+    let centroid =
+        |corners: &[UVec3]| corners.iter().map(|&c| point(c)).sum::<Vec3>() / corners.len() as f32;
+
+    // How far each sample lies above the wall: negative in the cavity.
+    let mut values = vec![0.0f32; (counts.x * counts.y * counts.z) as usize];
+    for z in 0..counts.z {
+        for y in 0..counts.y {
+            for x in 0..counts.x {
+                let p = point(UVec3::new(x, y, z));
+                let distance = bvh.closest(p).map_or(f32::INFINITY, |hit| hit.distance);
+                let inside = if bvh.contains(p) { distance } else { -distance };
+                // A sample exactly on the wall would give zero-length edges.
+                let value = depth - inside;
+                values[index(UVec3::new(x, y, z))] =
+                    if value == 0.0 { f32::EPSILON } else { value };
+            }
+        }
+    }
+
+    let mut surface = IndexedMesh::default();
+    let mut crossings: HashMap<(usize, usize), u32> = HashMap::new();
+    let mut crossing = |a: UVec3, b: UVec3| {
+        let (ia, ib) = (index(a), index(b));
+        *crossings
+            .entry((ia.min(ib), ia.max(ib)))
+            .or_insert_with(|| {
+                let (va, vb) = (values[ia], values[ib]);
+                surface
+                    .positions
+                    .push(point(a).lerp(point(b), va / (va - vb)));
+                (surface.positions.len() - 1) as u32
+            })
+    };
+    let mut triangles = Vec::new();
+    for z in 0..counts.z - 1 {
+        for y in 0..counts.y - 1 {
+            for x in 0..counts.x - 1 {
+                let base = UVec3::new(x, y, z);
+                for tetrahedron in TETRAHEDRA {
+                    let corners = tetrahedron.map(|offset| base + UVec3::from(offset));
+                    let (cavity, wall): (Vec<UVec3>, Vec<UVec3>) =
+                        corners.iter().partition(|&&c| values[index(c)] < 0.0);
+                    let pieces: Vec<[(UVec3, UVec3); 3]> =
+                        match (cavity.as_slice(), wall.as_slice()) {
+                            ([a], [b, c, d]) | ([b, c, d], [a]) => {
+                                vec![[(*a, *b), (*a, *c), (*a, *d)]]
+                            }
+                            ([a, b], [c, d]) => {
+                                vec![
+                                    [(*a, *c), (*a, *d), (*b, *d)],
+                                    [(*a, *c), (*b, *d), (*b, *c)],
+                                ]
+                            }
+                            _ => continue,
+                        };
+                    // Face from the cavity corners towards the others.
+                    let outwards = centroid(&wall) - centroid(&cavity);
+                    for piece in pieces {
+                        triangles.push((piece.map(|(a, b)| crossing(a, b)), outwards));
+                    }
+                }
+            }
+        }
+    }
+    for (mut triangle, outwards) in triangles {
+        let [a, b, c] = triangle.map(|v| surface.positions[v as usize]);
+        if (b - a).cross(c - a).dot(outwards) < 0.0 {
+            triangle.swap(1, 2);
+        }
+        surface.triangles.push(triangle);
+    }
+    decimate::decimate(&surface, KEPT_TRIANGLES)
+}
@@ -0,0 +1,251 @@
+//! STL reading (binary and ASCII) and writing (binary, or ASCII for the
+//! clipboard).
+
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::Path;
+
+use glam::Vec3;
+use kernel_api::TriMesh;
+use thiserror::Error;
+
+/// Size of the binary header and of one binary triangle record, in bytes.
+const BINARY_HEADER: usize = 84;
+const BINARY_TRIANGLE: usize = 50;
+/// Files are read in chunks of this size so reading large files can report progress.
+const READ_CHUNK: usize = 4 << 20;
+/// Share of the reported progress taken by reading the file (the rest is parsing).
+const READ_SHARE: f32 = 0.3;
+
+#[derive(Debug, Error)]
+pub enum StlError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("file is too short for its triangle count")]
+    Truncated,
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("file contains no triangles")]
+    Empty,
+}
+
+/// Read an STL file into an unwelded mesh (three vertices per triangle).
+pub fn read_stl(path: &Path) -> Result<TriMesh, StlError> {
+    read_stl_with_progress(path, |_| {})
+}
+
+/// [`read_stl`] that calls `progress` with the fraction done (0 to 1), about
+/// once per percent.
+pub fn read_stl_with_progress(path: &Path, progress: impl FnMut(f32)) -> Result<TriMesh, StlError> {
+    let mut progress = Progress::new(progress);
+    let mut file = std::fs::File::open(path)?;
+    let length = file.metadata()?.len() as usize;
+    let mut bytes = Vec::with_capacity(length);
+    loop {
+        let read = (&mut file)
+            .take(READ_CHUNK as u64)
+            .read_to_end(&mut bytes)?;
+        if read == 0 {
+            break;
+        }
+        progress.report(bytes.len() as f32 / length.max(1) as f32 * READ_SHARE);
+    }
+    let mut parsed = |fraction: f32| progress.report(READ_SHARE + fraction * (1.0 - READ_SHARE));
+    let mesh = if is_binary(&bytes) {
+        parse_binary(&bytes, &mut parsed)?
+    } else {
+        parse_ascii(&String::from_utf8_lossy(&bytes), &mut parsed)?
+    };
+    if mesh.indices.is_empty() {
+        return Err(StlError::Empty);
+    }
+    Ok(mesh)
+}
+
+/// Read ASCII STL text, such as data pasted from another program.
+pub fn parse_stl_text(text: &str) -> Result<TriMesh, StlError> {
+    let mesh = parse_ascii(text, &mut |_| {})?;
+    if mesh.indices.is_empty() {
+        return Err(StlError::Empty);
+    }
+    Ok(mesh)
+}
+
+/// ASCII STL text with one `solid` per named mesh.
+pub fn to_ascii_stl(meshes: &[(&str, &TriMesh)]) -> String {
+    let mut out = String::new();
+    for (name, mesh) in meshes {
+        // The name runs to the end of the line.
+        let name = name.replace(['\n', '\r'], " ");
+        let _ = writeln!(out, "solid {name}");
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(mesh.positions[triangle[i] as usize]));
+            let [nx, ny, nz] = (b - a).cross(c - a).normalize_or_zero().to_array();
+            let _ = writeln!(out, "  facet normal {nx} {ny} {nz}");
+            out.push_str("    outer loop\n");
+            for [x, y, z] in [a, b, c].map(|corner| corner.to_array()) {
+                let _ = writeln!(out, "      vertex {x} {y} {z}");
+            }
+            out.push_str("    endloop\n  endfacet\n");
+        }
+        let _ = writeln!(out, "endsolid {name}");
+    }
+    out
+}
+
+/// Write `mesh` as a binary STL file, with `name` in the header and facet
+/// normals from the triangles' winding.
+pub fn write_stl(mesh: &TriMesh, name: &str, path: &Path) -> Result<(), StlError> {
+    std::fs::write(path, to_binary_stl(mesh, name))?;
+    Ok(())
+}
+
+pub fn to_binary_stl(mesh: &TriMesh, name: &str) -> Vec<u8> {
+    let count = mesh.indices.len() / 3;
+    let mut bytes = Vec::with_capacity(BINARY_HEADER + count * BINARY_TRIANGLE);
+    let mut header = [b' '; 80];
+    // A header starting with "solid" would make readers take the file for ASCII.
+    let name = name.strip_prefix("solid").unwrap_or(name).as_bytes();
+    let length = name.len().min(header.len());
+    header[..length].copy_from_slice(&name[..length]);
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&(count as u32).to_le_bytes());
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(mesh.positions[triangle[i] as usize]));
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        for vector in [normal, a, b, c] {
+            for component in vector.to_array() {
+                bytes.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&[0, 0]);
+    }
+    bytes
+}
+
+/// Binary files may also start with "solid", so trust the size written in the
+/// header when it matches the file length.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() >= BINARY_HEADER {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if BINARY_HEADER + count * BINARY_TRIANGLE == bytes.len() {
+            return true;
+        }
+    }
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    !bytes[start..].starts_with(b"solid")
+}
+
+/// Forwards progress only when it advanced by at least a percent.
+pub(crate) struct Progress<F> {
+    callback: F,
+    reported: f32,
+}
+
+impl<F: FnMut(f32)> Progress<F> {
+    pub(crate) fn new(callback: F) -> Self {
+        Self {
+            callback,
+            reported: 0.0,
+        }
+    }
+
+    pub(crate) fn report(&mut self, fraction: f32) {
+        if fraction - self.reported >= 0.01 {
+            self.reported = fraction;
+            (self.callback)(fraction);
+        }
+    }
+}
+
+fn parse_binary(bytes: &[u8], progress: &mut impl FnMut(f32)) -> Result<TriMesh, StlError> {
+    if bytes.len() < BINARY_HEADER {
+        return Err(StlError::Truncated);
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    if bytes.len() < BINARY_HEADER + count * BINARY_TRIANGLE {
+        return Err(StlError::Truncated);
+    }
+    let float = |offset: usize| {
+        f32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+    let vector = |offset: usize| [float(offset), float(offset + 4), float(offset + 8)];
+
+    let mut mesh = TriMesh::default();
+    mesh.positions.reserve(count * 3);
+    mesh.normals.reserve(count * 3);
+    mesh.indices.reserve(count * 3);
+    for triangle in 0..count {
+        if triangle % 4096 == 0 {
+            progress(triangle as f32 / count as f32);
+        }
+        let record = BINARY_HEADER + triangle * BINARY_TRIANGLE;
+        let normal = vector(record);
+        let base = mesh.positions.len() as u32;
+        for corner in 0..3 {
+            mesh.positions.push(vector(record + 12 + corner * 12));
+            mesh.normals.push(normal);
+        }
+        mesh.indices.extend([base, base + 1, base + 2]);
+    }
+    Ok(mesh)
+}
+
+fn parse_ascii(text: &str, progress: &mut impl FnMut(f32)) -> Result<TriMesh, StlError> {
+    let mut mesh = TriMesh::default();
+    let mut normal = [0.0; 3];
+    let mut corners = Vec::with_capacity(3);
+    let mut parsed = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        parsed += line.len() + 1;
+        if index % 16384 == 0 {
+            progress(parsed as f32 / text.len().max(1) as f32);
+        }
+        let mut words = line.split_whitespace();
+        let error = |message: &str| StlError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+        let vector = |words: &mut std::str::SplitWhitespace| -> Result<[f32; 3], StlError> {
+            let mut value = [0.0; 3];
+            for component in &mut value {
+                *component = words
+                    .next()
+                    .and_then(|word| word.parse().ok())
+                    .ok_or_else(|| error("expected three numbers"))?;
+            }
+            Ok(value)
+        };
+        match words.next() {
+            Some("facet") => {
+                if words.next() == Some("normal") {
+                    normal = vector(&mut words)?;
+                }
+                corners.clear();
+            }
+            Some("vertex") => corners.push(vector(&mut words)?),
+            Some("endfacet") => {
+                if corners.len() != 3 {
+                    return Err(error("facet does not have three vertices"));
+                }
+                let base = mesh.positions.len() as u32;
+                for corner in corners.drain(..) {
+                    mesh.positions.push(corner);
+                    mesh.normals.push(normal);
+                }
+                mesh.indices.extend([base, base + 1, base + 2]);
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
@@ -0,0 +1,455 @@
+//! Text labels on mesh faces: glyph outlines of a bundled font, raised from
+//! the face (emboss) or cut into it (engrave).
+
+use std::collections::{HashMap, HashSet};
+
+use ab_glyph::{Font, FontRef, OutlineCurve};
+use glam::{Quat, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::cut::{contains, fill_loops, signed_area};
+use crate::design::distance_to_triangle;
+use crate::mesh::{self, edges_of, IndexedMesh};
+
+/// Longest segment (mm) glyph curves are split into.
+const CURVE_TOLERANCE: f32 = 0.2;
+/// Triangles this close (mm) to the plane of the picked one belong to the
+/// same flat face.
+const FACE_TOLERANCE: f32 = 1e-3;
+/// Lines of text are this many text heights apart.
+const LINE_SPACING: f32 = 1.6;
+
+/// Bundled typeface of a label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFont {
+    /// Ubuntu Light.
+    Sans,
+    /// Hack, a monospace font with heavier strokes.
+    Mono,
+}
+
+impl TextFont {
+    pub const ALL: [TextFont; 2] = [TextFont::Sans, TextFont::Mono];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TextFont::Sans => "Sans",
+            TextFont::Mono => "Mono",
+        }
+    }
+
+    fn font(self) -> FontRef<'static> {
+        let data = match self {
+            TextFont::Sans => epaint_default_fonts::UBUNTU_LIGHT,
+            TextFont::Mono => epaint_default_fonts::HACK_REGULAR,
+        };
+        FontRef::try_from_slice(data).expect("bundled fonts should parse")
+    }
+}
+
+/// Whether the text stands out of the face or is cut into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextMode {
+    Emboss,
+    Engrave,
+}
+
+impl TextMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            TextMode::Emboss => "Emboss",
+            TextMode::Engrave => "Engrave",
+        }
+    }
+}
+
+/// Text placed on a face, centered on the point it was placed at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextLabel {
+    /// One or more lines.
+    pub text: String,
+    pub font: TextFont,
+    /// Height of capital letters (mm).
+    pub size: f32,
+    /// Height of embossed text or depth of engraved text (mm).
+    pub depth: f32,
+    pub mode: TextMode,
+}
+
+impl Default for TextLabel {
+    fn default() -> Self {
+        Self {
+            text: "Label".to_string(),
+            font: TextFont::Mono,
+            size: 8.0,
+            depth: 0.8,
+            mode: TextMode::Emboss,
+        }
+    }
+}
+
+impl TextLabel {
+    /// Name for the feature, e.g. `Emboss "Label"`.
+    pub fn name(&self) -> String {
+        let first_line = self.text.lines().next().unwrap_or_default();
+        let mut shown: String = first_line.chars().take(20).collect();
+        if shown.len() < self.text.len() {
+            shown.push('…');
+        }
+        format!("{} \"{shown}\"", self.mode.label())
+    }
+
+    /// Closed outlines of the glyphs (mm), centered on the origin. Outer
+    /// outlines run counter-clockwise and the holes in them clockwise.
+    pub fn outlines(&self) -> Vec<Vec<Vec2>> {
+        let font = self.font.font();
+        let cap_height = font
+            .outline(font.glyph_id('H'))
+            .map(|outline| outline.bounds.min.y)
+            .filter(|height| *height > 0.0)
+            .unwrap_or(font.ascent_unscaled() * 0.7);
+        let scale = self.size.max(0.5) / cap_height;
+        let step = CURVE_TOLERANCE / scale;
+
+        let mut outlines: Vec<Vec<Vec2>> = Vec::new();
+        for (line, text) in self.text.lines().enumerate() {
+            let y = -(line as f32) * LINE_SPACING * cap_height;
+            let mut x = 0.0;
+            let mut previous = None;
+            for c in text.chars() {
+                let glyph = font.glyph_id(c);
+                if let Some(previous) = previous {
+                    x += font.kern_unscaled(previous, glyph);
+                }
+                if let Some(outline) = font.outline(glyph) {
+                    let offset = Vec2::new(x, y);
+                    outlines.extend(contours(&outline.curves, step).into_iter().map(|contour| {
+                        contour.into_iter().map(|p| (p + offset) * scale).collect()
+                    }));
+                }
+                x += font.h_advance_unscaled(glyph);
+                previous = Some(glyph);
+            }
+        }
+        outlines.retain(|contour| signed_area(contour).abs() > 1e-6);
+
+        let points = outlines.iter().flatten();
+        let min = points.clone().fold(Vec2::splat(f32::MAX), |a, &p| a.min(p));
+        let max = points.fold(Vec2::splat(f32::MIN), |a, &p| a.max(p));
+        let center = (min + max) * 0.5;
+        for contour in &mut outlines {
+            for point in contour.iter_mut() {
+                *point -= center;
+            }
+        }
+        // Fonts differ in which way they run outlines; go by nesting instead.
+        let levels: Vec<usize> = outlines
+            .iter()
+            .enumerate()
+            .map(|(i, contour)| {
+                outlines
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, other)| j != i && contains(other, contour[0]))
+                    .count()
+            })
+            .collect();
+        for (contour, level) in outlines.iter_mut().zip(levels) {
+            if (signed_area(contour) > 0.0) != (level % 2 == 0) {
+                contour.reverse();
+            }
+        }
+        outlines
+    }
+
+    /// `mesh` with the text placed at `origin` on the face with outward
+    /// `normal`, its baseline turned by `rotation` degrees about the normal.
+    /// Engraving needs a flat face around the text; if the text does not fit
+    /// on it, the mesh is returned unchanged.
+    pub fn apply(
+        &self,
+        mesh: &IndexedMesh,
+        origin: Vec3,
+        normal: Vec3,
+        rotation: f32,
+    ) -> IndexedMesh {
+        let outlines = self.outlines();
+        let depth = self.depth.max(0.05);
+        match self.mode {
+            TextMode::Emboss => {
+                let frame = Frame::new(origin, normal, rotation);
+                let mut result = mesh.clone();
+                mesh::merge(&mut result, &extrude(&frame, &outlines, depth));
+                result
+            }
+            TextMode::Engrave => engrave(mesh, &outlines, origin, normal, rotation, depth)
+                .unwrap_or_else(|| mesh.clone()),
+        }
+    }
+}
+
+/// Outlines of a glyph in font units, split into straight segments no
+/// longer than about `step`.
+fn contours(curves: &[OutlineCurve], step: f32) -> Vec<Vec<Vec2>> {
+    let point = |p: ab_glyph::Point| Vec2::new(p.x, p.y);
+    let mut contours: Vec<Vec<Vec2>> = Vec::new();
+    for curve in curves {
+        let (start, controls): (Vec2, Vec<Vec2>) = match *curve {
+            OutlineCurve::Line(a, b) => (point(a), vec![point(b)]),
+            OutlineCurve::Quad(a, b, c) => (point(a), vec![point(b), point(c)]),
+            OutlineCurve::Cubic(a, b, c, d) => (point(a), vec![point(b), point(c), point(d)]),
+        };
+        let end = controls[controls.len() - 1];
+        match contours.last_mut() {
+            Some(contour) if contour.last() == Some(&start) => {}
+            _ => contours.push(vec![start]),
+        }
+        let contour = contours.last_mut().expect("a contour was just pushed");
+        let length = controls
+            .iter()
+            .scan(start, |from, &to| {
+                Some(from.distance(std::mem::replace(from, to)))
+            })
+            .sum::<f32>();
+        let segments = if controls.len() == 1 {
+            1
+        } else {
+            ((length / step).ceil() as usize).clamp(1, 16)
+        };
+        for k in 1..=segments {
+            let t = k as f32 / segments as f32;
+            contour.push(match controls.as_slice() {
+                [b, c] => start.lerp(*b, t).lerp(b.lerp(*c, t), t),
+                [b, c, d] => {
+                    let (ab, bc, cd) = (start.lerp(*b, t), b.lerp(*c, t), c.lerp(*d, t));
+                    ab.lerp(bc, t).lerp(bc.lerp(cd, t), t)
+                }
+                _ => end,
+            });
+        }
+    }
+    for contour in &mut contours {
+        contour.dedup();
+        if contour.len() > 1 && contour.first() == contour.last() {
+            contour.pop();
+        }
+    }
+    contours.retain(|contour| contour.len() >= 3);
+    contours
+}
+
+/// Placement of the text: X along the baseline, Z out of the face.
+struct Frame {
+    origin: Vec3,
+    orientation: Quat,
+}
+
+impl Frame {
+    fn new(origin: Vec3, normal: Vec3, rotation: f32) -> Self {
+        let normal = normal.try_normalize().unwrap_or(Vec3::Z);
+        Self {
+            origin,
+            orientation: Quat::from_rotation_arc(Vec3::Z, normal)
+                * Quat::from_rotation_z(rotation.to_radians()),
+        }
+    }
+
+    fn to_world(&self, point: Vec2, z: f32) -> Vec3 {
+        self.origin + self.orientation * point.extend(z)
+    }
+
+    fn to_flat(&self, point: Vec3) -> Vec2 {
+        (self.orientation.inverse() * (point - self.origin)).truncate()
+    }
+}
+
+/// Add a vertex at height `z` for every outline point; returns the vertex
+/// loops.
+fn add_layer(
+    mesh: &mut IndexedMesh,
+    frame: &Frame,
+    outlines: &[Vec<Vec2>],
+    z: f32,
+) -> Vec<Vec<u32>> {
+    outlines
+        .iter()
+        .map(|contour| {
+            contour
+                .iter()
+                .map(|&point| {
+                    mesh.positions.push(frame.to_world(point, z));
+                    (mesh.positions.len() - 1) as u32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Side walls between two layers of the same outlines, facing out of the
+/// glyphs.
+fn walls(low: &[Vec<u32>], high: &[Vec<u32>]) -> Vec<[u32; 3]> {
+    let mut triangles = Vec::new();
+    for (low, high) in low.iter().zip(high) {
+        for a in 0..low.len() {
+            let b = (a + 1) % low.len();
+            triangles.push([low[a], low[b], high[b]]);
+            triangles.push([low[a], high[b], high[a]]);
+        }
+    }
+    triangles
+}
+
+/// Closed solid of the glyphs, from the face up to `height`.
+fn extrude(frame: &Frame, outlines: &[Vec<Vec2>], height: f32) -> IndexedMesh {
+    let mut solid = IndexedMesh::default();
+    let low = add_layer(&mut solid, frame, outlines, 0.0);
+    let high = add_layer(&mut solid, frame, outlines, height);
+    let flat = |vertex: u32| frame.to_flat(solid.positions[vertex as usize]);
+    let top = fill_loops(&high, flat);
+    let bottom = fill_loops(&low, flat);
+    solid.triangles.extend(top);
+    solid
+        .triangles
+        .extend(bottom.into_iter().map(|[a, b, c]| [a, c, b]));
+    solid.triangles.extend(walls(&low, &high));
+    solid
+}
+
+/// Cut the glyphs `depth` deep into the flat face of `mesh` nearest to
+/// `origin` that faces along `normal`. None if there is no such face or the
+/// text does not fit on it.
+fn engrave(
+    mesh: &IndexedMesh,
+    outlines: &[Vec<Vec2>],
+    origin: Vec3,
+    normal: Vec3,
+    rotation: f32,
+    depth: f32,
+) -> Option<IndexedMesh> {
+    let normal = normal.try_normalize()?;
+    let seed = (0..mesh.triangles.len())
+        .filter(|&t| {
+            mesh.face_normal(&mesh.triangles[t])
+                .try_normalize()
+                .is_some_and(|n| n.dot(normal) > 0.99)
+        })
+        .min_by(|&a, &b| {
+            let distance = |t: usize| {
+                let [a, b, c] = mesh.triangles[t].map(|i| mesh.positions[i as usize]);
+                distance_to_triangle(origin, a, b, c)
+            };
+            distance(a).total_cmp(&distance(b))
+        })?;
+    let face = flat_face(mesh, seed);
+    let loops = face_loops(mesh, &face)?;
+
+    // Work in the plane of the face, so the text lies exactly on it.
+    let corner = mesh.positions[mesh.triangles[seed][0] as usize];
+    let face_normal = mesh.face_normal(&mesh.triangles[seed]).normalize();
+    let frame = Frame::new(
+        origin - face_normal * (origin - corner).dot(face_normal),
+        face_normal,
+        rotation,
+    );
+    let flat = |vertex: u32| frame.to_flat(mesh.positions[vertex as usize]);
+    let rings: Vec<Vec<Vec2>> = loops
+        .iter()
+        .map(|ring| ring.iter().map(|&v| flat(v)).collect())
+        .collect();
+    let fits = outlines.iter().flatten().all(|&point| {
+        rings.iter().all(|ring| {
+            let outer = signed_area(ring) > 0.0;
+            contains(ring, point) == outer
+        })
+    });
+    if !fits {
+        return None;
+    }
+
+    let mut result = mesh.clone();
+    let removed: HashSet<usize> = face.into_iter().collect();
+    result.triangles = (0..mesh.triangles.len())
+        .filter(|t| !removed.contains(t))
+        .map(|t| mesh.triangles[t])
+        .collect();
+    let top = add_layer(&mut result, &frame, outlines, 0.0);
+    let bottom = add_layer(&mut result, &frame, outlines, -depth);
+    let flat = |vertex: u32| frame.to_flat(result.positions[vertex as usize]);
+    // The face around the glyphs (and inside their holes), the floor of the
+    // pocket, and its walls facing into it.
+    let surface: Vec<Vec<u32>> = loops
+        .iter()
+        .cloned()
+        .chain(top.iter().map(|ring| ring.iter().rev().copied().collect()))
+        .collect();
+    let mut triangles = fill_loops(&surface, flat);
+    triangles.extend(fill_loops(&bottom, flat));
+    triangles.extend(walls(&bottom, &top).into_iter().map(|[a, b, c]| [a, c, b]));
+    result.triangles.extend(triangles);
+    result.compact();
+    Some(result)
+}
+
+/// Triangles connected to `seed` that lie in its plane.
+fn flat_face(mesh: &IndexedMesh, seed: usize) -> Vec<usize> {
+    let normal = mesh.face_normal(&mesh.triangles[seed]).normalize();
+    let corner = mesh.positions[mesh.triangles[seed][0] as usize];
+    let in_plane = |t: usize| {
+        let triangle = &mesh.triangles[t];
+        mesh.face_normal(triangle)
+            .try_normalize()
+            .is_some_and(|n| n.dot(normal) > 1.0 - 1e-4)
+            && triangle
+                .iter()
+                .all(|&v| (mesh.positions[v as usize] - corner).dot(normal).abs() < FACE_TOLERANCE)
+    };
+    let mut by_edge: HashMap<(u32, u32), usize> = HashMap::new();
+    for (t, triangle) in mesh.triangles.iter().enumerate() {
+        for edge in edges_of(triangle) {
+            by_edge.insert(edge, t);
+        }
+    }
+    let mut face = vec![seed];
+    let mut visited = HashSet::from([seed]);
+    let mut next = 0;
+    while next < face.len() {
+        let t = face[next];
+        next += 1;
+        for (a, b) in edges_of(&mesh.triangles[t]) {
+            if let Some(&neighbor) = by_edge.get(&(b, a)) {
+                if in_plane(neighbor) && visited.insert(neighbor) {
+                    face.push(neighbor);
+                }
+            }
+        }
+    }
+    face
+}
+
+/// Border loops of a set of triangles, running the same way as the
+/// triangles. None if the border touches itself at a vertex.
+fn face_loops(mesh: &IndexedMesh, face: &[usize]) -> Option<Vec<Vec<u32>>> {
+    let edges: HashSet<(u32, u32)> = face
+        .iter()
+        .flat_map(|&t| edges_of(&mesh.triangles[t]))
+        .collect();
+    let mut next: HashMap<u32, u32> = HashMap::new();
+    for &(a, b) in &edges {
+        if !edges.contains(&(b, a)) && next.insert(a, b).is_some() {
+            return None;
+        }
+    }
+    let mut loops = Vec::new();
+    while let Some(&start) = next.keys().next() {
+        let mut ring = vec![start];
+        let mut current = next.remove(&start)?;
+        while current != start {
+            ring.push(current);
+            current = next.remove(&current)?;
+        }
+        loops.push(ring);
+    }
+    Some(loops)
+}
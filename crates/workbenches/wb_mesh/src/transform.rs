@@ -0,0 +1,77 @@
+//! Rigid moves of mesh bodies: the `Transform` step of a body's history and
+//! how a gizmo drag updates it.
+
+use core_document::GizmoMotion;
+use glam::{EulerRot, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::IndexedMesh;
+
+/// A turn about `pivot` followed by a shift.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RigidTransform {
+    pub translation: [f32; 3],
+    /// Turn about the X, Y, then Z axis (degrees), as for print placements.
+    pub rotation_deg: [f32; 3],
+    /// Point the turn is about (world space).
+    pub pivot: [f32; 3],
+}
+
+impl RigidTransform {
+    /// A transform that leaves meshes where they are, turning about `pivot`
+    /// once changed.
+    pub fn identity_at(pivot: Vec3) -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation_deg: [0.0; 3],
+            pivot: pivot.to_array(),
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        let [rx, ry, rz] = self.rotation_deg.map(f32::to_radians);
+        Quat::from_euler(EulerRot::ZYX, rz, ry, rx)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.translation == [0.0; 3] && self.rotation_deg == [0.0; 3]
+    }
+
+    pub fn apply(&self, mesh: &IndexedMesh) -> IndexedMesh {
+        let rotation = self.rotation();
+        let pivot = Vec3::from(self.pivot);
+        let translation = Vec3::from(self.translation);
+        IndexedMesh {
+            positions: mesh
+                .positions
+                .iter()
+                .map(|&p| rotation * (p - pivot) + pivot + translation)
+                .collect(),
+            triangles: mesh.triangles.clone(),
+        }
+    }
+
+    /// This transform followed by a gizmo `motion` about `origin`, keeping the
+    /// pivot. Scaling isn't rigid and is ignored.
+    pub fn then(&self, motion: &GizmoMotion, origin: Vec3) -> Self {
+        let (turn, shift) = match *motion {
+            GizmoMotion::Translate(offset) => (Quat::IDENTITY, Vec3::from(offset)),
+            GizmoMotion::Rotate { axis, angle } => (
+                Quat::from_axis_angle(Vec3::from(axis).normalize_or_zero(), angle),
+                Vec3::ZERO,
+            ),
+            GizmoMotion::Scale(_) => return *self,
+        };
+        let pivot = Vec3::from(self.pivot);
+        // p ↦ R(p - c) + c + t, then q ↦ Q(q - o) + o + d.
+        let rotation = turn * self.rotation();
+        let translation =
+            turn * (pivot + Vec3::from(self.translation) - origin) + origin + shift - pivot;
+        let (rz, ry, rx) = rotation.to_euler(EulerRot::ZYX);
+        Self {
+            translation: translation.to_array(),
+            rotation_deg: [rx, ry, rz].map(f32::to_degrees),
+            pivot: self.pivot,
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Writing bodies to USD (as a `.usda` text layer) or USDZ (that layer
+//! packed in an uncompressed zip), for AR viewers and DCC pipelines.
+//!
+//! Each body becomes an `Xform` placed at the center of its bounds, holding
+//! a `Mesh` around that center and a `UsdPreviewSurface` material with the
+//! body's color and opacity. The stage's up axis and `metersPerUnit` follow
+//! the export options (Y-up millimetres by default), so viewers show parts
+//! upright at their real size.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use axes::{AxisDirection, AxisSystem};
+use glam::Vec3;
+use kernel_api::{ExchangeOptions, TriMesh};
+use thiserror::Error;
+
+/// Name of the layer inside a USDZ package.
+const USDZ_LAYER: &str = "scene.usda";
+
+/// File data in a USDZ package starts at a multiple of this many bytes.
+const USDZ_ALIGNMENT: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum UsdError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("there are no bodies with geometry to export")]
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsdFormat {
+    Usda,
+    Usdz,
+}
+
+impl UsdFormat {
+    pub const ALL: [UsdFormat; 2] = [UsdFormat::Usda, UsdFormat::Usdz];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UsdFormat::Usda => "USD",
+            UsdFormat::Usdz => "USDZ",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            UsdFormat::Usda => "usda",
+            UsdFormat::Usdz => "usdz",
+        }
+    }
+
+    /// Tool id, also the `FileOpenRequest` id of the save dialog.
+    pub fn request_id(self) -> &'static str {
+        match self {
+            UsdFormat::Usda => "mesh.export_usd",
+            UsdFormat::Usdz => "mesh.export_usdz",
+        }
+    }
+
+    pub fn from_request_id(id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.request_id() == id)
+    }
+}
+
+/// A body to export, in document coordinates.
+#[derive(Debug, Clone)]
+pub struct UsdBody {
+    pub name: String,
+    pub mesh: TriMesh,
+    pub color: [f32; 3],
+    /// 1.0 is opaque.
+    pub opacity: f32,
+}
+
+/// Write `bodies` to `path` in `format`, converting from `document_axes`.
+pub fn export(
+    bodies: &[UsdBody],
+    options: &ExchangeOptions,
+    document_axes: AxisSystem,
+    format: UsdFormat,
+    path: &Path,
+) -> Result<(), UsdError> {
+    if bodies.iter().all(|body| body.mesh.indices.is_empty()) {
+        return Err(UsdError::Empty);
+    }
+    let layer = to_usda(bodies, options, document_axes);
+    let bytes = match format {
+        UsdFormat::Usda => layer.into_bytes(),
+        UsdFormat::Usdz => to_usdz(USDZ_LAYER, layer.as_bytes()),
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// The stage holding `bodies` as USD text.
+pub fn to_usda(bodies: &[UsdBody], options: &ExchangeOptions, document_axes: AxisSystem) -> String {
+    // Stages are Y-up or Z-up; Y-up is what every viewer supports.
+    let up = match options.file_axes.vertical().direction() {
+        AxisDirection::Z => "Z",
+        _ => "Y",
+    };
+    let meters_per_unit = options.file_unit.mm_per_unit() / 1000.0;
+
+    let mut out = String::new();
+    out.push_str("#usda 1.0\n(\n");
+    out.push_str("    defaultPrim = \"Scene\"\n");
+    out.push_str("    doc = \"Exported from printCAD\"\n");
+    let _ = writeln!(out, "    metersPerUnit = {meters_per_unit}");
+    let _ = writeln!(out, "    upAxis = \"{up}\"\n)\n");
+    out.push_str("def Xform \"Scene\" (\n    kind = \"assembly\"\n)\n{\n");
+
+    let mut used = HashSet::new();
+    for body in bodies {
+        if body.mesh.indices.is_empty() {
+            continue;
+        }
+        let mut mesh = body.mesh.clone();
+        options.export_mesh(&mut mesh, document_axes);
+        let Some((min, max)) = mesh.bounds() else {
+            continue;
+        };
+        let (min, max) = (Vec3::from(min), Vec3::from(max));
+        let center = (min + max) * 0.5;
+        let name = prim_name(&body.name, &mut used);
+        write_body(&mut out, &name, &mesh, center, max - min, body);
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn write_body(
+    out: &mut String,
+    name: &str,
+    mesh: &TriMesh,
+    center: Vec3,
+    size: Vec3,
+    body: &UsdBody,
+) {
+    let half = size * 0.5;
+    let material = format!("/Scene/{name}/Material");
+    let _ = writeln!(out, "    def Xform \"{name}\" (");
+    out.push_str("        kind = \"component\"\n    )\n    {\n");
+    let _ = writeln!(
+        out,
+        "        double3 xformOp:translate = ({}, {}, {})",
+        center.x, center.y, center.z
+    );
+    out.push_str("        uniform token[] xformOpOrder = [\"xformOp:translate\"]\n\n");
+
+    out.push_str("        def Mesh \"Mesh\" (\n");
+    out.push_str(
+        "            prepend apiSchemas = [\"MaterialBindingAPI\"]\n        )\n        {\n",
+    );
+    let _ = writeln!(
+        out,
+        "            float3[] extent = [({}, {}, {}), ({}, {}, {})]",
+        -half.x, -half.y, -half.z, half.x, half.y, half.z
+    );
+    let triangles = mesh.indices.len() / 3;
+    let _ = writeln!(
+        out,
+        "            int[] faceVertexCounts = [{}]",
+        join(std::iter::repeat("3").take(triangles))
+    );
+    let _ = writeln!(
+        out,
+        "            int[] faceVertexIndices = [{}]",
+        join(mesh.indices.iter())
+    );
+    let _ = writeln!(
+        out,
+        "            point3f[] points = [{}]",
+        join(
+            mesh.positions
+                .iter()
+                .map(|p| tuple(Vec3::from(*p) - center))
+        )
+    );
+    if mesh.normals.len() == mesh.positions.len() {
+        let _ = writeln!(
+            out,
+            "            normal3f[] normals = [{}] (\n                interpolation = \"vertex\"\n            )",
+            join(mesh.normals.iter().map(|n| tuple(Vec3::from(*n))))
+        );
+    }
+    let [r, g, b] = body.color;
+    let _ = writeln!(
+        out,
+        "            color3f[] primvars:displayColor = [({r}, {g}, {b})]"
+    );
+    let _ = writeln!(
+        out,
+        "            float[] primvars:displayOpacity = [{}]",
+        body.opacity
+    );
+    out.push_str("            uniform token subdivisionScheme = \"none\"\n");
+    let _ = writeln!(out, "            rel material:binding = <{material}>");
+    out.push_str("        }\n\n");
+
+    out.push_str("        def Material \"Material\"\n        {\n");
+    let _ = writeln!(
+        out,
+        "            token outputs:surface.connect = <{material}/Surface.outputs:surface>\n"
+    );
+    out.push_str("            def Shader \"Surface\"\n            {\n");
+    out.push_str("                uniform token info:id = \"UsdPreviewSurface\"\n");
+    let _ = writeln!(
+        out,
+        "                color3f inputs:diffuseColor = ({r}, {g}, {b})"
+    );
+    let _ = writeln!(
+        out,
+        "                float inputs:opacity = {}",
+        body.opacity
+    );
+    out.push_str("                float inputs:roughness = 0.5\n");
+    out.push_str("                token outputs:surface\n");
+    out.push_str("            }\n        }\n    }\n");
+}
+
+/// A valid prim name for `label`, not in `used` yet.
+fn prim_name(label: &str, used: &mut HashSet<String>) -> String {
+    let mut base: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !base.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        base.insert(0, '_');
+    }
+    let mut name = base.clone();
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{base}_{n}");
+        n += 1;
+    }
+    name
+}
+
+fn tuple(v: Vec3) -> String {
+    format!("({}, {}, {})", v.x, v.y, v.z)
+}
+
+fn join<T: ToString>(items: impl Iterator<Item = T>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A USDZ package: an uncompressed zip whose single file's data starts at a
+/// multiple of 64 bytes, as the format requires.
+pub fn to_usdz(name: &str, data: &[u8]) -> Vec<u8> {
+    const LOCAL_HEADER: usize = 30;
+    let crc = crc32(data);
+    let size = data.len() as u32;
+    // Pad the local header's extra field so the data lands aligned.
+    let unpadded = LOCAL_HEADER + name.len() + 4;
+    let padding = (USDZ_ALIGNMENT - unpadded % USDZ_ALIGNMENT) % USDZ_ALIGNMENT;
+    let extra_len = 4 + padding;
+
+    let mut out = Vec::with_capacity(unpadded + padding + data.len() + 128);
+    // Local file header: stored, no data descriptor, DOS date 1980-01-01.
+    out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(extra_len as u16).to_le_bytes());
+    out.extend_from_slice(name.as_bytes());
+    // An unregistered extra field (id 0x1986) holding zeros.
+    out.extend_from_slice(&0x1986u16.to_le_bytes());
+    out.extend_from_slice(&(padding as u16).to_le_bytes());
+    out.extend(std::iter::repeat(0).take(padding));
+    out.extend_from_slice(data);
+
+    let directory = out.len() as u32;
+    out.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    // No extra field or comment; disk 0; no attributes; header at offset 0.
+    out.extend_from_slice(&[0; 16]);
+    out.extend_from_slice(name.as_bytes());
+    let directory_len = out.len() as u32 - directory;
+
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+    out.extend_from_slice(&directory_len.to_le_bytes());
+    out.extend_from_slice(&directory.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+/// CRC-32 (IEEE) of `data`, as zip files store it.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
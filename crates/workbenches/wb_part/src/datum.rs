@@ -0,0 +1,218 @@
+//! Datum features: construction planes, axes, and points defined from other
+//! geometry, placed again whenever that geometry moves.
+
+use core_document::{
+    DatumGeometry, Document, DocumentResult, FeatureError, FeatureId, GeometryRef, ReferenceAxis,
+    ReferencePlane, WorkbenchFeature, WorkbenchId,
+};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use wb_sketch::{DatumPlaneFeature, SketchFeature, DATUM_WORKBENCH_ID};
+
+/// Workbench id the Part Design datum features are stored under.
+pub const PART_DATUM_WORKBENCH_ID: &str = "wb.part_datum";
+
+/// How a datum is placed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DatumDefinition {
+    /// Parallel to `base` (an origin or datum plane), `offset` along its
+    /// normal.
+    OffsetPlane { base: GeometryRef, offset: f32 },
+    /// Through three datum points, facing the way they turn counterclockwise.
+    ThreePointPlane { points: [FeatureId; 3] },
+    /// Along an edge: an origin axis, a datum axis, or a sketch line.
+    EdgeAxis { edge: GeometryRef },
+    /// At fixed coordinates.
+    Point { position: [f32; 3] },
+}
+
+impl DatumDefinition {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DatumDefinition::OffsetPlane { .. } => "Offset Plane",
+            DatumDefinition::ThreePointPlane { .. } => "Plane Through 3 Points",
+            DatumDefinition::EdgeAxis { .. } => "Axis From Edge",
+            DatumDefinition::Point { .. } => "Datum Point",
+        }
+    }
+
+    /// Where the datum is now, None when the geometry it is defined from is
+    /// gone or degenerate (e.g. three points on a line).
+    pub fn resolve(&self, document: &Document) -> Option<DatumGeometry> {
+        match self {
+            DatumDefinition::OffsetPlane { base, offset } => {
+                let plane = reference_plane(document, base)?;
+                let normal = Vec3::from(plane.normal);
+                Some(DatumGeometry::Plane(ReferencePlane {
+                    origin: (Vec3::from(plane.origin) + normal * *offset).to_array(),
+                    ..plane
+                }))
+            }
+            DatumDefinition::ThreePointPlane { points } => {
+                let [a, b, c] = points.map(|id| {
+                    document
+                        .datum_geometry(id)
+                        .and_then(|datum| datum.point())
+                        .map(Vec3::from)
+                });
+                let (a, b, c) = (a?, b?, c?);
+                let x_axis = (b - a).try_normalize()?;
+                let normal = x_axis.cross(c - a).try_normalize()?;
+                Some(DatumGeometry::Plane(ReferencePlane {
+                    origin: a.to_array(),
+                    normal: normal.to_array(),
+                    x_axis: x_axis.to_array(),
+                    y_axis: normal.cross(x_axis).to_array(),
+                }))
+            }
+            DatumDefinition::EdgeAxis { edge } => {
+                reference_axis(document, edge).map(DatumGeometry::Axis)
+            }
+            DatumDefinition::Point { position } => Some(DatumGeometry::Point(*position)),
+        }
+    }
+
+    fn references(&self) -> Vec<FeatureId> {
+        match self {
+            DatumDefinition::OffsetPlane { base, .. } => base.features(),
+            DatumDefinition::ThreePointPlane { points } => points.to_vec(),
+            DatumDefinition::EdgeAxis { edge } => edge.features(),
+            DatumDefinition::Point { .. } => Vec::new(),
+        }
+    }
+}
+
+/// A datum plane, axis, or point in the document's feature tree. `geometry`
+/// is where the definition placed it last, read by other workbenches through
+/// [`Document::datum_geometry`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatumFeature {
+    pub name: String,
+    pub definition: DatumDefinition,
+    pub geometry: DatumGeometry,
+}
+
+impl DatumFeature {
+    /// Datum placed by `definition`, None if it cannot be placed.
+    pub fn new(
+        name: impl Into<String>,
+        definition: DatumDefinition,
+        document: &Document,
+    ) -> Option<Self> {
+        Some(Self {
+            name: name.into(),
+            geometry: definition.resolve(document)?,
+            definition,
+        })
+    }
+}
+
+impl WorkbenchFeature for DatumFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(PART_DATUM_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("DatumFeature should always serialize")
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        serde_json::from_value(value.clone()).map_err(|e| {
+            core_document::DocumentError::Feature(FeatureError::Deserialization(e.to_string()))
+        })
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        self.definition.references()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Datum features of the document, oldest first.
+pub fn datum_features(document: &Document) -> Vec<(FeatureId, DatumFeature)> {
+    let mut nodes: Vec<_> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == PART_DATUM_WORKBENCH_ID)
+        .collect();
+    nodes.sort_by_key(|(_, node)| node.created_at);
+    nodes
+        .into_iter()
+        .filter_map(|(id, node)| DatumFeature::from_json(&node.data).ok().map(|d| (*id, d)))
+        .collect()
+}
+
+/// Place every datum again from its definition, marking the ones that moved
+/// dirty so the features built on them follow. Datums whose references are
+/// gone stay where they were.
+pub fn update_datums(document: &mut Document) {
+    let mut datums = datum_features(document);
+    let ids: Vec<FeatureId> = datums.iter().map(|(id, _)| *id).collect();
+    // Datums defined from other datums go after them, so a chain settles at once.
+    let order = document.feature_tree().recompute_order(&ids);
+    for id in order {
+        let Some(index) = datums.iter().position(|(datum_id, _)| *datum_id == id) else {
+            continue;
+        };
+        let (_, datum) = datums.swap_remove(index);
+        let Some(geometry) = datum.definition.resolve(document) else {
+            continue;
+        };
+        if geometry == datum.geometry {
+            continue;
+        }
+        let datum = DatumFeature { geometry, ..datum };
+        if document.update_feature_data(id, datum.to_json()).is_ok() {
+            document.mark_feature_dirty(id);
+        }
+    }
+}
+
+/// Plane of an origin plane, a datum plane, or a Sketch workbench datum
+/// plane.
+pub fn reference_plane(document: &Document, reference: &GeometryRef) -> Option<ReferencePlane> {
+    match reference {
+        GeometryRef::Origin(origin) => document.origin_plane(*origin),
+        GeometryRef::Datum(id) => {
+            let node = document.get_feature_meta(*id)?;
+            if node.workbench_id.as_str() == DATUM_WORKBENCH_ID {
+                let plane = DatumPlaneFeature::from_json(&node.data).ok()?.plane;
+                return Some(ReferencePlane {
+                    origin: plane.origin,
+                    normal: plane.normal,
+                    x_axis: plane.x_axis,
+                    y_axis: plane.y_axis,
+                });
+            }
+            document.datum_geometry(*id)?.plane()
+        }
+        _ => None,
+    }
+}
+
+/// Line of an origin axis, a datum axis, or a sketch line (named as in
+/// [`SketchFeature::topology`]).
+pub fn reference_axis(document: &Document, reference: &GeometryRef) -> Option<ReferenceAxis> {
+    match reference {
+        GeometryRef::Origin(origin) => document.origin_axis(*origin),
+        GeometryRef::Datum(id) => document.datum_geometry(*id)?.axis(),
+        GeometryRef::Element { name, .. } => {
+            let sketch_id = *name.features().first()?;
+            let sketch = SketchFeature::from_json(document.get_feature_data(sketch_id)?).ok()?;
+            let (_, start, end) = sketch
+                .line_edges(sketch_id)
+                .into_iter()
+                .find(|(edge, ..)| edge == name)?;
+            let direction = (Vec3::from(end) - Vec3::from(start)).try_normalize()?;
+            Some(ReferenceAxis {
+                origin: start,
+                direction: direction.to_array(),
+            })
+        }
+        GeometryRef::Face { .. } => None,
+    }
+}
@@ -0,0 +1,164 @@
+//! The kinds of Part Design features. They are all stored under the Part
+//! Design workbench id and told apart by the `kind` of their data.
+
+use core_document::{
+    BodyId, Document, DocumentError, DocumentResult, FeatureError, FeatureId, WorkbenchFeature,
+};
+use kernel_api::{Kernel, KernelError, KernelResult, TriMesh};
+use serde::{Deserialize, Serialize};
+
+use crate::fillet::FilletFeature;
+use crate::pad::{PadFeature, PocketFeature};
+use crate::revolve::RevolveFeature;
+use crate::solid::Solid;
+use crate::PART_DESIGN_WORKBENCH_ID;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PartFeature {
+    Revolve(RevolveFeature),
+    Pad(PadFeature),
+    Pocket(PocketFeature),
+    Fillet(FilletFeature),
+}
+
+impl PartFeature {
+    pub fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        let mut value = value.clone();
+        // Revolves were saved before there were other kinds.
+        if let Some(object) = value.as_object_mut() {
+            object.entry("kind").or_insert_with(|| "revolve".into());
+        }
+        serde_json::from_value(value)
+            .map_err(|e| DocumentError::Feature(FeatureError::Deserialization(e.to_string())))
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("PartFeature should always serialize")
+    }
+
+    /// Lower-case name of the kind, as in messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PartFeature::Revolve(_) => "revolve",
+            PartFeature::Pad(_) => "pad",
+            PartFeature::Pocket(_) => "pocket",
+            PartFeature::Fillet(_) => "fillet",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            PartFeature::Revolve(revolve) => revolve.name(),
+            PartFeature::Pad(pad) => pad.name(),
+            PartFeature::Pocket(pocket) => pocket.name(),
+            PartFeature::Fillet(fillet) => fillet.name(),
+        }
+    }
+
+    /// Solid of the feature, None while what it is built from is gone.
+    pub fn solid(&self) -> Option<Solid> {
+        match self {
+            PartFeature::Revolve(revolve) => revolve.solid(),
+            PartFeature::Pad(pad) => pad.solid(),
+            PartFeature::Pocket(pocket) => pocket.solid(),
+            PartFeature::Fillet(fillet) => fillet.solid(),
+        }
+    }
+
+    /// Feature whose solid this one changes, and takes the place of in the
+    /// body: the solid a pocket is cut from or a fillet rounds.
+    pub fn base(&self) -> Option<FeatureId> {
+        match self {
+            PartFeature::Revolve(_) | PartFeature::Pad(_) => None,
+            PartFeature::Pocket(pocket) => Some(pocket.base),
+            PartFeature::Fillet(fillet) => Some(fillet.base),
+        }
+    }
+
+    /// Build the feature's mesh through `kernel`.
+    pub fn build(&self, kernel: &mut dyn Kernel) -> KernelResult<TriMesh> {
+        // Revolves have a tessellated sweep of their own, which kernels
+        // without solids can make too.
+        if let PartFeature::Revolve(revolve) = self {
+            return revolve.build(kernel);
+        }
+        let solid = self.solid().ok_or_else(|| {
+            KernelError::InvalidInput(format!(
+                "the {} {} is built from is gone",
+                self.kind(),
+                self.name()
+            ))
+        })?;
+        solid.mesh(kernel)
+    }
+
+    /// The feature with its sketch, axis, and base looked up again.
+    fn resolved(&self, document: &Document) -> Self {
+        match self {
+            PartFeature::Revolve(revolve) => PartFeature::Revolve(revolve.resolved(document)),
+            PartFeature::Pad(pad) => PartFeature::Pad(pad.resolved(document)),
+            PartFeature::Pocket(pocket) => PartFeature::Pocket(pocket.resolved(document)),
+            PartFeature::Fillet(fillet) => PartFeature::Fillet(fillet.resolved(document)),
+        }
+    }
+}
+
+/// Error for data of another kind than `expected`.
+pub(crate) fn wrong_kind(expected: &str, feature: &PartFeature) -> DocumentError {
+    DocumentError::Feature(FeatureError::Deserialization(format!(
+        "expected a {expected}, found a {}",
+        feature.kind()
+    )))
+}
+
+/// Part Design features of the document, oldest first.
+pub fn part_features(document: &Document) -> Vec<(FeatureId, PartFeature)> {
+    let mut nodes: Vec<_> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == PART_DESIGN_WORKBENCH_ID)
+        .collect();
+    nodes.sort_by_key(|(_, node)| node.created_at);
+    nodes
+        .into_iter()
+        .filter_map(|(id, node)| Some((*id, PartFeature::from_json(&node.data).ok()?)))
+        .collect()
+}
+
+/// Solid of a Part Design feature, as its data is now.
+pub(crate) fn feature_solid(document: &Document, id: FeatureId) -> Option<Solid> {
+    PartFeature::from_json(document.get_feature_data(id)?)
+        .ok()?
+        .solid()
+}
+
+/// Latest Part Design feature of `body` that isn't suppressed: the solid a
+/// new pocket is cut from or a new fillet rounds.
+pub fn body_tip(document: &Document, body: BodyId) -> Option<FeatureId> {
+    part_features(document)
+        .into_iter()
+        .rev()
+        .map(|(id, _)| id)
+        .find(|id| {
+            document
+                .get_feature_meta(*id)
+                .is_some_and(|node| node.body == Some(body) && !node.suppressed)
+        })
+}
+
+/// Look up the sketches, axes, and bases of every Part Design feature again,
+/// marking the ones that changed dirty. Features are updated oldest first, so
+/// a pocket sees its base as already updated. A feature whose sketch or axis
+/// is gone keeps none, so regenerating it reports the error.
+pub fn update_features(document: &mut Document) {
+    for (id, feature) in part_features(document) {
+        let resolved = feature.resolved(document);
+        if resolved == feature {
+            continue;
+        }
+        if document.update_feature_data(id, resolved.to_json()).is_ok() {
+            document.mark_feature_dirty(id);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Fillets: a solid with every sharp edge rounded.
+
+use core_document::{Document, DocumentResult, FeatureId, WorkbenchFeature, WorkbenchId};
+use serde::{Deserialize, Serialize};
+
+use crate::feature::{feature_solid, wrong_kind, PartFeature};
+use crate::solid::Solid;
+use crate::PART_DESIGN_WORKBENCH_ID;
+
+/// The solid of `base` with its edges rounded. `base_solid` is that solid as
+/// it was when last looked up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilletFeature {
+    pub name: String,
+    /// Feature whose solid is rounded.
+    pub base: FeatureId,
+    /// Radius of the rounding, in model units.
+    pub radius: f32,
+    pub base_solid: Option<Solid>,
+}
+
+impl FilletFeature {
+    /// Fillet of `base`, None if it has no solid.
+    pub fn new(
+        name: impl Into<String>,
+        base: FeatureId,
+        radius: f32,
+        document: &Document,
+    ) -> Option<Self> {
+        Some(Self {
+            name: name.into(),
+            base,
+            radius,
+            base_solid: Some(feature_solid(document, base)?),
+        })
+    }
+
+    pub(crate) fn solid(&self) -> Option<Solid> {
+        Some(Solid::Fillet {
+            body: Box::new(self.base_solid.clone()?),
+            radius: self.radius,
+        })
+    }
+
+    pub(crate) fn resolved(&self, document: &Document) -> Self {
+        Self {
+            base_solid: feature_solid(document, self.base),
+            ..self.clone()
+        }
+    }
+}
+
+impl WorkbenchFeature for FilletFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(PART_DESIGN_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        PartFeature::Fillet(self.clone()).to_json()
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        match PartFeature::from_json(value)? {
+            PartFeature::Fillet(fillet) => Ok(fillet),
+            other => Err(wrong_kind("fillet", &other)),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        vec![self.base]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
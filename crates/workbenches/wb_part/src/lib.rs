@@ -1,19 +1,259 @@
+mod datum;
+mod feature;
+mod fillet;
+mod pad;
+#[cfg(feature = "egui")]
+mod panel;
+mod revolve;
+mod solid;
+
 use core_document::{
-    CommandDescriptor, InputResult, ToolDescriptor, Workbench, WorkbenchContext,
-    WorkbenchDescriptor, WorkbenchInputEvent, WorkbenchRuntimeContext,
+    BodyId, CommandDescriptor, FeatureId, InputResult, SelectionItem, TaskAction, TaskPanel,
+    ToolDescriptor, Workbench, WorkbenchContext, WorkbenchDescriptor, WorkbenchFeature,
+    WorkbenchInputEvent, WorkbenchRuntimeContext,
+};
+pub use datum::{
+    datum_features, reference_axis, reference_plane, update_datums, DatumDefinition, DatumFeature,
+    PART_DATUM_WORKBENCH_ID,
 };
+pub use feature::{body_tip, part_features, update_features, PartFeature};
+pub use fillet::FilletFeature;
+pub use pad::{PadFeature, PocketFeature};
+pub use revolve::{revolve_axes, revolve_features, RevolveFeature};
+pub use solid::Solid;
+use wb_sketch::SketchFeature;
+
+/// Workbench id of Part Design, which solid features are stored under.
+pub const PART_DESIGN_WORKBENCH_ID: &str = "wb.part-design";
 
 /// Part Design workbench: feature-based solid modeling.
-#[derive(Default)]
 pub struct PartDesignWorkbench {
     /// Example state: count of features (placeholder for real feature tree).
     feature_count: u32,
+    /// Offset of the next offset plane.
+    datum_offset: f32,
+    /// Coordinates of the next datum point.
+    datum_point: [f32; 3],
+    /// Line of the selected sketch the next axis runs along.
+    datum_edge: usize,
+    /// Degrees the next revolve turns.
+    revolve_angle: f32,
+    /// Revolve being set up in the task panel.
+    revolve_task: Option<RevolveTask>,
+    /// Length of the next pad.
+    pad_length: f32,
+    /// Depth of the next pocket.
+    pocket_depth: f32,
+    /// Radius of the next fillet.
+    fillet_radius: f32,
+}
+
+/// Sketch, axis, and angle of a revolve in the task panel.
+pub(crate) struct RevolveTask {
+    pub(crate) sketch: FeatureId,
+    /// Which of the sketch's candidate axes (`revolve_axes`) it turns around.
+    pub(crate) axis: usize,
+    pub(crate) angle: f32,
+    /// Revolve made by Apply, updated by later applies.
+    pub(crate) applied: Option<FeatureId>,
+}
+
+impl Default for PartDesignWorkbench {
+    fn default() -> Self {
+        Self {
+            feature_count: 0,
+            datum_offset: 0.0,
+            datum_point: [0.0; 3],
+            datum_edge: 0,
+            revolve_angle: 360.0,
+            revolve_task: None,
+            pad_length: 10.0,
+            pocket_depth: 5.0,
+            fillet_radius: 1.0,
+        }
+    }
+}
+
+impl PartDesignWorkbench {
+    /// Open the Revolve task for `sketch`.
+    pub(crate) fn open_revolve_task(&mut self, sketch: FeatureId) {
+        self.revolve_task = Some(RevolveTask {
+            sketch,
+            axis: 0,
+            angle: self.revolve_angle,
+            applied: None,
+        });
+    }
+
+    /// Revolve the task's sketch around the picked axis, into the sketch's
+    /// body, or update the revolve an earlier Apply made. Returns whether it
+    /// worked.
+    fn apply_revolve_task(&mut self, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        let Some(task) = &mut self.revolve_task else {
+            return false;
+        };
+        self.revolve_angle = task.angle;
+        let axes = revolve_axes(ctx.document, task.sketch);
+        let Some((_, axis)) = axes.get(task.axis).or(axes.first()).cloned() else {
+            ctx.log_warn("No axis in the sketch's plane to revolve around");
+            return false;
+        };
+        let applied = task
+            .applied
+            .and_then(|id| Some((id, ctx.document.get_feature_meta(id)?.name.clone())));
+        let name = match &applied {
+            Some((_, name)) => name.clone(),
+            None => format!("revolve_{}", revolve_features(ctx.document).len() + 1),
+        };
+        let Some(feature) =
+            RevolveFeature::new(name.clone(), task.sketch, axis, task.angle, ctx.document)
+        else {
+            ctx.log_warn("Cannot revolve: the sketch or axis is gone");
+            return false;
+        };
+        if let Some((id, _)) = applied {
+            return match ctx.document.update_feature_data(id, feature.to_json()) {
+                Ok(()) => {
+                    ctx.document.mark_feature_dirty(id);
+                    true
+                }
+                Err(err) => {
+                    ctx.log_error(format!("Failed to update revolve: {err}"));
+                    false
+                }
+            };
+        }
+        let body = ctx
+            .document
+            .get_feature_meta(task.sketch)
+            .and_then(|node| node.body);
+        match ctx
+            .document
+            .add_feature_in_body(feature, name.clone(), body)
+        {
+            Ok(id) => {
+                task.applied = Some(id);
+                ctx.log_info(format!("Created {name}"));
+                true
+            }
+            Err(err) => {
+                ctx.log_error(format!("Failed to create revolve: {err}"));
+                false
+            }
+        }
+    }
+
+    /// Pad `sketch` into its body.
+    pub(crate) fn add_pad(&self, ctx: &mut WorkbenchRuntimeContext, sketch: FeatureId) {
+        let name = Self::next_name(ctx, "pad");
+        match PadFeature::new(name.clone(), sketch, self.pad_length, false, ctx.document) {
+            Some(pad) => Self::add_to_sketch_body(ctx, pad, name, sketch),
+            None => ctx.log_warn("Cannot pad: the sketch is gone"),
+        }
+    }
+
+    /// Cut `sketch` from the solid of its body.
+    pub(crate) fn add_pocket(&self, ctx: &mut WorkbenchRuntimeContext, sketch: FeatureId) {
+        let base = ctx
+            .document
+            .get_feature_meta(sketch)
+            .and_then(|node| node.body)
+            .and_then(|body| body_tip(ctx.document, body));
+        let Some(base) = base else {
+            ctx.log_warn("Cannot pocket: the sketch's body has no solid to cut");
+            return;
+        };
+        let name = Self::next_name(ctx, "pocket");
+        match PocketFeature::new(name.clone(), sketch, self.pocket_depth, base, ctx.document) {
+            Some(pocket) => Self::add_to_sketch_body(ctx, pocket, name, sketch),
+            None => ctx.log_warn("Cannot pocket: the sketch or the body's solid is gone"),
+        }
+    }
+
+    /// Round the edges of `body`'s solid.
+    pub(crate) fn add_fillet(&self, ctx: &mut WorkbenchRuntimeContext, body: BodyId) {
+        let name = Self::next_name(ctx, "fillet");
+        let fillet = body_tip(ctx.document, body).and_then(|base| {
+            FilletFeature::new(name.clone(), base, self.fillet_radius, ctx.document)
+        });
+        let Some(fillet) = fillet else {
+            ctx.log_warn("Cannot fillet: the body has no solid to round");
+            return;
+        };
+        match ctx
+            .document
+            .add_feature_in_body(fillet, name.clone(), Some(body))
+        {
+            Ok(_) => ctx.log_info(format!("Created {name}")),
+            Err(err) => ctx.log_error(format!("Failed to create fillet: {err}")),
+        }
+    }
+
+    /// Name for a new feature of `kind`, numbered after the existing ones.
+    fn next_name(ctx: &WorkbenchRuntimeContext, kind: &str) -> String {
+        let count = part_features(ctx.document)
+            .iter()
+            .filter(|(_, feature)| feature.kind() == kind)
+            .count();
+        format!("{kind}_{}", count + 1)
+    }
+
+    /// Add `feature`, made from `sketch`, to the sketch's body.
+    fn add_to_sketch_body<F: WorkbenchFeature + 'static>(
+        ctx: &mut WorkbenchRuntimeContext,
+        feature: F,
+        name: String,
+        sketch: FeatureId,
+    ) {
+        let body = ctx
+            .document
+            .get_feature_meta(sketch)
+            .and_then(|node| node.body);
+        match ctx
+            .document
+            .add_feature_in_body(feature, name.clone(), body)
+        {
+            Ok(_) => ctx.log_info(format!("Created {name}")),
+            Err(err) => ctx.log_error(format!("Failed to create {name}: {err}")),
+        }
+    }
+
+    /// The body of the item under the cursor, or else of the one selected
+    /// on its own.
+    pub(crate) fn target_body(ctx: &WorkbenchRuntimeContext) -> Option<BodyId> {
+        let selected = match ctx.selection.items() {
+            [item] => Some(item),
+            _ => None,
+        };
+        ctx.hovered
+            .as_ref()
+            .and_then(|item| item.body(ctx.document))
+            .or_else(|| selected.and_then(|item| item.body(ctx.document)))
+    }
+
+    /// The sketch under the cursor, or else the one selected on its own.
+    pub(crate) fn target_sketch(ctx: &WorkbenchRuntimeContext) -> Option<FeatureId> {
+        let is_sketch = |id: &FeatureId| {
+            ctx.document
+                .get_feature_meta(*id)
+                .is_some_and(|node| node.workbench_id == SketchFeature::workbench_id())
+        };
+        let hovered = match ctx.hovered {
+            Some(SelectionItem::Feature(id)) => Some(id),
+            _ => None,
+        };
+        let selected = match ctx.selection.items() {
+            [SelectionItem::Feature(id)] => Some(*id),
+            _ => None,
+        };
+        hovered.filter(is_sketch).or(selected.filter(is_sketch))
+    }
 }
 
 impl Workbench for PartDesignWorkbench {
     fn descriptor(&self) -> WorkbenchDescriptor {
         WorkbenchDescriptor::new(
-            "wb.part-design",
+            PART_DESIGN_WORKBENCH_ID,
             "Part Design",
             "Feature-based solid modeling workbench.",
         )
@@ -30,6 +270,11 @@ impl Workbench for PartDesignWorkbench {
             "Pocket (Cut)",
             Some("modeling"),
         ));
+        context.register_tool(ToolDescriptor::new(
+            "part.revolve",
+            "Revolve",
+            Some("modeling"),
+        ));
         context.register_tool(ToolDescriptor::new(
             "part.fillet",
             "Fillet",
@@ -64,35 +309,89 @@ impl Workbench for PartDesignWorkbench {
         match event {
             WorkbenchInputEvent::MousePress {
                 button: core_document::MouseButton::Left,
-                viewport_pos,
+                ..
             } => match tool {
-                "part.pad" => {
-                    ctx.log_info(format!(
-                        "Pad tool: click at ({:.1}, {:.1}) - select a sketch face to extrude",
-                        viewport_pos.0, viewport_pos.1
-                    ));
-                    InputResult::consumed()
-                }
-                "part.pocket" => {
-                    ctx.log_info(format!(
-                        "Pocket tool: click at ({:.1}, {:.1}) - select a sketch face to cut",
-                        viewport_pos.0, viewport_pos.1
-                    ));
-                    InputResult::consumed()
-                }
-                "part.fillet" => {
-                    ctx.log_info(format!(
-                        "Fillet tool: click at ({:.1}, {:.1}) - select edges to fillet",
-                        viewport_pos.0, viewport_pos.1
-                    ));
-                    InputResult::consumed()
-                }
+                "part.pad" => match Self::target_sketch(ctx) {
+                    Some(sketch) => {
+                        self.add_pad(ctx, sketch);
+                        InputResult::finished()
+                    }
+                    None => {
+                        ctx.log_info("Pad tool: click a sketch to extrude it");
+                        InputResult::consumed()
+                    }
+                },
+                "part.pocket" => match Self::target_sketch(ctx) {
+                    Some(sketch) => {
+                        self.add_pocket(ctx, sketch);
+                        InputResult::finished()
+                    }
+                    None => {
+                        ctx.log_info("Pocket tool: click a sketch to cut it from its body");
+                        InputResult::consumed()
+                    }
+                },
+                "part.revolve" => match Self::target_sketch(ctx) {
+                    Some(sketch) => {
+                        self.open_revolve_task(sketch);
+                        InputResult::finished()
+                    }
+                    None => {
+                        ctx.log_info("Revolve tool: click a sketch to turn it around an axis");
+                        InputResult::consumed()
+                    }
+                },
+                "part.fillet" => match Self::target_body(ctx) {
+                    Some(body) => {
+                        self.add_fillet(ctx, body);
+                        InputResult::finished()
+                    }
+                    None => {
+                        ctx.log_info("Fillet tool: click a body to round its edges");
+                        InputResult::consumed()
+                    }
+                },
                 _ => InputResult::ignored(),
             },
             _ => InputResult::ignored(),
         }
     }
 
+    fn on_command(&mut self, command: &str, ctx: &mut WorkbenchRuntimeContext) {
+        if command != "part.recompute" {
+            return;
+        }
+        // Place datums first so the features built on them see where they are.
+        update_datums(ctx.document);
+        let roots = ctx.document.feature_tree().roots().to_vec();
+        for id in &roots {
+            ctx.document.mark_feature_dirty(*id);
+        }
+        ctx.log_info("Recomputing the feature tree");
+    }
+
+    fn regenerate_feature(
+        &self,
+        feature: &core_document::FeatureNode,
+    ) -> Option<core_document::FeatureBuild> {
+        // Revolves sweep their own mesh; pads, pockets, and fillets build
+        // solids and tessellate them.
+        let feature = PartFeature::from_json(&feature.data).map_err(|err| {
+            kernel_api::KernelError::InvalidInput(format!("invalid Part Design feature: {err}"))
+        });
+        Some(Box::new(move |kernel: &mut dyn kernel_api::Kernel| {
+            feature.and_then(|feature| feature.build(kernel))
+        }))
+    }
+
+    fn replaced_features(&self, feature: &core_document::FeatureNode) -> Vec<FeatureId> {
+        PartFeature::from_json(&feature.data)
+            .ok()
+            .and_then(|feature| feature.base())
+            .into_iter()
+            .collect()
+    }
+
     #[cfg(feature = "egui")]
     fn ui_left_panel(&mut self, ui: &mut egui::Ui, _ctx: &mut WorkbenchRuntimeContext) {
         ui.separator();
@@ -101,12 +400,40 @@ impl Workbench for PartDesignWorkbench {
     }
 
     #[cfg(feature = "egui")]
-    fn ui_right_panel(&mut self, ui: &mut egui::Ui, _ctx: &mut WorkbenchRuntimeContext) {
-        ui.heading("Feature Properties");
-        ui.label("Select a feature to edit its parameters.");
-        ui.separator();
-        ui.heading("Feature Tree");
-        ui.label("(Feature tree will appear here)");
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+
+    fn active_task(&self) -> Option<TaskPanel> {
+        self.revolve_task
+            .as_ref()
+            .map(|_| TaskPanel::new("Revolve").with_apply())
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_task(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        if let Some(task) = &mut self.revolve_task {
+            panel::revolve_task(ui, ctx, task);
+        }
+    }
+
+    fn on_task_action(&mut self, action: TaskAction, ctx: &mut WorkbenchRuntimeContext) {
+        match action {
+            TaskAction::Ok => {
+                if self.apply_revolve_task(ctx) {
+                    self.revolve_task = None;
+                }
+            }
+            TaskAction::Apply => {
+                self.apply_revolve_task(ctx);
+            }
+            TaskAction::Cancel => self.revolve_task = None,
+        }
     }
 
     #[cfg(feature = "egui")]
@@ -0,0 +1,184 @@
+//! Pads and pockets: a sketch's closed profile extruded straight out of its
+//! plane, as a solid of its own (pad) or cut from the body's solid (pocket).
+
+use core_document::{Document, DocumentResult, FeatureId, WorkbenchFeature, WorkbenchId};
+use glam::Vec3;
+use kernel_api::Profile;
+use serde::{Deserialize, Serialize};
+use wb_sketch::SketchFeature;
+
+use crate::feature::{feature_solid, wrong_kind, PartFeature};
+use crate::solid::Solid;
+use crate::PART_DESIGN_WORKBENCH_ID;
+
+/// A sketch's profile extruded along the sketch's normal. `profile` and
+/// `normal` are where the sketch was when last looked up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PadFeature {
+    pub name: String,
+    /// Sketch whose closed loops are extruded.
+    pub sketch: FeatureId,
+    /// Distance extruded, in model units.
+    pub length: f32,
+    /// Extrude against the sketch's normal instead of along it.
+    pub reversed: bool,
+    pub profile: Profile,
+    pub normal: Option<[f32; 3]>,
+}
+
+impl PadFeature {
+    /// Pad of `sketch`, None if it is gone.
+    pub fn new(
+        name: impl Into<String>,
+        sketch: FeatureId,
+        length: f32,
+        reversed: bool,
+        document: &Document,
+    ) -> Option<Self> {
+        let (profile, normal) = sketch_profile(document, sketch)?;
+        Some(Self {
+            name: name.into(),
+            sketch,
+            length,
+            reversed,
+            profile,
+            normal: Some(normal),
+        })
+    }
+
+    pub(crate) fn solid(&self) -> Option<Solid> {
+        let sign = if self.reversed { -1.0 } else { 1.0 };
+        Some(Solid::Extrude {
+            profile: self.profile.clone(),
+            direction: (Vec3::from(self.normal?) * self.length * sign).to_array(),
+        })
+    }
+
+    pub(crate) fn resolved(&self, document: &Document) -> Self {
+        let (profile, normal) = sketch_profile(document, self.sketch).unzip();
+        Self {
+            profile: profile.unwrap_or_default(),
+            normal,
+            ..self.clone()
+        }
+    }
+}
+
+impl WorkbenchFeature for PadFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(PART_DESIGN_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        PartFeature::Pad(self.clone()).to_json()
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        match PartFeature::from_json(value)? {
+            PartFeature::Pad(pad) => Ok(pad),
+            other => Err(wrong_kind("pad", &other)),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        vec![self.sketch]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A sketch's profile extruded into the material behind it (against the
+/// sketch's normal) and cut from the solid of `base`. `base_solid` is that
+/// solid as it was when last looked up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PocketFeature {
+    pub name: String,
+    /// Sketch whose closed loops are cut.
+    pub sketch: FeatureId,
+    /// Depth cut, in model units.
+    pub depth: f32,
+    /// Feature whose solid is cut.
+    pub base: FeatureId,
+    pub profile: Profile,
+    pub normal: Option<[f32; 3]>,
+    pub base_solid: Option<Solid>,
+}
+
+impl PocketFeature {
+    /// Pocket of `sketch` cut from `base`, None if either is gone.
+    pub fn new(
+        name: impl Into<String>,
+        sketch: FeatureId,
+        depth: f32,
+        base: FeatureId,
+        document: &Document,
+    ) -> Option<Self> {
+        let (profile, normal) = sketch_profile(document, sketch)?;
+        Some(Self {
+            name: name.into(),
+            sketch,
+            depth,
+            base,
+            profile,
+            normal: Some(normal),
+            base_solid: Some(feature_solid(document, base)?),
+        })
+    }
+
+    pub(crate) fn solid(&self) -> Option<Solid> {
+        let tool = Solid::Extrude {
+            profile: self.profile.clone(),
+            direction: (Vec3::from(self.normal?) * -self.depth).to_array(),
+        };
+        Some(Solid::Cut {
+            target: Box::new(self.base_solid.clone()?),
+            tool: Box::new(tool),
+        })
+    }
+
+    pub(crate) fn resolved(&self, document: &Document) -> Self {
+        let (profile, normal) = sketch_profile(document, self.sketch).unzip();
+        Self {
+            profile: profile.unwrap_or_default(),
+            normal,
+            base_solid: feature_solid(document, self.base),
+            ..self.clone()
+        }
+    }
+}
+
+impl WorkbenchFeature for PocketFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(PART_DESIGN_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        PartFeature::Pocket(self.clone()).to_json()
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        match PartFeature::from_json(value)? {
+            PartFeature::Pocket(pocket) => Ok(pocket),
+            other => Err(wrong_kind("pocket", &other)),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        vec![self.sketch, self.base]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Closed loops of `sketch` and the normal of its plane.
+fn sketch_profile(document: &Document, sketch: FeatureId) -> Option<(Profile, [f32; 3])> {
+    let sketch = SketchFeature::from_json(document.get_feature_data(sketch)?).ok()?;
+    let profile = Profile {
+        loops: sketch.profile_loops(),
+    };
+    Some((profile, sketch.plane.normal))
+}
@@ -0,0 +1,464 @@
+//! Right-panel UI of the Part Design workbench.
+
+use core_document::{
+    FeatureId, GeometryRef, SelectionItem, WorkbenchFeature, WorkbenchRuntimeContext,
+};
+use wb_sketch::SketchFeature;
+
+use crate::datum::{self, DatumDefinition, DatumFeature, PART_DATUM_WORKBENCH_ID};
+use crate::feature::{body_tip, PartFeature};
+use crate::revolve::{revolve_axes, RevolveFeature};
+use crate::{PartDesignWorkbench, RevolveTask, PART_DESIGN_WORKBENCH_ID};
+
+const AXIS_LABELS: [&str; 3] = ["X", "Y", "Z"];
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PartDesignWorkbench,
+) {
+    ui.heading("Feature Properties");
+    if let Some((id, feature)) = selected_datum(ctx) {
+        edit_datum(ui, ctx, id, feature);
+    } else if let Some((id, feature)) = selected_part_feature(ctx) {
+        match feature {
+            PartFeature::Revolve(revolve) => edit_revolve(ui, ctx, id, revolve),
+            feature => edit_solid(ui, ctx, id, feature),
+        }
+    } else {
+        ui.label("Select a feature to edit its parameters.");
+    }
+    ui.separator();
+    solid_section(ui, ctx, workbench);
+    ui.separator();
+    revolve_section(ui, ctx, workbench);
+    ui.separator();
+    datum_section(ui, ctx, workbench);
+}
+
+/// The Part Design feature selected on its own, if any.
+fn selected_part_feature(ctx: &WorkbenchRuntimeContext) -> Option<(FeatureId, PartFeature)> {
+    let [SelectionItem::Feature(id)] = ctx.selection.items() else {
+        return None;
+    };
+    let node = ctx.document.get_feature_meta(*id)?;
+    if node.workbench_id.as_str() != PART_DESIGN_WORKBENCH_ID {
+        return None;
+    }
+    Some((*id, PartFeature::from_json(&node.data).ok()?))
+}
+
+/// Length of a pad, depth of a pocket, or radius of a fillet.
+fn edit_solid(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    id: FeatureId,
+    mut feature: PartFeature,
+) {
+    let changed = match &mut feature {
+        PartFeature::Pad(pad) => {
+            ui.label("Edit Pad");
+            let changed = length_field(ui, "Length:", &mut pad.length);
+            changed | ui.checkbox(&mut pad.reversed, "Reversed").changed()
+        }
+        PartFeature::Pocket(pocket) => {
+            ui.label("Edit Pocket");
+            length_field(ui, "Depth:", &mut pocket.depth)
+        }
+        PartFeature::Fillet(fillet) => {
+            ui.label("Edit Fillet");
+            length_field(ui, "Radius:", &mut fillet.radius)
+        }
+        PartFeature::Revolve(_) => false,
+    };
+    if !changed {
+        return;
+    }
+    match ctx.document.update_feature_data(id, feature.to_json()) {
+        Ok(()) => ctx.document.mark_feature_dirty(id),
+        Err(err) => ctx.log_error(format!("Failed to update {}: {err}", feature.kind())),
+    }
+}
+
+/// Padding or pocketing the selected sketch, and filleting the selected
+/// body's solid.
+fn solid_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PartDesignWorkbench,
+) {
+    ui.heading("Pad, Pocket, Fillet");
+    let sketch = PartDesignWorkbench::target_sketch(ctx);
+
+    length_field(ui, "Length:", &mut workbench.pad_length);
+    let response = ui.add_enabled(sketch.is_some(), egui::Button::new("Pad Sketch"));
+    if let (true, Some(sketch)) = (response.clicked(), sketch) {
+        workbench.add_pad(ctx, sketch);
+    }
+    response.on_disabled_hover_text("Select a sketch");
+
+    let has_solid = |sketch: &FeatureId| {
+        ctx.document
+            .get_feature_meta(*sketch)
+            .and_then(|node| node.body)
+            .and_then(|body| body_tip(ctx.document, body))
+            .is_some()
+    };
+    let pocketed = sketch.filter(has_solid);
+    length_field(ui, "Depth:", &mut workbench.pocket_depth);
+    let response = ui.add_enabled(pocketed.is_some(), egui::Button::new("Pocket Sketch"));
+    if let (true, Some(sketch)) = (response.clicked(), pocketed) {
+        workbench.add_pocket(ctx, sketch);
+    }
+    response.on_disabled_hover_text("Select a sketch in a body with a solid");
+
+    let body = PartDesignWorkbench::target_body(ctx)
+        .filter(|body| body_tip(ctx.document, *body).is_some());
+    length_field(ui, "Radius:", &mut workbench.fillet_radius);
+    let response = ui.add_enabled(body.is_some(), egui::Button::new("Fillet Body"));
+    if let (true, Some(body)) = (response.clicked(), body) {
+        workbench.add_fillet(ctx, body);
+    }
+    response.on_disabled_hover_text("Select a body with a solid");
+}
+
+/// A positive distance in millimeters. Returns whether it changed.
+fn length_field(ui: &mut egui::Ui, label: &str, value: &mut f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(
+            egui::DragValue::new(value)
+                .speed(0.5)
+                .range(0.01..=f32::MAX)
+                .suffix(" mm"),
+        )
+        .changed()
+    })
+    .inner
+}
+
+fn edit_revolve(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    id: FeatureId,
+    feature: RevolveFeature,
+) {
+    ui.label("Edit Revolve");
+    let axes = revolve_axes(ctx.document, feature.sketch);
+    let mut axis = axes.iter().position(|(_, axis)| *axis == feature.axis);
+    let mut angle = feature.angle;
+    let mut changed = angle_field(ui, &mut angle);
+    changed |= axis_combo(ui, "part_revolve_edit_axis", &axes, &mut axis);
+    if !changed {
+        return;
+    }
+    let axis = axis
+        .and_then(|i| axes.get(i))
+        .map_or(feature.axis.clone(), |(_, axis)| axis.clone());
+    let Some(revolve) =
+        RevolveFeature::new(feature.name, feature.sketch, axis, angle, ctx.document)
+    else {
+        ctx.log_warn("Cannot update the revolve: its sketch or axis is gone");
+        return;
+    };
+    match ctx.document.update_feature_data(id, revolve.to_json()) {
+        Ok(()) => ctx.document.mark_feature_dirty(id),
+        Err(err) => ctx.log_error(format!("Failed to update revolve: {err}")),
+    }
+}
+
+/// Revolving the selected sketch around one of the axes in its plane.
+fn revolve_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PartDesignWorkbench,
+) {
+    ui.heading("Revolve");
+    let sketch = PartDesignWorkbench::target_sketch(ctx)
+        .filter(|sketch| !revolve_axes(ctx.document, *sketch).is_empty());
+    let response = ui.add_enabled(sketch.is_some(), egui::Button::new("Revolve Sketch…"));
+    if let (true, Some(sketch)) = (response.clicked(), sketch) {
+        workbench.open_revolve_task(sketch);
+    }
+    response.on_disabled_hover_text("Select a sketch with an axis in its plane");
+}
+
+/// Form of the Revolve task. Until the revolve is applied, selecting another
+/// sketch revolves that one instead.
+pub fn revolve_task(ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext, task: &mut RevolveTask) {
+    if task.applied.is_none() {
+        let selected = match ctx.selection.items() {
+            [SelectionItem::Feature(id)] => Some(*id),
+            _ => None,
+        };
+        if let Some(sketch) =
+            selected.filter(|id| *id != task.sketch && !revolve_axes(ctx.document, *id).is_empty())
+        {
+            task.sketch = sketch;
+            task.axis = 0;
+        }
+    }
+    let sketch_name = ctx
+        .document
+        .get_feature_meta(task.sketch)
+        .map_or("(deleted sketch)", |node| node.name.as_str());
+    ui.label(format!("Sketch: {sketch_name}"));
+    let axes = revolve_axes(ctx.document, task.sketch);
+    angle_field(ui, &mut task.angle);
+    if axes.is_empty() {
+        ui.weak("The sketch has no axis in its plane to revolve around.");
+    } else {
+        task.axis = task.axis.min(axes.len() - 1);
+        let mut axis = Some(task.axis);
+        if axis_combo(ui, "part_revolve_axis", &axes, &mut axis) {
+            task.axis = axis.unwrap_or_default();
+        }
+    }
+}
+
+/// Degrees a revolve turns. Returns whether it changed.
+fn angle_field(ui: &mut egui::Ui, angle: &mut f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label("Angle:");
+        ui.add(
+            egui::DragValue::new(angle)
+                .speed(1.0)
+                .range(1.0..=360.0)
+                .suffix("°"),
+        )
+        .changed()
+    })
+    .inner
+}
+
+/// Pick one of `axes` (labelled). Returns whether the pick changed.
+fn axis_combo(
+    ui: &mut egui::Ui,
+    id: &str,
+    axes: &[(String, GeometryRef)],
+    selected: &mut Option<usize>,
+) -> bool {
+    let text = selected
+        .and_then(|i| axes.get(i))
+        .map_or("(none)", |(label, _)| label.as_str());
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Axis:");
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(text)
+            .show_ui(ui, |ui| {
+                for (i, (label, _)) in axes.iter().enumerate() {
+                    changed |= ui.selectable_value(selected, Some(i), label).changed();
+                }
+            });
+    });
+    changed
+}
+
+/// The datum feature selected on its own, if any.
+fn selected_datum(ctx: &WorkbenchRuntimeContext) -> Option<(FeatureId, DatumFeature)> {
+    let [SelectionItem::Feature(id)] = ctx.selection.items() else {
+        return None;
+    };
+    let node = ctx.document.get_feature_meta(*id)?;
+    if node.workbench_id.as_str() != PART_DATUM_WORKBENCH_ID {
+        return None;
+    }
+    Some((*id, DatumFeature::from_json(&node.data).ok()?))
+}
+
+fn edit_datum(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    id: FeatureId,
+    mut feature: DatumFeature,
+) {
+    ui.label(format!("Edit {}", feature.definition.label()));
+    let changed = match &mut feature.definition {
+        DatumDefinition::OffsetPlane { offset, .. } => {
+            ui.horizontal(|ui| {
+                ui.label("Offset:");
+                ui.add(egui::DragValue::new(offset).speed(0.5).suffix(" mm"))
+                    .changed()
+            })
+            .inner
+        }
+        DatumDefinition::Point { position } => position_fields(ui, position),
+        DatumDefinition::ThreePointPlane { .. } | DatumDefinition::EdgeAxis { .. } => {
+            ui.label("Placed by the geometry it was made from.");
+            false
+        }
+    };
+    if !changed {
+        return;
+    }
+    if let Some(geometry) = feature.definition.resolve(ctx.document) {
+        feature.geometry = geometry;
+    }
+    match ctx.document.update_feature_data(id, feature.to_json()) {
+        Ok(()) => ctx.document.mark_feature_dirty(id),
+        Err(err) => ctx.log_error(format!("Failed to update datum: {err}")),
+    }
+}
+
+/// Buttons adding datums from the selection, each with what it needs.
+fn datum_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PartDesignWorkbench,
+) {
+    ui.heading("Datum Geometry");
+
+    let base_plane = selected_plane(ctx);
+    ui.horizontal(|ui| {
+        ui.label("Offset:");
+        ui.add(
+            egui::DragValue::new(&mut workbench.datum_offset)
+                .speed(0.5)
+                .suffix(" mm"),
+        );
+    });
+    let response = ui.add_enabled(base_plane.is_some(), egui::Button::new("Add Offset Plane"));
+    if let (true, Some(base)) = (response.clicked(), base_plane) {
+        let offset = workbench.datum_offset;
+        add_datum(ctx, DatumDefinition::OffsetPlane { base, offset });
+    }
+    response.on_disabled_hover_text("Select an origin plane or a datum plane");
+
+    let points = selected_points(ctx);
+    let response = ui.add_enabled(
+        points.is_some(),
+        egui::Button::new("Add Plane Through 3 Points"),
+    );
+    if let (true, Some(points)) = (response.clicked(), points) {
+        add_datum(ctx, DatumDefinition::ThreePointPlane { points });
+    }
+    response.on_disabled_hover_text("Select three datum points");
+
+    let edges = selected_edges(ctx);
+    if edges.len() > 1 {
+        workbench.datum_edge = workbench.datum_edge.min(edges.len() - 1);
+        egui::ComboBox::from_id_salt("part_datum_edge")
+            .selected_text(edges[workbench.datum_edge].0.clone())
+            .show_ui(ui, |ui| {
+                for (i, (label, _)) in edges.iter().enumerate() {
+                    ui.selectable_value(&mut workbench.datum_edge, i, label);
+                }
+            });
+    }
+    let edge = edges
+        .get(workbench.datum_edge)
+        .or(edges.first())
+        .map(|(_, edge)| edge.clone());
+    let response = ui.add_enabled(edge.is_some(), egui::Button::new("Add Axis From Edge"));
+    if let (true, Some(edge)) = (response.clicked(), edge) {
+        add_datum(ctx, DatumDefinition::EdgeAxis { edge });
+    }
+    response.on_disabled_hover_text("Select an origin axis, a datum axis, or a sketch with lines");
+
+    position_fields(ui, &mut workbench.datum_point);
+    if ui.button("Add Datum Point").clicked() {
+        let position = workbench.datum_point;
+        add_datum(ctx, DatumDefinition::Point { position });
+    }
+}
+
+/// X, Y, and Z of a position. Returns whether any changed.
+fn position_fields(ui: &mut egui::Ui, position: &mut [f32; 3]) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for (axis, label) in AXIS_LABELS.iter().enumerate() {
+            ui.label(*label);
+            changed |= ui
+                .add(egui::DragValue::new(&mut position[axis]).speed(0.5))
+                .changed();
+        }
+        changed
+    })
+    .inner
+}
+
+/// Selected origin plane, or the single selected datum plane.
+fn selected_plane(ctx: &WorkbenchRuntimeContext) -> Option<GeometryRef> {
+    if let Some(origin) = ctx.selected_reference.filter(|o| o.element.is_plane()) {
+        return Some(GeometryRef::Origin(origin));
+    }
+    let [SelectionItem::Feature(id)] = ctx.selection.items() else {
+        return None;
+    };
+    let reference = GeometryRef::Datum(*id);
+    datum::reference_plane(ctx.document, &reference).map(|_| reference)
+}
+
+/// Exactly three selected datum points, in selection order.
+fn selected_points(ctx: &WorkbenchRuntimeContext) -> Option<[FeatureId; 3]> {
+    let points: Vec<FeatureId> = ctx
+        .selection
+        .features()
+        .filter(|id| {
+            ctx.document
+                .datum_geometry(*id)
+                .is_some_and(|datum| datum.point().is_some())
+        })
+        .collect();
+    if points.len() != ctx.selection.len() {
+        return None;
+    }
+    points.try_into().ok()
+}
+
+/// Edges an axis can be made from, with labels: the selected origin axis or
+/// datum axis, or the lines of the selected sketch.
+fn selected_edges(ctx: &WorkbenchRuntimeContext) -> Vec<(String, GeometryRef)> {
+    if let Some(origin) = ctx.selected_reference.filter(|o| o.element.is_axis()) {
+        return vec![(
+            origin.element.label().to_string(),
+            GeometryRef::Origin(origin),
+        )];
+    }
+    let [SelectionItem::Feature(id)] = ctx.selection.items() else {
+        return Vec::new();
+    };
+    if ctx
+        .document
+        .datum_geometry(*id)
+        .is_some_and(|datum| datum.axis().is_some())
+    {
+        return vec![("Datum axis".to_string(), GeometryRef::Datum(*id))];
+    }
+    let Some(node) = ctx.document.get_feature_meta(*id) else {
+        return Vec::new();
+    };
+    let (Some(body), Ok(sketch)) = (node.body, SketchFeature::from_json(&node.data)) else {
+        return Vec::new();
+    };
+    sketch
+        .line_edges(*id)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, ..))| {
+            (
+                format!("Line {}", i + 1),
+                GeometryRef::Element { body, name },
+            )
+        })
+        .collect()
+}
+
+/// Add a datum placed by `definition` to the selected body.
+fn add_datum(ctx: &mut WorkbenchRuntimeContext, definition: DatumDefinition) {
+    let count = datum::datum_features(ctx.document).len();
+    let name = format!("datum_{}", count + 1);
+    let Some(feature) = DatumFeature::new(name.clone(), definition, ctx.document) else {
+        ctx.log_warn("Cannot place the datum: its geometry is degenerate");
+        return;
+    };
+    let body = ctx.selected_body();
+    match ctx
+        .document
+        .add_feature_in_body(feature, name.clone(), body)
+    {
+        Ok(_) => ctx.log_info(format!("Created {name}")),
+        Err(err) => ctx.log_error(format!("Failed to create datum: {err}")),
+    }
+}
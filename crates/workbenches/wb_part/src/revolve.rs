@@ -0,0 +1,186 @@
+//! Revolve features: solids swept by turning a sketch's closed profile
+//! around an axis lying in the sketch's plane.
+
+use core_document::{
+    Document, DocumentResult, FeatureId, GeometryRef, OriginElement, OriginRef, WorkbenchFeature,
+    WorkbenchId,
+};
+use glam::Vec3;
+use kernel_api::{Axis, Kernel, KernelError, KernelResult, Profile, TessellationSettings, TriMesh};
+use serde::{Deserialize, Serialize};
+use wb_sketch::SketchFeature;
+
+use crate::datum::{self, reference_axis};
+use crate::feature::{wrong_kind, PartFeature};
+use crate::solid::Solid;
+use crate::PART_DESIGN_WORKBENCH_ID;
+
+/// Largest distance of an axis from the sketch plane, in model units (and
+/// the sine of the largest angle between them).
+const PLANE_TOLERANCE: f32 = 1e-3;
+
+/// A sketch's profile turned around an axis. `profile` and `axis_line` are
+/// where the sketch and the axis were when last looked up; the kernel sweeps
+/// those, since regenerating a feature doesn't see the rest of the document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevolveFeature {
+    pub name: String,
+    /// Sketch whose closed loops are turned.
+    pub sketch: FeatureId,
+    /// An origin axis, a datum axis, or a sketch line.
+    pub axis: GeometryRef,
+    /// Degrees turned, up to 360.
+    pub angle: f32,
+    pub profile: Profile,
+    pub axis_line: Option<Axis>,
+}
+
+impl RevolveFeature {
+    /// Revolve of `sketch` around `axis`, None if either is gone.
+    pub fn new(
+        name: impl Into<String>,
+        sketch: FeatureId,
+        axis: GeometryRef,
+        angle: f32,
+        document: &Document,
+    ) -> Option<Self> {
+        let mut revolve = Self {
+            name: name.into(),
+            sketch,
+            axis,
+            angle,
+            profile: Profile::default(),
+            axis_line: None,
+        };
+        revolve.profile = revolve.resolve_profile(document)?;
+        revolve.axis_line = Some(revolve.resolve_axis(document)?);
+        Some(revolve)
+    }
+
+    fn resolve_profile(&self, document: &Document) -> Option<Profile> {
+        let sketch = SketchFeature::from_json(document.get_feature_data(self.sketch)?).ok()?;
+        Some(Profile {
+            loops: sketch.profile_loops(),
+        })
+    }
+
+    fn resolve_axis(&self, document: &Document) -> Option<Axis> {
+        let axis = reference_axis(document, &self.axis)?;
+        Some(Axis {
+            origin: axis.origin,
+            direction: axis.direction,
+        })
+    }
+
+    /// Sweep the profile through `kernel`.
+    pub fn build(&self, kernel: &mut dyn Kernel) -> KernelResult<TriMesh> {
+        let axis = self
+            .axis_line
+            .ok_or_else(|| KernelError::InvalidInput("the revolve axis is gone".to_string()))?;
+        kernel.revolve(
+            &self.profile,
+            &axis,
+            self.angle,
+            &TessellationSettings::default(),
+        )
+    }
+
+    pub(crate) fn solid(&self) -> Option<Solid> {
+        Some(Solid::Revolve {
+            profile: self.profile.clone(),
+            axis: self.axis_line?,
+            angle: self.angle,
+        })
+    }
+
+    pub(crate) fn resolved(&self, document: &Document) -> Self {
+        Self {
+            profile: self.resolve_profile(document).unwrap_or_default(),
+            axis_line: self.resolve_axis(document),
+            ..self.clone()
+        }
+    }
+}
+
+impl WorkbenchFeature for RevolveFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(PART_DESIGN_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        PartFeature::Revolve(self.clone()).to_json()
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        match PartFeature::from_json(value)? {
+            PartFeature::Revolve(revolve) => Ok(revolve),
+            other => Err(wrong_kind("revolve", &other)),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        let mut dependencies = vec![self.sketch];
+        for feature in self.axis.features() {
+            if !dependencies.contains(&feature) {
+                dependencies.push(feature);
+            }
+        }
+        dependencies
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Revolve features of the document.
+pub fn revolve_features(document: &Document) -> Vec<(FeatureId, RevolveFeature)> {
+    document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == PART_DESIGN_WORKBENCH_ID)
+        .filter_map(|(id, node)| Some((*id, RevolveFeature::from_json(&node.data).ok()?)))
+        .collect()
+}
+
+/// Axes `sketch` can be turned around, with labels: the origin axes of its
+/// body, datum axes, and the sketch's own lines, each only when it lies in
+/// the sketch's plane.
+pub fn revolve_axes(document: &Document, sketch: FeatureId) -> Vec<(String, GeometryRef)> {
+    let Some(node) = document.get_feature_meta(sketch) else {
+        return Vec::new();
+    };
+    let Ok(feature) = SketchFeature::from_json(&node.data) else {
+        return Vec::new();
+    };
+    let mut candidates = Vec::new();
+    if let Some(body) = node.body {
+        for element in [
+            OriginElement::XAxis,
+            OriginElement::YAxis,
+            OriginElement::ZAxis,
+        ] {
+            let origin = GeometryRef::Origin(OriginRef { body, element });
+            candidates.push((element.label().to_string(), origin));
+        }
+        for (i, (name, ..)) in feature.line_edges(sketch).into_iter().enumerate() {
+            let line = GeometryRef::Element { body, name };
+            candidates.push((format!("Sketch line {}", i + 1), line));
+        }
+    }
+    for (id, datum) in datum::datum_features(document) {
+        if datum.geometry.axis().is_some() {
+            candidates.push((datum.name, GeometryRef::Datum(id)));
+        }
+    }
+
+    let normal = Vec3::from(feature.plane.normal);
+    let origin = Vec3::from(feature.plane.origin);
+    candidates.retain(|(_, candidate)| {
+        reference_axis(document, candidate).is_some_and(|axis| {
+            Vec3::from(axis.direction).dot(normal).abs() < PLANE_TOLERANCE
+                && (Vec3::from(axis.origin) - origin).dot(normal).abs() < PLANE_TOLERANCE
+        })
+    });
+    candidates
+}
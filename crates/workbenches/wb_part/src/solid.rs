@@ -0,0 +1,75 @@
+//! Solids as the kernel builds them: the profiles a Part Design feature
+//! sweeps, and the cuts and roundings applied to the solids of earlier
+//! features. A feature keeps the whole recipe of the solid it changes, since
+//! regenerating a feature doesn't see the rest of the document.
+
+use kernel_api::{
+    Axis, BodyHandle, BooleanOp, Kernel, KernelResult, Profile, TessellationSettings, TriMesh,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Solid {
+    /// A profile moved along `direction` (its length is the distance).
+    Extrude {
+        profile: Profile,
+        direction: [f32; 3],
+    },
+    /// A profile turned around `axis` by `angle` degrees.
+    Revolve {
+        profile: Profile,
+        axis: Axis,
+        angle: f32,
+    },
+    /// The material of `target` that is not in `tool`.
+    Cut {
+        target: Box<Solid>,
+        tool: Box<Solid>,
+    },
+    /// `body` with its sharp edges rounded to `radius`.
+    Fillet { body: Box<Solid>, radius: f32 },
+}
+
+impl Solid {
+    /// Build the solid in `kernel`, releasing the bodies it is made from.
+    /// The caller releases the returned body.
+    pub fn build(&self, kernel: &mut dyn Kernel) -> KernelResult<BodyHandle> {
+        match self {
+            Solid::Extrude { profile, direction } => kernel.extrude(profile, *direction),
+            Solid::Revolve {
+                profile,
+                axis,
+                angle,
+            } => kernel.revolve_body(profile, axis, *angle),
+            Solid::Cut { target, tool } => {
+                let target = target.build(kernel)?;
+                let tool = match tool.build(kernel) {
+                    Ok(tool) => tool,
+                    Err(err) => {
+                        kernel.release(target);
+                        return Err(err);
+                    }
+                };
+                let cut = kernel.boolean(BooleanOp::Difference, target, tool);
+                kernel.release(target);
+                kernel.release(tool);
+                cut
+            }
+            Solid::Fillet { body, radius } => {
+                let body = body.build(kernel)?;
+                let rounded = kernel.fillet(body, *radius);
+                kernel.release(body);
+                rounded
+            }
+        }
+    }
+
+    /// Build the solid and tessellate it, keeping nothing in `kernel`.
+    pub fn mesh(&self, kernel: &mut dyn Kernel) -> KernelResult<TriMesh> {
+        let body = self.build(kernel)?;
+        let mesh = kernel.tessellate(body, &TessellationSettings::default());
+        kernel.release(body);
+        mesh
+    }
+}
@@ -0,0 +1,535 @@
+//! Printability analyses that color the faces of placed bodies.
+//!
+//! Overhang angles are measured from vertical, the way slicers do: a wall is 0°,
+//! a face pointing straight down is 90°, and upward-facing surfaces are negative.
+//! Draft angles are the same measure taken against a pull direction and with
+//! the sign flipped: faces turned towards the pull direction have positive
+//! draft, and overhangs are faces with negative draft against the build
+//! direction (+Z).
+
+use std::collections::HashMap;
+
+use core_document::bvh::{Bvh, Triangle};
+use glam::Vec3;
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{self, BedFrame, PlacedBody};
+
+pub const DEFAULT_OVERHANG_ANGLE_DEG: f32 = 45.0;
+
+/// Draft usually asked of molded parts.
+pub const DEFAULT_MIN_DRAFT_DEG: f32 = 1.0;
+
+/// 0.4 mm nozzle × 2 perimeters.
+pub const DEFAULT_MIN_WALL_THICKNESS: f32 = 0.8;
+
+/// Faces this close (in degrees) below the threshold are reported as borderline.
+const BORDERLINE_MARGIN_DEG: f32 = 10.0;
+
+/// Faces whose vertices are all this close to the bed rest on it and need no support.
+pub(crate) const BED_CONTACT_TOLERANCE: f32 = 0.05;
+
+/// Rays start this far inside a face so they do not hit the face they leave.
+const RAY_OFFSET: f32 = 1e-4;
+
+/// Vertices closer than this (mm) are treated as the same point when checking topology.
+const WELD_TOLERANCE: f32 = 1e-4;
+
+/// Which analysis colors the placed bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnalysisMode {
+    #[default]
+    None,
+    Overhangs,
+    WallThickness,
+    Clearance,
+    Draft,
+}
+
+impl AnalysisMode {
+    pub const ALL: [AnalysisMode; 5] = [
+        AnalysisMode::None,
+        AnalysisMode::Overhangs,
+        AnalysisMode::Draft,
+        AnalysisMode::WallThickness,
+        AnalysisMode::Clearance,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnalysisMode::None => "Off",
+            AnalysisMode::Overhangs => "Overhangs",
+            AnalysisMode::WallThickness => "Wall thickness",
+            AnalysisMode::Clearance => "Clearance between bodies",
+            AnalysisMode::Draft => "Draft",
+        }
+    }
+}
+
+/// One triangle of a placed body (printer coordinates).
+#[derive(Debug, Clone, Copy)]
+pub struct Face {
+    pub vertices: [Vec3; 3],
+    /// Outward unit normal.
+    pub normal: Vec3,
+    pub area: f32,
+}
+
+impl Face {
+    pub fn centroid(&self) -> Vec3 {
+        (self.vertices[0] + self.vertices[1] + self.vertices[2]) / 3.0
+    }
+
+    /// The face as a triangle of a [`Bvh`].
+    pub(crate) fn triangle(&self) -> Triangle {
+        Triangle {
+            vertices: self.vertices,
+            normal: self.normal,
+        }
+    }
+}
+
+/// Tree over `faces` for ray and nearest-point queries; its triangle indices
+/// are those of `faces`.
+pub(crate) fn face_tree(faces: &[Face]) -> Bvh {
+    Bvh::new(faces.iter().map(Face::triangle).collect())
+}
+
+/// Triangles of a placed body with their normals. Mesh normals are preferred;
+/// the winding is used for meshes without them.
+pub fn faces(body: &PlacedBody) -> Vec<Face> {
+    let has_normals = body.normals.len() == body.positions.len();
+    body.indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+            let vertices = [
+                *body.positions.get(a)?,
+                *body.positions.get(b)?,
+                *body.positions.get(c)?,
+            ];
+            let cross = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+            let averaged = if has_normals {
+                body.normals[a] + body.normals[b] + body.normals[c]
+            } else {
+                Vec3::ZERO
+            };
+            let normal = if averaged.length_squared() > f32::EPSILON {
+                averaged.normalize()
+            } else {
+                cross.normalize_or_zero()
+            };
+            Some(Face {
+                vertices,
+                normal,
+                area: cross.length() * 0.5,
+            })
+        })
+        .collect()
+}
+
+/// Faces grouped into one display mesh per color.
+#[derive(Debug, Clone, Default)]
+pub struct ColoredFaces {
+    meshes: Vec<([f32; 3], TriMesh)>,
+}
+
+impl ColoredFaces {
+    pub fn push(&mut self, color: [f32; 3], face: &Face) {
+        let index = match self.meshes.iter().position(|(c, _)| *c == color) {
+            Some(index) => index,
+            None => {
+                self.meshes.push((color, TriMesh::default()));
+                self.meshes.len() - 1
+            }
+        };
+        let mesh = &mut self.meshes[index].1;
+        let base = mesh.positions.len() as u32;
+        for vertex in face.vertices {
+            mesh.positions.push(vertex.to_array());
+            mesh.normals.push(face.normal.to_array());
+        }
+        mesh.indices.extend([base, base + 1, base + 2]);
+    }
+
+    /// Convert the meshes to world space for the viewport.
+    pub fn world_meshes(&self, frame: &BedFrame) -> Vec<(TriMesh, [f32; 3])> {
+        self.meshes
+            .iter()
+            .map(|(color, mesh)| (layout::mesh_to_world(mesh, frame), *color))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaceClass {
+    /// Supported by the bed itself.
+    OnBed,
+    /// Printable without supports.
+    Safe,
+    /// Just under the threshold; may print poorly.
+    Borderline,
+    /// Needs support material.
+    Overhang,
+}
+
+impl FaceClass {
+    pub const ALL: [FaceClass; 4] = [
+        FaceClass::OnBed,
+        FaceClass::Safe,
+        FaceClass::Borderline,
+        FaceClass::Overhang,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FaceClass::OnBed => "On bed",
+            FaceClass::Safe => "Self-supporting",
+            FaceClass::Borderline => "Borderline",
+            FaceClass::Overhang => "Needs support",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            FaceClass::OnBed => [0.35, 0.55, 0.85],
+            FaceClass::Safe => [0.3, 0.8, 0.4],
+            FaceClass::Borderline => [0.95, 0.8, 0.25],
+            FaceClass::Overhang => [0.95, 0.3, 0.25],
+        }
+    }
+}
+
+/// Overhang angle of a face with the given normal (printer coordinates), in degrees.
+pub fn overhang_angle_deg(normal: Vec3) -> f32 {
+    -draft_angle_deg(normal, Vec3::Z)
+}
+
+/// Draft angle of a face with the given normal against the pull direction,
+/// in degrees: 0° for a face parallel to the pull, 90° for one facing it.
+pub fn draft_angle_deg(normal: Vec3, pull: Vec3) -> f32 {
+    normal
+        .normalize_or_zero()
+        .dot(pull.normalize_or_zero())
+        .clamp(-1.0, 1.0)
+        .asin()
+        .to_degrees()
+}
+
+pub fn classify_overhang(face: &Face, threshold_deg: f32) -> FaceClass {
+    if face
+        .vertices
+        .iter()
+        .all(|p| p.z.abs() <= BED_CONTACT_TOLERANCE)
+    {
+        return FaceClass::OnBed;
+    }
+    let angle = overhang_angle_deg(face.normal);
+    if angle > threshold_deg {
+        FaceClass::Overhang
+    } else if angle > threshold_deg - BORDERLINE_MARGIN_DEG {
+        FaceClass::Borderline
+    } else {
+        FaceClass::Safe
+    }
+}
+
+/// Faces of one body colored by overhang class.
+#[derive(Debug, Clone, Default)]
+pub struct OverhangAnalysis {
+    pub faces: ColoredFaces,
+    /// Total area of faces needing support, in mm².
+    pub overhang_area: f32,
+    /// Centroid of the steepest overhanging face (printer coordinates).
+    pub steepest_at: Option<Vec3>,
+}
+
+/// Classify every triangle of a placed body against the overhang threshold.
+pub fn analyze_overhangs(body: &PlacedBody, threshold_deg: f32) -> OverhangAnalysis {
+    let mut result = OverhangAnalysis::default();
+    let mut steepest = f32::NEG_INFINITY;
+    for face in faces(body) {
+        let class = classify_overhang(&face, threshold_deg);
+        if class == FaceClass::Overhang {
+            result.overhang_area += face.area;
+            let angle = overhang_angle_deg(face.normal);
+            if angle > steepest {
+                steepest = angle;
+                result.steepest_at = Some(face.centroid());
+            }
+        }
+        result.faces.push(class.color(), &face);
+    }
+    result
+}
+
+/// Direction a part is pulled out of its mold or built in (printer
+/// coordinates).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PullDirection {
+    PlusX,
+    MinusX,
+    PlusY,
+    MinusY,
+    /// The build direction of a printer.
+    #[default]
+    PlusZ,
+    MinusZ,
+}
+
+impl PullDirection {
+    pub const ALL: [PullDirection; 6] = [
+        PullDirection::PlusX,
+        PullDirection::MinusX,
+        PullDirection::PlusY,
+        PullDirection::MinusY,
+        PullDirection::PlusZ,
+        PullDirection::MinusZ,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PullDirection::PlusX => "+X",
+            PullDirection::MinusX => "−X",
+            PullDirection::PlusY => "+Y",
+            PullDirection::MinusY => "−Y",
+            PullDirection::PlusZ => "+Z (build direction)",
+            PullDirection::MinusZ => "−Z",
+        }
+    }
+
+    pub fn vector(&self) -> Vec3 {
+        match self {
+            PullDirection::PlusX => Vec3::X,
+            PullDirection::MinusX => Vec3::NEG_X,
+            PullDirection::PlusY => Vec3::Y,
+            PullDirection::MinusY => Vec3::NEG_Y,
+            PullDirection::PlusZ => Vec3::Z,
+            PullDirection::MinusZ => Vec3::NEG_Z,
+        }
+    }
+}
+
+/// Per-document draft analysis settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DraftSettings {
+    pub pull: PullDirection,
+    /// Faces with less draft than this (degrees) either way are flagged.
+    pub min_angle_deg: f32,
+}
+
+impl Default for DraftSettings {
+    fn default() -> Self {
+        Self {
+            pull: PullDirection::default(),
+            min_angle_deg: DEFAULT_MIN_DRAFT_DEG,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DraftClass {
+    /// Turned towards the pull direction by at least the minimum draft.
+    Positive,
+    /// Closer to parallel with the pull direction than the minimum draft.
+    Insufficient,
+    /// Turned away from the pull direction: an undercut for a mold half.
+    Negative,
+}
+
+impl DraftClass {
+    pub const ALL: [DraftClass; 3] = [
+        DraftClass::Positive,
+        DraftClass::Insufficient,
+        DraftClass::Negative,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DraftClass::Positive => "Positive draft",
+            DraftClass::Insufficient => "Too little draft",
+            DraftClass::Negative => "Negative draft",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            DraftClass::Positive => [0.3, 0.8, 0.4],
+            DraftClass::Insufficient => [0.95, 0.8, 0.25],
+            DraftClass::Negative => [0.95, 0.3, 0.25],
+        }
+    }
+}
+
+pub fn classify_draft(face: &Face, settings: &DraftSettings) -> DraftClass {
+    let angle = draft_angle_deg(face.normal, settings.pull.vector());
+    let min = settings.min_angle_deg.max(0.0);
+    if angle >= min {
+        DraftClass::Positive
+    } else if angle > -min {
+        DraftClass::Insufficient
+    } else {
+        DraftClass::Negative
+    }
+}
+
+/// Faces of one body colored by draft class.
+#[derive(Debug, Clone, Default)]
+pub struct DraftAnalysis {
+    pub faces: ColoredFaces,
+    /// Total area of faces with too little draft, in mm².
+    pub insufficient_area: f32,
+    /// Total area of faces with negative draft, in mm².
+    pub negative_area: f32,
+}
+
+/// Classify every triangle of a placed body by its draft against the pull
+/// direction.
+pub fn analyze_draft(body: &PlacedBody, settings: &DraftSettings) -> DraftAnalysis {
+    let mut result = DraftAnalysis::default();
+    for face in faces(body) {
+        let class = classify_draft(&face, settings);
+        match class {
+            DraftClass::Insufficient => result.insufficient_area += face.area,
+            DraftClass::Negative => result.negative_area += face.area,
+            DraftClass::Positive => {}
+        }
+        result.faces.push(class.color(), &face);
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThicknessClass {
+    /// Thinner than the minimum printable wall.
+    Thin,
+    /// At least the minimum wall thickness.
+    Ok,
+    /// No opposite wall found (open geometry).
+    Unknown,
+}
+
+impl ThicknessClass {
+    pub const ALL: [ThicknessClass; 3] = [
+        ThicknessClass::Thin,
+        ThicknessClass::Ok,
+        ThicknessClass::Unknown,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThicknessClass::Thin => "Too thin to print",
+            ThicknessClass::Ok => "Printable",
+            ThicknessClass::Unknown => "No opposite wall",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            ThicknessClass::Thin => [0.95, 0.3, 0.25],
+            ThicknessClass::Ok => [0.3, 0.8, 0.4],
+            ThicknessClass::Unknown => [0.55, 0.55, 0.6],
+        }
+    }
+}
+
+/// Faces of one body colored by local wall thickness.
+#[derive(Debug, Clone, Default)]
+pub struct ThicknessAnalysis {
+    pub faces: ColoredFaces,
+    /// Total area of faces on walls thinner than the minimum, in mm².
+    pub thin_area: f32,
+    /// Thinnest wall found (None if no opposite wall was hit anywhere).
+    pub thinnest: Option<f32>,
+    /// Centroid of the face with the thinnest wall (printer coordinates).
+    pub thinnest_at: Option<Vec3>,
+}
+
+/// Measure wall thickness by casting a ray inward from every face to the
+/// opposite wall. The rays are cast against a [`Bvh`], so the cost grows as
+/// n·log(n) in the triangle count.
+pub fn analyze_wall_thickness(body: &PlacedBody, min_thickness: f32) -> ThicknessAnalysis {
+    let faces = faces(body);
+    let tree = face_tree(&faces);
+    let mut result = ThicknessAnalysis::default();
+    for (index, face) in faces.iter().enumerate() {
+        let direction = -face.normal;
+        let origin = face.centroid() + direction * RAY_OFFSET;
+        let thickness = tree
+            .ray_hit(origin, direction, Some(index))
+            .map(|t| t + RAY_OFFSET);
+
+        let class = match thickness {
+            Some(t) if t < min_thickness => ThicknessClass::Thin,
+            Some(_) => ThicknessClass::Ok,
+            None => ThicknessClass::Unknown,
+        };
+        if class == ThicknessClass::Thin {
+            result.thin_area += face.area;
+        }
+        if let Some(t) = thickness {
+            if result.thinnest.map_or(true, |thinnest| t < thinnest) {
+                result.thinnest = Some(t);
+                result.thinnest_at = Some(face.centroid());
+            }
+        }
+        result.faces.push(class.color(), face);
+    }
+    result
+}
+
+/// Vertex position snapped to the weld grid.
+pub(crate) type VertexKey = [i32; 3];
+
+/// Key under which coincident vertices are welded.
+pub(crate) fn weld_key(p: Vec3) -> VertexKey {
+    (p / WELD_TOLERANCE).round().as_ivec3().to_array()
+}
+
+/// Edges that keep a mesh from being a closed, manifold solid.
+#[derive(Debug, Clone, Default)]
+pub struct MeshValidity {
+    /// Edges used by only one triangle (holes in the surface).
+    pub open_edges: Vec<[Vec3; 2]>,
+    /// Edges shared by more than two triangles.
+    pub non_manifold_edges: Vec<[Vec3; 2]>,
+}
+
+impl MeshValidity {
+    pub fn is_watertight(&self) -> bool {
+        self.open_edges.is_empty()
+    }
+
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges.is_empty()
+    }
+}
+
+/// Count how many triangles use each edge, welding coincident vertices first.
+pub fn check_mesh_validity(body: &PlacedBody) -> MeshValidity {
+    // Welded edge → (triangles using it, one representative segment).
+    let mut edges: HashMap<(VertexKey, VertexKey), (usize, [Vec3; 2])> = HashMap::new();
+    for face in faces(body) {
+        for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+            let (pa, pb) = (face.vertices[a], face.vertices[b]);
+            let (ka, kb) = (weld_key(pa), weld_key(pb));
+            if ka == kb {
+                continue; // Degenerate edge.
+            }
+            let edge_key = if ka < kb { (ka, kb) } else { (kb, ka) };
+            edges.entry(edge_key).or_insert((0, [pa, pb])).0 += 1;
+        }
+    }
+
+    let mut validity = MeshValidity::default();
+    for (count, edge) in edges.into_values() {
+        match count {
+            1 => validity.open_edges.push(edge),
+            2 => {}
+            _ => validity.non_manifold_edges.push(edge),
+        }
+    }
+    validity
+}
@@ -0,0 +1,185 @@
+//! Automatic arrangement of bodies on the print bed.
+//!
+//! Each body is turned about the vertical axis so the bounding rectangle of its
+//! convex footprint is as small as possible, then the rectangles are packed in
+//! rows (tallest first) and the whole arrangement is centered on the bed.
+
+use core_document::{BedShape, BodyId, BodyMesh, PrintVolume};
+use glam::{Mat2, Vec2};
+
+use crate::layout::{self, BedFrame, Placement, PrintLayout};
+
+pub const DEFAULT_SPACING: f32 = 5.0;
+
+/// Rotation increment (degrees) when searching for the tightest footprint.
+const ROTATION_STEP_DEG: usize = 15;
+
+/// Result of an arrangement: new placements and the bodies that did not fit.
+#[derive(Debug, Clone, Default)]
+pub struct Arrangement {
+    pub placements: Vec<(BodyId, Placement)>,
+    pub unplaced: Vec<BodyId>,
+}
+
+/// A body turned to its tightest footprint, waiting to be packed.
+struct Item {
+    body: BodyId,
+    placement: Placement,
+    /// Lower-left corner of the footprint with `placement` applied.
+    min: Vec2,
+    size: Vec2,
+    /// Lowest point with `placement` applied.
+    min_z: f32,
+}
+
+/// Convex hull of 2D points (Andrew's monotone chain), counter-clockwise.
+pub fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let chain = |points: &mut dyn Iterator<Item = &Vec2>| {
+        let mut chain: Vec<Vec2> = Vec::new();
+        for &point in points {
+            while chain.len() >= 2
+                && (chain[chain.len() - 1] - chain[chain.len() - 2])
+                    .perp_dot(point - chain[chain.len() - 2])
+                    <= 0.0
+            {
+                chain.pop();
+            }
+            chain.push(point);
+        }
+        chain.pop();
+        chain
+    };
+    let mut hull = chain(&mut points.iter());
+    hull.extend(chain(&mut points.iter().rev()));
+    hull
+}
+
+/// Size of the axis-aligned bounding rectangle of `points` turned by `angle_deg`.
+fn rotated_extent(points: &[Vec2], angle_deg: f32) -> Vec2 {
+    let rotation = Mat2::from_angle(angle_deg.to_radians());
+    let (min, max) = points.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), &p| {
+            let p = rotation * p;
+            (min.min(p), max.max(p))
+        },
+    );
+    max - min
+}
+
+/// Usable rectangle of the bed (the inscribed square of a round bed).
+fn bed_rectangle(printer: &PrintVolume) -> (Vec2, Vec2) {
+    let ([min_x, min_y], [max_x, max_y]) = printer.bed_extent();
+    let (min, max) = (Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+    match printer.shape {
+        BedShape::Rectangular => (min, max),
+        BedShape::Circular => {
+            let center = (min + max) * 0.5;
+            let half = printer.size[0] * 0.5 / std::f32::consts::SQRT_2;
+            (center - Vec2::splat(half), center + Vec2::splat(half))
+        }
+    }
+}
+
+/// Turn a body about the vertical axis to its tightest footprint, and by a
+/// further 90° if that is what it takes to fit the bed width.
+fn prepare(
+    body_mesh: &BodyMesh,
+    current: Placement,
+    frame: &BedFrame,
+    bed_width: f32,
+) -> Option<Item> {
+    let placed = layout::place_body(body_mesh, current, frame, None)?;
+    let hull = convex_hull(placed.positions.iter().map(|p| p.truncate()).collect());
+    let mut best_angle = (0..180)
+        .step_by(ROTATION_STEP_DEG)
+        .map(|angle| angle as f32)
+        .min_by(|a, b| {
+            let (ea, eb) = (rotated_extent(&hull, *a), rotated_extent(&hull, *b));
+            (ea.x * ea.y).total_cmp(&(eb.x * eb.y))
+        })
+        .unwrap_or(0.0);
+    let extent = rotated_extent(&hull, best_angle);
+    if extent.x > bed_width && extent.y <= bed_width {
+        best_angle += 90.0;
+    }
+
+    let mut placement = current;
+    let rz = placement.rotation_deg[2] + best_angle;
+    placement.rotation_deg[2] = (rz + 180.0).rem_euclid(360.0) - 180.0;
+    let turned = layout::place_body(body_mesh, placement, frame, None)?;
+    Some(Item {
+        body: body_mesh.body,
+        placement,
+        min: turned.min.truncate(),
+        size: turned.size().truncate(),
+        min_z: turned.min.z,
+    })
+}
+
+/// Pack every body with geometry onto the bed, keeping `spacing` between them.
+pub fn arrange(
+    layout: &PrintLayout,
+    frame: &BedFrame,
+    body_meshes: &[BodyMesh],
+    printer: &PrintVolume,
+    spacing: f32,
+) -> Arrangement {
+    let (bed_min, bed_max) = bed_rectangle(printer);
+    let bed_size = bed_max - bed_min;
+    let mut items: Vec<Item> = body_meshes
+        .iter()
+        .filter_map(|body_mesh| {
+            prepare(
+                body_mesh,
+                layout.placement(body_mesh.body),
+                frame,
+                bed_size.x,
+            )
+        })
+        .collect();
+    items.sort_by(|a, b| {
+        b.size
+            .y
+            .total_cmp(&a.size.y)
+            .then(b.size.x.total_cmp(&a.size.x))
+    });
+
+    // Shelf packing: fill rows left to right, starting a new row when one is full.
+    let mut arrangement = Arrangement::default();
+    let mut packed: Vec<(Item, Vec2)> = Vec::new();
+    let (mut cursor, mut row_depth) = (Vec2::ZERO, 0.0f32);
+    let mut used = Vec2::ZERO;
+    for item in items {
+        if cursor.x > 0.0 && cursor.x + item.size.x > bed_size.x {
+            cursor = Vec2::new(0.0, cursor.y + row_depth + spacing);
+            row_depth = 0.0;
+        }
+        if cursor.x + item.size.x > bed_size.x || cursor.y + item.size.y > bed_size.y {
+            arrangement.unplaced.push(item.body);
+            continue;
+        }
+        let position = cursor;
+        used = used.max(position + item.size);
+        cursor.x += item.size.x + spacing;
+        row_depth = row_depth.max(item.size.y);
+        packed.push((item, position));
+    }
+
+    // Center the packed block on the bed and drop every body onto it.
+    let origin = bed_min + (bed_size - used) * 0.5;
+    for (item, position) in packed {
+        let mut placement = item.placement;
+        let offset = origin + position - item.min;
+        placement.translation[0] += offset.x;
+        placement.translation[1] += offset.y;
+        placement.translation[2] -= item.min_z;
+        arrangement.placements.push((item.body, placement));
+    }
+    arrangement
+}
@@ -0,0 +1,660 @@
+//! Layout, placed bodies, and analysis results kept between frames.
+//!
+//! The viewport overlays and the right panel show the same placed bodies and
+//! analyses every frame. Each is computed once for the inputs it depends on
+//! and again only when one of them changes: a body is placed again when its
+//! mesh, placement, compensation, the printer, or the axis convention change,
+//! and every result derived from a placed body is keyed by the revision of
+//! that placement and the settings it uses. Quick results are computed on the
+//! UI thread; wall thickness, clearance, and supports run as document jobs so
+//! the UI stays responsive. The cache is brought up to date in
+//! `Workbench::on_frame`, before anything reads it.
+
+use std::collections::HashMap;
+
+use core_document::{BodyId, BodyMesh, Document, JobId, JobQueue, PrintVolume, WorkbenchId};
+use kernel_api::TriMesh;
+
+use crate::analysis::{
+    self, AnalysisMode, DraftAnalysis, DraftSettings, OverhangAnalysis, ThicknessAnalysis,
+};
+use crate::clearance::{self, ClearanceAnalysis};
+use crate::compensation::Compensation;
+use crate::estimate::BodyMeasure;
+use crate::hollow::{self, HollowSettings, Hollowed};
+use crate::layout::{self, BedFrame, PlacedBody, Placement, PrintLayout};
+use crate::slice::{self, Contour};
+use crate::supports::{self, SupportPreview, SupportSettings};
+use crate::{
+    DRAIN_HOLE_COLOR, HOLLOW_COLOR, OUTSIDE_COLOR, PLACED_COLOR, SUPPORT_COLOR, WORKBENCH_ID,
+};
+
+/// Content hash of a body mesh, telling whether it changed since the last
+/// frame (the host hands over fresh meshes every frame).
+fn fingerprint(mesh: &TriMesh) -> u64 {
+    // FNV-1a over 32-bit words: one pass over the mesh, far cheaper than
+    // placing or analyzing it.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut mix = |word: u32| hash = (hash ^ u64::from(word)).wrapping_mul(0x0100_0000_01b3);
+    for p in mesh.positions.iter().chain(&mesh.normals) {
+        for c in p {
+            mix(c.to_bits());
+        }
+    }
+    for &index in &mesh.indices {
+        mix(index);
+    }
+    hash
+}
+
+/// A cached result as seen by the overlays and the panel.
+pub(crate) enum Cached<'a, T> {
+    Ready(&'a T),
+    /// Its job is still running.
+    Computing,
+    /// Not computed: its job was cancelled or failed, or it was never started.
+    Missing,
+}
+
+impl<'a, T> Cached<'a, T> {
+    pub(crate) fn ready(self) -> Option<&'a T> {
+        match self {
+            Cached::Ready(result) => Some(result),
+            Cached::Computing | Cached::Missing => None,
+        }
+    }
+
+    fn map<U>(self, f: impl FnOnce(&'a T) -> &'a U) -> Cached<'a, U> {
+        match self {
+            Cached::Ready(result) => Cached::Ready(f(result)),
+            Cached::Computing => Cached::Computing,
+            Cached::Missing => Cached::Missing,
+        }
+    }
+}
+
+/// A result with its faces converted to world space for the viewport.
+pub(crate) struct Overlay<T> {
+    pub result: T,
+    pub meshes: Vec<(TriMesh, [f32; 3])>,
+}
+
+/// Result of a job started for the inputs identified by `key`.
+struct Background<K, T> {
+    key: K,
+    job: Option<(JobQueue, JobId)>,
+    result: Option<T>,
+}
+
+impl<K: PartialEq, T: Send + 'static> Background<K, T> {
+    fn start(
+        jobs: &JobQueue,
+        label: &str,
+        key: K,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> Self {
+        Self {
+            key,
+            job: Some((jobs.clone(), jobs.spawn(label, work))),
+            result: None,
+        }
+    }
+
+    /// Pick up the result once the job has finished.
+    fn poll(&mut self) {
+        let Some((jobs, job)) = &self.job else {
+            return;
+        };
+        if jobs.is_running(*job) {
+            return;
+        }
+        self.result = jobs.take::<T>(*job).and_then(Result::ok);
+        self.job = None;
+    }
+
+    fn get(&self) -> Cached<'_, T> {
+        match (&self.result, &self.job) {
+            (Some(result), _) => Cached::Ready(result),
+            (None, Some(_)) => Cached::Computing,
+            (None, None) => Cached::Missing,
+        }
+    }
+
+    fn cancel(&self) {
+        if let Some((jobs, job)) = &self.job {
+            jobs.cancel(*job);
+        }
+    }
+}
+
+/// A placed body with the revision of its placement.
+#[derive(Clone, Copy)]
+struct Revised<'a> {
+    body: &'a PlacedBody,
+    revision: u64,
+}
+
+/// Recompute the result of every body in `bodies` whose key changed, and
+/// forget the other bodies. `key` gives the inputs of a body's result and
+/// `compute` the result for them.
+fn update_computed<K: PartialEq, T>(
+    slots: &mut HashMap<BodyId, (K, T)>,
+    bodies: &[Revised],
+    key: impl Fn(Revised) -> K,
+    compute: impl Fn(&PlacedBody, &K) -> T,
+) {
+    slots.retain(|id, _| bodies.iter().any(|revised| revised.body.body == *id));
+    for &revised in bodies {
+        let key = key(revised);
+        if slots
+            .get(&revised.body.body)
+            .is_some_and(|(old, _)| *old == key)
+        {
+            continue;
+        }
+        let result = compute(revised.body, &key);
+        slots.insert(revised.body.body, (key, result));
+    }
+}
+
+/// Start (or keep) the job of every body in `bodies` for its current key, and
+/// forget the other bodies. `key` gives the inputs of a body's result and
+/// `work` the job computing it.
+fn update_background<K: PartialEq, T: Send + 'static, W: FnOnce() -> T + Send + 'static>(
+    slots: &mut HashMap<BodyId, Background<K, T>>,
+    jobs: &JobQueue,
+    label: &str,
+    bodies: &[Revised],
+    key: impl Fn(Revised) -> K,
+    work: impl Fn(&PlacedBody) -> W,
+) {
+    slots.retain(|id, slot| {
+        let keep = bodies.iter().any(|revised| revised.body.body == *id);
+        if !keep {
+            slot.cancel();
+        }
+        keep
+    });
+    for &revised in bodies {
+        let key = key(revised);
+        match slots.get_mut(&revised.body.body) {
+            Some(slot) if slot.key == key => slot.poll(),
+            slot => {
+                if let Some(slot) = slot {
+                    slot.cancel();
+                }
+                let slot = Background::start(jobs, label, key, work(revised.body));
+                slots.insert(revised.body.body, slot);
+            }
+        }
+    }
+}
+
+/// Inputs a body is placed from: its mesh, placement, and compensation, the
+/// printer, and the axis convention.
+type PlaceKey = (
+    u64,
+    Placement,
+    Option<Compensation>,
+    Option<PrintVolume>,
+    BedFrame,
+);
+
+/// How a cached placed body came about.
+struct Placing {
+    key: PlaceKey,
+    /// Identifies this placement of the body; results derived from the placed
+    /// body are keyed by it.
+    revision: u64,
+    /// World-space copy shown when the body is moved or compensated.
+    copy: Option<(TriMesh, [f32; 3])>,
+}
+
+/// Inputs of results keyed by a body's revision and one angle or length.
+type RevisionKey = (u64, u32);
+
+/// Inputs of a body's supports: its revision, the overhang angle, and the
+/// support settings.
+type SupportKey = (u64, u32, SupportSettings);
+
+/// Inputs of the clearance analysis: the two bodies, their meshes, the
+/// minimum clearance, and the axis convention.
+type ClearanceKey = ([BodyId; 2], [u64; 2], u32, BedFrame);
+
+/// Inputs of the layer preview: the revision of every body, the layer, the
+/// layer height, and whether the layers below are shown.
+type SliceKey = (Vec<u64>, usize, u32, bool);
+
+/// Per-body results computed on the UI thread.
+type Computed<K, T> = HashMap<BodyId, (K, T)>;
+
+/// Per-body results computed by jobs.
+type Jobs<K, T> = HashMap<BodyId, Background<K, T>>;
+
+/// What the workbench shows, deciding which results are kept up to date.
+pub(crate) struct Shown {
+    pub analysis: AnalysisMode,
+    /// Supports are previewed for every body; otherwise they are only
+    /// generated for the selected body, whose statistics the panel shows.
+    pub supports: bool,
+    pub selected: Option<BodyId>,
+    pub clearance_bodies: [Option<BodyId>; 2],
+    /// Previewed layer, and whether the layers below it are drawn.
+    pub layer: Option<(usize, bool)>,
+}
+
+/// Cached layout, placed bodies, and results of the Print Preparation
+/// workbench.
+#[derive(Default)]
+pub(crate) struct PrintCache {
+    /// Layout storage `layout` was loaded from.
+    source: Option<serde_json::Value>,
+    layout: PrintLayout,
+    /// Bodies placed by the layout, in the order of the host's body meshes.
+    placed: Vec<PlacedBody>,
+    /// How each of `placed` came about.
+    placing: Vec<Placing>,
+    last_revision: u64,
+    measures: Computed<u64, BodyMeasure>,
+    overhangs: Computed<RevisionKey, Overlay<OverhangAnalysis>>,
+    drafts: Computed<(u64, DraftSettings), Overlay<DraftAnalysis>>,
+    /// The hollowed bodies with their cavities and drain holes cut out.
+    hollows: Jobs<(u64, HollowSettings), Overlay<Hollowed>>,
+    thickness: Jobs<RevisionKey, Overlay<ThicknessAnalysis>>,
+    supports: Jobs<SupportKey, Overlay<SupportPreview>>,
+    /// Clearance between the two bodies chosen for the analysis.
+    clearance: Option<Background<ClearanceKey, Overlay<ClearanceAnalysis>>>,
+    /// Contours of the layer preview, flagged as current or lower layer.
+    slices: Option<(SliceKey, Vec<(Contour, bool)>)>,
+}
+
+impl PrintCache {
+    /// Reload the layout if the document's layout storage changed.
+    pub(crate) fn sync_layout(&mut self, document: &Document) {
+        let data = document
+            .get_workbench_storage(&WorkbenchId::from(WORKBENCH_ID))
+            .map(|storage| &storage.data);
+        if data != self.source.as_ref() {
+            self.source = data.cloned();
+            self.layout = PrintLayout::load(document);
+        }
+    }
+
+    /// Bring the placed bodies and the results shown by the workbench up to
+    /// date with the document.
+    pub(crate) fn update(
+        &mut self,
+        document: &Document,
+        body_meshes: &[BodyMesh],
+        printer: Option<&PrintVolume>,
+        frame: BedFrame,
+        shown: &Shown,
+    ) {
+        self.sync_layout(document);
+        self.place(body_meshes, printer, frame);
+        self.update_clearance(
+            document.jobs(),
+            body_meshes,
+            shown.clearance_bodies,
+            frame,
+            shown.analysis == AnalysisMode::Clearance,
+        );
+
+        let jobs = document.jobs();
+        let layout = &self.layout;
+        let bodies: Vec<Revised> = self
+            .placed
+            .iter()
+            .zip(&self.placing)
+            .map(|(body, placing)| Revised {
+                body,
+                revision: placing.revision,
+            })
+            .collect();
+
+        update_computed(
+            &mut self.measures,
+            &bodies,
+            |revised| revised.revision,
+            |body, _| BodyMeasure::new(body),
+        );
+
+        let hollowed: Vec<Revised> = bodies
+            .iter()
+            .copied()
+            .filter(|revised| layout.hollow(revised.body.body).is_some())
+            .collect();
+        let hollow_settings = |body: BodyId| layout.hollow(body).cloned().unwrap_or_default();
+        update_background(
+            &mut self.hollows,
+            jobs,
+            "Hollowing",
+            &hollowed,
+            |revised| (revised.revision, hollow_settings(revised.body.body)),
+            |body| {
+                let (body, settings) = (body.clone(), hollow_settings(body.body));
+                move || {
+                    let result = hollow::hollow(&body, &settings, &frame);
+                    let (inner, holes) = hollow::world_meshes(&result, &frame);
+                    let mut meshes = vec![(inner, HOLLOW_COLOR)];
+                    if !holes.indices.is_empty() {
+                        meshes.push((holes, DRAIN_HOLE_COLOR));
+                    }
+                    Overlay { result, meshes }
+                }
+            },
+        );
+
+        let overhang_angle = layout.overhang_angle_deg;
+        match shown.analysis {
+            AnalysisMode::Overhangs => update_computed(
+                &mut self.overhangs,
+                &bodies,
+                |revised| (revised.revision, overhang_angle.to_bits()),
+                |body, _| {
+                    let result = analysis::analyze_overhangs(body, overhang_angle);
+                    let meshes = result.faces.world_meshes(&frame);
+                    Overlay { result, meshes }
+                },
+            ),
+            AnalysisMode::Draft => update_computed(
+                &mut self.drafts,
+                &bodies,
+                |revised| (revised.revision, layout.draft),
+                |body, _| {
+                    let result = analysis::analyze_draft(body, &layout.draft);
+                    let meshes = result.faces.world_meshes(&frame);
+                    Overlay { result, meshes }
+                },
+            ),
+            AnalysisMode::WallThickness => {
+                let min_thickness = layout.min_wall_thickness;
+                update_background(
+                    &mut self.thickness,
+                    jobs,
+                    "Wall thickness analysis",
+                    &bodies,
+                    |revised| (revised.revision, min_thickness.to_bits()),
+                    |body| {
+                        let body = body.clone();
+                        move || {
+                            let result = analysis::analyze_wall_thickness(&body, min_thickness);
+                            let meshes = result.faces.world_meshes(&frame);
+                            Overlay { result, meshes }
+                        }
+                    },
+                );
+            }
+            AnalysisMode::None | AnalysisMode::Clearance => {}
+        }
+
+        let supported: Vec<Revised> = bodies
+            .iter()
+            .copied()
+            .filter(|revised| shown.supports || shown.selected == Some(revised.body.body))
+            .collect();
+        let support_settings = layout.supports;
+        update_background(
+            &mut self.supports,
+            jobs,
+            "Support generation",
+            &supported,
+            |revised| (revised.revision, overhang_angle.to_bits(), support_settings),
+            |body| {
+                let body = body.clone();
+                move || {
+                    let result =
+                        supports::generate_supports(&body, overhang_angle, &support_settings);
+                    let meshes = if result.mesh.indices.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![(result.world_mesh(&frame), SUPPORT_COLOR)]
+                    };
+                    Overlay { result, meshes }
+                }
+            },
+        );
+
+        match shown.layer {
+            Some((layer, below)) => {
+                let key = (
+                    self.placing
+                        .iter()
+                        .map(|placing| placing.revision)
+                        .collect(),
+                    layer,
+                    layout.layer_height.to_bits(),
+                    below,
+                );
+                if !matches!(&self.slices, Some((old, _)) if *old == key) {
+                    let contours =
+                        slice::slice_layers(&self.placed, layer, layout.layer_height, below);
+                    self.slices = Some((key, contours));
+                }
+            }
+            None => self.slices = None,
+        }
+    }
+
+    /// Place the bodies whose inputs changed since the last frame, keep the
+    /// others, and forget the bodies that are gone.
+    fn place(&mut self, body_meshes: &[BodyMesh], printer: Option<&PrintVolume>, frame: BedFrame) {
+        let mut previous: HashMap<BodyId, (PlacedBody, Placing)> = self
+            .placed
+            .drain(..)
+            .zip(self.placing.drain(..))
+            .map(|(body, placing)| (body.body, (body, placing)))
+            .collect();
+        for body_mesh in body_meshes {
+            let id = body_mesh.body;
+            let compensation = self.layout.compensation(id).copied();
+            let key = (
+                fingerprint(&body_mesh.mesh),
+                self.layout.placement(id),
+                compensation,
+                printer.copied(),
+                frame,
+            );
+            if let Some((body, placing)) = previous.remove(&id) {
+                if placing.key == key {
+                    self.placed.push(body);
+                    self.placing.push(placing);
+                    continue;
+                }
+            }
+            let Some(body) = layout::place_in_layout(&self.layout, &frame, body_mesh, printer)
+            else {
+                continue;
+            };
+            let copy = (!body.placement.is_identity() || compensation.is_some()).then(|| {
+                let color = if body.fits() {
+                    PLACED_COLOR
+                } else {
+                    OUTSIDE_COLOR
+                };
+                (layout::placed_world_mesh(&body, &frame), color)
+            });
+            self.last_revision += 1;
+            self.placed.push(body);
+            self.placing.push(Placing {
+                key,
+                revision: self.last_revision,
+                copy,
+            });
+        }
+    }
+
+    /// Measure the clearance between `bodies` in the background when they,
+    /// their meshes, or the minimum clearance changed (only while `active`).
+    /// Forgets the result when they are not two different bodies with
+    /// geometry.
+    fn update_clearance(
+        &mut self,
+        jobs: &JobQueue,
+        body_meshes: &[BodyMesh],
+        bodies: [Option<BodyId>; 2],
+        frame: BedFrame,
+        active: bool,
+    ) {
+        let fingerprint = |id: Option<BodyId>| {
+            let index = self.placed.iter().position(|body| Some(body.body) == id)?;
+            Some(self.placing[index].key.0)
+        };
+        let pair = match bodies {
+            [Some(a), Some(b)] if a != b => fingerprint(Some(a))
+                .zip(fingerprint(Some(b)))
+                .map(|(fa, fb)| ([a, b], [fa, fb])),
+            _ => None,
+        };
+        let Some((ids, fingerprints)) = pair else {
+            if let Some(slot) = self.clearance.take() {
+                slot.cancel();
+            }
+            return;
+        };
+        if !active {
+            return;
+        }
+        let min_clearance = self.layout.min_clearance;
+        let key = (ids, fingerprints, min_clearance.to_bits(), frame);
+        match &mut self.clearance {
+            Some(slot) if slot.key == key => slot.poll(),
+            slot => {
+                if let Some(slot) = slot {
+                    slot.cancel();
+                }
+                // Compared where they sit in the model, not on the bed.
+                let model_body = |id: BodyId| {
+                    let body_mesh = body_meshes.iter().find(|m| m.body == id)?;
+                    layout::place_body(body_mesh, Placement::default(), &frame, None)
+                };
+                let (Some(a), Some(b)) = (model_body(ids[0]), model_body(ids[1])) else {
+                    *slot = None;
+                    return;
+                };
+                *slot = Some(Background::start(
+                    jobs,
+                    "Clearance analysis",
+                    key,
+                    move || {
+                        let result = clearance::analyze_clearance(&a, &b, min_clearance);
+                        let meshes = result.faces.world_meshes(&frame);
+                        Overlay { result, meshes }
+                    },
+                ));
+            }
+        }
+    }
+
+    /// The document's print layout, as of the last update.
+    pub(crate) fn layout(&self) -> &PrintLayout {
+        &self.layout
+    }
+
+    /// Bodies placed by the layout (those with geometry).
+    pub(crate) fn placed(&self) -> &[PlacedBody] {
+        &self.placed
+    }
+
+    pub(crate) fn measure(&self, body: BodyId) -> Option<&BodyMeasure> {
+        self.measures.get(&body).map(|(_, measure)| measure)
+    }
+
+    pub(crate) fn overhangs(&self, body: BodyId) -> Option<&OverhangAnalysis> {
+        self.overhangs
+            .get(&body)
+            .map(|(_, overlay)| &overlay.result)
+    }
+
+    pub(crate) fn draft(&self, body: BodyId) -> Option<&DraftAnalysis> {
+        self.drafts.get(&body).map(|(_, overlay)| &overlay.result)
+    }
+
+    /// `body` hollowed with its current settings (missing unless it is hollowed).
+    pub(crate) fn hollowed(&self, body: BodyId) -> Cached<'_, Hollowed> {
+        self.hollows
+            .get(&body)
+            .map_or(Cached::Missing, Background::get)
+            .map(|overlay| &overlay.result)
+    }
+
+    /// Wall thickness of `body` for its current geometry.
+    pub(crate) fn thickness(&self, body: BodyId) -> Cached<'_, ThicknessAnalysis> {
+        self.thickness
+            .get(&body)
+            .map_or(Cached::Missing, Background::get)
+            .map(|overlay| &overlay.result)
+    }
+
+    /// Supports of `body` for its current geometry.
+    pub(crate) fn supports(&self, body: BodyId) -> Cached<'_, SupportPreview> {
+        self.supports
+            .get(&body)
+            .map_or(Cached::Missing, Background::get)
+            .map(|overlay| &overlay.result)
+    }
+
+    /// Clearance between the chosen bodies (None when there are no two bodies
+    /// with geometry to compare).
+    pub(crate) fn clearance(&self) -> Option<Cached<'_, ClearanceAnalysis>> {
+        self.clearance
+            .as_ref()
+            .map(|slot| slot.get().map(|overlay| &overlay.result))
+    }
+
+    /// Contours of the layer preview, flagged as belonging to the current
+    /// layer (empty when the preview is off).
+    pub(crate) fn slices(&self) -> &[(Contour, bool)] {
+        self.slices
+            .as_ref()
+            .map_or(&[], |(_, contours)| contours.as_slice())
+    }
+
+    /// Viewport meshes of the placed copies or of `analysis`, of the hollowed
+    /// bodies, and of the supports when they are previewed.
+    pub(crate) fn meshes(
+        &self,
+        analysis: AnalysisMode,
+        supports: bool,
+    ) -> Vec<(TriMesh, [f32; 3])> {
+        let ids = || self.placed.iter().map(|body| body.body);
+        let mut meshes: Vec<_> = match analysis {
+            AnalysisMode::None => self
+                .placing
+                .iter()
+                .filter_map(|placing| placing.copy.clone())
+                .collect(),
+            AnalysisMode::Overhangs => ids()
+                .filter_map(|id| self.overhangs.get(&id))
+                .flat_map(|(_, overlay)| overlay.meshes.clone())
+                .collect(),
+            AnalysisMode::Draft => ids()
+                .filter_map(|id| self.drafts.get(&id))
+                .flat_map(|(_, overlay)| overlay.meshes.clone())
+                .collect(),
+            AnalysisMode::WallThickness => ids()
+                .filter_map(|id| self.thickness.get(&id)?.get().ready())
+                .flat_map(|overlay| overlay.meshes.clone())
+                .collect(),
+            AnalysisMode::Clearance => self
+                .clearance
+                .as_ref()
+                .and_then(|slot| slot.get().ready())
+                .map(|overlay| overlay.meshes.clone())
+                .unwrap_or_default(),
+        };
+        meshes.extend(
+            ids()
+                .filter_map(|id| self.hollows.get(&id)?.get().ready())
+                .flat_map(|overlay| overlay.meshes.clone()),
+        );
+        if supports {
+            meshes.extend(
+                ids()
+                    .filter_map(|id| self.supports.get(&id)?.get().ready())
+                    .flat_map(|overlay| overlay.meshes.clone()),
+            );
+        }
+        meshes
+    }
+}
@@ -0,0 +1,124 @@
+//! Clearance between two mating bodies, measured where they sit in the model
+//! (print placements are ignored so assemblies are checked as designed).
+
+use core_document::bvh::Bvh;
+use glam::Vec3;
+
+use crate::analysis::{self, ColoredFaces, Face};
+use crate::layout::PlacedBody;
+
+/// Typical gap for parts that have to slide or snap together (mm).
+pub const DEFAULT_MIN_CLEARANCE: f32 = 0.2;
+
+/// Faces up to this multiple of the minimum clearance are shown as close.
+const CLOSE_FACTOR: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClearanceClass {
+    /// Inside the other body.
+    Interfering,
+    /// Closer than the minimum clearance.
+    Tight,
+    /// Within twice the minimum clearance.
+    Close,
+    Ok,
+}
+
+impl ClearanceClass {
+    pub const ALL: [ClearanceClass; 4] = [
+        ClearanceClass::Interfering,
+        ClearanceClass::Tight,
+        ClearanceClass::Close,
+        ClearanceClass::Ok,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClearanceClass::Interfering => "Interfering",
+            ClearanceClass::Tight => "Tighter than minimum",
+            ClearanceClass::Close => "Close",
+            ClearanceClass::Ok => "Enough clearance",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            ClearanceClass::Interfering => [0.8, 0.2, 0.8],
+            ClearanceClass::Tight => [0.95, 0.3, 0.25],
+            ClearanceClass::Close => [0.95, 0.8, 0.25],
+            ClearanceClass::Ok => [0.3, 0.8, 0.4],
+        }
+    }
+}
+
+/// Faces of both bodies colored by their distance to the other body.
+#[derive(Debug, Clone, Default)]
+pub struct ClearanceAnalysis {
+    pub faces: ColoredFaces,
+    /// Smallest gap found (0 when the bodies interfere).
+    pub min_clearance: Option<f32>,
+    /// Closest pair of points (printer coordinates).
+    pub closest: Option<(Vec3, Vec3)>,
+    /// Total area of faces tighter than the minimum (including interfering ones), in mm².
+    pub tight_area: f32,
+    pub interfering: bool,
+}
+
+/// Distance from a face to the other body (sampled at its vertices and
+/// centroid) and the closest pair of points; 0 if the face is inside it.
+fn face_distance(face: &Face, other: &Bvh) -> (f32, Vec3, Vec3) {
+    let mut best = (f32::INFINITY, face.centroid(), face.centroid());
+    for p in [
+        face.centroid(),
+        face.vertices[0],
+        face.vertices[1],
+        face.vertices[2],
+    ] {
+        if let Some(hit) = other.closest(p) {
+            if hit.distance < best.0 {
+                best = (hit.distance, p, hit.point);
+            }
+        }
+    }
+    if other.contains(face.centroid()) {
+        best.0 = 0.0;
+    }
+    best
+}
+
+/// Measure how close every face of each body comes to the other body. Each
+/// body's faces are looked up in a [`Bvh`], so the cost grows as n·log(m)
+/// rather than with the product of the triangle counts.
+pub fn analyze_clearance(a: &PlacedBody, b: &PlacedBody, min_clearance: f32) -> ClearanceAnalysis {
+    let (faces_a, faces_b) = (analysis::faces(a), analysis::faces(b));
+    let (tree_a, tree_b) = (analysis::face_tree(&faces_a), analysis::face_tree(&faces_b));
+    let mut result = ClearanceAnalysis::default();
+    for (faces, other) in [(&faces_a, &tree_b), (&faces_b, &tree_a)] {
+        for face in faces {
+            let (distance, p, q) = face_distance(face, other);
+            let class = if distance <= 0.0 {
+                ClearanceClass::Interfering
+            } else if distance < min_clearance {
+                ClearanceClass::Tight
+            } else if distance < min_clearance * CLOSE_FACTOR {
+                ClearanceClass::Close
+            } else {
+                ClearanceClass::Ok
+            };
+            match class {
+                ClearanceClass::Interfering => {
+                    result.interfering = true;
+                    result.tight_area += face.area;
+                }
+                ClearanceClass::Tight => result.tight_area += face.area,
+                _ => {}
+            }
+            if distance.is_finite() && result.min_clearance.map_or(true, |min| distance < min) {
+                result.min_clearance = Some(distance);
+                result.closest = Some((p, q));
+            }
+            result.faces.push(class.color(), face);
+        }
+    }
+    result
+}
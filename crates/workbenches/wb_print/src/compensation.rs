@@ -0,0 +1,207 @@
+//! Print compensation: scaling and horizontal offsets that counter material
+//! shrinkage and elephant's foot (the squashed, bulging first layers).
+//!
+//! Like placements, compensation only changes the placed copy of a body; the
+//! modeled geometry keeps its nominal dimensions.
+
+use std::collections::HashMap;
+
+use core_document::{BodyId, PrintVolume};
+use glam::{Mat4, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{self, VertexKey};
+use crate::layout::{self, PlacedBody};
+
+/// Height (mm) over which the elephant's foot inset fades out (one first layer).
+pub const DEFAULT_ELEPHANT_FOOT_HEIGHT: f32 = 0.2;
+
+/// Faces whose normal has a smaller horizontal component are treated as flat
+/// and do not push their vertices sideways.
+const HORIZONTAL_EPSILON: f32 = 1e-3;
+
+/// Sharp corners move at most this multiple of the offset (like a miter limit).
+const MITER_LIMIT: f32 = 2.0;
+
+/// How one body is resized for printing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Compensation {
+    /// Scale factors along the printer X, Y and Z axes (1 = nominal).
+    pub scale: [f32; 3],
+    /// Horizontal expansion (mm): positive values grow the outline and shrink
+    /// holes, negative values do the opposite.
+    pub xy_offset: f32,
+    /// Inset (mm) of the bottom of the body, fading out at `elephant_foot_height`.
+    pub elephant_foot: f32,
+    pub elephant_foot_height: f32,
+}
+
+impl Default for Compensation {
+    fn default() -> Self {
+        Self {
+            scale: [1.0; 3],
+            xy_offset: 0.0,
+            elephant_foot: 0.0,
+            elephant_foot_height: DEFAULT_ELEPHANT_FOOT_HEIGHT,
+        }
+    }
+}
+
+impl Compensation {
+    pub fn is_identity(&self) -> bool {
+        self.scale == [1.0; 3] && self.xy_offset == 0.0 && self.elephant_foot == 0.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BodyCompensation {
+    pub body: BodyId,
+    pub compensation: Compensation,
+}
+
+/// Scale factor that makes up for a material shrinking by `percent`.
+pub fn shrinkage_scale(percent: f32) -> f32 {
+    1.0 / (1.0 - percent / 100.0).max(0.01)
+}
+
+/// Apply `compensation` to a placed body. Scaling happens about the center of
+/// the body's footprint at bed level, so the body stays where it was placed.
+pub fn compensate(
+    body: &mut PlacedBody,
+    compensation: &Compensation,
+    printer: Option<&PrintVolume>,
+) {
+    let scale = Vec3::from(compensation.scale);
+    let pivot = Vec3::new(
+        (body.min.x + body.max.x) * 0.5,
+        (body.min.y + body.max.y) * 0.5,
+        body.min.z,
+    );
+    let scaling =
+        Mat4::from_translation(pivot) * Mat4::from_scale(scale) * Mat4::from_translation(-pivot);
+    for position in &mut body.positions {
+        *position = scaling.transform_point3(*position);
+    }
+    for normal in &mut body.normals {
+        *normal = (*normal / scale).normalize_or_zero();
+    }
+    body.matrix = scaling * body.matrix;
+
+    let foot_top = body.min.z + compensation.elephant_foot_height;
+    let has_foot = compensation.elephant_foot != 0.0 && compensation.elephant_foot_height > 0.0;
+    if has_foot {
+        // Give the walls vertices at the top of the foot so only the foot is inset.
+        split_at(body, foot_top);
+    }
+    if compensation.xy_offset != 0.0 || has_foot {
+        let directions = offset_directions(body);
+        let bottom = body.min.z;
+        for position in &mut body.positions {
+            let mut distance = compensation.xy_offset;
+            if has_foot && position.z < foot_top {
+                let fade = (position.z - bottom) / (foot_top - bottom);
+                distance -= compensation.elephant_foot * (1.0 - fade.clamp(0.0, 1.0));
+            }
+            if let Some(direction) = directions.get(&analysis::weld_key(*position)) {
+                *position += (*direction * distance).extend(0.0);
+            }
+        }
+    }
+
+    if let Some((min, max)) = layout::bounds(body.positions.iter().copied()) {
+        body.min = min;
+        body.max = max;
+    }
+    body.vertices_outside = layout::vertices_outside(&body.positions, printer);
+}
+
+/// Horizontal direction each welded vertex moves by per millimetre of offset,
+/// lengthened at corners so the walls on both sides move by the full offset.
+fn offset_directions(body: &PlacedBody) -> HashMap<VertexKey, Vec2> {
+    let faces: Vec<_> = analysis::faces(body)
+        .into_iter()
+        .filter_map(|face| {
+            let horizontal = face.normal.truncate();
+            (horizontal.length() > HORIZONTAL_EPSILON).then(|| (face, horizontal.normalize()))
+        })
+        .collect();
+    let mut sums: HashMap<VertexKey, Vec2> = HashMap::new();
+    for (face, direction) in &faces {
+        for vertex in face.vertices {
+            *sums.entry(analysis::weld_key(vertex)).or_default() += *direction * face.area;
+        }
+    }
+    // Smallest agreement between the averaged direction and any adjacent wall.
+    let mut agreement: HashMap<VertexKey, f32> = HashMap::new();
+    for (face, direction) in &faces {
+        for vertex in face.vertices {
+            let key = analysis::weld_key(vertex);
+            let dot = sums[&key].normalize_or_zero().dot(*direction);
+            let entry = agreement.entry(key).or_insert(1.0);
+            *entry = entry.min(dot);
+        }
+    }
+    sums.into_iter()
+        .map(|(key, sum)| {
+            let dot = agreement[&key].max(1.0 / MITER_LIMIT);
+            (key, sum.normalize_or_zero() / dot)
+        })
+        .collect()
+}
+
+/// Cut every triangle crossing the plane at height `z` so the mesh has
+/// vertices along the cut. Both pieces keep the triangle's winding.
+fn split_at(body: &mut PlacedBody, z: f32) {
+    let has_normals = body.normals.len() == body.positions.len();
+    // Cut points shared by neighbouring triangles, keyed by edge.
+    let mut cuts: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(body.indices.len());
+    let side = |p: Vec3| {
+        if p.z < z - f32::EPSILON {
+            -1
+        } else if p.z > z + f32::EPSILON {
+            1
+        } else {
+            0
+        }
+    };
+    for triangle in body.indices.chunks_exact(3) {
+        let sides = [0, 1, 2].map(|i| side(body.positions[triangle[i] as usize]));
+        if !sides.contains(&-1) || !sides.contains(&1) {
+            indices.extend_from_slice(triangle);
+            continue;
+        }
+        // Walk the outline, inserting a cut point on each crossing edge.
+        let mut outline = Vec::with_capacity(4);
+        for i in 0..3 {
+            let j = (i + 1) % 3;
+            outline.push((triangle[i], sides[i]));
+            if sides[i] * sides[j] < 0 {
+                let (a, b) = (triangle[i].min(triangle[j]), triangle[i].max(triangle[j]));
+                let cut = *cuts.entry((a, b)).or_insert_with(|| {
+                    let (pa, pb) = (body.positions[a as usize], body.positions[b as usize]);
+                    let t = (z - pa.z) / (pb.z - pa.z);
+                    body.positions.push(pa.lerp(pb, t).with_z(z));
+                    if has_normals {
+                        let (na, nb) = (body.normals[a as usize], body.normals[b as usize]);
+                        body.normals.push(na.lerp(nb, t).normalize_or_zero());
+                    }
+                    (body.positions.len() - 1) as u32
+                });
+                outline.push((cut, 0));
+            }
+        }
+        // Both pieces are convex, so a fan triangulates each of them.
+        for keep in [-1, 1] {
+            let piece: Vec<u32> = outline
+                .iter()
+                .filter(|(_, side)| *side != -keep)
+                .map(|(index, _)| *index)
+                .collect();
+            for k in 1..piece.len().saturating_sub(1) {
+                indices.extend([piece[0], piece[k], piece[k + 1]]);
+            }
+        }
+    }
+    body.indices = indices;
+}
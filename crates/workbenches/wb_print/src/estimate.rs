@@ -0,0 +1,161 @@
+//! Rough print time and material estimates.
+//!
+//! Without a slicer the extruded volume is approximated from the mesh: a shell
+//! of the perimeter thickness under the whole surface, and the given infill
+//! share of the volume inside it. Print time is that volume divided by the
+//! volumetric flow rate, plus a fixed pause per layer. A loaded G-code file
+//! gives the exact extruded volume instead, from its filament length.
+
+use core_document::Material;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis;
+use crate::gcode::Toolpath;
+use crate::hollow::{self, Hollowed};
+use crate::layout::PlacedBody;
+
+/// Per-document estimator settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EstimateSettings {
+    /// Average volumetric flow rate while printing (mm³/s).
+    pub flow_rate: f32,
+    /// Infill density (%).
+    pub infill_percent: f32,
+    /// Thickness of the perimeters and top/bottom skins (mm).
+    pub shell_thickness: f32,
+    /// Time spent per layer on travel and layer changes (s).
+    pub layer_overhead: f32,
+    /// Filament diameter (mm), to convert G-code filament length to volume.
+    pub filament_diameter: f32,
+}
+
+impl Default for EstimateSettings {
+    fn default() -> Self {
+        Self {
+            flow_rate: 8.0,
+            infill_percent: 20.0,
+            shell_thickness: 0.8,
+            layer_overhead: 2.0,
+            filament_diameter: 1.75,
+        }
+    }
+}
+
+/// Estimated material and time of one body or a whole plate.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrintEstimate {
+    /// Extruded volume (mm³).
+    pub volume: f32,
+    /// Print time (s).
+    pub time: f32,
+    /// Mass (g), when the material is known.
+    pub mass: Option<f32>,
+    /// Cost, when the material is known.
+    pub cost: Option<f32>,
+}
+
+impl PrintEstimate {
+    fn new(volume: f32, time: f32, material: Option<&Material>) -> Self {
+        Self {
+            volume,
+            time,
+            mass: material.map(|material| material.mass(volume)),
+            cost: material.map(|material| material.cost(volume)),
+        }
+    }
+
+    /// Sum of several estimates. Mass and cost are only known if they are
+    /// known for every part.
+    pub fn total(estimates: &[PrintEstimate]) -> Self {
+        let sum = |value: fn(&PrintEstimate) -> Option<f32>| {
+            estimates.iter().map(value).sum::<Option<f32>>()
+        };
+        Self {
+            volume: estimates.iter().map(|e| e.volume).sum(),
+            time: estimates.iter().map(|e| e.time).sum(),
+            mass: sum(|e| e.mass),
+            cost: sum(|e| e.cost),
+        }
+    }
+}
+
+/// Geometry of a placed body that its estimate depends on, measured once
+/// rather than for every estimate.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BodyMeasure {
+    /// Enclosed volume (mm³).
+    pub volume: f32,
+    /// Surface area (mm²).
+    pub area: f32,
+    /// Height above its lowest point (mm).
+    pub height: f32,
+}
+
+impl BodyMeasure {
+    pub fn new(body: &PlacedBody) -> Self {
+        let faces = analysis::faces(body);
+        Self {
+            volume: hollow::enclosed_volume(&faces),
+            area: faces.iter().map(|face| face.area).sum(),
+            height: body.size().z,
+        }
+    }
+}
+
+/// Estimate for one placed body. A hollowed body is estimated from the
+/// material left around its cavity, with its walls printed solid.
+pub fn estimate_body(
+    measure: &BodyMeasure,
+    hollow: Option<&Hollowed>,
+    settings: &EstimateSettings,
+    layer_height: f32,
+    material: Option<&Material>,
+) -> PrintEstimate {
+    let volume = match hollow {
+        Some(hollowed) => hollowed.material_volume,
+        None => {
+            let solid = measure.volume;
+            let shell = (measure.area * settings.shell_thickness).min(solid);
+            shell + (solid - shell) * settings.infill_percent.clamp(0.0, 100.0) / 100.0
+        }
+    };
+    let layers = if layer_height > 0.0 {
+        (measure.height / layer_height).ceil()
+    } else {
+        0.0
+    };
+    PrintEstimate::new(volume, time(volume, layers, settings), material)
+}
+
+/// Estimate from a sliced G-code file.
+pub fn estimate_toolpath(
+    toolpath: &Toolpath,
+    settings: &EstimateSettings,
+    material: Option<&Material>,
+) -> PrintEstimate {
+    let radius = settings.filament_diameter / 2.0;
+    let volume = std::f32::consts::PI * radius * radius * toolpath.filament_length;
+    let layers = toolpath.layers.len() as f32;
+    PrintEstimate::new(volume, time(volume, layers, settings), material)
+}
+
+fn time(volume: f32, layers: f32, settings: &EstimateSettings) -> f32 {
+    let extrusion = if settings.flow_rate > 0.0 {
+        volume / settings.flow_rate
+    } else {
+        0.0
+    };
+    extrusion + layers * settings.layer_overhead
+}
+
+/// `3h 25m`, or `12m 30s` under an hour.
+pub fn format_duration(seconds: f32) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else {
+        format!("{minutes}m {secs:02}s")
+    }
+}
@@ -0,0 +1,425 @@
+//! G-code toolpath preview.
+//!
+//! Parses the moves of a sliced file (G0/G1 lines and G2/G3 arcs) into segments
+//! tagged with their layer and feature type so the output of a slicer can be
+//! checked against the bed inside printCAD. Feature types come from the `;TYPE:`
+//! comments written by Cura, PrusaSlicer, SuperSlicer, and OrcaSlicer; layers are
+//! detected from the height of extruding moves, so files without comments work too.
+
+use std::path::Path;
+
+use glam::Vec3;
+
+/// Arcs are split into segments of at most this length (mm).
+const ARC_SEGMENT_LENGTH: f32 = 0.5;
+
+/// Extruding moves more than this (mm) away from every known layer height start a new layer.
+const LAYER_TOLERANCE: f32 = 1e-3;
+
+/// Millimetres per inch (for files switched to inches with G20).
+const MM_PER_INCH: f32 = 25.4;
+
+/// Kind of extrusion a segment belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+    OuterWall,
+    InnerWall,
+    Infill,
+    SolidInfill,
+    Bridge,
+    Support,
+    Skirt,
+    Travel,
+    Other,
+}
+
+impl FeatureType {
+    pub const ALL: [FeatureType; 9] = [
+        FeatureType::OuterWall,
+        FeatureType::InnerWall,
+        FeatureType::Infill,
+        FeatureType::SolidInfill,
+        FeatureType::Bridge,
+        FeatureType::Support,
+        FeatureType::Skirt,
+        FeatureType::Travel,
+        FeatureType::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FeatureType::OuterWall => "Outer wall",
+            FeatureType::InnerWall => "Inner wall",
+            FeatureType::Infill => "Infill",
+            FeatureType::SolidInfill => "Solid infill",
+            FeatureType::Bridge => "Bridge",
+            FeatureType::Support => "Support",
+            FeatureType::Skirt => "Skirt / brim",
+            FeatureType::Travel => "Travel",
+            FeatureType::Other => "Other",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            FeatureType::OuterWall => [1.0, 0.55, 0.1],
+            FeatureType::InnerWall => [0.95, 0.85, 0.25],
+            FeatureType::Infill => [0.75, 0.25, 0.25],
+            FeatureType::SolidInfill => [0.65, 0.3, 0.85],
+            FeatureType::Bridge => [0.3, 0.6, 0.95],
+            FeatureType::Support => [0.3, 0.8, 0.4],
+            FeatureType::Skirt => [0.2, 0.8, 0.8],
+            FeatureType::Travel => [0.45, 0.45, 0.5],
+            FeatureType::Other => [0.8, 0.8, 0.8],
+        }
+    }
+
+    /// Feature named by a slicer's `;TYPE:` comment.
+    fn from_comment(name: &str) -> FeatureType {
+        let name = name.trim().to_ascii_lowercase();
+        let has = |needle: &str| name.contains(needle);
+        if has("support") {
+            FeatureType::Support
+        } else if has("skirt") || has("brim") {
+            FeatureType::Skirt
+        } else if has("bridge") {
+            FeatureType::Bridge
+        } else if has("external") || has("outer") || name == "wall-outer" {
+            FeatureType::OuterWall
+        } else if has("perimeter") || has("wall") {
+            FeatureType::InnerWall
+        } else if has("solid") || has("top") || has("bottom") || name == "skin" {
+            FeatureType::SolidInfill
+        } else if has("infill") || name == "fill" {
+            FeatureType::Infill
+        } else {
+            FeatureType::Other
+        }
+    }
+}
+
+/// One straight move (arcs are split into several).
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub from: Vec3,
+    pub to: Vec3,
+    pub feature: FeatureType,
+    /// Index into `Toolpath::layers`.
+    pub layer: usize,
+}
+
+/// All moves of a G-code file, in printer coordinates (millimetres).
+#[derive(Debug, Clone, Default)]
+pub struct Toolpath {
+    pub segments: Vec<Segment>,
+    /// Height of each layer, in the order they are first printed.
+    pub layers: Vec<f32>,
+    /// Filament pushed through the nozzle (mm of filament).
+    pub filament_length: f32,
+    /// Lowest and highest extrusion points.
+    pub bounds: Option<(Vec3, Vec3)>,
+}
+
+impl Toolpath {
+    /// Read and parse a G-code file.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Ok(parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Segments of the layers `first..=last`.
+    pub fn segments_in(&self, first: usize, last: usize) -> impl Iterator<Item = &Segment> {
+        self.segments
+            .iter()
+            .filter(move |segment| (first..=last).contains(&segment.layer))
+    }
+
+    pub fn extrusion_count(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.feature != FeatureType::Travel)
+            .count()
+    }
+}
+
+/// Parser state: the machine's modal settings and position.
+struct Machine {
+    position: Vec3,
+    extruder: f32,
+    absolute: bool,
+    absolute_extruder: bool,
+    scale: f32,
+    feature: FeatureType,
+    /// Layer of the last extruding move.
+    layer: usize,
+}
+
+/// Value of a single-letter word, e.g. `X12.5`.
+fn word(words: &[&str], letter: char) -> Option<f32> {
+    words.iter().find_map(|w| {
+        let mut chars = w.chars();
+        let first = chars.next()?;
+        if first.eq_ignore_ascii_case(&letter) {
+            chars.as_str().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse G-code text. Unknown commands are skipped; malformed words are ignored.
+pub fn parse(text: &str) -> Toolpath {
+    let mut machine = Machine {
+        position: Vec3::ZERO,
+        extruder: 0.0,
+        absolute: true,
+        absolute_extruder: true,
+        scale: 1.0,
+        feature: FeatureType::Other,
+        layer: 0,
+    };
+    let mut toolpath = Toolpath::default();
+
+    for line in text.lines() {
+        let (code, comment) = match line.split_once(';') {
+            Some((code, comment)) => (code, Some(comment)),
+            None => (line, None),
+        };
+        if let Some(name) = comment.and_then(|c| c.trim().strip_prefix("TYPE:")) {
+            machine.feature = FeatureType::from_comment(name);
+        }
+        let words: Vec<&str> = code.split_whitespace().collect();
+        let Some(command) = words.first().map(|w| w.to_ascii_uppercase()) else {
+            continue;
+        };
+        match command.as_str() {
+            "G0" | "G00" | "G1" | "G01" | "G2" | "G02" | "G3" | "G03" => {
+                move_to(&mut machine, &mut toolpath, &command, &words);
+            }
+            "G20" => machine.scale = MM_PER_INCH,
+            "G21" => machine.scale = 1.0,
+            "G90" => {
+                machine.absolute = true;
+                machine.absolute_extruder = true;
+            }
+            "G91" => {
+                machine.absolute = false;
+                machine.absolute_extruder = false;
+            }
+            "M82" => machine.absolute_extruder = true,
+            "M83" => machine.absolute_extruder = false,
+            "G92" => {
+                let scale = machine.scale;
+                let position = &mut machine.position;
+                for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+                    if let Some(value) = word(&words, letter) {
+                        position[axis] = value * scale;
+                    }
+                }
+                if let Some(e) = word(&words, 'E') {
+                    machine.extruder = e * scale;
+                }
+            }
+            "G28" => {
+                let axes: Vec<usize> = ['X', 'Y', 'Z']
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, letter)| {
+                        words[1..]
+                            .iter()
+                            .any(|w| w.eq_ignore_ascii_case(&letter.to_string()))
+                            || word(&words, *letter).is_some()
+                    })
+                    .map(|(axis, _)| axis)
+                    .collect();
+                if axes.is_empty() {
+                    machine.position = Vec3::ZERO;
+                } else {
+                    for axis in axes {
+                        machine.position[axis] = 0.0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    toolpath
+}
+
+/// Apply a linear or arc move and record its segments.
+fn move_to(machine: &mut Machine, toolpath: &mut Toolpath, command: &str, words: &[&str]) {
+    let scale = machine.scale;
+    let start = machine.position;
+    let mut target = start;
+    for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+        if let Some(value) = word(words, letter) {
+            target[axis] = if machine.absolute {
+                value * scale
+            } else {
+                start[axis] + value * scale
+            };
+        }
+    }
+    let extruding = match word(words, 'E') {
+        Some(e) => {
+            let e = e * scale;
+            let delta = if machine.absolute_extruder {
+                e - machine.extruder
+            } else {
+                e
+            };
+            machine.extruder = if machine.absolute_extruder {
+                e
+            } else {
+                machine.extruder + e
+            };
+            if delta > 0.0 {
+                toolpath.filament_length += delta;
+            }
+            delta > 0.0
+        }
+        None => false,
+    };
+    machine.position = target;
+    if target == start {
+        return;
+    }
+
+    let feature = if extruding {
+        machine.feature
+    } else {
+        FeatureType::Travel
+    };
+    if extruding {
+        // A move back down to an earlier height (e.g. sequential printing) reuses its layer.
+        let same_height = |z: &f32| (target.z - z).abs() <= LAYER_TOLERANCE;
+        machine.layer = match toolpath.layers.iter().position(same_height) {
+            Some(index) => index,
+            None => {
+                toolpath.layers.push(target.z);
+                toolpath.layers.len() - 1
+            }
+        };
+        let (min, max) = toolpath.bounds.unwrap_or((start, start));
+        toolpath.bounds = Some((min.min(start).min(target), max.max(start).max(target)));
+    }
+    let layer = machine.layer;
+
+    let points = match command {
+        "G2" | "G02" | "G3" | "G03" => {
+            let center_offset = Vec3::new(
+                word(words, 'I').unwrap_or(0.0) * scale,
+                word(words, 'J').unwrap_or(0.0) * scale,
+                0.0,
+            );
+            arc_points(start, target, start + center_offset, command.ends_with('3'))
+        }
+        _ => vec![target],
+    };
+    let mut from = start;
+    for to in points {
+        toolpath.segments.push(Segment {
+            from,
+            to,
+            feature,
+            layer,
+        });
+        from = to;
+    }
+}
+
+/// Points along an XY arc from `start` to `end` around `center` (Z is
+/// interpolated linearly, for helical moves). A full circle is drawn when the
+/// end point equals the start point.
+fn arc_points(start: Vec3, end: Vec3, center: Vec3, counter_clockwise: bool) -> Vec<Vec3> {
+    let radius = (start - center).truncate().length();
+    if radius <= f32::EPSILON {
+        return vec![end];
+    }
+    let angle_of = |p: Vec3| (p.y - center.y).atan2(p.x - center.x);
+    let (a0, a1) = (angle_of(start), angle_of(end));
+    let tau = std::f32::consts::TAU;
+    let mut sweep = if counter_clockwise { a1 - a0 } else { a0 - a1 };
+    sweep = sweep.rem_euclid(tau);
+    if sweep <= f32::EPSILON {
+        sweep = tau;
+    }
+    let steps = ((sweep * radius / ARC_SEGMENT_LENGTH).ceil() as usize).max(1);
+    let direction = if counter_clockwise { 1.0 } else { -1.0 };
+    (1..=steps)
+        .map(|step| {
+            if step == steps {
+                return end;
+            }
+            let t = step as f32 / steps as f32;
+            let angle = a0 + direction * sweep * t;
+            Vec3::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+                start.z + (end.z - start.z) * t,
+            )
+        })
+        .collect()
+}
+
+/// How toolpath segments are colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GcodeColoring {
+    #[default]
+    Feature,
+    Layer,
+}
+
+impl GcodeColoring {
+    pub const ALL: [GcodeColoring; 2] = [GcodeColoring::Feature, GcodeColoring::Layer];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GcodeColoring::Feature => "Feature type",
+            GcodeColoring::Layer => "Layer",
+        }
+    }
+}
+
+/// A loaded G-code file and how it is shown.
+#[derive(Debug, Clone)]
+pub struct GcodePreview {
+    pub toolpath: Toolpath,
+    /// File name shown in the panel.
+    pub name: String,
+    /// Layers shown (inclusive).
+    pub first_layer: usize,
+    pub last_layer: usize,
+    pub coloring: GcodeColoring,
+    pub show_travel: bool,
+}
+
+impl GcodePreview {
+    pub fn new(toolpath: Toolpath, name: String) -> Self {
+        let last_layer = toolpath.layers.len().saturating_sub(1);
+        Self {
+            toolpath,
+            name,
+            first_layer: 0,
+            last_layer,
+            coloring: GcodeColoring::default(),
+            show_travel: false,
+        }
+    }
+
+    /// Color of a segment under the current coloring mode.
+    pub fn color(&self, segment: &Segment) -> [f32; 3] {
+        match self.coloring {
+            GcodeColoring::Feature => segment.feature.color(),
+            GcodeColoring::Layer => {
+                let count = self.toolpath.layers.len().max(2) - 1;
+                let t = segment.layer as f32 / count as f32;
+                // Blue (first layer) to red (last layer).
+                [
+                    0.2 + 0.75 * t,
+                    0.35 + 0.3 * (1.0 - (2.0 * t - 1.0).abs()),
+                    0.95 - 0.75 * t,
+                ]
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Hollowing with drain holes for resin printing.
+//!
+//! Bodies are hollowed as meshes, since that is what every body has once it
+//! is placed for printing. The inner wall is the closed surface lying the
+//! wall thickness inside the placed mesh ([`wb_mesh::shell`]); parts thinner
+//! than twice the wall stay solid. Drain holes are cut through both walls as
+//! sixteen-sided prisms ([`wb_mesh::cut::subtract_convex`]), so the result is
+//! a closed mesh that is exported in place of the solid body. Holes are stored
+//! on the unplaced model so they follow the body when its placement changes.
+
+use core_document::bvh::{Bvh, Triangle};
+use core_document::BodyId;
+use glam::Vec3;
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+use wb_mesh::mesh::{self as indexed, IndexedMesh};
+use wb_mesh::{cut, shell};
+
+use crate::analysis::{self, Face, BED_CONTACT_TOLERANCE};
+use crate::layout::{self, BedFrame, PlacedBody};
+
+/// Typical resin wall thickness (mm).
+pub const DEFAULT_WALL_THICKNESS: f32 = 2.0;
+
+pub const DEFAULT_DRAIN_DIAMETER: f32 = 3.0;
+
+/// Automatic drain holes are placed within this fraction of the body height
+/// above its lowest point.
+const AUTO_DRAIN_REGION: f32 = 0.1;
+
+/// Drain holes reach this far (mm) past the outer surface and into the cavity.
+const HOLE_CLEARANCE: f32 = 0.5;
+
+/// Sides of the prisms cut as drain holes.
+const HOLE_SIDES: usize = 16;
+
+/// Faces this close (mm) to the body's surface are part of its outer wall.
+const SURFACE_TOLERANCE: f32 = 1e-3;
+
+/// A hole through the wall of a hollowed body.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DrainHole {
+    /// Point on the model surface (world space, before placement).
+    pub position: [f32; 3],
+    /// Outward surface normal at `position` (world space, before placement).
+    pub direction: [f32; 3],
+    pub diameter: f32,
+}
+
+/// How one body is hollowed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HollowSettings {
+    pub wall_thickness: f32,
+    #[serde(default)]
+    pub drain_holes: Vec<DrainHole>,
+}
+
+impl Default for HollowSettings {
+    fn default() -> Self {
+        Self {
+            wall_thickness: DEFAULT_WALL_THICKNESS,
+            drain_holes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BodyHollow {
+    pub body: BodyId,
+    pub settings: HollowSettings,
+}
+
+/// A hollowed body, in printer coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct Hollowed {
+    /// The body with its cavity and drain holes cut out.
+    pub mesh: IndexedMesh,
+    /// Faces of `mesh` lining the cavity (they point into it).
+    pub inner: TriMesh,
+    /// Faces of `mesh` lining the drain holes.
+    pub holes: TriMesh,
+    /// Volume of the solid body in mm³.
+    pub solid_volume: f32,
+    /// Volume of material left after hollowing in mm³.
+    pub material_volume: f32,
+    /// `mesh` is closed (it is not when the body's own mesh has holes).
+    pub watertight: bool,
+}
+
+impl Hollowed {
+    /// Fraction of material saved by hollowing (0–1).
+    pub fn savings(&self) -> f32 {
+        if self.solid_volume > 0.0 {
+            1.0 - self.material_volume / self.solid_volume
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Volume enclosed by the faces; the winding of each face is taken from its normal.
+pub(crate) fn enclosed_volume<'a>(faces: impl IntoIterator<Item = &'a Face>) -> f32 {
+    faces
+        .into_iter()
+        .map(|face| {
+            let [a, b, c] = face.vertices;
+            let cross = (b - a).cross(c - a);
+            let signed = a.dot(cross) / 6.0;
+            if cross.dot(face.normal) < 0.0 {
+                -signed
+            } else {
+                signed
+            }
+        })
+        .sum::<f32>()
+        .abs()
+}
+
+/// Hollow the placed body to the wall thickness and cut its drain holes.
+pub fn hollow(body: &PlacedBody, settings: &HollowSettings, frame: &BedFrame) -> Hollowed {
+    let outer = body.indexed_mesh();
+    let thickness = settings.wall_thickness;
+
+    let mut mesh = outer.clone();
+    let mut cavity = shell::inner_surface(&outer, thickness);
+    indexed::flip_normals(&mut cavity);
+    indexed::merge(&mut mesh, &cavity);
+
+    // Each hole runs from outside the body to inside the cavity along its axis.
+    let holes: Vec<(Vec3, Vec3, f32)> = settings
+        .drain_holes
+        .iter()
+        .map(|hole| {
+            let (position, direction) = placed_hole(body, frame, hole);
+            (position, direction, hole.diameter * 0.5)
+        })
+        .filter(|&(_, direction, radius)| direction != Vec3::ZERO && radius > 0.0)
+        .collect();
+    for &(position, direction, radius) in &holes {
+        let top = position + direction * HOLE_CLEARANCE;
+        let bottom = position - direction * (thickness + HOLE_CLEARANCE);
+        let u = direction.any_orthonormal_vector();
+        let v = direction.cross(u);
+        let mut planes = vec![(top, direction), (bottom, -direction)];
+        planes.extend((0..HOLE_SIDES).map(|k| {
+            let angle = std::f32::consts::TAU * k as f32 / HOLE_SIDES as f32;
+            let normal = u * angle.cos() + v * angle.sin();
+            (position + normal * radius, normal)
+        }));
+        mesh = cut::subtract_convex(&mesh, &planes);
+    }
+
+    // Sort the faces that are not on the body's surface into cavity and holes.
+    let surface = Bvh::new(
+        outer
+            .triangles
+            .iter()
+            .filter_map(|triangle| Triangle::new(triangle.map(|v| outer.positions[v as usize])))
+            .collect(),
+    );
+    let in_hole = |p: Vec3| {
+        holes.iter().any(|&(position, direction, radius)| {
+            let along = (p - position).dot(direction);
+            along <= HOLE_CLEARANCE
+                && along >= -(thickness + HOLE_CLEARANCE)
+                && (p - position - direction * along).length() <= radius * 1.01
+        })
+    };
+    let (mut inner, mut hole_walls) = (mesh.clone(), mesh.clone());
+    inner.triangles.clear();
+    hole_walls.triangles.clear();
+    for triangle in &mesh.triangles {
+        let centroid = triangle
+            .iter()
+            .map(|&v| mesh.positions[v as usize])
+            .sum::<Vec3>()
+            / 3.0;
+        let on_surface = surface
+            .closest(centroid)
+            .is_some_and(|hit| hit.distance < SURFACE_TOLERANCE);
+        if in_hole(centroid) {
+            hole_walls.triangles.push(*triangle);
+        } else if !on_surface {
+            inner.triangles.push(*triangle);
+        }
+    }
+
+    let solid_volume = mesh_volume(&outer);
+    Hollowed {
+        inner: inner.to_trimesh(),
+        holes: hole_walls.to_trimesh(),
+        solid_volume,
+        material_volume: mesh_volume(&mesh).clamp(0.0, solid_volume),
+        watertight: mesh.stats().is_closed(),
+        mesh,
+    }
+}
+
+/// Volume enclosed by a closed mesh whose faces point outwards.
+fn mesh_volume(mesh: &IndexedMesh) -> f32 {
+    mesh.triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|v| mesh.positions[v as usize]);
+            a.dot(b.cross(c)) / 6.0
+        })
+        .sum::<f32>()
+        .abs()
+}
+
+/// Cavity and drain hole walls converted to world space for the viewport.
+pub fn world_meshes(hollowed: &Hollowed, frame: &BedFrame) -> (TriMesh, TriMesh) {
+    (
+        layout::mesh_to_world(&hollowed.inner, frame),
+        layout::mesh_to_world(&hollowed.holes, frame),
+    )
+}
+
+/// Drain hole position and outward direction in placed printer coordinates.
+fn placed_hole(body: &PlacedBody, frame: &BedFrame, hole: &DrainHole) -> (Vec3, Vec3) {
+    let position = body
+        .matrix
+        .transform_point3(frame.to_printer(Vec3::from(hole.position)));
+    let direction = body
+        .matrix
+        .transform_vector3(frame.to_printer(Vec3::from(hole.direction)))
+        .normalize_or_zero();
+    (position, direction)
+}
+
+/// Drain hole at a point of the placed body (printer coordinates), stored on
+/// the unplaced model.
+fn hole_from_placed(
+    body: &PlacedBody,
+    frame: &BedFrame,
+    position: Vec3,
+    normal: Vec3,
+    diameter: f32,
+) -> DrainHole {
+    let inverse = body.matrix.inverse();
+    DrainHole {
+        position: frame
+            .to_world(inverse.transform_point3(position))
+            .to_array(),
+        direction: frame
+            .to_world(inverse.transform_vector3(normal).normalize_or_zero())
+            .to_array(),
+        diameter,
+    }
+}
+
+/// Drain hole on the face of `body` nearest to a picked world-space point of
+/// the unplaced model.
+pub fn hole_at(
+    body: &PlacedBody,
+    frame: &BedFrame,
+    world_point: [f32; 3],
+    diameter: f32,
+) -> Option<DrainHole> {
+    let point = body
+        .matrix
+        .transform_point3(frame.to_printer(Vec3::from(world_point)));
+    let face = analysis::faces(body).into_iter().min_by(|a, b| {
+        a.centroid()
+            .distance_squared(point)
+            .total_cmp(&b.centroid().distance_squared(point))
+    })?;
+    Some(hole_from_placed(body, frame, point, face.normal, diameter))
+}
+
+/// Drain holes near the lowest part of the body (where resin collects when the
+/// print is lifted out of the vat): one at the lowest face and, if the region is
+/// wide enough, a second one as far from it as possible so air can enter.
+pub fn auto_drain_holes(body: &PlacedBody, frame: &BedFrame, diameter: f32) -> Vec<DrainHole> {
+    let region_top = body.min.z + body.size().z * AUTO_DRAIN_REGION;
+    let candidates: Vec<Face> = analysis::faces(body)
+        .into_iter()
+        .filter(|face| face.vertices.iter().any(|v| v.z > BED_CONTACT_TOLERANCE))
+        .filter(|face| face.centroid().z <= region_top)
+        .collect();
+    let Some(lowest) = candidates
+        .iter()
+        .min_by(|a, b| a.centroid().z.total_cmp(&b.centroid().z))
+    else {
+        return Vec::new();
+    };
+    let mut holes = vec![hole_from_placed(
+        body,
+        frame,
+        lowest.centroid(),
+        lowest.normal,
+        diameter,
+    )];
+    let farthest = candidates.iter().max_by(|a, b| {
+        a.centroid()
+            .distance_squared(lowest.centroid())
+            .total_cmp(&b.centroid().distance_squared(lowest.centroid()))
+    });
+    if let Some(face) = farthest {
+        if face.centroid().distance(lowest.centroid()) > diameter * 2.0 {
+            holes.push(hole_from_placed(
+                body,
+                frame,
+                face.centroid(),
+                face.normal,
+                diameter,
+            ));
+        }
+    }
+    holes
+}
@@ -0,0 +1,411 @@
+//! Placement of bodies on the print bed.
+//!
+//! Placements only affect print preparation: the modeled geometry is left
+//! untouched and the layout is stored in the document's `wb.print` storage.
+
+use axes::AxisSystem;
+use core_document::{BodyId, BodyMesh, Document, PrintVolume, WorkbenchId};
+use glam::{EulerRot, Mat4, Quat, Vec3};
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+use wb_mesh::mesh::IndexedMesh;
+
+use crate::analysis::{DraftSettings, DEFAULT_MIN_WALL_THICKNESS, DEFAULT_OVERHANG_ANGLE_DEG};
+use crate::arrange;
+use crate::clearance::DEFAULT_MIN_CLEARANCE;
+use crate::compensation::{self, BodyCompensation, Compensation};
+use crate::estimate::EstimateSettings;
+use crate::hollow::{BodyHollow, HollowSettings};
+use crate::slice;
+use crate::supports::SupportSettings;
+use crate::WORKBENCH_ID;
+
+/// How one body is moved onto the bed (in printer coordinates, millimetres).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Placement {
+    pub translation: [f32; 3],
+    /// Rotation about the printer X, Y and Z axes in degrees (applied in that order).
+    pub rotation_deg: [f32; 3],
+    /// Uniform scale factor.
+    pub scale: f32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation_deg: [0.0; 3],
+            scale: 1.0,
+        }
+    }
+}
+
+impl Placement {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Transform from unplaced to placed printer coordinates. Rotation and scale
+    /// happen about `pivot` so editing them does not move the body across the bed.
+    pub fn matrix(&self, pivot: Vec3) -> Mat4 {
+        Mat4::from_translation(Vec3::from(self.translation) + pivot)
+            * Mat4::from_scale_rotation_translation(
+                Vec3::splat(self.scale),
+                self.rotation(),
+                Vec3::ZERO,
+            )
+            * Mat4::from_translation(-pivot)
+    }
+
+    pub fn rotation(&self) -> Quat {
+        let [rx, ry, rz] = self.rotation_deg.map(f32::to_radians);
+        Quat::from_euler(EulerRot::ZYX, rz, ry, rx)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BodyPlacement {
+    body: BodyId,
+    placement: Placement,
+}
+
+/// Placements of all bodies laid out for printing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrintLayout {
+    #[serde(default)]
+    placements: Vec<BodyPlacement>,
+    /// Faces steeper than this (degrees from vertical) are flagged as needing support.
+    #[serde(default = "default_overhang_angle")]
+    pub overhang_angle_deg: f32,
+    /// Walls thinner than this (mm) are flagged as unprintable.
+    #[serde(default = "default_min_wall_thickness")]
+    pub min_wall_thickness: f32,
+    #[serde(default)]
+    pub draft: DraftSettings,
+    #[serde(default)]
+    pub supports: SupportSettings,
+    #[serde(default)]
+    hollows: Vec<BodyHollow>,
+    #[serde(default)]
+    compensations: Vec<BodyCompensation>,
+    /// Gap (mm) kept between bodies by automatic arrangement.
+    #[serde(default = "default_arrange_spacing")]
+    pub arrange_spacing: f32,
+    /// Gaps between mating bodies smaller than this (mm) are flagged.
+    #[serde(default = "default_min_clearance")]
+    pub min_clearance: f32,
+    /// Layer height (mm) used by the layer preview.
+    #[serde(default = "default_layer_height")]
+    pub layer_height: f32,
+    #[serde(default)]
+    pub estimate: EstimateSettings,
+}
+
+impl Default for PrintLayout {
+    fn default() -> Self {
+        Self {
+            placements: Vec::new(),
+            overhang_angle_deg: default_overhang_angle(),
+            min_wall_thickness: default_min_wall_thickness(),
+            draft: DraftSettings::default(),
+            supports: SupportSettings::default(),
+            hollows: Vec::new(),
+            compensations: Vec::new(),
+            arrange_spacing: default_arrange_spacing(),
+            min_clearance: default_min_clearance(),
+            layer_height: default_layer_height(),
+            estimate: EstimateSettings::default(),
+        }
+    }
+}
+
+fn default_overhang_angle() -> f32 {
+    DEFAULT_OVERHANG_ANGLE_DEG
+}
+
+fn default_min_wall_thickness() -> f32 {
+    DEFAULT_MIN_WALL_THICKNESS
+}
+
+fn default_arrange_spacing() -> f32 {
+    arrange::DEFAULT_SPACING
+}
+
+fn default_min_clearance() -> f32 {
+    DEFAULT_MIN_CLEARANCE
+}
+
+fn default_layer_height() -> f32 {
+    slice::DEFAULT_LAYER_HEIGHT
+}
+
+impl PrintLayout {
+    /// Load the layout stored in the document (empty if none was saved).
+    pub fn load(document: &Document) -> Self {
+        document
+            .get_workbench_storage(&WorkbenchId::from(WORKBENCH_ID))
+            .and_then(|storage| serde_json::from_value(storage.data.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Store the layout in the document (marks the document dirty).
+    pub fn save(&self, document: &mut Document) {
+        if let Ok(data) = serde_json::to_value(self) {
+            document.set_workbench_storage(WorkbenchId::from(WORKBENCH_ID), data);
+        }
+    }
+
+    pub fn placement(&self, body: BodyId) -> Placement {
+        self.placements
+            .iter()
+            .find(|entry| entry.body == body)
+            .map(|entry| entry.placement)
+            .unwrap_or_default()
+    }
+
+    pub fn set_placement(&mut self, body: BodyId, placement: Placement) {
+        self.placements.retain(|entry| entry.body != body);
+        if !placement.is_identity() {
+            self.placements.push(BodyPlacement { body, placement });
+        }
+    }
+
+    /// Hollowing of `body` (None if it prints solid).
+    pub fn hollow(&self, body: BodyId) -> Option<&HollowSettings> {
+        self.hollows
+            .iter()
+            .find(|entry| entry.body == body)
+            .map(|entry| &entry.settings)
+    }
+
+    pub fn set_hollow(&mut self, body: BodyId, settings: Option<HollowSettings>) {
+        self.hollows.retain(|entry| entry.body != body);
+        if let Some(settings) = settings {
+            self.hollows.push(BodyHollow { body, settings });
+        }
+    }
+
+    /// Shrinkage and elephant's foot compensation of `body` (None if it prints nominal).
+    pub fn compensation(&self, body: BodyId) -> Option<&Compensation> {
+        self.compensations
+            .iter()
+            .find(|entry| entry.body == body)
+            .map(|entry| &entry.compensation)
+    }
+
+    pub fn set_compensation(&mut self, body: BodyId, compensation: Option<Compensation>) {
+        self.compensations.retain(|entry| entry.body != body);
+        if let Some(compensation) = compensation.filter(|c| !c.is_identity()) {
+            self.compensations
+                .push(BodyCompensation { body, compensation });
+        }
+    }
+}
+
+/// Printer coordinate frame expressed in world space: X to the right, Y towards
+/// the back of the bed, Z up (following the active axis convention).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BedFrame {
+    right: Vec3,
+    back: Vec3,
+    up: Vec3,
+}
+
+impl BedFrame {
+    pub fn new(axes: &AxisSystem) -> Self {
+        Self {
+            right: axes.right_vec(),
+            back: axes.back_vec(),
+            up: axes.up_vec(),
+        }
+    }
+
+    pub fn to_printer(&self, world: Vec3) -> Vec3 {
+        Vec3::new(
+            world.dot(self.right),
+            world.dot(self.back),
+            world.dot(self.up),
+        )
+    }
+
+    pub fn to_world(&self, printer: Vec3) -> Vec3 {
+        self.right * printer.x + self.back * printer.y + self.up * printer.z
+    }
+}
+
+/// A body's geometry after applying its placement, in printer coordinates.
+#[derive(Debug, Clone)]
+pub struct PlacedBody {
+    pub body: BodyId,
+    pub placement: Placement,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u32>,
+    pub min: Vec3,
+    pub max: Vec3,
+    /// Transform from unplaced to placed printer coordinates.
+    pub matrix: Mat4,
+    /// Point the placement rotates and scales about, in placed printer coordinates.
+    pub pivot: Vec3,
+    /// Number of vertices lying outside the build volume.
+    pub vertices_outside: usize,
+}
+
+impl PlacedBody {
+    pub fn fits(&self) -> bool {
+        self.vertices_outside == 0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// The placed surface with its vertices welded, as mesh operations take it.
+    pub fn indexed_mesh(&self) -> IndexedMesh {
+        IndexedMesh::from_trimesh(&TriMesh {
+            positions: self.positions.iter().map(|p| p.to_array()).collect(),
+            normals: Vec::new(),
+            indices: self.indices.clone(),
+        })
+    }
+}
+
+/// Bounding box of a set of points (None if empty).
+pub(crate) fn bounds(points: impl IntoIterator<Item = Vec3>) -> Option<(Vec3, Vec3)> {
+    points.into_iter().fold(None, |acc, p| match acc {
+        None => Some((p, p)),
+        Some((min, max)) => Some((min.min(p), max.max(p))),
+    })
+}
+
+/// Number of points lying outside the build volume (0 without a printer).
+pub(crate) fn vertices_outside(positions: &[Vec3], printer: Option<&PrintVolume>) -> usize {
+    printer
+        .map(|volume| {
+            positions
+                .iter()
+                .filter(|p| !volume.contains(p.to_array()))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Apply the layout (placements and compensation) to every body mesh supplied
+/// by the host.
+pub fn place_bodies(
+    layout: &PrintLayout,
+    frame: &BedFrame,
+    body_meshes: &[BodyMesh],
+    printer: Option<&PrintVolume>,
+) -> Vec<PlacedBody> {
+    body_meshes
+        .iter()
+        .filter_map(|body_mesh| place_in_layout(layout, frame, body_mesh, printer))
+        .collect()
+}
+
+/// Apply the layout to one body mesh (None if the mesh is empty).
+pub fn place_in_layout(
+    layout: &PrintLayout,
+    frame: &BedFrame,
+    body_mesh: &BodyMesh,
+    printer: Option<&PrintVolume>,
+) -> Option<PlacedBody> {
+    let mut placed = place_body(body_mesh, layout.placement(body_mesh.body), frame, printer)?;
+    if let Some(compensation) = layout.compensation(body_mesh.body) {
+        compensation::compensate(&mut placed, compensation, printer);
+    }
+    Some(placed)
+}
+
+/// Apply one placement to a body mesh (None if the mesh is empty).
+pub fn place_body(
+    body_mesh: &BodyMesh,
+    placement: Placement,
+    frame: &BedFrame,
+    printer: Option<&PrintVolume>,
+) -> Option<PlacedBody> {
+    let local: Vec<Vec3> = body_mesh
+        .mesh
+        .positions
+        .iter()
+        .map(|&p| frame.to_printer(Vec3::from(p)))
+        .collect();
+    let (min, max) = bounds(local.iter().copied())?;
+    let pivot = (min + max) * 0.5;
+    let matrix = placement.matrix(pivot);
+    let positions: Vec<Vec3> = local.iter().map(|&p| matrix.transform_point3(p)).collect();
+    let normals = body_mesh
+        .mesh
+        .normals
+        .iter()
+        .map(|&n| placement.rotation() * frame.to_printer(Vec3::from(n)))
+        .collect();
+    let (min, max) = bounds(positions.iter().copied())?;
+    let vertices_outside = vertices_outside(&positions, printer);
+    Some(PlacedBody {
+        body: body_mesh.body,
+        placement,
+        positions,
+        normals,
+        indices: body_mesh.mesh.indices.clone(),
+        min,
+        max,
+        matrix,
+        pivot: matrix.transform_point3(pivot),
+        vertices_outside,
+    })
+}
+
+/// Placement that moves `body` so it sits centered on the bed.
+pub fn centered_on_bed(body: &PlacedBody, printer: &PrintVolume) -> Placement {
+    let ([min_x, min_y], [max_x, max_y]) = printer.bed_extent();
+    let bed_center = Vec3::new((min_x + max_x) * 0.5, (min_y + max_y) * 0.5, 0.0);
+    let body_center = Vec3::new(
+        (body.min.x + body.max.x) * 0.5,
+        (body.min.y + body.max.y) * 0.5,
+        body.min.z,
+    );
+    let mut placement = body.placement;
+    placement.translation = (Vec3::from(placement.translation) + bed_center - body_center).into();
+    placement
+}
+
+/// Placement that lowers (or raises) `body` so its lowest point rests on the bed.
+pub fn dropped_to_bed(body: &PlacedBody) -> Placement {
+    let mut placement = body.placement;
+    placement.translation[2] -= body.min.z;
+    placement
+}
+
+/// Mesh in printer coordinates converted to world space.
+pub fn mesh_to_world(mesh: &TriMesh, frame: &BedFrame) -> TriMesh {
+    let to_world = |points: &[[f32; 3]]| -> Vec<[f32; 3]> {
+        points
+            .iter()
+            .map(|&p| frame.to_world(Vec3::from(p)).to_array())
+            .collect()
+    };
+    TriMesh {
+        positions: to_world(&mesh.positions),
+        normals: to_world(&mesh.normals),
+        indices: mesh.indices.clone(),
+    }
+}
+
+/// Mesh of a placed body converted back to world space (for viewport display).
+pub fn placed_world_mesh(body: &PlacedBody, frame: &BedFrame) -> TriMesh {
+    TriMesh {
+        positions: body
+            .positions
+            .iter()
+            .map(|&p| frame.to_world(p).to_array())
+            .collect(),
+        normals: body
+            .normals
+            .iter()
+            .map(|&n| frame.to_world(n).to_array())
+            .collect(),
+        indices: body.indices.clone(),
+    }
+}
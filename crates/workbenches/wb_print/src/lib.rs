@@ -0,0 +1,613 @@
+pub mod analysis;
+pub mod arrange;
+mod cache;
+pub mod clearance;
+pub mod compensation;
+pub mod estimate;
+pub mod gcode;
+pub mod hollow;
+pub mod layout;
+pub mod orient;
+mod overlay;
+#[cfg(feature = "egui")]
+mod panel;
+pub mod report;
+pub mod slice;
+pub mod supports;
+
+use analysis::AnalysisMode;
+use core_document::{
+    BodyId, FeatureId, Gizmo, GizmoDrag, GizmoDragPhase, GizmoKind, GizmoMotion, ToolDescriptor,
+    Workbench, WorkbenchContext, WorkbenchDescriptor, WorkbenchRuntimeContext,
+};
+use glam::{EulerRot, Quat, Vec3};
+use layout::{BedFrame, Placement, PrintLayout};
+use orient::OrientObjective;
+
+/// Workbench identifier (also the key of the document's layout storage).
+pub const WORKBENCH_ID: &str = "wb.print";
+
+/// `FileOpenRequest` id used to pick a G-code file.
+const GCODE_REQUEST: &str = "print.gcode";
+
+/// `FileOpenRequest` id used to pick where the plate is exported.
+const EXPORT_REQUEST: &str = "print.export";
+
+/// Id of the gizmo that moves or rotates the selected body on the bed.
+const PLACEMENT_GIZMO: &str = "print.placement";
+
+/// Viewport colors of bodies moved or resized for printing that fit the build
+/// volume, and of those that do not.
+const PLACED_COLOR: [f32; 3] = [0.85, 0.65, 0.2];
+const OUTSIDE_COLOR: [f32; 3] = [0.95, 0.3, 0.25];
+
+/// Viewport color of previewed support structures.
+const SUPPORT_COLOR: [f32; 3] = [0.6, 0.62, 0.7];
+
+/// Viewport colors of the inner wall and drain holes of hollowed bodies.
+const HOLLOW_COLOR: [f32; 3] = [0.55, 0.45, 0.75];
+const DRAIN_HOLE_COLOR: [f32; 3] = [0.2, 0.75, 0.85];
+
+/// Print Preparation workbench: lays bodies out inside the printer's build volume.
+pub struct PrintWorkbench {
+    /// Body whose placement is being edited in the right panel.
+    selected_body: Option<BodyId>,
+    /// Analysis used to color placed bodies (instead of plain placed copies).
+    analysis: AnalysisMode,
+    /// Nozzle width (mm) for the minimum wall thickness helper.
+    nozzle_width: f32,
+    /// Perimeter count for the minimum wall thickness helper.
+    wall_perimeters: u32,
+    /// Result of the last "Check Printability" run.
+    report: Option<report::PrintabilityReport>,
+    /// What automatic orientation minimizes.
+    orient_objective: OrientObjective,
+    /// Show generated support structures under overhangs.
+    show_supports: bool,
+    /// Diameter (mm) of drain holes added by the tools.
+    drain_diameter: f32,
+    /// Loaded G-code toolpaths, shown until closed.
+    gcode: Option<gcode::GcodePreview>,
+    /// Layer shown by the layer preview (None when the preview is off).
+    preview_layer: Option<usize>,
+    /// Also draw contours of the layers below the previewed one.
+    show_lower_layers: bool,
+    /// The two bodies compared by the clearance analysis.
+    clearance_bodies: [Option<BodyId>; 2],
+    /// Material shrinkage (%) for the compensation scale helper.
+    shrinkage_percent: f32,
+    /// Viewport handles shown on the selected body (move or rotate).
+    placement_handles: Option<GizmoKind>,
+    /// Placement of the selected body when the current handle drag started.
+    drag_start: Option<Placement>,
+    /// Placed bodies and analysis results kept between frames.
+    cache: cache::PrintCache,
+}
+
+impl Default for PrintWorkbench {
+    fn default() -> Self {
+        Self {
+            selected_body: None,
+            analysis: AnalysisMode::None,
+            nozzle_width: 0.4,
+            wall_perimeters: 2,
+            report: None,
+            orient_objective: OrientObjective::default(),
+            show_supports: false,
+            drain_diameter: hollow::DEFAULT_DRAIN_DIAMETER,
+            gcode: None,
+            preview_layer: None,
+            show_lower_layers: false,
+            clearance_bodies: [None; 2],
+            shrinkage_percent: 0.0,
+            placement_handles: None,
+            drag_start: None,
+            cache: cache::PrintCache::default(),
+        }
+    }
+}
+
+impl Workbench for PrintWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Print Preparation",
+            "Place bodies on the print bed and check they fit the build volume.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new_action(
+            "print.center_all",
+            "Center All on Bed",
+            Some("layout"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.drop_all",
+            "Drop All to Bed",
+            Some("layout"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.arrange",
+            "Arrange on Bed",
+            Some("layout"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.orient",
+            "Auto-Orient",
+            Some("layout"),
+        ));
+        context.register_tool(ToolDescriptor::new(
+            "print.drain_hole",
+            "Place Drain Hole",
+            Some("hollow"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.check",
+            "Check Printability",
+            Some("analysis"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.export",
+            "Export Plate…",
+            Some("export"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "print.gcode",
+            "Open G-code…",
+            Some("gcode"),
+        ));
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Print Preparation workbench activated");
+        if ctx.printer.is_none() {
+            ctx.log_warn("No printer profile is active; add one under Settings → Printers");
+        }
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Print Preparation workbench deactivated");
+    }
+
+    fn on_frame(&mut self, _dt: f32, ctx: &mut WorkbenchRuntimeContext) {
+        let shown = cache::Shown {
+            analysis: self.analysis,
+            supports: self.show_supports,
+            selected: self.selected_body,
+            clearance_bodies: self.clearance_bodies,
+            layer: self
+                .preview_layer
+                .map(|layer| (layer, self.show_lower_layers)),
+        };
+        self.cache.update(
+            ctx.document,
+            ctx.body_meshes,
+            ctx.printer.as_ref(),
+            BedFrame::new(&ctx.axes),
+            &shown,
+        );
+    }
+
+    fn on_input(
+        &mut self,
+        event: &core_document::WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> core_document::InputResult {
+        match active_tool {
+            Some("print.center_all") => {
+                let Some(printer) = ctx.printer else {
+                    ctx.log_warn("No printer profile is active");
+                    return core_document::InputResult::consumed();
+                };
+                self.update_all(ctx, |body| layout::centered_on_bed(body, &printer));
+                core_document::InputResult::consumed()
+            }
+            Some("print.drop_all") => {
+                self.update_all(ctx, layout::dropped_to_bed);
+                core_document::InputResult::consumed()
+            }
+            Some("print.arrange") => {
+                self.arrange(ctx);
+                core_document::InputResult::consumed()
+            }
+            Some("print.orient") => {
+                let selected = self.selected_body.or(ctx.selected_body());
+                let Some(body) = selected else {
+                    ctx.log_warn("Select a body to orient");
+                    return core_document::InputResult::consumed();
+                };
+                if let Some(placement) = self.auto_orient(ctx, body) {
+                    let mut layout = PrintLayout::load(ctx.document);
+                    layout.set_placement(body, placement);
+                    layout.save(ctx.document);
+                }
+                core_document::InputResult::consumed()
+            }
+            Some("print.check") => {
+                self.check_printability(ctx);
+                core_document::InputResult::consumed()
+            }
+            Some("print.export") => {
+                request_export_file(ctx);
+                core_document::InputResult::consumed()
+            }
+            Some("print.gcode") => {
+                request_gcode_file(ctx);
+                core_document::InputResult::consumed()
+            }
+            Some("print.drain_hole") => match event {
+                core_document::WorkbenchInputEvent::MousePress {
+                    button: core_document::MouseButton::Left,
+                    ..
+                } => {
+                    self.place_drain_hole(ctx);
+                    core_document::InputResult::consumed()
+                }
+                _ => core_document::InputResult::ignored(),
+            },
+            _ => core_document::InputResult::ignored(),
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        self.with_cache(ctx, |workbench, ctx, cache| {
+            panel::right_panel(ui, ctx, workbench, cache);
+        });
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_analysis_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        self.with_cache(ctx, |workbench, ctx, cache| {
+            panel::analysis_panel(ui, ctx, workbench, cache);
+        });
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_analysis_panel(&self) -> bool {
+        true
+    }
+
+    fn get_overlay_meshes(
+        &self,
+        _ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<(kernel_api::TriMesh, [f32; 3])> {
+        self.cache.meshes(self.analysis, self.show_supports)
+    }
+
+    fn get_screen_space_overlays(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<core_document::ScreenSpaceOverlay> {
+        let frame = BedFrame::new(&ctx.axes);
+        let mut overlays =
+            overlay::print_overlays(ctx, frame, ctx.printer.as_ref(), self.cache.placed());
+        if self.preview_layer.is_some() {
+            overlays.extend(overlay::slice_overlays(ctx, frame, self.cache.slices()));
+        }
+        if let Some(preview) = &self.gcode {
+            overlays.extend(overlay::gcode_overlays(ctx, frame, preview));
+        }
+        overlays
+    }
+
+    fn gizmos(&self, ctx: &WorkbenchRuntimeContext) -> Vec<Gizmo> {
+        let (Some(kind), Some(body)) = (self.placement_handles, self.selected_body) else {
+            return Vec::new();
+        };
+        let Some(placed) = self
+            .cache
+            .placed()
+            .iter()
+            .find(|placed| placed.body == body)
+        else {
+            return Vec::new();
+        };
+        let frame = BedFrame::new(&ctx.axes);
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| frame.to_world(axis).to_array());
+        vec![Gizmo::new(
+            PLACEMENT_GIZMO,
+            kind,
+            frame.to_world(placed.pivot).to_array(),
+        )
+        .with_axes(axes)]
+    }
+
+    fn on_gizmo_drag(&mut self, drag: &GizmoDrag, ctx: &mut WorkbenchRuntimeContext) {
+        let Some(body) = self.selected_body.filter(|_| drag.gizmo == PLACEMENT_GIZMO) else {
+            return;
+        };
+        let mut layout = PrintLayout::load(ctx.document);
+        if drag.phase == GizmoDragPhase::Start {
+            self.drag_start = Some(layout.placement(body));
+            return;
+        }
+        let Some(start) = self.drag_start else {
+            return;
+        };
+        let frame = BedFrame::new(&ctx.axes);
+        let mut placement = start;
+        match (drag.phase, drag.motion) {
+            (GizmoDragPhase::Cancel, _) => {}
+            (_, GizmoMotion::Translate(offset)) => {
+                let offset = frame.to_printer(Vec3::from(offset));
+                placement.translation = (Vec3::from(start.translation) + offset).into();
+            }
+            (_, GizmoMotion::Rotate { axis, angle }) => {
+                let axis = frame.to_printer(Vec3::from(axis)).normalize_or_zero();
+                let rotation = Quat::from_axis_angle(axis, angle) * start.rotation();
+                let (rz, ry, rx) = rotation.to_euler(EulerRot::ZYX);
+                placement.rotation_deg = [rx, ry, rz].map(f32::to_degrees);
+            }
+            (_, GizmoMotion::Scale(_)) => {}
+        }
+        if matches!(drag.phase, GizmoDragPhase::End | GizmoDragPhase::Cancel) {
+            self.drag_start = None;
+        }
+        layout.set_placement(body, placement);
+        layout.save(ctx.document);
+    }
+
+    fn on_file_opened(
+        &mut self,
+        request_id: &str,
+        path: &std::path::Path,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) {
+        if request_id == EXPORT_REQUEST {
+            self.export_plate(ctx, path);
+            return;
+        }
+        if request_id != GCODE_REQUEST {
+            return;
+        }
+        match gcode::Toolpath::load(path) {
+            Ok(toolpath) if toolpath.layers.is_empty() => {
+                ctx.log_warn(format!("No extrusion moves found in {}", path.display()));
+            }
+            Ok(toolpath) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                ctx.log_info(format!(
+                    "Loaded {} ({} layers, {} moves)",
+                    name,
+                    toolpath.layers.len(),
+                    toolpath.segments.len()
+                ));
+                self.gcode = Some(gcode::GcodePreview::new(toolpath, name));
+            }
+            Err(err) => ctx.log_error(format!("Failed to read {}: {err}", path.display())),
+        }
+    }
+}
+
+/// Ask the host for a G-code file to preview.
+fn request_gcode_file(ctx: &mut WorkbenchRuntimeContext) {
+    ctx.file_open_request = Some(core_document::FileOpenRequest {
+        id: GCODE_REQUEST.to_string(),
+        filter_name: "G-code".to_string(),
+        extensions: ["gcode", "gco", "g"].map(String::from).to_vec(),
+        save_name: None,
+        folder: false,
+    });
+}
+
+/// Ask the host where to save the plate, named after the document.
+fn request_export_file(ctx: &mut WorkbenchRuntimeContext) {
+    let name = ctx.document.name().trim();
+    let stem = if name.is_empty() { "plate" } else { name };
+    ctx.file_open_request = Some(core_document::FileOpenRequest {
+        id: EXPORT_REQUEST.to_string(),
+        filter_name: "STL".to_string(),
+        extensions: vec!["stl".to_string()],
+        save_name: Some(format!("{stem}.stl")),
+        folder: false,
+    });
+}
+
+impl PrintWorkbench {
+    /// Run a panel `draw`, which reads the cached results while it edits the
+    /// rest of the workbench, for the selected body (or the one selected in
+    /// the document).
+    #[cfg(feature = "egui")]
+    fn with_cache(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        draw: impl FnOnce(&mut Self, &mut WorkbenchRuntimeContext, &cache::PrintCache),
+    ) {
+        self.selected_body = self
+            .selected_body
+            .or(ctx.selected_body())
+            .filter(|id| ctx.document.bodies().iter().any(|body| body.id == *id));
+        let mut cache = std::mem::take(&mut self.cache);
+        cache.sync_layout(ctx.document);
+        draw(self, ctx, &cache);
+        self.cache = cache;
+    }
+
+    /// Search for the orientation of `body` that best meets the selected objective
+    /// and log the improvement. The caller stores the returned placement.
+    fn auto_orient(&self, ctx: &mut WorkbenchRuntimeContext, body: BodyId) -> Option<Placement> {
+        let Some(body_mesh) = ctx.body_meshes.iter().find(|m| m.body == body) else {
+            ctx.log_warn("The selected body has no geometry to orient");
+            return None;
+        };
+        let frame = BedFrame::new(&ctx.axes);
+        let layout = PrintLayout::load(ctx.document);
+        let result = orient::optimize_orientation(
+            body_mesh,
+            layout.placement(body),
+            &frame,
+            self.orient_objective,
+            layout.overhang_angle_deg,
+        )?;
+        let format = |value: f32| match self.orient_objective {
+            OrientObjective::OverhangArea => ctx.units.format_area(value),
+            OrientObjective::SupportVolume => ctx.units.format_volume(value),
+            OrientObjective::PrintHeight => ctx.units.format_length(value),
+        };
+        let message = format!(
+            "Auto-orient ({}): {} → {}",
+            self.orient_objective.label().to_lowercase(),
+            format(result.initial_score),
+            format(result.score)
+        );
+        ctx.log_info(message);
+        Some(result.placement)
+    }
+
+    /// Pack all bodies onto the bed and store their new placements.
+    fn arrange(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let Some(printer) = ctx.printer else {
+            ctx.log_warn("No printer profile is active");
+            return;
+        };
+        let frame = BedFrame::new(&ctx.axes);
+        let mut layout = PrintLayout::load(ctx.document);
+        let arrangement = arrange::arrange(
+            &layout,
+            &frame,
+            ctx.body_meshes,
+            &printer,
+            layout.arrange_spacing,
+        );
+        if arrangement.placements.is_empty() && arrangement.unplaced.is_empty() {
+            ctx.log_warn("No bodies with geometry to place");
+            return;
+        }
+        for (body, placement) in &arrangement.placements {
+            layout.set_placement(*body, *placement);
+        }
+        layout.save(ctx.document);
+        if arrangement.unplaced.is_empty() {
+            ctx.log_info(format!(
+                "Arranged {} bodies on the bed",
+                arrangement.placements.len()
+            ));
+        } else {
+            ctx.log_warn(format!(
+                "Arranged {} bodies; {} did not fit on the bed",
+                arrangement.placements.len(),
+                arrangement.unplaced.len()
+            ));
+        }
+    }
+
+    /// Add a drain hole where the cursor is on a hollowed body.
+    fn place_drain_hole(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let (Some(point), Some(body)) = (ctx.hovered_world_pos, ctx.hovered_body()) else {
+            ctx.log_warn("Click on a body to place a drain hole");
+            return;
+        };
+        let point = ctx.to_body(Some(body), point);
+        let frame = BedFrame::new(&ctx.axes);
+        let mut layout = PrintLayout::load(ctx.document);
+        let Some(mut settings) = layout.hollow(body).cloned() else {
+            ctx.log_warn("Hollow the body before placing drain holes");
+            return;
+        };
+        let placed = ctx
+            .body_meshes
+            .iter()
+            .find(|m| m.body == body)
+            .and_then(|m| layout::place_body(m, layout.placement(body), &frame, None));
+        let Some(hole) =
+            placed.and_then(|placed| hollow::hole_at(&placed, &frame, point, self.drain_diameter))
+        else {
+            ctx.log_warn("The body has no geometry to drill");
+            return;
+        };
+        settings.drain_holes.push(hole);
+        layout.set_hollow(body, Some(settings));
+        layout.save(ctx.document);
+        ctx.log_info("Drain hole added");
+    }
+
+    /// Write every placed body to one STL file in printer coordinates, as it
+    /// sits on the bed, with hollowed bodies written hollow.
+    fn export_plate(&mut self, ctx: &mut WorkbenchRuntimeContext, path: &std::path::Path) {
+        let frame = BedFrame::new(&ctx.axes);
+        let layout = PrintLayout::load(ctx.document);
+        let placed = layout::place_bodies(&layout, &frame, ctx.body_meshes, ctx.printer.as_ref());
+        if placed.is_empty() {
+            ctx.log_warn("No bodies with geometry to export");
+            return;
+        }
+        let mut plate = wb_mesh::mesh::IndexedMesh::default();
+        for body in &placed {
+            let Some(settings) = layout.hollow(body.body) else {
+                wb_mesh::mesh::merge(&mut plate, &body.indexed_mesh());
+                continue;
+            };
+            // Hollow now if the background job has not finished yet.
+            match self.cache.hollowed(body.body).ready() {
+                Some(hollowed) => wb_mesh::mesh::merge(&mut plate, &hollowed.mesh),
+                None => {
+                    let hollowed = hollow::hollow(body, settings, &frame);
+                    wb_mesh::mesh::merge(&mut plate, &hollowed.mesh);
+                }
+            }
+        }
+        let name = ctx.document.name().to_string();
+        match wb_mesh::stl::write_stl(&plate.to_trimesh(), &name, path) {
+            Ok(()) => ctx.log_info(format!(
+                "Exported {} bod{} to {}",
+                placed.len(),
+                if placed.len() == 1 { "y" } else { "ies" },
+                path.display()
+            )),
+            Err(err) => ctx.log_error(format!("Failed to export {}: {err}", path.display())),
+        }
+    }
+
+    /// Run all print checks and keep the report for the right panel.
+    fn check_printability(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let frame = BedFrame::new(&ctx.axes);
+        let layout = PrintLayout::load(ctx.document);
+        let placed = layout::place_bodies(&layout, &frame, ctx.body_meshes, ctx.printer.as_ref());
+        let report = report::check_printability(
+            ctx.document,
+            &layout,
+            &frame,
+            &placed,
+            ctx.printer.as_ref(),
+            &ctx.units,
+        );
+        match report.status() {
+            report::CheckStatus::Pass => ctx.log_info("Printability check passed"),
+            report::CheckStatus::Warning => ctx.log_warn("Printability check found warnings"),
+            report::CheckStatus::Fail => ctx.log_warn("Printability check found problems"),
+        }
+        self.report = Some(report);
+    }
+
+    /// Recompute the placement of every body with `update` and store the layout.
+    fn update_all(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        update: impl Fn(&layout::PlacedBody) -> layout::Placement,
+    ) {
+        let frame = BedFrame::new(&ctx.axes);
+        let mut layout = PrintLayout::load(ctx.document);
+        let placed = layout::place_bodies(&layout, &frame, ctx.body_meshes, ctx.printer.as_ref());
+        if placed.is_empty() {
+            ctx.log_warn("No bodies with geometry to place");
+            return;
+        }
+        for body in &placed {
+            layout.set_placement(body.body, update(body));
+        }
+        layout.save(ctx.document);
+    }
+}
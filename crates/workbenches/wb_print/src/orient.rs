@@ -0,0 +1,116 @@
+//! Automatic part orientation: searches tilts of a body for the one that needs
+//! the least support.
+
+use core_document::BodyMesh;
+
+use crate::analysis::{self, FaceClass};
+use crate::layout::{self, BedFrame, PlacedBody, Placement};
+
+/// Tilt increment (degrees) for the rotation search about X and Y.
+const SEARCH_STEP_DEG: usize = 15;
+
+/// Relative score difference below which candidates are considered equal.
+const SCORE_TOLERANCE: f32 = 1e-3;
+
+/// What the orientation search minimizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrientObjective {
+    /// Area of faces steeper than the overhang threshold.
+    #[default]
+    OverhangArea,
+    /// Estimated support volume: overhang area (projected onto the bed) times its height.
+    SupportVolume,
+    /// Overall print height (fewer layers, faster prints).
+    PrintHeight,
+}
+
+impl OrientObjective {
+    pub const ALL: [OrientObjective; 3] = [
+        OrientObjective::OverhangArea,
+        OrientObjective::SupportVolume,
+        OrientObjective::PrintHeight,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrientObjective::OverhangArea => "Least overhang area",
+            OrientObjective::SupportVolume => "Least support volume",
+            OrientObjective::PrintHeight => "Lowest print height",
+        }
+    }
+}
+
+/// Outcome of an orientation search.
+#[derive(Debug, Clone, Copy)]
+pub struct OrientResult {
+    /// Best placement found, resting on the bed.
+    pub placement: Placement,
+    /// Objective value of the starting placement.
+    pub initial_score: f32,
+    /// Objective value of `placement`.
+    pub score: f32,
+}
+
+/// Objective value for a placed body as if it rested on the bed.
+pub fn score(body: &PlacedBody, objective: OrientObjective, threshold_deg: f32) -> f32 {
+    if objective == OrientObjective::PrintHeight {
+        return body.size().z;
+    }
+    analysis::faces(body)
+        .iter()
+        .map(|face| {
+            let mut dropped = *face;
+            for vertex in &mut dropped.vertices {
+                vertex.z -= body.min.z;
+            }
+            if analysis::classify_overhang(&dropped, threshold_deg) != FaceClass::Overhang {
+                return 0.0;
+            }
+            match objective {
+                OrientObjective::SupportVolume => {
+                    dropped.area * dropped.normal.z.abs() * dropped.centroid().z
+                }
+                _ => dropped.area,
+            }
+        })
+        .sum()
+}
+
+/// Try tilts about X and Y (keeping the Z rotation, scale, and bed position) and
+/// return the best one, dropped onto the bed. Ties go to the lower print.
+pub fn optimize_orientation(
+    body_mesh: &BodyMesh,
+    current: Placement,
+    frame: &BedFrame,
+    objective: OrientObjective,
+    threshold_deg: f32,
+) -> Option<OrientResult> {
+    let initial = layout::place_body(body_mesh, current, frame, None)?;
+    let initial_score = score(&initial, objective, threshold_deg);
+    let mut best = (initial_score, initial.size().z, current);
+
+    for rx in (0..360).step_by(SEARCH_STEP_DEG) {
+        for ry in (0..360).step_by(SEARCH_STEP_DEG) {
+            let mut candidate = current;
+            candidate.rotation_deg = [rx as f32, ry as f32, current.rotation_deg[2]];
+            let Some(placed) = layout::place_body(body_mesh, candidate, frame, None) else {
+                continue;
+            };
+            let candidate_score = score(&placed, objective, threshold_deg);
+            let height = placed.size().z;
+            let margin = SCORE_TOLERANCE * best.0.abs().max(1.0);
+            let better = candidate_score < best.0 - margin
+                || (candidate_score <= best.0 + margin && height < best.1 - SCORE_TOLERANCE);
+            if better {
+                best = (candidate_score, height, candidate);
+            }
+        }
+    }
+
+    let placed = layout::place_body(body_mesh, best.2, frame, None)?;
+    Some(OrientResult {
+        placement: layout::dropped_to_bed(&placed),
+        initial_score,
+        score: best.0,
+    })
+}
@@ -0,0 +1,189 @@
+//! Screen-space drawing of the build volume and placed body bounds.
+
+use core_document::{BedShape, PrintVolume, ScreenSpaceOverlay, WorkbenchRuntimeContext};
+use glam::Vec3;
+
+use crate::gcode::{FeatureType, GcodePreview};
+use crate::layout::{BedFrame, PlacedBody};
+use crate::slice::Contour;
+
+const BED_COLOR: [f32; 3] = [0.35, 0.55, 0.85];
+const VOLUME_COLOR: [f32; 3] = [0.3, 0.4, 0.55];
+const FITS_COLOR: [f32; 3] = [0.3, 0.85, 0.4];
+const EXCEEDS_COLOR: [f32; 3] = [0.95, 0.3, 0.25];
+const CIRCLE_SEGMENTS: usize = 64;
+const CONTOUR_COLOR: [f32; 3] = [0.2, 0.85, 0.95];
+const OPEN_CONTOUR_COLOR: [f32; 3] = [0.95, 0.3, 0.25];
+const LOWER_CONTOUR_COLOR: [f32; 3] = [0.3, 0.45, 0.6];
+
+/// Toolpath segments drawn per frame; larger ranges are thinned out evenly
+/// (the top layer is always drawn in full).
+const MAX_TOOLPATH_SEGMENTS: usize = 100_000;
+
+/// Collects projected line segments, dropping any that end behind the camera.
+struct LineBuilder<'c, 'a> {
+    ctx: &'c WorkbenchRuntimeContext<'a>,
+    frame: BedFrame,
+    lines: Vec<ScreenSpaceOverlay>,
+}
+
+impl LineBuilder<'_, '_> {
+    fn line(&mut self, start: Vec3, end: Vec3, color: [f32; 3], thickness: f32) {
+        let project = |p: Vec3| {
+            self.ctx
+                .world_to_viewport(self.frame.to_world(p).to_array())
+        };
+        if let (Some(a), Some(b)) = (project(start), project(end)) {
+            self.lines.push(ScreenSpaceOverlay::new(
+                [a.0, a.1],
+                [b.0, b.1],
+                color,
+                thickness,
+            ));
+        }
+    }
+
+    fn polyline(&mut self, points: &[Vec3], color: [f32; 3], thickness: f32) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color, thickness);
+        }
+    }
+}
+
+/// Bed outline at height `z` (closed polygon, printer coordinates).
+fn bed_outline(printer: &PrintVolume, z: f32) -> Vec<Vec3> {
+    let ([min_x, min_y], [max_x, max_y]) = printer.bed_extent();
+    match printer.shape {
+        BedShape::Rectangular => vec![
+            Vec3::new(min_x, min_y, z),
+            Vec3::new(max_x, min_y, z),
+            Vec3::new(max_x, max_y, z),
+            Vec3::new(min_x, max_y, z),
+            Vec3::new(min_x, min_y, z),
+        ],
+        BedShape::Circular => {
+            let center = Vec3::new((min_x + max_x) * 0.5, (min_y + max_y) * 0.5, z);
+            let radius = printer.size[0] * 0.5;
+            (0..=CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    center + Vec3::new(angle.cos(), angle.sin(), 0.0) * radius
+                })
+                .collect()
+        }
+    }
+}
+
+/// Lines outlining the build volume and the bounds of every placed body.
+pub fn print_overlays(
+    ctx: &WorkbenchRuntimeContext,
+    frame: BedFrame,
+    printer: Option<&PrintVolume>,
+    placed: &[PlacedBody],
+) -> Vec<ScreenSpaceOverlay> {
+    let mut builder = LineBuilder {
+        ctx,
+        frame,
+        lines: Vec::new(),
+    };
+
+    if let Some(printer) = printer {
+        let height = printer.size[2];
+        let bottom = bed_outline(printer, 0.0);
+        let top = bed_outline(printer, height);
+        builder.polyline(&bottom, BED_COLOR, 2.0);
+        builder.polyline(&top, VOLUME_COLOR, 1.0);
+        // Vertical edges at the corners (quadrant points for round beds).
+        let step = (bottom.len() - 1) / 4;
+        for i in (0..bottom.len() - 1).step_by(step.max(1)) {
+            builder.line(bottom[i], top[i], VOLUME_COLOR, 1.0);
+        }
+    }
+
+    for body in placed {
+        let color = if body.fits() {
+            FITS_COLOR
+        } else {
+            EXCEEDS_COLOR
+        };
+        let (min, max) = (body.min, body.max);
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(
+                if x { max.x } else { min.x },
+                if y { max.y } else { min.y },
+                if z { max.z } else { min.z },
+            )
+        };
+        for z in [false, true] {
+            builder.polyline(
+                &[
+                    corner(false, false, z),
+                    corner(true, false, z),
+                    corner(true, true, z),
+                    corner(false, true, z),
+                    corner(false, false, z),
+                ],
+                color,
+                1.5,
+            );
+        }
+        for (x, y) in [(false, false), (true, false), (true, true), (false, true)] {
+            builder.line(corner(x, y, false), corner(x, y, true), color, 1.5);
+        }
+    }
+
+    builder.lines
+}
+
+/// Toolpath lines of the visible G-code layers; the top visible layer is drawn thicker.
+pub fn gcode_overlays(
+    ctx: &WorkbenchRuntimeContext,
+    frame: BedFrame,
+    preview: &GcodePreview,
+) -> Vec<ScreenSpaceOverlay> {
+    let mut builder = LineBuilder {
+        ctx,
+        frame,
+        lines: Vec::new(),
+    };
+    let visible = |feature: FeatureType| preview.show_travel || feature != FeatureType::Travel;
+    let (first, last) = (preview.first_layer, preview.last_layer);
+    let below: Vec<_> = preview
+        .toolpath
+        .segments_in(first, last)
+        .filter(|segment| segment.layer != last && visible(segment.feature))
+        .collect();
+    let stride = (below.len() / MAX_TOOLPATH_SEGMENTS).max(1);
+    for segment in below.into_iter().step_by(stride) {
+        builder.line(segment.from, segment.to, preview.color(segment), 1.0);
+    }
+    for segment in preview.toolpath.segments_in(last, last) {
+        if visible(segment.feature) {
+            builder.line(segment.from, segment.to, preview.color(segment), 2.0);
+        }
+    }
+    builder.lines
+}
+
+/// Contours of the layer preview (see [`crate::slice::slice_layers`]); those
+/// of the current layer are drawn thicker.
+pub fn slice_overlays(
+    ctx: &WorkbenchRuntimeContext,
+    frame: BedFrame,
+    contours: &[(Contour, bool)],
+) -> Vec<ScreenSpaceOverlay> {
+    let mut builder = LineBuilder {
+        ctx,
+        frame,
+        lines: Vec::new(),
+    };
+    for (contour, current) in contours {
+        let (color, thickness) = match (current, contour.closed) {
+            (false, _) => (LOWER_CONTOUR_COLOR, 1.0),
+            (true, true) => (CONTOUR_COLOR, 2.0),
+            (true, false) => (OPEN_CONTOUR_COLOR, 2.0),
+        };
+        builder.polyline(&contour.points, color, thickness);
+    }
+    builder.lines
+}
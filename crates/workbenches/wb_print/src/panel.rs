@@ -0,0 +1,1255 @@
+//! Right-panel and Analysis-panel UI of the Print Preparation workbench.
+
+use core_document::{BedShape, BodyId, CameraFocusRequest, GizmoKind, WorkbenchRuntimeContext};
+
+use crate::analysis::{
+    AnalysisMode, DraftAnalysis, DraftClass, FaceClass, OverhangAnalysis, PullDirection,
+    ThicknessAnalysis, ThicknessClass,
+};
+use crate::cache::{Cached, PrintCache};
+use crate::clearance::{ClearanceAnalysis, ClearanceClass};
+use crate::compensation;
+use crate::estimate::{self, EstimateSettings, PrintEstimate};
+use crate::gcode::{FeatureType, GcodeColoring, GcodePreview};
+use crate::hollow::{self, HollowSettings, Hollowed};
+use crate::layout::{self, BedFrame, PlacedBody, Placement, PrintLayout};
+use crate::orient::OrientObjective;
+use crate::report::CheckStatus;
+use crate::slice::{self, Contour};
+use crate::supports::{SupportPreview, SupportStyle};
+use crate::PrintWorkbench;
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PrintWorkbench,
+    cache: &PrintCache,
+) {
+    let selected_body = &mut workbench.selected_body;
+    let units = ctx.units;
+    ui.heading("Printer");
+    match ctx.printer {
+        Some(printer) => {
+            let [width, depth, height] = printer.size;
+            let footprint = match printer.shape {
+                BedShape::Rectangular => format!(
+                    "{} × {}",
+                    units.format_value(width),
+                    units.format_value(depth)
+                ),
+                BedShape::Circular => format!("⌀ {}", units.format_value(width)),
+            };
+            ui.label(format!(
+                "Build volume: {} × {}",
+                footprint,
+                units.format_length(height)
+            ));
+        }
+        None => {
+            ui.label("No printer profile is active.");
+        }
+    }
+    ui.weak("Printer profiles are edited in Settings → Printers.");
+
+    let frame = BedFrame::new(&ctx.axes);
+    let mut layout = cache.layout().clone();
+    let placed = cache.placed();
+
+    ui.separator();
+    ui.heading("Bodies");
+    if ctx.document.bodies().is_empty() {
+        ui.label("The document has no bodies.");
+    }
+    for body in ctx.document.bodies() {
+        let status = match placed.iter().find(|p| p.body == body.id) {
+            Some(p) if p.fits() => "✔ fits".to_string(),
+            Some(p) => format!("⚠ {} vertices outside", p.vertices_outside),
+            None => "no geometry".to_string(),
+        };
+        let is_selected = *selected_body == Some(body.id);
+        if ui
+            .selectable_label(is_selected, format!("{} — {}", body.name, status))
+            .clicked()
+        {
+            *selected_body = Some(body.id);
+        }
+    }
+
+    let exceeding: Vec<_> = placed.iter().filter(|p| !p.fits()).collect();
+    if !exceeding.is_empty() && ctx.printer.is_some() {
+        ui.add_space(4.0);
+        ui.colored_label(
+            egui::Color32::from_rgb(230, 90, 70),
+            format!(
+                "{} bod{} exceed the build volume.",
+                exceeding.len(),
+                if exceeding.len() == 1 { "y" } else { "ies" }
+            ),
+        );
+    }
+
+    let (arrange_clicked, spacing_changed) = ui
+        .horizontal(|ui| {
+            let enabled = ctx.printer.is_some() && !placed.is_empty();
+            let clicked = ui
+                .add_enabled(enabled, egui::Button::new("Arrange on bed"))
+                .clicked();
+            ui.label("Spacing:");
+            let changed = ui
+                .add(
+                    egui::DragValue::new(&mut layout.arrange_spacing)
+                        .range(0.0..=50.0)
+                        .speed(0.1)
+                        .suffix(" mm"),
+                )
+                .changed();
+            (clicked, changed)
+        })
+        .inner;
+    if spacing_changed {
+        layout.save(ctx.document);
+    }
+    if arrange_clicked {
+        workbench.arrange(ctx);
+        // Pick up the new placements so later edits this frame do not undo them.
+        layout = PrintLayout::load(ctx.document);
+    }
+
+    let selected = workbench
+        .selected_body
+        .and_then(|id| placed.iter().find(|p| p.body == id));
+
+    ui.separator();
+    ui.heading("Supports");
+    let supports = selected.map(|body| cache.supports(body.body));
+    if supports_section(ui, ctx, workbench, &mut layout, supports) {
+        layout.save(ctx.document);
+    }
+
+    if let Some(body) = selected {
+        ui.separator();
+        ui.heading("Hollowing");
+        if hollow_section(
+            ui,
+            ctx,
+            &frame,
+            &mut layout,
+            body,
+            cache.hollowed(body.body),
+            &mut workbench.drain_diameter,
+        ) {
+            layout.save(ctx.document);
+        }
+
+        ui.separator();
+        ui.heading("Compensation");
+        if compensation_section(
+            ui,
+            ctx,
+            &mut layout,
+            body.body,
+            &mut workbench.shrinkage_percent,
+        ) {
+            layout.save(ctx.document);
+        }
+    }
+
+    ui.separator();
+    ui.heading("Layer Preview");
+    if layer_section(ui, ctx, workbench, &mut layout, placed, cache.slices()) {
+        layout.save(ctx.document);
+    }
+
+    ui.separator();
+    ui.heading("G-code Preview");
+    gcode_section(ui, ctx, &mut workbench.gcode);
+
+    ui.separator();
+    ui.heading("Estimate");
+    if estimate_section(ui, ctx, cache, &mut layout, &workbench.gcode) {
+        layout.save(ctx.document);
+    }
+
+    let selected_body = &mut workbench.selected_body;
+    let Some(body_id) = *selected_body else {
+        return;
+    };
+    ui.separator();
+    ui.heading("Placement");
+    let current = placed.iter().find(|p| p.body == body_id);
+    if let Some(body) = current {
+        let size = body.size();
+        ui.label(format!(
+            "Size: {} × {} × {}",
+            units.format_value(size.x),
+            units.format_value(size.y),
+            units.format_length(size.z)
+        ));
+    }
+
+    let mut placement = layout.placement(body_id);
+    let mut changed = placement_editor(ui, &mut placement);
+
+    ui.horizontal(|ui| {
+        ui.label("Handles:");
+        let handles = &mut workbench.placement_handles;
+        ui.selectable_value(handles, None, "Off");
+        ui.selectable_value(handles, Some(GizmoKind::Translate), "Move");
+        ui.selectable_value(handles, Some(GizmoKind::Rotate), "Rotate");
+    });
+
+    ui.horizontal(|ui| {
+        if let (Some(body), Some(printer)) = (current, ctx.printer) {
+            if ui.button("Center on bed").clicked() {
+                placement = layout::centered_on_bed(body, &printer);
+                changed = true;
+            }
+        }
+        if let Some(body) = current {
+            if ui.button("Drop to bed").clicked() {
+                placement = layout::dropped_to_bed(body);
+                changed = true;
+            }
+        }
+        if ui.button("Reset").clicked() {
+            placement = Placement::default();
+            changed = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("print_orient_objective")
+            .selected_text(workbench.orient_objective.label())
+            .show_ui(ui, |ui| {
+                for objective in OrientObjective::ALL {
+                    ui.selectable_value(
+                        &mut workbench.orient_objective,
+                        objective,
+                        objective.label(),
+                    );
+                }
+            });
+        if ui
+            .add_enabled(current.is_some(), egui::Button::new("Auto-orient"))
+            .clicked()
+        {
+            if let Some(best) = workbench.auto_orient(ctx, body_id) {
+                placement = best;
+                changed = true;
+            }
+        }
+    });
+
+    if changed {
+        layout.set_placement(body_id, placement);
+        layout.save(ctx.document);
+    }
+}
+
+/// The Analysis panel: the analysis shown on the selected body and the
+/// printability check.
+pub fn analysis_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PrintWorkbench,
+    cache: &PrintCache,
+) {
+    let mut layout = cache.layout().clone();
+    let placed = cache.placed();
+    ui.heading("Analysis");
+    egui::ComboBox::from_id_salt("print_analysis_mode")
+        .selected_text(workbench.analysis.label())
+        .show_ui(ui, |ui| {
+            for mode in AnalysisMode::ALL {
+                ui.selectable_value(&mut workbench.analysis, mode, mode.label());
+            }
+        });
+    let selected = workbench
+        .selected_body
+        .and_then(|id| placed.iter().find(|p| p.body == id));
+    let settings_changed = match workbench.analysis {
+        AnalysisMode::None => false,
+        AnalysisMode::Overhangs => overhang_section(
+            ui,
+            ctx,
+            &mut layout,
+            selected.and_then(|body| cache.overhangs(body.body)),
+        ),
+        AnalysisMode::Draft => draft_section(
+            ui,
+            ctx,
+            &mut layout,
+            selected.and_then(|body| cache.draft(body.body)),
+        ),
+        AnalysisMode::WallThickness => thickness_section(
+            ui,
+            ctx,
+            &mut layout,
+            selected.map(|body| cache.thickness(body.body)),
+            &mut workbench.nozzle_width,
+            &mut workbench.wall_perimeters,
+        ),
+        AnalysisMode::Clearance => {
+            clearance_section(ui, ctx, workbench, &mut layout, cache.clearance())
+        }
+    };
+    if settings_changed {
+        layout.save(ctx.document);
+    }
+
+    ui.separator();
+    ui.heading("Printability");
+    ui.horizontal(|ui| {
+        if ui.button("Check Printability").clicked() {
+            workbench.check_printability(ctx);
+        }
+        if workbench.report.is_some() && ui.button("Clear").clicked() {
+            workbench.report = None;
+        }
+    });
+    if let Some(report) = &workbench.report {
+        report_section(ui, ctx, report);
+    }
+}
+
+fn status_color(status: CheckStatus) -> egui::Color32 {
+    match status {
+        CheckStatus::Pass => egui::Color32::from_rgb(90, 200, 110),
+        CheckStatus::Warning => egui::Color32::from_rgb(240, 200, 70),
+        CheckStatus::Fail => egui::Color32::from_rgb(230, 90, 70),
+    }
+}
+
+/// Summary of the last printability check with zoom-to-problem links.
+fn report_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    report: &crate::report::PrintabilityReport,
+) {
+    if report.bodies.is_empty() {
+        ui.label("No bodies with geometry to check.");
+        return;
+    }
+    for body in &report.bodies {
+        let status = body.status();
+        let title = egui::RichText::new(format!("{} {}", status.icon(), body.name))
+            .color(status_color(status));
+        egui::CollapsingHeader::new(title)
+            .id_salt(("print_report", body.body))
+            .default_open(status != CheckStatus::Pass)
+            .show(ui, |ui| {
+                for check in &body.checks {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(status_color(check.status), check.status.icon());
+                        ui.strong(check.name);
+                        ui.label(&check.summary);
+                    });
+                    for problem in &check.problems {
+                        ui.horizontal(|ui| {
+                            ui.add_space(16.0);
+                            if ui.small_button("Zoom").clicked() {
+                                ctx.camera_focus_request = Some(CameraFocusRequest {
+                                    center: problem.location,
+                                    radius: problem.radius,
+                                });
+                            }
+                            ui.label(&problem.description);
+                        });
+                    }
+                }
+            });
+    }
+}
+
+fn color32(color: [f32; 3]) -> egui::Color32 {
+    let [r, g, b] = color.map(|c| (c * 255.0) as u8);
+    egui::Color32::from_rgb(r, g, b)
+}
+
+fn legend(ui: &mut egui::Ui, entries: impl IntoIterator<Item = ([f32; 3], &'static str)>) {
+    for (color, label) in entries {
+        ui.colored_label(color32(color), label);
+    }
+}
+
+/// Overhang threshold and legend; returns true if the threshold changed.
+fn overhang_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    layout: &mut PrintLayout,
+    selected: Option<&OverhangAnalysis>,
+) -> bool {
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label("Threshold:");
+            ui.add(
+                egui::DragValue::new(&mut layout.overhang_angle_deg)
+                    .range(0.0..=89.0)
+                    .speed(1.0)
+                    .suffix("° from vertical"),
+            )
+            .changed()
+        })
+        .inner;
+    legend(
+        ui,
+        FaceClass::ALL.map(|class| (class.color(), class.label())),
+    );
+    if let Some(result) = selected {
+        ui.label(format!(
+            "Area needing support: {}",
+            ctx.units.format_area(result.overhang_area)
+        ));
+    }
+    changed
+}
+
+/// Pull direction, minimum draft, and legend; returns true if the settings
+/// changed.
+fn draft_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    layout: &mut PrintLayout,
+    selected: Option<&DraftAnalysis>,
+) -> bool {
+    let settings = &mut layout.draft;
+    let mut changed = false;
+    egui::Grid::new("print_draft_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Pull direction:");
+            egui::ComboBox::from_id_salt("print_draft_pull")
+                .selected_text(settings.pull.label())
+                .show_ui(ui, |ui| {
+                    for pull in PullDirection::ALL {
+                        changed |= ui
+                            .selectable_value(&mut settings.pull, pull, pull.label())
+                            .changed();
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Minimum draft:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.min_angle_deg)
+                        .range(0.0..=45.0)
+                        .speed(0.1)
+                        .suffix("°"),
+                )
+                .changed();
+            ui.end_row();
+        });
+    legend(
+        ui,
+        DraftClass::ALL.map(|class| (class.color(), class.label())),
+    );
+    if let Some(result) = selected {
+        ui.label(format!(
+            "Too little draft: {}",
+            ctx.units.format_area(result.insufficient_area)
+        ));
+        ui.label(format!(
+            "Negative draft: {}",
+            ctx.units.format_area(result.negative_area)
+        ));
+    }
+    changed
+}
+
+/// Support preview toggle and settings; returns true if the settings changed.
+fn supports_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    workbench: &mut PrintWorkbench,
+    layout: &mut PrintLayout,
+    selected: Option<Cached<SupportPreview>>,
+) -> bool {
+    ui.checkbox(&mut workbench.show_supports, "Preview supports");
+    let settings = &mut layout.supports;
+    let mut changed = false;
+    egui::Grid::new("print_support_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Style:");
+            egui::ComboBox::from_id_salt("print_support_style")
+                .selected_text(settings.style.label())
+                .show_ui(ui, |ui| {
+                    for style in SupportStyle::ALL {
+                        changed |= ui
+                            .selectable_value(&mut settings.style, style, style.label())
+                            .changed();
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Spacing:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.spacing)
+                        .range(0.5..=50.0)
+                        .speed(0.1)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Diameter:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.diameter)
+                        .range(0.2..=10.0)
+                        .speed(0.05)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+        });
+    ui.weak("Supports are a preview only and use the overhang threshold.");
+    match selected {
+        Some(Cached::Ready(preview)) if preview.contacts == 0 => {
+            ui.label("The selected body needs no support.");
+        }
+        Some(Cached::Ready(preview)) => {
+            ui.label(format!(
+                "{} contact points, about {} of support",
+                preview.contacts,
+                ctx.units.format_volume(preview.volume)
+            ));
+        }
+        Some(Cached::Computing) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Generating supports…");
+            });
+        }
+        Some(Cached::Missing) | None => {}
+    }
+    changed
+}
+
+/// Layer preview toggle, layer height, and layer slider; returns true if the
+/// layer height changed.
+fn layer_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    workbench: &mut PrintWorkbench,
+    layout: &mut PrintLayout,
+    placed: &[PlacedBody],
+    slices: &[(Contour, bool)],
+) -> bool {
+    let mut enabled = workbench.preview_layer.is_some();
+    if ui.checkbox(&mut enabled, "Show layer contours").changed() {
+        workbench.preview_layer = enabled.then_some(0);
+    }
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label("Layer height:");
+            ui.add(
+                egui::DragValue::new(&mut layout.layer_height)
+                    .range(0.01..=2.0)
+                    .speed(0.01)
+                    .suffix(" mm"),
+            )
+            .changed()
+        })
+        .inner;
+    let Some(layer) = &mut workbench.preview_layer else {
+        return changed;
+    };
+
+    let count = slice::layer_count(placed, layout.layer_height);
+    if count == 0 {
+        ui.label("No bodies above the bed to slice.");
+        return changed;
+    }
+    *layer = (*layer).min(count - 1);
+    ui.add(
+        egui::Slider::new(layer, 0..=count - 1)
+            .custom_formatter(|n, _| format!("{}", n as usize + 1))
+            .text(format!("of {}", count)),
+    );
+    let z = slice::layer_z(*layer, layout.layer_height);
+    let contours: Vec<_> = slices
+        .iter()
+        .filter(|(_, current)| *current)
+        .map(|(contour, _)| contour)
+        .collect();
+    let open = contours.iter().filter(|contour| !contour.closed).count();
+    ui.label(format!(
+        "Cut at {}: {} contours",
+        ctx.units.format_length(z),
+        contours.len()
+    ));
+    if open > 0 {
+        ui.colored_label(
+            egui::Color32::from_rgb(230, 90, 70),
+            format!("{} contours are open (holes in the mesh)", open),
+        );
+    }
+    ui.checkbox(&mut workbench.show_lower_layers, "Show layers below");
+    changed
+}
+
+/// Print time, mass, and cost per body and for the plate.
+fn estimate_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    cache: &PrintCache,
+    layout: &mut PrintLayout,
+    gcode: &Option<GcodePreview>,
+) -> bool {
+    let settings = &mut layout.estimate;
+    let mut changed = false;
+    egui::Grid::new("print_estimate_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Flow rate:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.flow_rate)
+                        .range(0.5..=100.0)
+                        .speed(0.1)
+                        .suffix(" mm³/s"),
+                )
+                .changed();
+            ui.end_row();
+            ui.label("Infill:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.infill_percent)
+                        .range(0.0..=100.0)
+                        .speed(1.0)
+                        .suffix(" %"),
+                )
+                .changed();
+            ui.end_row();
+            ui.label("Shell thickness:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.shell_thickness)
+                        .range(0.0..=10.0)
+                        .speed(0.05)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+            ui.label("Per layer:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.layer_overhead)
+                        .range(0.0..=60.0)
+                        .speed(0.1)
+                        .suffix(" s"),
+                )
+                .changed();
+            ui.end_row();
+        });
+    let settings: EstimateSettings = *settings;
+
+    let placed = cache.placed();
+    if placed.is_empty() {
+        ui.label("No bodies to estimate.");
+        return changed;
+    }
+    let library = ctx.document.materials();
+    let estimates: Vec<(String, PrintEstimate)> = placed
+        .iter()
+        .filter_map(|body| {
+            let measure = cache.measure(body.body)?;
+            let hollow = cache.hollowed(body.body).ready();
+            let material = ctx.document.body_material(body.body);
+            let name = ctx
+                .document
+                .bodies()
+                .iter()
+                .find(|b| b.id == body.body)
+                .map(|b| b.name.clone())
+                .unwrap_or_default();
+            let estimate = estimate::estimate_body(
+                measure,
+                hollow,
+                &settings,
+                layout.layer_height,
+                material.as_ref(),
+            );
+            Some((name, estimate))
+        })
+        .collect();
+    let plate = PrintEstimate::total(
+        &estimates
+            .iter()
+            .map(|(_, estimate)| *estimate)
+            .collect::<Vec<_>>(),
+    );
+
+    let row = |ui: &mut egui::Ui, name: &str, estimate: &PrintEstimate| {
+        ui.label(name);
+        ui.label(estimate::format_duration(estimate.time));
+        ui.label(ctx.units.format_volume(estimate.volume));
+        match (estimate.mass, estimate.cost) {
+            (Some(mass), Some(cost)) => {
+                ui.label(format!("{mass:.1} g"));
+                ui.label(library.format_price(cost));
+            }
+            _ => {
+                ui.weak("—");
+                ui.weak("—");
+            }
+        }
+        ui.end_row();
+    };
+    egui::Grid::new("print_estimates")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            for header in ["Body", "Time", "Volume", "Mass", "Cost"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for (name, estimate) in &estimates {
+                row(ui, name, estimate);
+            }
+            row(ui, "Plate", &plate);
+        });
+    if plate.cost.is_none() {
+        ui.weak("Assign materials to bodies (Materials window) for mass and cost.");
+    }
+
+    if let Some(preview) = gcode {
+        // The G-code prints whatever was sliced; cost it with the plate's
+        // material when every body uses the same one.
+        let mut materials = placed
+            .iter()
+            .map(|body| ctx.document.body_material(body.body));
+        let first = materials.next().flatten();
+        let material = first.filter(|first| materials.all(|m| m.as_ref() == Some(first)));
+        let sliced = estimate::estimate_toolpath(&preview.toolpath, &settings, material.as_ref());
+        ui.add_space(4.0);
+        ui.label(format!(
+            "From G-code: {}, {}",
+            estimate::format_duration(sliced.time),
+            ctx.units.format_volume(sliced.volume)
+        ));
+        if let (Some(mass), Some(cost)) = (sliced.mass, sliced.cost) {
+            ui.label(format!("{mass:.1} g, {}", library.format_price(cost)));
+        }
+    }
+    changed
+}
+
+/// Loaded G-code with its layer range, coloring, and legend.
+fn gcode_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    gcode: &mut Option<GcodePreview>,
+) {
+    let Some(preview) = gcode else {
+        ui.label("Load sliced G-code to check the toolpaths on the bed.");
+        if ui.button("Open G-code…").clicked() {
+            crate::request_gcode_file(ctx);
+        }
+        return;
+    };
+
+    let toolpath = &preview.toolpath;
+    ui.strong(&preview.name);
+    ui.label(format!(
+        "{} layers, {} extrusion moves, {} of filament",
+        toolpath.layers.len(),
+        toolpath.extrusion_count(),
+        ctx.units.format_length(toolpath.filament_length)
+    ));
+
+    let top = toolpath.layers.len().saturating_sub(1);
+    let height = |layer: usize| {
+        toolpath
+            .layers
+            .get(layer)
+            .map(|z| ctx.units.format_length(*z))
+            .unwrap_or_default()
+    };
+    let (first_height, last_height) = (height(preview.first_layer), height(preview.last_layer));
+    egui::Grid::new("print_gcode_layers")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Top layer:");
+            ui.add(
+                egui::Slider::new(&mut preview.last_layer, 0..=top)
+                    .custom_formatter(|n, _| format!("{}", n as usize + 1))
+                    .text(last_height),
+            );
+            ui.end_row();
+            ui.label("Bottom layer:");
+            ui.add(
+                egui::Slider::new(&mut preview.first_layer, 0..=top)
+                    .custom_formatter(|n, _| format!("{}", n as usize + 1))
+                    .text(first_height),
+            );
+            ui.end_row();
+        });
+    preview.first_layer = preview.first_layer.min(preview.last_layer);
+    ui.horizontal(|ui| {
+        if ui.button("Single layer").clicked() {
+            preview.first_layer = preview.last_layer;
+        }
+        if ui.button("All layers").clicked() {
+            preview.first_layer = 0;
+            preview.last_layer = top;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Color by:");
+        for coloring in GcodeColoring::ALL {
+            ui.radio_value(&mut preview.coloring, coloring, coloring.label());
+        }
+    });
+    ui.checkbox(&mut preview.show_travel, "Show travel moves");
+    if preview.coloring == GcodeColoring::Feature {
+        legend(
+            ui,
+            FeatureType::ALL
+                .into_iter()
+                .filter(|feature| preview.show_travel || *feature != FeatureType::Travel)
+                .map(|feature| (feature.color(), feature.label())),
+        );
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Open another…").clicked() {
+            crate::request_gcode_file(ctx);
+        }
+        if ui.button("Close").clicked() {
+            *gcode = None;
+        }
+    });
+}
+
+/// Hollowing of the selected body; returns true if its settings changed.
+fn hollow_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    frame: &BedFrame,
+    layout: &mut PrintLayout,
+    body: &PlacedBody,
+    result: Cached<'_, Hollowed>,
+    drain_diameter: &mut f32,
+) -> bool {
+    let mut settings = layout.hollow(body.body).cloned();
+    let mut hollowed = settings.is_some();
+    let mut changed = ui.checkbox(&mut hollowed, "Hollow").changed();
+    if changed {
+        settings = hollowed.then(HollowSettings::default);
+    }
+
+    if let Some(settings) = &mut settings {
+        match result {
+            Cached::Ready(result) if !result.watertight => {
+                ui.colored_label(
+                    egui::Color32::from_rgb(240, 200, 70),
+                    "The hollowed mesh is not closed; check the body's mesh for holes.",
+                );
+            }
+            Cached::Ready(result) if result.inner.indices.is_empty() => {
+                ui.label("No part is thicker than twice the wall; the body stays solid.");
+            }
+            Cached::Computing => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Hollowing…");
+                });
+            }
+            Cached::Ready(_) | Cached::Missing => {}
+        }
+        ui.horizontal(|ui| {
+            ui.label("Wall thickness:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut settings.wall_thickness)
+                        .range(0.2..=20.0)
+                        .speed(0.05)
+                        .suffix(" mm"),
+                )
+                .changed();
+        });
+        if let Cached::Ready(result) = result {
+            ui.label(format!(
+                "Material: {} of {} ({:.0}% saved)",
+                ctx.units.format_volume(result.material_volume),
+                ctx.units.format_volume(result.solid_volume),
+                result.savings() * 100.0
+            ));
+        }
+
+        ui.label(format!("Drain holes: {}", settings.drain_holes.len()));
+        let mut removed = None;
+        for (index, hole) in settings.drain_holes.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add_space(16.0);
+                ui.label(format!(
+                    "#{} ⌀ {}",
+                    index + 1,
+                    ctx.units.format_length(hole.diameter)
+                ));
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            settings.drain_holes.remove(index);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Hole diameter:");
+            ui.add(
+                egui::DragValue::new(drain_diameter)
+                    .range(0.5..=20.0)
+                    .speed(0.1)
+                    .suffix(" mm"),
+            );
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Auto drain holes").clicked() {
+                settings.drain_holes = hollow::auto_drain_holes(body, frame, *drain_diameter);
+                changed = true;
+            }
+            if !settings.drain_holes.is_empty() && ui.button("Clear holes").clicked() {
+                settings.drain_holes.clear();
+                changed = true;
+            }
+        });
+        ui.weak("Use the Place Drain Hole tool and click the body to add holes by hand.");
+        ui.weak("Export Plate writes the body hollowed, with its drain holes cut.");
+    }
+
+    if changed {
+        layout.set_hollow(body.body, settings);
+    }
+    changed
+}
+
+/// Shrinkage scaling and XY offsets of one body; returns true if they changed.
+fn compensation_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    layout: &mut PrintLayout,
+    body: BodyId,
+    shrinkage: &mut f32,
+) -> bool {
+    let mut compensation = layout.compensation(body).copied().unwrap_or_default();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Scale:");
+        for (axis, factor) in ["X", "Y", "Z"].iter().zip(&mut compensation.scale) {
+            let mut percent = *factor * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .range(50.0..=200.0)
+                        .speed(0.01)
+                        .prefix(format!("{axis} "))
+                        .suffix(" %"),
+                )
+                .changed()
+            {
+                *factor = percent / 100.0;
+                changed = true;
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Horizontal expansion:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut compensation.xy_offset)
+                    .range(-2.0..=2.0)
+                    .speed(0.005)
+                    .suffix(" mm"),
+            )
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Elephant's foot:");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut compensation.elephant_foot)
+                    .range(0.0..=2.0)
+                    .speed(0.005)
+                    .suffix(" mm"),
+            )
+            .changed();
+        ui.label("over");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut compensation.elephant_foot_height)
+                    .range(0.05..=5.0)
+                    .speed(0.01)
+                    .suffix(" mm"),
+            )
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Material shrinkage:");
+        ui.add(
+            egui::DragValue::new(shrinkage)
+                .range(0.0..=10.0)
+                .speed(0.01)
+                .suffix(" %"),
+        );
+        if ui.button("Set XY scale").clicked() {
+            let factor = compensation::shrinkage_scale(*shrinkage);
+            compensation.scale[0] = factor;
+            compensation.scale[1] = factor;
+            changed = true;
+        }
+    });
+
+    if changed {
+        layout.set_compensation(body, Some(compensation));
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Apply to all bodies").clicked() {
+            for body_mesh in ctx.body_meshes {
+                layout.set_compensation(body_mesh.body, Some(compensation));
+            }
+            changed = true;
+        }
+        if !compensation.is_identity() && ui.button("Reset").clicked() {
+            layout.set_compensation(body, None);
+            changed = true;
+        }
+    });
+    ui.weak("Only the printed copy is resized; the model keeps its nominal size.");
+    changed
+}
+
+/// Body pickers, minimum clearance, and result; returns true if the minimum changed.
+fn clearance_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut PrintWorkbench,
+    layout: &mut PrintLayout,
+    clearance: Option<Cached<ClearanceAnalysis>>,
+) -> bool {
+    let bodies = ctx.document.bodies();
+    let name = |id: Option<BodyId>| {
+        id.and_then(|id| bodies.iter().find(|body| body.id == id))
+            .map(|body| body.name.clone())
+            .unwrap_or_else(|| "Choose…".to_string())
+    };
+    // Start with the selected body and the first other one.
+    let [first, second] = &mut workbench.clearance_bodies;
+    if first.is_none() {
+        *first = workbench
+            .selected_body
+            .or(bodies.first().map(|body| body.id));
+    }
+    if second.is_none() {
+        *second = bodies
+            .iter()
+            .map(|body| body.id)
+            .find(|id| Some(*id) != *first);
+    }
+    for (index, choice) in workbench.clearance_bodies.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(if index == 0 { "Body A:" } else { "Body B:" });
+            egui::ComboBox::from_id_salt(("print_clearance_body", index))
+                .selected_text(name(*choice))
+                .show_ui(ui, |ui| {
+                    for body in bodies {
+                        ui.selectable_value(choice, Some(body.id), &body.name);
+                    }
+                });
+        });
+    }
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label("Minimum clearance:");
+            ui.add(
+                egui::DragValue::new(&mut layout.min_clearance)
+                    .range(0.0..=10.0)
+                    .speed(0.01)
+                    .suffix(" mm"),
+            )
+            .changed()
+        })
+        .inner;
+    legend(
+        ui,
+        ClearanceClass::ALL.map(|class| (class.color(), class.label())),
+    );
+
+    let [a, b] = workbench.clearance_bodies;
+    if a.is_some() && a == b {
+        ui.label("Choose two different bodies.");
+        return changed;
+    }
+    let result = match clearance {
+        None => {
+            ui.label("Both bodies need geometry to compare.");
+            return changed;
+        }
+        Some(Cached::Computing) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Measuring clearance…");
+            });
+            return changed;
+        }
+        Some(Cached::Missing) => {
+            ui.weak("Not measured (the analysis was cancelled); change a setting to retry.");
+            return changed;
+        }
+        Some(Cached::Ready(result)) => result,
+    };
+    if result.interfering {
+        ui.colored_label(
+            color32(ClearanceClass::Interfering.color()),
+            "The bodies interfere.",
+        );
+    }
+    if let (Some(min), Some((p, q))) = (result.min_clearance, result.closest) {
+        ui.horizontal(|ui| {
+            ui.label(format!("Smallest gap: {}", ctx.units.format_length(min)));
+            if ui.small_button("Zoom").clicked() {
+                let frame = BedFrame::new(&ctx.axes);
+                ctx.camera_focus_request = Some(CameraFocusRequest {
+                    center: frame.to_world((p + q) * 0.5).to_array(),
+                    radius: (layout.min_clearance * 10.0).max(p.distance(q) * 2.0),
+                });
+            }
+        });
+    }
+    if result.tight_area > 0.0 {
+        ui.label(format!(
+            "Tight or interfering: {}",
+            ctx.units.format_area(result.tight_area)
+        ));
+    }
+    changed
+}
+
+/// Minimum wall thickness and legend, with the result for the selected body;
+/// returns true if the minimum changed.
+fn thickness_section(
+    ui: &mut egui::Ui,
+    ctx: &WorkbenchRuntimeContext,
+    layout: &mut PrintLayout,
+    selected: Option<Cached<ThicknessAnalysis>>,
+    nozzle_width: &mut f32,
+    perimeters: &mut u32,
+) -> bool {
+    let mut changed = ui
+        .horizontal(|ui| {
+            ui.label("Minimum wall:");
+            ui.add(
+                egui::DragValue::new(&mut layout.min_wall_thickness)
+                    .range(0.05..=20.0)
+                    .speed(0.05)
+                    .suffix(" mm"),
+            )
+            .changed()
+        })
+        .inner;
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::DragValue::new(nozzle_width)
+                .range(0.1..=2.0)
+                .speed(0.05)
+                .suffix(" mm nozzle"),
+        );
+        ui.label("×");
+        ui.add(
+            egui::DragValue::new(perimeters)
+                .range(1..=10)
+                .suffix(" perimeters"),
+        );
+        if ui.button("Use").clicked() {
+            layout.min_wall_thickness = *nozzle_width * *perimeters as f32;
+            changed = true;
+        }
+    });
+    legend(
+        ui,
+        ThicknessClass::ALL.map(|class| (class.color(), class.label())),
+    );
+    match selected {
+        None => {}
+        Some(Cached::Missing) => {
+            ui.weak("Not measured (the analysis was cancelled); change the minimum to retry.");
+        }
+        Some(Cached::Computing) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Measuring walls…");
+            });
+        }
+        Some(Cached::Ready(result)) => {
+            match result.thinnest {
+                Some(thinnest) => ui.label(format!(
+                    "Thinnest wall: {}",
+                    ctx.units.format_length(thinnest)
+                )),
+                None => ui.label("No closed walls found."),
+            };
+            if result.thin_area > 0.0 {
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 90, 70),
+                    format!(
+                        "Thin walls cover {}",
+                        ctx.units.format_area(result.thin_area)
+                    ),
+                );
+            }
+        }
+    }
+    changed
+}
+
+fn placement_editor(ui: &mut egui::Ui, placement: &mut Placement) -> bool {
+    let mut changed = false;
+    egui::Grid::new("print_placement_grid")
+        .num_columns(4)
+        .show(ui, |ui| {
+            ui.label("Move:");
+            for value in &mut placement.translation {
+                changed |= ui
+                    .add(egui::DragValue::new(value).speed(0.5).suffix(" mm"))
+                    .changed();
+            }
+            ui.end_row();
+
+            ui.label("Rotate:");
+            for value in &mut placement.rotation_deg {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(value)
+                            .range(-360.0..=360.0)
+                            .speed(1.0)
+                            .suffix("°"),
+                    )
+                    .changed();
+            }
+            ui.end_row();
+
+            ui.label("Scale:");
+            let mut percent = placement.scale * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .range(1.0..=10000.0)
+                        .speed(1.0)
+                        .suffix(" %"),
+                )
+                .changed()
+            {
+                placement.scale = percent / 100.0;
+                changed = true;
+            }
+            ui.end_row();
+        });
+    changed
+}
@@ -0,0 +1,312 @@
+//! One-click printability report combining all print checks.
+
+use core_document::{BodyId, Document, PrintVolume, UnitFormat};
+use glam::Vec3;
+
+use crate::analysis::{self, BED_CONTACT_TOLERANCE};
+use crate::layout::{BedFrame, PlacedBody, PrintLayout};
+
+/// Problems listed per check; the summary still counts all of them.
+const MAX_LISTED_PROBLEMS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Pass,
+    Warning,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✔",
+            CheckStatus::Warning => "⚠",
+            CheckStatus::Fail => "✖",
+        }
+    }
+}
+
+/// A located problem the user can zoom to.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub description: String,
+    /// Where the problem is (world space).
+    pub location: [f32; 3],
+    /// Rough size of the region to show around `location`.
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub summary: String,
+    pub problems: Vec<Problem>,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, status: CheckStatus, summary: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            summary: summary.into(),
+            problems: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BodyReport {
+    pub body: BodyId,
+    pub name: String,
+    pub checks: Vec<CheckResult>,
+}
+
+impl BodyReport {
+    /// Worst status of any check.
+    pub fn status(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Pass)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrintabilityReport {
+    pub bodies: Vec<BodyReport>,
+}
+
+impl PrintabilityReport {
+    pub fn status(&self) -> CheckStatus {
+        self.bodies
+            .iter()
+            .map(BodyReport::status)
+            .max()
+            .unwrap_or(CheckStatus::Pass)
+    }
+}
+
+/// Run every check on every placed body.
+pub fn check_printability(
+    document: &Document,
+    layout: &PrintLayout,
+    frame: &BedFrame,
+    placed: &[PlacedBody],
+    printer: Option<&PrintVolume>,
+    units: &UnitFormat,
+) -> PrintabilityReport {
+    let bodies = placed
+        .iter()
+        .map(|body| {
+            let name = document
+                .bodies()
+                .iter()
+                .find(|b| b.id == body.body)
+                .map(|b| b.name.clone())
+                .unwrap_or_else(|| "Body".to_string());
+            let locate = |point: Vec3, radius: f32, description: String| Problem {
+                description,
+                location: frame.to_world(point).to_array(),
+                radius,
+            };
+            BodyReport {
+                body: body.body,
+                name,
+                checks: vec![
+                    mesh_validity_check(body, &locate),
+                    wall_thickness_check(body, layout, units, &locate),
+                    overhang_check(body, layout, units, &locate),
+                    bed_fit_check(body, printer, units, &locate),
+                ]
+                .into_iter()
+                .chain(drain_hole_check(body, layout))
+                .collect(),
+            }
+        })
+        .collect();
+    PrintabilityReport { bodies }
+}
+
+fn edge_problems(
+    edges: &[[Vec3; 2]],
+    label: &str,
+    locate: &impl Fn(Vec3, f32, String) -> Problem,
+) -> Vec<Problem> {
+    edges
+        .iter()
+        .take(MAX_LISTED_PROBLEMS)
+        .enumerate()
+        .map(|(i, [a, b])| {
+            locate(
+                (*a + *b) * 0.5,
+                a.distance(*b),
+                format!("{} edge {}", label, i + 1),
+            )
+        })
+        .collect()
+}
+
+fn mesh_validity_check(
+    body: &PlacedBody,
+    locate: &impl Fn(Vec3, f32, String) -> Problem,
+) -> CheckResult {
+    let validity = analysis::check_mesh_validity(body);
+    let mut issues = Vec::new();
+    if !validity.is_watertight() {
+        issues.push(format!("{} open edges", validity.open_edges.len()));
+    }
+    if !validity.is_manifold() {
+        issues.push(format!(
+            "{} non-manifold edges",
+            validity.non_manifold_edges.len()
+        ));
+    }
+    if issues.is_empty() {
+        return CheckResult::new("Mesh", CheckStatus::Pass, "Watertight and manifold");
+    }
+    let mut check = CheckResult::new("Mesh", CheckStatus::Fail, issues.join(", "));
+    check.problems = edge_problems(&validity.non_manifold_edges, "Non-manifold", locate);
+    check
+        .problems
+        .extend(edge_problems(&validity.open_edges, "Open", locate));
+    check.problems.truncate(MAX_LISTED_PROBLEMS);
+    check
+}
+
+fn wall_thickness_check(
+    body: &PlacedBody,
+    layout: &PrintLayout,
+    units: &UnitFormat,
+    locate: &impl Fn(Vec3, f32, String) -> Problem,
+) -> CheckResult {
+    let result = analysis::analyze_wall_thickness(body, layout.min_wall_thickness);
+    let minimum = units.format_length(layout.min_wall_thickness);
+    let (Some(thinnest), Some(at)) = (result.thinnest, result.thinnest_at) else {
+        return CheckResult::new(
+            "Wall thickness",
+            CheckStatus::Warning,
+            "No closed walls to measure",
+        );
+    };
+    if result.thin_area <= 0.0 {
+        return CheckResult::new(
+            "Wall thickness",
+            CheckStatus::Pass,
+            format!(
+                "Thinnest wall {} (minimum {})",
+                units.format_length(thinnest),
+                minimum
+            ),
+        );
+    }
+    let mut check = CheckResult::new(
+        "Wall thickness",
+        CheckStatus::Fail,
+        format!(
+            "{} of walls thinner than {}",
+            units.format_area(result.thin_area),
+            minimum
+        ),
+    );
+    check.problems.push(locate(
+        at,
+        layout.min_wall_thickness * 10.0,
+        format!("Thinnest wall: {}", units.format_length(thinnest)),
+    ));
+    check
+}
+
+fn overhang_check(
+    body: &PlacedBody,
+    layout: &PrintLayout,
+    units: &UnitFormat,
+    locate: &impl Fn(Vec3, f32, String) -> Problem,
+) -> CheckResult {
+    let result = analysis::analyze_overhangs(body, layout.overhang_angle_deg);
+    match result.steepest_at {
+        None => CheckResult::new(
+            "Overhangs",
+            CheckStatus::Pass,
+            format!("Nothing steeper than {:.0}°", layout.overhang_angle_deg),
+        ),
+        Some(at) => {
+            let mut check = CheckResult::new(
+                "Overhangs",
+                CheckStatus::Warning,
+                format!("{} needs support", units.format_area(result.overhang_area)),
+            );
+            let radius = result.overhang_area.sqrt().max(1.0);
+            check
+                .problems
+                .push(locate(at, radius, "Steepest overhang".to_string()));
+            check
+        }
+    }
+}
+
+/// Only reported for hollowed bodies: a closed cavity traps uncured resin.
+fn drain_hole_check(body: &PlacedBody, layout: &PrintLayout) -> Option<CheckResult> {
+    let settings = layout.hollow(body.body)?;
+    Some(match settings.drain_holes.len() {
+        0 => CheckResult::new(
+            "Drain holes",
+            CheckStatus::Warning,
+            "Hollow body has no drain holes; resin will be trapped inside",
+        ),
+        1 => CheckResult::new(
+            "Drain holes",
+            CheckStatus::Warning,
+            "Only one drain hole; add a second so air can enter",
+        ),
+        count => CheckResult::new(
+            "Drain holes",
+            CheckStatus::Pass,
+            format!("{} drain holes", count),
+        ),
+    })
+}
+
+fn bed_fit_check(
+    body: &PlacedBody,
+    printer: Option<&PrintVolume>,
+    units: &UnitFormat,
+    locate: &impl Fn(Vec3, f32, String) -> Problem,
+) -> CheckResult {
+    if printer.is_none() {
+        return CheckResult::new(
+            "Bed fit",
+            CheckStatus::Warning,
+            "No printer profile is active",
+        );
+    }
+    let center = (body.min + body.max) * 0.5;
+    let radius = body.size().length() * 0.5;
+    if !body.fits() {
+        let mut check = CheckResult::new(
+            "Bed fit",
+            CheckStatus::Fail,
+            format!(
+                "{} vertices outside the build volume",
+                body.vertices_outside
+            ),
+        );
+        check
+            .problems
+            .push(locate(center, radius, "Body bounds".to_string()));
+        return check;
+    }
+    if body.min.z > BED_CONTACT_TOLERANCE {
+        let mut check = CheckResult::new(
+            "Bed fit",
+            CheckStatus::Warning,
+            format!("Floating {} above the bed", units.format_length(body.min.z)),
+        );
+        check
+            .problems
+            .push(locate(center, radius, "Body bounds".to_string()));
+        return check;
+    }
+    CheckResult::new("Bed fit", CheckStatus::Pass, "Inside the build volume")
+}
@@ -0,0 +1,147 @@
+//! Layer preview: contours of the placed bodies cut by horizontal planes.
+//!
+//! This is not a slicer: it only intersects the meshes with one plane per
+//! layer (at mid-layer height, like slicers do) and chains the cut edges into
+//! contours, which is enough to see how thin features and overhangs turn out.
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use crate::analysis::{self, VertexKey};
+use crate::layout::PlacedBody;
+
+pub const DEFAULT_LAYER_HEIGHT: f32 = 0.2;
+
+/// Layers below the current one drawn by the layer preview (spread evenly).
+const MAX_LOWER_LAYERS: usize = 50;
+
+/// A closed or open polyline where a layer plane cuts a body.
+#[derive(Debug, Clone, Default)]
+pub struct Contour {
+    pub points: Vec<Vec3>,
+    /// False where the mesh has holes and the cut could not be closed.
+    pub closed: bool,
+}
+
+/// Height of the cutting plane of `layer` (0-based).
+pub fn layer_z(layer: usize, layer_height: f32) -> f32 {
+    (layer as f32 + 0.5) * layer_height
+}
+
+/// Contours of `layer` for the layer preview, flagged as current, after those
+/// of a selection of the layers below it when `show_below` is set.
+pub fn slice_layers(
+    placed: &[PlacedBody],
+    layer: usize,
+    layer_height: f32,
+    show_below: bool,
+) -> Vec<(Contour, bool)> {
+    let mut contours = Vec::new();
+    if show_below {
+        let stride = (layer / MAX_LOWER_LAYERS).max(1);
+        for lower in (0..layer).step_by(stride) {
+            let z = layer_z(lower, layer_height);
+            for body in placed {
+                contours.extend(slice_body(body, z).into_iter().map(|c| (c, false)));
+            }
+        }
+    }
+    let z = layer_z(layer, layer_height);
+    for body in placed {
+        contours.extend(slice_body(body, z).into_iter().map(|c| (c, true)));
+    }
+    contours
+}
+
+/// Number of layers needed to print all bodies.
+pub fn layer_count(placed: &[PlacedBody], layer_height: f32) -> usize {
+    let top = placed.iter().map(|body| body.max.z).fold(0.0f32, f32::max);
+    if layer_height <= 0.0 {
+        return 0;
+    }
+    (top / layer_height).ceil() as usize
+}
+
+/// Where the plane at height `z` crosses each triangle of `body`.
+fn cut_segments(body: &PlacedBody, z: f32) -> Vec<[Vec3; 2]> {
+    analysis::faces(body)
+        .iter()
+        .filter_map(|face| {
+            // Vertices on the plane count as above it, so each crossing edge is
+            // found exactly once and a triangle yields zero or two points.
+            let above = face.vertices.map(|v| v.z >= z);
+            let mut points = [(0, 1), (1, 2), (2, 0)].into_iter().filter_map(|(i, j)| {
+                if above[i] == above[j] {
+                    return None;
+                }
+                // Order the edge so neighbouring triangles compute the same point.
+                let (a, b) = (face.vertices[i], face.vertices[j]);
+                let (a, b) = if analysis::weld_key(a) < analysis::weld_key(b) {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                let t = (z - a.z) / (b.z - a.z);
+                Some(a.lerp(b, t).with_z(z))
+            });
+            Some([points.next()?, points.next()?])
+        })
+        .collect()
+}
+
+/// Contours of `body` cut at height `z`.
+pub fn slice_body(body: &PlacedBody, z: f32) -> Vec<Contour> {
+    let segments = cut_segments(body, z);
+    // Segments meeting at each welded endpoint.
+    let mut ends: HashMap<VertexKey, Vec<usize>> = HashMap::new();
+    for (index, segment) in segments.iter().enumerate() {
+        for point in segment {
+            ends.entry(analysis::weld_key(*point))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut contours = Vec::new();
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut points = vec![segments[start][0], segments[start][1]];
+        // Walk forward from the last point, then backward from the first.
+        for backward in [false, true] {
+            loop {
+                let tip = if backward {
+                    points[0]
+                } else {
+                    points[points.len() - 1]
+                };
+                let next = ends
+                    .get(&analysis::weld_key(tip))
+                    .and_then(|candidates| candidates.iter().find(|&&i| !used[i]).copied());
+                let Some(next) = next else {
+                    break;
+                };
+                used[next] = true;
+                let [a, b] = segments[next];
+                let point = if analysis::weld_key(a) == analysis::weld_key(tip) {
+                    b
+                } else {
+                    a
+                };
+                if backward {
+                    points.insert(0, point);
+                } else {
+                    points.push(point);
+                }
+            }
+        }
+        let closed = points.len() > 2
+            && analysis::weld_key(points[0]) == analysis::weld_key(points[points.len() - 1]);
+        contours.push(Contour { points, closed });
+    }
+    contours
+}
@@ -0,0 +1,313 @@
+//! Preview-only support structures under overhanging faces.
+//!
+//! Supports are generated from the placed geometry every time they are shown
+//! and are never stored as document bodies; they only give an idea of how much
+//! support a placement needs before the model goes to a slicer.
+
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::{PI, TAU};
+
+use core_document::bvh::Bvh;
+use glam::{Vec2, Vec3};
+use kernel_api::TriMesh;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{self, Face, FaceClass};
+use crate::layout::{self, BedFrame, PlacedBody};
+
+/// Sides of the prism used to draw a strut.
+const STRUT_SIDES: usize = 8;
+
+/// Contact tips are this fraction of the support diameter.
+const TIP_RATIO: f32 = 0.4;
+
+/// Tree trunks are this multiple of the support diameter.
+const TRUNK_RATIO: f32 = 1.5;
+
+/// Tree supports gather contact points from cells this many spacings wide.
+const TREE_CLUSTER_CELLS: f32 = 3.0;
+
+/// Supports shorter than this (mm) are not generated.
+const MIN_SUPPORT_HEIGHT: f32 = 0.2;
+
+/// Contact points start this far below the face so the downward ray misses it.
+const CONTACT_OFFSET: f32 = 1e-3;
+
+/// Contact points closer than this (mm) in height are merged.
+const CONTACT_MERGE: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SupportStyle {
+    /// One straight pillar per contact point.
+    #[default]
+    Pillars,
+    /// Trunks that branch out to nearby contact points.
+    Tree,
+}
+
+impl SupportStyle {
+    pub const ALL: [SupportStyle; 2] = [SupportStyle::Pillars, SupportStyle::Tree];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SupportStyle::Pillars => "Pillars",
+            SupportStyle::Tree => "Tree",
+        }
+    }
+}
+
+/// Per-document support preview settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SupportSettings {
+    pub style: SupportStyle,
+    /// Distance between contact points (mm).
+    pub spacing: f32,
+    /// Pillar (and branch) diameter (mm).
+    pub diameter: f32,
+}
+
+impl Default for SupportSettings {
+    fn default() -> Self {
+        Self {
+            style: SupportStyle::Pillars,
+            spacing: 3.0,
+            diameter: 1.0,
+        }
+    }
+}
+
+/// A point on an overhang that needs holding up, and what it stands on.
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    /// Point on the overhanging face (printer coordinates).
+    top: Vec3,
+    /// Height of the bed or model surface below `top`.
+    base_z: f32,
+}
+
+/// Generated support geometry for one body, in printer coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct SupportPreview {
+    pub mesh: TriMesh,
+    /// Number of points where supports touch the overhangs.
+    pub contacts: usize,
+    /// Estimated support volume in mm³.
+    pub volume: f32,
+}
+
+impl SupportPreview {
+    /// Support mesh converted to world space for the viewport.
+    pub fn world_mesh(&self, frame: &BedFrame) -> TriMesh {
+        layout::mesh_to_world(&self.mesh, frame)
+    }
+
+    fn strut(&mut self, from: Vec3, to: Vec3, r_from: f32, r_to: f32) {
+        self.volume += strut(&mut self.mesh, from, to, r_from, r_to);
+    }
+
+    /// A straight pillar from the base up to a contact, tapered at the tip
+    /// (and at the foot when it stands on the model).
+    fn pillar(&mut self, contact: &Contact, radius: f32, on_bed: bool) {
+        let height = contact.top.z - contact.base_z;
+        let taper = (radius * 2.0).min(height * 0.5);
+        let foot = contact.top.with_z(contact.base_z);
+        let tip = contact.top;
+        let (r_foot, r_tip) = (
+            if on_bed { radius } else { radius * TIP_RATIO },
+            radius * TIP_RATIO,
+        );
+        let lower = foot + Vec3::Z * if on_bed { 0.0 } else { taper };
+        let upper = tip - Vec3::Z * taper;
+        if !on_bed {
+            self.strut(foot, lower, r_foot, radius);
+        }
+        self.strut(lower, upper, radius, radius);
+        self.strut(upper, tip, radius, r_tip);
+    }
+}
+
+/// Add a tapered strut from `from` (radius `r_from`) to `to` (radius `r_to`) to
+/// `mesh` and return its volume.
+pub(crate) fn strut(mesh: &mut TriMesh, from: Vec3, to: Vec3, r_from: f32, r_to: f32) -> f32 {
+    let axis = to - from;
+    let length = axis.length();
+    if length <= f32::EPSILON {
+        return 0.0;
+    }
+    let (u, v) = (axis / length).any_orthonormal_pair();
+    let base = mesh.positions.len() as u32;
+    for i in 0..STRUT_SIDES {
+        let angle = i as f32 / STRUT_SIDES as f32 * TAU;
+        let radial = u * angle.cos() + v * angle.sin();
+        for (center, radius) in [(from, r_from), (to, r_to)] {
+            mesh.positions.push((center + radial * radius).to_array());
+            mesh.normals.push(radial.to_array());
+        }
+    }
+    let sides = STRUT_SIDES as u32;
+    for i in 0..sides {
+        let (a, b) = (base + i * 2, base + (i + 1) % sides * 2);
+        mesh.indices.extend([a, b, a + 1, b, b + 1, a + 1]);
+    }
+    PI * length / 3.0 * (r_from * r_from + r_from * r_to + r_to * r_to)
+}
+
+/// Build support geometry under the overhanging faces of `body`.
+pub fn generate_supports(
+    body: &PlacedBody,
+    threshold_deg: f32,
+    settings: &SupportSettings,
+) -> SupportPreview {
+    let faces = analysis::faces(body);
+    let bvh = analysis::face_tree(&faces);
+    let contacts = contact_points(&faces, &bvh, body.min.z, threshold_deg, settings.spacing);
+    let radius = settings.diameter * 0.5;
+    let mut preview = SupportPreview {
+        contacts: contacts.len(),
+        ..SupportPreview::default()
+    };
+    let on_bed = |contact: &Contact| contact.base_z <= body.min.z.min(0.0) + CONTACT_MERGE;
+
+    match settings.style {
+        SupportStyle::Pillars => {
+            for contact in &contacts {
+                preview.pillar(contact, radius, on_bed(contact));
+            }
+        }
+        SupportStyle::Tree => {
+            let cell = settings.spacing * TREE_CLUSTER_CELLS;
+            let mut clusters: HashMap<[i32; 2], Vec<Contact>> = HashMap::new();
+            for contact in contacts {
+                let key = [
+                    (contact.top.x / cell).floor() as i32,
+                    (contact.top.y / cell).floor() as i32,
+                ];
+                clusters.entry(key).or_default().push(contact);
+            }
+            for cluster in clusters.values() {
+                tree(&mut preview, &bvh, cluster, radius, body.min.z);
+            }
+        }
+    }
+    preview
+}
+
+/// One tree: a trunk under the middle of the cluster with a 45° branch to each
+/// contact. Falls back to pillars when there is no room for the branches.
+fn tree(preview: &mut SupportPreview, faces: &Bvh, cluster: &[Contact], radius: f32, floor: f32) {
+    let center = cluster
+        .iter()
+        .map(|contact| contact.top.truncate())
+        .sum::<Vec2>()
+        / cluster.len() as f32;
+    let lowest = cluster
+        .iter()
+        .map(|contact| contact.top.z)
+        .fold(f32::INFINITY, f32::min);
+    let reach = cluster
+        .iter()
+        .map(|contact| contact.top.truncate().distance(center))
+        .fold(radius, f32::max);
+    let trunk_top = center.extend(lowest - reach);
+    let base_z = surface_below(faces, trunk_top, None, floor);
+    let on_bed = base_z <= floor.min(0.0) + CONTACT_MERGE;
+    if trunk_top.z - base_z < MIN_SUPPORT_HEIGHT || cluster.len() == 1 {
+        for contact in cluster {
+            let on_bed = contact.base_z <= floor.min(0.0) + CONTACT_MERGE;
+            preview.pillar(contact, radius, on_bed);
+        }
+        return;
+    }
+
+    let trunk_radius = radius * TRUNK_RATIO;
+    let foot = trunk_top.with_z(base_z);
+    if on_bed {
+        preview.strut(foot, trunk_top, trunk_radius, trunk_radius);
+    } else {
+        let taper = (trunk_radius * 2.0).min((trunk_top.z - base_z) * 0.5);
+        let lower = foot + Vec3::Z * taper;
+        preview.strut(foot, lower, radius * TIP_RATIO, trunk_radius);
+        preview.strut(lower, trunk_top, trunk_radius, trunk_radius);
+    }
+    for contact in cluster {
+        preview.strut(trunk_top, contact.top, radius, radius * TIP_RATIO);
+    }
+}
+
+/// Height of the first model face below `point`, or the bed (z = 0, or the
+/// body's lowest point if it floats below the bed).
+fn surface_below(faces: &Bvh, point: Vec3, skip: Option<usize>, floor: f32) -> f32 {
+    let origin = point - Vec3::Z * CONTACT_OFFSET;
+    faces
+        .ray_hit(origin, Vec3::NEG_Z, skip)
+        .map(|t| origin.z - t)
+        .unwrap_or(floor.min(0.0))
+}
+
+/// Contact points on a regular XY grid over every overhanging face.
+fn contact_points(
+    faces: &[Face],
+    bvh: &Bvh,
+    floor: f32,
+    threshold_deg: f32,
+    spacing: f32,
+) -> Vec<Contact> {
+    let spacing = spacing.max(0.1);
+    let mut seen = HashSet::new();
+    let mut contacts = Vec::new();
+    for (index, face) in faces.iter().enumerate() {
+        if analysis::classify_overhang(face, threshold_deg) != FaceClass::Overhang {
+            continue;
+        }
+        let [a, b, c] = face.vertices;
+        let (min, max) = (a.min(b).min(c), a.max(b).max(c));
+        let (i0, i1) = (
+            (min.x / spacing).ceil() as i32,
+            (max.x / spacing).floor() as i32,
+        );
+        let (j0, j1) = (
+            (min.y / spacing).ceil() as i32,
+            (max.y / spacing).floor() as i32,
+        );
+        for i in i0..=i1 {
+            for j in j0..=j1 {
+                let xy = Vec2::new(i as f32, j as f32) * spacing;
+                let Some(z) = height_on_face(face, xy) else {
+                    continue;
+                };
+                let key = (i, j, (z / CONTACT_MERGE).round() as i32);
+                if !seen.insert(key) {
+                    continue;
+                }
+                let top = xy.extend(z);
+                let base_z = surface_below(bvh, top, Some(index), floor);
+                if top.z - base_z >= MIN_SUPPORT_HEIGHT {
+                    contacts.push(Contact { top, base_z });
+                }
+            }
+        }
+    }
+    contacts
+}
+
+/// Height of the face above the XY point, if the point lies inside the face's
+/// projection onto the bed.
+fn height_on_face(face: &Face, xy: Vec2) -> Option<f32> {
+    let [a, b, c] = face.vertices;
+    let (v0, v1, v2) = (
+        b.truncate() - a.truncate(),
+        c.truncate() - a.truncate(),
+        xy - a.truncate(),
+    );
+    let det = v0.perp_dot(v1);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let u = v2.perp_dot(v1) / det;
+    let v = v0.perp_dot(v2) / det;
+    if u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some(a.z + u * (b.z - a.z) + v * (c.z - a.z))
+}
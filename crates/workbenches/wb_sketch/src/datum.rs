@@ -0,0 +1,152 @@
+//! Datum planes: free-standing construction planes that sketches attach to.
+
+use core_document::{
+    Document, DocumentResult, FeatureError, FeatureId, GeometryRef, GizmoMotion, WorkbenchFeature,
+    WorkbenchId,
+};
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::feature::SketchFeature;
+use crate::sketch::SketchPlane;
+
+/// Workbench id the datum plane features are stored under.
+pub const DATUM_WORKBENCH_ID: &str = "wb.datum";
+
+/// A construction plane stored in the document's feature tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatumPlaneFeature {
+    pub name: String,
+    pub plane: SketchPlane,
+}
+
+impl DatumPlaneFeature {
+    pub fn new(name: impl Into<String>, plane: SketchPlane) -> Self {
+        Self {
+            name: name.into(),
+            plane,
+        }
+    }
+}
+
+impl WorkbenchFeature for DatumPlaneFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(DATUM_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("DatumPlaneFeature should always serialize")
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        serde_json::from_value(value.clone()).map_err(|e| {
+            core_document::DocumentError::Feature(FeatureError::Deserialization(e.to_string()))
+        })
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Plane through `origin` facing the camera: the normal points back at the
+/// viewer and the X axis runs to the right of the screen, so a sketch on it is
+/// seen square-on and upright.
+pub(crate) fn view_plane(
+    origin: [f32; 3],
+    camera_position: [f32; 3],
+    camera_target: [f32; 3],
+    view_proj: Option<[[f32; 4]; 4]>,
+) -> Option<SketchPlane> {
+    let normal =
+        (Vec3::from_array(camera_position) - Vec3::from_array(camera_target)).try_normalize()?;
+    // The first row of the view-projection matrix is the screen-right
+    // direction (scaled by the projection); fall back to a world axis when it
+    // is missing or parallel to the view direction.
+    let screen_right = view_proj.map(|m| Mat4::from_cols_array_2d(&m).row(0).truncate());
+    let x_axis = screen_right
+        .and_then(|right| (right - normal * right.dot(normal)).try_normalize())
+        .or_else(|| Vec3::Z.cross(normal).try_normalize())
+        .or_else(|| Vec3::Y.cross(normal).try_normalize())?;
+    let y_axis = normal.cross(x_axis);
+    Some(SketchPlane {
+        origin,
+        normal: normal.to_array(),
+        x_axis: x_axis.to_array(),
+        y_axis: y_axis.to_array(),
+    })
+}
+
+/// `plane` moved by a gizmo motion: translations shift its origin, rotations
+/// turn its axes about the origin. Scaling leaves it unchanged.
+pub(crate) fn moved_plane(plane: &SketchPlane, motion: &GizmoMotion) -> SketchPlane {
+    match *motion {
+        GizmoMotion::Translate(offset) => SketchPlane {
+            origin: (Vec3::from(plane.origin) + Vec3::from(offset)).to_array(),
+            ..*plane
+        },
+        GizmoMotion::Rotate { axis, angle } => {
+            let Some(axis) = Vec3::from(axis).try_normalize() else {
+                return *plane;
+            };
+            let rotation = Quat::from_axis_angle(axis, angle);
+            let turn = |v: [f32; 3]| (rotation * Vec3::from(v)).to_array();
+            SketchPlane {
+                origin: plane.origin,
+                normal: turn(plane.normal),
+                x_axis: turn(plane.x_axis),
+                y_axis: turn(plane.y_axis),
+            }
+        }
+        GizmoMotion::Scale(_) => *plane,
+    }
+}
+
+/// Move sketches on datum planes placed by other workbenches (e.g. Part
+/// Design's offset planes) onto where the datum is now. Their geometry is
+/// stored in plane coordinates, so it comes along.
+pub fn follow_datum_planes(document: &mut Document) {
+    let moved: Vec<(FeatureId, SketchFeature)> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == "wb.sketch")
+        .filter_map(|(id, node)| {
+            let mut sketch = SketchFeature::from_json(&node.data).ok()?;
+            let Some(GeometryRef::Datum(datum)) = sketch.attachment else {
+                return None;
+            };
+            let plane = document.datum_geometry(datum)?.plane()?;
+            // From the datum's body frame to the sketch's.
+            let world = core_document::from_frame(plane.origin, document.feature_position(datum));
+            let plane = SketchPlane {
+                origin: core_document::to_frame(world, document.feature_position(*id)),
+                normal: plane.normal,
+                x_axis: plane.x_axis,
+                y_axis: plane.y_axis,
+            };
+            let current = sketch.plane;
+            let unchanged = [
+                (plane.origin, current.origin),
+                (plane.normal, current.normal),
+                (plane.x_axis, current.x_axis),
+                (plane.y_axis, current.y_axis),
+            ]
+            .iter()
+            .all(|(a, b)| a == b);
+            if unchanged {
+                return None;
+            }
+            sketch.plane = plane;
+            Some((*id, sketch))
+        })
+        .collect();
+    for (id, sketch) in moved {
+        if document.update_feature_data(id, sketch.to_json()).is_ok() {
+            document.mark_feature_dirty(id);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Driving dimensions of the active sketch drawn over the viewport: the
+//! length, distance, radius, diameter, and angle constraints, each with its
+//! value in a label that can be double-clicked to edit it. Other visible
+//! sketches show theirs faded, unless turned off per sketch.
+
+use core_document::{
+    FeatureId, ScreenSpaceLabel, ScreenSpaceOverlay, UnitFormat, WorkbenchFeature,
+    WorkbenchRuntimeContext,
+};
+use glam::Vec2;
+use uuid::Uuid;
+
+use crate::point_coords;
+use crate::sketch::{Constraint, GeometryElement, Sketch, SketchPlane, Vec2D};
+use crate::SketchFeature;
+
+const COLOR: [f32; 3] = [1.0, 0.6, 0.2];
+const THICKNESS: f32 = 1.5;
+/// Color and thickness of the dimensions of sketches not being edited.
+const FADED_COLOR: [f32; 3] = [0.6, 0.45, 0.3];
+const FADED_THICKNESS: f32 = 1.0;
+/// How far (pixels) length dimensions sit off the measured geometry.
+const OFFSET: f32 = 24.0;
+/// Gap between the geometry and the start of an extension line (pixels).
+const EXTENSION_GAP: f32 = 4.0;
+/// Radius (pixels) of the arc marking an angle.
+const ANGLE_RADIUS: f32 = 40.0;
+/// Approximate size of a label (pixels), for centering and picking: the
+/// host draws 13 pt text with a few pixels of padding.
+const LABEL_CHAR_WIDTH: f32 = 8.0;
+const LABEL_HEIGHT: f32 = 22.0;
+
+/// A dimension projected to the viewport.
+pub(crate) struct DimensionView {
+    /// Index of the constraint in the sketch.
+    pub constraint: usize,
+    pub lines: Vec<ScreenSpaceOverlay>,
+    pub label: ScreenSpaceLabel,
+}
+
+impl DimensionView {
+    /// Whether `pos` (viewport pixels) is over the label.
+    pub fn label_contains(&self, pos: (f32, f32)) -> bool {
+        let [x, y] = self.label.position;
+        let width = label_width(&self.label.text);
+        (x..=x + width).contains(&pos.0) && (y - LABEL_HEIGHT..=y).contains(&pos.1)
+    }
+}
+
+/// Dimensions of `sketch` on `plane` that are in front of the camera.
+/// `editing` is the constraint whose label is highlighted.
+pub(crate) fn dimension_views(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    editing: Option<usize>,
+) -> Vec<DimensionView> {
+    let mut views = Vec::new();
+    for (index, constraint) in sketch.constraints.iter().enumerate() {
+        let Some((lines, center, text)) = dimension(sketch, plane, ctx, constraint) else {
+            continue;
+        };
+        let highlighted = editing == Some(index);
+        views.push(DimensionView {
+            constraint: index,
+            lines: lines
+                .into_iter()
+                .map(|(a, b)| ScreenSpaceOverlay::new(a.to_array(), b.to_array(), COLOR, THICKNESS))
+                .collect(),
+            label: ScreenSpaceLabel {
+                position: [
+                    center.x - label_width(&text) / 2.0,
+                    center.y + LABEL_HEIGHT / 2.0,
+                ],
+                text,
+                color: COLOR,
+                highlighted,
+            },
+        });
+    }
+    views
+}
+
+/// Faded dimensions of the visible sketches that show them outside sketch
+/// mode, restricted to the sketches `shown` accepts (e.g. all but the one
+/// being edited, which draws its own).
+pub fn sketch_dimensions(
+    ctx: &WorkbenchRuntimeContext,
+    shown: impl Fn(FeatureId) -> bool,
+) -> (Vec<ScreenSpaceOverlay>, Vec<ScreenSpaceLabel>) {
+    let mut lines = Vec::new();
+    let mut labels = Vec::new();
+    for (id, node) in ctx.document.feature_tree().all_nodes() {
+        if node.workbench_id.as_str() != "wb.sketch"
+            || !node.visible
+            || node.suppressed
+            || !shown(*id)
+        {
+            continue;
+        }
+        let Ok(feature) = SketchFeature::from_json(&node.data) else {
+            continue;
+        };
+        if !feature.show_dimensions {
+            continue;
+        }
+        // Sketches of other bodies are drawn at their body's position.
+        let offset = ctx.body_offset(node.body);
+        let plane = SketchPlane {
+            origin: [0, 1, 2].map(|i| feature.plane.origin[i] + offset[i]),
+            ..feature.plane
+        };
+        for view in dimension_views(&feature.sketch, &plane, ctx, None) {
+            lines.extend(view.lines.into_iter().map(|line| {
+                ScreenSpaceOverlay::new(line.start, line.end, FADED_COLOR, FADED_THICKNESS)
+            }));
+            labels.push(ScreenSpaceLabel {
+                color: FADED_COLOR,
+                ..view.label
+            });
+        }
+    }
+    (lines, labels)
+}
+
+type Segments = Vec<(Vec2, Vec2)>;
+
+/// Lines, label center, and text of a dimensional constraint.
+fn dimension(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    constraint: &Constraint,
+) -> Option<(Segments, Vec2, String)> {
+    let project = |id: Uuid| to_viewport(plane, ctx, point_coords(sketch, id)?.to_glam());
+    match constraint {
+        Constraint::Length { line, length } => {
+            let (a, b) = line_ends(sketch, *line)?;
+            linear(project(a)?, project(b)?, *length, &ctx.units)
+        }
+        Constraint::Distance {
+            point1,
+            point2,
+            distance,
+        } => linear(project(*point1)?, project(*point2)?, *distance, &ctx.units),
+        Constraint::Radius { circle, radius } => {
+            let (center, rim) = circle_dimension(sketch, plane, ctx, *circle)?;
+            let direction = (rim - center).normalize_or_zero();
+            Some((
+                vec![(center, rim)],
+                rim + direction * LABEL_HEIGHT,
+                format!("R {}", ctx.units.format_length(*radius)),
+            ))
+        }
+        Constraint::Diameter { circle, diameter } => {
+            let (center, rim) = circle_dimension(sketch, plane, ctx, *circle)?;
+            let direction = (rim - center).normalize_or_zero();
+            Some((
+                vec![(2.0 * center - rim, rim)],
+                rim + direction * LABEL_HEIGHT,
+                format!("Ø {}", ctx.units.format_length(*diameter)),
+            ))
+        }
+        Constraint::Angle {
+            line1,
+            line2,
+            angle_rad,
+        } => angle(sketch, plane, ctx, *line1, *line2, *angle_rad),
+        _ => None,
+    }
+}
+
+/// Dimension line parallel to `a`–`b`, with extension lines back to it.
+fn linear(a: Vec2, b: Vec2, value: f32, units: &UnitFormat) -> Option<(Segments, Vec2, String)> {
+    let direction = (b - a).try_normalize()?;
+    // Keep the dimension above (on screen) horizontal geometry.
+    let mut normal = direction.perp();
+    if normal.y > 0.0 {
+        normal = -normal;
+    }
+    let (start, end) = (a + normal * OFFSET, b + normal * OFFSET);
+    let segments = vec![
+        (start, end),
+        (a + normal * EXTENSION_GAP, start + normal * EXTENSION_GAP),
+        (b + normal * EXTENSION_GAP, end + normal * EXTENSION_GAP),
+    ];
+    let center = (start + end) / 2.0 + normal * (LABEL_HEIGHT / 2.0);
+    Some((segments, center, units.format_length(value)))
+}
+
+/// Center of a circle/arc and a point on its rim, up and to the right.
+fn circle_dimension(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    id: Uuid,
+) -> Option<(Vec2, Vec2)> {
+    let (center, radius) = match sketch.get_geometry(id)? {
+        GeometryElement::Circle(circle) => (circle.center, circle.radius),
+        GeometryElement::Arc(arc) => (arc.center, arc.radius),
+        _ => return None,
+    };
+    let center = point_coords(sketch, center)?.to_glam();
+    let rim = center + Vec2::splat(std::f32::consts::FRAC_1_SQRT_2 * radius);
+    Some((
+        to_viewport(plane, ctx, center)?,
+        to_viewport(plane, ctx, rim)?,
+    ))
+}
+
+/// Arc between two lines around the point where they meet (or the start of
+/// the first line, if they are parallel).
+fn angle(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    line1: Uuid,
+    line2: Uuid,
+    angle_rad: f32,
+) -> Option<(Segments, Vec2, String)> {
+    let coords = |id: Uuid| point_coords(sketch, id).map(|p| p.to_glam());
+    let (a1, b1) = line_ends(sketch, line1)?;
+    let (a2, b2) = line_ends(sketch, line2)?;
+    let (a1, b1, a2, b2) = (coords(a1)?, coords(b1)?, coords(a2)?, coords(b2)?);
+    let (d1, d2) = (b1 - a1, b2 - a2);
+    let cross = d1.perp_dot(d2);
+    let vertex = if cross.abs() > 1e-6 {
+        a1 + d1 * ((a2 - a1).perp_dot(d2) / cross)
+    } else {
+        a1
+    };
+    // Each arm points from the vertex towards the middle of its line.
+    let arm = |a: Vec2, b: Vec2, d: Vec2| {
+        let toward = (a + b) / 2.0 - vertex;
+        if toward.length_squared() > 1e-12 {
+            toward
+        } else {
+            d
+        }
+    };
+    let to_screen = |p: Vec2| to_viewport(plane, ctx, p);
+    let origin = to_screen(vertex)?;
+    let first = (to_screen(vertex + arm(a1, b1, d1))? - origin).try_normalize()?;
+    let second = (to_screen(vertex + arm(a2, b2, d2))? - origin).try_normalize()?;
+
+    let sweep = first.angle_to(second);
+    const SEGMENTS: usize = 16;
+    let point = |t: f32| origin + Vec2::from_angle(sweep * t).rotate(first) * ANGLE_RADIUS;
+    let segments = (0..SEGMENTS)
+        .map(|i| {
+            (
+                point(i as f32 / SEGMENTS as f32),
+                point((i + 1) as f32 / SEGMENTS as f32),
+            )
+        })
+        .collect();
+    let bisector = Vec2::from_angle(sweep / 2.0).rotate(first);
+    let center = origin + bisector * (ANGLE_RADIUS + LABEL_HEIGHT);
+    Some((segments, center, format!("{:.1}°", angle_rad.to_degrees())))
+}
+
+/// Viewport position of the sketch point `p`.
+fn to_viewport(plane: &SketchPlane, ctx: &WorkbenchRuntimeContext, p: Vec2) -> Option<Vec2> {
+    let world = plane.sketch_to_world(Vec2D::from_glam(p));
+    ctx.world_to_viewport(world).map(|(x, y)| Vec2::new(x, y))
+}
+
+fn line_ends(sketch: &Sketch, id: Uuid) -> Option<(Uuid, Uuid)> {
+    match sketch.get_geometry(id)? {
+        GeometryElement::Line(line) => Some((line.start, line.end)),
+        _ => None,
+    }
+}
+
+fn label_width(text: &str) -> f32 {
+    text.chars().count() as f32 * LABEL_CHAR_WIDTH + 10.0
+}
@@ -0,0 +1,182 @@
+//! Sketches on planar body faces.
+//!
+//! Bodies are triangle meshes without named faces, so a face is the set of
+//! triangles lying in one plane. A sketch keeps the plane of the face it was
+//! created on and is moved along the normal when the face moves (a pad made
+//! taller, a body translated); a face that turned is not found again.
+
+use core_document::{BodyMesh, Document, FeatureId, GeometryRef, WorkbenchFeature};
+use glam::Vec3;
+use kernel_api::TriMesh;
+
+use crate::feature::SketchFeature;
+use crate::sketch::SketchPlane;
+
+/// Largest angle between the normals of triangles of one face (as
+/// `1 - cos`), about 0.8°.
+const NORMAL_TOLERANCE: f32 = 1e-4;
+
+/// Largest distance of a face's corners from its plane, in model units.
+const PLANE_TOLERANCE: f32 = 1e-3;
+
+/// Triangles of `mesh` as corner positions.
+fn triangles(mesh: &TriMesh) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+    let corner = move |i: u32| Vec3::from(mesh.positions[i as usize]);
+    let indexed = mesh
+        .indices
+        .chunks_exact(3)
+        .map(move |t| [corner(t[0]), corner(t[1]), corner(t[2])]);
+    let listed = mesh
+        .positions
+        .chunks_exact(3)
+        .filter(|_| mesh.indices.is_empty())
+        .map(|t| [t[0], t[1], t[2]].map(Vec3::from));
+    indexed.chain(listed)
+}
+
+/// Unit normal of the triangle `abc` (None when it has no area).
+fn unit_normal([a, b, c]: [Vec3; 3]) -> Option<Vec3> {
+    (b - a).cross(c - a).try_normalize()
+}
+
+/// Offset along `normal` of the plane through the triangle, when the
+/// triangle lies in a plane facing along `normal`.
+fn plane_offset(triangle: [Vec3; 3], normal: Vec3) -> Option<f32> {
+    let own = unit_normal(triangle)?;
+    if own.dot(normal) < 1.0 - NORMAL_TOLERANCE {
+        return None;
+    }
+    let offset = triangle[0].dot(normal);
+    triangle[1..]
+        .iter()
+        .all(|corner| (corner.dot(normal) - offset).abs() < PLANE_TOLERANCE)
+        .then_some(offset)
+}
+
+/// Sketch plane on the planar face of `mesh` at `point`: the triangle
+/// nearest to the point (of those touching it, the one facing most along the
+/// picked `normal`) together with every triangle in its plane. The origin is
+/// their area-weighted center and the X axis is world X laid onto the plane
+/// (world Y when the face is nearly square to X).
+pub(crate) fn face_plane(
+    mesh: &TriMesh,
+    point: [f32; 3],
+    normal: Option<[f32; 3]>,
+) -> Option<SketchPlane> {
+    let point = Vec3::from(point);
+    let picked = normal.map(Vec3::from).unwrap_or_default();
+    let candidates: Vec<(f32, Vec3, Vec3)> = triangles(mesh)
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle;
+            let distance = distance_to_triangle(point, a, b, c);
+            Some((distance, unit_normal(triangle)?, a))
+        })
+        .collect();
+    let nearest = candidates
+        .iter()
+        .map(|(distance, ..)| *distance)
+        .fold(f32::INFINITY, f32::min);
+    let &(_, normal, corner) = candidates
+        .iter()
+        .filter(|(distance, ..)| *distance <= nearest + PLANE_TOLERANCE)
+        .max_by(|(_, a, _), (_, b, _)| a.dot(picked).total_cmp(&b.dot(picked)))?;
+
+    let offset = corner.dot(normal);
+    let mut area = 0.0;
+    let mut center = Vec3::ZERO;
+    for triangle in triangles(mesh) {
+        if plane_offset(triangle, normal).is_some_and(|o| (o - offset).abs() < PLANE_TOLERANCE) {
+            let [a, b, c] = triangle;
+            let weight = (b - a).cross(c - a).length() / 2.0;
+            area += weight;
+            center += (a + b + c) / 3.0 * weight;
+        }
+    }
+    if area <= 0.0 {
+        return None;
+    }
+    let origin = center / area;
+    let along = if normal.x.abs() < 0.9 {
+        Vec3::X
+    } else {
+        Vec3::Y
+    };
+    let x_axis = (along - normal * normal.dot(along)).try_normalize()?;
+    Some(SketchPlane {
+        origin: origin.to_array(),
+        normal: normal.to_array(),
+        x_axis: x_axis.to_array(),
+        y_axis: normal.cross(x_axis).to_array(),
+    })
+}
+
+/// Move sketches placed on a body face onto the face's current position. The
+/// face is the plane facing the same way as the sketch nearest to it in the
+/// mesh of the feature that made it (`feature_mesh`), or of the whole body
+/// for mesh bodies. Sketches whose face is gone stay where they are.
+pub fn follow_body_faces<'a>(
+    document: &mut Document,
+    feature_mesh: impl Fn(FeatureId) -> Option<&'a TriMesh>,
+    body_meshes: &[BodyMesh],
+) {
+    let attached: Vec<(FeatureId, SketchFeature)> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == "wb.sketch")
+        .filter_map(|(id, node)| Some((*id, SketchFeature::from_json(&node.data).ok()?)))
+        .filter(|(_, sketch)| matches!(sketch.attachment, Some(GeometryRef::Face { .. })))
+        .collect();
+    for (id, mut sketch) in attached {
+        let Some(GeometryRef::Face { body, feature }) = sketch.attachment else {
+            continue;
+        };
+        let mesh = match feature {
+            Some(feature) => feature_mesh(feature),
+            None => body_meshes.iter().find(|m| m.body == body).map(|m| &m.mesh),
+        };
+        let Some(mesh) = mesh else {
+            continue;
+        };
+        let Some(normal) = Vec3::from(sketch.plane.normal).try_normalize() else {
+            continue;
+        };
+        let current = Vec3::from(sketch.plane.origin).dot(normal);
+        let nearest = triangles(mesh)
+            .filter_map(|triangle| plane_offset(triangle, normal))
+            .min_by(|a, b| (a - current).abs().total_cmp(&(b - current).abs()));
+        let Some(offset) = nearest.filter(|offset| (offset - current).abs() >= PLANE_TOLERANCE)
+        else {
+            continue;
+        };
+        let origin = Vec3::from(sketch.plane.origin) + normal * (offset - current);
+        sketch.plane.origin = origin.to_array();
+        if document.update_feature_data(id, sketch.to_json()).is_ok() {
+            document.mark_feature_dirty(id);
+        }
+    }
+}
+
+/// Distance from `p` to the triangle `abc`.
+fn distance_to_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    let normal = (b - a).cross(c - a);
+    let Some(unit) = normal.try_normalize() else {
+        return f32::INFINITY;
+    };
+    // Inside the triangle's prism the nearest point is on the plane,
+    // otherwise it lies on one of the edges.
+    let projected = p - unit * (p - a).dot(unit);
+    let inside = [(a, b), (b, c), (c, a)]
+        .iter()
+        .all(|&(from, to)| (to - from).cross(projected - from).dot(normal) >= 0.0);
+    if inside {
+        return (p - projected).length();
+    }
+    [(a, b), (b, c), (c, a)]
+        .iter()
+        .map(|&(from, to)| {
+            let edge = to - from;
+            let t = ((p - from).dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+            (p - (from + edge * t)).length()
+        })
+        .fold(f32::INFINITY, f32::min)
+}
@@ -1,10 +1,13 @@
 //! Sketch feature implementation for the document feature tree.
 
-use core_document::{DocumentResult, FeatureError, FeatureId, WorkbenchFeature, WorkbenchId};
+use core_document::{
+    DocumentResult, ElementKind, FeatureError, FeatureId, GeometryRef, NamedTopology, TopoName,
+    WorkbenchFeature, WorkbenchId,
+};
 use serde::{Deserialize, Serialize};
-use serde_json;
 
-use crate::sketch::{Sketch, SketchPlane};
+use crate::profile;
+use crate::sketch::{GeometryElement, Sketch, SketchPlane};
 
 /// A sketch feature that can be stored in the document's feature tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,18 +16,96 @@ pub struct SketchFeature {
     pub sketch: Sketch,
     /// The reference plane for the sketch.
     pub plane: SketchPlane,
+    /// Geometry the sketch is placed on (None for a free-standing plane).
+    #[serde(default)]
+    pub attachment: Option<GeometryRef>,
+    /// Whether the sketch's driving dimensions stay in the viewport (faded)
+    /// while it isn't being edited.
+    #[serde(default = "show_dimensions_default")]
+    pub show_dimensions: bool,
+}
+
+fn show_dimensions_default() -> bool {
+    true
 }
 
 impl SketchFeature {
     pub fn new(sketch: Sketch, plane: SketchPlane) -> Self {
-        Self { sketch, plane }
+        Self {
+            sketch,
+            plane,
+            attachment: None,
+            show_dimensions: true,
+        }
     }
 
     pub fn from_sketch(sketch: Sketch) -> Self {
-        Self {
-            sketch,
-            plane: SketchPlane::default(),
+        Self::new(sketch, SketchPlane::default())
+    }
+
+    /// Place the sketch on `attachment`.
+    pub fn attached_to(mut self, attachment: GeometryRef) -> Self {
+        self.attachment = Some(attachment);
+        self
+    }
+
+    /// Persistent names of the sketch's curves (edges) and points (vertices),
+    /// tagged with the ids of the geometry elements so they survive adding or
+    /// removing other geometry. `id` is the sketch's feature id.
+    pub fn topology(&self, id: FeatureId) -> NamedTopology {
+        let mut topology = NamedTopology::default();
+        for element in &self.sketch.geometry {
+            let tag = element.id().to_string();
+            match element {
+                GeometryElement::Point(_) => {
+                    topology
+                        .vertices
+                        .push(TopoName::generated(id, ElementKind::Vertex, tag))
+                }
+                GeometryElement::Line(_) | GeometryElement::Arc(_) | GeometryElement::Circle(_) => {
+                    topology
+                        .edges
+                        .push(TopoName::generated(id, ElementKind::Edge, tag))
+                }
+            }
         }
+        topology
+    }
+
+    /// World-space end points of the sketch's lines, each with its edge name
+    /// (as in [`SketchFeature::topology`]). `id` is the sketch's feature id.
+    pub fn line_edges(&self, id: FeatureId) -> Vec<(TopoName, [f32; 3], [f32; 3])> {
+        let position = |point| match self.sketch.get_geometry(point) {
+            Some(GeometryElement::Point(point)) => Some(self.plane.sketch_to_world(point.position)),
+            _ => None,
+        };
+        self.sketch
+            .geometry
+            .iter()
+            .filter_map(|element| match element {
+                GeometryElement::Line(line) => Some((
+                    TopoName::generated(id, ElementKind::Edge, line.id.to_string()),
+                    position(line.start)?,
+                    position(line.end)?,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// World-space closed loops of the sketch's curves (circles and chains of
+    /// lines and arcs meeting end to end), for sweeping into solids. The last
+    /// point of a loop joins the first.
+    pub fn profile_loops(&self) -> Vec<Vec<[f32; 3]>> {
+        profile::closed_loops(&self.sketch)
+            .into_iter()
+            .map(|points| {
+                points
+                    .into_iter()
+                    .map(|point| self.plane.sketch_to_world(point))
+                    .collect()
+            })
+            .collect()
     }
 }
 
@@ -44,8 +125,11 @@ impl WorkbenchFeature for SketchFeature {
     }
 
     fn dependencies(&self) -> Vec<FeatureId> {
-        // Sketches have no dependencies (they are root features)
-        Vec::new()
+        // Sketches only depend on the feature whose geometry they are placed on.
+        self.attachment
+            .as_ref()
+            .map(GeometryRef::features)
+            .unwrap_or_default()
     }
 
     fn name(&self) -> &str {
@@ -0,0 +1,250 @@
+//! Glyphs marking the geometric constraints of the active sketch (coincident,
+//! parallel, perpendicular, tangent, ...) next to the elements they constrain,
+//! drawn over the viewport. Clicking one with the select tool selects its
+//! constraint, which Delete removes.
+
+use std::collections::HashMap;
+
+use core_document::{ScreenSpaceOverlay, WorkbenchRuntimeContext};
+use glam::Vec2;
+use uuid::Uuid;
+
+use crate::point_coords;
+use crate::sketch::{Constraint, GeometryElement, Sketch, SketchPlane, Vec2D};
+
+const COLOR: [f32; 3] = [0.55, 0.75, 0.95];
+const HOVER_COLOR: [f32; 3] = [1.0, 0.9, 0.3];
+const SELECTED_COLOR: [f32; 3] = [0.3, 0.7, 1.0];
+const THICKNESS: f32 = 1.5;
+const SELECTED_THICKNESS: f32 = 2.5;
+/// Side of a glyph's frame (pixels).
+const SIZE: f32 = 16.0;
+/// Space between glyphs stacked on the same element (pixels).
+const GAP: f32 = 3.0;
+/// Distance from the element to the center of its first glyph (pixels).
+const OFFSET: f32 = 16.0;
+/// Segments of the circles drawn in glyphs.
+const CIRCLE_SEGMENTS: usize = 12;
+
+/// The symbol drawn for a kind of constraint.
+#[derive(Clone, Copy)]
+enum Symbol {
+    Fixed,
+    Coincident,
+    Parallel,
+    Perpendicular,
+    Equal,
+    OnCurve,
+    Horizontal,
+    Vertical,
+    Tangent,
+}
+
+/// A constraint glyph placed in the viewport.
+pub(crate) struct GlyphView {
+    /// Index of the constraint in the sketch.
+    pub constraint: usize,
+    center: Vec2,
+    symbol: Symbol,
+}
+
+impl GlyphView {
+    /// Whether `pos` (viewport pixels) is over the glyph.
+    fn contains(&self, pos: Vec2) -> bool {
+        let half = SIZE / 2.0;
+        (pos - self.center).abs().cmple(Vec2::splat(half)).all()
+    }
+}
+
+/// Glyphs of the geometric constraints of `sketch` on `plane`: one next to
+/// each element a constraint ties (only the point, for coincident points and
+/// points on curves), stacked away from the element when it has several.
+/// Dimensional constraints are shown as dimensions instead.
+pub(crate) fn glyph_views(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+) -> Vec<GlyphView> {
+    let mut stacked: HashMap<Uuid, usize> = HashMap::new();
+    let mut views = Vec::new();
+    for (index, constraint) in sketch.constraints.iter().enumerate() {
+        let Some(symbol) = symbol(constraint) else {
+            continue;
+        };
+        let elements = match *constraint {
+            Constraint::Coincident { point1, .. } => vec![point1],
+            Constraint::PointOnLine { point, .. } | Constraint::PointOnCircle { point, .. } => {
+                vec![point]
+            }
+            _ => constraint.elements(),
+        };
+        for element in elements {
+            let Some((anchor, direction)) = anchor(sketch, plane, ctx, element) else {
+                continue;
+            };
+            let slot = stacked.entry(element).or_default();
+            let center = anchor + direction * (OFFSET + *slot as f32 * (SIZE + GAP));
+            *slot += 1;
+            views.push(GlyphView {
+                constraint: index,
+                center,
+                symbol,
+            });
+        }
+    }
+    views
+}
+
+/// Constraint of the glyph under `pos` (viewport pixels).
+pub(crate) fn pick(views: &[GlyphView], pos: Vec2) -> Option<usize> {
+    views
+        .iter()
+        .find(|view| view.contains(pos))
+        .map(|view| view.constraint)
+}
+
+/// Frames and symbols of the glyphs; those of the `hovered` and `selected`
+/// constraints are highlighted.
+pub(crate) fn overlays(
+    views: &[GlyphView],
+    hovered: Option<usize>,
+    selected: Option<usize>,
+) -> Vec<ScreenSpaceOverlay> {
+    let mut lines = Vec::new();
+    for view in views {
+        let (color, thickness) = if selected == Some(view.constraint) {
+            (SELECTED_COLOR, SELECTED_THICKNESS)
+        } else if hovered == Some(view.constraint) {
+            (HOVER_COLOR, THICKNESS)
+        } else {
+            (COLOR, THICKNESS)
+        };
+        let half = SIZE / 2.0;
+        // Symbols are drawn in a -1..1 square (y down) inside the frame.
+        let scale = half - 4.0;
+        let to_screen = |(x, y): (f32, f32)| (view.center + Vec2::new(x, y) * scale).to_array();
+        let frame = [
+            (-1.0, -1.0),
+            (1.0, -1.0),
+            (1.0, 1.0),
+            (-1.0, 1.0),
+            (-1.0, -1.0),
+        ];
+        lines.extend(frame.windows(2).map(|w| {
+            let corner = |(x, y): (f32, f32)| (view.center + Vec2::new(x, y) * half).to_array();
+            ScreenSpaceOverlay::new(corner(w[0]), corner(w[1]), color, 1.0)
+        }));
+        lines.extend(
+            strokes(view.symbol).into_iter().map(|(a, b)| {
+                ScreenSpaceOverlay::new(to_screen(a), to_screen(b), color, thickness)
+            }),
+        );
+    }
+    lines
+}
+
+fn symbol(constraint: &Constraint) -> Option<Symbol> {
+    Some(match constraint {
+        Constraint::FixedPoint { .. } => Symbol::Fixed,
+        Constraint::Coincident { .. } => Symbol::Coincident,
+        Constraint::Parallel { .. } => Symbol::Parallel,
+        Constraint::Perpendicular { .. } => Symbol::Perpendicular,
+        Constraint::EqualLength { .. } | Constraint::EqualRadius { .. } => Symbol::Equal,
+        Constraint::PointOnLine { .. } | Constraint::PointOnCircle { .. } => Symbol::OnCurve,
+        Constraint::Horizontal { .. } => Symbol::Horizontal,
+        Constraint::Vertical { .. } => Symbol::Vertical,
+        Constraint::Tangent { .. } => Symbol::Tangent,
+        Constraint::Length { .. }
+        | Constraint::Radius { .. }
+        | Constraint::Diameter { .. }
+        | Constraint::Distance { .. }
+        | Constraint::Angle { .. } => return None,
+    })
+}
+
+type Stroke = ((f32, f32), (f32, f32));
+
+/// Line segments drawing `symbol` in a -1..1 square, y pointing down.
+fn strokes(symbol: Symbol) -> Vec<Stroke> {
+    match symbol {
+        // A ground mark.
+        Symbol::Fixed => vec![
+            ((0.0, -1.0), (0.0, 0.2)),
+            ((-1.0, 0.2), (1.0, 0.2)),
+            ((-0.6, 0.2), (-1.0, 1.0)),
+            ((0.0, 0.2), (-0.4, 1.0)),
+            ((0.6, 0.2), (0.2, 1.0)),
+        ],
+        Symbol::Coincident => circle((0.0, 0.0), 0.6),
+        Symbol::Parallel => vec![((-0.7, 1.0), (0.0, -1.0)), ((0.0, 1.0), (0.7, -1.0))],
+        Symbol::Perpendicular => vec![((0.0, -1.0), (0.0, 1.0)), ((-1.0, 1.0), (1.0, 1.0))],
+        Symbol::Equal => vec![((-1.0, -0.4), (1.0, -0.4)), ((-1.0, 0.4), (1.0, 0.4))],
+        Symbol::OnCurve => {
+            let mut strokes = circle((0.0, 0.0), 0.4);
+            strokes.push(((-1.0, 1.0), (1.0, -1.0)));
+            strokes
+        }
+        Symbol::Horizontal => vec![((-1.0, 0.0), (1.0, 0.0))],
+        Symbol::Vertical => vec![((0.0, -1.0), (0.0, 1.0))],
+        Symbol::Tangent => {
+            let mut strokes = circle((0.0, -0.3), 0.6);
+            strokes.push(((-1.0, 0.3), (1.0, 0.3)));
+            strokes
+        }
+    }
+}
+
+fn circle((x, y): (f32, f32), radius: f32) -> Vec<Stroke> {
+    let point = |i: usize| {
+        let angle = std::f32::consts::TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+        (x + radius * angle.cos(), y + radius * angle.sin())
+    };
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| (point(i), point(i + 1)))
+        .collect()
+}
+
+/// Where the glyphs of an element start (viewport pixels) and the screen
+/// direction they stack in: up and to the right of points, below the middle
+/// of lines (dimensions go above), and out from the lower left of circles
+/// and arcs.
+fn anchor(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    id: Uuid,
+) -> Option<(Vec2, Vec2)> {
+    let project = |p: Vec2| {
+        let world = plane.sketch_to_world(Vec2D::from_glam(p));
+        ctx.world_to_viewport(world).map(|(x, y)| Vec2::new(x, y))
+    };
+    let coords = |id: Uuid| point_coords(sketch, id).map(Vec2D::to_glam);
+    match sketch.get_geometry(id)? {
+        GeometryElement::Point(point) => Some((
+            project(point.position.to_glam())?,
+            Vec2::new(1.0, -1.0).normalize(),
+        )),
+        GeometryElement::Line(line) => {
+            let (a, b) = (project(coords(line.start)?)?, project(coords(line.end)?)?);
+            let mut normal = (b - a).try_normalize()?.perp();
+            if normal.y < 0.0 {
+                normal = -normal;
+            }
+            Some(((a + b) / 2.0, normal))
+        }
+        GeometryElement::Circle(circle) => {
+            curve_anchor(project, coords(circle.center)?, circle.radius)
+        }
+        GeometryElement::Arc(arc) => curve_anchor(project, coords(arc.center)?, arc.radius),
+    }
+}
+
+fn curve_anchor(
+    project: impl Fn(Vec2) -> Option<Vec2>,
+    center: Vec2,
+    radius: f32,
+) -> Option<(Vec2, Vec2)> {
+    let rim = center - Vec2::splat(std::f32::consts::FRAC_1_SQRT_2 * radius);
+    let (center, rim) = (project(center)?, project(rim)?);
+    Some((rim, (rim - center).try_normalize()?))
+}
@@ -0,0 +1,158 @@
+//! The sketch grid drawn over the viewport while a sketch is edited: lines
+//! every grid spacing on the sketch plane, with emphasized major lines every
+//! few cells. When lines get too dense to tell apart, the grid steps up to
+//! the next coarser one (major lines become minor ones), so it stays
+//! readable at every zoom level.
+
+use core_document::{ScreenSpaceOverlay, WorkbenchRuntimeContext};
+use glam::Vec2;
+
+use crate::sketch::{SketchPlane, Vec2D};
+
+const MINOR_COLOR: [f32; 3] = [0.28, 0.28, 0.31];
+const MAJOR_COLOR: [f32; 3] = [0.4, 0.4, 0.45];
+const THICKNESS: f32 = 1.0;
+/// Closest (pixels) two lines of one kind may be drawn.
+const MIN_GAP: f32 = 8.0;
+/// Coarser grids tried before giving up on drawing one.
+const MAX_COARSENING: usize = 8;
+/// Most lines of one kind drawn in each direction.
+const MAX_LINES: i64 = 400;
+/// Viewport positions sampled per side to find the visible part of the plane.
+const SAMPLES: usize = 5;
+/// Pieces each line is drawn in, so parts behind the camera can be dropped.
+const PIECES: usize = 8;
+
+/// Grid lines of `plane` in view, if the host asks for the grid.
+pub(crate) fn grid_overlays(
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+) -> Vec<ScreenSpaceOverlay> {
+    let spacing = ctx.snap.grid_spacing;
+    if !ctx.snap.show_grid || spacing <= 0.0 {
+        return Vec::new();
+    }
+    let Some((min, max)) = visible_bounds(plane, ctx) else {
+        return Vec::new();
+    };
+    // Looking along the plane, the far side of the bounds can be very far
+    // away; lines are kept around what is under the middle of the viewport.
+    let (width, height) = (ctx.viewport.2 as f32, ctx.viewport.3 as f32);
+    let center = ctx
+        .viewport_to_plane((width / 2.0, height / 2.0), plane.origin, plane.normal)
+        .map(|world| plane.world_to_sketch(world).to_glam())
+        .unwrap_or((min + max) / 2.0);
+
+    let subdivisions = ctx.snap.grid_subdivisions;
+    let coarsening = if subdivisions > 1 {
+        subdivisions as f32
+    } else {
+        10.0
+    };
+    let mut step = spacing;
+    let mut readable = false;
+    for _ in 0..MAX_COARSENING {
+        match pixel_gap(plane, ctx, center, step) {
+            Some(gap) if gap >= MIN_GAP => {
+                readable = true;
+                break;
+            }
+            Some(_) => step *= coarsening,
+            None => break,
+        }
+    }
+    if !readable {
+        return Vec::new();
+    }
+    let mut levels = vec![(step, MINOR_COLOR)];
+    if subdivisions > 1 {
+        levels.push((step * subdivisions as f32, MAJOR_COLOR));
+    }
+
+    let mut lines = Vec::new();
+    for (step, color) in levels {
+        for axis in [Vec2::X, Vec2::Y] {
+            let along = axis.perp().abs();
+            let middle = (center.dot(axis) / step).round() as i64;
+            let first = ((min.dot(axis) / step).ceil() as i64).max(middle - MAX_LINES / 2);
+            let last = ((max.dot(axis) / step).floor() as i64).min(middle + MAX_LINES / 2);
+            let reach = (MAX_LINES / 2) as f32 * step;
+            let start = min.dot(along).max(center.dot(along) - reach);
+            let end = max.dot(along).min(center.dot(along) + reach);
+            for k in first..=last {
+                let offset = axis * (k as f32 * step);
+                let a = offset + along * start;
+                let b = offset + along * end;
+                lines.extend(line(plane, ctx, a, b, color));
+            }
+        }
+    }
+    lines
+}
+
+/// Sketch-space bounding box of the part of the plane seen in the viewport.
+fn visible_bounds(plane: &SketchPlane, ctx: &WorkbenchRuntimeContext) -> Option<(Vec2, Vec2)> {
+    let (width, height) = (ctx.viewport.2 as f32, ctx.viewport.3 as f32);
+    let mut bounds: Option<(Vec2, Vec2)> = None;
+    for i in 0..SAMPLES {
+        for j in 0..SAMPLES {
+            let pos = (
+                width * i as f32 / (SAMPLES - 1) as f32,
+                height * j as f32 / (SAMPLES - 1) as f32,
+            );
+            let Some(world) = ctx.viewport_to_plane(pos, plane.origin, plane.normal) else {
+                continue;
+            };
+            let p = plane.world_to_sketch(world).to_glam();
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(p), max.max(p)),
+                None => (p, p),
+            });
+        }
+    }
+    bounds
+}
+
+/// Distance (pixels) between lines `step` apart around `at`, in the
+/// direction the plane is seen least foreshortened.
+fn pixel_gap(
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    at: Vec2,
+    step: f32,
+) -> Option<f32> {
+    let origin = to_viewport(plane, ctx, at)?;
+    let x = to_viewport(plane, ctx, at + Vec2::X * step)?;
+    let y = to_viewport(plane, ctx, at + Vec2::Y * step)?;
+    Some(origin.distance(x).max(origin.distance(y)))
+}
+
+/// The line from `a` to `b` (sketch space), without the pieces behind the camera.
+fn line(
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    a: Vec2,
+    b: Vec2,
+    color: [f32; 3],
+) -> Vec<ScreenSpaceOverlay> {
+    let points: Vec<Option<Vec2>> = (0..=PIECES)
+        .map(|i| to_viewport(plane, ctx, a.lerp(b, i as f32 / PIECES as f32)))
+        .collect();
+    points
+        .windows(2)
+        .filter_map(|w| {
+            let (start, end) = (w[0]?, w[1]?);
+            Some(ScreenSpaceOverlay::new(
+                start.to_array(),
+                end.to_array(),
+                color,
+                THICKNESS,
+            ))
+        })
+        .collect()
+}
+
+fn to_viewport(plane: &SketchPlane, ctx: &WorkbenchRuntimeContext, p: Vec2) -> Option<Vec2> {
+    let world = plane.sketch_to_world(Vec2D::from_glam(p));
+    ctx.world_to_viewport(world).map(|(x, y)| Vec2::new(x, y))
+}
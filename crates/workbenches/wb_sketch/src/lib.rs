@@ -1,16 +1,53 @@
+mod datum;
+mod dimension;
+mod face;
 mod feature;
+mod glyph;
+mod grid;
+#[cfg(feature = "egui")]
+mod panel;
+mod profile;
+mod reference;
 pub mod render;
+mod select;
 mod sketch;
+mod snap;
+mod solver;
+#[cfg(feature = "egui")]
+mod svg;
 
+#[cfg(feature = "egui")]
+use core_document::UnitFormat;
 use core_document::{
-    BodyId, CommandDescriptor, FeatureId, InputResult, ToolDescriptor, Workbench, WorkbenchContext,
+    CommandDescriptor, FeatureId, GeometryRef, Gizmo, GizmoDrag, GizmoDragPhase, GizmoKind,
+    InputResult, KeyCode, SelectionItem, ToolDescriptor, Workbench, WorkbenchContext,
     WorkbenchDescriptor, WorkbenchFeature, WorkbenchInputEvent, WorkbenchRuntimeContext,
 };
+pub use datum::{follow_datum_planes, DatumPlaneFeature, DATUM_WORKBENCH_ID};
+pub use dimension::sketch_dimensions;
+pub use face::follow_body_faces;
 pub use feature::SketchFeature;
-use sketch::{GeometryElement, Line, Point, Sketch, Vec2D};
+pub use reference::{reference_images, ReferenceImageFeature, REFERENCE_IMAGE_WORKBENCH_ID};
+use sketch::{GeometryElement, Line, Point, Sketch, SketchPlane, Vec2D};
+use solver::SolveStatus;
 use uuid::Uuid;
 
+/// Whether the plane handles may move `feature`'s plane: free-standing
+/// sketches and sketches on view datum planes. Sketches on body faces, origin
+/// planes, and datum planes defined from other geometry stay where their
+/// geometry is.
+fn movable_plane(feature: &SketchFeature, document: &core_document::Document) -> bool {
+    match feature.attachment {
+        None => true,
+        Some(GeometryRef::Datum(id)) => document
+            .get_feature_meta(id)
+            .is_some_and(|node| node.workbench_id.as_str() == DATUM_WORKBENCH_ID),
+        Some(_) => false,
+    }
+}
+
 /// Sketch workbench: 2D drawing with constraints.
+#[derive(Default)]
 pub struct SketchWorkbench {
     /// Currently active sketch feature ID (if any).
     active_sketch_id: Option<FeatureId>,
@@ -20,18 +57,32 @@ pub struct SketchWorkbench {
     circle_tool_state: Option<Uuid>,
     /// Arc tool state: (center, start) points (if clicking to create an arc).
     arc_tool_state: Option<(Uuid, Uuid)>,
+    /// Outcome of the last solve of the active sketch.
+    solve_status: Option<SolveStatus>,
+    /// Constraint whose dimension was double-clicked, edited in the right panel.
+    editing_dimension: Option<usize>,
+    /// Geometry selected with the select tool.
+    selected: Vec<Uuid>,
+    /// Geometry under the cursor with the select tool.
+    hovered_element: Option<Uuid>,
+    /// Constraint selected by clicking its glyph (or its row in the panel).
+    selected_constraint: Option<usize>,
+    /// Constraint whose glyph is under the cursor with the select tool.
+    hovered_constraint: Option<usize>,
+    /// Point or box drag in progress with the select tool.
+    select_drag: Option<select::SelectDrag>,
+    /// Constraint being set up in the right panel.
+    #[cfg(feature = "egui")]
+    constraint_draft: panel::ConstraintDraft,
+    /// Handles shown on the active sketch's plane to move or turn it (None
+    /// hides them).
+    plane_handles: Option<GizmoKind>,
+    /// The active sketch's plane when its handles were grabbed.
+    plane_drag: Option<SketchPlane>,
 }
 
-impl Default for SketchWorkbench {
-    fn default() -> Self {
-        Self {
-            active_sketch_id: None,
-            line_tool_state: None,
-            circle_tool_state: None,
-            arc_tool_state: None,
-        }
-    }
-}
+/// Id of the gizmo moving the active sketch's plane.
+const PLANE_GIZMO: &str = "sketch.plane";
 
 impl SketchWorkbench {
     /// Get the active sketch from the document.
@@ -72,6 +123,284 @@ impl SketchWorkbench {
         }
     }
 
+    /// Place the active sketch on `plane`. A sketch on a datum plane takes the
+    /// datum plane with it, so the two stay together.
+    fn set_active_plane(&self, ctx: &mut WorkbenchRuntimeContext, plane: SketchPlane) {
+        let (Some(id), Some(mut feature)) = (self.active_sketch_id, self.get_active_sketch(ctx))
+        else {
+            return;
+        };
+        feature.plane = plane;
+        if let Some(GeometryRef::Datum(datum_id)) = feature.attachment {
+            let datum = ctx
+                .document
+                .get_feature_data(datum_id)
+                .and_then(|data| DatumPlaneFeature::from_json(data).ok());
+            if let Some(datum) = datum {
+                let datum = DatumPlaneFeature { plane, ..datum };
+                match ctx.document.update_feature_data(datum_id, datum.to_json()) {
+                    Ok(()) => ctx.document.mark_feature_dirty(datum_id),
+                    Err(e) => ctx.log_error(format!("Failed to move datum plane: {}", e)),
+                }
+            }
+        }
+        if self.update_active_sketch(ctx, feature) {
+            ctx.document.mark_feature_dirty(id);
+        }
+    }
+
+    /// Solve the active sketch's constraints, moving its geometry, and
+    /// remember how constrained it is.
+    fn solve_active_sketch(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let Some((feature_id, mut sketch_feature)) = self.get_active_sketch_mut(ctx) else {
+            ctx.log_warn("No active sketch to solve");
+            return;
+        };
+        let status = solver::solve(&mut sketch_feature.sketch);
+        self.solve_status = Some(status);
+        if status == SolveStatus::Conflicting {
+            ctx.log_warn("Sketch constraints conflict; geometry left unchanged");
+        }
+        if self.update_active_sketch(ctx, sketch_feature) {
+            ctx.document.mark_feature_dirty(feature_id);
+        }
+    }
+
+    /// Add the SVG paths in pasted `text` to the active sketch as joined
+    /// lines, selecting them. False when the text holds no path data.
+    #[cfg(feature = "egui")]
+    fn paste_paths(&mut self, text: &str, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        let subpaths = match svg::read_paths(text) {
+            Some(Ok(subpaths)) => subpaths,
+            Some(Err(err)) => {
+                ctx.log_error(format!("Cannot paste SVG path data: {err}"));
+                return true;
+            }
+            None => return false,
+        };
+        let Some((feature_id, mut sketch_feature)) = self.get_active_sketch_mut(ctx) else {
+            return false;
+        };
+        let sketch = &mut sketch_feature.sketch;
+        let mut added = Vec::new();
+        for subpath in &subpaths {
+            let points: Vec<Uuid> = subpath
+                .points
+                .iter()
+                .map(|position| sketch.add_geometry(GeometryElement::Point(Point::new(*position))))
+                .collect();
+            let mut ends: Vec<(Uuid, Uuid)> = points.windows(2).map(|w| (w[0], w[1])).collect();
+            if subpath.closed {
+                ends.push((points[points.len() - 1], points[0]));
+            }
+            for (start, end) in ends {
+                added.push(sketch.add_geometry(GeometryElement::Line(Line::new(start, end))));
+            }
+        }
+        if added.is_empty() {
+            ctx.log_info("The pasted path data draws nothing");
+            return true;
+        }
+        let count = added.len();
+        if self.update_active_sketch(ctx, sketch_feature) {
+            ctx.document.mark_feature_dirty(feature_id);
+            self.clear_selection();
+            self.selected = added;
+            ctx.log_info(format!(
+                "Pasted {count} line(s) from {} SVG path(s)",
+                subpaths.len()
+            ));
+        }
+        true
+    }
+
+    /// Dimensions of the sketch being edited, projected to the viewport.
+    fn dimension_views(&self, ctx: &WorkbenchRuntimeContext) -> Vec<dimension::DimensionView> {
+        match self.get_active_sketch(ctx) {
+            Some(feature) => dimension::dimension_views(
+                &feature.sketch,
+                &feature.plane,
+                ctx,
+                self.editing_dimension,
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        self.hovered_element = None;
+        self.selected_constraint = None;
+        self.hovered_constraint = None;
+        self.select_drag = None;
+    }
+
+    /// Cancel a point move or box selection of the select tool, or else clear
+    /// the selection. Returns whether there was anything to cancel.
+    fn cancel_select(&mut self, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        match self.select_drag.take() {
+            Some(select::SelectDrag::Point {
+                original,
+                moved: true,
+                ..
+            }) => {
+                if let Some((feature_id, mut feature)) = self.get_active_sketch_mut(ctx) {
+                    feature.sketch = *original;
+                    if self.update_active_sketch(ctx, feature) {
+                        ctx.document.mark_feature_dirty(feature_id);
+                    }
+                }
+                ctx.log_info("Sketch: Cancelled move");
+                true
+            }
+            Some(_) => true,
+            None if self.selected.is_empty() && self.selected_constraint.is_none() => false,
+            None => {
+                self.clear_selection();
+                true
+            }
+        }
+    }
+
+    /// Input for the `sketch.select` tool: click or drag a box to select,
+    /// drag points to move them, Delete to remove the selection.
+    fn select_input(
+        &mut self,
+        event: &WorkbenchInputEvent,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> InputResult {
+        use core_document::{KeyCode, MouseButton};
+        use select::SelectDrag;
+
+        let Some((feature_id, mut feature)) = self.get_active_sketch_mut(ctx) else {
+            return InputResult::ignored();
+        };
+        match event {
+            WorkbenchInputEvent::MouseMove { viewport_pos } => {
+                let pos = glam::Vec2::from(*viewport_pos);
+                match &mut self.select_drag {
+                    Some(SelectDrag::Point { point, moved, .. }) => {
+                        let point = *point;
+                        *moved = true;
+                        let plane = feature.plane;
+                        if let Some(world) =
+                            ctx.viewport_to_plane(*viewport_pos, plane.origin, plane.normal)
+                        {
+                            let to = plane.world_to_sketch(world);
+                            select::move_point(&mut feature.sketch, point, to);
+                            if self.update_active_sketch(ctx, feature) {
+                                ctx.document.mark_feature_dirty(feature_id);
+                            }
+                        }
+                        InputResult::consumed()
+                    }
+                    Some(SelectDrag::Box { current, .. }) => {
+                        *current = pos;
+                        InputResult::consumed()
+                    }
+                    None => {
+                        let glyphs = glyph::glyph_views(&feature.sketch, &feature.plane, ctx);
+                        self.hovered_constraint = glyph::pick(&glyphs, pos);
+                        self.hovered_element = match self.hovered_constraint {
+                            Some(_) => None,
+                            None => select::pick(&feature.sketch, &feature.plane, ctx, pos),
+                        };
+                        InputResult::ignored()
+                    }
+                }
+            }
+            WorkbenchInputEvent::MousePress {
+                button: MouseButton::Left,
+                viewport_pos,
+            } => {
+                let pos = glam::Vec2::from(*viewport_pos);
+                // Glyphs are drawn over the geometry, so they are picked first.
+                let glyphs = glyph::glyph_views(&feature.sketch, &feature.plane, ctx);
+                if let Some(constraint) = glyph::pick(&glyphs, pos) {
+                    self.selected.clear();
+                    self.selected_constraint = Some(constraint);
+                    self.select_drag = None;
+                    return InputResult::consumed();
+                }
+                self.selected_constraint = None;
+                let picked = select::pick(&feature.sketch, &feature.plane, ctx, pos);
+                self.select_drag = match picked {
+                    Some(id) => {
+                        // Pressing on the selection keeps it, so a drag moves
+                        // the pressed point without losing the rest.
+                        if !self.selected.contains(&id) {
+                            self.selected = vec![id];
+                        }
+                        let is_point = matches!(
+                            feature.sketch.get_geometry(id),
+                            Some(GeometryElement::Point(_))
+                        );
+                        is_point.then(|| SelectDrag::Point {
+                            point: id,
+                            original: Box::new(feature.sketch.clone()),
+                            moved: false,
+                        })
+                    }
+                    None => Some(SelectDrag::Box {
+                        start: pos,
+                        current: pos,
+                    }),
+                };
+                InputResult::consumed()
+            }
+            WorkbenchInputEvent::MouseRelease {
+                button: MouseButton::Left,
+                ..
+            } => match self.select_drag.take() {
+                Some(SelectDrag::Point { moved, .. }) => {
+                    if moved && !feature.sketch.constraints.is_empty() {
+                        self.solve_active_sketch(ctx);
+                    }
+                    InputResult::consumed()
+                }
+                Some(SelectDrag::Box { start, current }) => {
+                    self.selected = if start.distance(current) < select::DRAG_THRESHOLD {
+                        Vec::new()
+                    } else {
+                        select::in_box(&feature.sketch, &feature.plane, ctx, start, current)
+                    };
+                    InputResult::consumed()
+                }
+                None => InputResult::ignored(),
+            },
+            WorkbenchInputEvent::KeyPress {
+                key: KeyCode::Delete | KeyCode::Backspace,
+            } => {
+                if let Some(index) = self.selected_constraint.take() {
+                    if index >= feature.sketch.constraints.len() {
+                        return InputResult::ignored();
+                    }
+                    feature.sketch.constraints.remove(index);
+                    self.hovered_constraint = None;
+                    self.editing_dimension = None;
+                    if self.update_active_sketch(ctx, feature) {
+                        ctx.document.mark_feature_dirty(feature_id);
+                    }
+                    self.solve_active_sketch(ctx);
+                    ctx.log_info("Deleted sketch constraint");
+                    return InputResult::consumed();
+                }
+                if self.selected.is_empty() {
+                    return InputResult::ignored();
+                }
+                let removed = feature.sketch.remove_geometry(&self.selected);
+                self.clear_selection();
+                self.editing_dimension = None;
+                if self.update_active_sketch(ctx, feature) {
+                    ctx.document.mark_feature_dirty(feature_id);
+                }
+                ctx.log_info(format!("Deleted {removed} sketch element(s)"));
+                InputResult::consumed()
+            }
+            _ => InputResult::ignored(),
+        }
+    }
+
     fn sync_active_sketch_from_ctx(&mut self, ctx: &mut WorkbenchRuntimeContext) {
         if let Some(feature_id) = ctx.active_document_object {
             if self.is_sketch_feature(ctx, feature_id) && self.active_sketch_id != Some(feature_id)
@@ -80,6 +409,9 @@ impl SketchWorkbench {
                 self.line_tool_state = None;
                 self.circle_tool_state = None;
                 self.arc_tool_state = None;
+                self.solve_status = None;
+                self.editing_dimension = None;
+                self.clear_selection();
 
                 if let Some(sketch_feature) = self.get_active_sketch(ctx) {
                     let plane = sketch_feature.plane;
@@ -100,6 +432,140 @@ impl SketchWorkbench {
             .unwrap_or(false)
     }
 
+    /// Add `sketch` to the selected body (so it appears under that body in
+    /// the feature tree) and start editing it.
+    fn start_sketch(
+        &mut self,
+        ctx: &mut WorkbenchRuntimeContext,
+        sketch: Sketch,
+        attachment: Option<GeometryRef>,
+    ) {
+        let sketch_name = sketch.name.clone();
+        let owning_body = ctx.selected_body();
+        let mut plane = sketch.plane;
+        // The plane of the geometry sketched on is relative to that geometry's
+        // body; the sketch keeps it relative to its own.
+        if let Some(attachment) = &attachment {
+            let position = |body: Option<core_document::BodyId>| {
+                body.map_or([0.0; 3], |body| ctx.document.body_position(body))
+            };
+            let world =
+                core_document::from_frame(plane.origin, position(attachment.body(ctx.document)));
+            plane.origin = core_document::to_frame(world, position(owning_body));
+        }
+        let mut sketch_feature = SketchFeature::new(sketch, plane);
+        if let Some(attachment) = attachment {
+            sketch_feature = sketch_feature.attached_to(attachment);
+        }
+
+        match ctx
+            .document
+            .add_feature_in_body(sketch_feature, sketch_name.clone(), owning_body)
+        {
+            Ok(feature_id) => {
+                self.active_sketch_id = Some(feature_id);
+                self.line_tool_state = None;
+                self.circle_tool_state = None;
+                self.arc_tool_state = None;
+                self.solve_status = None;
+                self.editing_dimension = None;
+                self.clear_selection();
+                ctx.active_document_object = Some(feature_id);
+                ctx.camera_orient_request = Some(core_document::CameraOrientRequest {
+                    plane_origin: plane.origin,
+                    plane_normal: plane.normal,
+                    plane_up: plane.y_axis,
+                });
+                ctx.log_info(format!("Created new sketch: {}", sketch_name));
+            }
+            Err(e) => {
+                ctx.log_error(format!("Failed to create sketch: {}", e));
+            }
+        }
+    }
+
+    /// Origin plane selected in the tree, or the datum plane selected on its
+    /// own, and the reference to attach a sketch on it to.
+    fn selected_plane(ctx: &WorkbenchRuntimeContext) -> Option<(SketchPlane, GeometryRef)> {
+        let (reference, attachment) = match (ctx.selected_reference, ctx.selection.items()) {
+            (Some(origin), _) => (
+                ctx.document.origin_plane(origin)?,
+                GeometryRef::Origin(origin),
+            ),
+            (None, [SelectionItem::Feature(id)]) => {
+                let plane = ctx.document.datum_geometry(*id)?.plane()?;
+                (plane, GeometryRef::Datum(*id))
+            }
+            _ => return None,
+        };
+        let plane = SketchPlane {
+            origin: reference.origin,
+            normal: reference.normal,
+            x_axis: reference.x_axis,
+            y_axis: reference.y_axis,
+        };
+        Some((plane, attachment))
+    }
+
+    /// Plane of the planar body face under the cursor, and the reference to
+    /// attach a sketch on it to. Sketch geometry is not a face.
+    fn hovered_face(&self, ctx: &WorkbenchRuntimeContext) -> Option<(SketchPlane, GeometryRef)> {
+        let feature = match ctx.hovered? {
+            SelectionItem::Feature(id) if self.is_sketch_feature(ctx, id) => return None,
+            SelectionItem::Feature(id) => Some(id),
+            _ => None,
+        };
+        let body = ctx.hovered_body()?;
+        let mesh = &ctx.body_meshes.iter().find(|m| m.body == body)?.mesh;
+        let hovered = ctx.to_body(Some(body), ctx.hovered_world_pos?);
+        let plane = face::face_plane(mesh, hovered, ctx.hovered_normal)?;
+        Some((plane, GeometryRef::Face { body, feature }))
+    }
+
+    /// Create a datum plane facing the camera through the point under the
+    /// cursor (or the orbit center when nothing is hovered), and a sketch on it.
+    fn sketch_on_view_plane(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        let origin = ctx.hovered_world_pos.unwrap_or(ctx.camera_target);
+        let Some(plane) = datum::view_plane(
+            origin,
+            ctx.camera_position,
+            ctx.camera_target,
+            ctx.view_proj,
+        ) else {
+            ctx.log_warn("Cannot place a view plane: the camera has no view direction");
+            return;
+        };
+
+        let datum_count = ctx
+            .document
+            .feature_tree()
+            .all_nodes()
+            .filter(|(_, node)| node.workbench_id.as_str() == DATUM_WORKBENCH_ID)
+            .count();
+        let datum_name = format!("datum_plane_{}", datum_count + 1);
+        let owning_body = ctx.selected_body();
+        let plane = SketchPlane {
+            origin: ctx.to_body(owning_body, plane.origin),
+            ..plane
+        };
+        let datum_id = match ctx.document.add_feature_in_body(
+            DatumPlaneFeature::new(datum_name.clone(), plane),
+            datum_name.clone(),
+            owning_body,
+        ) {
+            Ok(id) => id,
+            Err(e) => {
+                ctx.log_error(format!("Failed to create datum plane: {}", e));
+                return;
+            }
+        };
+        ctx.log_info(format!("Created datum plane: {}", datum_name));
+
+        let mut sketch = Sketch::new(Self::next_sketch_name(ctx.document));
+        sketch.plane = plane;
+        self.start_sketch(ctx, sketch, Some(GeometryRef::Datum(datum_id)));
+    }
+
     fn next_sketch_name(document: &core_document::Document) -> String {
         let mut max_index = None::<u32>;
         for (_, node) in document.feature_tree().all_nodes() {
@@ -133,13 +599,25 @@ impl Workbench for SketchWorkbench {
             Some("sketch"),
         ));
         // Register sketch tools (radio button behavior - only one active at a time)
-        context.register_tool(ToolDescriptor::new("sketch.line", "Line", Some("sketch")));
-        context.register_tool(ToolDescriptor::new("sketch.arc", "Arc", Some("sketch")));
-        context.register_tool(ToolDescriptor::new(
-            "sketch.circle",
-            "Circle",
+        context.register_tool(ToolDescriptor::new_action(
+            "sketch.create_on_view",
+            "Sketch on View Plane",
             Some("sketch"),
         ));
+        context.register_tool(
+            ToolDescriptor::new("sketch.select", "Select", Some("sketch"))
+                .with_shortcut(KeyCode::S),
+        );
+        context.register_tool(
+            ToolDescriptor::new("sketch.line", "Line", Some("sketch")).with_shortcut(KeyCode::L),
+        );
+        context.register_tool(
+            ToolDescriptor::new("sketch.arc", "Arc", Some("sketch")).with_shortcut(KeyCode::A),
+        );
+        context.register_tool(
+            ToolDescriptor::new("sketch.circle", "Circle", Some("sketch"))
+                .with_shortcut(KeyCode::C),
+        );
         context.register_command(CommandDescriptor::new(
             "sketch.constraints.solve",
             "Solve Constraints",
@@ -166,17 +644,14 @@ impl Workbench for SketchWorkbench {
 
         // Handle "Finish Sketch" action
         if active_tool == Some("sketch.finish") {
-            if self.active_sketch_id.is_some() {
-                self.active_sketch_id = None;
-                self.line_tool_state = None;
-                self.circle_tool_state = None;
-                self.arc_tool_state = None;
-                ctx.log_info("Finished sketch editing");
-                return InputResult::consumed();
-            } else {
-                ctx.log_warn("No active sketch to finish");
-                return InputResult::consumed();
-            }
+            self.finish_editing(ctx);
+            return InputResult::consumed();
+        }
+
+        // Handle "Solve Constraints" command
+        if active_tool == Some("sketch.constraints.solve") {
+            self.solve_active_sketch(ctx);
+            return InputResult::consumed();
         }
 
         // Handle "Create Sketch" action
@@ -188,43 +663,82 @@ impl Workbench for SketchWorkbench {
                 return InputResult::ignored();
             }
 
-            let sketch_name = Self::next_sketch_name(&ctx.document);
-            let sketch = Sketch::new(sketch_name.clone());
-            let plane = sketch.plane;
-            let sketch_feature = SketchFeature::new(sketch, plane);
-            // Attach sketch to currently selected body if available so it appears
-            // under that body in the feature tree.
-            let owning_body = ctx.selected_body_id.map(BodyId);
+            let mut sketch = Sketch::new(Self::next_sketch_name(ctx.document));
+            // Sketch on the origin or datum plane selected in the tree, if any.
+            if let Some((plane, attachment)) = Self::selected_plane(ctx) {
+                sketch.plane = plane;
+                self.start_sketch(ctx, sketch, Some(attachment));
+                return InputResult::consumed();
+            }
 
-            match ctx
-                .document
-                .add_feature_in_body(sketch_feature, sketch_name.clone(), owning_body)
-            {
-                Ok(feature_id) => {
-                    self.active_sketch_id = Some(feature_id);
-                    self.line_tool_state = None;
-                    self.circle_tool_state = None;
-                    self.arc_tool_state = None;
-                    ctx.active_document_object = Some(feature_id);
-                    ctx.camera_orient_request = Some(core_document::CameraOrientRequest {
-                        plane_origin: plane.origin,
-                        plane_normal: plane.normal,
-                        plane_up: plane.y_axis,
-                    });
-                    ctx.log_info(format!("Created new sketch: {}", sketch_name));
-                }
-                Err(e) => {
-                    ctx.log_error(format!("Failed to create sketch: {}", e));
+            // Otherwise the next click picks the planar body face to sketch
+            // on; a click on empty space uses the default plane.
+            let WorkbenchInputEvent::MousePress {
+                button: core_document::MouseButton::Left,
+                ..
+            } = event
+            else {
+                return InputResult::ignored();
+            };
+            let attachment = self.hovered_face(ctx).map(|(plane, attachment)| {
+                sketch.plane = plane;
+                attachment
+            });
+            self.start_sketch(ctx, sketch, attachment);
+            return InputResult::consumed();
+        }
+
+        // Double-clicking a dimension label opens its value in the right panel.
+        if let WorkbenchInputEvent::MouseDoubleClick {
+            button: core_document::MouseButton::Left,
+            viewport_pos,
+        } = event
+        {
+            let picked = self
+                .dimension_views(ctx)
+                .into_iter()
+                .find(|view| view.label_contains(*viewport_pos));
+            return match picked {
+                Some(view) => {
+                    self.editing_dimension = Some(view.constraint);
+                    InputResult::consumed()
                 }
+                None => InputResult::ignored(),
+            };
+        }
+
+        // Handle "Sketch on View Plane": the next click picks the point the
+        // datum plane passes through.
+        if active_tool == Some("sketch.create_on_view") {
+            if self.active_sketch_id.is_some() {
+                return InputResult::ignored();
             }
+            let WorkbenchInputEvent::MousePress {
+                button: core_document::MouseButton::Left,
+                ..
+            } = event
+            else {
+                return InputResult::ignored();
+            };
+            self.sketch_on_view_plane(ctx);
             return InputResult::consumed();
         }
 
         // Only handle input if a sketch tool is active
         let tool = match active_tool {
-            Some(t) if t.starts_with("sketch.") && t != "sketch.create" => t,
+            Some(t)
+                if t.starts_with("sketch.")
+                    && t != "sketch.create"
+                    && t != "sketch.create_on_view" =>
+            {
+                t
+            }
             _ => return InputResult::ignored(),
         };
+        if tool == "sketch.select" {
+            return self.select_input(event, ctx);
+        }
+        self.clear_selection();
 
         match event {
             WorkbenchInputEvent::MousePress {
@@ -255,24 +769,25 @@ viewport_pos = ({:.1}, {:.1})",
                     }
                 };
 
-                // Convert world position to sketch 2D coordinates
-                let plane_origin = glam::Vec3::from_array(sketch_feature.plane.origin);
-                let plane_x = glam::Vec3::from_array(sketch_feature.plane.x_axis);
-                let plane_y = glam::Vec3::from_array(sketch_feature.plane.y_axis);
-                let world_vec = glam::Vec3::from_array(world_pos) - plane_origin;
-                let sketch_x = world_vec.dot(plane_x);
-                let sketch_y = world_vec.dot(plane_y);
-                let sketch_pos = sketch::Vec2D::new(sketch_x, sketch_y);
+                // Convert world position to sketch 2D coordinates, then apply grid/object snaps
+                let raw_pos = sketch_feature.plane.world_to_sketch(world_pos);
+                let (sketch_pos, snap_target) =
+                    snap::snap_point(&sketch_feature.sketch, &sketch_feature.plane, raw_pos, ctx);
 
                 ctx.log_info(format!(
-                    "Sketch click: viewport=({:.1}, {:.1}) world=({:.2}, {:.2}, {:.2}) sketch=({:.2}, {:.2})",
+                    "Sketch click: viewport=({:.1}, {:.1}) world=({:.2}, {:.2}, {:.2}) sketch=({:.2}, {:.2}){}",
                     viewport_pos.0,
                     viewport_pos.1,
                     world_pos[0],
                     world_pos[1],
                     world_pos[2],
-                    sketch_x,
-                    sketch_y
+                    sketch_pos.x,
+                    sketch_pos.y,
+                    match snap_target {
+                        Some(snap::SnapTarget::Object(kind)) => format!(" [snap: {kind:?}]"),
+                        Some(snap::SnapTarget::Grid) => " [snap: grid]".to_string(),
+                        None => String::new(),
+                    }
                 ));
 
                 match tool {
@@ -310,7 +825,7 @@ viewport_pos = ({:.1}, {:.1})",
                                 }
 
                                 self.line_tool_state = None;
-                                InputResult::consumed()
+                                InputResult::finished()
                             } else {
                                 // First click: create start point
                                 let start_point = Point::new(sketch_pos);
@@ -374,7 +889,7 @@ viewport_pos = ({:.1}, {:.1})",
                                     }
 
                                     self.circle_tool_state = None;
-                                    InputResult::consumed()
+                                    InputResult::finished()
                                 } else {
                                     ctx.log_error("Circle center point not found");
                                     self.circle_tool_state = None;
@@ -457,7 +972,7 @@ viewport_pos = ({:.1}, {:.1})",
                                     }
 
                                     self.arc_tool_state = None;
-                                    InputResult::consumed()
+                                    InputResult::finished()
                                 } else {
                                     ctx.log_error("Arc center or start point not found");
                                     self.arc_tool_state = None;
@@ -505,27 +1020,50 @@ viewport_pos = ({:.1}, {:.1})",
                     _ => InputResult::ignored(),
                 }
             }
-            WorkbenchInputEvent::KeyPress {
-                key: core_document::KeyCode::Escape,
-            } => {
-                // Cancel current tool operation
-                if self.line_tool_state.is_some()
-                    || self.circle_tool_state.is_some()
-                    || self.arc_tool_state.is_some()
-                {
-                    self.line_tool_state = None;
-                    self.circle_tool_state = None;
-                    self.arc_tool_state = None;
-                    ctx.log_info("Sketch: Cancelled current tool operation");
-                } else {
-                    ctx.log_info("Sketch: Escape pressed");
-                }
-                InputResult::consumed()
-            }
             _ => InputResult::ignored(),
         }
     }
 
+    fn cancel_tool_operation(&mut self, tool: &str, ctx: &mut WorkbenchRuntimeContext) -> bool {
+        if tool == "sketch.select" {
+            return self.cancel_select(ctx);
+        }
+        // Drop the points placed so far; they aren't part of anything yet.
+        let placed: Vec<Uuid> = self
+            .line_tool_state
+            .take()
+            .into_iter()
+            .chain(self.circle_tool_state.take())
+            .chain(
+                self.arc_tool_state
+                    .take()
+                    .into_iter()
+                    .flat_map(|(c, s)| [c, s]),
+            )
+            .collect();
+        if placed.is_empty() {
+            return false;
+        }
+        if let Some((feature_id, mut feature)) = self.get_active_sketch_mut(ctx) {
+            feature.sketch.remove_geometry(&placed);
+            if self.update_active_sketch(ctx, feature) {
+                ctx.document.mark_feature_dirty(feature_id);
+            }
+        }
+        ctx.log_info("Sketch: Cancelled current tool operation");
+        true
+    }
+
+    fn finish_tool_operation(&mut self, _tool: &str, ctx: &mut WorkbenchRuntimeContext) {
+        // Points placed so far stay in the sketch as points.
+        self.line_tool_state = None;
+        self.circle_tool_state = None;
+        self.arc_tool_state = None;
+        if let Some(select::SelectDrag::Point { moved: true, .. }) = self.select_drag.take() {
+            self.solve_active_sketch(ctx);
+        }
+    }
+
     #[cfg(feature = "egui")]
     fn ui_left_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
         self.sync_active_sketch_from_ctx(ctx);
@@ -547,7 +1085,7 @@ viewport_pos = ({:.1}, {:.1})",
                     .max_height(240.0)
                     .show(ui, |ui| {
                         for (idx, geom) in sketch.geometry.iter().enumerate() {
-                            ui.label(describe_geometry(idx + 1, sketch, geom));
+                            ui.label(describe_geometry(idx + 1, sketch, geom, &ctx.units));
                         }
                     });
             }
@@ -570,6 +1108,17 @@ viewport_pos = ({:.1}, {:.1})",
                 "Constraints: {}",
                 sketch_feature.sketch.constraints.len()
             ));
+            let mut show_dimensions = sketch_feature.show_dimensions;
+            if ui
+                .checkbox(&mut show_dimensions, "Show dimensions outside sketch mode")
+                .changed()
+            {
+                let feature = SketchFeature {
+                    show_dimensions,
+                    ..sketch_feature.clone()
+                };
+                self.update_active_sketch(ctx, feature);
+            }
 
             if let Some(id) = self.active_sketch_id {
                 if let Some(meta) = ctx.document.get_feature_meta(id) {
@@ -587,28 +1136,148 @@ viewport_pos = ({:.1}, {:.1})",
             if let Some((_center_id, _start_id)) = self.arc_tool_state {
                 ui.label("Arc tool: click for end point");
             }
+            if !self.selected.is_empty() {
+                ui.label(format!(
+                    "{} element(s) selected: press Delete to remove",
+                    self.selected.len()
+                ));
+            }
+
+            if movable_plane(&sketch_feature, ctx.document) {
+                ui.horizontal(|ui| {
+                    ui.label("Plane handles:");
+                    ui.selectable_value(&mut self.plane_handles, None, "Off");
+                    ui.selectable_value(
+                        &mut self.plane_handles,
+                        Some(GizmoKind::Translate),
+                        "Move",
+                    );
+                    ui.selectable_value(&mut self.plane_handles, Some(GizmoKind::Rotate), "Rotate");
+                });
+            }
+
+            panel::paste_paths(ui, ctx, self);
+
+            ui.separator();
+            panel::dimension_editor(ui, ctx, self, &sketch_feature.sketch);
+            panel::constraints_section(ui, ctx, self, &sketch_feature.sketch);
 
             ui.separator();
             ui.label("Exit sketch mode to return to normal view.");
             if ui.button("Exit Sketch Mode").clicked() {
-                ctx.finish_sketch_requested = true;
+                ctx.execute_command("sketch.finish");
             }
         } else {
             ui.heading("Sketch Info");
             ui.label("No sketch selected. Select one in the tree or create a new sketch.");
         }
+
+        ui.separator();
+        let sketch_plane = self.get_active_sketch(ctx).map(|feature| feature.plane);
+        panel::reference_images_section(ui, ctx, sketch_plane);
     }
 
     #[cfg(feature = "egui")]
     fn wants_right_panel(&self) -> bool {
-        self.active_sketch_id.is_some()
+        // Reference images are managed from the panel even without a sketch.
+        true
+    }
+
+    fn gizmos(&self, ctx: &WorkbenchRuntimeContext) -> Vec<Gizmo> {
+        let Some(kind) = self.plane_handles else {
+            return Vec::new();
+        };
+        match self.get_active_sketch(ctx) {
+            Some(feature) if movable_plane(&feature, ctx.document) => {
+                let plane = feature.plane;
+                vec![Gizmo::new(PLANE_GIZMO, kind, plane.origin).with_axes([
+                    plane.x_axis,
+                    plane.y_axis,
+                    plane.normal,
+                ])]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn on_gizmo_drag(&mut self, drag: &GizmoDrag, ctx: &mut WorkbenchRuntimeContext) {
+        if drag.gizmo != PLANE_GIZMO {
+            return;
+        }
+        if drag.phase == GizmoDragPhase::Start {
+            self.plane_drag = self.get_active_sketch(ctx).map(|feature| feature.plane);
+            return;
+        }
+        let Some(start) = self.plane_drag else {
+            return;
+        };
+        let plane = match drag.phase {
+            GizmoDragPhase::Cancel => start,
+            _ => datum::moved_plane(&start, &drag.motion),
+        };
+        self.set_active_plane(ctx, plane);
+        match drag.phase {
+            GizmoDragPhase::End => {
+                self.plane_drag = None;
+                ctx.log_info("Sketch plane moved");
+            }
+            GizmoDragPhase::Cancel => self.plane_drag = None,
+            _ => {}
+        }
+    }
+
+    fn on_file_opened(
+        &mut self,
+        request_id: &str,
+        path: &std::path::Path,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) {
+        if request_id != reference::IMPORT_REQUEST {
+            return;
+        }
+        // Place the image on the sketch being edited, else on the origin plane
+        // selected in the tree.
+        let plane = self
+            .get_active_sketch(ctx)
+            .map(|feature| feature.plane)
+            .or_else(|| {
+                let reference = ctx
+                    .selected_reference
+                    .and_then(|origin| ctx.document.origin_plane(origin))?;
+                Some(SketchPlane {
+                    origin: reference.origin,
+                    normal: reference.normal,
+                    x_axis: reference.x_axis,
+                    y_axis: reference.y_axis,
+                })
+            })
+            .unwrap_or_default();
+        let image = match reference::import(ctx.document, path, plane) {
+            Ok(image) => image,
+            Err(err) => {
+                ctx.log_error(format!("Failed to import {}: {err}", path.display()));
+                return;
+            }
+        };
+        let name = image.name.clone();
+        let body = ctx.selected_body();
+        match ctx.document.add_feature_in_body(image, name.clone(), body) {
+            Ok(id) => {
+                ctx.active_document_object = Some(id);
+                ctx.log_info(format!("Added reference image: {name}"));
+            }
+            Err(err) => ctx.log_error(format!("Failed to add reference image: {err}")),
+        }
     }
 
     fn is_tool_enabled(&self, tool_id: &str, ctx: &WorkbenchRuntimeContext) -> bool {
         match tool_id {
             "sketch.create" => {
                 // "Create Sketch" requires a body to attach the sketch to
-                ctx.selected_body_id.is_some()
+                ctx.selected_body().is_some()
+            }
+            "sketch.create_on_view" => {
+                ctx.selected_body().is_some() && self.active_sketch_id.is_none()
             }
             _ => {
                 // Other sketch tools (line, arc, circle) require an active sketch
@@ -633,12 +1302,42 @@ viewport_pos = ({:.1}, {:.1})",
             self.line_tool_state = None;
             self.circle_tool_state = None;
             self.arc_tool_state = None;
+            self.clear_selection();
             ctx.log_info("Exited sketch editing mode (sketch remains selected)");
+            // Bring the features built from the sketch up to date.
+            ctx.execute_command("part.recompute");
         } else {
             ctx.log_warn("Not in sketch editing mode");
         }
     }
 
+    fn on_command(&mut self, command: &str, ctx: &mut WorkbenchRuntimeContext) {
+        match command {
+            "sketch.finish" => self.finish_editing(ctx),
+            "sketch.constraints.solve" => self.solve_active_sketch(ctx),
+            _ => {}
+        }
+    }
+
+    fn edit_feature(&mut self, feature: FeatureId, ctx: &mut WorkbenchRuntimeContext) {
+        if self.is_sketch_feature(ctx, feature) {
+            ctx.active_document_object = Some(feature);
+            self.sync_active_sketch_from_ctx(ctx);
+        }
+    }
+
+    fn regenerate_feature(
+        &self,
+        feature: &core_document::FeatureNode,
+    ) -> Option<core_document::FeatureBuild> {
+        // Sketches are drawn as wireframes; they don't need the kernel.
+        let sketch = SketchFeature::from_json(&feature.data)
+            .map_err(|err| kernel_api::KernelError::InvalidInput(format!("invalid sketch: {err}")));
+        Some(Box::new(move |_: &mut dyn kernel_api::Kernel| {
+            sketch.map(|sketch| render::sketch_to_mesh(&sketch.sketch, &sketch.plane))
+        }))
+    }
+
     fn get_overlay_meshes(
         &self,
         _ctx: &WorkbenchRuntimeContext,
@@ -649,10 +1348,50 @@ viewport_pos = ({:.1}, {:.1})",
 
     fn get_screen_space_overlays(
         &self,
-        _ctx: &WorkbenchRuntimeContext,
+        ctx: &WorkbenchRuntimeContext,
         _active_feature: Option<FeatureId>,
     ) -> Vec<core_document::ScreenSpaceOverlay> {
-        Vec::new()
+        let feature = self.get_active_sketch(ctx);
+        // The grid goes first so everything else is drawn over it.
+        let mut overlays = feature
+            .as_ref()
+            .map(|feature| grid::grid_overlays(&feature.plane, ctx))
+            .unwrap_or_default();
+        let editing = self.active_sketch_id;
+        overlays.extend(dimension::sketch_dimensions(ctx, |id| Some(id) != editing).0);
+        overlays.extend(
+            self.dimension_views(ctx)
+                .into_iter()
+                .flat_map(|view| view.lines),
+        );
+        if let Some(feature) = feature {
+            let glyphs = glyph::glyph_views(&feature.sketch, &feature.plane, ctx);
+            overlays.extend(glyph::overlays(
+                &glyphs,
+                self.hovered_constraint,
+                self.selected_constraint,
+            ));
+            overlays.extend(select::overlays(
+                &feature.sketch,
+                &feature.plane,
+                ctx,
+                &self.selected,
+                self.hovered_element,
+                self.select_drag.as_ref(),
+            ));
+        }
+        overlays
+    }
+
+    fn get_screen_space_labels(
+        &self,
+        ctx: &WorkbenchRuntimeContext,
+        _active_feature: Option<FeatureId>,
+    ) -> Vec<core_document::ScreenSpaceLabel> {
+        let editing = self.active_sketch_id;
+        let (_, mut labels) = dimension::sketch_dimensions(ctx, |id| Some(id) != editing);
+        labels.extend(self.dimension_views(ctx).into_iter().map(|view| view.label));
+        labels
     }
 }
 
@@ -660,10 +1399,8 @@ fn parse_sketch_index(name: &str) -> Option<u32> {
     let lower = name.to_ascii_lowercase();
     let rest = if let Some(r) = lower.strip_prefix("sketch_") {
         r
-    } else if let Some(r) = lower.strip_prefix("sketch") {
-        r
     } else {
-        return None;
+        lower.strip_prefix("sketch")?
     };
 
     let trimmed = rest.trim_start_matches(&['_', '.', ' '][..]);
@@ -675,20 +1412,21 @@ fn parse_sketch_index(name: &str) -> Option<u32> {
 }
 
 #[cfg(feature = "egui")]
-fn describe_geometry(index: usize, sketch: &Sketch, element: &GeometryElement) -> String {
+fn describe_geometry(
+    index: usize,
+    sketch: &Sketch,
+    element: &GeometryElement,
+    units: &UnitFormat,
+) -> String {
+    let point = |p: Vec2D| format!("({}, {})", units.format_value(p.x), units.format_value(p.y));
+    let length = |value: f32| units.format_length(value);
     match element {
-        GeometryElement::Point(point) => format!(
-            "{}. Point ({:.2}, {:.2})",
-            index, point.position.x, point.position.y
-        ),
+        GeometryElement::Point(p) => format!("{}. Point {}", index, point(p.position)),
         GeometryElement::Line(line) => {
             let start = point_coords(sketch, line.start);
             let end = point_coords(sketch, line.end);
             match (start, end) {
-                (Some(s), Some(e)) => format!(
-                    "{}. Line ({:.2}, {:.2}) → ({:.2}, {:.2})",
-                    index, s.x, s.y, e.x, e.y
-                ),
+                (Some(s), Some(e)) => format!("{}. Line {} → {}", index, point(s), point(e)),
                 _ => format!("{}. Line (incomplete)", index),
             }
         }
@@ -696,10 +1434,12 @@ fn describe_geometry(index: usize, sketch: &Sketch, element: &GeometryElement) -
             let center = point_coords(sketch, circle.center);
             match center {
                 Some(c) => format!(
-                    "{}. Circle center ({:.2}, {:.2}) radius {:.2}",
-                    index, c.x, c.y, circle.radius
+                    "{}. Circle center {} radius {}",
+                    index,
+                    point(c),
+                    length(circle.radius)
                 ),
-                None => format!("{}. Circle radius {:.2}", index, circle.radius),
+                None => format!("{}. Circle radius {}", index, length(circle.radius)),
             }
         }
         GeometryElement::Arc(arc) => {
@@ -708,16 +1448,18 @@ fn describe_geometry(index: usize, sketch: &Sketch, element: &GeometryElement) -
             let end = point_coords(sketch, arc.end);
             match (center, start, end) {
                 (Some(c), Some(s), Some(e)) => format!(
-                    "{}. Arc center ({:.2}, {:.2}) start ({:.2}, {:.2}) end ({:.2}, {:.2})",
-                    index, c.x, c.y, s.x, s.y, e.x, e.y
+                    "{}. Arc center {} start {} end {}",
+                    index,
+                    point(c),
+                    point(s),
+                    point(e)
                 ),
-                _ => format!("{}. Arc radius {:.2}", index, arc.radius),
+                _ => format!("{}. Arc radius {}", index, length(arc.radius)),
             }
         }
     }
 }
 
-#[cfg(feature = "egui")]
 fn point_coords(sketch: &Sketch, id: Uuid) -> Option<Vec2D> {
     match sketch.get_geometry(id)? {
         GeometryElement::Point(point) => Some(point.position),
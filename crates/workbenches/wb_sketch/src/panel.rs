@@ -0,0 +1,620 @@
+//! Constraint editing in the right panel of the Sketch workbench.
+
+use core_document::{WorkbenchFeature, WorkbenchRuntimeContext};
+use uuid::Uuid;
+
+use crate::reference;
+use crate::sketch::{Constraint, GeometryElement, Sketch, SketchPlane};
+use crate::solver::SolveStatus;
+use crate::{point_coords, SketchWorkbench};
+
+/// Kinds of constraint that can be added from the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ConstraintKind {
+    #[default]
+    Coincident,
+    Horizontal,
+    Vertical,
+    Parallel,
+    Perpendicular,
+    Tangent,
+    EqualLength,
+    EqualRadius,
+    Distance,
+    Length,
+    Angle,
+    Radius,
+    Diameter,
+    PointOnLine,
+    PointOnCircle,
+    Fixed,
+}
+
+/// Geometry a constraint refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Point,
+    Line,
+    Circle,
+    /// A line or a circle/arc.
+    Curve,
+}
+
+impl Slot {
+    fn accepts(self, element: &GeometryElement) -> bool {
+        matches!(
+            (self, element),
+            (Slot::Point, GeometryElement::Point(_))
+                | (Slot::Line | Slot::Curve, GeometryElement::Line(_))
+                | (
+                    Slot::Circle | Slot::Curve,
+                    GeometryElement::Circle(_) | GeometryElement::Arc(_)
+                )
+        )
+    }
+}
+
+impl ConstraintKind {
+    const ALL: [ConstraintKind; 16] = [
+        ConstraintKind::Coincident,
+        ConstraintKind::Horizontal,
+        ConstraintKind::Vertical,
+        ConstraintKind::Parallel,
+        ConstraintKind::Perpendicular,
+        ConstraintKind::Tangent,
+        ConstraintKind::EqualLength,
+        ConstraintKind::EqualRadius,
+        ConstraintKind::Distance,
+        ConstraintKind::Length,
+        ConstraintKind::Angle,
+        ConstraintKind::Radius,
+        ConstraintKind::Diameter,
+        ConstraintKind::PointOnLine,
+        ConstraintKind::PointOnCircle,
+        ConstraintKind::Fixed,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ConstraintKind::Coincident => "Coincident",
+            ConstraintKind::Horizontal => "Horizontal",
+            ConstraintKind::Vertical => "Vertical",
+            ConstraintKind::Parallel => "Parallel",
+            ConstraintKind::Perpendicular => "Perpendicular",
+            ConstraintKind::Tangent => "Tangent",
+            ConstraintKind::EqualLength => "Equal length",
+            ConstraintKind::EqualRadius => "Equal radius",
+            ConstraintKind::Distance => "Distance",
+            ConstraintKind::Length => "Length",
+            ConstraintKind::Angle => "Angle",
+            ConstraintKind::Radius => "Radius",
+            ConstraintKind::Diameter => "Diameter",
+            ConstraintKind::PointOnLine => "Point on line",
+            ConstraintKind::PointOnCircle => "Point on circle",
+            ConstraintKind::Fixed => "Fixed",
+        }
+    }
+
+    /// Geometry the constraint needs, in order.
+    fn slots(self) -> &'static [Slot] {
+        match self {
+            ConstraintKind::Coincident | ConstraintKind::Distance => &[Slot::Point, Slot::Point],
+            ConstraintKind::Horizontal | ConstraintKind::Vertical | ConstraintKind::Length => {
+                &[Slot::Line]
+            }
+            ConstraintKind::Parallel
+            | ConstraintKind::Perpendicular
+            | ConstraintKind::EqualLength
+            | ConstraintKind::Angle => &[Slot::Line, Slot::Line],
+            ConstraintKind::Tangent => &[Slot::Curve, Slot::Circle],
+            ConstraintKind::EqualRadius => &[Slot::Circle, Slot::Circle],
+            ConstraintKind::Radius | ConstraintKind::Diameter => &[Slot::Circle],
+            ConstraintKind::PointOnLine => &[Slot::Point, Slot::Line],
+            ConstraintKind::PointOnCircle => &[Slot::Point, Slot::Circle],
+            ConstraintKind::Fixed => &[Slot::Point],
+        }
+    }
+
+    /// The constraint carries a dimension (length in mm, or angle in degrees).
+    fn has_value(self) -> bool {
+        matches!(
+            self,
+            ConstraintKind::Distance
+                | ConstraintKind::Length
+                | ConstraintKind::Angle
+                | ConstraintKind::Radius
+                | ConstraintKind::Diameter
+        )
+    }
+}
+
+/// Constraint being set up in the panel before it is added.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConstraintDraft {
+    kind: ConstraintKind,
+    elements: [Option<Uuid>; 2],
+    /// Dimension in mm, or degrees for angles.
+    value: f32,
+}
+
+impl ConstraintDraft {
+    fn build(&self, sketch: &Sketch) -> Option<Constraint> {
+        let slots = self.kind.slots();
+        let first = self.elements[0]?;
+        let second = if slots.len() > 1 {
+            let second = self.elements[1]?;
+            if second == first {
+                return None;
+            }
+            second
+        } else {
+            first
+        };
+        let value = self.value;
+        Some(match self.kind {
+            ConstraintKind::Coincident => Constraint::Coincident {
+                point1: first,
+                point2: second,
+            },
+            ConstraintKind::Horizontal => Constraint::Horizontal { element: first },
+            ConstraintKind::Vertical => Constraint::Vertical { element: first },
+            ConstraintKind::Parallel => Constraint::Parallel {
+                line1: first,
+                line2: second,
+            },
+            ConstraintKind::Perpendicular => Constraint::Perpendicular {
+                line1: first,
+                line2: second,
+            },
+            ConstraintKind::Tangent => Constraint::Tangent {
+                curve1: first,
+                curve2: second,
+            },
+            ConstraintKind::EqualLength => Constraint::EqualLength {
+                line1: first,
+                line2: second,
+            },
+            ConstraintKind::EqualRadius => Constraint::EqualRadius {
+                circle1: first,
+                circle2: second,
+            },
+            ConstraintKind::Distance => Constraint::Distance {
+                point1: first,
+                point2: second,
+                distance: value,
+            },
+            ConstraintKind::Length => Constraint::Length {
+                line: first,
+                length: value,
+            },
+            ConstraintKind::Angle => Constraint::Angle {
+                line1: first,
+                line2: second,
+                angle_rad: value.to_radians(),
+            },
+            ConstraintKind::Radius => Constraint::Radius {
+                circle: first,
+                radius: value,
+            },
+            ConstraintKind::Diameter => Constraint::Diameter {
+                circle: first,
+                diameter: value,
+            },
+            ConstraintKind::PointOnLine => Constraint::PointOnLine {
+                point: first,
+                line: second,
+            },
+            ConstraintKind::PointOnCircle => Constraint::PointOnCircle {
+                point: first,
+                circle: second,
+            },
+            ConstraintKind::Fixed => Constraint::FixedPoint {
+                point: first,
+                position: point_coords(sketch, first)?,
+            },
+        })
+    }
+
+    /// Current measurement of the picked geometry, used as the default dimension.
+    fn measure(&self, sketch: &Sketch) -> Option<f32> {
+        let [first, second] = self.elements;
+        match self.kind {
+            ConstraintKind::Distance => {
+                let a = point_coords(sketch, first?)?.to_glam();
+                let b = point_coords(sketch, second?)?.to_glam();
+                Some(a.distance(b))
+            }
+            ConstraintKind::Length => Some(line_vector(sketch, first?)?.length()),
+            ConstraintKind::Angle => {
+                let a = line_vector(sketch, first?)?;
+                let b = line_vector(sketch, second?)?;
+                Some(a.angle_to(b).to_degrees())
+            }
+            ConstraintKind::Radius => circle_radius(sketch, first?),
+            ConstraintKind::Diameter => Some(2.0 * circle_radius(sketch, first?)?),
+            _ => None,
+        }
+    }
+}
+
+/// Constraint status, list, and editor for the active sketch.
+pub(crate) fn constraints_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut SketchWorkbench,
+    sketch: &Sketch,
+) {
+    ui.heading("Constraints");
+    match workbench.solve_status {
+        Some(status) => {
+            let color = match status {
+                SolveStatus::FullyConstrained => egui::Color32::from_rgb(80, 200, 80),
+                SolveStatus::UnderConstrained { .. } => ui.visuals().text_color(),
+                SolveStatus::Redundant { .. } => egui::Color32::from_rgb(230, 180, 40),
+                SolveStatus::Conflicting => egui::Color32::from_rgb(220, 60, 50),
+            };
+            ui.colored_label(color, status.describe());
+        }
+        None => {
+            ui.weak("Not solved yet");
+        }
+    }
+
+    let mut constraints = sketch.constraints.clone();
+    let mut changed = false;
+    let mut removed = None;
+    egui::ScrollArea::vertical()
+        .id_salt("sketch_constraints")
+        .max_height(200.0)
+        .show(ui, |ui| {
+            for (index, constraint) in constraints.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let selected = workbench.selected_constraint == Some(index);
+                    let text = describe_constraint(sketch, constraint);
+                    if ui.selectable_label(selected, text).clicked() {
+                        workbench.selected_constraint = Some(index);
+                        workbench.selected.clear();
+                    }
+                    changed |= value_editor(ui, constraint);
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+        });
+    if let Some(index) = removed {
+        constraints.remove(index);
+        workbench.editing_dimension = None;
+        workbench.selected_constraint = None;
+        changed = true;
+    }
+
+    ui.separator();
+    ui.label("Add constraint");
+    let draft = &mut workbench.constraint_draft;
+    let kind_before = draft.kind;
+    egui::ComboBox::from_id_salt("sketch_constraint_kind")
+        .selected_text(draft.kind.label())
+        .show_ui(ui, |ui| {
+            for kind in ConstraintKind::ALL {
+                ui.selectable_value(&mut draft.kind, kind, kind.label());
+            }
+        });
+    if draft.kind != kind_before {
+        draft.elements = [None; 2];
+    }
+    let mut picked = false;
+    for (slot_index, slot) in draft.kind.slots().iter().enumerate() {
+        let choice = &mut draft.elements[slot_index];
+        let selected = choice
+            .and_then(|id| element_label(sketch, id))
+            .unwrap_or_else(|| "Choose geometry".to_string());
+        egui::ComboBox::from_id_salt(("sketch_constraint_element", slot_index))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for element in sketch.geometry.iter().filter(|e| slot.accepts(e)) {
+                    let label = element_label(sketch, element.id()).unwrap_or_default();
+                    picked |= ui
+                        .selectable_value(choice, Some(element.id()), label)
+                        .changed();
+                }
+            });
+    }
+    if picked {
+        if let Some(value) = draft.measure(sketch) {
+            draft.value = value;
+        }
+    }
+    if draft.kind.has_value() {
+        let suffix = if draft.kind == ConstraintKind::Angle {
+            "°"
+        } else {
+            " mm"
+        };
+        ui.add(
+            egui::DragValue::new(&mut draft.value)
+                .speed(0.1)
+                .suffix(suffix),
+        );
+    }
+    let built = draft.build(sketch);
+    if ui
+        .add_enabled(built.is_some(), egui::Button::new("Add"))
+        .clicked()
+    {
+        if let Some(constraint) = built {
+            constraints.push(constraint);
+            draft.elements = [None; 2];
+            changed = true;
+        }
+    }
+
+    ui.separator();
+    let solve_clicked = ui.button("Solve Constraints").clicked();
+
+    if changed {
+        if let Some((feature_id, mut feature)) = workbench.get_active_sketch_mut(ctx) {
+            feature.sketch.constraints = constraints;
+            if workbench.update_active_sketch(ctx, feature) {
+                ctx.document.mark_feature_dirty(feature_id);
+            }
+        }
+    }
+    if changed || solve_clicked {
+        workbench.solve_active_sketch(ctx);
+    }
+}
+
+/// Value of the dimension double-clicked in the viewport, applied and solved
+/// as it is edited.
+pub(crate) fn dimension_editor(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut SketchWorkbench,
+    sketch: &Sketch,
+) {
+    let Some(index) = workbench.editing_dimension else {
+        return;
+    };
+    let Some(mut constraint) = sketch.constraints.get(index).cloned() else {
+        workbench.editing_dimension = None;
+        return;
+    };
+    let mut changed = false;
+    let mut done = false;
+    ui.group(|ui| {
+        ui.strong("Edit Dimension");
+        ui.horizontal(|ui| {
+            ui.label(describe_constraint(sketch, &constraint));
+            changed = value_editor(ui, &mut constraint);
+        });
+        done = ui.button("Done").clicked();
+    });
+    if done {
+        workbench.editing_dimension = None;
+    }
+    if changed {
+        if let Some((feature_id, mut feature)) = workbench.get_active_sketch_mut(ctx) {
+            feature.sketch.constraints[index] = constraint;
+            if workbench.update_active_sketch(ctx, feature) {
+                ctx.document.mark_feature_dirty(feature_id);
+            }
+        }
+        workbench.solve_active_sketch(ctx);
+    }
+}
+
+/// Editor for the dimension of a constraint, returning whether it changed.
+fn value_editor(ui: &mut egui::Ui, constraint: &mut Constraint) -> bool {
+    match constraint {
+        Constraint::Distance {
+            distance: value, ..
+        }
+        | Constraint::Length { length: value, .. }
+        | Constraint::Radius { radius: value, .. }
+        | Constraint::Diameter {
+            diameter: value, ..
+        } => ui
+            .add(
+                egui::DragValue::new(value)
+                    .speed(0.1)
+                    .range(0.0..=f32::MAX)
+                    .suffix(" mm"),
+            )
+            .changed(),
+        Constraint::Angle { angle_rad, .. } => {
+            let mut degrees = angle_rad.to_degrees();
+            let changed = ui
+                .add(egui::DragValue::new(&mut degrees).speed(0.5).suffix("°"))
+                .changed();
+            if changed {
+                *angle_rad = degrees.to_radians();
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+fn describe_constraint(sketch: &Sketch, constraint: &Constraint) -> String {
+    let name = |id: &Uuid| element_label(sketch, *id).unwrap_or_else(|| "missing".to_string());
+    match constraint {
+        Constraint::FixedPoint { point, .. } => format!("Fixed {}", name(point)),
+        Constraint::Coincident { point1, point2 } => {
+            format!("Coincident {}, {}", name(point1), name(point2))
+        }
+        Constraint::Parallel { line1, line2 } => {
+            format!("Parallel {}, {}", name(line1), name(line2))
+        }
+        Constraint::Perpendicular { line1, line2 } => {
+            format!("Perpendicular {}, {}", name(line1), name(line2))
+        }
+        Constraint::EqualLength { line1, line2 } => {
+            format!("Equal length {}, {}", name(line1), name(line2))
+        }
+        Constraint::Length { line, .. } => format!("Length {}", name(line)),
+        Constraint::EqualRadius { circle1, circle2 } => {
+            format!("Equal radius {}, {}", name(circle1), name(circle2))
+        }
+        Constraint::Radius { circle, .. } => format!("Radius {}", name(circle)),
+        Constraint::Diameter { circle, .. } => format!("Diameter {}", name(circle)),
+        Constraint::PointOnLine { point, line } => {
+            format!("{} on {}", name(point), name(line))
+        }
+        Constraint::PointOnCircle { point, circle } => {
+            format!("{} on {}", name(point), name(circle))
+        }
+        Constraint::Horizontal { element } => format!("Horizontal {}", name(element)),
+        Constraint::Vertical { element } => format!("Vertical {}", name(element)),
+        Constraint::Distance { point1, point2, .. } => {
+            format!("Distance {}, {}", name(point1), name(point2))
+        }
+        Constraint::Angle { line1, line2, .. } => {
+            format!("Angle {}, {}", name(line1), name(line2))
+        }
+        Constraint::Tangent { curve1, curve2 } => {
+            format!("Tangent {}, {}", name(curve1), name(curve2))
+        }
+    }
+}
+
+/// "3. Line": the element's position in the geometry list, as in the left panel.
+fn element_label(sketch: &Sketch, id: Uuid) -> Option<String> {
+    let index = sketch.geometry.iter().position(|e| e.id() == id)?;
+    let kind = match sketch.geometry[index] {
+        GeometryElement::Point(_) => "Point",
+        GeometryElement::Line(_) => "Line",
+        GeometryElement::Arc(_) => "Arc",
+        GeometryElement::Circle(_) => "Circle",
+    };
+    Some(format!("{}. {}", index + 1, kind))
+}
+
+fn line_vector(sketch: &Sketch, id: Uuid) -> Option<glam::Vec2> {
+    match sketch.get_geometry(id)? {
+        GeometryElement::Line(line) => Some(
+            point_coords(sketch, line.end)?.to_glam() - point_coords(sketch, line.start)?.to_glam(),
+        ),
+        _ => None,
+    }
+}
+
+fn circle_radius(sketch: &Sketch, id: Uuid) -> Option<f32> {
+    match sketch.get_geometry(id)? {
+        GeometryElement::Circle(circle) => Some(circle.radius),
+        GeometryElement::Arc(arc) => Some(arc.radius),
+        _ => None,
+    }
+}
+
+/// Hint for pasting SVG paths, and the paste itself when no text field has
+/// focus.
+pub(crate) fn paste_paths(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut SketchWorkbench,
+) {
+    ui.weak("Ctrl+V pastes SVG path data or <path> elements as lines.");
+    if ui.ctx().memory(|memory| memory.focused().is_some()) {
+        return;
+    }
+    let pasted = ui.input(|input| {
+        input.events.iter().find_map(|event| match event {
+            egui::Event::Paste(text) => Some(text.clone()),
+            _ => None,
+        })
+    });
+    if let Some(text) = pasted {
+        if !workbench.paste_paths(&text, ctx) {
+            ctx.log_info("The clipboard holds no SVG path data");
+        }
+    }
+}
+
+/// Reference image list, import button, and placement of the selected image.
+pub(crate) fn reference_images_section(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    sketch_plane: Option<SketchPlane>,
+) {
+    ui.heading("Reference Images");
+    if ui.button("Import Image…").clicked() {
+        ctx.file_open_request = Some(core_document::FileOpenRequest {
+            id: reference::IMPORT_REQUEST.to_string(),
+            filter_name: "Image".to_string(),
+            extensions: ["png", "jpg", "jpeg"].map(String::from).to_vec(),
+            save_name: None,
+            folder: false,
+        });
+    }
+
+    let images = reference::reference_images(ctx.document);
+    for (id, image) in &images {
+        let selected = ctx.active_document_object == Some(*id);
+        if ui.selectable_label(selected, &image.name).clicked() {
+            ctx.active_document_object = Some(*id);
+        }
+    }
+
+    let Some((id, mut image)) = ctx
+        .active_document_object
+        .and_then(|active| images.into_iter().find(|(id, _)| *id == active))
+    else {
+        return;
+    };
+
+    ui.separator();
+    let mut changed = false;
+    egui::Grid::new("reference_image_properties")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Center:");
+            ui.horizontal(|ui| {
+                for (axis, value) in ["X", "Y", "Z"].into_iter().zip(&mut image.plane.origin) {
+                    ui.label(axis);
+                    changed |= ui
+                        .add(egui::DragValue::new(value).speed(0.5).suffix(" mm"))
+                        .changed();
+                }
+            });
+            ui.end_row();
+
+            ui.label("Width:");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut image.width)
+                        .speed(0.5)
+                        .range(1.0..=10_000.0)
+                        .suffix(" mm"),
+                )
+                .changed();
+            ui.end_row();
+
+            ui.label("Opacity:");
+            changed |= ui
+                .add(egui::Slider::new(&mut image.opacity, 0.0..=1.0))
+                .changed();
+            ui.end_row();
+        });
+    ui.weak(format!("Height: {:.1} mm", image.height()));
+    if let Some(plane) = sketch_plane {
+        if ui
+            .button("Move to Sketch Plane")
+            .on_hover_text("Lay the image on the active sketch's plane, keeping its center")
+            .clicked()
+        {
+            let center = plane.world_to_sketch(image.plane.origin);
+            image.plane = SketchPlane {
+                origin: plane.sketch_to_world(center),
+                ..plane
+            };
+            changed = true;
+        }
+    }
+
+    if changed {
+        if let Err(err) = ctx.document.update_feature_data(id, image.to_json()) {
+            ctx.log_error(format!("Failed to update reference image: {err}"));
+        }
+    }
+}
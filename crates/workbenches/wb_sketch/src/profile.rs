@@ -0,0 +1,181 @@
+//! Closed profiles formed by a sketch's curves, for features that sweep them.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::sketch::{Constraint, GeometryElement, Sketch, Vec2D};
+
+/// Curve ends closer than this (in sketch units) meet.
+const JOIN_TOLERANCE: f32 = 1e-4;
+
+/// Segments a full circle is split into; arcs get their share.
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// A line or arc as a polyline between the points its ends are joined at.
+struct Curve {
+    from: Uuid,
+    to: Uuid,
+    points: Vec<Vec2D>,
+}
+
+/// Closed loops of the sketch as polygons, the last point not repeating the
+/// first: every circle, and every chain of lines and arcs whose ends meet
+/// (at a shared point, coincident points, or points at the same position).
+/// Curves that don't close a loop, or meet two or more others at one end,
+/// are left out.
+pub(crate) fn closed_loops(sketch: &Sketch) -> Vec<Vec<Vec2D>> {
+    let positions: HashMap<Uuid, Vec2D> = sketch
+        .geometry
+        .iter()
+        .filter_map(|element| match element {
+            GeometryElement::Point(point) => Some((point.id, point.position)),
+            _ => None,
+        })
+        .collect();
+    let joined = join_points(sketch, &positions);
+    let node = |id: &Uuid| joined.get(id).copied().unwrap_or(*id);
+
+    let mut loops = Vec::new();
+    let mut curves = Vec::new();
+    for element in &sketch.geometry {
+        match element {
+            GeometryElement::Line(line) => {
+                let (Some(start), Some(end)) =
+                    (positions.get(&line.start), positions.get(&line.end))
+                else {
+                    continue;
+                };
+                curves.push(Curve {
+                    from: node(&line.start),
+                    to: node(&line.end),
+                    points: vec![*start, *end],
+                });
+            }
+            GeometryElement::Arc(arc) => {
+                let (Some(center), Some(start), Some(end)) = (
+                    positions.get(&arc.center),
+                    positions.get(&arc.start),
+                    positions.get(&arc.end),
+                ) else {
+                    continue;
+                };
+                // Counterclockwise from start to end, as the arc is drawn.
+                let (from, to) = (*start - *center, *end - *center);
+                let start_angle = from.y.atan2(from.x);
+                let mut sweep = to.y.atan2(to.x) - start_angle;
+                if sweep < 0.0 {
+                    sweep += std::f32::consts::TAU;
+                }
+                let segments = ((CIRCLE_SEGMENTS as f32 * sweep / std::f32::consts::TAU).ceil()
+                    as usize)
+                    .max(1);
+                let mut points = vec![*start];
+                points.extend((1..segments).map(|i| {
+                    let angle = start_angle + sweep * i as f32 / segments as f32;
+                    *center + Vec2D::new(arc.radius * angle.cos(), arc.radius * angle.sin())
+                }));
+                points.push(*end);
+                curves.push(Curve {
+                    from: node(&arc.start),
+                    to: node(&arc.end),
+                    points,
+                });
+            }
+            GeometryElement::Circle(circle) => {
+                let Some(center) = positions.get(&circle.center) else {
+                    continue;
+                };
+                loops.push(
+                    (0..CIRCLE_SEGMENTS)
+                        .map(|i| {
+                            let angle = std::f32::consts::TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+                            *center
+                                + Vec2D::new(
+                                    circle.radius * angle.cos(),
+                                    circle.radius * angle.sin(),
+                                )
+                        })
+                        .collect(),
+                );
+            }
+            GeometryElement::Point(_) => {}
+        }
+    }
+
+    let mut degree: HashMap<Uuid, usize> = HashMap::new();
+    for curve in &curves {
+        *degree.entry(curve.from).or_default() += 1;
+        *degree.entry(curve.to).or_default() += 1;
+    }
+    let usable = |curve: &Curve| degree[&curve.from] == 2 && degree[&curve.to] == 2;
+    let mut visited = HashSet::new();
+    for (first, curve) in curves.iter().enumerate() {
+        if !usable(curve) || !visited.insert(first) {
+            continue;
+        }
+        let mut points = curve.points[..curve.points.len() - 1].to_vec();
+        let mut at = curve.to;
+        let closed = loop {
+            if at == curve.from {
+                break true;
+            }
+            let next = curves.iter().enumerate().find(|(i, next)| {
+                !visited.contains(i) && usable(next) && (next.from == at || next.to == at)
+            });
+            let Some((i, next)) = next else {
+                break false;
+            };
+            visited.insert(i);
+            if next.from == at {
+                points.extend_from_slice(&next.points[..next.points.len() - 1]);
+                at = next.to;
+            } else {
+                points.extend(next.points[1..].iter().rev());
+                at = next.from;
+            }
+        };
+        if closed && points.len() >= 3 {
+            loops.push(points);
+        }
+    }
+    loops
+}
+
+/// Point each point is joined with (through coincident constraints or the
+/// same position), the same one for every point of a group.
+fn join_points(sketch: &Sketch, positions: &HashMap<Uuid, Vec2D>) -> HashMap<Uuid, Uuid> {
+    fn root(parent: &HashMap<Uuid, Uuid>, mut id: Uuid) -> Uuid {
+        while let Some(&up) = parent.get(&id).filter(|up| **up != id) {
+            id = up;
+        }
+        id
+    }
+    fn union(parent: &mut HashMap<Uuid, Uuid>, a: Uuid, b: Uuid) {
+        let (a, b) = (root(parent, a), root(parent, b));
+        if a != b {
+            parent.insert(a, b);
+        }
+    }
+
+    let mut parent: HashMap<Uuid, Uuid> = positions.keys().map(|id| (*id, *id)).collect();
+    for constraint in &sketch.constraints {
+        if let Constraint::Coincident { point1, point2 } = constraint {
+            if parent.contains_key(point1) && parent.contains_key(point2) {
+                union(&mut parent, *point1, *point2);
+            }
+        }
+    }
+    let points: Vec<(Uuid, Vec2D)> = positions.iter().map(|(id, p)| (*id, *p)).collect();
+    for (i, (a, pa)) in points.iter().enumerate() {
+        for (b, pb) in &points[i + 1..] {
+            if (*pa - *pb).to_glam().length() <= JOIN_TOLERANCE {
+                union(&mut parent, *a, *b);
+            }
+        }
+    }
+    positions
+        .keys()
+        .map(|id| (*id, root(&parent, *id)))
+        .collect()
+}
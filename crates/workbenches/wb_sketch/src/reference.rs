@@ -0,0 +1,158 @@
+//! Reference images: pictures placed on a plane to trace parts or blueprints
+//! in sketches. The image file is embedded in the document as an asset.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use core_document::{
+    AssetReference, AssetType, Document, DocumentResult, FeatureError, FeatureId, WorkbenchFeature,
+    WorkbenchId,
+};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::sketch::SketchPlane;
+
+/// Workbench id the reference image features are stored under.
+pub const REFERENCE_IMAGE_WORKBENCH_ID: &str = "wb.reference_image";
+
+/// File dialog request id for importing an image.
+pub(crate) const IMPORT_REQUEST: &str = "sketch.reference_image.import";
+
+/// Width a newly imported image is given.
+const DEFAULT_WIDTH_MM: f32 = 100.0;
+
+/// An image shown on a plane of the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceImageFeature {
+    pub name: String,
+    /// Asset holding the PNG or JPEG file.
+    pub asset: Uuid,
+    /// Plane the image lies in; the origin is the image center and the X axis
+    /// runs along its width.
+    pub plane: SketchPlane,
+    /// Width in model units (mm).
+    pub width: f32,
+    /// Height over width of the picture.
+    pub aspect: f32,
+    /// 0 (invisible) to 1 (opaque).
+    pub opacity: f32,
+}
+
+impl ReferenceImageFeature {
+    pub fn height(&self) -> f32 {
+        self.width * self.aspect
+    }
+
+    /// World positions of the top-left, top-right, bottom-right, and
+    /// bottom-left corners.
+    pub fn corners(&self) -> [[f32; 3]; 4] {
+        let center = Vec3::from_array(self.plane.origin);
+        let half_x = Vec3::from_array(self.plane.x_axis) * self.width * 0.5;
+        let half_y = Vec3::from_array(self.plane.y_axis) * self.height() * 0.5;
+        [
+            center - half_x + half_y,
+            center + half_x + half_y,
+            center + half_x - half_y,
+            center - half_x - half_y,
+        ]
+        .map(|corner| corner.to_array())
+    }
+}
+
+impl WorkbenchFeature for ReferenceImageFeature {
+    fn workbench_id() -> WorkbenchId {
+        WorkbenchId::from(REFERENCE_IMAGE_WORKBENCH_ID)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("ReferenceImageFeature should always serialize")
+    }
+
+    fn from_json(value: &serde_json::Value) -> DocumentResult<Self> {
+        serde_json::from_value(value.clone()).map_err(|e| {
+            core_document::DocumentError::Feature(FeatureError::Deserialization(e.to_string()))
+        })
+    }
+
+    fn dependencies(&self) -> Vec<FeatureId> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Reference images of the document, oldest first.
+pub fn reference_images(document: &Document) -> Vec<(FeatureId, ReferenceImageFeature)> {
+    let mut nodes: Vec<_> = document
+        .feature_tree()
+        .all_nodes()
+        .filter(|(_, node)| node.workbench_id.as_str() == REFERENCE_IMAGE_WORKBENCH_ID)
+        .collect();
+    nodes.sort_by_key(|(_, node)| node.created_at);
+    nodes
+        .into_iter()
+        .filter_map(|(id, node)| {
+            ReferenceImageFeature::from_json(&node.data)
+                .ok()
+                .map(|image| (*id, image))
+        })
+        .collect()
+}
+
+/// Embed the image at `path` in the document and build a feature showing it
+/// centered on `plane`.
+pub(crate) fn import(
+    document: &mut Document,
+    path: &Path,
+    plane: SketchPlane,
+) -> Result<ReferenceImageFeature, String> {
+    let asset_type = AssetType::from_extension(
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default(),
+    );
+    if !asset_type.is_image() {
+        return Err("only PNG and JPEG images are supported".to_string());
+    }
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    let (width_px, height_px) = image::ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|err| err.to_string())?
+        .into_dimensions()
+        .map_err(|err| err.to_string())?;
+    if width_px == 0 || height_px == 0 {
+        return Err("the image is empty".to_string());
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let asset = AssetReference::new(
+        "",
+        asset_type,
+        serde_json::json!({
+            "file_name": file_name,
+            "width_px": width_px,
+            "height_px": height_px,
+        }),
+    );
+    let asset_id = document.add_asset_with_data(asset, bytes);
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "reference image".to_string());
+    Ok(ReferenceImageFeature {
+        name,
+        asset: asset_id,
+        plane,
+        width: DEFAULT_WIDTH_MM,
+        aspect: height_px as f32 / width_px as f32,
+        opacity: 0.5,
+    })
+}
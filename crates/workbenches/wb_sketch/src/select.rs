@@ -0,0 +1,264 @@
+//! The `sketch.select` tool: picking geometry of the active sketch under the
+//! cursor or inside a box, the highlight drawn for it, and dragging points.
+
+use core_document::{ScreenSpaceOverlay, WorkbenchRuntimeContext};
+use glam::Vec2;
+use uuid::Uuid;
+
+use crate::point_coords;
+use crate::sketch::{GeometryElement, Sketch, SketchPlane, Vec2D};
+
+const HOVER_COLOR: [f32; 3] = [1.0, 0.9, 0.3];
+const SELECTED_COLOR: [f32; 3] = [0.3, 0.7, 1.0];
+const BOX_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+const THICKNESS: f32 = 3.0;
+/// How close (pixels) the cursor must be to pick an element.
+const PICK_RADIUS: f32 = 8.0;
+/// Half the size (pixels) of the square marking a point.
+const POINT_MARKER: f32 = 4.0;
+/// Presses that travel less than this (pixels) are clicks, not box drags.
+pub(crate) const DRAG_THRESHOLD: f32 = 4.0;
+const CIRCLE_SEGMENTS: usize = 48;
+const ARC_SEGMENTS: usize = 16;
+
+/// What a left-button drag with the select tool is doing.
+pub(crate) enum SelectDrag {
+    /// Moving a point. `original` is the sketch before the drag, restored
+    /// when the drag is cancelled.
+    Point {
+        point: Uuid,
+        original: Box<Sketch>,
+        moved: bool,
+    },
+    /// Selecting everything inside the box between two viewport positions.
+    Box { start: Vec2, current: Vec2 },
+}
+
+/// The element under `pos` (viewport pixels); points win over the curves
+/// through them.
+pub(crate) fn pick(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    pos: Vec2,
+) -> Option<Uuid> {
+    let mut best: Option<(bool, f32, Uuid)> = None;
+    for element in &sketch.geometry {
+        let Some(outline) = projected(sketch, plane, ctx, element) else {
+            continue;
+        };
+        let distance = polyline_distance(&outline, pos);
+        if distance > PICK_RADIUS {
+            continue;
+        }
+        let is_point = matches!(element, GeometryElement::Point(_));
+        let better = match best {
+            None => true,
+            Some((best_is_point, best_distance, _)) => {
+                (is_point && !best_is_point)
+                    || (is_point == best_is_point && distance < best_distance)
+            }
+        };
+        if better {
+            best = Some((is_point, distance, element.id()));
+        }
+    }
+    best.map(|(_, _, id)| id)
+}
+
+/// Elements lying entirely inside the box between `a` and `b` (viewport pixels).
+pub(crate) fn in_box(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    a: Vec2,
+    b: Vec2,
+) -> Vec<Uuid> {
+    let (min, max) = (a.min(b), a.max(b));
+    sketch
+        .geometry
+        .iter()
+        .filter(|element| {
+            projected(sketch, plane, ctx, element).is_some_and(|outline| {
+                outline
+                    .iter()
+                    .all(|p| p.cmpge(min).all() && p.cmple(max).all())
+            })
+        })
+        .map(GeometryElement::id)
+        .collect()
+}
+
+/// Highlight of the selected and hovered elements, and the selection box.
+pub(crate) fn overlays(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    selected: &[Uuid],
+    hovered: Option<Uuid>,
+    drag: Option<&SelectDrag>,
+) -> Vec<ScreenSpaceOverlay> {
+    let mut lines = Vec::new();
+    let mut highlight =
+        |id: Uuid, color: [f32; 3]| {
+            let Some(element) = sketch.get_geometry(id) else {
+                return;
+            };
+            let Some(outline) = projected(sketch, plane, ctx, element) else {
+                return;
+            };
+            let segments = match outline.as_slice() {
+                [p] => rectangle(*p - POINT_MARKER, *p + POINT_MARKER),
+                _ => outline.windows(2).map(|w| (w[0], w[1])).collect(),
+            };
+            lines.extend(segments.into_iter().map(|(a, b)| {
+                ScreenSpaceOverlay::new(a.to_array(), b.to_array(), color, THICKNESS)
+            }));
+        };
+    if let Some(id) = hovered.filter(|id| !selected.contains(id)) {
+        highlight(id, HOVER_COLOR);
+    }
+    for &id in selected {
+        highlight(id, SELECTED_COLOR);
+    }
+    if let Some(SelectDrag::Box { start, current }) = drag {
+        lines.extend(
+            rectangle(*start, *current)
+                .into_iter()
+                .map(|(a, b)| ScreenSpaceOverlay::new(a.to_array(), b.to_array(), BOX_COLOR, 1.0)),
+        );
+    }
+    lines
+}
+
+/// Move `point` to `to`, keeping the arcs through it round: dragging an arc's
+/// center carries its end points along, dragging an end point changes the
+/// radius and slides the other end point onto it.
+pub(crate) fn move_point(sketch: &mut Sketch, point: Uuid, to: Vec2D) {
+    let Some(from) = point_coords(sketch, point) else {
+        return;
+    };
+    let coords = |id: Uuid| point_coords(sketch, id).map(Vec2D::to_glam);
+    let mut moves = vec![(point, to.to_glam())];
+    let mut radii = Vec::new();
+    for element in &sketch.geometry {
+        let GeometryElement::Arc(arc) = element else {
+            continue;
+        };
+        if arc.center == point {
+            let delta = to.to_glam() - from.to_glam();
+            for end in [arc.start, arc.end] {
+                if let Some(position) = coords(end) {
+                    moves.push((end, position + delta));
+                }
+            }
+        } else if arc.start == point || arc.end == point {
+            let Some(center) = coords(arc.center) else {
+                continue;
+            };
+            let radius = to.to_glam().distance(center);
+            let other = if arc.start == point {
+                arc.end
+            } else {
+                arc.start
+            };
+            if let Some(position) = coords(other).filter(|_| other != point) {
+                let direction = (position - center).try_normalize().unwrap_or(Vec2::X);
+                moves.push((other, center + direction * radius));
+            }
+            radii.push((arc.id, radius));
+        }
+    }
+    for (id, position) in moves {
+        if let Some(GeometryElement::Point(p)) = sketch.get_geometry_mut(id) {
+            p.position = Vec2D::from_glam(position);
+        }
+    }
+    for (id, radius) in radii {
+        if let Some(GeometryElement::Arc(arc)) = sketch.get_geometry_mut(id) {
+            arc.radius = radius;
+        }
+    }
+}
+
+/// Viewport polyline of an element (a single position for a point), or None
+/// if any of it is behind the camera.
+fn projected(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    ctx: &WorkbenchRuntimeContext,
+    element: &GeometryElement,
+) -> Option<Vec<Vec2>> {
+    outline(sketch, element)?
+        .into_iter()
+        .map(|p| {
+            let world = plane.sketch_to_world(Vec2D::from_glam(p));
+            ctx.world_to_viewport(world).map(|(x, y)| Vec2::new(x, y))
+        })
+        .collect()
+}
+
+/// Sketch-space polyline of an element, as drawn by the renderer.
+fn outline(sketch: &Sketch, element: &GeometryElement) -> Option<Vec<Vec2>> {
+    let coords = |id: Uuid| point_coords(sketch, id).map(Vec2D::to_glam);
+    let around = |center: Vec2, radius: f32, start: f32, sweep: f32, segments: usize| {
+        (0..=segments)
+            .map(|i| {
+                let angle = start + sweep * i as f32 / segments as f32;
+                center + Vec2::from_angle(angle) * radius
+            })
+            .collect()
+    };
+    Some(match element {
+        GeometryElement::Point(point) => vec![point.position.to_glam()],
+        GeometryElement::Line(line) => vec![coords(line.start)?, coords(line.end)?],
+        GeometryElement::Circle(circle) => around(
+            coords(circle.center)?,
+            circle.radius,
+            0.0,
+            std::f32::consts::TAU,
+            CIRCLE_SEGMENTS,
+        ),
+        GeometryElement::Arc(arc) => {
+            let center = coords(arc.center)?;
+            let (start, end) = (coords(arc.start)? - center, coords(arc.end)? - center);
+            let start_angle = start.y.atan2(start.x);
+            let mut end_angle = end.y.atan2(end.x);
+            if end_angle < start_angle {
+                end_angle += std::f32::consts::TAU;
+            }
+            around(
+                center,
+                arc.radius,
+                start_angle,
+                end_angle - start_angle,
+                ARC_SEGMENTS,
+            )
+        }
+    })
+}
+
+fn polyline_distance(points: &[Vec2], pos: Vec2) -> f32 {
+    match points {
+        [p] => p.distance(pos),
+        _ => points
+            .windows(2)
+            .map(|w| segment_distance(w[0], w[1], pos))
+            .fold(f32::INFINITY, f32::min),
+    }
+}
+
+fn segment_distance(a: Vec2, b: Vec2, pos: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 1e-12 {
+        ((pos - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (a + ab * t).distance(pos)
+}
+
+fn rectangle(a: Vec2, b: Vec2) -> Vec<(Vec2, Vec2)> {
+    let (c, d) = (Vec2::new(b.x, a.y), Vec2::new(a.x, b.y));
+    vec![(a, c), (c, b), (b, d), (d, a)]
+}
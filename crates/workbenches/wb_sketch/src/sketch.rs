@@ -1,5 +1,7 @@
 //! Sketch data model: 2D geometry primitives and constraints.
 
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -91,6 +93,40 @@ impl Sketch {
     pub fn get_geometry_mut(&mut self, id: Uuid) -> Option<&mut GeometryElement> {
         self.geometry.iter_mut().find(|g| g.id() == id)
     }
+
+    /// Remove geometry elements along with everything that depends on them:
+    /// curves defined by a removed point, points left unused by removed
+    /// curves, and constraints on any removed element. Returns how many
+    /// elements were removed.
+    pub fn remove_geometry(&mut self, ids: &[Uuid]) -> usize {
+        let mut removed: HashSet<Uuid> = ids.iter().copied().collect();
+        for element in &self.geometry {
+            if element.points().iter().any(|p| removed.contains(p)) {
+                removed.insert(element.id());
+            }
+        }
+        let freed: Vec<Uuid> = self
+            .geometry
+            .iter()
+            .filter(|element| removed.contains(&element.id()))
+            .flat_map(GeometryElement::points)
+            .collect();
+        for point in freed {
+            let still_used = self.geometry.iter().any(|element| {
+                !removed.contains(&element.id()) && element.points().contains(&point)
+            });
+            if !still_used {
+                removed.insert(point);
+            }
+        }
+
+        let before = self.geometry.len();
+        self.geometry
+            .retain(|element| !removed.contains(&element.id()));
+        self.constraints
+            .retain(|constraint| constraint.elements().iter().all(|id| !removed.contains(id)));
+        before - self.geometry.len()
+    }
 }
 
 /// Reference plane for a sketch (2D coordinate system in 3D space).
@@ -106,6 +142,25 @@ pub struct SketchPlane {
     pub y_axis: [f32; 3],
 }
 
+impl SketchPlane {
+    /// Convert a 2D sketch position to world coordinates.
+    pub fn sketch_to_world(&self, pos: Vec2D) -> [f32; 3] {
+        let origin = glam::Vec3::from_array(self.origin);
+        let x_axis = glam::Vec3::from_array(self.x_axis);
+        let y_axis = glam::Vec3::from_array(self.y_axis);
+        (origin + x_axis * pos.x + y_axis * pos.y).to_array()
+    }
+
+    /// Project a world position onto the plane, returning 2D sketch coordinates.
+    pub fn world_to_sketch(&self, world: [f32; 3]) -> Vec2D {
+        let offset = glam::Vec3::from_array(world) - glam::Vec3::from_array(self.origin);
+        Vec2D::new(
+            offset.dot(glam::Vec3::from_array(self.x_axis)),
+            offset.dot(glam::Vec3::from_array(self.y_axis)),
+        )
+    }
+}
+
 impl Default for SketchPlane {
     fn default() -> Self {
         // Default to XY plane at origin
@@ -136,6 +191,16 @@ impl GeometryElement {
             GeometryElement::Circle(c) => c.id,
         }
     }
+
+    /// IDs of the points that define this element (none for a point).
+    pub fn points(&self) -> Vec<Uuid> {
+        match self {
+            GeometryElement::Point(_) => Vec::new(),
+            GeometryElement::Line(l) => vec![l.start, l.end],
+            GeometryElement::Arc(a) => vec![a.center, a.start, a.end],
+            GeometryElement::Circle(c) => vec![c.center],
+        }
+    }
 }
 
 /// A point in 2D sketch space.
@@ -240,6 +305,8 @@ pub enum Constraint {
     EqualRadius { circle1: Uuid, circle2: Uuid },
     /// Circle/arc has a specific radius.
     Radius { circle: Uuid, radius: f32 },
+    /// Circle/arc has a specific diameter.
+    Diameter { circle: Uuid, diameter: f32 },
     /// Point lies on a line.
     PointOnLine { point: Uuid, line: Uuid },
     /// Point lies on a circle/arc.
@@ -260,4 +327,32 @@ pub enum Constraint {
         line2: Uuid,
         angle_rad: f32,
     },
+    /// A line and a circle/arc, or two circles/arcs, touch without crossing.
+    Tangent { curve1: Uuid, curve2: Uuid },
+}
+
+impl Constraint {
+    /// IDs of the geometry elements the constraint refers to.
+    pub fn elements(&self) -> Vec<Uuid> {
+        match *self {
+            Constraint::FixedPoint { point, .. } => vec![point],
+            Constraint::Coincident { point1, point2 }
+            | Constraint::Distance { point1, point2, .. } => vec![point1, point2],
+            Constraint::Parallel { line1, line2 }
+            | Constraint::Perpendicular { line1, line2 }
+            | Constraint::EqualLength { line1, line2 }
+            | Constraint::Angle { line1, line2, .. } => vec![line1, line2],
+            Constraint::Length { line, .. } => vec![line],
+            Constraint::EqualRadius { circle1, circle2 } => vec![circle1, circle2],
+            Constraint::Radius { circle, .. } | Constraint::Diameter { circle, .. } => {
+                vec![circle]
+            }
+            Constraint::PointOnLine { point, line } => vec![point, line],
+            Constraint::PointOnCircle { point, circle } => vec![point, circle],
+            Constraint::Horizontal { element } | Constraint::Vertical { element } => {
+                vec![element]
+            }
+            Constraint::Tangent { curve1, curve2 } => vec![curve1, curve2],
+        }
+    }
 }
@@ -0,0 +1,151 @@
+//! Point snapping for sketch tools.
+//!
+//! Object snaps (existing geometry) are tried first in the user's priority order,
+//! then the grid. Distances are measured in screen pixels so the snap radius feels
+//! the same at every zoom level.
+
+use core_document::{ObjectSnapKind, WorkbenchRuntimeContext};
+
+use crate::sketch::{GeometryElement, Sketch, SketchPlane, Vec2D};
+
+/// What a picked point was snapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SnapTarget {
+    Object(ObjectSnapKind),
+    Grid,
+}
+
+/// Snap a raw sketch-space position according to the host's snap options.
+pub(crate) fn snap_point(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    raw: Vec2D,
+    ctx: &WorkbenchRuntimeContext,
+) -> (Vec2D, Option<SnapTarget>) {
+    if let Some((kind, pos)) = object_snap(sketch, plane, raw, ctx) {
+        return (pos, Some(SnapTarget::Object(kind)));
+    }
+
+    let spacing = ctx.snap.grid_spacing;
+    if ctx.snap.snap_to_grid && spacing > 0.0 {
+        let snapped = Vec2D::new(
+            (raw.x / spacing).round() * spacing,
+            (raw.y / spacing).round() * spacing,
+        );
+        return (snapped, Some(SnapTarget::Grid));
+    }
+
+    (raw, None)
+}
+
+fn object_snap(
+    sketch: &Sketch,
+    plane: &SketchPlane,
+    raw: Vec2D,
+    ctx: &WorkbenchRuntimeContext,
+) -> Option<(ObjectSnapKind, Vec2D)> {
+    if ctx.snap.object_snaps.is_empty() {
+        return None;
+    }
+    let cursor = ctx.world_to_viewport(plane.sketch_to_world(raw))?;
+    let radius = ctx.snap.snap_radius_px;
+
+    for &kind in &ctx.snap.object_snaps {
+        let nearest = snap_candidates(sketch, kind)
+            .into_iter()
+            .filter_map(|pos| {
+                let screen = ctx.world_to_viewport(plane.sketch_to_world(pos))?;
+                let dist = ((screen.0 - cursor.0).powi(2) + (screen.1 - cursor.1).powi(2)).sqrt();
+                (dist <= radius).then_some((dist, pos))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, pos)) = nearest {
+            return Some((kind, pos));
+        }
+    }
+    None
+}
+
+fn snap_candidates(sketch: &Sketch, kind: ObjectSnapKind) -> Vec<Vec2D> {
+    let point = |id| match sketch.get_geometry(id) {
+        Some(GeometryElement::Point(p)) => Some(p.position),
+        _ => None,
+    };
+
+    match kind {
+        ObjectSnapKind::Endpoint => {
+            // Every sketch point except circle/arc centers (those are Center snaps).
+            let centers: Vec<_> = sketch
+                .geometry
+                .iter()
+                .filter_map(|g| match g {
+                    GeometryElement::Circle(c) => Some(c.center),
+                    GeometryElement::Arc(a) => Some(a.center),
+                    _ => None,
+                })
+                .collect();
+            sketch
+                .geometry
+                .iter()
+                .filter_map(|g| match g {
+                    GeometryElement::Point(p) if !centers.contains(&p.id) => Some(p.position),
+                    _ => None,
+                })
+                .collect()
+        }
+        ObjectSnapKind::Midpoint => sketch
+            .geometry
+            .iter()
+            .filter_map(|g| match g {
+                GeometryElement::Line(l) => {
+                    let (a, b) = (point(l.start)?, point(l.end)?);
+                    Some(Vec2D::new((a.x + b.x) * 0.5, (a.y + b.y) * 0.5))
+                }
+                _ => None,
+            })
+            .collect(),
+        ObjectSnapKind::Center => sketch
+            .geometry
+            .iter()
+            .filter_map(|g| match g {
+                GeometryElement::Circle(c) => point(c.center),
+                GeometryElement::Arc(a) => point(a.center),
+                _ => None,
+            })
+            .collect(),
+        ObjectSnapKind::Intersection => {
+            let segments: Vec<_> = sketch
+                .geometry
+                .iter()
+                .filter_map(|g| match g {
+                    GeometryElement::Line(l) => Some((point(l.start)?, point(l.end)?)),
+                    _ => None,
+                })
+                .collect();
+            let mut hits = Vec::new();
+            for (i, a) in segments.iter().enumerate() {
+                for b in &segments[i + 1..] {
+                    if let Some(hit) = segment_intersection(*a, *b) {
+                        hits.push(hit);
+                    }
+                }
+            }
+            hits
+        }
+    }
+}
+
+/// Intersection point of two segments, if they cross (parallel segments never do).
+fn segment_intersection((p1, p2): (Vec2D, Vec2D), (q1, q2): (Vec2D, Vec2D)) -> Option<Vec2D> {
+    let r = p2 - p1;
+    let s = q2 - q1;
+    let denom = r.x * s.y - r.y * s.x;
+    if denom.abs() < 1e-9 {
+        return None;
+    }
+    let qp = q1 - p1;
+    let t = (qp.x * s.y - qp.y * s.x) / denom;
+    let u = (qp.x * r.y - qp.y * r.x) / denom;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u))
+        .then(|| Vec2D::new(p1.x + r.x * t, p1.y + r.y * t))
+}
@@ -0,0 +1,598 @@
+//! 2D constraint solver for sketches.
+//!
+//! Point coordinates and circle/arc radii are the unknowns; every constraint
+//! adds one or two equations that are zero when it holds. The solver runs
+//! damped Newton steps with the minimum-norm update, so under-constrained
+//! geometry moves as little as possible. The rank of the equations at the
+//! result tells how many degrees of freedom are left and how many
+//! constraints are redundant.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::sketch::{Constraint, GeometryElement, Sketch, Vec2D};
+
+const MAX_ITERATIONS: usize = 100;
+/// Residual (in sketch units, or radians for angles) below which an equation holds.
+const TOLERANCE: f64 = 1e-6;
+/// Pivot size below which an equation counts as dependent on the others.
+const RANK_TOLERANCE: f64 = 1e-8;
+
+/// Result of solving a sketch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SolveStatus {
+    /// Every constraint holds and no geometry can move.
+    FullyConstrained,
+    /// Every constraint holds; the geometry has this many degrees of freedom left.
+    UnderConstrained { dof: usize },
+    /// Every constraint holds, but this many of them repeat what others already fix.
+    Redundant { count: usize },
+    /// The constraints contradict each other and could not all be met.
+    Conflicting,
+}
+
+impl SolveStatus {
+    pub(crate) fn describe(self) -> String {
+        match self {
+            SolveStatus::FullyConstrained => "Fully constrained".to_string(),
+            SolveStatus::UnderConstrained { dof } => {
+                format!("Under-constrained: {dof} degrees of freedom")
+            }
+            SolveStatus::Redundant { count } => {
+                format!("Over-constrained: {count} redundant constraints")
+            }
+            SolveStatus::Conflicting => "Over-constrained: conflicting constraints".to_string(),
+        }
+    }
+}
+
+/// Move the sketch geometry so its constraints hold, returning how
+/// constrained the result is. Constraints referring to missing geometry are
+/// ignored.
+pub(crate) fn solve(sketch: &mut Sketch) -> SolveStatus {
+    let system = System::new(sketch);
+    let mut values = system.initial.clone();
+    let mut residuals = system.residuals(&values);
+
+    for _ in 0..MAX_ITERATIONS {
+        if max_abs(&residuals) < TOLERANCE {
+            break;
+        }
+        let jacobian = system.jacobian(&values);
+        let Some(step) = min_norm_step(&jacobian, &residuals) else {
+            break;
+        };
+        // Halve the step until it reduces the error (damping for far starts).
+        let error = norm(&residuals);
+        let mut scale = 1.0;
+        let mut improved = false;
+        while scale > 1e-4 {
+            let candidate: Vec<f64> = values
+                .iter()
+                .zip(&step)
+                .map(|(value, delta)| value + delta * scale)
+                .collect();
+            let candidate_residuals = system.residuals(&candidate);
+            if norm(&candidate_residuals) < error {
+                values = candidate;
+                residuals = candidate_residuals;
+                improved = true;
+                break;
+            }
+            scale *= 0.5;
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    let converged = max_abs(&residuals) < TOLERANCE;
+    if converged {
+        system.write_back(sketch, &values);
+    }
+    let rank = rank(system.jacobian(&values));
+    let status = if !converged {
+        SolveStatus::Conflicting
+    } else if rank < system.equations.len() {
+        SolveStatus::Redundant {
+            count: system.equations.len() - rank,
+        }
+    } else if rank == values.len() {
+        SolveStatus::FullyConstrained
+    } else {
+        SolveStatus::UnderConstrained {
+            dof: values.len() - rank,
+        }
+    };
+    sketch.is_fully_constrained = status == SolveStatus::FullyConstrained;
+    status
+}
+
+/// Index of an unknown in the value vector.
+type Var = usize;
+
+/// One equation of the system, in terms of unknowns.
+enum Equation {
+    /// `a - value`.
+    Fix { a: Var, value: f64 },
+    /// `a - b`.
+    Equal { a: Var, b: Var },
+    /// Distance between points `p` and `q`, minus `value`.
+    Distance {
+        p: [Var; 2],
+        q: [Var; 2],
+        value: f64,
+    },
+    /// Difference of the lengths of `p1`..`q1` and `p2`..`q2`.
+    EqualDistance {
+        first: [[Var; 2]; 2],
+        second: [[Var; 2]; 2],
+    },
+    /// Distance between points `p` and `q`, minus radius `r`.
+    OnCircle {
+        p: [Var; 2],
+        center: [Var; 2],
+        r: Var,
+    },
+    /// Sine of the angle between two directions.
+    Parallel {
+        first: [[Var; 2]; 2],
+        second: [[Var; 2]; 2],
+    },
+    /// Cosine of the angle between two directions.
+    Perpendicular {
+        first: [[Var; 2]; 2],
+        second: [[Var; 2]; 2],
+    },
+    /// Angle from the first direction to the second, minus `value` (radians).
+    Angle {
+        first: [[Var; 2]; 2],
+        second: [[Var; 2]; 2],
+        value: f64,
+    },
+    /// Signed distance from `p` to the line through `a` and `b`.
+    OnLine { p: [Var; 2], line: [[Var; 2]; 2] },
+    /// Distance from the circle center to the line, minus its radius.
+    TangentLine {
+        line: [[Var; 2]; 2],
+        center: [Var; 2],
+        r: Var,
+    },
+    /// Circles touching from outside or inside, whichever is closer.
+    TangentCircles {
+        first: ([Var; 2], Var),
+        second: ([Var; 2], Var),
+    },
+}
+
+struct System {
+    initial: Vec<f64>,
+    points: HashMap<Uuid, [Var; 2]>,
+    radii: HashMap<Uuid, Var>,
+    lines: HashMap<Uuid, [[Var; 2]; 2]>,
+    centers: HashMap<Uuid, [Var; 2]>,
+    equations: Vec<Equation>,
+}
+
+impl System {
+    fn new(sketch: &Sketch) -> Self {
+        let mut system = System {
+            initial: Vec::new(),
+            points: HashMap::new(),
+            radii: HashMap::new(),
+            lines: HashMap::new(),
+            centers: HashMap::new(),
+            equations: Vec::new(),
+        };
+        for element in &sketch.geometry {
+            if let GeometryElement::Point(point) = element {
+                let x = system.push(point.position.x);
+                let y = system.push(point.position.y);
+                system.points.insert(point.id, [x, y]);
+            }
+        }
+        for element in &sketch.geometry {
+            match element {
+                GeometryElement::Point(_) => {}
+                GeometryElement::Line(line) => {
+                    if let (Some(&start), Some(&end)) =
+                        (system.points.get(&line.start), system.points.get(&line.end))
+                    {
+                        system.lines.insert(line.id, [start, end]);
+                    }
+                }
+                GeometryElement::Circle(circle) => {
+                    if let Some(&center) = system.points.get(&circle.center) {
+                        let r = system.push(circle.radius);
+                        system.radii.insert(circle.id, r);
+                        system.centers.insert(circle.id, center);
+                    }
+                }
+                GeometryElement::Arc(arc) => {
+                    let (Some(&center), Some(&start), Some(&end)) = (
+                        system.points.get(&arc.center),
+                        system.points.get(&arc.start),
+                        system.points.get(&arc.end),
+                    ) else {
+                        continue;
+                    };
+                    let r = system.push(arc.radius);
+                    system.radii.insert(arc.id, r);
+                    system.centers.insert(arc.id, center);
+                    // The arc's end points stay on its circle.
+                    system.equations.push(Equation::OnCircle {
+                        p: start,
+                        center,
+                        r,
+                    });
+                    system
+                        .equations
+                        .push(Equation::OnCircle { p: end, center, r });
+                }
+            }
+        }
+        for constraint in &sketch.constraints {
+            system.add_constraint(constraint);
+        }
+        system
+    }
+
+    fn push(&mut self, value: f32) -> Var {
+        self.initial.push(f64::from(value));
+        self.initial.len() - 1
+    }
+
+    fn circle(&self, id: &Uuid) -> Option<([Var; 2], Var)> {
+        Some((*self.centers.get(id)?, *self.radii.get(id)?))
+    }
+
+    fn add_constraint(&mut self, constraint: &Constraint) {
+        let points = &self.points;
+        let lines = &self.lines;
+        let mut equations = Vec::new();
+        match constraint {
+            Constraint::FixedPoint { point, position } => {
+                if let Some(&[x, y]) = points.get(point) {
+                    equations.push(Equation::Fix {
+                        a: x,
+                        value: f64::from(position.x),
+                    });
+                    equations.push(Equation::Fix {
+                        a: y,
+                        value: f64::from(position.y),
+                    });
+                }
+            }
+            Constraint::Coincident { point1, point2 } => {
+                if let (Some(&[x1, y1]), Some(&[x2, y2])) = (points.get(point1), points.get(point2))
+                {
+                    equations.push(Equation::Equal { a: x1, b: x2 });
+                    equations.push(Equation::Equal { a: y1, b: y2 });
+                }
+            }
+            Constraint::Horizontal { element } => {
+                if let Some(&[[_, y1], [_, y2]]) = lines.get(element) {
+                    equations.push(Equation::Equal { a: y1, b: y2 });
+                }
+            }
+            Constraint::Vertical { element } => {
+                if let Some(&[[x1, _], [x2, _]]) = lines.get(element) {
+                    equations.push(Equation::Equal { a: x1, b: x2 });
+                }
+            }
+            Constraint::Parallel { line1, line2 } => {
+                if let (Some(&first), Some(&second)) = (lines.get(line1), lines.get(line2)) {
+                    equations.push(Equation::Parallel { first, second });
+                }
+            }
+            Constraint::Perpendicular { line1, line2 } => {
+                if let (Some(&first), Some(&second)) = (lines.get(line1), lines.get(line2)) {
+                    equations.push(Equation::Perpendicular { first, second });
+                }
+            }
+            Constraint::Angle {
+                line1,
+                line2,
+                angle_rad,
+            } => {
+                if let (Some(&first), Some(&second)) = (lines.get(line1), lines.get(line2)) {
+                    equations.push(Equation::Angle {
+                        first,
+                        second,
+                        value: f64::from(*angle_rad),
+                    });
+                }
+            }
+            Constraint::EqualLength { line1, line2 } => {
+                if let (Some(&first), Some(&second)) = (lines.get(line1), lines.get(line2)) {
+                    equations.push(Equation::EqualDistance { first, second });
+                }
+            }
+            Constraint::Length { line, length } => {
+                if let Some(&[p, q]) = lines.get(line) {
+                    equations.push(Equation::Distance {
+                        p,
+                        q,
+                        value: f64::from(*length),
+                    });
+                }
+            }
+            Constraint::Distance {
+                point1,
+                point2,
+                distance,
+            } => {
+                if let (Some(&p), Some(&q)) = (points.get(point1), points.get(point2)) {
+                    equations.push(Equation::Distance {
+                        p,
+                        q,
+                        value: f64::from(*distance),
+                    });
+                }
+            }
+            Constraint::EqualRadius { circle1, circle2 } => {
+                if let (Some(&a), Some(&b)) = (self.radii.get(circle1), self.radii.get(circle2)) {
+                    equations.push(Equation::Equal { a, b });
+                }
+            }
+            Constraint::Radius { circle, radius } => {
+                if let Some(&a) = self.radii.get(circle) {
+                    equations.push(Equation::Fix {
+                        a,
+                        value: f64::from(*radius),
+                    });
+                }
+            }
+            Constraint::Diameter { circle, diameter } => {
+                if let Some(&a) = self.radii.get(circle) {
+                    equations.push(Equation::Fix {
+                        a,
+                        value: f64::from(*diameter) / 2.0,
+                    });
+                }
+            }
+            Constraint::PointOnLine { point, line } => {
+                if let (Some(&p), Some(&line)) = (points.get(point), lines.get(line)) {
+                    equations.push(Equation::OnLine { p, line });
+                }
+            }
+            Constraint::PointOnCircle { point, circle } => {
+                if let (Some(&p), Some((center, r))) = (points.get(point), self.circle(circle)) {
+                    equations.push(Equation::OnCircle { p, center, r });
+                }
+            }
+            Constraint::Tangent { curve1, curve2 } => {
+                let line = lines.get(curve1).or_else(|| lines.get(curve2)).copied();
+                let circle1 = self.circle(curve1);
+                let circle2 = self.circle(curve2);
+                match (line, circle1, circle2) {
+                    (Some(line), Some((center, r)), None)
+                    | (Some(line), None, Some((center, r))) => {
+                        equations.push(Equation::TangentLine { line, center, r });
+                    }
+                    (None, Some(first), Some(second)) => {
+                        equations.push(Equation::TangentCircles { first, second });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.equations.extend(equations);
+    }
+
+    fn residuals(&self, values: &[f64]) -> Vec<f64> {
+        self.equations
+            .iter()
+            .map(|equation| equation.residual(values))
+            .collect()
+    }
+
+    /// Derivatives of every equation by every unknown (central differences).
+    fn jacobian(&self, values: &[f64]) -> Vec<Vec<f64>> {
+        let mut rows = vec![vec![0.0; values.len()]; self.equations.len()];
+        let mut probe = values.to_vec();
+        for var in 0..values.len() {
+            let h = 1e-7 * values[var].abs().max(1.0);
+            probe[var] = values[var] + h;
+            let forward = self.residuals(&probe);
+            probe[var] = values[var] - h;
+            let backward = self.residuals(&probe);
+            probe[var] = values[var];
+            for (row, (f, b)) in rows.iter_mut().zip(forward.iter().zip(&backward)) {
+                row[var] = (f - b) / (2.0 * h);
+            }
+        }
+        rows
+    }
+
+    fn write_back(&self, sketch: &mut Sketch, values: &[f64]) {
+        for element in &mut sketch.geometry {
+            match element {
+                GeometryElement::Point(point) => {
+                    if let Some(&[x, y]) = self.points.get(&point.id) {
+                        point.position = Vec2D::new(values[x] as f32, values[y] as f32);
+                    }
+                }
+                GeometryElement::Circle(circle) => {
+                    if let Some(&r) = self.radii.get(&circle.id) {
+                        circle.radius = values[r] as f32;
+                    }
+                }
+                GeometryElement::Arc(arc) => {
+                    if let Some(&r) = self.radii.get(&arc.id) {
+                        arc.radius = values[r] as f32;
+                    }
+                }
+                GeometryElement::Line(_) => {}
+            }
+        }
+    }
+}
+
+impl Equation {
+    fn residual(&self, v: &[f64]) -> f64 {
+        let point = |[x, y]: [Var; 2]| (v[x], v[y]);
+        let direction = |[a, b]: [[Var; 2]; 2]| {
+            let (ax, ay) = point(a);
+            let (bx, by) = point(b);
+            (bx - ax, by - ay)
+        };
+        let length = |(x, y): (f64, f64)| x.hypot(y);
+        let distance = |p: [Var; 2], q: [Var; 2]| {
+            let (px, py) = point(p);
+            let (qx, qy) = point(q);
+            length((qx - px, qy - py))
+        };
+        // Cross and dot products of unit directions (0 for degenerate lines).
+        let cross_dot = |first: [[Var; 2]; 2], second: [[Var; 2]; 2]| {
+            let (ax, ay) = direction(first);
+            let (bx, by) = direction(second);
+            let scale = length((ax, ay)) * length((bx, by));
+            if scale < 1e-12 {
+                return (0.0, 0.0);
+            }
+            ((ax * by - ay * bx) / scale, (ax * bx + ay * by) / scale)
+        };
+        let line_distance = |p: [Var; 2], line: [[Var; 2]; 2]| {
+            let (dx, dy) = direction(line);
+            let (sx, sy) = point(line[0]);
+            let (px, py) = point(p);
+            let len = length((dx, dy)).max(1e-12);
+            (dx * (py - sy) - dy * (px - sx)) / len
+        };
+        match *self {
+            Equation::Fix { a, value } => v[a] - value,
+            Equation::Equal { a, b } => v[a] - v[b],
+            Equation::Distance { p, q, value } => distance(p, q) - value,
+            Equation::EqualDistance { first, second } => {
+                distance(first[0], first[1]) - distance(second[0], second[1])
+            }
+            Equation::OnCircle { p, center, r } => distance(p, center) - v[r],
+            Equation::Parallel { first, second } => cross_dot(first, second).0,
+            Equation::Perpendicular { first, second } => cross_dot(first, second).1,
+            Equation::Angle {
+                first,
+                second,
+                value,
+            } => {
+                let (sin, cos) = cross_dot(first, second);
+                let diff = sin.atan2(cos) - value;
+                // Wrap into -pi..pi so the solver takes the short way round.
+                (diff + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU)
+                    - std::f64::consts::PI
+            }
+            Equation::OnLine { p, line } => line_distance(p, line),
+            Equation::TangentLine { line, center, r } => line_distance(center, line).abs() - v[r],
+            Equation::TangentCircles { first, second } => {
+                let centers = distance(first.0, second.0);
+                let outside = centers - (v[first.1] + v[second.1]);
+                let inside = centers - (v[first.1] - v[second.1]).abs();
+                if outside.abs() <= inside.abs() {
+                    outside
+                } else {
+                    inside
+                }
+            }
+        }
+    }
+}
+
+/// Smallest change `dx` with `J dx = -r`: `dx = -Jᵀ (J Jᵀ + λI)⁻¹ r`.
+fn min_norm_step(jacobian: &[Vec<f64>], residuals: &[f64]) -> Option<Vec<f64>> {
+    let m = residuals.len();
+    let n = jacobian.first().map_or(0, Vec::len);
+    if m == 0 || n == 0 {
+        return None;
+    }
+    let mut normal = vec![vec![0.0; m]; m];
+    for i in 0..m {
+        for j in 0..=i {
+            let dot: f64 = jacobian[i]
+                .iter()
+                .zip(&jacobian[j])
+                .map(|(a, b)| a * b)
+                .sum();
+            normal[i][j] = dot;
+            normal[j][i] = dot;
+        }
+        // Slight damping keeps the system solvable with redundant equations.
+        normal[i][i] += 1e-9;
+    }
+    let y = solve_linear(normal, residuals.iter().map(|r| -r).collect())?;
+    Some(
+        (0..n)
+            .map(|var| (0..m).map(|row| jacobian[row][var] * y[row]).sum())
+            .collect(),
+    )
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting.
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-18 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (above, below) = a.split_at_mut(col + 1);
+        let pivot_row = &above[col];
+        for (offset, row) in below.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            if factor != 0.0 {
+                for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot_value;
+                }
+                b[col + 1 + offset] -= factor * b[col];
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Rank of a matrix, by elimination with row pivoting.
+fn rank(mut rows: Vec<Vec<f64>>) -> usize {
+    let cols = rows.first().map_or(0, Vec::len);
+    let mut rank = 0;
+    for col in 0..cols {
+        if rank == rows.len() {
+            break;
+        }
+        let Some(pivot) =
+            (rank..rows.len()).max_by(|&i, &j| rows[i][col].abs().total_cmp(&rows[j][col].abs()))
+        else {
+            break;
+        };
+        if rows[pivot][col].abs() < RANK_TOLERANCE {
+            continue;
+        }
+        rows.swap(rank, pivot);
+        let (above, below) = rows.split_at_mut(rank + 1);
+        let pivot_row = &above[rank];
+        for row in below {
+            let factor = row[col] / pivot_row[col];
+            if factor != 0.0 {
+                for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+fn norm(values: &[f64]) -> f64 {
+    values.iter().map(|v| v * v).sum::<f64>().sqrt()
+}
+
+fn max_abs(values: &[f64]) -> f64 {
+    values.iter().fold(0.0, |max, v| max.max(v.abs()))
+}
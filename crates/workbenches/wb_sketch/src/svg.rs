@@ -0,0 +1,389 @@
+//! SVG path data pasted into a sketch. Curves and arcs come in as short
+//! lines; coordinates are taken as sketch units with y pointing up, so a
+//! drawing exported in millimetres keeps its size.
+
+use std::f32::consts::TAU;
+
+use crate::sketch::Vec2D;
+
+/// Largest gap between a flattened curve and the lines replacing it.
+const TOLERANCE: f32 = 0.05;
+
+/// Most lines one curve is split into.
+const MAX_SEGMENTS: usize = 64;
+
+/// One `M`…`Z` run of a path as the points it passes through.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Subpath {
+    pub points: Vec<Vec2D>,
+    pub closed: bool,
+}
+
+/// The subpaths in pasted text: the `d` attribute of every `<path>` in SVG
+/// markup, or bare path data. None when the text is neither; transforms
+/// on the markup are ignored.
+pub(crate) fn read_paths(text: &str) -> Option<Result<Vec<Subpath>, String>> {
+    let text = text.trim();
+    if text.starts_with('<') {
+        let data = path_attributes(text);
+        if data.is_empty() {
+            return None;
+        }
+        let mut subpaths = Vec::new();
+        for d in data {
+            match parse_path(d) {
+                Ok(paths) => subpaths.extend(paths),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        Some(Ok(subpaths))
+    } else if text.starts_with(['M', 'm']) {
+        Some(parse_path(text))
+    } else {
+        None
+    }
+}
+
+/// Values of the `d` attributes of `<path>` elements.
+fn path_attributes(markup: &str) -> Vec<&str> {
+    let mut data = Vec::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find("<path") {
+        rest = &rest[start + 5..];
+        let element = &rest[..rest.find('>').unwrap_or(rest.len())];
+        let value = element
+            .match_indices("d=")
+            .find(|(at, _)| element[..*at].ends_with(char::is_whitespace))
+            .and_then(|(at, _)| {
+                let value = &element[at + 2..];
+                let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+                let value = &value[1..];
+                value.find(quote).map(|end| &value[..end])
+            });
+        data.extend(value);
+    }
+    data
+}
+
+/// Numbers and command letters of path data.
+struct Tokens<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl Tokens<'_> {
+    fn skip_separators(&mut self) {
+        while self
+            .text
+            .get(self.at)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.at += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.text.get(self.at)?;
+        c.is_ascii_alphabetic().then(|| {
+            self.at += 1;
+            c
+        })
+    }
+
+    /// Whether a number follows, so the last command repeats.
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        self.text
+            .get(self.at)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.'))
+    }
+
+    fn number(&mut self) -> Result<f32, String> {
+        self.skip_separators();
+        let start = self.at;
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+        while let Some(&c) = self.text.get(self.at) {
+            let sign_allowed = self.at == start
+                || (seen_exponent && matches!(self.text[self.at - 1], b'e' | b'E'));
+            match c {
+                b'0'..=b'9' => {}
+                b'-' | b'+' if sign_allowed => {}
+                b'.' if !seen_dot && !seen_exponent => seen_dot = true,
+                b'e' | b'E' if !seen_exponent && self.at > start => seen_exponent = true,
+                _ => break,
+            }
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.at])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| format!("expected a number at offset {start}"))
+    }
+
+    /// An arc flag, which may be written without a separator after it.
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        match self.text.get(self.at) {
+            Some(b'0') => {
+                self.at += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.at += 1;
+                Ok(true)
+            }
+            _ => Err(format!("expected an arc flag at offset {}", self.at)),
+        }
+    }
+
+    fn point(&mut self) -> Result<Vec2D, String> {
+        Ok(Vec2D::new(self.number()?, self.number()?))
+    }
+}
+
+/// Path data in SVG coordinates (y down) as subpaths with y up.
+fn parse_path(data: &str) -> Result<Vec<Subpath>, String> {
+    let mut tokens = Tokens {
+        text: data.as_bytes(),
+        at: 0,
+    };
+    let mut subpaths: Vec<Subpath> = Vec::new();
+    let mut current = Vec2D::new(0.0, 0.0);
+    let mut start = current;
+    // Second control point of the last curve, for S and T.
+    let mut last_control: Option<(u8, Vec2D)> = None;
+    let mut command = None;
+
+    loop {
+        let letter = match tokens.command() {
+            Some(letter) => letter,
+            None if tokens.has_number() => match command {
+                // Coordinates after a moveto are lines.
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(letter) => letter,
+                None => return Err("path data must start with a moveto".to_string()),
+            },
+            None if tokens.at >= tokens.text.len() => break,
+            None => return Err(format!("unexpected character at offset {}", tokens.at)),
+        };
+        command = Some(letter);
+        let relative = letter.is_ascii_lowercase();
+        let offset = move |p: Vec2D| if relative { current + p } else { p };
+        let mut points = Vec::new();
+        let mut control = None;
+
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                current = offset(tokens.point()?);
+                start = current;
+                subpaths.push(Subpath {
+                    points: vec![current],
+                    closed: false,
+                });
+            }
+            b'L' => points.push(offset(tokens.point()?)),
+            b'H' => {
+                let x = tokens.number()?;
+                points.push(Vec2D::new(
+                    if relative { current.x + x } else { x },
+                    current.y,
+                ));
+            }
+            b'V' => {
+                let y = tokens.number()?;
+                points.push(Vec2D::new(
+                    current.x,
+                    if relative { current.y + y } else { y },
+                ));
+            }
+            b'C' | b'S' => {
+                let c1 = if letter.eq_ignore_ascii_case(&b'C') {
+                    offset(tokens.point()?)
+                } else {
+                    reflect(last_control, b'C', current)
+                };
+                let c2 = offset(tokens.point()?);
+                let end = offset(tokens.point()?);
+                points = flatten(|t| cubic(current, c1, c2, end, t), end);
+                control = Some((b'C', c2));
+            }
+            b'Q' | b'T' => {
+                let c = if letter.eq_ignore_ascii_case(&b'Q') {
+                    offset(tokens.point()?)
+                } else {
+                    reflect(last_control, b'Q', current)
+                };
+                let end = offset(tokens.point()?);
+                points = flatten(|t| quadratic(current, c, end, t), end);
+                control = Some((b'Q', c));
+            }
+            b'A' => {
+                let (rx, ry) = (tokens.number()?, tokens.number()?);
+                let rotation = tokens.number()?.to_radians();
+                let (large, sweep) = (tokens.flag()?, tokens.flag()?);
+                let end = offset(tokens.point()?);
+                points = arc(current, end, rx, ry, rotation, large, sweep);
+            }
+            b'Z' => {
+                if let Some(subpath) = subpaths.last_mut() {
+                    subpath.closed = true;
+                }
+                current = start;
+                // Drawing after a close starts again from the same point.
+                tokens.skip_separators();
+                if tokens.at < tokens.text.len() {
+                    subpaths.push(Subpath {
+                        points: vec![current],
+                        closed: false,
+                    });
+                }
+                command = None;
+            }
+            _ => return Err(format!("unsupported path command '{}'", letter as char)),
+        }
+        last_control = control;
+        if let Some(&end) = points.last() {
+            let subpath = match subpaths.last_mut() {
+                Some(subpath) if !subpath.closed => subpath,
+                _ => return Err("path data must start with a moveto".to_string()),
+            };
+            subpath.points.extend(points);
+            current = end;
+        }
+    }
+
+    Ok(subpaths
+        .into_iter()
+        .map(|subpath| {
+            let mut points: Vec<Vec2D> = Vec::with_capacity(subpath.points.len());
+            for point in subpath.points {
+                let point = Vec2D::new(point.x, -point.y);
+                if points.last().map_or(true, |last| !same(*last, point)) {
+                    points.push(point);
+                }
+            }
+            if subpath.closed && points.len() > 1 && same(points[0], points[points.len() - 1]) {
+                points.pop();
+            }
+            Subpath {
+                closed: subpath.closed && points.len() > 2,
+                points,
+            }
+        })
+        .filter(|subpath| subpath.points.len() > 1)
+        .collect())
+}
+
+fn same(a: Vec2D, b: Vec2D) -> bool {
+    (a - b).to_glam().length() < 1e-6
+}
+
+/// The first control point of a smooth curve: the last one mirrored through
+/// the current point, or the current point after another kind of command.
+fn reflect(last: Option<(u8, Vec2D)>, kind: u8, current: Vec2D) -> Vec2D {
+    match last {
+        Some((last_kind, control)) if last_kind == kind => current + (current - control),
+        _ => current,
+    }
+}
+
+fn cubic(p0: Vec2D, p1: Vec2D, p2: Vec2D, p3: Vec2D, t: f32) -> Vec2D {
+    let (p0, p1, p2, p3) = (p0.to_glam(), p1.to_glam(), p2.to_glam(), p3.to_glam());
+    let u = 1.0 - t;
+    Vec2D::from_glam(
+        p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t),
+    )
+}
+
+fn quadratic(p0: Vec2D, p1: Vec2D, p2: Vec2D, t: f32) -> Vec2D {
+    let (p0, p1, p2) = (p0.to_glam(), p1.to_glam(), p2.to_glam());
+    let u = 1.0 - t;
+    Vec2D::from_glam(p0 * (u * u) + p1 * (2.0 * u * t) + p2 * (t * t))
+}
+
+/// Points along `curve` (over t in 0..=1) after its start, ending at `end`, spaced so the
+/// lines between them stay within `TOLERANCE` of it.
+fn flatten(curve: impl Fn(f32) -> Vec2D, end: Vec2D) -> Vec<Vec2D> {
+    // Length of a fine polyline, as a measure of how much to split.
+    let length: f32 = (1..=16)
+        .map(|i| {
+            let (a, b) = (curve((i - 1) as f32 / 16.0), curve(i as f32 / 16.0));
+            (b - a).to_glam().length()
+        })
+        .sum();
+    let segments = ((length / (8.0 * TOLERANCE).sqrt()).ceil() as usize).clamp(1, MAX_SEGMENTS);
+    let mut points: Vec<Vec2D> = (1..segments)
+        .map(|i| curve(i as f32 / segments as f32))
+        .collect();
+    points.push(end);
+    points
+}
+
+/// Points along an elliptical arc after `from`, ending at `to`, following
+/// the SVG endpoint parameterisation.
+fn arc(
+    from: Vec2D,
+    to: Vec2D,
+    rx: f32,
+    ry: f32,
+    rotation: f32,
+    large: bool,
+    sweep: bool,
+) -> Vec<Vec2D> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 || same(from, to) {
+        return vec![to];
+    }
+    let (sin, cos) = rotation.sin_cos();
+    let half = (from - to).to_glam() * 0.5;
+    let x1 = cos * half.x + sin * half.y;
+    let y1 = -sin * half.x + cos * half.y;
+    // Radii too small to reach are scaled up until they just do.
+    let fit = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if fit > 1.0 {
+        rx *= fit.sqrt();
+        ry *= fit.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut factor = (numerator / denominator).max(0.0).sqrt();
+    if large == sweep {
+        factor = -factor;
+    }
+    let cx1 = factor * rx * y1 / ry;
+    let cy1 = -factor * ry * x1 / rx;
+    let middle = (from.to_glam() + to.to_glam()) * 0.5;
+    let center = glam::Vec2::new(
+        cos * cx1 - sin * cy1 + middle.x,
+        sin * cx1 + cos * cy1 + middle.y,
+    );
+
+    let angle = |x: f32, y: f32| y.atan2(x);
+    let start_angle = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start_angle;
+    if sweep && delta < 0.0 {
+        delta += TAU;
+    } else if !sweep && delta > 0.0 {
+        delta -= TAU;
+    }
+
+    let radius = rx.max(ry);
+    let step = 2.0 * (1.0 - TOLERANCE / radius).clamp(-1.0, 1.0).acos();
+    let segments = if step > 0.0 {
+        ((delta.abs() / step).ceil() as usize).clamp(1, MAX_SEGMENTS)
+    } else {
+        MAX_SEGMENTS
+    };
+    let mut points: Vec<Vec2D> = (1..segments)
+        .map(|i| {
+            let theta = start_angle + delta * i as f32 / segments as f32;
+            let (x, y) = (rx * theta.cos(), ry * theta.sin());
+            Vec2D::from_glam(center + glam::Vec2::new(cos * x - sin * y, sin * x + cos * y))
+        })
+        .collect();
+    points.push(to);
+    points
+}
@@ -0,0 +1,135 @@
+#[cfg(feature = "egui")]
+mod panel;
+
+use core_document::{
+    BindingError, CellRef, FeatureId, ToolDescriptor, Workbench, WorkbenchContext,
+    WorkbenchDescriptor, WorkbenchRuntimeContext,
+};
+
+/// Workbench identifier.
+pub const WORKBENCH_ID: &str = "wb.spreadsheet";
+
+/// Spreadsheet workbench: edits the document's parameter table and binds
+/// feature values to it, so a design table drives dimensions across the model.
+pub struct SpreadsheetWorkbench {
+    /// Cell shown in the formula bar.
+    selected: CellRef,
+    /// Formula bar text, committed on Enter or when focus leaves it.
+    content: String,
+    alias: String,
+    /// Cell the formula bar and alias texts were loaded from.
+    loaded: Option<CellRef>,
+    /// Show the table window.
+    show_table: bool,
+    /// Binding being added: feature, field (JSON pointer), and expression.
+    bind_feature: Option<FeatureId>,
+    bind_field: Option<String>,
+    bind_expression: String,
+    /// Bindings that failed when they were last applied.
+    errors: Vec<BindingError>,
+}
+
+impl Default for SpreadsheetWorkbench {
+    fn default() -> Self {
+        Self {
+            selected: CellRef::new(0, 0),
+            content: String::new(),
+            alias: String::new(),
+            loaded: None,
+            show_table: true,
+            bind_feature: None,
+            bind_field: None,
+            bind_expression: String::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl Workbench for SpreadsheetWorkbench {
+    fn descriptor(&self) -> WorkbenchDescriptor {
+        WorkbenchDescriptor::new(
+            WORKBENCH_ID,
+            "Spreadsheet",
+            "Edit the document's parameter table and drive feature values from it.",
+        )
+    }
+
+    fn configure(&self, context: &mut WorkbenchContext) {
+        context.register_tool(ToolDescriptor::new_action(
+            "spreadsheet.show",
+            "Parameter Table",
+            Some("table"),
+        ));
+        context.register_tool(ToolDescriptor::new_action(
+            "spreadsheet.recompute",
+            "Recompute Bindings",
+            Some("table"),
+        ));
+    }
+
+    fn on_activate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Spreadsheet workbench activated");
+        self.show_table = true;
+        self.loaded = None;
+    }
+
+    fn on_deactivate(&mut self, ctx: &mut WorkbenchRuntimeContext) {
+        ctx.log_info("Spreadsheet workbench deactivated");
+    }
+
+    fn on_input(
+        &mut self,
+        _event: &core_document::WorkbenchInputEvent,
+        active_tool: Option<&str>,
+        ctx: &mut WorkbenchRuntimeContext,
+    ) -> core_document::InputResult {
+        match active_tool {
+            Some("spreadsheet.show") => {
+                self.show_table = true;
+                core_document::InputResult::consumed()
+            }
+            Some("spreadsheet.recompute") => {
+                let errors = ctx.document.apply_parameters();
+                self.report(ctx, errors);
+                core_document::InputResult::consumed()
+            }
+            _ => core_document::InputResult::ignored(),
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn ui_right_panel(&mut self, ui: &mut egui::Ui, ctx: &mut WorkbenchRuntimeContext) {
+        panel::right_panel(ui, ctx, self);
+    }
+
+    #[cfg(feature = "egui")]
+    fn wants_right_panel(&self) -> bool {
+        true
+    }
+}
+
+impl SpreadsheetWorkbench {
+    /// Keep the binding errors for the panel and log new ones.
+    fn report(&mut self, ctx: &mut WorkbenchRuntimeContext, errors: Vec<BindingError>) {
+        for error in &errors {
+            let name = ctx
+                .document
+                .get_feature_meta(error.feature)
+                .map_or("(deleted feature)", |node| node.name.as_str())
+                .to_string();
+            ctx.log_warn(format!("Binding of {name} failed: {error}"));
+        }
+        self.errors = errors;
+    }
+}
+
+/// Short display of a number: up to six decimals, without trailing zeros.
+fn format_number(value: f64) -> String {
+    let text = format!("{value:.6}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
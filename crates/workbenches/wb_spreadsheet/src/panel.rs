@@ -0,0 +1,332 @@
+//! Right-panel UI of the Spreadsheet workbench and the parameter table window.
+
+use core_document::parameters::{self, CellValue, Evaluation};
+use core_document::{CellRef, FeatureId, ParameterSheet, WorkbenchRuntimeContext};
+
+use crate::{format_number, SpreadsheetWorkbench};
+
+/// The table always shows at least this many columns and rows.
+const MIN_COLUMNS: u32 = 6;
+const MIN_ROWS: u32 = 20;
+const CELL_SIZE: [f32; 2] = [80.0, 20.0];
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 80, 60);
+
+pub fn right_panel(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut SpreadsheetWorkbench,
+) {
+    let mut sheet = ctx.document.parameters().clone();
+    let selected = workbench.selected;
+    if workbench.loaded != Some(selected) {
+        let cell = sheet.cell(selected);
+        workbench.content = cell.map(|cell| cell.content.clone()).unwrap_or_default();
+        workbench.alias = cell.and_then(|cell| cell.alias.clone()).unwrap_or_default();
+        workbench.loaded = Some(selected);
+    }
+
+    ui.heading("Parameters");
+    ui.checkbox(&mut workbench.show_table, "Show table");
+    ui.add_space(4.0);
+    egui::Grid::new("spreadsheet_cell")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Cell");
+            ui.strong(selected.to_string());
+            ui.end_row();
+
+            ui.label("Content");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut workbench.content)
+                    .hint_text("12, 3 in, =width * 2, or text"),
+            );
+            if response.lost_focus() {
+                sheet.set_content(selected, workbench.content.clone());
+            }
+            ui.end_row();
+
+            ui.label("Alias");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut workbench.alias).hint_text("name for formulas"),
+            );
+            if response.lost_focus() {
+                if let Err(err) = sheet.set_alias(selected, Some(&workbench.alias)) {
+                    ctx.log_warn(format!("Cannot name {selected}: {err}"));
+                    workbench.loaded = None;
+                }
+            }
+            ui.end_row();
+        });
+
+    let evaluation = sheet.evaluate();
+    match evaluation.value(selected) {
+        Some(Ok(CellValue::Number(value))) => {
+            ui.label(format!("Value: {}", format_number(*value)));
+        }
+        Some(Err(err)) => {
+            ui.colored_label(ERROR_COLOR, err.to_string());
+        }
+        _ => {}
+    }
+    ui.weak("Lengths are in millimetres; a unit suffix (in, cm, …) converts a number.");
+
+    ui.separator();
+    bindings(ui, ctx, workbench, &sheet, &evaluation);
+
+    if workbench.show_table {
+        let mut open = true;
+        egui::Window::new("Parameter Table")
+            .open(&mut open)
+            .default_size([560.0, 380.0])
+            .resizable(true)
+            .show(ui.ctx(), |ui| {
+                table(ui, workbench, &sheet, &evaluation);
+            });
+        workbench.show_table = open;
+    }
+
+    if sheet != *ctx.document.parameters() {
+        let errors = ctx.document.set_parameters(sheet);
+        workbench.report(ctx, errors);
+    }
+}
+
+/// Existing feature bindings and the controls to add one.
+fn bindings(
+    ui: &mut egui::Ui,
+    ctx: &mut WorkbenchRuntimeContext,
+    workbench: &mut SpreadsheetWorkbench,
+    sheet: &ParameterSheet,
+    evaluation: &Evaluation,
+) {
+    ui.heading("Bindings");
+    let mut features: Vec<(FeatureId, String, i64)> = ctx
+        .document
+        .feature_tree()
+        .all_nodes()
+        .map(|(id, node)| (*id, node.name.clone(), node.created_at))
+        .collect();
+    features.sort_by_key(|(_, _, created_at)| *created_at);
+    let feature_name = |id: FeatureId| {
+        features
+            .iter()
+            .find(|(feature, _, _)| *feature == id)
+            .map_or("(deleted feature)", |(_, name, _)| name.as_str())
+    };
+
+    let mut bound: Vec<(FeatureId, String, String)> = ctx
+        .document
+        .feature_tree()
+        .all_nodes()
+        .flat_map(|(id, node)| {
+            node.expressions
+                .iter()
+                .map(move |(path, expression)| (*id, path.clone(), expression.clone()))
+        })
+        .collect();
+    bound.sort_by(|a, b| (feature_name(a.0), &a.1).cmp(&(feature_name(b.0), &b.1)));
+    if bound.is_empty() {
+        ui.weak("No feature values are driven by the table yet.");
+    }
+    let mut release = None;
+    for (feature, path, expression) in &bound {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} {}", feature_name(*feature), field_label(path)));
+            ui.label(format!("= {expression}"));
+            let failed = workbench
+                .errors
+                .iter()
+                .find(|error| error.feature == *feature && error.path == *path);
+            match (evaluation.expression(expression), failed) {
+                (Ok(value), None) => {
+                    ui.weak(format!("→ {}", format_number(value)));
+                }
+                (Err(err), _) => {
+                    ui.colored_label(ERROR_COLOR, "⚠")
+                        .on_hover_text(err.to_string());
+                }
+                (_, Some(error)) => {
+                    ui.colored_label(ERROR_COLOR, "⚠")
+                        .on_hover_text(error.error.to_string());
+                }
+            }
+            if ui
+                .small_button("✖")
+                .on_hover_text("Stop driving this value")
+                .clicked()
+            {
+                release = Some((*feature, path.clone()));
+            }
+        });
+    }
+    if let Some((feature, path)) = release {
+        match ctx.document.set_feature_expression(feature, &path, None) {
+            Ok(errors) => workbench.report(ctx, errors),
+            Err(err) => ctx.log_error(format!("Failed to remove binding: {err}")),
+        }
+    }
+
+    ui.add_space(6.0);
+    ui.label("Drive a feature value:");
+    egui::ComboBox::from_id_salt("spreadsheet_bind_feature")
+        .selected_text(
+            workbench
+                .bind_feature
+                .map_or("Choose feature", feature_name),
+        )
+        .show_ui(ui, |ui| {
+            for (id, name, _) in &features {
+                if ui
+                    .selectable_value(&mut workbench.bind_feature, Some(*id), name)
+                    .changed()
+                {
+                    workbench.bind_field = None;
+                }
+            }
+        });
+    let Some(node) = workbench
+        .bind_feature
+        .and_then(|id| ctx.document.get_feature_meta(id))
+    else {
+        return;
+    };
+    let fields: Vec<(String, f64)> = parameters::numeric_fields(&node.data)
+        .into_iter()
+        .filter(|(path, _)| !node.expressions.contains_key(path))
+        .collect();
+    if fields.is_empty() {
+        ui.weak("This feature has no free numeric values.");
+        return;
+    }
+    egui::ComboBox::from_id_salt("spreadsheet_bind_field")
+        .selected_text(
+            workbench
+                .bind_field
+                .as_deref()
+                .map_or_else(|| "Choose value".to_string(), field_label),
+        )
+        .show_ui(ui, |ui| {
+            for (path, value) in &fields {
+                ui.selectable_value(
+                    &mut workbench.bind_field,
+                    Some(path.clone()),
+                    format!("{} ({})", field_label(path), format_number(*value)),
+                );
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut workbench.bind_expression)
+                .hint_text("expression, e.g. width / 2"),
+        );
+        if ui
+            .small_button(workbench.selected.to_string())
+            .on_hover_text("Use the selected cell")
+            .clicked()
+        {
+            workbench.bind_expression = sheet
+                .cell(workbench.selected)
+                .and_then(|cell| cell.alias.clone())
+                .unwrap_or_else(|| workbench.selected.to_string());
+        }
+    });
+    let preview = (!workbench.bind_expression.trim().is_empty())
+        .then(|| evaluation.expression(&workbench.bind_expression));
+    match &preview {
+        Some(Ok(value)) => {
+            ui.weak(format!("→ {}", format_number(*value)));
+        }
+        Some(Err(err)) => {
+            ui.colored_label(ERROR_COLOR, err.to_string());
+        }
+        None => {}
+    }
+    let ready = matches!(preview, Some(Ok(_))) && workbench.bind_field.is_some();
+    if ui.add_enabled(ready, egui::Button::new("Bind")).clicked() {
+        let (Some(feature), Some(path)) = (workbench.bind_feature, workbench.bind_field.take())
+        else {
+            return;
+        };
+        let expression = workbench.bind_expression.trim().to_string();
+        match ctx
+            .document
+            .set_feature_expression(feature, &path, Some(expression.clone()))
+        {
+            Ok(errors) => {
+                ctx.log_info(format!(
+                    "{} {} = {expression}",
+                    feature_name(feature),
+                    field_label(&path)
+                ));
+                workbench.report(ctx, errors);
+                workbench.bind_expression.clear();
+            }
+            Err(err) => ctx.log_error(format!("Failed to bind value: {err}")),
+        }
+    }
+}
+
+/// Grid of evaluated cells; clicking a cell selects it for editing.
+fn table(
+    ui: &mut egui::Ui,
+    workbench: &mut SpreadsheetWorkbench,
+    sheet: &ParameterSheet,
+    evaluation: &Evaluation,
+) {
+    let (used_columns, used_rows) = sheet.used_size();
+    let columns = (used_columns + 1)
+        .max(MIN_COLUMNS)
+        .max(workbench.selected.column + 2);
+    let rows = (used_rows + 1)
+        .max(MIN_ROWS)
+        .max(workbench.selected.row + 2);
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("parameter_table")
+            .striped(true)
+            .spacing([2.0, 2.0])
+            .show(ui, |ui| {
+                ui.label("");
+                for column in 0..columns {
+                    ui.centered_and_justified(|ui| ui.strong(CellRef::column_name(column)));
+                }
+                ui.end_row();
+                for row in 0..rows {
+                    ui.strong((row + 1).to_string());
+                    for column in 0..columns {
+                        let cell = CellRef::new(column, row);
+                        let text = match evaluation.value(cell) {
+                            None => egui::RichText::new(""),
+                            Some(Ok(CellValue::Number(value))) => {
+                                egui::RichText::new(format_number(*value))
+                            }
+                            Some(Ok(CellValue::Text(text))) => egui::RichText::new(text),
+                            Some(Err(_)) => egui::RichText::new("#ERR").color(ERROR_COLOR),
+                        };
+                        let mut response = ui.add_sized(
+                            CELL_SIZE,
+                            egui::Button::selectable(workbench.selected == cell, text),
+                        );
+                        if let Some(content) = sheet.cell(cell) {
+                            let mut hover = content.content.clone();
+                            if let Some(alias) = &content.alias {
+                                hover = format!("{alias}: {hover}");
+                            }
+                            if let Some(Err(err)) = evaluation.value(cell) {
+                                hover = format!("{hover}\n{err}");
+                            }
+                            response = response.on_hover_text(hover);
+                        }
+                        if response.clicked() {
+                            workbench.selected = cell;
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+/// Field path shown to the user (`operation/ratio`).
+fn field_label(path: &str) -> String {
+    path.trim_start_matches('/').to_string()
+}